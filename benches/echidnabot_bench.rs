@@ -15,7 +15,7 @@
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use echidnabot::api::rate_limit::WebhookRateLimiter;
-use echidnabot::dispatcher::ProverKind;
+use echidnabot::dispatcher::{ExtensionTable, ProverKind};
 use echidnabot::scheduler::ProofJob;
 use echidnabot::store::models::goal_fingerprint;
 use hmac::{Hmac, Mac};
@@ -116,6 +116,11 @@ fn bench_prover_kind(c: &mut Criterion) {
         b.iter(|| black_box(ProverKind::from_extension("xyz")));
     });
 
+    g.bench_function("route_path", |b| {
+        let routes = ExtensionTable::builtin();
+        b.iter(|| black_box(routes.provers_for("theories/Arith/Plus.v")));
+    });
+
     g.bench_function("tier_lookup", |b| {
         let p = ProverKind::new("isabelle");
         b.iter(|| black_box(p.tier()));
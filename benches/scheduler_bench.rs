@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Criterion benchmarks for `JobScheduler` queue operations.
+//!
+//! Webhook handlers enqueue while the dispatch loop (and remote workers)
+//! dequeue, so these measure both a single caller against a deep queue and
+//! many tasks hammering the scheduler at once. Same thresholds and
+//! baseline workflow as `echidnabot_bench.rs`:
+//!
+//! `cargo bench --bench scheduler_bench -- --save-baseline main`, then
+//! `cargo bench --bench scheduler_bench -- --baseline main` on the branch.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use echidnabot::config::FleetConfig;
+use echidnabot::dispatcher::ProverKind;
+use echidnabot::fleet::workers::{ResourceClass, WorkerCapabilities, WorkerRegistry, LOCAL_WORKER};
+use echidnabot::scheduler::{JobPriority, JobResult, JobScheduler, ProofJob};
+use uuid::Uuid;
+
+const PROVERS: [&str; 4] = ["coq", "lean", "agda", "metamath"];
+
+fn job(i: usize) -> ProofJob {
+    let priority = match i % 4 {
+        0 => JobPriority::Low,
+        1 => JobPriority::Normal,
+        2 => JobPriority::High,
+        _ => JobPriority::Critical,
+    };
+    ProofJob::new(Uuid::new_v4(), format!("{:040x}", i), ProverKind::new(PROVERS[i % 4]), vec![])
+        .with_priority(priority)
+}
+
+fn done() -> JobResult {
+    JobResult {
+        success: true,
+        message: String::new(),
+        prover_output: String::new(),
+        duration_ms: 1,
+        verified_files: vec![],
+        failed_files: vec![],
+        confidence: None,
+        axioms: None,
+        prover_version: None,
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(8)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+// ──────────────────────────────────────────────────────────────────────────────
+// Enqueue into a deep queue
+// Duplicate detection and priority placement must not scan the queue.
+// ──────────────────────────────────────────────────────────────────────────────
+
+fn bench_enqueue_deep_queue(c: &mut Criterion) {
+    let rt = runtime();
+    let mut g = c.benchmark_group("scheduler_enqueue");
+
+    for depth in [0usize, 1_000, 10_000] {
+        g.bench_with_input(BenchmarkId::new("queue_depth", depth), &depth, |b, &depth| {
+            b.iter_batched(
+                || {
+                    let scheduler = JobScheduler::new(8, depth + 16);
+                    rt.block_on(async {
+                        for i in 0..depth {
+                            scheduler.enqueue(job(i)).await.unwrap();
+                        }
+                    });
+                    (scheduler, job(depth))
+                },
+                |(scheduler, next)| rt.block_on(async { black_box(scheduler.enqueue(next).await.unwrap()) }),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    g.finish();
+}
+
+// ──────────────────────────────────────────────────────────────────────────────
+// Enqueue / dequeue / complete under contention
+// `tasks` concurrent producers each enqueue, start and complete jobs.
+// ──────────────────────────────────────────────────────────────────────────────
+
+const JOBS_PER_TASK: usize = 250;
+
+fn bench_contention(c: &mut Criterion) {
+    let rt = runtime();
+    let mut g = c.benchmark_group("scheduler_contention");
+    g.measurement_time(Duration::from_secs(10));
+
+    for tasks in [1usize, 4, 16, 64] {
+        g.throughput(Throughput::Elements((tasks * JOBS_PER_TASK) as u64));
+        g.bench_with_input(BenchmarkId::new("tasks", tasks), &tasks, |b, &tasks| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let scheduler = Arc::new(JobScheduler::new(tasks * 4, tasks * JOBS_PER_TASK));
+                    let start = Instant::now();
+                    rt.block_on(async {
+                        let handles: Vec<_> = (0..tasks)
+                            .map(|t| {
+                                let scheduler = scheduler.clone();
+                                tokio::spawn(async move {
+                                    for i in 0..JOBS_PER_TASK {
+                                        scheduler.enqueue(job(t * JOBS_PER_TASK + i)).await.unwrap();
+                                        if let Some(started) = scheduler.try_start_next().await {
+                                            scheduler.complete_job(started.id, done()).await;
+                                        }
+                                    }
+                                })
+                            })
+                            .collect();
+                        for handle in handles {
+                            handle.await.unwrap();
+                        }
+                    });
+                    total += start.elapsed();
+                }
+                total
+            });
+        });
+    }
+    g.finish();
+}
+
+// ──────────────────────────────────────────────────────────────────────────────
+// Capability-routed dequeue
+// A worker skipping jobs it can't run (e.g. a queue full of Isabelle jobs
+// ahead of one Coq job) should stay cheap.
+// ──────────────────────────────────────────────────────────────────────────────
+
+fn bench_routed_dequeue(c: &mut Criterion) {
+    let rt = runtime();
+    let mut g = c.benchmark_group("scheduler_routed_dequeue");
+
+    for skipped in [10usize, 1_000] {
+        g.bench_with_input(BenchmarkId::new("skipped", skipped), &skipped, |b, &skipped| {
+            b.iter_batched(
+                || {
+                    let registry = Arc::new(WorkerRegistry::new(&FleetConfig::default()));
+                    registry.register(
+                        LOCAL_WORKER,
+                        WorkerCapabilities {
+                            provers: vec![ProverKind::new("coq")],
+                            resource_class: ResourceClass::Standard,
+                        },
+                    );
+                    let scheduler = JobScheduler::new(8, skipped + 1).with_workers(registry);
+                    rt.block_on(async {
+                        for i in 0..skipped {
+                            let isabelle = ProofJob::new(
+                                Uuid::new_v4(),
+                                format!("{:040x}", i),
+                                ProverKind::new("isabelle"),
+                                vec![],
+                            );
+                            scheduler.enqueue(isabelle).await.unwrap();
+                        }
+                        let coq = ProofJob::new(Uuid::new_v4(), "c".repeat(40), ProverKind::new("coq"), vec![]);
+                        scheduler.enqueue(coq).await.unwrap();
+                    });
+                    scheduler
+                },
+                |scheduler| rt.block_on(async { black_box(scheduler.try_start_next().await) }),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    g.finish();
+}
+
+criterion_group!(benches, bench_enqueue_deep_queue, bench_contention, bench_routed_dequeue);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- file_timings — per-file verification durations, one row per proof file
+-- per job. Baseline for the duration trend anomaly detector
+-- (src/perf/anomaly.rs). Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS file_timings (
+    id              TEXT PRIMARY KEY,
+    job_id          TEXT NOT NULL REFERENCES proof_jobs(id),
+    repo_id         TEXT NOT NULL REFERENCES repositories(id),
+    prover          TEXT NOT NULL,
+    file_path       TEXT NOT NULL,
+    commit_sha      TEXT NOT NULL,
+    duration_ms     INTEGER NOT NULL,
+    success         INTEGER NOT NULL,
+    created_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_file_timings_repo_prover_file
+    ON file_timings (repo_id, prover, file_path);
+
+CREATE INDEX IF NOT EXISTS idx_file_timings_job_id
+    ON file_timings (job_id);
@@ -0,0 +1,16 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- api_tokens — bearer tokens for /graphql and /metrics (src/api/auth.rs).
+-- Only the SHA-256 hash of each token is stored; the plaintext is shown
+-- once by `echidnabot token mint`. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id              TEXT PRIMARY KEY,
+    name            TEXT NOT NULL,
+    token_hash      TEXT NOT NULL UNIQUE,
+    scope           TEXT NOT NULL,
+    created_at      TEXT NOT NULL,
+    last_used_at    TEXT,
+    revoked_at      TEXT
+);
@@ -0,0 +1,15 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- quarantined_proofs — proof files whose failures are reported but do not
+-- fail the job. Populated by the "Quarantine" check-run action
+-- (src/api/check_actions.rs). Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS quarantined_proofs (
+    id              TEXT PRIMARY KEY,
+    repo_id         TEXT NOT NULL REFERENCES repositories(id),
+    file_path       TEXT NOT NULL,
+    reason          TEXT,
+    created_at      TEXT NOT NULL,
+    UNIQUE (repo_id, file_path)
+);
@@ -0,0 +1,7 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- proof_jobs.retry_of — the job a retry re-runs (`retryJob` mutation,
+-- `echidnabot retry`). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_jobs ADD COLUMN IF NOT EXISTS retry_of TEXT REFERENCES proof_jobs(id);
@@ -0,0 +1,25 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- webhook_deliveries — raw webhook bodies accepted by the ingest endpoint
+-- and processed by the background worker (src/api/ingest.rs). Redeliveries
+-- of the same platform delivery id are ignored.
+-- Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id              TEXT PRIMARY KEY,
+    platform        TEXT NOT NULL,
+    event_type      TEXT NOT NULL,
+    delivery_id     TEXT,
+    payload         BYTEA NOT NULL,
+    status          TEXT NOT NULL,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    last_error      TEXT,
+    received_at     TEXT NOT NULL,
+    next_attempt_at TEXT NOT NULL,
+    processed_at    TEXT,
+    UNIQUE (platform, delivery_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+    ON webhook_deliveries (status, next_attempt_at);
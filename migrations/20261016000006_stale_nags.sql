@@ -0,0 +1,18 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- stale_nags — per-PR progress of the stale proof-failure policy
+-- (src/stale.rs): reminders sent, labels escalated, draft/close applied.
+-- Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS stale_nags (
+    repo_id          TEXT NOT NULL REFERENCES repositories(id),
+    pr_number        INTEGER NOT NULL,
+    failing_since    TEXT NOT NULL,
+    reminders        INTEGER NOT NULL DEFAULT 0,
+    last_reminded_at TEXT,
+    escalated        INTEGER NOT NULL DEFAULT 0,
+    drafted          INTEGER NOT NULL DEFAULT 0,
+    closed           INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (repo_id, pr_number)
+);
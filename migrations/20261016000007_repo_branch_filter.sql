@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.branch_include / branch_exclude — JSON arrays of branch
+-- patterns whose pushes are verified (empty include = default branch
+-- only). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS branch_include TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS branch_exclude TEXT NOT NULL DEFAULT '[]';
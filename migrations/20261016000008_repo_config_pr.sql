@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.config_pr_offered_at — when the first-event bootstrap PR
+-- adding `.echidnabot.toml` was offered (NULL = never). Mirrors
+-- `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS config_pr_offered_at TEXT;
@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.path_include / path_exclude — JSON arrays of path globs
+-- whose proof files are verified (empty include = every proof file).
+-- Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS path_include TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS path_exclude TEXT NOT NULL DEFAULT '[]';
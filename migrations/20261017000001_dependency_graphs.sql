@@ -0,0 +1,16 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- dependency_graphs — proof import graph per (repo, prover), rebuilt by
+-- every job and used to narrow push verification to changed files and
+-- their dependents (src/depgraph.rs). `graph` is JSON. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS dependency_graphs (
+    repo_id         TEXT NOT NULL REFERENCES repositories(id),
+    prover          TEXT NOT NULL,
+    commit_sha      TEXT NOT NULL,
+    graph           TEXT NOT NULL,
+    updated_at      TEXT NOT NULL,
+    PRIMARY KEY (repo_id, prover)
+);
@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.visibility — 'public' or 'private': whether requests
+-- without an API token may see the repository's status (badge, /status,
+-- public GraphQL queries). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS visibility TEXT NOT NULL DEFAULT 'private';
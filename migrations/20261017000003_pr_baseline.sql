@@ -0,0 +1,11 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- proof_jobs.base_sha — base commit of the PR a job verifies, and an
+-- index for looking up that commit's per-file results (the PR baseline
+-- comparison, src/baseline.rs). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_jobs ADD COLUMN IF NOT EXISTS base_sha TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_file_timings_repo_commit
+    ON file_timings (repo_id, commit_sha);
@@ -0,0 +1,25 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- federated_results — signed results imported from other echidnabot
+-- instances (src/federation.rs), one row per peer/repo/commit/prover,
+-- with the envelope as received. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS federated_results (
+    id              TEXT PRIMARY KEY,
+    peer            TEXT NOT NULL,
+    platform        TEXT NOT NULL,
+    repo            TEXT NOT NULL,
+    commit_sha      TEXT NOT NULL,
+    prover          TEXT NOT NULL,
+    success         INTEGER NOT NULL,
+    verified_files  TEXT NOT NULL,
+    failed_files    TEXT NOT NULL,
+    completed_at    TEXT NOT NULL,
+    envelope        TEXT NOT NULL,
+    imported_at     TEXT NOT NULL,
+    UNIQUE (peer, platform, repo, commit_sha, prover)
+);
+
+CREATE INDEX IF NOT EXISTS idx_federated_results_lookup
+    ON federated_results (platform, repo, commit_sha, prover);
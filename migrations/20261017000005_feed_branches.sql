@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.default_branch and proof_jobs.branch — which pushes belong
+-- to the default branch, for the per-repository results feeds
+-- (src/api/feed.rs). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS default_branch TEXT;
+ALTER TABLE proof_jobs ADD COLUMN IF NOT EXISTS branch TEXT;
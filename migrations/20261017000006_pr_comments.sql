@@ -0,0 +1,15 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- pr_comments — the bot's result comment per PR and prover, so later
+-- runs edit it instead of posting another (src/pr_comment.rs).
+-- Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS pr_comments (
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    pr_number INTEGER NOT NULL,
+    prover TEXT NOT NULL,
+    comment_id TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (repo_id, pr_number, prover)
+);
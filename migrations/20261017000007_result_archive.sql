@@ -0,0 +1,7 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- proof_results.archive_cid — content address of a job's archived
+-- artifacts (src/archive.rs). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_results ADD COLUMN IF NOT EXISTS archive_cid TEXT;
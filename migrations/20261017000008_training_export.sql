@@ -0,0 +1,21 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- training_examples — anonymized (goal, tactic) pairs from passing jobs
+-- of opted-in repositories, queued for the training export
+-- (src/feedback/export.rs). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS training_export INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS training_examples (
+    id TEXT PRIMARY KEY,
+    prover TEXT NOT NULL,
+    goal TEXT NOT NULL,
+    tactic TEXT NOT NULL,
+    digest TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    exported_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_examples_pending
+    ON training_examples(exported_at, created_at);
@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- repositories.reverify_cron / last_reverify_at — per-repository cron
+-- schedule for full re-verification of the default branch, and when it
+-- last fired (src/reverify.rs). Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS reverify_cron TEXT;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS last_reverify_at TEXT;
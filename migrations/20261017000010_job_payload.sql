@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- proof_jobs.payload — the job's file list and run options as
+-- schema-versioned JSON (`JobPayload` in src/store/models.rs). Rows
+-- without one fall back to the version-1 array in `file_paths`. Mirrors
+-- `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_jobs ADD COLUMN IF NOT EXISTS payload TEXT;
@@ -0,0 +1,13 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- announcements — the instance-wide maintenance banner prepended to bot
+-- comments and check summaries while active (src/announcement.rs). One
+-- row at most. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS announcements (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    message TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT
+);
@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- webhook_deliveries.trace_parent — W3C traceparent of the request that
+-- delivered the webhook, so background processing joins its trace
+-- (src/observability.rs). Jobs keep theirs in proof_jobs.payload.
+-- Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS trace_parent TEXT;
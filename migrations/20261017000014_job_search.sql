@@ -0,0 +1,7 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- Job search (`searchJobs`) filters and orders by queue time. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE INDEX IF NOT EXISTS idx_jobs_queued_at ON proof_jobs(queued_at);
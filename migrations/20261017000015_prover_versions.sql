@@ -0,0 +1,9 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- Prover toolchain versions (src/toolchain.rs): the version each result
+-- was verified with, and per-repository pins as a JSON object of prover
+-- slug → version. Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_results ADD COLUMN IF NOT EXISTS prover_version TEXT;
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS prover_pins TEXT NOT NULL DEFAULT '{}';
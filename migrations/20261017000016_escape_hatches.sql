@@ -0,0 +1,17 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- escape_hatches — `sorry`, `Admitted`, new axioms and other `[axioms]`
+-- forbidden tokens found in the files a job checked
+-- (src/trust/escape_hatch.rs), one row per file and line. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS escape_hatches (
+    id TEXT PRIMARY KEY,
+    job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+    file_path TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_escape_hatches_job ON escape_hatches(job_id);
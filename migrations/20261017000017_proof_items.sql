@@ -0,0 +1,18 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- proof_items — per-file and per-theorem statuses and timings parsed from
+-- each job's prover output (src/proof_items.rs). `theorem` is NULL for
+-- the file's own row. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS proof_items (
+    id TEXT PRIMARY KEY,
+    job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+    file_path TEXT NOT NULL,
+    theorem TEXT,
+    line INTEGER,
+    status TEXT NOT NULL,
+    duration_ms INTEGER,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_proof_items_job ON proof_items(job_id);
@@ -0,0 +1,21 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- flaky_runs — failed files re-run under a repository's `[flaky] reruns`
+-- policy, with the verdict: `Deterministic` when no run passed, `Flaky`
+-- when a later one did (src/flaky.rs). `timed_out` marks a failed
+-- attempt that hit the timeout. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS flaky_runs (
+    id TEXT PRIMARY KEY,
+    job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    prover TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    commit_sha TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    verdict TEXT NOT NULL,
+    timed_out INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_flaky_runs_repo ON flaky_runs(repo_id, created_at);
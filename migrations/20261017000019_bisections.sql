@@ -0,0 +1,27 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- bisections — searches for the commit that broke a proof on a default
+-- branch (src/bisect.rs). `pending` is a JSON array of the untested
+-- commits between `good_sha` and `bad_sha`, oldest first; `probe_job` the
+-- job checking the current midpoint. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS bisections (
+    id TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    prover TEXT NOT NULL,
+    branch TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    theorem TEXT,
+    good_sha TEXT NOT NULL,
+    bad_sha TEXT NOT NULL,
+    pending TEXT NOT NULL,
+    probe_job TEXT,
+    probes INTEGER NOT NULL DEFAULT 0,
+    reused INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL,
+    issue TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_bisections_repo ON bisections(repo_id, created_at);
@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- Repository prover flags (src/executor/prover_args.rs): the validated
+-- `[provers.<slug>] flags` each result was verified with, as a JSON array.
+-- Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_results ADD COLUMN IF NOT EXISTS prover_args TEXT NOT NULL DEFAULT '[]';
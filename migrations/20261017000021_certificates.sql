@@ -0,0 +1,16 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- certificates — signed verification certificates issued by the auditor
+-- (src/certificate.rs). `envelope` is the signed envelope as JSON; a
+-- re-issue for the same commit replaces the row. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS certificates (
+    id TEXT PRIMARY KEY,
+    repo_id TEXT NOT NULL REFERENCES repositories(id),
+    commit_sha TEXT NOT NULL,
+    envelope TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE(repo_id, commit_sha)
+);
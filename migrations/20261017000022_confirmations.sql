@@ -0,0 +1,17 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- confirmations — pending two-step confirmations of destructive GraphQL
+-- mutations (src/api/confirm.rs). Only the SHA-256 of the token is kept;
+-- a row is deleted when used, or once expired. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS confirmations (
+    token_hash TEXT PRIMARY KEY,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    subject_digest TEXT NOT NULL,
+    impact TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
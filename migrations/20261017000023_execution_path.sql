@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- `[executor] dispatch`: where each result's proofs were checked —
+-- `echidna`, `local` or `local-fallback`. Mirrors
+-- `SqliteStore::run_migrations`.
+
+ALTER TABLE proof_results ADD COLUMN IF NOT EXISTS execution_path TEXT;
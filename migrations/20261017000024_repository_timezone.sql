@@ -0,0 +1,8 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- Per-repository IANA time zone for `reverify_cron` schedules and
+-- `[calendar]` blackout windows (src/calendar.rs). Mirrors
+-- `SqliteStore::run_migrations`.
+
+ALTER TABLE repositories ADD COLUMN IF NOT EXISTS timezone TEXT;
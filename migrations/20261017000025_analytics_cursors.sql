@@ -0,0 +1,14 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- analytics_cursors — how far each `[[analytics.sinks]]` entry has
+-- exported verification events (src/analytics.rs): the last finished job
+-- shipped, in (completed_at, job id) order. Deleting a row re-exports
+-- everything. Mirrors `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS analytics_cursors (
+    sink TEXT PRIMARY KEY,
+    completed_at TEXT NOT NULL,
+    job_id TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
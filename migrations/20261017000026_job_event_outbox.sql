@@ -0,0 +1,18 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- job_event_outbox — job lifecycle events waiting for `[events]`
+-- (src/events.rs) to publish them to NATS or Kafka. Rows are written by
+-- triggers on proof_jobs, which `serve` installs when `[events] enabled`
+-- and drops otherwise, and deleted once published. Mirrors
+-- `SqliteStore::run_migrations`.
+
+CREATE TABLE IF NOT EXISTS job_event_outbox (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id TEXT NOT NULL,
+    job_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    occurred_at TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT
+);
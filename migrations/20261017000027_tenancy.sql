@@ -0,0 +1,15 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2026 Jonathan D.A. Jewell (hyperpolymath)
+--
+-- Tenant isolation (src/tenancy.rs): api_tokens.tenant confines a token
+-- to one repository owner, and tenant_keys holds each owner's data key,
+-- wrapped by `[tenancy] master_key`, that seals its repositories'
+-- webhook secrets. Mirrors `SqliteStore::run_migrations`.
+
+ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS tenant TEXT;
+
+CREATE TABLE IF NOT EXISTS tenant_keys (
+    owner TEXT PRIMARY KEY,
+    wrapped_key TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
@@ -4,9 +4,11 @@
 
 use async_trait::async_trait;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::http::{ApiRequest, ApiResponse, HttpApi, ReqwestApi};
 use super::{
-    CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
+    Capabilities, CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
     PlatformAdapter, PrId, RepoId, ReviewCommentLocation,
 };
 use crate::error::{Error, Result};
@@ -14,8 +16,9 @@
 /// Bitbucket adapter (clone-only implementation)
 pub struct BitbucketAdapter {
     base_url: String,
+    api_url: String,
     token: Option<String>,
-    client: reqwest::Client,
+    http: Arc<dyn HttpApi>,
 }
 
 impl BitbucketAdapter {
@@ -23,26 +26,58 @@ pub fn new(base_url: Option<&str>) -> Self {
         let base = base_url.unwrap_or("https://bitbucket.org");
         Self {
             base_url: base.trim_end_matches('/').to_string(),
+            api_url: "https://api.bitbucket.org/2.0".to_string(),
             token: std::env::var("BITBUCKET_TOKEN").ok(),
-            client: reqwest::Client::new(),
+            http: Arc::new(ReqwestApi::default()),
         }
     }
 
+    /// Call the API at `url` instead of `https://api.bitbucket.org/2.0`
+    /// (e.g. a test server).
+    pub fn with_api_url(mut self, url: &str) -> Self {
+        self.api_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send requests through `http` instead of the network.
+    pub fn with_http(mut self, http: Arc<dyn HttpApi>) -> Self {
+        self.http = http;
+        self
+    }
+
     fn repo_url(&self, repo: &RepoId) -> String {
         format!("{}/{}/{}.git", self.base_url, repo.owner, repo.name)
     }
 
     fn api_url(&self) -> String {
-        "https://api.bitbucket.org/2.0".to_string()
+        self.api_url.clone()
     }
 
     fn project_path(&self, repo: &RepoId) -> String {
         format!("{}/{}", repo.owner, repo.name)
     }
+
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse> {
+        self.http
+            .send(request)
+            .await
+            .map_err(|e| Error::GitHub(e.to_string()))
+    }
 }
 
 #[async_trait]
 impl PlatformAdapter for BitbucketAdapter {
+    fn capabilities(&self) -> Capabilities {
+        // Writes need a token; "check runs" here are build statuses.
+        let write = self.token.is_some();
+        Capabilities {
+            commit_statuses: write,
+            comments: write,
+            comment_updates: write,
+            ..Capabilities::default()
+        }
+    }
+
     async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
         let temp_dir = tempfile::tempdir().map_err(Error::Io)?;
         let clone_path = temp_dir.keep();
@@ -137,18 +172,14 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         });
 
         let response = self
-            .client
-            .post(&url)
-            .bearer_auth(token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(CheckRunId(
             data["uuid"]
@@ -158,6 +189,10 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         ))
     }
 
+    async fn create_commit_status(&self, repo: &RepoId, check: CheckRun) -> Result<()> {
+        self.create_check_run(repo, check).await.map(|_| ())
+    }
+
     async fn update_check_run(&self, _id: CheckRunId, _status: CheckStatus) -> Result<()> {
         // Bitbucket doesn't support updating build statuses after creation
         // To update, you would need to POST again with the same key
@@ -184,18 +219,14 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         });
 
         let response = self
-            .client
-            .post(&url)
-            .bearer_auth(token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(CommentId(
             data["id"]
@@ -205,6 +236,49 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         ))
     }
 
+    async fn update_comment(
+        &self,
+        repo: &RepoId,
+        pr: PrId,
+        comment: &CommentId,
+        body: &str,
+    ) -> Result<()> {
+        let token = self.token.as_ref().ok_or_else(|| {
+            Error::Config("BITBUCKET_TOKEN not set".to_string())
+        })?;
+
+        let project_path = self.project_path(repo);
+        let url = format!(
+            "{}/repositories/{}/pullrequests/{}/comments/{}",
+            self.api_url(),
+            project_path,
+            pr.0,
+            comment.0
+        );
+
+        let payload = serde_json::json!({
+            "content": {
+                "raw": body,
+            },
+        });
+
+        let response = self
+            .send(
+                ApiRequest::put(url)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .json(payload),
+            )
+            .await?;
+
+        if !response.is_success() {
+            return Err(Error::GitHub(format!(
+                "Bitbucket comment update returned {}",
+                response.status
+            )));
+        }
+        Ok(())
+    }
+
     async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId> {
         let token = self.token.as_ref().ok_or_else(|| {
             Error::Config("BITBUCKET_TOKEN not set".to_string())
@@ -226,18 +300,14 @@ async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId>
         });
 
         let response = self
-            .client
-            .post(&url)
-            .bearer_auth(token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("Bearer {}", token))
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(IssueId(
             data["id"]
@@ -260,17 +330,10 @@ async fn get_default_branch(&self, repo: &RepoId) -> Result<String> {
         );
 
         let response = self
-            .client
-            .get(&url)
-            .bearer_auth(token)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(ApiRequest::get(url).header("Authorization", format!("Bearer {}", token)))
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(data["mainbranch"]["name"]
             .as_str()
@@ -306,29 +369,25 @@ async fn get_file_contents(
             urlencoding::encode(r#ref),
             encoded_path,
         );
-        let mut req = self.client.get(&url);
+        let mut req = ApiRequest::get(url);
         if let Some(token) = self.token.as_ref() {
-            req = req.bearer_auth(token);
+            req = req.header("Authorization", format!("Bearer {}", token));
         }
-        let resp = req
-            .send()
+        let resp = self
+            .http
+            .send(req)
             .await
             .map_err(|e| Error::GitHub(format!("Bitbucket source API: {}", e)))?;
-        let status = resp.status();
-        if status == reqwest::StatusCode::NOT_FOUND {
+        if resp.status == 404 {
             return Ok(None);
         }
-        if !status.is_success() {
+        if !resp.is_success() {
             return Err(Error::GitHub(format!(
                 "Bitbucket source API returned {}",
-                status
+                resp.status
             )));
         }
-        let body = resp
-            .text()
-            .await
-            .map_err(|e| Error::GitHub(format!("Bitbucket source response: {}", e)))?;
-        Ok(Some(body))
+        Ok(Some(resp.body))
     }
 
     async fn create_review_comment(
@@ -346,3 +405,29 @@ async fn create_review_comment(
         self.create_comment(repo, pr, body).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::http::Method;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::Platform;
+
+    #[tokio::test]
+    async fn source_reads_resolve_the_ref_and_encode_segments() {
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/2.0/repositories/o/r/src/main/theories/My%20File.v", 200, "Qed.");
+        let bitbucket = BitbucketAdapter::new(None)
+            .with_api_url("https://bitbucket.test/2.0/")
+            .with_http(api.clone());
+        let repo = RepoId::new(Platform::Bitbucket, "o", "r");
+
+        let contents = bitbucket
+            .get_file_contents(&repo, Some("main"), "theories/My File.v")
+            .await
+            .unwrap();
+        assert_eq!(contents.as_deref(), Some("Qed."));
+        assert_eq!(bitbucket.get_file_contents(&repo, None, "gone.v").await.unwrap(), None);
+        assert!(api.requests()[1].url.ends_with("/src/HEAD/gone.v"));
+    }
+}
@@ -37,10 +37,10 @@
 //! No first-class Forgejo/Gitea Rust SDK exists with the maturity of
 //! `octocrab`. The crates that do exist (`gitea-sdk`, `forgejo-api`) are
 //! either pre-1.0 with breaking releases or unmaintained. This adapter
-//! therefore uses `reqwest` + `serde_json` directly — the same pattern
-//! the GitLab and Bitbucket adapters use. If a stable SDK lands, the
-//! private methods can be migrated module-internally without changing
-//! the `PlatformAdapter` trait surface.
+//! therefore builds REST calls with `serde_json` and sends them through
+//! [`super::http::HttpApi`] — the same pattern every adapter uses. If a
+//! stable SDK lands, the private methods can be migrated
+//! module-internally without changing the `PlatformAdapter` trait surface.
 //!
 //! # TODOs
 //!
@@ -56,9 +56,11 @@
 
 use async_trait::async_trait;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::http::{ApiRequest, HttpApi, ReqwestApi};
 use super::{
-    CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
+    Capabilities, CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
     PlatformAdapter, PrId, RepoId, ReviewCommentLocation,
 };
 use crate::error::{Error, Result};
@@ -71,11 +73,12 @@
 ///
 /// Holds a `base_url` (e.g. `https://codeberg.org` or a self-hosted
 /// Forgejo URL), an optional personal access token (read from the
-/// `CODEBERG_TOKEN` env var by default), and a shared `reqwest` client.
+/// `CODEBERG_TOKEN` env var by default), and the [`HttpApi`] requests go
+/// through.
 pub struct CodebergAdapter {
     base_url: String,
     token: Option<String>,
-    client: reqwest::Client,
+    http: Arc<dyn HttpApi>,
 }
 
 impl CodebergAdapter {
@@ -93,10 +96,16 @@ pub fn new(base_url: Option<&str>) -> Self {
             // works against the broader Forgejo/Gitea ecosystem
             // without bespoke env vars.
             token: std::env::var("CODEBERG_TOKEN").ok(),
-            client: reqwest::Client::new(),
+            http: Arc::new(ReqwestApi::default()),
         }
     }
 
+    /// Send requests through `http` instead of the network.
+    pub fn with_http(mut self, http: Arc<dyn HttpApi>) -> Self {
+        self.http = http;
+        self
+    }
+
     /// HTTPS clone URL for the repo.
     fn repo_url(&self, repo: &RepoId) -> String {
         format!("{}/{}/{}.git", self.base_url, repo.owner, repo.name)
@@ -117,6 +126,17 @@ fn repo_path(&self, repo: &RepoId) -> String {
 
 #[async_trait]
 impl PlatformAdapter for CodebergAdapter {
+    fn capabilities(&self) -> Capabilities {
+        // Writes need a token; "check runs" here are commit statuses.
+        let write = self.token.is_some();
+        Capabilities {
+            commit_statuses: write,
+            comments: write,
+            comment_updates: write,
+            ..Capabilities::default()
+        }
+    }
+
     async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
         // Mirrors github/gitlab/bitbucket — shallow clone, then fall
         // back to fetch+checkout for a specific commit if the initial
@@ -220,24 +240,24 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("token {}", token))
-            .json(&payload)
-            .send()
+            .http
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("token {}", token))
+                    .json(payload),
+            )
             .await
             .map_err(|e| Error::GitHub(format!("Codeberg statuses API: {}", e)))?;
 
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(Error::GitHub(format!(
                 "Codeberg statuses API returned {}",
-                response.status()
+                response.status
             )));
         }
 
-        let data: serde_json::Value = response
+        let data = response
             .json()
-            .await
             .map_err(|e| Error::GitHub(format!("Codeberg statuses response: {}", e)))?;
 
         Ok(CheckRunId(
@@ -248,6 +268,10 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         ))
     }
 
+    async fn create_commit_status(&self, repo: &RepoId, check: CheckRun) -> Result<()> {
+        self.create_check_run(repo, check).await.map(|_| ())
+    }
+
     async fn update_check_run(&self, _id: CheckRunId, _status: CheckStatus) -> Result<()> {
         // Gitea/Forgejo commit statuses are append-only (like GitLab
         // pipeline statuses and Bitbucket build statuses). To "update",
@@ -277,24 +301,24 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("token {}", token))
-            .json(&payload)
-            .send()
+            .http
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("token {}", token))
+                    .json(payload),
+            )
             .await
             .map_err(|e| Error::GitHub(format!("Codeberg comments API: {}", e)))?;
 
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(Error::GitHub(format!(
                 "Codeberg comments API returned {}",
-                response.status()
+                response.status
             )));
         }
 
-        let data: serde_json::Value = response
+        let data = response
             .json()
-            .await
             .map_err(|e| Error::GitHub(format!("Codeberg comments response: {}", e)))?;
 
         Ok(CommentId(
@@ -305,6 +329,44 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         ))
     }
 
+    async fn update_comment(
+        &self,
+        repo: &RepoId,
+        _pr: PrId,
+        comment: &CommentId,
+        body: &str,
+    ) -> Result<()> {
+        // PATCH /api/v1/repos/{owner}/{repo}/issues/comments/{id}
+        let token = self.token.as_ref().ok_or_else(|| {
+            Error::Config("CODEBERG_TOKEN not set".to_string())
+        })?;
+
+        let url = format!(
+            "{}/repos/{}/issues/comments/{}",
+            self.api_url(),
+            self.repo_path(repo),
+            comment.0,
+        );
+
+        let response = self
+            .http
+            .send(
+                ApiRequest::patch(url)
+                    .header("Authorization", format!("token {}", token))
+                    .json(serde_json::json!({ "body": body })),
+            )
+            .await
+            .map_err(|e| Error::GitHub(format!("Codeberg comments API: {}", e)))?;
+
+        if !response.is_success() {
+            return Err(Error::GitHub(format!(
+                "Codeberg comments API returned {}",
+                response.status
+            )));
+        }
+        Ok(())
+    }
+
     async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId> {
         // POST /api/v1/repos/{owner}/{repo}/issues
         // Labels in Gitea are *numeric IDs*, not strings — so the
@@ -342,24 +404,24 @@ async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId>
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("token {}", token))
-            .json(&payload)
-            .send()
+            .http
+            .send(
+                ApiRequest::post(url)
+                    .header("Authorization", format!("token {}", token))
+                    .json(payload),
+            )
             .await
             .map_err(|e| Error::GitHub(format!("Codeberg issues API: {}", e)))?;
 
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(Error::GitHub(format!(
                 "Codeberg issues API returned {}",
-                response.status()
+                response.status
             )));
         }
 
-        let data: serde_json::Value = response
+        let data = response
             .json()
-            .await
             .map_err(|e| Error::GitHub(format!("Codeberg issues response: {}", e)))?;
 
         // Gitea returns the issue `number` (per-repo, human-facing) —
@@ -380,26 +442,26 @@ async fn get_default_branch(&self, repo: &RepoId) -> Result<String> {
             self.repo_path(repo),
         );
 
-        let mut req = self.client.get(&url);
+        let mut req = ApiRequest::get(url);
         if let Some(token) = self.token.as_ref() {
             req = req.header("Authorization", format!("token {}", token));
         }
 
-        let response = req
-            .send()
+        let response = self
+            .http
+            .send(req)
             .await
             .map_err(|e| Error::GitHub(format!("Codeberg repo API: {}", e)))?;
 
-        if !response.status().is_success() {
+        if !response.is_success() {
             return Err(Error::GitHub(format!(
                 "Codeberg repo API returned {}",
-                response.status()
+                response.status
             )));
         }
 
-        let data: serde_json::Value = response
+        let data = response
             .json()
-            .await
             .map_err(|e| Error::GitHub(format!("Codeberg repo response: {}", e)))?;
 
         Ok(data["default_branch"]
@@ -435,32 +497,27 @@ async fn get_file_contents(
             url.push_str(&format!("?ref={}", urlencoding::encode(r)));
         }
 
-        let mut req = self.client.get(&url);
+        let mut req = ApiRequest::get(url);
         if let Some(token) = self.token.as_ref() {
             req = req.header("Authorization", format!("token {}", token));
         }
 
-        let resp = req
-            .send()
+        let resp = self
+            .http
+            .send(req)
             .await
             .map_err(|e| Error::GitHub(format!("Codeberg raw API: {}", e)))?;
 
-        let status = resp.status();
-        if status == reqwest::StatusCode::NOT_FOUND {
+        if resp.status == 404 {
             return Ok(None);
         }
-        if !status.is_success() {
+        if !resp.is_success() {
             return Err(Error::GitHub(format!(
                 "Codeberg raw API returned {}",
-                status
+                resp.status
             )));
         }
-
-        let body = resp
-            .text()
-            .await
-            .map_err(|e| Error::GitHub(format!("Codeberg raw response: {}", e)))?;
-        Ok(Some(body))
+        Ok(Some(resp.body))
     }
 
     async fn create_review_comment(
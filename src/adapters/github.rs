@@ -1,39 +1,42 @@
 // SPDX-License-Identifier: MPL-2.0
 // Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
-//! GitHub platform adapter using Octocrab
+//! GitHub platform adapter over the REST API
 
 use async_trait::async_trait;
+use base64::Engine;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::http::{check, ApiRequest, ApiResponse, HttpApi, Method, ReqwestApi};
 use super::{
-    CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
-    PlatformAdapter, PrId, RepoId, ReviewCommentLocation,
+    AnnotationLevel, Capabilities, CheckAnnotation, CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, MergeMethod,
+    NewIssue, NewPullRequest, PlatformAdapter, PrId, PullRequestInfo, RepoId, ReviewCommentLocation,
 };
 use crate::error::{Error, Result};
 
-/// GitHub adapter using Octocrab
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// GitHub adapter
 pub struct GitHubAdapter {
-    client: octocrab::Octocrab,
-    /// Raw HTTP client for APIs not covered by Octocrab (e.g. inline review comments).
-    http: reqwest::Client,
+    http: Arc<dyn HttpApi>,
+    api_url: String,
     token: String,
 }
 
 impl GitHubAdapter {
     /// Create a new GitHub adapter with a token
     pub fn new(token: &str) -> Result<Self> {
-        let client = octocrab::Octocrab::builder()
-            .personal_token(token.to_string())
-            .build()
-            .map_err(|e| Error::GitHub(e.to_string()))?;
-
-        let http = reqwest::Client::builder()
+        let client = reqwest::Client::builder()
             .user_agent("echidnabot/0.1.0")
             .build()
             .map_err(|e| Error::GitHub(e.to_string()))?;
 
-        Ok(Self { client, http, token: token.to_string() })
+        Ok(Self {
+            http: Arc::new(ReqwestApi::new(client)),
+            api_url: DEFAULT_API_URL.to_string(),
+            token: token.to_string(),
+        })
     }
 
     /// Create adapter from environment variable
@@ -42,10 +45,125 @@ pub fn from_env() -> Result<Self> {
             .map_err(|_| Error::Config("GITHUB_TOKEN not set".to_string()))?;
         Self::new(&token)
     }
+
+    /// Call the API at `url` instead of `https://api.github.com` (a GitHub
+    /// Enterprise Server `/api/v3` root, or a test server).
+    pub fn with_api_url(mut self, url: &str) -> Self {
+        self.api_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Send requests through `http` instead of the network.
+    pub fn with_http(mut self, http: Arc<dyn HttpApi>) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// An authenticated request for `path` under the API root.
+    fn request(&self, method: Method, path: &str) -> ApiRequest {
+        let request = ApiRequest::new(method, format!("{}{}", self.api_url, path))
+            .header("Accept", "application/vnd.github+json");
+        // Without a token, public repositories still answer reads.
+        if self.token.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", self.token))
+        }
+    }
+
+    /// Send `request`; a non-2xx answer is an error naming `what`.
+    async fn send(&self, request: ApiRequest, what: &str) -> Result<ApiResponse> {
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        check("GitHub", what, response)
+    }
+}
+
+/// GitHub REST spelling of a check-run conclusion.
+fn conclusion_str(conclusion: &CheckConclusion) -> &'static str {
+    match conclusion {
+        CheckConclusion::Success => "success",
+        CheckConclusion::Failure => "failure",
+        CheckConclusion::Neutral => "neutral",
+        CheckConclusion::Cancelled => "cancelled",
+        CheckConclusion::Skipped => "skipped",
+        CheckConclusion::TimedOut => "timed_out",
+        CheckConclusion::ActionRequired => "action_required",
+    }
+}
+
+/// GitHub accepts at most this many annotations per request.
+const MAX_ANNOTATIONS: usize = 50;
+
+fn annotation_json(annotation: &CheckAnnotation) -> serde_json::Value {
+    serde_json::json!({
+        "path": annotation.path,
+        "start_line": annotation.line,
+        "end_line": annotation.line,
+        "annotation_level": match annotation.level {
+            AnnotationLevel::Notice => "notice",
+            AnnotationLevel::Warning => "warning",
+            AnnotationLevel::Failure => "failure",
+        },
+        "message": annotation.message,
+    })
+}
+
+/// `id` (or another numeric field) of a JSON response, as a string.
+fn numeric_field(response: &ApiResponse, field: &str, what: &str) -> Result<String> {
+    let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+    data[field]
+        .as_u64()
+        .map(|id| id.to_string())
+        .ok_or_else(|| Error::GitHub(format!("Missing {} in {} response", field, what)))
+}
+
+/// Percent-encode each segment of a repository path.
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|s| urlencoding::encode(s).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A PR's head, state and labels from its REST representation.
+fn pull_request_info(body: &serde_json::Value) -> PullRequestInfo {
+    PullRequestInfo {
+        head_sha: body["head"]["sha"].as_str().unwrap_or_default().to_string(),
+        open: body["state"] == "open" && body["merged"] != true,
+        draft: body["draft"] == true,
+        labels: body["labels"]
+            .as_array()
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(|l| l["name"].as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
 }
 
 #[async_trait]
 impl PlatformAdapter for GitHubAdapter {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            check_runs: true,
+            commit_statuses: true,
+            // `update_check_run` lacks the repository; see below.
+            check_run_updates: false,
+            comments: true,
+            comment_updates: true,
+            review_comments: true,
+            labels: true,
+            pull_requests: true,
+            commit_pull_requests: true,
+        }
+    }
+
     async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
         // Create a temporary directory for the clone
         let temp_dir = tempfile::tempdir().map_err(Error::Io)?;
@@ -111,48 +229,76 @@ async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
     }
 
     async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<CheckRunId> {
-        let checks = self.client.checks(&repo.owner, &repo.name);
-
-        use octocrab::params::checks::{CheckRunConclusion as OctoConclusion, CheckRunStatus as OctoStatus};
-
-        let (status, conclusion) = match check.status {
-            CheckStatus::Queued => (OctoStatus::Queued, None),
-            CheckStatus::InProgress => (OctoStatus::InProgress, None),
-            CheckStatus::Completed { conclusion, .. } => {
-                let c = match conclusion {
-                    CheckConclusion::Success => OctoConclusion::Success,
-                    CheckConclusion::Failure => OctoConclusion::Failure,
-                    CheckConclusion::Neutral => OctoConclusion::Neutral,
-                    CheckConclusion::Cancelled => OctoConclusion::Cancelled,
-                    CheckConclusion::Skipped => OctoConclusion::Skipped,
-                    CheckConclusion::TimedOut => OctoConclusion::TimedOut,
-                    CheckConclusion::ActionRequired => OctoConclusion::ActionRequired,
-                };
-                (OctoStatus::Completed, Some(c))
+        let mut payload = serde_json::json!({
+            "name": check.name,
+            "head_sha": check.head_sha,
+        });
+        if !check.actions.is_empty() {
+            payload["actions"] = serde_json::json!(check.actions);
+        }
+        match check.status {
+            CheckStatus::Queued => payload["status"] = "queued".into(),
+            CheckStatus::InProgress => payload["status"] = "in_progress".into(),
+            CheckStatus::Completed { conclusion, summary } => {
+                payload["status"] = "completed".into();
+                payload["conclusion"] = conclusion_str(&conclusion).into();
+                payload["output"] = serde_json::json!({
+                    "title": check.name,
+                    "summary": summary,
+                });
+                if !check.annotations.is_empty() {
+                    payload["output"]["annotations"] = check
+                        .annotations
+                        .iter()
+                        .take(MAX_ANNOTATIONS)
+                        .map(annotation_json)
+                        .collect();
+                }
             }
-        };
-
-        // Build check run request
-        let mut builder = checks.create_check_run(check.name, check.head_sha);
-
-        builder = builder.status(status);
-
-        if let Some(c) = conclusion {
-            builder = builder.conclusion(c);
         }
-
         if let Some(url) = check.details_url {
-            builder = builder.details_url(url);
+            payload["details_url"] = url.into();
+        }
+        if let Some(external_id) = check.external_id {
+            payload["external_id"] = external_id.into();
         }
 
-        let result = builder.send().await.map_err(|e| Error::GitHub(e.to_string()))?;
+        let path = format!("/repos/{}/{}/check-runs", repo.owner, repo.name);
+        let response = self
+            .send(self.request(Method::Post, &path).json(payload), "Check run")
+            .await?;
+        Ok(CheckRunId(numeric_field(&response, "id", "check run")?))
+    }
 
-        Ok(CheckRunId(result.id.to_string()))
+    async fn create_commit_status(&self, repo: &RepoId, check: CheckRun) -> Result<()> {
+        let (state, description) = match check.status {
+            CheckStatus::Queued | CheckStatus::InProgress => ("pending", String::new()),
+            CheckStatus::Completed { conclusion, summary } => {
+                let state = match conclusion {
+                    CheckConclusion::Success | CheckConclusion::Neutral | CheckConclusion::Skipped => "success",
+                    CheckConclusion::Failure | CheckConclusion::ActionRequired => "failure",
+                    CheckConclusion::Cancelled | CheckConclusion::TimedOut => "error",
+                };
+                (state, summary)
+            }
+        };
+        let mut payload = serde_json::json!({
+            "state": state,
+            "context": check.name,
+            "description": description,
+        });
+        if let Some(url) = check.details_url {
+            payload["target_url"] = url.into();
+        }
+        let path = format!("/repos/{}/{}/statuses/{}", repo.owner, repo.name, check.head_sha);
+        self.send(self.request(Method::Post, &path).json(payload), "Commit status")
+            .await?;
+        Ok(())
     }
 
     async fn update_check_run(&self, id: CheckRunId, status: CheckStatus) -> Result<()> {
-        // Note: Octocrab doesn't have direct update_check_run, would need raw API
-        // For now, log and return Ok
+        // PATCH /repos/{owner}/{repo}/check-runs/{id} needs the repository,
+        // which this method is not given. For now, log and return Ok
         tracing::info!("Would update check run {} to {:?}", id.0, status);
         Ok(())
     }
@@ -160,39 +306,61 @@ async fn update_check_run(&self, id: CheckRunId, status: CheckStatus) -> Result<
     async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<CommentId> {
         let pr_num: u64 = pr.0.parse().map_err(|_| Error::GitHub("Invalid PR ID".to_string()))?;
 
-        let comment = self
-            .client
-            .issues(&repo.owner, &repo.name)
-            .create_comment(pr_num, body)
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        // PR conversation comments are issue comments.
+        let path = format!("/repos/{}/{}/issues/{}/comments", repo.owner, repo.name, pr_num);
+        let response = self
+            .send(
+                self.request(Method::Post, &path)
+                    .json(serde_json::json!({ "body": body })),
+                "Comment",
+            )
+            .await?;
+        Ok(CommentId(numeric_field(&response, "id", "comment")?))
+    }
 
-        Ok(CommentId(comment.id.to_string()))
+    async fn update_comment(
+        &self,
+        repo: &RepoId,
+        _pr: PrId,
+        comment: &CommentId,
+        body: &str,
+    ) -> Result<()> {
+        // PR conversation comments are issue comments, addressed by id alone.
+        let path = format!(
+            "/repos/{}/{}/issues/comments/{}",
+            repo.owner, repo.name, comment.0
+        );
+        self.send(
+            self.request(Method::Patch, &path)
+                .json(serde_json::json!({ "body": body })),
+            "Comment update",
+        )
+        .await?;
+        Ok(())
     }
 
     async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId> {
-        let created = self
-            .client
-            .issues(&repo.owner, &repo.name)
-            .create(&issue.title)
-            .body(&issue.body)
-            .labels(issue.labels)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
-
-        Ok(IssueId(created.number.to_string()))
+        let path = format!("/repos/{}/{}/issues", repo.owner, repo.name);
+        let response = self
+            .send(
+                self.request(Method::Post, &path).json(serde_json::json!({
+                    "title": issue.title,
+                    "body": issue.body,
+                    "labels": issue.labels,
+                })),
+                "Issue",
+            )
+            .await?;
+        Ok(IssueId(numeric_field(&response, "number", "issue")?))
     }
 
     async fn get_default_branch(&self, repo: &RepoId) -> Result<String> {
-        let repo_info = self
-            .client
-            .repos(&repo.owner, &repo.name)
-            .get()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
-
-        Ok(repo_info.default_branch.unwrap_or_else(|| "main".to_string()))
+        let path = format!("/repos/{}/{}", repo.owner, repo.name);
+        let response = self
+            .send(self.request(Method::Get, &path), "Repository lookup")
+            .await?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(data["default_branch"].as_str().unwrap_or("main").to_string())
     }
 
     async fn get_file_contents(
@@ -201,35 +369,32 @@ async fn get_file_contents(
         branch: Option<&str>,
         path: &str,
     ) -> Result<Option<String>> {
-        // Bind the `repos(...)` call to a local; `get_content()` borrows
-        // from it, so without the binding the temp drops mid-expression.
-        let repo_handle = self.client.repos(&repo.owner, &repo.name);
-        let mut req = repo_handle.get_content().path(path);
+        // The raw media type skips the JSON base64 envelope:
+        //   GET /repos/{owner}/{repo}/contents/{path}?ref={branch}
+        let mut url = format!(
+            "/repos/{}/{}/contents/{}",
+            repo.owner,
+            repo.name,
+            encode_path(path)
+        );
         if let Some(r) = branch {
-            req = req.r#ref(r);
+            url.push_str(&format!("?ref={}", urlencoding::encode(r)));
         }
-        match req.send().await {
-            Ok(mut content) => {
-                // For a file path the response has a single item; directories
-                // return many. We only call with file paths.
-                if let Some(item) = content.items.pop() {
-                    Ok(item.decoded_content())
-                } else {
-                    Ok(None)
-                }
-            }
-            Err(e) => {
-                let msg = e.to_string();
-                // Map missing-file (404 / NotFound) to Ok(None) so callers
-                // can cascade through the directive resolver. Real
-                // failures (auth, rate-limit, network) bubble up.
-                if msg.contains("404") || msg.to_lowercase().contains("not found") {
-                    Ok(None)
-                } else {
-                    Err(Error::GitHub(msg))
-                }
-            }
+        let request = self
+            .request(Method::Get, &url)
+            .header("Accept", "application/vnd.github.raw+json");
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        // Map missing-file (404) to Ok(None) so callers can cascade
+        // through the directive resolver. Real failures (auth,
+        // rate-limit, network) bubble up.
+        if response.status == 404 {
+            return Ok(None);
         }
+        Ok(Some(check("GitHub", "Contents lookup", response)?.body))
     }
 
     async fn create_review_comment(
@@ -243,10 +408,7 @@ async fn create_review_comment(
 
         // GitHub API: POST /repos/{owner}/{repo}/pulls/{pull_number}/comments
         // Requires commit_id, path, side, and line (or position for legacy diffs).
-        let url = format!(
-            "https://api.github.com/repos/{}/{}/pulls/{}/comments",
-            repo.owner, repo.name, pr_num
-        );
+        let path = format!("/repos/{}/{}/pulls/{}/comments", repo.owner, repo.name, pr_num);
 
         let payload = serde_json::json!({
             "body": body,
@@ -256,36 +418,427 @@ async fn create_review_comment(
             "line": location.line,
         });
 
+        // 422 = file not in diff; callers fall back to create_comment.
         let response = self
-            .http
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .header("Accept", "application/vnd.github.v3+json")
-            .json(&payload)
-            .send()
-            .await
+            .send(self.request(Method::Post, &path).json(payload), "Review comment")
+            .await?;
+        Ok(CommentId(numeric_field(&response, "id", "review comment")?))
+    }
+
+    async fn approve_pull_request(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<()> {
+        let path = format!("/repos/{}/{}/pulls/{}/reviews", repo.owner, repo.name, pr.0);
+        self.send(
+            self.request(Method::Post, &path)
+                .json(serde_json::json!({ "event": "APPROVE", "body": body })),
+            "Approval",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_pull_request(&self, repo: &RepoId, pr: PrId) -> Result<PullRequestInfo> {
+        let path = format!("/repos/{}/{}/pulls/{}", repo.owner, repo.name, pr.0);
+        let response = self.send(self.request(Method::Get, &path), "PR lookup").await?;
+        let body = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(pull_request_info(&body))
+    }
+
+    async fn list_pull_requests_for_commit(&self, repo: &RepoId, commit: &str) -> Result<Vec<PrId>> {
+        let path = format!("/repos/{}/{}/commits/{}/pulls", repo.owner, repo.name, commit);
+        let response = self.send(self.request(Method::Get, &path), "Commit PR lookup").await?;
+        let body = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(body
+            .as_array()
+            .map(|prs| {
+                prs.iter()
+                    .filter(|pr| pr["state"] == "open")
+                    .filter_map(|pr| pr["number"].as_u64())
+                    .map(|number| PrId(number.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn list_open_pull_requests(&self, repo: &RepoId) -> Result<Vec<(PrId, PullRequestInfo)>> {
+        let path = format!("/repos/{}/{}/pulls?state=open&per_page=100", repo.owner, repo.name);
+        let response = self.send(self.request(Method::Get, &path), "PR listing").await?;
+        let body = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(body
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|pr| Some((PrId(pr["number"].as_u64()?.to_string()), pull_request_info(pr))))
+            .collect())
+    }
+
+    async fn get_branch_head(&self, repo: &RepoId, branch: &str) -> Result<String> {
+        let path = format!("/repos/{}/{}/git/ref/heads/{}", repo.owner, repo.name, branch);
+        let body = self
+            .send(self.request(Method::Get, &path), "Branch lookup")
+            .await?
+            .json()
             .map_err(|e| Error::GitHub(e.to_string()))?;
+        body["object"]["sha"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| Error::GitHub(format!("No SHA for branch {}", branch)))
+    }
+
+    async fn get_commit_parents(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        let path = format!("/repos/{}/{}/commits/{}", repo.owner, repo.name, commit);
+        let body = self
+            .send(self.request(Method::Get, &path), "Commit lookup")
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(body["parents"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|parent| parent["sha"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn list_commits_between(&self, repo: &RepoId, base: &str, head: &str) -> Result<Vec<String>> {
+        // The comparison lists at most 250 commits.
+        let path = format!("/repos/{}/{}/compare/{}...{}", repo.owner, repo.name, base, head);
+        let body = self
+            .send(self.request(Method::Get, &path), "Commit comparison")
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(body["commits"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|commit| commit["sha"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn list_commit_checks(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        let path = format!("/repos/{}/{}/commits/{}/check-runs?per_page=100", repo.owner, repo.name, commit);
+        let runs = self
+            .send(self.request(Method::Get, &path), "Check run listing")
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let path = format!("/repos/{}/{}/commits/{}/status", repo.owner, repo.name, commit);
+        let statuses = self
+            .send(self.request(Method::Get, &path), "Commit status lookup")
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let runs = runs["check_runs"].as_array().into_iter().flatten().map(|run| &run["name"]);
+        let contexts = statuses["statuses"].as_array().into_iter().flatten().map(|s| &s["context"]);
+        Ok(runs
+            .chain(contexts)
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn merge_pull_request(
+        &self,
+        repo: &RepoId,
+        pr: PrId,
+        head_sha: &str,
+        method: MergeMethod,
+    ) -> Result<()> {
+        let path = format!("/repos/{}/{}/pulls/{}/merge", repo.owner, repo.name, pr.0);
+        // `sha` makes GitHub refuse the merge (409) if the head moved;
+        // 405 = not mergeable (branch protection, conflicts).
+        self.send(
+            self.request(Method::Put, &path)
+                .json(serde_json::json!({ "sha": head_sha, "merge_method": method.as_str() })),
+            "Merge",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn add_labels(&self, repo: &RepoId, pr: PrId, labels: &[String]) -> Result<()> {
+        // PR labels live on the issue API.
+        let path = format!("/repos/{}/{}/issues/{}/labels", repo.owner, repo.name, pr.0);
+        self.send(
+            self.request(Method::Post, &path)
+                .json(serde_json::json!({ "labels": labels })),
+            "Labelling",
+        )
+        .await?;
+        Ok(())
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            // 422 = file not in diff; callers fall back to create_comment.
-            return Err(Error::GitHub(format!(
-                "Review comment rejected by GitHub ({}): {}",
-                status, text
-            )));
+    async fn mark_pull_request_draft(&self, repo: &RepoId, pr: PrId) -> Result<()> {
+        // Only the GraphQL API can convert a PR to draft, and it wants
+        // the PR's node id.
+        let path = format!("/repos/{}/{}/pulls/{}", repo.owner, repo.name, pr.0);
+        let pull = self
+            .send(self.request(Method::Get, &path), "PR lookup")
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let node_id = pull["node_id"]
+            .as_str()
+            .ok_or_else(|| Error::GitHub("Missing node_id in PR response".to_string()))?;
+
+        let response = self
+            .send(
+                self.request(Method::Post, "/graphql").json(serde_json::json!({
+                    "query": "mutation($id: ID!) { convertPullRequestToDraft(input: {pullRequestId: $id}) { pullRequest { isDraft } } }",
+                    "variables": { "id": node_id },
+                })),
+                "Draft conversion",
+            )
+            .await?
+            .json()
+            .map_err(|e| Error::GitHub(e.to_string()))?;
+        if let Some(errors) = response.get("errors") {
+            return Err(Error::GitHub(format!("Draft conversion rejected by GitHub: {}", errors)));
         }
+        Ok(())
+    }
 
-        let data: serde_json::Value = response
+    async fn close_pull_request(&self, repo: &RepoId, pr: PrId) -> Result<()> {
+        let path = format!("/repos/{}/{}/pulls/{}", repo.owner, repo.name, pr.0);
+        self.send(
+            self.request(Method::Patch, &path)
+                .json(serde_json::json!({ "state": "closed" })),
+            "Close",
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn list_files(&self, repo: &RepoId, branch: &str) -> Result<Vec<String>> {
+        let path = format!(
+            "/repos/{}/{}/git/trees/{}?recursive=1",
+            repo.owner, repo.name, branch
+        );
+        let response = self.send(self.request(Method::Get, &path), "Tree lookup").await?;
+        let body = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        // Very large trees come back truncated; detection only needs a
+        // representative sample, so that is fine here.
+        Ok(body["tree"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry["type"] == "blob")
+            .filter_map(|entry| entry["path"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn create_pull_request(&self, repo: &RepoId, pr: NewPullRequest) -> Result<PrId> {
+        // 1. Tip of the base branch.
+        let path = format!("/repos/{}/{}/git/ref/heads/{}", repo.owner, repo.name, pr.base);
+        let base = self
+            .send(self.request(Method::Get, &path), "Base branch lookup")
+            .await?
             .json()
-            .await
             .map_err(|e| Error::GitHub(e.to_string()))?;
+        let base_sha = base["object"]["sha"]
+            .as_str()
+            .ok_or_else(|| Error::GitHub(format!("No SHA for branch {}", pr.base)))?;
+
+        // 2. Head branch from that commit.
+        let path = format!("/repos/{}/{}/git/refs", repo.owner, repo.name);
+        self.send(
+            self.request(Method::Post, &path).json(serde_json::json!({
+                "ref": format!("refs/heads/{}", pr.head),
+                "sha": base_sha,
+            })),
+            "Branch creation",
+        )
+        .await?;
+
+        // 3. One commit per file.
+        for (file, content) in &pr.files {
+            let path = format!(
+                "/repos/{}/{}/contents/{}",
+                repo.owner,
+                repo.name,
+                encode_path(file)
+            );
+            self.send(
+                self.request(Method::Put, &path).json(serde_json::json!({
+                    "message": pr.commit_message,
+                    "content": base64::engine::general_purpose::STANDARD.encode(content),
+                    "branch": pr.head,
+                })),
+                "File creation",
+            )
+            .await?;
+        }
+        // Replacing a file takes the blob SHA it has on the branch.
+        for (file, content) in &pr.changes {
+            let path = format!(
+                "/repos/{}/{}/contents/{}",
+                repo.owner,
+                repo.name,
+                encode_path(file)
+            );
+            let lookup = format!("{}?ref={}", path, urlencoding::encode(&pr.head));
+            let existing = self
+                .send(self.request(Method::Get, &lookup), "File lookup")
+                .await?
+                .json()
+                .map_err(|e| Error::GitHub(e.to_string()))?;
+            let blob_sha = existing["sha"]
+                .as_str()
+                .ok_or_else(|| Error::GitHub(format!("No SHA for file {}", file)))?;
+            self.send(
+                self.request(Method::Put, &path).json(serde_json::json!({
+                    "message": pr.commit_message,
+                    "content": base64::engine::general_purpose::STANDARD.encode(content),
+                    "branch": pr.head,
+                    "sha": blob_sha,
+                })),
+                "File update",
+            )
+            .await?;
+        }
+
+        // 4. The PR itself.
+        let path = format!("/repos/{}/{}/pulls", repo.owner, repo.name);
+        let response = self
+            .send(
+                self.request(Method::Post, &path).json(serde_json::json!({
+                    "title": pr.title,
+                    "head": pr.head,
+                    "base": pr.base,
+                    "body": pr.body,
+                })),
+                "Pull request",
+            )
+            .await?;
+        Ok(PrId(numeric_field(&response, "number", "pull request")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::Platform;
+
+    fn repo() -> RepoId {
+        RepoId::new(Platform::GitHub, "o", "r")
+    }
+
+    #[tokio::test]
+    async fn create_pull_request_branches_commits_and_opens() {
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/repos/o/r/git/ref/heads/main", 200, r#"{"object": {"sha": "base123"}}"#);
+        api.respond(Method::Post, "/repos/o/r/git/refs", 201, "{}");
+        api.respond(Method::Put, "/repos/o/r/contents/.echidnabot.toml", 201, "{}");
+        api.respond(Method::Post, "/repos/o/r/pulls", 201, r#"{"number": 12}"#);
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+
+        let pr = NewPullRequest {
+            title: "Add config".into(),
+            body: "".into(),
+            base: "main".into(),
+            head: "echidnabot/setup".into(),
+            commit_message: "Add .echidnabot.toml".into(),
+            files: vec![(".echidnabot.toml".into(), "[provers]\n".into())],
+            changes: vec![],
+        };
+        assert_eq!(github.create_pull_request(&repo(), pr).await.unwrap().0, "12");
+
+        let requests = api.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].header_value("authorization"), Some("Bearer t"));
+        assert_eq!(requests[1].body.as_ref().unwrap()["sha"], "base123");
+        assert_eq!(requests[2].body.as_ref().unwrap()["content"], "W3Byb3ZlcnNdCg==");
+        assert_eq!(requests[2].body.as_ref().unwrap()["branch"], "echidnabot/setup");
+    }
+
+    #[tokio::test]
+    async fn create_pull_request_updates_existing_files_by_blob_sha() {
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/repos/o/r/git/ref/heads/main", 200, r#"{"object": {"sha": "base123"}}"#);
+        api.respond(Method::Post, "/repos/o/r/git/refs", 201, "{}");
+        api.respond(Method::Get, "/repos/o/r/contents/proofs/A.v", 200, r#"{"sha": "blob9"}"#);
+        api.respond(Method::Put, "/repos/o/r/contents/proofs/A.v", 200, "{}");
+        api.respond(Method::Post, "/repos/o/r/pulls", 201, r#"{"number": 13}"#);
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+
+        let pr = NewPullRequest {
+            title: "Repair A.v".into(),
+            body: "".into(),
+            base: "main".into(),
+            head: "echidnabot/repair/abc-a-v".into(),
+            commit_message: "Repair proofs/A.v".into(),
+            files: vec![],
+            changes: vec![("proofs/A.v".into(), "Qed.\n".into())],
+        };
+        assert_eq!(github.create_pull_request(&repo(), pr).await.unwrap().0, "13");
 
-        Ok(CommentId(
-            data["id"]
-                .as_u64()
-                .map(|id| id.to_string())
-                .ok_or_else(|| Error::GitHub("Missing id in review comment response".to_string()))?,
-        ))
+        let requests = api.requests();
+        assert_eq!(requests.len(), 5);
+        assert!(requests[2].url.ends_with("?ref=echidnabot%2Frepair%2Fabc-a-v"));
+        assert_eq!(requests[3].body.as_ref().unwrap()["sha"], "blob9");
+        assert_eq!(requests[3].body.as_ref().unwrap()["content"], "UWVkLgo=");
+    }
+
+    #[tokio::test]
+    async fn commit_pull_requests_are_the_open_ones() {
+        let api = Arc::new(MockApi::new());
+        api.respond(
+            Method::Get,
+            "/repos/o/r/commits/abc/pulls",
+            200,
+            r#"[{"number": 4, "state": "open"}, {"number": 2, "state": "closed"}, {"number": 9, "state": "open"}]"#,
+        );
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+
+        let prs = github.list_pull_requests_for_commit(&repo(), "abc").await.unwrap();
+        let numbers: Vec<&str> = prs.iter().map(|pr| pr.0.as_str()).collect();
+        assert_eq!(numbers, ["4", "9"]);
+    }
+
+    #[tokio::test]
+    async fn file_contents_map_404_to_none_and_surface_other_failures() {
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/repos/o/r/contents/proofs/A.v", 200, "Qed.");
+        api.respond(Method::Get, "/repos/o/r/contents/secret.v", 403, "rate limited");
+        let github = GitHubAdapter::new("").unwrap().with_http(api.clone());
+
+        let found = github.get_file_contents(&repo(), Some("dev"), "proofs/A.v").await.unwrap();
+        assert_eq!(found.as_deref(), Some("Qed."));
+        assert!(api.requests()[0].url.ends_with("?ref=dev"));
+        assert_eq!(api.requests()[0].header_value("authorization"), None, "no token, no auth header");
+
+        assert_eq!(github.get_file_contents(&repo(), None, "missing.v").await.unwrap(), None);
+        let err = github.get_file_contents(&repo(), None, "secret.v").await.unwrap_err();
+        assert!(err.to_string().contains("403"));
+    }
+
+    #[tokio::test]
+    async fn api_url_points_at_a_wiremock_server() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/o/r/check-runs"))
+            .and(body_partial_json(serde_json::json!({ "status": "completed", "conclusion": "failure" })))
+            .respond_with(ResponseTemplate::new(201).set_body_string(r#"{"id": 7}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let github = GitHubAdapter::new("t").unwrap().with_api_url(&server.uri());
+        let check = CheckRun {
+            name: "echidnabot".into(),
+            head_sha: "abc".into(),
+            status: CheckStatus::Completed {
+                conclusion: CheckConclusion::Failure,
+                summary: "1 proof failed".into(),
+            },
+            details_url: None,
+            external_id: None,
+            actions: Vec::new(),
+            annotations: Vec::new(),
+        };
+        assert_eq!(github.create_check_run(&repo(), check).await.unwrap().0, "7");
     }
 }
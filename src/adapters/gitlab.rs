@@ -4,9 +4,11 @@
 
 use async_trait::async_trait;
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::http::{ApiRequest, ApiResponse, HttpApi, ReqwestApi};
 use super::{
-    CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
+    Capabilities, CheckConclusion, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, NewIssue,
     PlatformAdapter, PrId, RepoId, ReviewCommentLocation,
 };
 use crate::error::{Error, Result};
@@ -15,7 +17,7 @@
 pub struct GitLabAdapter {
     base_url: String,
     token: Option<String>,
-    client: reqwest::Client,
+    http: Arc<dyn HttpApi>,
 }
 
 impl GitLabAdapter {
@@ -24,10 +26,16 @@ pub fn new(base_url: Option<&str>) -> Self {
         Self {
             base_url: base.trim_end_matches('/').to_string(),
             token: std::env::var("GITLAB_TOKEN").ok(),
-            client: reqwest::Client::new(),
+            http: Arc::new(ReqwestApi::default()),
         }
     }
 
+    /// Send requests through `http` instead of the network.
+    pub fn with_http(mut self, http: Arc<dyn HttpApi>) -> Self {
+        self.http = http;
+        self
+    }
+
     fn repo_url(&self, repo: &RepoId) -> String {
         format!("{}/{}/{}.git", self.base_url, repo.owner, repo.name)
     }
@@ -39,10 +47,29 @@ fn api_url(&self) -> String {
     fn project_path(&self, repo: &RepoId) -> String {
         format!("{}/{}", repo.owner, repo.name)
     }
+
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse> {
+        self.http
+            .send(request)
+            .await
+            .map_err(|e| Error::GitHub(e.to_string()))
+    }
 }
 
 #[async_trait]
 impl PlatformAdapter for GitLabAdapter {
+    fn capabilities(&self) -> Capabilities {
+        // Writes need a token; "check runs" here are pipeline statuses.
+        let write = self.token.is_some();
+        Capabilities {
+            commit_statuses: write,
+            comments: write,
+            comment_updates: write,
+            commit_pull_requests: true,
+            ..Capabilities::default()
+        }
+    }
+
     async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
         let temp_dir = tempfile::tempdir().map_err(Error::Io)?;
         let clone_path = temp_dir.keep();
@@ -138,18 +165,14 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("PRIVATE-TOKEN", token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("PRIVATE-TOKEN", token.as_str())
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(CheckRunId(
             data["id"]
@@ -159,6 +182,10 @@ async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<Check
         ))
     }
 
+    async fn create_commit_status(&self, repo: &RepoId, check: CheckRun) -> Result<()> {
+        self.create_check_run(repo, check).await.map(|_| ())
+    }
+
     async fn update_check_run(&self, _id: CheckRunId, _status: CheckStatus) -> Result<()> {
         // GitLab doesn't support updating commit statuses after creation
         Ok(())
@@ -183,18 +210,14 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("PRIVATE-TOKEN", token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("PRIVATE-TOKEN", token.as_str())
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(CommentId(
             data["id"]
@@ -204,6 +227,44 @@ async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<Co
         ))
     }
 
+    async fn update_comment(
+        &self,
+        repo: &RepoId,
+        pr: PrId,
+        comment: &CommentId,
+        body: &str,
+    ) -> Result<()> {
+        let token = self.token.as_ref().ok_or_else(|| {
+            Error::Config("GITLAB_TOKEN not set".to_string())
+        })?;
+
+        let project_path = self.project_path(repo);
+        let encoded_project = urlencoding::encode(&project_path);
+        let url = format!(
+            "{}/projects/{}/merge_requests/{}/notes/{}",
+            self.api_url(),
+            encoded_project,
+            pr.0,
+            comment.0
+        );
+
+        let response = self
+            .send(
+                ApiRequest::put(url)
+                    .header("PRIVATE-TOKEN", token.as_str())
+                    .json(serde_json::json!({ "body": body })),
+            )
+            .await?;
+
+        if !response.is_success() {
+            return Err(Error::GitHub(format!(
+                "GitLab note update returned {}",
+                response.status
+            )));
+        }
+        Ok(())
+    }
+
     async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId> {
         let token = self.token.as_ref().ok_or_else(|| {
             Error::Config("GITLAB_TOKEN not set".to_string())
@@ -224,18 +285,14 @@ async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId>
         });
 
         let response = self
-            .client
-            .post(&url)
-            .header("PRIVATE-TOKEN", token)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(
+                ApiRequest::post(url)
+                    .header("PRIVATE-TOKEN", token.as_str())
+                    .json(payload),
+            )
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(IssueId(
             data["iid"]
@@ -259,17 +316,10 @@ async fn get_default_branch(&self, repo: &RepoId) -> Result<String> {
         );
 
         let response = self
-            .client
-            .get(&url)
-            .header("PRIVATE-TOKEN", token)
-            .send()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+            .send(ApiRequest::get(url).header("PRIVATE-TOKEN", token.as_str()))
+            .await?;
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::GitHub(e.to_string()))?;
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
 
         Ok(data["default_branch"]
             .as_str()
@@ -301,29 +351,57 @@ async fn get_file_contents(
             file_path,
             urlencoding::encode(r#ref),
         );
-        let mut req = self.client.get(&url);
+        let mut req = ApiRequest::get(url);
         if let Some(token) = self.token.as_ref() {
-            req = req.header("PRIVATE-TOKEN", token);
+            req = req.header("PRIVATE-TOKEN", token.as_str());
         }
-        let resp = req
-            .send()
+        let resp = self
+            .http
+            .send(req)
             .await
             .map_err(|e| Error::GitHub(format!("GitLab files API: {}", e)))?;
-        let status = resp.status();
-        if status == reqwest::StatusCode::NOT_FOUND {
+        if resp.status == 404 {
             return Ok(None);
         }
-        if !status.is_success() {
+        if !resp.is_success() {
             return Err(Error::GitHub(format!(
                 "GitLab files API returned {}",
-                status
+                resp.status
             )));
         }
-        let body = resp
-            .text()
-            .await
-            .map_err(|e| Error::GitHub(format!("GitLab files response: {}", e)))?;
-        Ok(Some(body))
+        Ok(Some(resp.body))
+    }
+
+    async fn list_pull_requests_for_commit(&self, repo: &RepoId, commit: &str) -> Result<Vec<PrId>> {
+        let project_path = self.project_path(repo);
+        let url = format!(
+            "{}/projects/{}/repository/commits/{}/merge_requests",
+            self.api_url(),
+            urlencoding::encode(&project_path),
+            commit
+        );
+        let mut req = ApiRequest::get(url);
+        if let Some(token) = self.token.as_ref() {
+            req = req.header("PRIVATE-TOKEN", token.as_str());
+        }
+        let response = self.send(req).await?;
+        if !response.is_success() {
+            return Err(Error::GitHub(format!(
+                "GitLab commit MR lookup returned {}",
+                response.status
+            )));
+        }
+        let data = response.json().map_err(|e| Error::GitHub(e.to_string()))?;
+        Ok(data
+            .as_array()
+            .map(|mrs| {
+                mrs.iter()
+                    .filter(|mr| mr["state"] == "opened")
+                    .filter_map(|mr| mr["iid"].as_u64())
+                    .map(|iid| PrId(iid.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
     async fn create_review_comment(
@@ -342,3 +420,34 @@ async fn create_review_comment(
         self.create_comment(repo, pr, body).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+
+    #[tokio::test]
+    async fn mr_notes_go_to_the_configured_instance() {
+        use wiremock::matchers::{body_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/group%2Fproofs/merge_requests/3/notes"))
+            .and(header("PRIVATE-TOKEN", "glpat"))
+            .and(body_json(serde_json::json!({ "body": "All proofs verified" })))
+            .respond_with(ResponseTemplate::new(201).set_body_string(r#"{"id": 41}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut gitlab = GitLabAdapter::new(Some(&server.uri()));
+        gitlab.token = Some("glpat".into());
+        let repo = RepoId::new(Platform::GitLab, "group", "proofs");
+        let note = gitlab
+            .create_comment(&repo, PrId("3".into()), "All proofs verified")
+            .await
+            .unwrap();
+        assert_eq!(note.0, "41");
+    }
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! HTTP layer shared by the platform adapters
+//!
+//! Every REST call the GitHub, GitLab, Bitbucket and Codeberg adapters
+//! make goes through an [`HttpApi`]. In production that is [`ReqwestApi`].
+//! Tests either swap in [`super::testing::MockApi`] with the adapter's
+//! `with_http`, or keep the real client and point the adapter at a
+//! `wiremock` server (`with_api_url`, or the base URL for GitLab and
+//! Codeberg). Both leave the adapter's own request building and response
+//! handling under test.
+
+use async_trait::async_trait;
+
+use crate::error::{Error, Result};
+
+/// HTTP method of an [`ApiRequest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+/// One platform API call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiRequest {
+    pub method: Method,
+    /// Absolute URL, query string included.
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// JSON body, if any.
+    pub body: Option<serde_json::Value>,
+}
+
+impl ApiRequest {
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new(Method::Get, url)
+    }
+
+    pub fn post(url: impl Into<String>) -> Self {
+        Self::new(Method::Post, url)
+    }
+
+    pub fn put(url: impl Into<String>) -> Self {
+        Self::new(Method::Put, url)
+    }
+
+    pub fn patch(url: impl Into<String>) -> Self {
+        Self::new(Method::Patch, url)
+    }
+
+    /// Set a header, replacing any earlier value (names are
+    /// case-insensitive).
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn json(mut self, body: serde_json::Value) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// The value of header `name`, if set.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The URL's path, without scheme, host or query string.
+    pub fn path(&self) -> &str {
+        let rest = self.url.split_once("://").map_or(self.url.as_str(), |(_, rest)| rest);
+        let path = rest.find('/').map_or("/", |i| &rest[i..]);
+        path.split(['?', '#']).next().unwrap_or(path)
+    }
+}
+
+/// A platform's answer: status code and raw body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl ApiResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn json(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+/// Transport for platform API calls.
+///
+/// Only transport failures are errors; a 4xx or 5xx is an `Ok` response
+/// for the adapter to interpret.
+#[async_trait]
+pub trait HttpApi: Send + Sync {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse>;
+}
+
+/// [`HttpApi`] over a `reqwest` client.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestApi {
+    client: reqwest::Client,
+}
+
+impl ReqwestApi {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl HttpApi for ReqwestApi {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse> {
+        let method = match request.method {
+            Method::Get => reqwest::Method::GET,
+            Method::Post => reqwest::Method::POST,
+            Method::Put => reqwest::Method::PUT,
+            Method::Patch => reqwest::Method::PATCH,
+            Method::Delete => reqwest::Method::DELETE,
+        };
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(body) = &request.body {
+            builder = builder.json(body);
+        }
+        let response = builder.send().await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        Ok(ApiResponse { status, body })
+    }
+}
+
+/// `response`, or an error naming `what` and the platform's answer when
+/// the status is not 2xx.
+pub(crate) fn check(platform: &str, what: &str, response: ApiResponse) -> Result<ApiResponse> {
+    if response.is_success() {
+        Ok(response)
+    } else {
+        Err(Error::GitHub(format!(
+            "{} rejected by {} ({}): {}",
+            what, platform, response.status, response.body
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_replaces_case_insensitively() {
+        let request = ApiRequest::get("https://api.github.com/repos/o/r?x=1")
+            .header("Accept", "application/json")
+            .header("accept", "text/plain");
+        assert_eq!(request.headers.len(), 1);
+        assert_eq!(request.header_value("ACCEPT"), Some("text/plain"));
+        assert_eq!(request.path(), "/repos/o/r");
+        assert_eq!(ApiRequest::get("http://127.0.0.1:8080").path(), "/");
+    }
+
+    #[tokio::test]
+    async fn reqwest_api_talks_to_a_wiremock_server() {
+        use wiremock::matchers::{body_json, header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/things"))
+            .and(header("x-token", "t"))
+            .and(body_json(serde_json::json!({ "a": 1 })))
+            .respond_with(ResponseTemplate::new(201).set_body_string(r#"{"id": 5}"#))
+            .mount(&server)
+            .await;
+
+        let api = ReqwestApi::default();
+        let response = api
+            .send(
+                ApiRequest::post(format!("{}/things", server.uri()))
+                    .header("x-token", "t")
+                    .json(serde_json::json!({ "a": 1 })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.json().unwrap()["id"], 5);
+
+        let missing = api.send(ApiRequest::get(server.uri())).await.unwrap();
+        assert_eq!(missing.status, 404, "non-2xx is a response, not an error");
+        assert!(check("GitHub", "Lookup", missing).is_err());
+    }
+}
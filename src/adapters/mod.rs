@@ -8,6 +8,9 @@
 pub mod gitlab;
 pub mod bitbucket;
 pub mod codeberg;
+pub mod http;
+pub mod read_only;
+pub mod testing;
 
 use async_trait::async_trait;
 use std::path::PathBuf;
@@ -73,7 +76,7 @@ pub enum CheckStatus {
 }
 
 /// Check run conclusion
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum CheckConclusion {
     Success,
     Failure,
@@ -91,6 +94,75 @@ pub struct CheckRun {
     pub head_sha: String,
     pub status: CheckStatus,
     pub details_url: Option<String>,
+    /// Opaque correlation id echoed back by the platform in later
+    /// check-run events. echidnabot stores the job id here.
+    pub external_id: Option<String>,
+    /// Buttons rendered on the check run (GitHub only; max 3). Clicking
+    /// one delivers a `check_run` / `requested_action` webhook.
+    pub actions: Vec<CheckRunAction>,
+    /// Notes attached to lines of the checked files (GitHub only; the
+    /// first 50 are sent). Shown only with a completed status.
+    pub annotations: Vec<CheckAnnotation>,
+}
+
+/// A note on one line of a file, shown with the check run's diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckAnnotation {
+    /// Repo-relative path.
+    pub path: String,
+    /// 1-based.
+    pub line: u32,
+    pub level: AnnotationLevel,
+    pub message: String,
+}
+
+/// How an annotation is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+/// A requested-action button on a check run.
+///
+/// GitHub limits: `label` ≤ 20 chars, `description` ≤ 40 chars,
+/// `identifier` ≤ 20 chars.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckRunAction {
+    pub label: String,
+    pub description: String,
+    pub identifier: String,
+}
+
+/// How a pull request is merged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    Merge,
+    #[default]
+    Squash,
+    Rebase,
+}
+
+impl MergeMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeMethod::Merge => "merge",
+            MergeMethod::Squash => "squash",
+            MergeMethod::Rebase => "rebase",
+        }
+    }
+}
+
+/// Current state of a pull request, as the platform reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestInfo {
+    pub head_sha: String,
+    /// Open and not yet merged.
+    pub open: bool,
+    pub draft: bool,
+    pub labels: Vec<String>,
 }
 
 /// Issue to create
@@ -101,6 +173,23 @@ pub struct NewIssue {
     pub labels: Vec<String>,
 }
 
+/// Pull request to open, together with the files it adds or changes.
+///
+/// The adapter creates `head` from the tip of `base`, commits each file
+/// onto it with `commit_message`, then opens the PR.
+#[derive(Debug, Clone)]
+pub struct NewPullRequest {
+    pub title: String,
+    pub body: String,
+    pub base: String,
+    pub head: String,
+    pub commit_message: String,
+    /// `(path, content)` pairs; the paths must not exist on `base` yet.
+    pub files: Vec<(String, String)>,
+    /// `(path, content)` pairs replacing files that exist on `base`.
+    pub changes: Vec<(String, String)>,
+}
+
 /// Location anchor for an inline PR review comment.
 ///
 /// Used by Consultant mode to attach failure notes directly to the
@@ -132,25 +221,57 @@ pub fn build_adapter(
 ) -> crate::error::Result<Box<dyn PlatformAdapter>> {
     use crate::adapters::{
         bitbucket::BitbucketAdapter, codeberg::CodebergAdapter, github::GitHubAdapter,
-        gitlab::GitLabAdapter,
+        gitlab::GitLabAdapter, read_only::ReadOnlyAdapter,
     };
-    match platform {
+    let adapter: Box<dyn PlatformAdapter> = match platform {
         Platform::GitHub => {
             let token = config
                 .github
                 .as_ref()
                 .and_then(|g| g.token.clone())
                 .unwrap_or_default();
-            Ok(Box::new(GitHubAdapter::new(&token)?))
+            Box::new(GitHubAdapter::new(&token)?)
         }
-        Platform::GitLab => Ok(Box::new(GitLabAdapter::new(
+        Platform::GitLab => Box::new(GitLabAdapter::new(
             config.gitlab.as_ref().map(|g| g.url.as_str()),
-        ))),
-        Platform::Bitbucket => Ok(Box::new(BitbucketAdapter::new(None))),
-        Platform::Codeberg => Ok(Box::new(CodebergAdapter::new(
+        )),
+        Platform::Bitbucket => Box::new(BitbucketAdapter::new(None)),
+        Platform::Codeberg => Box::new(CodebergAdapter::new(
             config.codeberg.as_ref().map(|c| c.url.as_str()),
-        ))),
+        )),
+    };
+    if config.bot.observer {
+        return Ok(Box::new(ReadOnlyAdapter::new(adapter)));
     }
+    Ok(adapter)
+}
+
+/// What an adapter can actually do on its platform.
+///
+/// Callers consult this instead of calling a method and treating its
+/// error (or, for `update_check_run`, its silent `Ok`) as the answer;
+/// see [`crate::reporting`] for how results degrade from check runs to
+/// commit statuses to comments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// Check runs with a Markdown summary and action buttons.
+    pub check_runs: bool,
+    /// Commit statuses: a state and a one-line description.
+    pub commit_statuses: bool,
+    /// Check runs / statuses can be changed after creation.
+    pub check_run_updates: bool,
+    /// Comments on PRs.
+    pub comments: bool,
+    /// Editing a posted comment (sticky result comments).
+    pub comment_updates: bool,
+    /// Comments anchored to a line of the diff.
+    pub review_comments: bool,
+    /// Labels on PRs.
+    pub labels: bool,
+    /// Reading, approving, merging, drafting and closing PRs.
+    pub pull_requests: bool,
+    /// Listing the PRs that contain a commit.
+    pub commit_pull_requests: bool,
 }
 
 /// Platform adapter trait
@@ -158,18 +279,47 @@ pub fn build_adapter(
 /// Abstracts operations across GitHub, GitLab, Bitbucket
 #[async_trait]
 pub trait PlatformAdapter: Send + Sync {
+    /// What this adapter supports. The default claims nothing.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+
     /// Clone a repository to a local path
     async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf>;
 
     /// Create a check run (GitHub) or pipeline status (GitLab)
     async fn create_check_run(&self, repo: &RepoId, check: CheckRun) -> Result<CheckRunId>;
 
+    /// Set a commit status for `check.head_sha`, named `check.name`, with
+    /// the check's summary as its description (callers shorten it).
+    ///
+    /// On platforms without check runs `create_check_run` already does
+    /// this. Adapters without commit statuses return an error.
+    async fn create_commit_status(&self, repo: &RepoId, check: CheckRun) -> Result<()> {
+        let _ = (repo, check);
+        Err(crate::error::Error::Unsupported(
+            "commit statuses are not supported on this platform".to_string(),
+        ))
+    }
+
     /// Update a check run status
     async fn update_check_run(&self, id: CheckRunId, status: CheckStatus) -> Result<()>;
 
     /// Create a comment on a PR/MR
     async fn create_comment(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<CommentId>;
 
+    /// Replace the body of a comment previously created on `pr`.
+    ///
+    /// Used to keep one result comment per PR up to date. An error (e.g.
+    /// the comment was deleted) tells the caller to create a new one.
+    async fn update_comment(
+        &self,
+        repo: &RepoId,
+        pr: PrId,
+        comment: &CommentId,
+        body: &str,
+    ) -> Result<()>;
+
     /// Create an issue
     async fn create_issue(&self, repo: &RepoId, issue: NewIssue) -> Result<IssueId>;
 
@@ -204,4 +354,164 @@ async fn create_review_comment(
         body: &str,
         location: ReviewCommentLocation,
     ) -> Result<CommentId>;
+
+    /// Submit an approving review on a PR.
+    ///
+    /// Used by the dependency-PR auto-merge policy. Adapters without
+    /// review support return an error.
+    async fn approve_pull_request(&self, repo: &RepoId, pr: PrId, body: &str) -> Result<()> {
+        let _ = (repo, pr, body);
+        Err(crate::error::Error::Internal(
+            "approve_pull_request is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Fetch a PR's head, state and labels.
+    ///
+    /// Used by auto-merge to re-check the PR just before merging.
+    /// Adapters without PR lookup return an error.
+    async fn get_pull_request(&self, repo: &RepoId, pr: PrId) -> Result<PullRequestInfo> {
+        let _ = (repo, pr);
+        Err(crate::error::Error::Internal(
+            "get_pull_request is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Open PRs whose commits include `commit`.
+    ///
+    /// Lets the result of a push-triggered job be reported on the PRs the
+    /// pushed commit belongs to. Adapters without the lookup return an
+    /// error.
+    async fn list_pull_requests_for_commit(&self, repo: &RepoId, commit: &str) -> Result<Vec<PrId>> {
+        let _ = (repo, commit);
+        Err(crate::error::Error::Internal(
+            "list_pull_requests_for_commit is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Every open PR with its current head.
+    ///
+    /// Used by the freshness reconciler (see `crate::freshness`) to find
+    /// heads without a result. Adapters without PR listing return an
+    /// error.
+    async fn list_open_pull_requests(&self, repo: &RepoId) -> Result<Vec<(PrId, PullRequestInfo)>> {
+        let _ = repo;
+        Err(crate::error::Error::Internal(
+            "list_open_pull_requests is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// The commit `branch` currently points at.
+    ///
+    /// Adapters without branch lookup return an error.
+    async fn get_branch_head(&self, repo: &RepoId, branch: &str) -> Result<String> {
+        let _ = (repo, branch);
+        Err(crate::error::Error::Internal(
+            "get_branch_head is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// SHAs of `commit`'s parents; more than one for a merge commit.
+    ///
+    /// Used by the `[push] commits = "merges"` policy. Adapters without
+    /// commit lookup return an error.
+    async fn get_commit_parents(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        let _ = (repo, commit);
+        Err(crate::error::Error::Internal(
+            "get_commit_parents is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Commits reachable from `head` but not from `base`, oldest first.
+    ///
+    /// After a force-push, `base` the new head and `head` the old one give
+    /// the commits the push dropped. Adapters without the lookup return an
+    /// error.
+    async fn list_commits_between(&self, repo: &RepoId, base: &str, head: &str) -> Result<Vec<String>> {
+        let _ = (repo, base, head);
+        Err(crate::error::Error::Internal(
+            "list_commits_between is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Names of the check runs and commit-status contexts posted on
+    /// `commit`.
+    ///
+    /// Lets the freshness reconciler re-post a result the platform lost.
+    /// Adapters without the lookup return an error.
+    async fn list_commit_checks(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        let _ = (repo, commit);
+        Err(crate::error::Error::Internal(
+            "list_commit_checks is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Merge a PR, but only if its head is still `head_sha` — a push
+    /// after verification must not be merged unverified.
+    ///
+    /// Adapters without merge support return an error.
+    async fn merge_pull_request(
+        &self,
+        repo: &RepoId,
+        pr: PrId,
+        head_sha: &str,
+        method: MergeMethod,
+    ) -> Result<()> {
+        let _ = (repo, pr, head_sha, method);
+        Err(crate::error::Error::Internal(
+            "merge_pull_request is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Add labels to a PR, keeping the ones it already has.
+    ///
+    /// Used by the stale-failure policy to escalate. Adapters without
+    /// label support return an error.
+    async fn add_labels(&self, repo: &RepoId, pr: PrId, labels: &[String]) -> Result<()> {
+        let _ = (repo, pr, labels);
+        Err(crate::error::Error::Internal(
+            "add_labels is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Convert an open PR back to a draft.
+    ///
+    /// Adapters without draft support return an error.
+    async fn mark_pull_request_draft(&self, repo: &RepoId, pr: PrId) -> Result<()> {
+        let _ = (repo, pr);
+        Err(crate::error::Error::Internal(
+            "mark_pull_request_draft is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Close a PR without merging it.
+    ///
+    /// Adapters without PR state changes return an error.
+    async fn close_pull_request(&self, repo: &RepoId, pr: PrId) -> Result<()> {
+        let _ = (repo, pr);
+        Err(crate::error::Error::Internal(
+            "close_pull_request is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// List every file path on `branch`, relative to the repo root.
+    ///
+    /// Used to detect provers for the bootstrap config PR. Adapters
+    /// without tree listing return an error.
+    async fn list_files(&self, repo: &RepoId, branch: &str) -> Result<Vec<String>> {
+        let _ = (repo, branch);
+        Err(crate::error::Error::Internal(
+            "list_files is not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Create a branch, commit files onto it and open a PR.
+    ///
+    /// Adapters without PR creation return an error.
+    async fn create_pull_request(&self, repo: &RepoId, pr: NewPullRequest) -> Result<PrId> {
+        let _ = (repo, pr);
+        Err(crate::error::Error::Internal(
+            "create_pull_request is not supported on this platform".to_string(),
+        ))
+    }
 }
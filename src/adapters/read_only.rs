@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Read-only adapter for observer instances (`[bot] observer = true`)
+//!
+//! A compliance mirror ingests the same webhooks as the primary instance,
+//! verifies and records everything, but must never write to a platform.
+//! [`super::build_adapter`] wraps every adapter in [`ReadOnlyAdapter`] on
+//! such an instance: reads (cloning, file contents, PR and commit lookups)
+//! go through, and every write — check runs, statuses, comments, issues,
+//! labels, reviews, merges, PR changes — fails with
+//! [`Error::Unsupported`] without a request being made. Its capabilities
+//! claim none of the write channels, so reporting finds nothing to post to.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+
+use super::{
+    Capabilities, CheckRun, CheckRunId, CheckStatus, CommentId, IssueId, MergeMethod, NewIssue,
+    NewPullRequest, PlatformAdapter, PrId, PullRequestInfo, RepoId, ReviewCommentLocation,
+};
+use crate::error::{Error, Result};
+
+/// An adapter with its writes refused.
+pub struct ReadOnlyAdapter {
+    inner: Box<dyn PlatformAdapter>,
+}
+
+impl ReadOnlyAdapter {
+    pub fn new(inner: Box<dyn PlatformAdapter>) -> Self {
+        Self { inner }
+    }
+}
+
+fn refused<T>(operation: &str) -> Result<T> {
+    tracing::debug!("Observer mode: {} not sent", operation);
+    Err(Error::Unsupported(format!("{} is disabled on a read-only observer instance", operation)))
+}
+
+#[async_trait]
+impl PlatformAdapter for ReadOnlyAdapter {
+    fn capabilities(&self) -> Capabilities {
+        let inner = self.inner.capabilities();
+        Capabilities {
+            commit_pull_requests: inner.commit_pull_requests,
+            ..Capabilities::default()
+        }
+    }
+
+    async fn clone_repo(&self, repo: &RepoId, commit: &str) -> Result<PathBuf> {
+        self.inner.clone_repo(repo, commit).await
+    }
+
+    async fn create_check_run(&self, _repo: &RepoId, _check: CheckRun) -> Result<CheckRunId> {
+        refused("create_check_run")
+    }
+
+    async fn create_commit_status(&self, _repo: &RepoId, _check: CheckRun) -> Result<()> {
+        refused("create_commit_status")
+    }
+
+    async fn update_check_run(&self, _id: CheckRunId, _status: CheckStatus) -> Result<()> {
+        refused("update_check_run")
+    }
+
+    async fn create_comment(&self, _repo: &RepoId, _pr: PrId, _body: &str) -> Result<CommentId> {
+        refused("create_comment")
+    }
+
+    async fn update_comment(
+        &self,
+        _repo: &RepoId,
+        _pr: PrId,
+        _comment: &CommentId,
+        _body: &str,
+    ) -> Result<()> {
+        refused("update_comment")
+    }
+
+    async fn create_issue(&self, _repo: &RepoId, _issue: NewIssue) -> Result<IssueId> {
+        refused("create_issue")
+    }
+
+    async fn get_default_branch(&self, repo: &RepoId) -> Result<String> {
+        self.inner.get_default_branch(repo).await
+    }
+
+    async fn get_file_contents(
+        &self,
+        repo: &RepoId,
+        branch: Option<&str>,
+        path: &str,
+    ) -> Result<Option<String>> {
+        self.inner.get_file_contents(repo, branch, path).await
+    }
+
+    async fn create_review_comment(
+        &self,
+        _repo: &RepoId,
+        _pr: PrId,
+        _body: &str,
+        _location: ReviewCommentLocation,
+    ) -> Result<CommentId> {
+        refused("create_review_comment")
+    }
+
+    async fn approve_pull_request(&self, _repo: &RepoId, _pr: PrId, _body: &str) -> Result<()> {
+        refused("approve_pull_request")
+    }
+
+    async fn get_pull_request(&self, repo: &RepoId, pr: PrId) -> Result<PullRequestInfo> {
+        self.inner.get_pull_request(repo, pr).await
+    }
+
+    async fn list_pull_requests_for_commit(&self, repo: &RepoId, commit: &str) -> Result<Vec<PrId>> {
+        self.inner.list_pull_requests_for_commit(repo, commit).await
+    }
+
+    async fn list_open_pull_requests(&self, repo: &RepoId) -> Result<Vec<(PrId, PullRequestInfo)>> {
+        self.inner.list_open_pull_requests(repo).await
+    }
+
+    async fn get_branch_head(&self, repo: &RepoId, branch: &str) -> Result<String> {
+        self.inner.get_branch_head(repo, branch).await
+    }
+
+    async fn get_commit_parents(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        self.inner.get_commit_parents(repo, commit).await
+    }
+
+    async fn list_commits_between(&self, repo: &RepoId, base: &str, head: &str) -> Result<Vec<String>> {
+        self.inner.list_commits_between(repo, base, head).await
+    }
+
+    async fn list_commit_checks(&self, repo: &RepoId, commit: &str) -> Result<Vec<String>> {
+        self.inner.list_commit_checks(repo, commit).await
+    }
+
+    async fn merge_pull_request(
+        &self,
+        _repo: &RepoId,
+        _pr: PrId,
+        _head_sha: &str,
+        _method: MergeMethod,
+    ) -> Result<()> {
+        refused("merge_pull_request")
+    }
+
+    async fn add_labels(&self, _repo: &RepoId, _pr: PrId, _labels: &[String]) -> Result<()> {
+        refused("add_labels")
+    }
+
+    async fn mark_pull_request_draft(&self, _repo: &RepoId, _pr: PrId) -> Result<()> {
+        refused("mark_pull_request_draft")
+    }
+
+    async fn close_pull_request(&self, _repo: &RepoId, _pr: PrId) -> Result<()> {
+        refused("close_pull_request")
+    }
+
+    async fn list_files(&self, repo: &RepoId, branch: &str) -> Result<Vec<String>> {
+        self.inner.list_files(repo, branch).await
+    }
+
+    async fn create_pull_request(&self, _repo: &RepoId, _pr: NewPullRequest) -> Result<PrId> {
+        refused("create_pull_request")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::github::GitHubAdapter;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::{CheckConclusion, Platform};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn writes_are_refused_without_a_request() {
+        let api = Arc::new(MockApi::new());
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+        let observer = ReadOnlyAdapter::new(Box::new(github));
+        let repo = RepoId::new(Platform::GitHub, "o".into(), "r".into());
+
+        let check = CheckRun {
+            name: "echidnabot/Coq".into(),
+            head_sha: "abc".into(),
+            status: CheckStatus::Completed {
+                conclusion: CheckConclusion::Success,
+                summary: "ok".into(),
+            },
+            details_url: None,
+            external_id: None,
+            annotations: vec![],
+            actions: vec![],
+        };
+        assert!(observer.create_check_run(&repo, check).await.is_err());
+        assert!(observer.create_comment(&repo, PrId("1".into()), "hi").await.is_err());
+        let merge = observer.merge_pull_request(&repo, PrId("1".into()), "abc", MergeMethod::Squash);
+        assert!(merge.await.is_err());
+        assert!(api.requests().is_empty());
+
+        let capabilities = observer.capabilities();
+        assert!(!capabilities.check_runs && !capabilities.commit_statuses);
+        assert!(!capabilities.comments && !capabilities.review_comments);
+    }
+}
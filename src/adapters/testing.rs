@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Test doubles for the platform adapters
+//!
+//! [`MockApi`] stands in for the network behind any adapter's
+//! `with_http`, so code built on [`super::PlatformAdapter`] can be
+//! unit-tested without a platform or a server:
+//!
+//! ```
+//! use std::sync::Arc;
+//! use echidnabot::adapters::github::GitHubAdapter;
+//! use echidnabot::adapters::http::Method;
+//! use echidnabot::adapters::testing::MockApi;
+//! use echidnabot::adapters::{Platform, PlatformAdapter, PrId, RepoId};
+//!
+//! # tokio_test::block_on(async {
+//! let api = Arc::new(MockApi::new());
+//! api.respond(Method::Post, "/repos/o/r/issues/7/comments", 201, r#"{"id": 99}"#);
+//!
+//! let github = GitHubAdapter::new("token").unwrap().with_http(api.clone());
+//! let repo = RepoId::new(Platform::GitHub, "o", "r");
+//! let comment = github.create_comment(&repo, PrId("7".into()), "Proofs pass").await.unwrap();
+//!
+//! assert_eq!(comment.0, "99");
+//! assert_eq!(api.requests()[0].body.as_ref().unwrap()["body"], "Proofs pass");
+//! # });
+//! ```
+//!
+//! To exercise the real client as well (headers on the wire, TLS setup),
+//! point the adapter at a `wiremock` server instead.
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::http::{ApiRequest, ApiResponse, HttpApi, Method};
+use crate::error::Result;
+
+/// Canned-response [`HttpApi`] that records every request.
+///
+/// Responses are matched on method and URL path (query string ignored).
+/// The most recent matching [`respond`](Self::respond) wins, so a test can
+/// override a shared default. Unmatched requests get a 404.
+#[derive(Debug, Default)]
+pub struct MockApi {
+    routes: Mutex<Vec<(Method, String, ApiResponse)>>,
+    requests: Mutex<Vec<ApiRequest>>,
+}
+
+impl MockApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `method path` with `status` and `body`.
+    pub fn respond(&self, method: Method, path: &str, status: u16, body: impl Into<String>) {
+        self.routes
+            .lock()
+            .unwrap()
+            .push((method, path.to_string(), ApiResponse::new(status, body)));
+    }
+
+    /// Every request sent so far, oldest first.
+    pub fn requests(&self) -> Vec<ApiRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests sent to `method path`.
+    pub fn requests_to(&self, method: Method, path: &str) -> Vec<ApiRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == method && r.path() == path)
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl HttpApi for MockApi {
+    async fn send(&self, request: ApiRequest) -> Result<ApiResponse> {
+        let response = self
+            .routes
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(method, path, _)| *method == request.method && path == request.path())
+            .map(|(_, _, response)| response.clone())
+            .unwrap_or_else(|| ApiResponse::new(404, r#"{"message": "Not Found"}"#));
+        self.requests.lock().unwrap().push(request);
+        Ok(response)
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Ad-hoc checks of unregistered repositories (`echidnabot check --adhoc`)
+//!
+//! The repository is shallow-cloned into a temporary directory by
+//! [`clone_git_url`], verified like a local checkout and the clone
+//! removed. [`check`] is given no store: the repository is not
+//! registered, and no job or result is recorded.
+//!
+//! [`clone_git_url`] also clones registered repositories on platforms
+//! whose adapter has no token to clone with.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// A clone made by [`clone_git_url`]. The caller owns `path`.
+#[derive(Debug, Clone)]
+pub struct Checkout {
+    pub path: PathBuf,
+    /// SHA of the commit checked out.
+    pub commit: String,
+}
+
+/// `--adhoc` takes a repository URL, not a registered `owner/name`.
+pub fn validate_url(url: &str) -> Result<()> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return Ok(());
+    }
+    Err(Error::InvalidInput(format!(
+        "--adhoc expects a repository URL (https://host/owner/name), got '{}'",
+        url
+    )))
+}
+
+/// Clone `url` at `commit` (default `HEAD`), run `verify` on the clone
+/// and remove it. Returns what `verify` did.
+pub async fn check<F, Fut>(url: &str, commit: Option<&str>, verify: F) -> Result<bool>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let commit = commit.unwrap_or("HEAD");
+    tracing::info!("Cloning {} at {} for an ad-hoc check", url, commit);
+    let checkout = clone_git_url(url, commit).await?;
+    tracing::info!("Checking {} at {}", url, checkout.commit);
+    let passed = verify(checkout.path.clone()).await;
+    if let Err(e) = tokio::fs::remove_dir_all(&checkout.path).await {
+        tracing::debug!("Removing {} failed: {}", checkout.path.display(), e);
+    }
+    passed
+}
+
+/// Shallow-clone `url` at `commit` (a SHA, branch, tag or `HEAD`) into a
+/// new temporary directory, which is removed again if that fails.
+pub async fn clone_git_url(url: &str, commit: &str) -> Result<Checkout> {
+    let path = tempfile::tempdir()?.keep();
+    match clone_into(url, commit, &path).await {
+        Ok(commit) => Ok(Checkout { path, commit }),
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Clone into `path`; returns the SHA checked out.
+async fn clone_into(url: &str, commit: &str, path: &Path) -> Result<String> {
+    let target = path.to_string_lossy().to_string();
+
+    let cloned = if commit == "HEAD" {
+        git(None, &["clone", "--depth", "1", url, &target]).await?
+    } else {
+        git(None, &["clone", "--depth", "1", "--branch", commit, url, &target]).await?
+    };
+
+    // `--branch` takes branches and tags only; a SHA is fetched.
+    if !cloned && commit != "HEAD" {
+        if !git(None, &["clone", "--depth", "1", url, &target]).await? {
+            return Err(Error::Internal(format!("Failed to clone {}", url)));
+        }
+        if !git(Some(path), &["fetch", "--depth", "1", "origin", commit]).await? {
+            return Err(Error::Internal(format!("Failed to fetch {} from {}", commit, url)));
+        }
+        if !git(Some(path), &["checkout", commit]).await? {
+            return Err(Error::Internal(format!("Failed to check out {} of {}", commit, url)));
+        }
+    } else if !cloned {
+        return Err(Error::Internal(format!("Failed to clone {}", url)));
+    }
+
+    let output = tokio::process::Command::new("git")
+        .current_dir(path)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(Error::Internal(format!("No commit checked out from {}", url)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Run `git args` (in `dir`, if given); returns whether it succeeded.
+async fn git(dir: Option<&Path>, args: &[&str]) -> Result<bool> {
+    let mut command = tokio::process::Command::new("git");
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    Ok(command.args(args).status().await?.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .output()
+            .expect("git is installed");
+        assert!(output.status.success(), "git {:?}: {:?}", args, output);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// A bare repository holding one commit with `Main.lean`, by
+    /// `file://` URL, and that commit's SHA.
+    fn fixture(root: &Path) -> (String, String) {
+        let work = root.join("work");
+        std::fs::create_dir(&work).unwrap();
+        run(&work, &["init", "--quiet", "--initial-branch", "main"]);
+        std::fs::write(work.join("Main.lean"), "theorem t : True := trivial\n").unwrap();
+        run(&work, &["add", "Main.lean"]);
+        let identity = ["-c", "user.name=t", "-c", "user.email=t@example.com"];
+        run(&work, &[&identity[..], &["commit", "--quiet", "-m", "init"][..]].concat());
+        let sha = run(&work, &["rev-parse", "HEAD"]);
+        let bare = root.join("repo.git");
+        run(root, &["clone", "--quiet", "--bare", "work", "repo.git"]);
+        (format!("file://{}", bare.display()), sha)
+    }
+
+    #[test]
+    fn only_repository_urls_are_accepted() {
+        assert!(validate_url("https://codeberg.org/o/r").is_ok());
+        assert!(validate_url("http://git.internal/o/r.git").is_ok());
+        for url in ["o/r", "git@github.com:o/r.git", "file:///tmp/r.git", ""] {
+            assert!(matches!(validate_url(url), Err(Error::InvalidInput(_))), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn check_verifies_a_clone_and_leaves_nothing_behind() {
+        let root = tempfile::tempdir().unwrap();
+        let (url, sha) = fixture(root.path());
+
+        for commit in [None, Some("main"), Some(sha.as_str())] {
+            let mut seen = None;
+            let passed = check(&url, commit, |path| {
+                assert!(path.join("Main.lean").is_file());
+                assert_eq!(run(&path, &["rev-parse", "HEAD"]), sha);
+                seen = Some(path);
+                async { Ok(true) }
+            })
+            .await
+            .unwrap();
+            assert!(passed);
+            assert!(!seen.unwrap().exists(), "the clone is removed");
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_commits_and_repositories_fail() {
+        let root = tempfile::tempdir().unwrap();
+        let (url, _) = fixture(root.path());
+
+        let missing = "0123456789abcdef0123456789abcdef01234567";
+        let err = clone_git_url(&url, missing).await.unwrap_err();
+        assert!(matches!(err, Error::Internal(ref m) if m.contains("fetch")), "{:?}", err);
+
+        let nowhere = format!("file://{}", root.path().join("nowhere.git").display());
+        for commit in ["HEAD", "main"] {
+            let err = clone_git_url(&nowhere, commit).await.unwrap_err();
+            assert!(matches!(err, Error::Internal(_)), "{:?}", err);
+        }
+    }
+}
@@ -0,0 +1,753 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Verification events for analytics warehouses (`[analytics]`)
+//!
+//! Every finished job becomes one [`VerificationEvent`] row — repository,
+//! commit, prover, outcome, queue wait and run time — inserted into each
+//! `[[analytics.sinks]]` entry in batches of `batch_size`:
+//!
+//! - `clickhouse`: `INSERT … FORMAT JSONEachRow` over the HTTP interface;
+//! - `bigquery`: `tabledata.insertAll`, with the event id as `insertId`;
+//! - `s3`: one JSON Lines object per batch, signed with AWS Signature V4,
+//!   so any S3-compatible store (and whatever loads from it) works.
+//!
+//! Events are read from the store rather than captured as jobs finish:
+//! each sink keeps a cursor (the last job it received, in completion
+//! order) and [`run_analytics_export`] carries it forward every
+//! `interval_secs`. A sink that was down catches up when it is back, and
+//! a new sink starts with the whole history. `echidnabot analytics
+//! backfill` moves a sink's cursor back to re-send a period, and
+//! `echidnabot analytics schema` prints the table to create.
+//!
+//! Rows carry [`EVENT_SCHEMA`]. A batch is sent again when its cursor
+//! could not be saved, so tables should deduplicate on `event_id`: the
+//! ClickHouse schema uses `ReplacingMergeTree` for this.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::config::{AnalyticsConfig, AnalyticsSink, AnalyticsSinkKind, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::JobId;
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{AnalyticsCursor, ProofJobRecord, ProofResultRecord, Repository};
+use crate::store::Store;
+
+/// Bumped whenever a column changes meaning or is removed.
+pub const EVENT_SCHEMA: u32 = 1;
+
+/// Jobs finished more recently than this wait for the next run, so one
+/// whose completion is still being written is not skipped.
+const SETTLE_SECS: i64 = 30;
+
+const BIGQUERY_API: &str = "https://bigquery.googleapis.com";
+
+/// Columns of the events table: name, ClickHouse type, BigQuery type and
+/// whether the column can be null.
+const COLUMNS: &[(&str, &str, &str, bool)] = &[
+    ("schema_version", "UInt32", "INTEGER", false),
+    ("event_id", "UUID", "STRING", false),
+    ("repo_id", "UUID", "STRING", false),
+    ("platform", "LowCardinality(Nullable(String))", "STRING", true),
+    ("owner", "Nullable(String)", "STRING", true),
+    ("repo", "Nullable(String)", "STRING", true),
+    ("commit_sha", "String", "STRING", false),
+    ("branch", "Nullable(String)", "STRING", true),
+    ("pr_number", "Nullable(UInt64)", "INTEGER", true),
+    ("prover", "LowCardinality(String)", "STRING", false),
+    ("status", "LowCardinality(String)", "STRING", false),
+    ("priority", "LowCardinality(String)", "STRING", false),
+    ("success", "Nullable(Bool)", "BOOLEAN", true),
+    ("retry_of", "Nullable(UUID)", "STRING", true),
+    ("queued_at", "DateTime64(3, 'UTC')", "TIMESTAMP", false),
+    ("started_at", "Nullable(DateTime64(3, 'UTC'))", "TIMESTAMP", true),
+    ("completed_at", "DateTime64(3, 'UTC')", "TIMESTAMP", false),
+    ("queue_wait_ms", "Nullable(Int64)", "INTEGER", true),
+    ("duration_ms", "Nullable(Int64)", "INTEGER", true),
+    ("verified_files", "UInt32", "INTEGER", false),
+    ("failed_files", "UInt32", "INTEGER", false),
+    ("prover_version", "Nullable(String)", "STRING", true),
+    ("execution_path", "LowCardinality(Nullable(String))", "STRING", true),
+];
+
+/// One finished job, as exported.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct VerificationEvent {
+    pub schema_version: u32,
+    /// The job id.
+    pub event_id: Uuid,
+    pub repo_id: Uuid,
+    /// `None` once the repository has been removed.
+    pub platform: Option<String>,
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    pub commit_sha: String,
+    pub branch: Option<String>,
+    pub pr_number: Option<u64>,
+    pub prover: String,
+    /// `completed`, `failed` or `cancelled`.
+    pub status: String,
+    pub priority: String,
+    /// `None` when the job recorded no result.
+    pub success: Option<bool>,
+    pub retry_of: Option<Uuid>,
+    pub queued_at: String,
+    pub started_at: Option<String>,
+    pub completed_at: String,
+    pub queue_wait_ms: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub verified_files: u32,
+    pub failed_files: u32,
+    pub prover_version: Option<String>,
+    pub execution_path: Option<String>,
+}
+
+/// RFC 3339 to the millisecond, which every warehouse reads.
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+impl VerificationEvent {
+    /// The event for a finished `job`.
+    pub fn new(
+        job: &ProofJobRecord,
+        result: Option<&ProofResultRecord>,
+        repo: Option<&Repository>,
+    ) -> Self {
+        let completed_at = job.completed_at.unwrap_or(job.queued_at);
+        let run_time = job.started_at.map(|start| (completed_at - start).num_milliseconds());
+        Self {
+            schema_version: EVENT_SCHEMA,
+            event_id: job.id,
+            repo_id: job.repo_id,
+            platform: repo.map(|r| format!("{:?}", r.platform).to_lowercase()),
+            owner: repo.map(|r| r.owner.clone()),
+            repo: repo.map(|r| r.name.clone()),
+            commit_sha: job.commit_sha.clone(),
+            branch: job.branch.clone(),
+            pr_number: job.pr_number,
+            prover: job.prover.as_str().to_string(),
+            status: format!("{:?}", job.status).to_lowercase(),
+            priority: format!("{:?}", job.priority).to_lowercase(),
+            success: result.map(|r| r.success),
+            retry_of: job.retry_of,
+            queued_at: timestamp(job.queued_at),
+            started_at: job.started_at.map(timestamp),
+            completed_at: timestamp(completed_at),
+            queue_wait_ms: job.started_at.map(|start| (start - job.queued_at).num_milliseconds()),
+            duration_ms: result.map(|r| r.duration_ms).or(run_time),
+            verified_files: result.map_or(0, |r| r.verified_files.len() as u32),
+            failed_files: result.map_or(0, |r| r.failed_files.len() as u32),
+            prover_version: result.and_then(|r| r.prover_version.clone()),
+            execution_path: result.and_then(|r| r.execution_path).map(|p| p.as_str().to_string()),
+        }
+    }
+}
+
+fn kind_name(kind: AnalyticsSinkKind) -> &'static str {
+    match kind {
+        AnalyticsSinkKind::Clickhouse => "clickhouse",
+        AnalyticsSinkKind::Bigquery => "bigquery",
+        AnalyticsSinkKind::S3 => "s3",
+    }
+}
+
+fn required<'a>(sink: &AnalyticsSink, key: &str, value: &'a Option<String>) -> Result<&'a str> {
+    value.as_deref().filter(|v| !v.trim().is_empty()).ok_or_else(|| {
+        Error::Config(format!(
+            "analytics sink '{}' ({}) needs {}",
+            sink.name,
+            kind_name(sink.kind),
+            key
+        ))
+    })
+}
+
+impl AnalyticsSink {
+    /// Check that the settings `kind` needs are present.
+    pub fn validate(&self) -> Result<()> {
+        match self.kind {
+            AnalyticsSinkKind::Clickhouse => {
+                required(self, "url", &self.url)?;
+                let table = required(self, "table", &self.table)?;
+                // The name is spliced into the INSERT statement.
+                if !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    return Err(Error::Config(format!(
+                        "analytics sink '{}': table '{}' is not a plain [database.]table name",
+                        self.name, table
+                    )));
+                }
+            }
+            AnalyticsSinkKind::Bigquery => {
+                required(self, "project", &self.project)?;
+                required(self, "dataset", &self.dataset)?;
+                required(self, "table", &self.table)?;
+                required(self, "token", &self.token)?;
+            }
+            AnalyticsSinkKind::S3 => {
+                required(self, "url", &self.url)?;
+                required(self, "bucket", &self.bucket)?;
+                required(self, "access_key_id", &self.access_key_id)?;
+                required(self, "secret_access_key", &self.secret_access_key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AnalyticsConfig {
+    /// Check every sink, and that their names are unique; run at startup
+    /// and on reload.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for sink in &self.sinks {
+            if sink.name.trim().is_empty() {
+                return Err(Error::Config("[[analytics.sinks]] name is empty".into()));
+            }
+            if !names.insert(sink.name.as_str()) {
+                return Err(Error::Config(format!(
+                    "analytics sink '{}' is configured twice",
+                    sink.name
+                )));
+            }
+            sink.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn sink(&self, name: &str) -> Result<&AnalyticsSink> {
+        self.sinks
+            .iter()
+            .find(|sink| sink.name == name)
+            .ok_or_else(|| Error::InvalidInput(format!("no analytics sink named '{}'", name)))
+    }
+}
+
+/// The statement creating `sink`'s table (ClickHouse), or its schema as
+/// `bq mk --schema` takes it (BigQuery). S3 objects are self-describing
+/// JSON Lines; this gives the BigQuery schema for loading them.
+pub fn ddl(sink: &AnalyticsSink) -> Result<String> {
+    if sink.kind == AnalyticsSinkKind::Clickhouse {
+        let table = required(sink, "table", &sink.table)?;
+        let columns: Vec<String> = COLUMNS
+            .iter()
+            .map(|(name, clickhouse, _, _)| format!("    {} {}", name, clickhouse))
+            .collect();
+        return Ok(format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n{}\n)\nENGINE = ReplacingMergeTree\n\
+             PARTITION BY toYYYYMM(completed_at)\nORDER BY event_id\n",
+            table,
+            columns.join(",\n")
+        ));
+    }
+    let fields: Vec<serde_json::Value> = COLUMNS
+        .iter()
+        .map(|(name, _, bigquery, nullable)| {
+            serde_json::json!({
+                "name": name,
+                "type": bigquery,
+                "mode": if *nullable { "NULLABLE" } else { "REQUIRED" },
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&fields)? + "\n")
+}
+
+/// Events as JSON Lines.
+pub fn render_rows(events: &[VerificationEvent]) -> Result<String> {
+    let mut out = String::new();
+    for event in events {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// The events for `jobs`, with their results and repositories.
+async fn events_for(
+    store: &dyn Store,
+    jobs: &[ProofJobRecord],
+) -> Result<Vec<VerificationEvent>> {
+    let ids: Vec<JobId> = jobs.iter().map(|job| JobId(job.id)).collect();
+    let results: HashMap<Uuid, ProofResultRecord> = store
+        .get_results_by_job_ids(&ids)
+        .await?
+        .into_iter()
+        .map(|result| (result.job_id, result))
+        .collect();
+    let mut repos: HashMap<Uuid, Option<Repository>> = HashMap::new();
+    for job in jobs {
+        if !repos.contains_key(&job.repo_id) {
+            repos.insert(job.repo_id, store.get_repository(job.repo_id).await?);
+        }
+    }
+    Ok(jobs
+        .iter()
+        .map(|job| {
+            let repo = repos.get(&job.repo_id).and_then(Option::as_ref);
+            VerificationEvent::new(job, results.get(&job.id), repo)
+        })
+        .collect())
+}
+
+/// Send every job finished since `sink`'s cursor, `batch_size` at a time,
+/// and return how many were exported. The cursor moves after each batch;
+/// the first batch that cannot be sent is tried again on the next run.
+pub async fn export_pending(
+    store: &dyn Store,
+    sink: &AnalyticsSink,
+    batch_size: usize,
+    client: &reqwest::Client,
+) -> Result<usize> {
+    let batch_size = batch_size.max(1);
+    let settled = Utc::now() - chrono::Duration::seconds(SETTLE_SECS);
+    let mut after = store
+        .get_analytics_cursor(&sink.name)
+        .await?
+        .map(|cursor| (cursor.completed_at, cursor.job_id));
+    let mut exported = 0;
+    loop {
+        let page = store.list_jobs_finished_after(after, batch_size).await?;
+        let fetched = page.len();
+        let jobs: Vec<ProofJobRecord> = page
+            .into_iter()
+            .take_while(|job| job.completed_at.is_some_and(|at| at <= settled))
+            .collect();
+        let Some(last) = jobs.last() else {
+            break;
+        };
+        let position = (last.completed_at.unwrap_or(last.queued_at), last.id);
+
+        ship(sink, client, &events_for(store, &jobs).await?).await?;
+        store
+            .set_analytics_cursor(&AnalyticsCursor {
+                sink: sink.name.clone(),
+                completed_at: position.0,
+                job_id: position.1,
+                updated_at: Utc::now(),
+            })
+            .await?;
+        after = Some(position);
+        exported += jobs.len();
+        if jobs.len() < fetched || fetched < batch_size {
+            break;
+        }
+    }
+    Ok(exported)
+}
+
+/// Move `sink`'s cursor back so the next export re-sends every job
+/// finished from `since` on, or everything without it.
+pub async fn rewind(
+    store: &dyn Store,
+    sink: &AnalyticsSink,
+    since: Option<DateTime<Utc>>,
+) -> Result<()> {
+    match since {
+        Some(since) => {
+            store
+                .set_analytics_cursor(&AnalyticsCursor {
+                    sink: sink.name.clone(),
+                    // Any job finished at `since` sorts after the nil id.
+                    completed_at: since,
+                    job_id: Uuid::nil(),
+                    updated_at: Utc::now(),
+                })
+                .await
+        }
+        None => store.delete_analytics_cursor(&sink.name).await,
+    }
+}
+
+/// Fail with the response body, which says what a warehouse rejected.
+async fn checked(sink: &AnalyticsSink, response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Internal(format!(
+        "analytics sink '{}' answered {}: {}",
+        sink.name,
+        status,
+        body.chars().take(500).collect::<String>()
+    )))
+}
+
+async fn ship(
+    sink: &AnalyticsSink,
+    client: &reqwest::Client,
+    events: &[VerificationEvent],
+) -> Result<()> {
+    match sink.kind {
+        AnalyticsSinkKind::Clickhouse => {
+            let url = required(sink, "url", &sink.url)?;
+            let table = required(sink, "table", &sink.table)?;
+            let query = format!("INSERT INTO {} FORMAT JSONEachRow", table);
+            let mut request = client
+                .post(url)
+                .query(&[("query", query.as_str()), ("date_time_input_format", "best_effort")])
+                .body(render_rows(events)?);
+            if let Some(user) = &sink.user {
+                request = request.header("X-ClickHouse-User", user);
+            }
+            if let Some(password) = &sink.password {
+                request = request.header("X-ClickHouse-Key", password);
+            }
+            checked(sink, request.send().await?).await?;
+        }
+        AnalyticsSinkKind::Bigquery => {
+            let url = format!(
+                "{}/bigquery/v2/projects/{}/datasets/{}/tables/{}/insertAll",
+                sink.url.as_deref().unwrap_or(BIGQUERY_API).trim_end_matches('/'),
+                required(sink, "project", &sink.project)?,
+                required(sink, "dataset", &sink.dataset)?,
+                required(sink, "table", &sink.table)?,
+            );
+            let rows: Vec<serde_json::Value> = events
+                .iter()
+                .map(|event| serde_json::json!({ "insertId": event.event_id, "json": event }))
+                .collect();
+            let response = client
+                .post(url)
+                .bearer_auth(required(sink, "token", &sink.token)?)
+                .json(&serde_json::json!({ "rows": rows }))
+                .send()
+                .await?;
+            // Rejected rows come back with a 200; none of the batch is kept.
+            let answer: serde_json::Value = checked(sink, response).await?.json().await?;
+            if let Some(errors) = answer["insertErrors"].as_array().filter(|e| !e.is_empty()) {
+                return Err(Error::Internal(format!(
+                    "analytics sink '{}' rejected {} row(s): {}",
+                    sink.name,
+                    errors.len(),
+                    errors[0]
+                )));
+            }
+        }
+        AnalyticsSinkKind::S3 => {
+            let now = Utc::now();
+            let key = format!(
+                "{}v{}/{}/events_{}_{}.jsonl",
+                sink.prefix.as_deref().unwrap_or_default(),
+                EVENT_SCHEMA,
+                now.format("%Y-%m-%d"),
+                now.format("%Y%m%dT%H%M%S%.3fZ"),
+                &Uuid::new_v4().simple().to_string()[..8]
+            );
+            let body = render_rows(events)?.into_bytes();
+            let (url, headers) = s3_put(sink, &key, &body, now)?;
+            let mut request = client
+                .put(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+                .body(body);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            checked(sink, request.send().await?).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Percent-encode as SigV4 canonical URIs expect: all but unreserved
+/// characters.
+fn aws_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The SigV4 key for `date` (`YYYYMMDD`).
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    hmac_sha256(&key, "aws4_request")
+}
+
+/// The path-style URL for `key` in `sink`'s bucket and the SigV4 headers
+/// for a `PUT` of `body` to it.
+fn s3_put(
+    sink: &AnalyticsSink,
+    key: &str,
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Result<(reqwest::Url, Vec<(&'static str, String)>)> {
+    let endpoint = required(sink, "url", &sink.url)?.trim_end_matches('/');
+    let bucket = required(sink, "bucket", &sink.bucket)?;
+    let access_key_id = required(sink, "access_key_id", &sink.access_key_id)?;
+    let secret = required(sink, "secret_access_key", &sink.secret_access_key)?;
+    let region = sink.region.as_deref().unwrap_or("us-east-1");
+
+    let path: Vec<String> = std::iter::once(bucket)
+        .chain(key.split('/'))
+        .map(aws_encode)
+        .collect();
+    let mut url = reqwest::Url::parse(endpoint)
+        .map_err(|e| Error::Config(format!("analytics sink '{}' url: {}", sink.name, e)))?;
+    url.set_path(&format!("/{}", path.join("/")));
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => {
+            return Err(Error::Config(format!("analytics sink '{}' url has no host", sink.name)))
+        }
+    };
+
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(body));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        url.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signature =
+        hex::encode(hmac_sha256(&signing_key(secret, &date, region, "s3"), &string_to_sign));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key_id, scope, signed_headers, signature
+    );
+    Ok((
+        url,
+        vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            ("authorization", authorization),
+        ],
+    ))
+}
+
+/// Export to every sink every `interval` until shutdown.
+pub async fn run_analytics_export(
+    store: Arc<dyn Store>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let client = reqwest::Client::new();
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Analytics export observed shutdown signal — stopping");
+                return;
+            }
+        }
+        let analytics = config.load_full().analytics.clone();
+        for sink in &analytics.sinks {
+            match export_pending(store.as_ref(), sink, analytics.batch_size, &client).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("Exported {} event(s) to sink '{}'", n, sink.name),
+                Err(e) => tracing::warn!("Analytics export to '{}' failed: {}", sink.name, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, JobStatus, ProofJob};
+    use crate::store::SqliteStore;
+
+    fn sink(kind: AnalyticsSinkKind) -> AnalyticsSink {
+        AnalyticsSink {
+            name: "lake".into(),
+            kind,
+            url: None,
+            table: None,
+            user: None,
+            password: None,
+            project: None,
+            dataset: None,
+            token: None,
+            bucket: None,
+            region: None,
+            prefix: None,
+            access_key_id: None,
+            secret_access_key: None,
+        }
+    }
+
+    #[test]
+    fn rows_match_the_table_schema() {
+        let repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        let mut job = ProofJobRecord::from(ProofJob::new(
+            repo.id,
+            "abc".into(),
+            ProverKind::new("coq"),
+            vec![],
+        ));
+        let queued = DateTime::parse_from_rfc3339("2026-10-17T09:00:00Z").unwrap();
+        job.queued_at = queued.with_timezone(&Utc);
+        job.started_at = Some(job.queued_at + chrono::Duration::seconds(4));
+        job.completed_at = Some(job.queued_at + chrono::Duration::seconds(10));
+        job.status = JobStatus::Failed;
+
+        let event = VerificationEvent::new(&job, None, Some(&repo));
+        assert_eq!(event.platform.as_deref(), Some("github"));
+        assert_eq!(event.status, "failed");
+        assert_eq!(event.queued_at, "2026-10-17T09:00:00.000Z");
+        assert_eq!(event.queue_wait_ms, Some(4000));
+        assert_eq!(event.duration_ms, Some(6000), "run time without a result");
+        assert_eq!(event.success, None);
+
+        let row: serde_json::Value =
+            serde_json::from_str(render_rows(&[event]).unwrap().trim_end()).unwrap();
+        let mut keys: Vec<&str> = row.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        let mut columns: Vec<&str> = COLUMNS.iter().map(|(name, ..)| *name).collect();
+        columns.sort_unstable();
+        assert_eq!(keys, columns);
+    }
+
+    #[test]
+    fn sinks_need_their_settings() {
+        let mut clickhouse = sink(AnalyticsSinkKind::Clickhouse);
+        clickhouse.url = Some("http://localhost:8123".into());
+        assert!(clickhouse.validate().is_err());
+        clickhouse.table = Some("echidnabot.events; DROP TABLE x".into());
+        assert!(clickhouse.validate().is_err());
+        clickhouse.table = Some("echidnabot.events".into());
+        assert!(clickhouse.validate().is_ok());
+        assert!(ddl(&clickhouse).unwrap().contains("ENGINE = ReplacingMergeTree"));
+
+        let config = AnalyticsConfig {
+            sinks: vec![clickhouse.clone(), clickhouse],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err(), "duplicate names");
+        assert!(sink(AnalyticsSinkKind::S3).validate().is_err());
+
+        let schema: serde_json::Value =
+            serde_json::from_str(&ddl(&sink(AnalyticsSinkKind::Bigquery)).unwrap()).unwrap();
+        assert_eq!(schema[0]["name"], "schema_version");
+        assert_eq!(schema[0]["mode"], "REQUIRED");
+    }
+
+    #[test]
+    fn s3_requests_are_signed_with_sigv4() {
+        // AWS's published derivation example.
+        let secret = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+        let key = signing_key(secret, "20120215", "us-east-1", "iam");
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+
+        let mut s3 = sink(AnalyticsSinkKind::S3);
+        s3.url = Some("https://s3.eu-west-1.amazonaws.com".into());
+        s3.bucket = Some("acme-analytics".into());
+        s3.region = Some("eu-west-1".into());
+        s3.access_key_id = Some("AKIDEXAMPLE".into());
+        s3.secret_access_key = Some("secret".into());
+        let now = DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z").unwrap().with_timezone(&Utc);
+        let (url, headers) = s3_put(&s3, "echidnabot/v1/a b.jsonl", b"{}\n", now).unwrap();
+
+        assert_eq!(
+            url.as_str(),
+            "https://s3.eu-west-1.amazonaws.com/acme-analytics/echidnabot/v1/a%20b.jsonl"
+        );
+        let headers: HashMap<_, _> = headers.into_iter().collect();
+        assert_eq!(headers["x-amz-date"], "20261017T120000Z");
+        assert!(headers["authorization"].starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20261017/eu-west-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+    }
+
+    #[tokio::test]
+    async fn export_resumes_from_the_cursor_and_rewinds() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        store.create_repository(&repo).await.unwrap();
+        let finished = Utc::now() - chrono::Duration::minutes(5);
+        for i in 0..3 {
+            let mut job = ProofJobRecord::from(ProofJob::new(
+                repo.id,
+                format!("c{}", i),
+                ProverKind::new("coq"),
+                vec![],
+            ));
+            job.status = JobStatus::Completed;
+            job.completed_at = Some(finished + chrono::Duration::seconds(i));
+            store.create_job(&job).await.unwrap();
+            let result = JobResult {
+                success: true,
+                message: "ok".into(),
+                prover_output: String::new(),
+                duration_ms: 5,
+                verified_files: vec!["A.v".into()],
+                failed_files: vec![],
+                confidence: None,
+                axioms: None,
+                prover_version: None,
+                prover_args: vec![],
+                execution_path: None,
+            };
+            store.save_result(&ProofResultRecord::new(JobId(job.id), &result)).await.unwrap();
+        }
+
+        // Nothing listens on this port, so the first batch fails and the
+        // cursor stays put.
+        let mut clickhouse = sink(AnalyticsSinkKind::Clickhouse);
+        clickhouse.url = Some("http://127.0.0.1:9".into());
+        clickhouse.table = Some("events".into());
+        let client = reqwest::Client::new();
+        assert!(export_pending(&store, &clickhouse, 2, &client).await.is_err());
+        assert!(store.get_analytics_cursor("lake").await.unwrap().is_none());
+
+        let events = events_for(&store, &store.list_jobs_finished_after(None, 10).await.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].commit_sha, "c0");
+        assert_eq!(events[0].verified_files, 1);
+        assert_eq!(events[0].repo.as_deref(), Some("proofs"));
+
+        rewind(&store, &clickhouse, Some(finished + chrono::Duration::seconds(1))).await.unwrap();
+        let cursor = store.get_analytics_cursor("lake").await.unwrap().unwrap();
+        let after = Some((cursor.completed_at, cursor.job_id));
+        let pending = store.list_jobs_finished_after(after, 10).await.unwrap();
+        assert_eq!(pending.iter().map(|j| j.commit_sha.as_str()).collect::<Vec<_>>(), ["c1", "c2"]);
+        rewind(&store, &clickhouse, None).await.unwrap();
+        assert!(store.get_analytics_cursor("lake").await.unwrap().is_none());
+    }
+}
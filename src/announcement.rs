@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Maintenance announcements
+//!
+//! During an incident every bot comment and check summary should say so.
+//! An admin sets one instance-wide announcement with the
+//! `setAnnouncement(message, ttlSecs)` mutation; while it is active the
+//! message is prepended as a banner to result comments, check run
+//! summaries, command replies and stale-PR reminders. It ends when its TTL
+//! runs out or `clearAnnouncement` is called. The announcement lives in
+//! the store, so it survives restarts and reaches every worker.
+
+use chrono::Utc;
+
+use crate::store::models::AnnouncementRecord;
+use crate::store::Store;
+
+/// The active announcement, if any. Best-effort: a store error counts as
+/// none, so a comment is never held back by it.
+pub async fn active(store: &dyn Store) -> Option<AnnouncementRecord> {
+    match store.get_announcement().await {
+        Ok(announcement) => announcement.filter(|a| a.is_active(Utc::now())),
+        Err(e) => {
+            tracing::warn!("Reading the announcement failed: {}", e);
+            None
+        }
+    }
+}
+
+/// `body` with the announcement's banner, as a quote block, in front.
+pub fn with_banner(announcement: Option<&AnnouncementRecord>, body: &str) -> String {
+    match announcement {
+        Some(announcement) => format!("> 📢 **{}**\n\n{}", announcement.message.trim(), body),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banner_is_prepended_until_expiry() {
+        let announcement = AnnouncementRecord::new(
+            "degraded: ECHIDNA latency".into(),
+            Some(chrono::Duration::minutes(30)),
+        );
+        assert_eq!(
+            with_banner(Some(&announcement), "✅ All proofs verified"),
+            "> 📢 **degraded: ECHIDNA latency**\n\n✅ All proofs verified"
+        );
+        assert_eq!(with_banner(None, "body"), "body");
+
+        assert!(announcement.is_active(Utc::now()));
+        assert!(!announcement.is_active(Utc::now() + chrono::Duration::hours(1)));
+        assert!(AnnouncementRecord::new("until cleared".into(), None).is_active(Utc::now()));
+    }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Result attestation endpoints
+//!
+//! * `GET /.well-known/echidnabot/keys.json` — the published key set.
+//!   Open, like `/badge`: public keys are meant to be fetched by anyone.
+//! * `GET /jobs/{id}/attestation` — a finished job's signed envelope,
+//!   behind the same read-scope middleware as `/jobs/{id}/sarif`.
+//! * `GET /certificates/{platform}/{owner}/{repo}/{sha}` — the signed
+//!   verification certificate of a commit ([`crate::certificate`]). Open
+//!   for public repositories like `/status`; private, unregistered and
+//!   uncertified all answer the same 404.
+//!
+//! See [`crate::attest`] for the envelope format.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use uuid::Uuid;
+
+use super::auth::{reject_foreign_job, AuthContext};
+use super::badge::parse_platform;
+use super::webhooks::AppState;
+use crate::attest::{build_job_attestation, KeySet, ResultSigner};
+use crate::scheduler::JobId;
+
+pub async fn keys(State(state): State<AppState>) -> Response {
+    match KeySet::from_config(&state.config().attestation) {
+        Ok(keys) => Json(keys).into_response(),
+        Err(e) => {
+            tracing::error!("Attestation key set unavailable: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Key set unavailable").into_response()
+        }
+    }
+}
+
+pub async fn job_attestation(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Response {
+    let Ok(job_id) = Uuid::parse_str(&id) else {
+        return (StatusCode::BAD_REQUEST, "Invalid job id").into_response();
+    };
+    if let Some(rejected) = reject_foreign_job(&state, &auth, JobId(job_id)).await {
+        return rejected;
+    }
+    let signer = match ResultSigner::from_config(&state.config().attestation) {
+        Ok(Some(signer)) => signer,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Attestations are not enabled").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Attestation signing key unavailable: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Signing key unavailable").into_response();
+        }
+    };
+
+    match build_job_attestation(state.store.as_ref(), &signer, JobId(job_id)).await {
+        Ok(Some(envelope)) => Json(envelope).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No completed job with that id").into_response(),
+        Err(e) => {
+            tracing::error!("Attestation for job {} failed: {}", job_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Attestation failed").into_response()
+        }
+    }
+}
+
+pub async fn commit_certificate(
+    State(state): State<AppState>,
+    auth: Option<Extension<AuthContext>>,
+    Path((platform, owner, name, sha)): Path<(String, String, String, String)>,
+) -> Response {
+    let auth = auth.map_or_else(AuthContext::public, |Extension(auth)| auth);
+    let not_found = || (StatusCode::NOT_FOUND, "No certificate for that commit").into_response();
+
+    let Some(platform) = parse_platform(&platform) else {
+        return not_found();
+    };
+    let repo = match state.store.get_repository_by_name(platform, &owner, &name).await {
+        Ok(Some(repo)) if auth.can_view(&repo) => repo,
+        Ok(_) => return not_found(),
+        Err(e) => {
+            tracing::error!("Certificate lookup for {}/{} failed: {}", owner, name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Certificate lookup failed").into_response();
+        }
+    };
+    match state.store.get_certificate(repo.id, &sha).await {
+        Ok(Some(certificate)) => Json(certificate.envelope).into_response(),
+        Ok(None) => not_found(),
+        Err(e) => {
+            tracing::error!("Certificate lookup for {} {} failed: {}", repo.full_name(), sha, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Certificate lookup failed").into_response()
+        }
+    }
+}
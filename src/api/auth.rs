@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! API token authentication for GraphQL and admin routes
+//!
+//! Tokens are opaque bearer strings (`ebt_` + 64 hex chars, 256 bits of
+//! entropy) minted via `echidnabot token mint`. Only the SHA-256 hash is
+//! persisted in the `api_tokens` table — the plaintext is shown once at
+//! mint time and never again. A salted KDF (argon2/bcrypt) would buy
+//! nothing here: the tokens are uniformly random, so a dictionary attack
+//! against the hash is no easier than guessing the token itself.
+//!
+//! Scopes are hierarchical — each one implies the ones below it:
+//!
+//! | Scope     | Grants                                                   |
+//! |-----------|----------------------------------------------------------|
+//! | `read`    | GraphQL queries, `/metrics`                              |
+//! | `trigger` | + `triggerCheck`, `requestSuggestions`, outcome recording |
+//! | `admin`   | + repository registration / settings / enable toggles    |
+//!
+//! Enforcement is two-tier: [`auth_middleware`] rejects requests carrying
+//! no (or an unknown / revoked) token with 401, and resolvers call
+//! [`require_scope`] to gate individual mutations.
+//!
+//! When `[auth] required = false` (the default, for backwards
+//! compatibility) requests to `/metrics` and the job exports without an
+//! `Authorization` header are admitted with admin scope; a
+//! presented-but-invalid token is still rejected.
+//!
+//! Repository visibility (`public` / `private`, see
+//! [`crate::store::models::Visibility`]) governs tokenless access to
+//! status. [`public_auth_middleware`] admits such requests as
+//! [`AuthContext::public`], which only sees public repositories, on the
+//! badge, `/status` and `/graphql` routes whatever `required` says. A
+//! public principal can run the repository, job and prover queries,
+//! scoped to public repositories; every other operation, mutations
+//! included, fails [`require_scope`].
+//!
+//! With `[auth.oidc]` configured, a browser session from
+//! [`crate::api::oidc`] stands in for a token on requests without an
+//! `Authorization` header, with the scope its groups map to; anonymous
+//! callers are then treated as if `required` were set.
+//!
+//! A token minted with `--tenant <owner>` is confined to that owner's
+//! repositories (see [`crate::tenancy`]): [`AuthContext::can_view`] hides
+//! the rest, and operations spanning the instance fail
+//! [`require_instance_scope`] whatever the token's scope.
+
+use async_graphql::ErrorExtensions;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::webhooks::AppState;
+use crate::scheduler::JobId;
+use crate::store::models::Repository;
+use crate::store::Store;
+pub use crate::store::models::{TokenScope, Visibility};
+
+/// Prefix on every minted token — makes leaked tokens greppable by
+/// secret scanners and distinguishes them from platform PATs.
+pub const TOKEN_PREFIX: &str = "ebt_";
+
+/// The authenticated principal for one request. Inserted into request
+/// extensions by [`auth_middleware`] and forwarded into GraphQL context
+/// data by the `/graphql` handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthContext {
+    /// `None` for anonymous access.
+    pub token_id: Option<Uuid>,
+    /// OIDC subject of a logged-in user.
+    pub user: Option<String>,
+    pub scope: TokenScope,
+    /// Anonymous access limited to public repositories; see
+    /// [`AuthContext::public`].
+    pub public_only: bool,
+    /// The repository owner a tenant-scoped token is confined to.
+    pub tenant: Option<String>,
+}
+
+impl AuthContext {
+    /// Anonymous principal on `/metrics` and the job exports when
+    /// `[auth] required = false`.
+    pub fn open() -> Self {
+        Self {
+            token_id: None,
+            user: None,
+            scope: TokenScope::Admin,
+            public_only: false,
+            tenant: None,
+        }
+    }
+
+    /// Anonymous principal that may only read public repositories.
+    pub fn public() -> Self {
+        Self {
+            token_id: None,
+            user: None,
+            scope: TokenScope::Read,
+            public_only: true,
+            tenant: None,
+        }
+    }
+
+    /// Who this principal is in the audit log: `token:<id>`,
+    /// `oidc:<subject>` for a logged-in user, or `anonymous`.
+    pub fn audit_actor(&self) -> String {
+        match (self.token_id, &self.user) {
+            (Some(id), _) => format!("token:{}", id),
+            (None, Some(user)) => format!("oidc:{}", user),
+            (None, None) => "anonymous".to_string(),
+        }
+    }
+
+    pub fn allows(&self, needed: TokenScope) -> bool {
+        !self.public_only && self.scope >= needed
+    }
+
+    /// Like [`AuthContext::allows`], for operations spanning every
+    /// tenant: never for a tenant-scoped token.
+    pub fn allows_instance(&self, needed: TokenScope) -> bool {
+        self.tenant.is_none() && self.allows(needed)
+    }
+
+    /// Whether repositories of `owner` are within this principal's tenant.
+    pub fn in_tenant(&self, owner: &str) -> bool {
+        self.tenant.as_deref().is_none_or(|tenant| tenant == owner)
+    }
+
+    /// Whether this principal may see `repo`'s status.
+    pub fn can_view(&self, repo: &Repository) -> bool {
+        (!self.public_only || repo.visibility == Visibility::Public) && self.in_tenant(&repo.owner)
+    }
+}
+
+/// Mint a fresh plaintext token.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", TOKEN_PREFIX, hex::encode(bytes))
+}
+
+/// SHA-256 hex digest of a plaintext token — the only form persisted.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Extract the bearer credential from an `Authorization` header value.
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+fn unauthorized(message: &'static str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer realm=\"echidnabot\"")],
+        message,
+    )
+        .into_response()
+}
+
+/// Axum middleware: resolve the bearer token to an [`AuthContext`].
+///
+/// Applied to `/metrics` and the per-job export routes. `/health` stays
+/// open for load balancers, and the playground GET too unless
+/// [`crate::api::oidc::require_login`] guards it.
+pub async fn auth_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let anonymous = (!state.config().auth.requires_credentials()).then(AuthContext::open);
+    authenticate(&state, request, next, anonymous).await
+}
+
+/// Like [`auth_middleware`], but a request without a token is never
+/// rejected: it becomes [`AuthContext::public`]. Applied to the badge,
+/// `/status` and `/graphql` (POST) routes.
+pub async fn public_auth_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    authenticate(&state, request, next, Some(AuthContext::public())).await
+}
+
+/// The logged-in user of a request without a token, under `[auth.oidc]`.
+fn session(state: &AppState, request: &Request<Body>) -> Option<AuthContext> {
+    let config = state.config();
+    let session = super::oidc::session(config.auth.oidc.as_ref()?, request.headers())?;
+    Some(AuthContext {
+        token_id: None,
+        user: Some(session.subject),
+        scope: session.scope,
+        public_only: false,
+        tenant: None,
+    })
+}
+
+/// Resolve the bearer token, else the OIDC session, and insert the
+/// [`AuthContext`]. Requests with neither get `anonymous`, or 401 when
+/// that is `None`.
+async fn authenticate(
+    state: &AppState,
+    mut request: Request<Body>,
+    next: Next,
+    anonymous: Option<AuthContext>,
+) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| bearer_token(v).map(str::to_string));
+
+    let auth = match presented {
+        Some(Some(token)) => {
+            match state.store.get_api_token_by_hash(&hash_token(&token)).await {
+                Ok(Some(record)) if record.revoked_at.is_none() => {
+                    if let Err(e) = state.store.touch_api_token(record.id).await {
+                        tracing::debug!("touch_api_token failed for {}: {}", record.id, e);
+                    }
+                    AuthContext {
+                        token_id: Some(record.id),
+                        user: None,
+                        scope: record.scope,
+                        public_only: false,
+                        tenant: record.tenant,
+                    }
+                }
+                Ok(_) => return unauthorized("Invalid or revoked API token"),
+                Err(e) => {
+                    tracing::error!("API token lookup failed: {}", e);
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Token lookup failed")
+                        .into_response();
+                }
+            }
+        }
+        Some(None) => return unauthorized("Malformed Authorization header; expected Bearer token"),
+        None => match (session(state, &request), anonymous) {
+            (Some(auth), _) | (None, Some(auth)) => auth,
+            (None, None) => return unauthorized("API token required"),
+        },
+    };
+
+    request.extensions_mut().insert(auth);
+    next.run(request).await
+}
+
+/// Resolver-side scope gate. Requests that never passed through
+/// [`auth_middleware`] (in-process schema execution, tests) carry no
+/// [`AuthContext`] and are treated as trusted.
+pub fn require_scope(
+    ctx: &async_graphql::Context<'_>,
+    needed: TokenScope,
+) -> async_graphql::Result<()> {
+    let forbidden = |message: String| {
+        async_graphql::Error::new(message).extend_with(|_, e| e.set("code", "FORBIDDEN"))
+    };
+    match ctx.data_opt::<AuthContext>() {
+        Some(auth) if auth.public_only => Err(forbidden(
+            "Forbidden: this operation requires an API token".to_string(),
+        )),
+        Some(auth) if !auth.allows(needed) => Err(forbidden(format!(
+            "Forbidden: this operation requires the '{}' scope (token has '{}')",
+            needed, auth.scope
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// [`require_scope`] for operations spanning every tenant, which a
+/// tenant-scoped token may not run.
+pub fn require_instance_scope(
+    ctx: &async_graphql::Context<'_>,
+    needed: TokenScope,
+) -> async_graphql::Result<()> {
+    require_scope(ctx, needed)?;
+    match ctx.data_opt::<AuthContext>().and_then(|auth| auth.tenant.as_deref()) {
+        Some(tenant) => Err(async_graphql::Error::new(format!(
+            "Forbidden: this operation spans tenants (token is confined to '{}')",
+            tenant
+        ))
+        .extend_with(|_, e| e.set("code", "FORBIDDEN"))),
+        None => Ok(()),
+    }
+}
+
+/// Resolver-side visibility check; see [`AuthContext::can_view`]. As with
+/// [`require_scope`], a request without an [`AuthContext`] is trusted.
+pub fn can_view(ctx: &async_graphql::Context<'_>, repo: &Repository) -> bool {
+    ctx.data_opt::<AuthContext>()
+        .is_none_or(|auth| auth.can_view(repo))
+}
+
+/// The tenant the caller is confined to, if any.
+pub fn tenant(ctx: &async_graphql::Context<'_>) -> Option<String> {
+    ctx.data_opt::<AuthContext>().and_then(|auth| auth.tenant.clone())
+}
+
+/// For the job export routes: whether `auth` may see job `job_id`. An
+/// unknown job is left for the handler to answer 404.
+pub async fn can_view_job(
+    store: &dyn Store,
+    auth: &AuthContext,
+    job_id: JobId,
+) -> crate::Result<bool> {
+    if auth.tenant.is_none() {
+        return Ok(true);
+    }
+    let Some(job) = store.get_job(job_id).await? else {
+        return Ok(true);
+    };
+    let repo = store.get_repository(job.repo_id).await?;
+    Ok(repo.is_some_and(|repo| auth.can_view(&repo)))
+}
+
+/// [`can_view_job`] as a response: 404 for another tenant's job, as for
+/// an unknown one, and `None` to go ahead.
+pub async fn reject_foreign_job(
+    state: &AppState,
+    auth: &AuthContext,
+    job_id: JobId,
+) -> Option<Response> {
+    match can_view_job(state.store.as_ref(), auth, job_id).await {
+        Ok(true) => None,
+        Ok(false) => Some((StatusCode::NOT_FOUND, "No job with that id").into_response()),
+        Err(e) => {
+            tracing::error!("Job lookup for {} failed: {}", job_id, e);
+            Some((StatusCode::INTERNAL_SERVER_ERROR, "Job lookup failed").into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_hierarchical() {
+        let admin = AuthContext { scope: TokenScope::Admin, ..AuthContext::open() };
+        let read = AuthContext { scope: TokenScope::Read, ..AuthContext::open() };
+        assert!(admin.allows(TokenScope::Trigger));
+        assert!(read.allows(TokenScope::Read));
+        assert!(!read.allows(TokenScope::Trigger));
+        assert!(!read.allows(TokenScope::Admin));
+        assert!(!AuthContext::public().allows(TokenScope::Read));
+
+        let user = AuthContext { user: Some("u1".into()), ..read };
+        assert_eq!(user.audit_actor(), "oidc:u1");
+    }
+
+    #[test]
+    fn public_principal_only_sees_public_repos() {
+        use crate::adapters::Platform;
+
+        let mut repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        assert_eq!(repo.visibility, Visibility::Private, "private by default");
+        assert!(!AuthContext::public().can_view(&repo));
+        assert!(AuthContext::open().can_view(&repo));
+
+        repo.visibility = Visibility::Public;
+        assert!(AuthContext::public().can_view(&repo));
+    }
+
+    #[test]
+    fn tenant_tokens_only_see_their_owner() {
+        use crate::adapters::Platform;
+
+        let acme = AuthContext { tenant: Some("acme".into()), ..AuthContext::open() };
+        let ours = Repository::new(Platform::GitHub, "acme".into(), "r".into());
+        let theirs = Repository::new(Platform::GitHub, "globex".into(), "r".into());
+        assert!(acme.can_view(&ours));
+        assert!(!acme.can_view(&theirs));
+        assert!(acme.allows(TokenScope::Admin));
+        assert!(!acme.allows_instance(TokenScope::Read));
+        assert!(AuthContext::open().allows_instance(TokenScope::Admin));
+    }
+
+    #[test]
+    fn scope_round_trips_through_str() {
+        for scope in [TokenScope::Read, TokenScope::Trigger, TokenScope::Admin] {
+            assert_eq!(scope.as_str().parse::<TokenScope>().unwrap(), scope);
+        }
+        assert!("root".parse::<TokenScope>().is_err());
+    }
+
+    #[test]
+    fn generated_tokens_are_prefixed_and_unique() {
+        let a = generate_token();
+        let b = generate_token();
+        assert!(a.starts_with(TOKEN_PREFIX));
+        assert_eq!(a.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn hash_is_stable_and_not_plaintext() {
+        let t = "ebt_deadbeef";
+        assert_eq!(hash_token(t), hash_token(t));
+        assert_eq!(hash_token(t).len(), 64);
+        assert!(!hash_token(t).contains("deadbeef"));
+    }
+
+    #[test]
+    fn bearer_parsing() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+    }
+}
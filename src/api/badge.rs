@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /badge/{platform}/{owner}/{repo}[.svg]` — live verification badge
+//!
+//! Renders a shields.io "flat"-style SVG from the latest finished
+//! verification of the default branch, for embedding in a proof
+//! repository's README:
+//!
+//! ```markdown
+//! ![proofs](https://echidnabot.example.org/badge/github/owner/repo.svg)
+//! ![lean](https://echidnabot.example.org/badge/github/owner/repo.svg?prover=lean)
+//! ```
+//!
+//! | State      | Colour      | When                                          |
+//! |------------|-------------|-----------------------------------------------|
+//! | `verified` | bright green | Every prover's latest finished job succeeded  |
+//! | `failing`  | red         | Some prover's latest finished job failed      |
+//! | `unknown`  | grey        | A prover has no finished job, or repo not registered / disabled |
+//!
+//! Only pushes to the default branch count ([`is_default_branch_push`]):
+//! PR checks, PR baselines and force-pushed-out commits don't colour the
+//! README. With `?prover=` the badge shows that prover alone; without, it
+//! combines the enabled provers as `/status` does ([`crate::api::status`]).
+//!
+//! The endpoint needs no token (README images are fetched anonymously),
+//! but only public repositories get a real status without one; see
+//! [`crate::api::auth::public_auth_middleware`]. Private, unregistered and
+//! disabled repositories all answer `unknown` rather than 404, so the badge
+//! does not reveal which repositories the instance watches.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+
+use super::auth::AuthContext;
+use super::webhooks::AppState;
+use crate::adapters::Platform;
+use crate::dispatcher::ProverKind;
+use crate::error::Result;
+use crate::scheduler::{JobId, JobStatus};
+use crate::store::models::{ProofJobRecord, Repository};
+use crate::store::Store;
+
+/// How many recent jobs to scan for a finished one.
+pub(crate) const JOB_SCAN_LIMIT: usize = 50;
+
+/// Cache lifetime for badge responses. GitHub's image proxy (camo)
+/// honours this, so README badges refresh within a minute or so.
+const BADGE_MAX_AGE_SECS: u32 = 60;
+
+/// Badge state derived from the latest finished job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeStatus {
+    Verified,
+    Failing,
+    Unknown,
+}
+
+impl BadgeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BadgeStatus::Verified => "verified",
+            BadgeStatus::Failing => "failing",
+            BadgeStatus::Unknown => "unknown",
+        }
+    }
+
+    fn colour(&self) -> &'static str {
+        match self {
+            BadgeStatus::Verified => "#4c1",
+            BadgeStatus::Failing => "#e05d44",
+            BadgeStatus::Unknown => "#9f9f9f",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BadgeQuery {
+    /// Restrict to one prover; also becomes the badge label.
+    pub prover: Option<String>,
+    /// Override the left-hand label (default `proofs`).
+    pub label: Option<String>,
+}
+
+pub async fn repo_badge(
+    State(state): State<AppState>,
+    auth: Option<Extension<AuthContext>>,
+    Path((platform, owner, repo)): Path<(String, String, String)>,
+    Query(query): Query<BadgeQuery>,
+) -> Response {
+    let name = repo.strip_suffix(".svg").unwrap_or(&repo);
+    let prover = query.prover.as_deref().map(ProverKind::new);
+    // Without the auth middleware in front, treat the caller as anonymous.
+    let auth = auth.map_or_else(AuthContext::public, |Extension(auth)| auth);
+
+    let status = match parse_platform(&platform) {
+        Some(platform) => {
+            let lookup = async {
+                match state.store.get_repository_by_name(platform, &owner, name).await? {
+                    Some(repo) if auth.can_view(&repo) => {
+                        repo_status(state.store.as_ref(), &repo, prover.as_ref()).await
+                    }
+                    _ => Ok(BadgeStatus::Unknown),
+                }
+            };
+            match lookup.await {
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::warn!("Badge lookup for {}/{} failed: {}", owner, name, e);
+                    BadgeStatus::Unknown
+                }
+            }
+        }
+        None => BadgeStatus::Unknown,
+    };
+
+    let label = query
+        .label
+        .or_else(|| prover.as_ref().map(|p| p.display_name().to_string()))
+        .unwrap_or_else(|| "proofs".to_string());
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml;charset=utf-8".to_string()),
+            (
+                header::CACHE_CONTROL,
+                format!("max-age={}, s-maxage={}", BADGE_MAX_AGE_SECS, BADGE_MAX_AGE_SECS),
+            ),
+        ],
+        render_badge(&label, status),
+    )
+        .into_response()
+}
+
+pub(crate) fn parse_platform(s: &str) -> Option<Platform> {
+    match s.to_lowercase().as_str() {
+        "github" => Some(Platform::GitHub),
+        "gitlab" => Some(Platform::GitLab),
+        "bitbucket" => Some(Platform::Bitbucket),
+        "codeberg" => Some(Platform::Codeberg),
+        _ => None,
+    }
+}
+
+/// Status of the repository's default branch, optionally restricted to
+/// one prover. Queued and running jobs are skipped so a push does not
+/// flip the badge to `unknown` while it is being checked. Ignores
+/// visibility; callers decide who may see the answer.
+pub async fn latest_status(
+    store: &dyn Store,
+    platform: Platform,
+    owner: &str,
+    name: &str,
+    prover: Option<&ProverKind>,
+) -> Result<BadgeStatus> {
+    match store.get_repository_by_name(platform, owner, name).await? {
+        Some(repo) => repo_status(store, &repo, prover).await,
+        None => Ok(BadgeStatus::Unknown),
+    }
+}
+
+/// [`latest_status`] for an already loaded repository.
+pub async fn repo_status(
+    store: &dyn Store,
+    repo: &Repository,
+    prover: Option<&ProverKind>,
+) -> Result<BadgeStatus> {
+    if !repo.enabled {
+        return Ok(BadgeStatus::Unknown);
+    }
+
+    let jobs = store.list_jobs_for_repo(repo.id, JOB_SCAN_LIMIT).await?;
+    if let Some(prover) = prover {
+        return Ok(prover_status(store, repo, &jobs, prover).await?.0);
+    }
+    let mut statuses = Vec::new();
+    for prover in &repo.enabled_provers {
+        statuses.push(prover_status(store, repo, &jobs, prover).await?.0);
+    }
+    Ok(overall(&statuses))
+}
+
+/// Whether `job` verified a push to the repository's default branch: not
+/// a PR check or PR baseline, and not orphaned by a force-push or branch
+/// deletion. The badge, `/status` and the results feed report only these.
+pub(crate) fn is_default_branch_push(job: &ProofJobRecord, repo: &Repository) -> bool {
+    let on_default_branch = match (&job.branch, &repo.default_branch) {
+        (Some(branch), Some(default)) => branch == default,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    job.pr_number.is_none() && on_default_branch && job.payload.orphaned.is_none()
+}
+
+/// `prover`'s latest finished default-branch job among `jobs` (newest
+/// first), and its status.
+pub(crate) async fn prover_status<'a>(
+    store: &dyn Store,
+    repo: &Repository,
+    jobs: &'a [ProofJobRecord],
+    prover: &ProverKind,
+) -> Result<(BadgeStatus, Option<&'a ProofJobRecord>)> {
+    let latest = jobs.iter().find(|job| {
+        job.prover == *prover
+            && matches!(job.status, JobStatus::Completed | JobStatus::Failed)
+            && is_default_branch_push(job, repo)
+    });
+    let Some(job) = latest else {
+        return Ok((BadgeStatus::Unknown, None));
+    };
+    let success = match store.get_result_for_job(JobId(job.id)).await? {
+        Some(result) => result.success,
+        None => job.status == JobStatus::Completed,
+    };
+    let status = if success {
+        BadgeStatus::Verified
+    } else {
+        BadgeStatus::Failing
+    };
+    Ok((status, Some(job)))
+}
+
+/// `failing` if any prover fails, `verified` if every one (at least one)
+/// passes, `unknown` otherwise.
+pub(crate) fn overall(statuses: &[BadgeStatus]) -> BadgeStatus {
+    if statuses.contains(&BadgeStatus::Failing) {
+        BadgeStatus::Failing
+    } else if !statuses.is_empty() && statuses.iter().all(|s| *s == BadgeStatus::Verified) {
+        BadgeStatus::Verified
+    } else {
+        BadgeStatus::Unknown
+    }
+}
+
+/// Approximate rendered width of `text` in 11px Verdana. Exact metrics
+/// would need a font table; this keeps badges within a few pixels.
+fn text_width(text: &str) -> u32 {
+    text.chars()
+        .map(|c| match c {
+            'i' | 'l' | 'j' | 'I' | '.' | ',' | ':' | '!' | '|' | '\'' => 4,
+            'm' | 'w' | 'M' | 'W' => 10,
+            c if c.is_ascii_uppercase() => 8,
+            _ => 7,
+        })
+        .sum()
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Render a flat two-segment badge.
+pub fn render_badge(label: &str, status: BadgeStatus) -> String {
+    let label: String = label.chars().take(40).collect();
+    let message = status.as_str();
+    let lw = text_width(&label) + 10;
+    let mw = text_width(message) + 10;
+    let width = lw + mw;
+    let label = escape_xml(&label);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{lw}" height="20" fill="#555"/><rect x="{lw}" width="{mw}" height="20" fill="{colour}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{lx}" y="14">{label}</text><text x="{mx}" y="14">{message}</text></g></svg>"##,
+        width = width,
+        lw = lw,
+        mw = mw,
+        lx = lw / 2,
+        mx = lw + mw / 2,
+        colour = status.colour(),
+        label = label,
+        message = message,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn badge_escapes_label_and_colours_status() {
+        let svg = render_badge("<script>", BadgeStatus::Failing);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("&lt;script&gt;"));
+        assert!(!svg.contains("<script>"));
+        assert!(svg.contains("#e05d44"));
+        assert!(svg.contains(">failing</text>"));
+    }
+
+    #[test]
+    fn platform_parsing() {
+        assert_eq!(parse_platform("GitHub"), Some(Platform::GitHub));
+        assert_eq!(parse_platform("sourcehut"), None);
+    }
+}
@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Check-run requested actions (GitHub)
+//!
+//! Failed check runs carry three buttons. Clicking one makes GitHub send a
+//! `check_run` webhook with `action = "requested_action"`; the job id is
+//! recovered from the check run's `external_id` (set by the reporter in
+//! `main.rs`).
+//!
+//! | Button                  | Identifier        | Effect                                          |
+//! |-------------------------|-------------------|-------------------------------------------------|
+//! | Re-run (longer)         | `rerun_longer`    | Re-enqueue with [`RERUN_TIMEOUT_MULTIPLIER`]× timeout |
+//! | Suggest tactics         | `suggest_tactics` | Ask ECHIDNA for tactics, post them to the PR    |
+//! | Quarantine this file    | `quarantine`      | Failing files stop gating future jobs           |
+//!
+//! Quarantine is file-granular, and the button says so: a job verifies
+//! whole files, so the failing file is the smallest unit it can attribute
+//! a failure to, and every theorem in it is quarantined with it.
+//! Quarantined files still run and their failures are listed in the job
+//! message. Undo with the `unquarantineProof` GraphQL mutation.
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+use super::webhooks::AppState;
+use crate::adapters::{
+    CheckConclusion, CheckRun, CheckRunAction, CheckStatus, Platform, PrId, RepoId,
+};
+use crate::audit::AuditAction;
+use crate::dispatcher::EchidnaClient;
+use crate::error::Result;
+use crate::scheduler::{JobId, JobPriority, ProofJob};
+use crate::store::models::{ProofJobRecord, QuarantinedProof, Repository};
+
+pub const ACTION_RERUN_LONGER: &str = "rerun_longer";
+pub const ACTION_SUGGEST: &str = "suggest_tactics";
+pub const ACTION_QUARANTINE: &str = "quarantine";
+
+/// Timeout multiplier applied by "Re-run with larger timeout", relative
+/// to `[echidna] timeout_secs`.
+pub const RERUN_TIMEOUT_MULTIPLIER: u64 = 4;
+
+/// Prover output sent to ECHIDNA as the goal-state proxy is capped here.
+const MAX_GOAL_STATE_CHARS: usize = 2000;
+
+/// Buttons attached to a failed check run.
+pub fn failure_actions() -> Vec<CheckRunAction> {
+    vec![
+        CheckRunAction {
+            label: "Re-run (longer)".to_string(),
+            description: "Retry with a larger timeout".to_string(),
+            identifier: ACTION_RERUN_LONGER.to_string(),
+        },
+        CheckRunAction {
+            label: "Suggest tactics".to_string(),
+            description: "Ask ECHIDNA for tactic suggestions".to_string(),
+            identifier: ACTION_SUGGEST.to_string(),
+        },
+        CheckRunAction {
+            label: "Quarantine this file".to_string(),
+            description: "Stop the failing files gating checks".to_string(),
+            identifier: ACTION_QUARANTINE.to_string(),
+        },
+    ]
+}
+
+/// Dispatch a `requested_action` click.
+///
+/// `owner` / `name` come from the webhook payload and must match the
+/// job's repository — a check run's external id is not a capability.
+pub async fn handle_requested_action(
+    state: &AppState,
+    platform: Platform,
+    owner: &str,
+    name: &str,
+    external_id: &str,
+    identifier: &str,
+    actor: Option<&str>,
+) -> Result<()> {
+    let Ok(job_uuid) = Uuid::parse_str(external_id) else {
+        tracing::debug!("requested_action with non-job external_id '{}'", external_id);
+        return Ok(());
+    };
+    let Some(job) = state.store.get_job(JobId(job_uuid)).await? else {
+        tracing::debug!("requested_action for unknown job {}", job_uuid);
+        return Ok(());
+    };
+    let Some(repo) = state.store.get_repository(job.repo_id).await? else {
+        return Ok(());
+    };
+    if repo.platform != platform
+        || !repo.owner.eq_ignore_ascii_case(owner)
+        || !repo.name.eq_ignore_ascii_case(name)
+    {
+        tracing::warn!(
+            "requested_action for job {} arrived from {}/{} but job belongs to {}",
+            job_uuid,
+            owner,
+            name,
+            repo.full_name()
+        );
+        return Ok(());
+    }
+
+    tracing::info!(
+        "Check-run action '{}' on job {} ({}) by {}",
+        identifier,
+        job_uuid,
+        repo.full_name(),
+        actor.unwrap_or("unknown")
+    );
+
+    match identifier {
+        ACTION_RERUN_LONGER => rerun_with_longer_timeout(state, &job).await,
+        ACTION_SUGGEST => post_suggestions(state, &repo, &job).await,
+        ACTION_QUARANTINE => quarantine_failures(state, &repo, &job, actor).await,
+        other => {
+            tracing::debug!("Ignoring unknown check-run action '{}'", other);
+            Ok(())
+        }
+    }
+}
+
+async fn rerun_with_longer_timeout(state: &AppState, job: &ProofJobRecord) -> Result<()> {
+    // Older builds stored discovered files as absolute paths into their
+    // (now deleted) clone; let the re-run rediscover them.
+    let file_paths = if job.payload.file_paths.iter().any(|p| Path::new(p).is_absolute()) {
+        Vec::new()
+    } else {
+        job.payload.file_paths.clone()
+    };
+    let timeout = state.config().echidna.timeout_secs * RERUN_TIMEOUT_MULTIPLIER;
+
+    let rerun = ProofJob::new(job.repo_id, job.commit_sha.clone(), job.prover.clone(), file_paths)
+        .with_priority(JobPriority::High)
+        .with_context(job.pr_number, job.delivery_id.clone())
+        .with_timeout(timeout);
+    state.store.create_job(&ProofJobRecord::from(rerun.clone())).await?;
+    match state.scheduler.enqueue(rerun).await? {
+        Some(id) => tracing::info!("Re-enqueued job {} as {} with {}s timeout", job.id, id, timeout),
+        None => tracing::info!("Re-run of job {} not enqueued (duplicate or queue full)", job.id),
+    }
+    Ok(())
+}
+
+async fn post_suggestions(state: &AppState, repo: &Repository, job: &ProofJobRecord) -> Result<()> {
+    let output = state
+        .store
+        .get_result_for_job(JobId(job.id))
+        .await?
+        .map(|r| r.prover_output)
+        .unwrap_or_default();
+    let goal_state: String = output.chars().take(MAX_GOAL_STATE_CHARS).collect();
+
+    let echidna = EchidnaClient::new(&state.config().echidna);
+    let raw = echidna.suggest_tactics(&job.prover, "", &goal_state).await?;
+    let reranker = crate::feedback::Reranker::new(state.store.clone());
+    let suggestions = match reranker.rerank(&job.prover, &goal_state, raw.clone()).await {
+        Ok(reranked) => reranked,
+        Err(e) => {
+            tracing::debug!("Reranker error ({}); using raw suggestions", e);
+            raw
+        }
+    };
+
+    let mut body = format!(
+        "### 💡 Tactic suggestions for `{}` ({})\n\n",
+        &job.commit_sha[..job.commit_sha.len().min(8)],
+        job.prover.display_name()
+    );
+    if suggestions.is_empty() {
+        body.push_str("ECHIDNA had no suggestions for this failure.\n");
+    } else {
+        for s in suggestions.iter().take(5) {
+            body.push_str(&crate::result_formatter::format_tactic_suggestion(s));
+            body.push('\n');
+        }
+    }
+    let announcement = crate::announcement::active(state.store.as_ref()).await;
+    let body = crate::announcement::with_banner(announcement.as_ref(), &body);
+
+    let adapter = crate::adapters::build_adapter(&state.config(), repo.platform)?;
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    match job.pr_number {
+        Some(pr) => {
+            adapter.create_comment(&repo_id, PrId(pr.to_string()), &body).await?;
+            let target = Some(format!("PR #{}", pr));
+            crate::audit::record(state.store.as_ref(), crate::audit::BOT, AuditAction::CommentPosted, Some(repo), target, &body)
+                .await;
+        }
+        None => {
+            // Push-triggered job: no PR to comment on, so surface the
+            // suggestions as their own neutral check run on the commit.
+            let check = CheckRun {
+                name: "echidnabot/suggestions".to_string(),
+                head_sha: job.commit_sha.clone(),
+                status: CheckStatus::Completed {
+                    conclusion: CheckConclusion::Neutral,
+                    summary: body.clone(),
+                },
+                details_url: None,
+                external_id: Some(job.id.to_string()),
+                actions: vec![],
+                annotations: vec![],
+            };
+            let target = Some(format!("{} @ {}", check.name, job.commit_sha));
+            adapter.create_check_run(&repo_id, check).await?;
+            crate::audit::record(state.store.as_ref(), crate::audit::BOT, AuditAction::CheckRunCreated, Some(repo), target, &body)
+                .await;
+        }
+    }
+    Ok(())
+}
+
+/// "Quarantine this file": quarantine every file `job` failed on.
+async fn quarantine_failures(
+    state: &AppState,
+    repo: &Repository,
+    job: &ProofJobRecord,
+    actor: Option<&str>,
+) -> Result<()> {
+    // file_timings carry repo-relative paths; the result row may hold
+    // absolute paths into the job's clone.
+    let failing: Vec<String> = state
+        .store
+        .list_file_timings_for_job(JobId(job.id))
+        .await?
+        .into_iter()
+        .filter(|t| !t.success)
+        .map(|t| t.file_path)
+        .collect();
+
+    if failing.is_empty() {
+        tracing::info!("Quarantine requested for job {} but no failing files recorded", job.id);
+        return Ok(());
+    }
+
+    let reason = format!(
+        "Quarantined from check run of job {} by {}",
+        job.id,
+        actor.unwrap_or("unknown")
+    );
+    let audit_actor = actor.map_or_else(|| crate::audit::BOT.to_string(), crate::audit::user);
+    for file_path in failing {
+        tracing::info!("Quarantining {} in {}", file_path, repo.full_name());
+        state
+            .store
+            .quarantine_proof(&QuarantinedProof::new(repo.id, file_path.clone(), Some(reason.clone())))
+            .await?;
+        crate::audit::record(
+            state.store.as_ref(),
+            &audit_actor,
+            AuditAction::ProofQuarantined,
+            Some(repo),
+            Some(file_path),
+            &reason,
+        )
+        .await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_actions_fit_github_limits() {
+        let actions = failure_actions();
+        assert!(actions.len() <= 3, "GitHub allows at most three actions");
+        for a in &actions {
+            assert!(a.label.chars().count() <= 20, "label too long: {}", a.label);
+            assert!(a.description.chars().count() <= 40, "description too long: {}", a.description);
+            assert!(a.identifier.chars().count() <= 20, "identifier too long: {}", a.identifier);
+        }
+        let quarantine = actions.iter().find(|a| a.identifier == ACTION_QUARANTINE).unwrap();
+        assert_eq!(quarantine.label, "Quarantine this file", "it is not per theorem");
+    }
+}
@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Execution of `@echidnabot` comment commands
+//!
+//! Parsing and the permission map live in [`crate::modes::commands`];
+//! this module resolves the commenter's role, runs what they are allowed
+//! to run and replies on the PR. Explicit commands work in every bot
+//! mode. Free-form questions ([`BotCommand::Ask`]) are handed to the
+//! Consultant-mode Q&A handler, which stays silent outside Consultant
+//! mode as before.
+//!
+//! `check` and `rerun` act on the commit of the PR's most recent job:
+//! issue-comment payloads do not carry the PR head SHA, and the last
+//! commit we verified is the one the commenter is looking at.
+
+use super::webhooks::{handle_consultant_mention, AppState};
+use crate::adapters::{Platform, PrId, RepoId};
+use crate::audit::AuditAction;
+use crate::diagnostics::{attribute_to_files, parse_diagnostics};
+use crate::error::Result;
+use crate::modes::commands::{
+    help_reply, parse_authorized, permission_denied_reply, BotCommand, CommandOutcome, Role,
+};
+use crate::scheduler::{JobId, JobPriority, JobStatus, ProofJob};
+use crate::store::models::{ProofJobRecord, Repository};
+
+/// Recent jobs scanned for the ones belonging to a PR.
+const PR_JOB_SCAN_LIMIT: usize = 50;
+
+/// Diagnostics quoted in an `explain` reply.
+const MAX_EXPLAINED_DIAGNOSTICS: usize = 5;
+
+/// Who wrote a comment, as far as the payload tells us.
+#[derive(Debug, Clone, Copy)]
+pub struct Commenter<'a> {
+    pub login: Option<&'a str>,
+    /// GitHub `author_association`, or its equivalent derived from the
+    /// platform's payload; `None` when the payload says nothing.
+    pub association: Option<&'a str>,
+}
+
+/// Handle a PR comment that mentions the bot.
+pub async fn handle_comment_command(
+    state: &AppState,
+    platform: Platform,
+    owner: &str,
+    name: &str,
+    pr_number: u64,
+    body: &str,
+    commenter: Commenter<'_>,
+) -> Result<()> {
+    let Some(repo) = state
+        .store
+        .get_repository_by_name(platform, owner, name)
+        .await?
+    else {
+        tracing::debug!("Comment command on unregistered repo {}/{} — ignoring", owner, name);
+        return Ok(());
+    };
+
+    let role = Role::resolve(commenter.association, commenter.login, &repo.owner);
+    let config = state.config();
+    let permissions = &config.commands.permissions;
+
+    let reply = match parse_authorized(body, role, permissions) {
+        CommandOutcome::NotACommand => return Ok(()),
+        CommandOutcome::Denied {
+            command,
+            required,
+            actual,
+        } => {
+            tracing::info!(
+                "Denied '{}' on {} PR #{} for {} ({} < {})",
+                command.usage(),
+                repo.full_name(),
+                pr_number,
+                commenter.login.unwrap_or("unknown"),
+                actual,
+                required
+            );
+            permission_denied_reply(&command, required, actual, commenter.login)
+        }
+        CommandOutcome::Allowed(BotCommand::Ask(_)) => {
+            return handle_consultant_mention(state, platform, owner, name, pr_number, body).await;
+        }
+        CommandOutcome::Allowed(BotCommand::Help) => help_reply(role, permissions),
+        CommandOutcome::Allowed(BotCommand::Explain) => explain(state, &repo, pr_number).await?,
+        CommandOutcome::Allowed(command @ (BotCommand::Check | BotCommand::Rerun { .. })) => {
+            tracing::info!(
+                "'{}' on {} PR #{} by {}",
+                command.usage(),
+                repo.full_name(),
+                pr_number,
+                commenter.login.unwrap_or("unknown")
+            );
+            rerun(state, &repo, pr_number, &command).await?
+        }
+    };
+
+    let announcement = crate::announcement::active(state.store.as_ref()).await;
+    let reply = crate::announcement::with_banner(announcement.as_ref(), &reply);
+    let adapter = crate::adapters::build_adapter(&state.config(), repo.platform)?;
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    match adapter
+        .create_comment(&repo_id, PrId(pr_number.to_string()), &reply)
+        .await
+    {
+        Ok(_) => {
+            crate::audit::record(
+                state.store.as_ref(),
+                crate::audit::BOT,
+                AuditAction::CommentPosted,
+                Some(&repo),
+                Some(format!("PR #{}", pr_number)),
+                &reply,
+            )
+            .await
+        }
+        Err(e) => tracing::warn!(
+            "Command reply failed for {} PR #{}: {}",
+            repo.full_name(),
+            pr_number,
+            e
+        ),
+    }
+    Ok(())
+}
+
+async fn pr_jobs(state: &AppState, repo: &Repository, pr_number: u64) -> Result<Vec<ProofJobRecord>> {
+    Ok(state
+        .store
+        .list_jobs_for_repo(repo.id, PR_JOB_SCAN_LIMIT)
+        .await?
+        .into_iter()
+        .filter(|j| j.pr_number == Some(pr_number))
+        .collect())
+}
+
+/// Reply to `explain`: where and why the PR's latest failing job failed.
+async fn explain(state: &AppState, repo: &Repository, pr_number: u64) -> Result<String> {
+    let jobs = pr_jobs(state, repo, pr_number).await?;
+    let Some(latest) = jobs.first() else {
+        return Ok("I haven't verified any commit on this PR yet, so there is nothing to explain.\n".to_string());
+    };
+
+    // Only the newest commit matters; an older failure may already be fixed.
+    let mut failing = None;
+    for job in jobs.iter().filter(|j| j.commit_sha == latest.commit_sha) {
+        let result = state.store.get_result_for_job(JobId(job.id)).await?;
+        let failed = match &result {
+            Some(r) => !r.success,
+            None => job.status == JobStatus::Failed,
+        };
+        if failed {
+            failing = Some((job, result));
+            break;
+        }
+    }
+    let Some((job, result)) = failing else {
+        return Ok(format!(
+            "✅ Nothing to explain: no proof failures recorded for `{:.8}`.\n",
+            latest.commit_sha
+        ));
+    };
+
+    let mut out = format!(
+        "### 🔍 Why `{:.8}` failed ({})\n\n",
+        job.commit_sha,
+        job.prover.display_name()
+    );
+    let Some(result) = result else {
+        out.push_str(job.error_message.as_deref().unwrap_or("The job failed before producing a result."));
+        out.push('\n');
+        return Ok(out);
+    };
+    out.push_str(&format!("{}\n\n", result.message));
+
+    let timings = state.store.list_file_timings_for_job(JobId(job.id)).await?;
+    let files: Vec<String> = timings.iter().map(|t| t.file_path.clone()).collect();
+    let failed_files: Vec<String> = timings
+        .iter()
+        .filter(|t| !t.success)
+        .map(|t| t.file_path.clone())
+        .collect();
+    let mut diags = parse_diagnostics(&result.prover_output);
+    attribute_to_files(&mut diags, &files, &failed_files);
+
+    if diags.is_empty() {
+        let excerpt: String = result.prover_output.lines().take(15).collect::<Vec<_>>().join("\n");
+        if !excerpt.trim().is_empty() {
+            out.push_str(&format!("```\n{}\n```\n", excerpt));
+        }
+        return Ok(out);
+    }
+    for d in diags.iter().take(MAX_EXPLAINED_DIAGNOSTICS) {
+        let location = match (&d.file, d.line) {
+            (Some(f), Some(l)) => format!("`{}:{}`", f, l),
+            (Some(f), None) => format!("`{}`", f),
+            _ => "(unknown location)".to_string(),
+        };
+        let message: String = d.message.lines().take(6).collect::<Vec<_>>().join("\n");
+        out.push_str(&format!("- {} — {:?}\n  ```\n  {}\n  ```\n", location, d.severity, message.replace('\n', "\n  ")));
+    }
+    if diags.len() > MAX_EXPLAINED_DIAGNOSTICS {
+        out.push_str(&format!("\n…and {} more.\n", diags.len() - MAX_EXPLAINED_DIAGNOSTICS));
+    }
+    Ok(out)
+}
+
+/// `check`, `rerun` and `rerun --full` at the PR's latest verified commit.
+async fn rerun(
+    state: &AppState,
+    repo: &Repository,
+    pr_number: u64,
+    command: &BotCommand,
+) -> Result<String> {
+    let jobs = pr_jobs(state, repo, pr_number).await?;
+    let Some(latest) = jobs.first() else {
+        return Ok(
+            "I haven't seen a commit on this PR yet — push a change to a proof file and I'll verify it.\n"
+                .to_string(),
+        );
+    };
+    let sha = latest.commit_sha.clone();
+
+    let mut planned: Vec<ProofJob> = Vec::new();
+    match command {
+        BotCommand::Rerun { full: false } => {
+            // Newest job per prover only; earlier re-runs of the same
+            // commit would otherwise be re-queued again.
+            let mut seen = Vec::new();
+            for job in jobs.iter().filter(|j| j.commit_sha == sha) {
+                if seen.contains(&job.prover) {
+                    continue;
+                }
+                seen.push(job.prover.clone());
+                let failing: Vec<String> = state
+                    .store
+                    .list_file_timings_for_job(JobId(job.id))
+                    .await?
+                    .into_iter()
+                    .filter(|t| !t.success)
+                    .map(|t| t.file_path)
+                    .collect();
+                if !failing.is_empty() {
+                    planned.push(ProofJob::new(repo.id, sha.clone(), job.prover.clone(), failing));
+                }
+            }
+            if planned.is_empty() {
+                return Ok(format!(
+                    "No failing proof files recorded for `{:.8}`; nothing to re-run. \
+                     Use `@echidnabot rerun --full` to re-verify everything.\n",
+                    sha
+                ));
+            }
+        }
+        _ => {
+            for prover in &repo.enabled_provers {
+                planned.push(ProofJob::new(repo.id, sha.clone(), prover.clone(), Vec::new()));
+            }
+        }
+    }
+
+    let mut enqueued = 0;
+    for job in planned {
+        let job = job
+            .with_priority(JobPriority::High)
+            .with_context(Some(pr_number), None);
+        state.store.create_job(&ProofJobRecord::from(job.clone())).await?;
+        if state.scheduler.enqueue(job).await?.is_some() {
+            enqueued += 1;
+        }
+    }
+    Ok(format!(
+        "🔁 `{}` queued {} job(s) for `{:.8}`.\n",
+        command.usage(),
+        enqueued,
+        sha
+    ))
+}
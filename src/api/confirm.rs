@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Two-step confirmation of destructive mutations
+//!
+//! With `[auth] confirm_destructive = true`, `purgeRepository` and
+//! (unless a dry run) `bulkUpdateRepoSettings` do nothing on a first call.
+//! They answer with a [`Confirmation`]: an impact summary and a token
+//! valid for `confirmation_ttl_secs`. Calling the same mutation again with
+//! `confirmationToken` set runs it. A token
+//!
+//! - is good for one call, and only for the caller it was issued to;
+//! - is bound to what the mutation would do, so it is refused when the
+//!   request — or, for a bulk update, the set of changes it plans —
+//!   differs from the one confirmed;
+//! - is stored only as its SHA-256.
+//!
+//! Each token issued gets a `confirmation.requested` audit entry holding
+//! the impact summary; the mutation, once run, is audited as usual. A
+//! token is checked whenever one is given, even with confirmations off.
+
+use async_graphql::{ErrorExtensions, SimpleObject};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+use super::auth::{generate_token, hash_token};
+use crate::audit::{self, AuditAction};
+use crate::store::models::ConfirmationRecord;
+use crate::store::Store;
+
+/// Present in the GraphQL context when destructive mutations need
+/// confirming.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfirmationPolicy {
+    pub ttl: Duration,
+}
+
+/// A destructive mutation held until it is called again with `token`.
+#[derive(SimpleObject, Clone, Debug)]
+pub struct Confirmation {
+    /// Pass as `confirmationToken` to run the mutation.
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    /// What the mutation will do.
+    pub impact: String,
+}
+
+/// Whether a destructive mutation may run.
+#[derive(Debug)]
+pub enum Gate {
+    Proceed,
+    Pending(Confirmation),
+}
+
+fn rejected(message: impl Into<String>) -> async_graphql::Error {
+    async_graphql::Error::new(message.into())
+        .extend_with(|_, e| e.set("code", "CONFIRMATION_REJECTED"))
+}
+
+/// Decide whether `action` by `actor` runs now. `subject_digest` pins what
+/// it would do; `impact` is shown when a token is issued.
+pub async fn gate(
+    store: &dyn Store,
+    policy: Option<&ConfirmationPolicy>,
+    actor: &str,
+    action: &str,
+    subject_digest: String,
+    impact: String,
+    token: Option<&str>,
+) -> async_graphql::Result<Gate> {
+    if let Some(token) = token {
+        let pending = store
+            .take_confirmation(&hash_token(token))
+            .await
+            .map_err(super::graphql::store_error)?
+            .ok_or_else(|| rejected("Unknown or already used confirmation token"))?;
+        if pending.expires_at <= Utc::now() {
+            return Err(rejected("Confirmation token expired; request a new one"));
+        }
+        if pending.action != action || pending.actor != actor {
+            return Err(rejected("Confirmation token was issued for another mutation or caller"));
+        }
+        if pending.subject_digest != subject_digest {
+            return Err(rejected(
+                "What this mutation would do has changed since it was confirmed; \
+                 request a new token",
+            ));
+        }
+        return Ok(Gate::Proceed);
+    }
+    let Some(policy) = policy else {
+        return Ok(Gate::Proceed);
+    };
+
+    let token = generate_token();
+    let now = Utc::now();
+    let ttl = chrono::Duration::from_std(policy.ttl).unwrap_or(chrono::Duration::minutes(5));
+    let record = ConfirmationRecord {
+        token_hash: hash_token(&token),
+        action: action.to_string(),
+        actor: actor.to_string(),
+        subject_digest,
+        impact: impact.clone(),
+        created_at: now,
+        expires_at: now + ttl,
+    };
+    store.create_confirmation(&record).await.map_err(super::graphql::store_error)?;
+    audit::record(
+        store,
+        actor,
+        AuditAction::ConfirmationRequested,
+        None,
+        Some(action.to_string()),
+        impact.as_bytes(),
+    )
+    .await;
+    Ok(Gate::Pending(Confirmation { token, expires_at: record.expires_at, impact }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn a_token_runs_the_confirmed_request_once() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let policy = ConfirmationPolicy { ttl: Duration::from_secs(60) };
+        let ask = |digest: &str, token: Option<String>| {
+            let store = &store;
+            let digest = digest.to_string();
+            async move {
+                let token = token.as_deref();
+                gate(store, Some(&policy), "token:1", "purgeRepository", digest, "x".into(), token)
+                    .await
+            }
+        };
+
+        let Gate::Pending(confirmation) = ask("d1", None).await.unwrap() else {
+            panic!("first call should wait for confirmation");
+        };
+        assert_eq!(confirmation.impact, "x");
+        assert!(matches!(ask("d1", Some(confirmation.token.clone())).await, Ok(Gate::Proceed)));
+        assert!(ask("d1", Some(confirmation.token)).await.is_err(), "single use");
+
+        let Gate::Pending(other) = ask("d1", None).await.unwrap() else { unreachable!() };
+        assert!(ask("d2", Some(other.token)).await.is_err(), "bound to the request");
+
+        let Gate::Pending(theirs) = ask("d1", None).await.unwrap() else { unreachable!() };
+        let by_someone_else = gate(
+            &store,
+            Some(&policy),
+            "token:2",
+            "purgeRepository",
+            "d1".into(),
+            "x".into(),
+            Some(&theirs.token),
+        );
+        assert!(by_someone_else.await.is_err());
+
+        // Off: runs straight away.
+        let off = gate(&store, None, "token:1", "purgeRepository", "d1".into(), "x".into(), None);
+        assert!(matches!(off.await, Ok(Gate::Proceed)));
+    }
+}
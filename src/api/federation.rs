@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /federation/results?since=<RFC 3339>&after=<id>&limit=<n>` — the
+//! signed results feed other instances import from
+//!
+//! Open, like the key set: it only carries public repositories, and every
+//! entry is a signed envelope. Answers 404 unless `[federation] publish`
+//! is set and a signing key is configured. See [`crate::federation`].
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::webhooks::AppState;
+use crate::attest::ResultSigner;
+use crate::federation::{build_feed, FeedCursor, DEFAULT_FEED_LIMIT};
+
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// The page's `next_after`, given with its `next` as `since`.
+    #[serde(default)]
+    pub after: Option<Uuid>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+pub async fn results(State(state): State<AppState>, Query(query): Query<FeedQuery>) -> Response {
+    if !state.config().federation.publish {
+        return (StatusCode::NOT_FOUND, "Federation publishing is not enabled").into_response();
+    }
+    let signer = match ResultSigner::from_config(&state.config().attestation) {
+        Ok(Some(signer)) => signer,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, "Attestations are not enabled").into_response();
+        }
+        Err(e) => {
+            tracing::error!("Attestation signing key unavailable: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Signing key unavailable").into_response();
+        }
+    };
+
+    let cursor = FeedCursor {
+        since: query.since.unwrap_or(DateTime::UNIX_EPOCH),
+        after: query.after,
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_FEED_LIMIT);
+    match build_feed(state.store.as_ref(), &signer, cursor, limit).await {
+        Ok(page) => Json(page).into_response(),
+        Err(e) => {
+            tracing::error!("Federation feed failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Feed unavailable").into_response()
+        }
+    }
+}
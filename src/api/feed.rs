@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /feeds/{platform}/{owner}/{repo}.atom|.rss` — results feeds
+//!
+//! One entry per finished verification of the repository's default
+//! branch, newest first, for following a proof library from a feed reader:
+//!
+//! ```text
+//! https://echidnabot.example.org/feeds/github/leanprover-community/mathlib4.atom
+//! https://echidnabot.example.org/feeds/github/leanprover-community/mathlib4.rss
+//! ```
+//!
+//! The default branch is the one push payloads last reported; until one
+//! has been seen, pushes to any branch are listed. PR checks, manual runs,
+//! PR baselines and commits a force-push dropped never are.
+//!
+//! Access follows the status API: no token for public repositories, and
+//! the same 404 for private, unregistered and disabled ones. Responses
+//! carry an `ETag` and a five-minute `Cache-Control`, so pollers mostly get
+//! `304 Not Modified`. Links are absolute only with `[server] public_url`.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::auth::AuthContext;
+use super::badge::{escape_xml, is_default_branch_push, parse_platform};
+use super::webhooks::AppState;
+use crate::error::Result;
+use crate::scheduler::{JobId, JobStatus};
+use crate::store::models::{Repository, Visibility};
+use crate::store::Store;
+
+/// Recent jobs scanned for feed entries.
+const JOB_SCAN_LIMIT: usize = 200;
+
+/// Entries per feed.
+pub const MAX_ENTRIES: usize = 30;
+
+/// Cache lifetime for feed responses.
+const FEED_MAX_AGE_SECS: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    /// Split `mathlib4.atom` into the repository name and format.
+    pub fn split(feed: &str) -> Option<(&str, FeedFormat)> {
+        if let Some(name) = feed.strip_suffix(".atom") {
+            Some((name, FeedFormat::Atom))
+        } else {
+            feed.strip_suffix(".rss").map(|name| (name, FeedFormat::Rss))
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "application/atom+xml;charset=utf-8",
+            FeedFormat::Rss => "application/rss+xml;charset=utf-8",
+        }
+    }
+}
+
+/// One finished verification.
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub job_id: Uuid,
+    pub prover: String,
+    pub commit_sha: String,
+    pub branch: Option<String>,
+    pub success: bool,
+    pub message: String,
+    pub updated: DateTime<Utc>,
+}
+
+impl FeedEntry {
+    fn title(&self) -> String {
+        let short = &self.commit_sha[..self.commit_sha.len().min(7)];
+        if self.success {
+            format!("✅ {} verified {}", self.prover, short)
+        } else {
+            format!("❌ {} failed {}", self.prover, short)
+        }
+    }
+}
+
+pub async fn repo_feed(
+    State(state): State<AppState>,
+    auth: Option<Extension<AuthContext>>,
+    Path((platform, owner, feed)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let auth = auth.map_or_else(AuthContext::public, |Extension(auth)| auth);
+    let not_found = || (StatusCode::NOT_FOUND, "Feed not found").into_response();
+
+    let (Some(platform), Some((name, format))) = (parse_platform(&platform), FeedFormat::split(&feed))
+    else {
+        return not_found();
+    };
+    let repo = match state.store.get_repository_by_name(platform, &owner, name).await {
+        Ok(Some(repo)) if repo.enabled && auth.can_view(&repo) => repo,
+        Ok(_) => return not_found(),
+        Err(e) => {
+            tracing::error!("Feed lookup for {}/{} failed: {}", owner, name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Feed lookup failed").into_response();
+        }
+    };
+    let entries = match feed_entries(state.store.as_ref(), &repo).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::error!("Feed for {} failed: {}", repo.full_name(), e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Feed lookup failed").into_response();
+        }
+    };
+
+    let config = state.config();
+    let public_url = config.server.public_url.as_deref();
+    let body = match format {
+        FeedFormat::Atom => render_atom(&repo, &entries, public_url),
+        FeedFormat::Rss => render_rss(&repo, &entries, public_url),
+    };
+    let etag = format!("\"{}\"", &hex::encode(Sha256::digest(body.as_bytes()))[..32]);
+    let cache_control = format!(
+        "{}, max-age={}",
+        if repo.visibility == Visibility::Public { "public" } else { "private" },
+        FEED_MAX_AGE_SECS
+    );
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, cache_control)],
+        )
+            .into_response();
+    }
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, cache_control),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Finished push verifications of the default branch, newest first.
+pub async fn feed_entries(store: &dyn Store, repo: &Repository) -> Result<Vec<FeedEntry>> {
+    let mut entries = Vec::new();
+    for job in store.list_jobs_for_repo(repo.id, JOB_SCAN_LIMIT).await? {
+        if !matches!(job.status, JobStatus::Completed | JobStatus::Failed) {
+            continue;
+        }
+        if !is_default_branch_push(&job, repo) {
+            continue;
+        }
+        let result = store.get_result_for_job(JobId(job.id)).await?;
+        let (success, message) = match result {
+            Some(result) => (result.success, result.message),
+            None => (
+                job.status == JobStatus::Completed,
+                job.error_message.clone().unwrap_or_default(),
+            ),
+        };
+        entries.push(FeedEntry {
+            job_id: job.id,
+            prover: job.prover.display_name().to_string(),
+            commit_sha: job.commit_sha.clone(),
+            branch: job.branch.clone(),
+            success,
+            message,
+            updated: job.completed_at.unwrap_or(job.queued_at),
+        });
+        if entries.len() == MAX_ENTRIES {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+fn feed_title(repo: &Repository) -> String {
+    format!("echidnabot: {}", repo.full_name())
+}
+
+/// `(feed path, status URL)` under `public_url`.
+fn links(repo: &Repository, public_url: Option<&str>, ext: &str) -> Option<(String, String)> {
+    let base = public_url?.trim_end_matches('/');
+    let path = format!(
+        "{}/{}/{}",
+        format!("{:?}", repo.platform).to_lowercase(),
+        repo.owner,
+        repo.name
+    );
+    Some((
+        format!("{}/feeds/{}.{}", base, path, ext),
+        format!("{}/status/{}", base, path),
+    ))
+}
+
+fn entry_summary(entry: &FeedEntry) -> String {
+    let mut summary = match &entry.branch {
+        Some(branch) => format!("{} on {} at {}.", entry.prover, branch, entry.commit_sha),
+        None => format!("{} at {}.", entry.prover, entry.commit_sha),
+    };
+    if !entry.message.is_empty() {
+        summary.push(' ');
+        summary.push_str(&entry.message);
+    }
+    summary
+}
+
+/// Render an Atom 1.0 feed.
+pub fn render_atom(repo: &Repository, entries: &[FeedEntry], public_url: Option<&str>) -> String {
+    let links = links(repo, public_url, "atom");
+    let updated = entries.first().map_or(repo.created_at, |e| e.updated);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    out.push_str(&format!("  <id>urn:uuid:{}</id>\n", repo.id));
+    out.push_str(&format!("  <title>{}</title>\n", escape_xml(&feed_title(repo))));
+    out.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+    out.push_str("  <author><name>echidnabot</name></author>\n");
+    if let Some((feed, status)) = &links {
+        out.push_str(&format!("  <link rel=\"self\" href=\"{}\"/>\n", escape_xml(feed)));
+        out.push_str(&format!("  <link rel=\"alternate\" href=\"{}\"/>\n", escape_xml(status)));
+    }
+    for entry in entries {
+        out.push_str("  <entry>\n");
+        out.push_str(&format!("    <id>urn:uuid:{}</id>\n", entry.job_id));
+        out.push_str(&format!("    <title>{}</title>\n", escape_xml(&entry.title())));
+        out.push_str(&format!("    <updated>{}</updated>\n", entry.updated.to_rfc3339()));
+        if let Some((_, status)) = &links {
+            out.push_str(&format!("    <link href=\"{}\"/>\n", escape_xml(status)));
+        }
+        out.push_str(&format!("    <summary>{}</summary>\n", escape_xml(&entry_summary(entry))));
+        out.push_str("  </entry>\n");
+    }
+    out.push_str("</feed>\n");
+    out
+}
+
+/// Render an RSS 2.0 feed.
+pub fn render_rss(repo: &Repository, entries: &[FeedEntry], public_url: Option<&str>) -> String {
+    let links = links(repo, public_url, "rss");
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<rss version=\"2.0\">\n  <channel>\n");
+    out.push_str(&format!("    <title>{}</title>\n", escape_xml(&feed_title(repo))));
+    if let Some((_, status)) = &links {
+        out.push_str(&format!("    <link>{}</link>\n", escape_xml(status)));
+    }
+    out.push_str(&format!(
+        "    <description>Proof verification results for {}</description>\n",
+        escape_xml(&repo.full_name())
+    ));
+    if let Some(first) = entries.first() {
+        out.push_str(&format!("    <lastBuildDate>{}</lastBuildDate>\n", first.updated.to_rfc2822()));
+    }
+    for entry in entries {
+        out.push_str("    <item>\n");
+        out.push_str(&format!("      <title>{}</title>\n", escape_xml(&entry.title())));
+        out.push_str(&format!(
+            "      <guid isPermaLink=\"false\">urn:uuid:{}</guid>\n",
+            entry.job_id
+        ));
+        out.push_str(&format!("      <pubDate>{}</pubDate>\n", entry.updated.to_rfc2822()));
+        if let Some((_, status)) = &links {
+            out.push_str(&format!("      <link>{}</link>\n", escape_xml(status)));
+        }
+        out.push_str(&format!(
+            "      <description>{}</description>\n",
+            escape_xml(&entry_summary(entry))
+        ));
+        out.push_str("    </item>\n");
+    }
+    out.push_str("  </channel>\n</rss>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use crate::store::models::{ProofJobRecord, ProofResultRecord};
+    use crate::store::SqliteStore;
+
+    async fn finished(store: &SqliteStore, job: ProofJob, success: bool) {
+        let mut record = ProofJobRecord::from(job.clone());
+        record.status = if success { JobStatus::Completed } else { JobStatus::Failed };
+        record.completed_at = Some(Utc::now());
+        store.create_job(&record).await.unwrap();
+        let result = JobResult {
+            success,
+            message: "Checked <Foo.lean>".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        store.save_result(&ProofResultRecord::new(job.id, &result)).await.unwrap();
+    }
+
+    #[test]
+    fn splits_feed_names() {
+        assert_eq!(FeedFormat::split("mathlib4.atom"), Some(("mathlib4", FeedFormat::Atom)));
+        assert_eq!(FeedFormat::split("a.b.rss"), Some(("a.b", FeedFormat::Rss)));
+        assert_eq!(FeedFormat::split("mathlib4"), None);
+    }
+
+    #[tokio::test]
+    async fn lists_default_branch_pushes_only() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let mut repo = Repository::new(Platform::GitHub, "owner".into(), "lib".into());
+        repo.default_branch = Some("main".into());
+        store.create_repository(&repo).await.unwrap();
+        let lean = ProverKind::new("lean");
+
+        let push = |sha: &str, branch: &str| {
+            ProofJob::new(repo.id, sha.into(), lean.clone(), vec![]).with_branch(Some(branch.into()))
+        };
+        finished(&store, push("aaaaaaaaaa", "main"), true).await;
+        finished(&store, push("bbbbbbbbbb", "feature"), true).await;
+        let pr = ProofJob::new(repo.id, "cccccccccc".into(), lean.clone(), vec![])
+            .with_context(Some(7), None);
+        finished(&store, pr, false).await;
+
+        let entries = feed_entries(&store, &repo).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].commit_sha, "aaaaaaaaaa");
+
+        let atom = render_atom(&repo, &entries, Some("https://bot.example.org/"));
+        assert!(atom.contains("<link rel=\"self\" href=\"https://bot.example.org/feeds/github/owner/lib.atom\"/>"));
+        assert!(atom.contains(&format!("<id>urn:uuid:{}</id>", entries[0].job_id)));
+        assert!(atom.contains("Checked &lt;Foo.lean&gt;"), "summaries are escaped");
+
+        let rss = render_rss(&repo, &entries, None);
+        assert!(rss.contains("<rss version=\"2.0\">"));
+        assert!(!rss.contains("<link>"), "no absolute links without public_url");
+    }
+}
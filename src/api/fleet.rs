@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `/fleet` — remote verification workers
+//!
+//! A worker registers with the provers it carries and its resource class,
+//! then polls for jobs it can run (see [`crate::fleet::workers`]):
+//!
+//! | Route                                        | Scope     |                                   |
+//! |----------------------------------------------|-----------|-----------------------------------|
+//! | `GET /fleet/workers`                         | `read`    | live workers + unschedulable jobs |
+//! | `POST /fleet/workers`                        | `trigger` | register / heartbeat              |
+//! | `DELETE /fleet/workers/{id}`                 | `trigger` | leave the fleet                   |
+//! | `POST /fleet/workers/{id}/claim`             | `trigger` | 200 + job, or 204 when none fits  |
+//! | `POST /fleet/workers/{id}/jobs/{job}/result` | `trigger` | report a claimed job's result     |
+//!
+//! Workers run every tenant's jobs, so a tenant-scoped token (see
+//! [`crate::tenancy`]) is refused whatever its scope.
+//!
+//! Re-registering within `[fleet] worker_ttl_secs` keeps a worker live;
+//! claims answer 404 once it has expired, and jobs it held are requeued —
+//! or cancelled, when the same check has been queued again meanwhile.
+//! Reported results go through the same finalization and platform
+//! reporting as local jobs.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::auth::{auth_middleware, AuthContext, TokenScope};
+use super::webhooks::AppState;
+use crate::dispatcher::ProverKind;
+use crate::fleet::workers::{ResourceClass, WorkerCapabilities, WorkerInfo, LOCAL_WORKER};
+use crate::scheduler::{JobId, JobResult, JobStatus, ProofJob};
+
+/// Fleet routes, behind [`auth_middleware`].
+pub fn fleet_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/fleet/workers", get(list_workers).post(register_worker))
+        .route("/fleet/workers/{id}", delete(deregister_worker))
+        .route("/fleet/workers/{id}/claim", post(claim_job))
+        .route("/fleet/workers/{id}/jobs/{job_id}/result", post(report_result))
+        .route_layer(middleware::from_fn_with_state(state, auth_middleware))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterWorker {
+    pub id: String,
+    #[serde(flatten)]
+    pub capabilities: WorkerCapabilities,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnschedulableJob {
+    pub job_id: JobId,
+    pub prover: ProverKind,
+    pub required_class: ResourceClass,
+    pub queued_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FleetOverview {
+    pub workers: Vec<WorkerInfo>,
+    pub unschedulable: Vec<UnschedulableJob>,
+}
+
+/// Where a claimed job's sources live.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimedRepository {
+    pub platform: String,
+    pub owner: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimedJob {
+    pub job: ProofJob,
+    pub repository: ClaimedRepository,
+}
+
+fn forbidden() -> Response {
+    (StatusCode::FORBIDDEN, "Token lacks trigger scope").into_response()
+}
+
+async fn list_workers(State(state): State<AppState>, Extension(auth): Extension<AuthContext>) -> Response {
+    if !auth.allows_instance(TokenScope::Read) {
+        return (StatusCode::FORBIDDEN, "Token lacks read scope").into_response();
+    }
+    let workers = state.scheduler.workers();
+    let unschedulable = state
+        .scheduler
+        .unschedulable_jobs()
+        .await
+        .into_iter()
+        .map(|job| UnschedulableJob {
+            job_id: job.id,
+            required_class: workers.required_class(&job.prover),
+            prover: job.prover,
+            queued_at: job.queued_at,
+        })
+        .collect();
+    Json(FleetOverview {
+        workers: workers.live(),
+        unschedulable,
+    })
+    .into_response()
+}
+
+async fn register_worker(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Json(body): Json<RegisterWorker>,
+) -> Response {
+    if !auth.allows_instance(TokenScope::Trigger) {
+        return forbidden();
+    }
+    if body.id.trim().is_empty() || body.id == LOCAL_WORKER {
+        return (StatusCode::BAD_REQUEST, "Invalid worker id").into_response();
+    }
+    Json(state.scheduler.workers().register(&body.id, body.capabilities)).into_response()
+}
+
+async fn deregister_worker(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Response {
+    if !auth.allows_instance(TokenScope::Trigger) {
+        return forbidden();
+    }
+    if id == LOCAL_WORKER || !state.scheduler.workers().deregister(&id) {
+        return (StatusCode::NOT_FOUND, "Unknown worker").into_response();
+    }
+    let requeued =
+        crate::scheduler::supersede::requeue_orphaned(state.store.as_ref(), &state.scheduler);
+    if let Err(e) = requeued.await {
+        tracing::warn!("Recording worker {}'s dropped jobs failed: {}", id, e);
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn claim_job(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Response {
+    if !auth.allows_instance(TokenScope::Trigger) {
+        return forbidden();
+    }
+    if id == LOCAL_WORKER || state.scheduler.workers().touch(&id).is_none() {
+        return (StatusCode::NOT_FOUND, "Unknown or expired worker; register again").into_response();
+    }
+    match claim_next(&state, &id).await {
+        Ok(Some(claimed)) => Json(claimed).into_response(),
+        Ok(None) => StatusCode::NO_CONTENT.into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Claim failed").into_response(),
+    }
+}
+
+/// Start the next job `worker_id` can run and describe it for the worker.
+/// `Ok(None)` when nothing fits; `Err` when the job could not be handed
+/// over, in which case it is finalized as failed. Shared by the HTTP and
+/// NATS transports.
+pub async fn claim_next(state: &AppState, worker_id: &str) -> crate::Result<Option<ClaimedJob>> {
+    let Some(job) = state.scheduler.try_start_next_for(worker_id).await else {
+        return Ok(None);
+    };
+
+    match claimed(state, job).await {
+        Ok(claimed) => Ok(Some(claimed)),
+        Err((job, e)) => {
+            tracing::error!("Handing job {} to worker {} failed: {}", job.id, worker_id, e);
+            // Finalized like any other failed job.
+            let result = JobResult {
+                success: false,
+                message: format!("Dispatch failed: {}", e),
+                prover_output: String::new(),
+                duration_ms: 0,
+                verified_files: vec![],
+                failed_files: vec![],
+                confidence: None,
+                axioms: None,
+                prover_version: None,
+                prover_args: vec![],
+                execution_path: None,
+            };
+            state.scheduler.submit_remote_result(worker_id, job.id, result).await;
+            Err(e)
+        }
+    }
+}
+
+/// Mark the job running in the store and look up its repository.
+async fn claimed(state: &AppState, job: ProofJob) -> Result<ClaimedJob, (ProofJob, crate::Error)> {
+    let lookup = async {
+        if let Some(mut record) = state.store.get_job(job.id).await? {
+            record.status = JobStatus::Running;
+            record.started_at = job.started_at;
+            state.store.update_job(&record).await?;
+        }
+        state
+            .store
+            .get_repository(job.repo_id)
+            .await?
+            .ok_or_else(|| crate::Error::RepoNotFound(job.repo_id.to_string()))
+    };
+    let outcome = lookup.await;
+    match outcome {
+        Ok(repo) => Ok(ClaimedJob {
+            repository: ClaimedRepository {
+                platform: format!("{:?}", repo.platform),
+                owner: repo.owner,
+                name: repo.name,
+            },
+            job,
+        }),
+        Err(e) => Err((job, e)),
+    }
+}
+
+async fn report_result(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path((id, job_id)): Path<(String, String)>,
+    Json(result): Json<JobResult>,
+) -> Response {
+    if !auth.allows_instance(TokenScope::Trigger) {
+        return forbidden();
+    }
+    let Ok(job_id) = Uuid::parse_str(&job_id) else {
+        return (StatusCode::BAD_REQUEST, "Invalid job id").into_response();
+    };
+    if state.scheduler.submit_remote_result(&id, JobId(job_id), result).await {
+        StatusCode::ACCEPTED.into_response()
+    } else {
+        (StatusCode::CONFLICT, "Job is not claimed by this worker").into_response()
+    }
+}
@@ -3,7 +3,10 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! GraphQL schema and resolvers
 
-use async_graphql::{Context, EmptySubscription, Object, Schema, SimpleObject, ID};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{
+    ComplexObject, Context, EmptySubscription, ErrorExtensions, Object, Schema, SimpleObject, ID,
+};
 use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -13,11 +16,17 @@
     ProverKind as CoreProverKind,
     TacticSuggestion as CoreSuggestion,
 };
+use crate::api::auth::{
+    can_view, require_instance_scope, require_scope, tenant, AuthContext, TokenScope,
+};
+use crate::api::confirm::{self, Confirmation, ConfirmationPolicy, Gate};
+use crate::api::loaders::{RecentJobs, RepoById, ResultOf, StoreLoader};
+use crate::audit::AuditAction;
 use crate::dispatcher::echidna_client::ProverStatus as CoreProverStatus;
-use crate::scheduler::{JobPriority, JobScheduler};
+use crate::scheduler::{JobId, JobPriority, JobScheduler};
 use crate::store::models::{
-    ProofJobRecord, Repository as StoreRepository, TacticOutcomeRecord,
-    goal_fingerprint,
+    AnnouncementRecord, AuditFilter, AuditRecord, JobFilter, JobOrder, ProofItemRecord, ProofJobRecord, ProofResultRecord, QuarantinedProof as QuarantinedProofRecord, Repository as StoreRepository,
+    TacticOutcomeRecord, Visibility as StoreVisibility, goal_fingerprint,
 };
 use crate::store::Store;
 
@@ -26,8 +35,10 @@
 
 /// Create the GraphQL schema
 pub fn create_schema(state: GraphQLState) -> EchidnabotSchema {
+    let loader = DataLoader::new(StoreLoader::new(state.store.clone()), tokio::spawn);
     Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(state)
+        .data(loader)
         .finish()
 }
 
@@ -89,6 +100,22 @@ pub enum ProofStatus {
     Unknown,
 }
 
+/// How a file or theorem of a job fared
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ProofItemStatus {
+    Verified,
+    Failed,
+    /// Accepted with `Admitted`, `sorry` or the like standing in for a proof
+    Admitted,
+}
+
+/// Repository visibility to callers without an API token
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
 /// Prover availability status
 #[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ProverStatus {
@@ -100,6 +127,7 @@ pub enum ProverStatus {
 
 /// Repository information
 #[derive(SimpleObject, Clone)]
+#[graphql(complex)]
 pub struct Repository {
     pub id: ID,
     pub platform: Platform,
@@ -107,10 +135,35 @@ pub struct Repository {
     pub name: String,
     pub enabled_provers: Vec<ProverKind>,
     pub last_checked_commit: Option<String>,
+    /// Branch patterns verified on push; empty means the default branch.
+    pub branch_include: Vec<String>,
+    pub branch_exclude: Vec<String>,
+    /// Path globs whose proof files are verified; empty means all.
+    pub path_include: Vec<String>,
+    pub path_exclude: Vec<String>,
+    pub visibility: Visibility,
+    /// Contributes anonymized examples to the training export.
+    pub training_export: bool,
+    /// Cron schedule for full re-verification of the default branch, in
+    /// the repository's time zone.
+    pub reverify_cron: Option<String>,
+    /// IANA time zone of schedules and blackout windows; unset inherits
+    /// the org's or instance's (see `crate::calendar`).
+    pub timezone: Option<String>,
+    /// Required prover versions; see `crate::toolchain`.
+    pub prover_pins: Vec<ProverPin>,
+}
+
+/// A required prover version, e.g. Lean `4.7.0`. `4.7` accepts any 4.7.x.
+#[derive(SimpleObject, Clone)]
+pub struct ProverPin {
+    pub prover: ProverKind,
+    pub version: String,
 }
 
 /// Proof job information
 #[derive(SimpleObject, Clone)]
+#[graphql(complex)]
 pub struct ProofJob {
     pub id: ID,
     pub repo_id: ID,
@@ -120,15 +173,166 @@ pub struct ProofJob {
     pub queued_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
+    /// The job this one re-runs, if it was created by `retryJob`.
+    pub retry_of: Option<ID>,
+    /// Base commit of the PR, for PR jobs compared against it.
+    pub base_sha: Option<String>,
+    /// Files discovery resolved for a job without a file list.
+    pub resolved_files: Vec<String>,
+    /// Project roots of `resolved_files`; `""` is the repository root.
+    pub project_roots: Vec<String>,
+    /// Why the job's commit left its branch (force-push, deletion), if it did.
+    pub orphaned: Option<String>,
 }
 
 /// Proof verification result
 #[derive(SimpleObject, Clone)]
 pub struct ProofResult {
+    pub success: bool,
     pub status: ProofStatus,
     pub message: String,
     pub prover_output: String,
     pub duration_ms: i32,
+    pub verified_files: Vec<String>,
+    pub failed_files: Vec<String>,
+    /// Version the prover reported, when it could be discovered
+    pub prover_version: Option<String>,
+    /// The repository's `[provers.<slug>] flags` the prover ran with
+    pub prover_args: Vec<String>,
+    /// `echidna`, `local` or `local-fallback` (checked locally because
+    /// ECHIDNA could not be reached); `null` when nothing was checked
+    pub execution_path: Option<String>,
+}
+
+#[ComplexObject]
+impl Repository {
+    /// The repository's jobs, newest first. Batched across repositories.
+    async fn jobs(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Option<Vec<ProofJob>>> {
+        let key = RecentJobs {
+            repo_id: parse_id(&self.id)?,
+            limit: limit.unwrap_or(50).max(1) as usize,
+        };
+        let jobs = loader(ctx)?.load_one(key).await?.unwrap_or_default();
+        Ok(Some(jobs.into_iter().map(ProofJob::from).collect()))
+    }
+}
+
+#[ComplexObject]
+impl ProofJob {
+    /// The job's result; `null` until it finishes.
+    async fn result(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ProofResult>> {
+        let key = ResultOf(JobId(parse_id(&self.id)?));
+        Ok(loader(ctx)?.load_one(key).await?.map(ProofResult::from))
+    }
+
+    /// The repository the job belongs to.
+    async fn repository(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Repository>> {
+        let key = RepoById(parse_id(&self.repo_id)?);
+        let repo = loader(ctx)?.load_one(key).await?;
+        Ok(repo.filter(|repo| can_view(ctx, repo)).map(Repository::from))
+    }
+
+    /// The job's files and the theorems its prover output names, each
+    /// file followed by its theorems; optionally only those with `status`.
+    /// Empty until it finishes.
+    async fn items(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<ProofItemStatus>,
+    ) -> async_graphql::Result<Vec<ProofItem>> {
+        let state = ctx.data::<GraphQLState>()?;
+        let items = state
+            .store
+            .list_proof_items(JobId(parse_id(&self.id)?))
+            .await
+            .map_err(store_error)?;
+        Ok(items
+            .into_iter()
+            .map(ProofItem::from)
+            .filter(|item| status.is_none_or(|status| item.status == status))
+            .collect())
+    }
+}
+
+/// One file or theorem of a job's result
+#[derive(SimpleObject, Clone)]
+pub struct ProofItem {
+    pub file_path: String,
+    /// `null` for the file itself.
+    pub theorem: Option<String>,
+    pub line: Option<i32>,
+    pub status: ProofItemStatus,
+    pub duration_ms: Option<i64>,
+}
+
+impl From<ProofItemRecord> for ProofItem {
+    fn from(r: ProofItemRecord) -> Self {
+        use crate::proof_items::ItemStatus;
+        Self {
+            file_path: r.file_path,
+            theorem: r.theorem,
+            line: r.line.map(|l| l as i32),
+            status: match r.status {
+                ItemStatus::Verified => ProofItemStatus::Verified,
+                ItemStatus::Failed => ProofItemStatus::Failed,
+                ItemStatus::Admitted => ProofItemStatus::Admitted,
+            },
+            duration_ms: r.duration_ms,
+        }
+    }
+}
+
+/// Why a job was reported the way it was (see `crate::decision`)
+#[derive(SimpleObject, Clone)]
+pub struct JobExplanation {
+    pub job_id: ID,
+    /// `verifier`, `advisor`, `consultant` or `regulator`; `null` until
+    /// the job reports.
+    pub mode: Option<String>,
+    /// `directive`, `repository` or `daemon_default`
+    pub mode_source: Option<String>,
+    /// The repository has a directive file
+    pub directive: bool,
+    /// Confidence level of the result, 1–5
+    pub trust_level: Option<i32>,
+    pub cache_hits: Vec<String>,
+    /// Policies that changed the outcome, in the order they applied
+    pub policies: Vec<PolicyHit>,
+    /// Check run conclusion, e.g. `failure`
+    pub conclusion: Option<String>,
+    pub blocks_merge: bool,
+}
+
+/// A policy that applied to a job, and what it did
+#[derive(SimpleObject, Clone)]
+pub struct PolicyHit {
+    /// e.g. `budgets`, `axioms`, `coverage_gate`
+    pub policy: String,
+    pub effect: String,
+}
+
+impl JobExplanation {
+    fn new(job_id: Uuid, trace: crate::decision::DecisionTrace) -> Self {
+        Self {
+            job_id: ID::from(job_id.to_string()),
+            mode: trace.mode.map(|m| m.to_string().to_lowercase()),
+            mode_source: trace.mode_source.map(|s| s.as_str().to_string()),
+            directive: trace.directive,
+            trust_level: trace.trust_level.map(i32::from),
+            cache_hits: trace.cache_hits,
+            policies: trace
+                .policies
+                .into_iter()
+                .map(|p| PolicyHit { policy: p.policy, effect: p.effect })
+                .collect(),
+            conclusion: trace.conclusion,
+            blocks_merge: trace.blocks_merge,
+        }
+    }
 }
 
 /// Prover information
@@ -175,6 +379,228 @@ fn from(r: TacticOutcomeRecord) -> Self {
     }
 }
 
+/// A proof file whose failures no longer fail checks
+#[derive(SimpleObject, Clone)]
+pub struct QuarantinedProof {
+    pub repo_id: ID,
+    pub file_path: String,
+    pub reason: Option<String>,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+impl From<QuarantinedProofRecord> for QuarantinedProof {
+    fn from(q: QuarantinedProofRecord) -> Self {
+        Self {
+            repo_id: ID::from(q.repo_id.to_string()),
+            file_path: q.file_path,
+            reason: q.reason,
+            quarantined_at: q.created_at,
+        }
+    }
+}
+
+/// The maintenance banner on bot comments and check summaries
+#[derive(SimpleObject, Clone)]
+pub struct Announcement {
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    /// `None` until cleared
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<AnnouncementRecord> for Announcement {
+    fn from(a: AnnouncementRecord) -> Self {
+        Self {
+            message: a.message,
+            created_at: a.created_at,
+            expires_at: a.expires_at,
+        }
+    }
+}
+
+/// One entry of the audit log of bot actions
+#[derive(SimpleObject, Clone)]
+pub struct AuditEntry {
+    pub id: ID,
+    pub at: DateTime<Utc>,
+    /// `echidnabot`, `token:<id>`, `user:<login>` or `cli`
+    pub actor: String,
+    /// e.g. `check_run.created`, `comment.posted`, `repo.settings_changed`
+    pub action: String,
+    pub repo_id: Option<ID>,
+    /// `owner/name` at the time
+    pub repo: Option<String>,
+    /// e.g. `PR #12`
+    pub target: Option<String>,
+    /// SHA-256 (hex) of what was sent
+    pub payload_digest: String,
+}
+
+impl From<AuditRecord> for AuditEntry {
+    fn from(a: AuditRecord) -> Self {
+        Self {
+            id: ID::from(a.id.to_string()),
+            at: a.at,
+            actor: a.actor,
+            action: a.action,
+            repo_id: a.repo_id.map(|id| ID::from(id.to_string())),
+            repo: a.repo,
+            target: a.target,
+            payload_digest: a.payload_digest,
+        }
+    }
+}
+
+/// Period covered by `instanceStats`
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum StatsPeriod {
+    /// The last 24 hours
+    Day,
+    /// The last 7 days
+    Week,
+    /// The last 30 days
+    Month,
+}
+
+/// Usage of the instance over a period, for capacity planning
+#[derive(SimpleObject, Clone)]
+pub struct InstanceStats {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Concurrent job slots (`[scheduler] max_concurrent`)
+    pub capacity: i32,
+    /// Jobs queued in the period
+    pub jobs: i32,
+    pub failed: i32,
+    pub jobs_per_day: f64,
+    /// Mean time from queued to started
+    pub avg_wait_ms: Option<f64>,
+    /// Fraction of the job slots' time spent running jobs
+    pub utilization: f64,
+    /// `sorry`, `Admitted`, new axioms, ... in the files the jobs checked
+    pub escape_hatches: i32,
+    /// Jobs per UTC day, oldest first; days without jobs are left out
+    pub daily: Vec<DailyJobs>,
+    /// Busiest first
+    pub provers: Vec<ProverUsage>,
+    /// Repositories with the most run time, busiest first
+    pub top_repos: Vec<RepoUsage>,
+}
+
+/// Jobs queued on one day
+#[derive(SimpleObject, Clone)]
+pub struct DailyJobs {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub jobs: i32,
+}
+
+/// One prover's share of the period
+#[derive(SimpleObject, Clone)]
+pub struct ProverUsage {
+    pub prover: String,
+    pub jobs: i32,
+    pub failed: i32,
+    pub avg_wait_ms: Option<f64>,
+    /// Total run time of its finished jobs
+    pub busy_ms: i64,
+    pub utilization: f64,
+}
+
+/// One repository's use of the period
+#[derive(SimpleObject, Clone)]
+pub struct RepoUsage {
+    pub repo_id: ID,
+    /// `owner/name`; `null` for a deleted repository
+    pub repo: Option<String>,
+    pub jobs: i32,
+    pub busy_ms: i64,
+    /// `sorry`, `Admitted`, new axioms, ... in the files its jobs checked
+    pub escape_hatches: i32,
+}
+
+/// A queue wait objective over one rolling window (`[slo]`)
+#[derive(SimpleObject, Clone)]
+pub struct QueueSlo {
+    /// `low`, `normal`, `high` (PR checks) or `critical` (manual runs)
+    pub priority: String,
+    /// Longest a job may wait in the queue before it starts
+    pub limit_secs: i64,
+    pub window_secs: i64,
+    /// Compliance required
+    pub target: f64,
+    /// Jobs that started, or are still queued past the limit
+    pub jobs: i32,
+    /// Of them, how many started within the limit
+    pub within: i32,
+    /// `within / jobs`; `null` without jobs
+    pub compliance: Option<f64>,
+    pub breached: bool,
+    pub avg_wait_ms: Option<f64>,
+    pub max_wait_ms: Option<f64>,
+}
+
+impl From<crate::slo::SloStatus> for QueueSlo {
+    fn from(s: crate::slo::SloStatus) -> Self {
+        Self {
+            priority: crate::slo::priority_name(s.priority).to_string(),
+            limit_secs: s.limit_secs as i64,
+            window_secs: s.window_secs as i64,
+            target: s.target,
+            jobs: s.jobs as i32,
+            within: s.within as i32,
+            compliance: s.compliance,
+            breached: s.breached(),
+            avg_wait_ms: s.avg_wait_ms,
+            max_wait_ms: s.max_wait_ms,
+        }
+    }
+}
+
+impl From<crate::stats::InstanceStats> for InstanceStats {
+    fn from(s: crate::stats::InstanceStats) -> Self {
+        Self {
+            since: s.since,
+            until: s.until,
+            capacity: s.capacity as i32,
+            jobs: s.jobs as i32,
+            failed: s.failed as i32,
+            jobs_per_day: s.jobs_per_day,
+            avg_wait_ms: s.avg_wait_ms,
+            utilization: s.utilization,
+            escape_hatches: s.escape_hatches as i32,
+            daily: s
+                .daily
+                .into_iter()
+                .map(|(day, jobs)| DailyJobs { day, jobs: jobs as i32 })
+                .collect(),
+            provers: s
+                .provers
+                .into_iter()
+                .map(|p| ProverUsage {
+                    prover: p.prover,
+                    jobs: p.jobs as i32,
+                    failed: p.failed as i32,
+                    avg_wait_ms: p.avg_wait_ms,
+                    busy_ms: p.busy_ms as i64,
+                    utilization: p.utilization,
+                })
+                .collect(),
+            top_repos: s
+                .top_repos
+                .into_iter()
+                .map(|r| RepoUsage {
+                    repo_id: ID::from(r.repo_id.to_string()),
+                    repo: r.name,
+                    jobs: r.jobs as i32,
+                    busy_ms: r.busy_ms as i64,
+                    escape_hatches: r.escape_hatches as i32,
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Input for recording a tactic outcome from an external agent
 #[derive(async_graphql::InputObject)]
 pub struct RecordTacticOutcomeInput {
@@ -192,6 +618,38 @@ pub struct RecordTacticOutcomeInput {
     pub job_id: Option<ID>,
 }
 
+/// Filter for `searchJobs`; unset fields match everything
+#[derive(async_graphql::InputObject, Default)]
+pub struct JobSearchFilter {
+    pub repo_id: Option<ID>,
+    pub status: Option<JobStatus>,
+    pub prover: Option<ProverKind>,
+    /// Leading characters of the commit SHA
+    pub commit_prefix: Option<String>,
+    /// Queued at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Queued before
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Order of `searchJobs` results, by queue time
+#[derive(async_graphql::Enum, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum JobSortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// One page of `searchJobs` results
+#[derive(SimpleObject, Clone)]
+pub struct JobSearchPage {
+    pub jobs: Vec<ProofJob>,
+    /// Jobs matching the filter, across all pages
+    pub total_count: i32,
+    /// Whether a page follows this one
+    pub has_next_page: bool,
+}
+
 // =============================================================================
 // Query Root
 // =============================================================================
@@ -207,68 +665,245 @@ async fn repository(
         platform: Platform,
         owner: String,
         name: String,
-    ) -> Option<Repository> {
-        let state = ctx.data::<GraphQLState>().ok()?;
+    ) -> async_graphql::Result<Option<Repository>> {
+        let state = ctx.data::<GraphQLState>()?;
         let repo = state
             .store
             .get_repository_by_name(map_platform(platform), &owner, &name)
             .await
-            .ok()??;
-        Some(repo.into())
+            .map_err(store_error)?;
+        Ok(repo.filter(|repo| can_view(ctx, repo)).map(Repository::from))
     }
 
-    /// List all registered repositories
+    /// List all registered repositories. A repository that fails to load
+    /// is `null` in the list, with an error at its index; the others
+    /// still resolve.
     async fn repositories(
         &self,
         ctx: &Context<'_>,
         platform: Option<Platform>,
-    ) -> Vec<Repository> {
-        let state = match ctx.data::<GraphQLState>() {
-            Ok(state) => state,
-            Err(_) => return vec![],
-        };
-        let repos = state
+    ) -> async_graphql::Result<Option<Vec<Option<Repository>>>> {
+        let state = ctx.data::<GraphQLState>()?;
+        let rows = state
             .store
-            .list_repositories(platform.map(map_platform))
+            .list_repositories_partial(platform.map(map_platform))
             .await
-            .unwrap_or_default();
-        repos.into_iter().map(Repository::from).collect()
+            .map_err(store_error)?;
+        let mut repos = Vec::new();
+        for row in rows {
+            match row {
+                Ok(repo) if can_view(ctx, &repo) => repos.push(Some(repo.into())),
+                Ok(_) => {}
+                // An unreadable row cannot be checked for visibility.
+                Err(_) if restricted(ctx) => {}
+                Err(e) => {
+                    report_item_error(ctx, repos.len(), e);
+                    repos.push(None);
+                }
+            }
+        }
+        Ok(Some(repos))
     }
 
     /// Get a proof job by ID
-    async fn job(&self, ctx: &Context<'_>, id: ID) -> Option<ProofJob> {
-        let state = ctx.data::<GraphQLState>().ok()?;
-        let job_id = Uuid::parse_str(id.as_str()).ok()?;
-        let job = state
-            .store
-            .get_job(crate::scheduler::JobId(job_id))
-            .await
-            .ok()??;
-        Some(job.into())
+    async fn job(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<ProofJob>> {
+        let job_id = parse_id(&id)?;
+        let job = loader(ctx)?.load_one(JobId(job_id)).await?;
+        match job {
+            Some(job) if repo_visible(ctx, job.repo_id).await => Ok(Some(job.into())),
+            _ => Ok(None),
+        }
     }
 
-    /// List jobs for a repository
+    /// The decision inputs behind a job's reported conclusion: mode and
+    /// its source, policies triggered, cache hits, trust level. `null`
+    /// for an unknown job or one that ran before traces were recorded.
+    async fn explain_job(
+        &self,
+        ctx: &Context<'_>,
+        job_id: ID,
+    ) -> async_graphql::Result<Option<JobExplanation>> {
+        let job_id = parse_id(&job_id)?;
+        match loader(ctx)?.load_one(JobId(job_id)).await? {
+            Some(job) if repo_visible(ctx, job.repo_id).await => {
+                Ok(job.payload.decision.map(|trace| JobExplanation::new(job.id, trace)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// List jobs for a repository, newest first. As with `repositories`,
+    /// a job that fails to load is `null` with an error at its index.
     async fn jobs_for_repo(
         &self,
         ctx: &Context<'_>,
         repo_id: ID,
         limit: Option<i32>,
-    ) -> Vec<ProofJob> {
-        let state = match ctx.data::<GraphQLState>() {
-            Ok(state) => state,
-            Err(_) => return vec![],
+    ) -> async_graphql::Result<Option<Vec<Option<ProofJob>>>> {
+        let state = ctx.data::<GraphQLState>()?;
+        let repo_uuid = parse_id(&repo_id)?;
+        if !repo_visible(ctx, repo_uuid).await {
+            return Ok(Some(vec![]));
+        }
+        let limit = limit.unwrap_or(50).max(1) as usize;
+        let rows = state
+            .store
+            .list_jobs_for_repo_partial(repo_uuid, limit)
+            .await
+            .map_err(store_error)?;
+        let jobs = rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| match row {
+                Ok(job) => Some(ProofJob::from(job)),
+                Err(e) => {
+                    report_item_error(ctx, index, e);
+                    None
+                }
+            })
+            .collect();
+        Ok(Some(jobs))
+    }
+
+    /// Jobs matching `filter`, newest first unless `order` says otherwise,
+    /// `limit` (default 50, at most 500) at a time from `offset`. Searching
+    /// across repositories needs a token; a public caller must name a
+    /// repository it can see.
+    async fn search_jobs(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: JobSearchFilter,
+        #[graphql(default)] order: JobSortOrder,
+        offset: Option<i32>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Option<JobSearchPage>> {
+        let state = ctx.data::<GraphQLState>()?;
+        let filter = JobFilter {
+            repo_id: filter.repo_id.as_ref().map(parse_id).transpose()?,
+            owner: tenant(ctx),
+            status: filter.status.map(map_job_status_to_core),
+            prover: filter.prover.map(map_prover_kind_to_core),
+            commit_prefix: filter.commit_prefix.filter(|p| !p.is_empty()),
+            since: filter.since,
+            until: filter.until,
         };
-        let repo_uuid = match Uuid::parse_str(repo_id.as_str()) {
-            Ok(id) => id,
-            Err(_) => return vec![],
+        match filter.repo_id {
+            Some(repo_id) if !repo_visible(ctx, repo_id).await => {
+                return Ok(Some(JobSearchPage { jobs: vec![], total_count: 0, has_next_page: false }));
+            }
+            Some(_) => {}
+            None => require_scope(ctx, TokenScope::Read)?,
+        }
+        let order = match order {
+            JobSortOrder::NewestFirst => JobOrder::NewestFirst,
+            JobSortOrder::OldestFirst => JobOrder::OldestFirst,
         };
-        let limit = limit.unwrap_or(50).max(1) as usize;
+        let offset = offset.unwrap_or(0).max(0) as usize;
+        let limit = limit.unwrap_or(50).clamp(1, 500) as usize;
         let jobs = state
             .store
-            .list_jobs_for_repo(repo_uuid, limit)
+            .search_jobs(&filter, order, offset, limit)
+            .await
+            .map_err(store_error)?;
+        let total = state.store.count_jobs(&filter).await.map_err(store_error)?;
+        Ok(Some(JobSearchPage {
+            has_next_page: ((offset + jobs.len()) as u64) < total,
+            jobs: jobs.into_iter().map(ProofJob::from).collect(),
+            total_count: total.min(i32::MAX as u64) as i32,
+        }))
+    }
+
+    /// Proof files quarantined for a repository
+    async fn quarantined_proofs(
+        &self,
+        ctx: &Context<'_>,
+        repo_id: ID,
+    ) -> async_graphql::Result<Option<Vec<QuarantinedProof>>> {
+        let state = ctx.data::<GraphQLState>()?;
+        let repo_uuid = parse_id(&repo_id)?;
+        if !repo_visible(ctx, repo_uuid).await {
+            return Ok(Some(vec![]));
+        }
+        let proofs = state
+            .store
+            .list_quarantined_proofs(repo_uuid)
+            .await
+            .map_err(store_error)?;
+        Ok(Some(proofs.into_iter().map(QuarantinedProof::from).collect()))
+    }
+
+    /// The active maintenance announcement, if any
+    async fn announcement(&self, ctx: &Context<'_>) -> Option<Announcement> {
+        let state = ctx.data::<GraphQLState>().ok()?;
+        crate::announcement::active(state.store.as_ref())
+            .await
+            .map(Announcement::from)
+    }
+
+    /// Audit log entries, newest first; all filters are optional.
+    /// Requires an admin token; a tenant's token sees the entries about its
+    /// repositories.
+    #[allow(clippy::too_many_arguments)]
+    async fn audit_log(
+        &self,
+        ctx: &Context<'_>,
+        repo_id: Option<ID>,
+        action: Option<String>,
+        actor: Option<String>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<Option<Vec<AuditEntry>>> {
+        require_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let filter = AuditFilter {
+            repo_id: repo_id.as_ref().map(parse_id).transpose()?,
+            owner: tenant(ctx),
+            action,
+            actor,
+            since,
+            until,
+        };
+        let limit = limit.unwrap_or(100).clamp(1, 1000) as usize;
+        let entries = state.store.list_audit(&filter, limit).await.map_err(store_error)?;
+        Ok(Some(entries.into_iter().map(AuditEntry::from).collect()))
+    }
+
+    /// Jobs per day, queue wait, utilization per prover and the `top`
+    /// (default 10) repositories by run time over `period` (default
+    /// `WEEK`). Requires an admin token.
+    async fn instance_stats(
+        &self,
+        ctx: &Context<'_>,
+        period: Option<StatsPeriod>,
+        top: Option<i32>,
+    ) -> async_graphql::Result<InstanceStats> {
+        require_instance_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let period = match period.unwrap_or(StatsPeriod::Week) {
+            StatsPeriod::Day => crate::stats::Period::Day,
+            StatsPeriod::Week => crate::stats::Period::Week,
+            StatsPeriod::Month => crate::stats::Period::Month,
+        };
+        let capacity = state.scheduler.stats().await.max_concurrent;
+        let top = top.unwrap_or(10).clamp(1, 100) as usize;
+        let stats = crate::stats::collect(state.store.as_ref(), period, capacity, top)
             .await
-            .unwrap_or_default();
-        jobs.into_iter().map(ProofJob::from).collect()
+            .map_err(store_error)?;
+        Ok(stats.into())
+    }
+
+    /// Queue wait compliance for each `[slo]` objective and window,
+    /// highest priority first.
+    async fn queue_slo(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<QueueSlo>> {
+        // Waits are counted over every tenant's jobs.
+        require_instance_scope(ctx, TokenScope::Read)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let config = state.scheduler.slo();
+        let statuses = crate::slo::evaluate(state.store.as_ref(), &config, Utc::now())
+            .await
+            .map_err(store_error)?;
+        Ok(statuses.into_iter().map(QueueSlo::from).collect())
     }
 
     /// List available provers
@@ -317,13 +952,13 @@ async fn tactic_outcomes(
         prover: ProverKind,
         goal_fingerprint: String,
         limit: Option<i32>,
-    ) -> Vec<TacticOutcome> {
-        let state = match ctx.data::<GraphQLState>() {
-            Ok(s) => s,
-            Err(_) => return vec![],
-        };
+    ) -> async_graphql::Result<Option<Vec<TacticOutcome>>> {
+        // Tactic history spans repositories, so it is never public, nor
+        // seen by a tenant's token.
+        require_instance_scope(ctx, TokenScope::Read)?;
+        let state = ctx.data::<GraphQLState>()?;
         let limit = limit.unwrap_or(50).max(1) as usize;
-        state
+        let outcomes = state
             .store
             .list_tactic_outcomes_by_fingerprint(
                 map_prover_kind_to_core(prover),
@@ -331,10 +966,8 @@ async fn tactic_outcomes(
                 limit,
             )
             .await
-            .unwrap_or_default()
-            .into_iter()
-            .map(TacticOutcome::from)
-            .collect()
+            .map_err(store_error)?;
+        Ok(Some(outcomes.into_iter().map(TacticOutcome::from).collect()))
     }
 
     /// List recorded tactic outcomes for a specific (prover, tactic) pair
@@ -345,23 +978,118 @@ async fn tactic_outcomes_by_tactic(
         prover: ProverKind,
         tactic: String,
         limit: Option<i32>,
-    ) -> Vec<TacticOutcome> {
-        let state = match ctx.data::<GraphQLState>() {
-            Ok(s) => s,
-            Err(_) => return vec![],
-        };
+    ) -> async_graphql::Result<Option<Vec<TacticOutcome>>> {
+        require_instance_scope(ctx, TokenScope::Read)?;
+        let state = ctx.data::<GraphQLState>()?;
         let limit = limit.unwrap_or(200).max(1) as usize;
-        state
+        let outcomes = state
             .store
             .list_tactic_outcomes_by_tactic(map_prover_kind_to_core(prover), &tactic, limit)
             .await
-            .unwrap_or_default()
-            .into_iter()
-            .map(TacticOutcome::from)
-            .collect()
+            .map_err(store_error)?;
+        Ok(Some(outcomes.into_iter().map(TacticOutcome::from).collect()))
     }
 }
 
+/// Whether the caller may see the repository with id `repo_id`. Only
+/// public and tenant-scoped principals need the lookup; for them unknown
+/// ids are hidden.
+async fn repo_visible(ctx: &Context<'_>, repo_id: Uuid) -> bool {
+    if !restricted(ctx) {
+        return true;
+    }
+    let Ok(loader) = loader(ctx) else {
+        return false;
+    };
+    match loader.load_one(RepoById(repo_id)).await {
+        Ok(Some(repo)) => can_view(ctx, &repo),
+        _ => false,
+    }
+}
+
+/// The schema's batching loader; see [`crate::api::loaders`].
+fn loader<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a DataLoader<StoreLoader>> {
+    ctx.data::<DataLoader<StoreLoader>>()
+}
+
+/// Record a mutation in the audit log under the calling token.
+async fn audit(
+    ctx: &Context<'_>,
+    state: &GraphQLState,
+    action: AuditAction,
+    repo: Option<&StoreRepository>,
+    target: Option<String>,
+    payload: impl AsRef<[u8]>,
+) {
+    crate::audit::record(state.store.as_ref(), &audit_actor(ctx), action, repo, target, payload).await;
+}
+
+fn audit_actor(ctx: &Context<'_>) -> String {
+    ctx.data_opt::<AuthContext>()
+        .map_or_else(|| "anonymous".to_string(), AuthContext::audit_actor)
+}
+
+/// Whether the caller sees only some repositories: public ones, or its
+/// tenant's.
+fn restricted(ctx: &Context<'_>) -> bool {
+    ctx.data_opt::<AuthContext>()
+        .is_some_and(|auth| auth.public_only || auth.tenant.is_some())
+}
+
+/// The repository with id `repo_id`, if the caller may administer it;
+/// another tenant's repository is not found, like an unknown one.
+async fn tenant_repository(
+    ctx: &Context<'_>,
+    state: &GraphQLState,
+    repo_id: Uuid,
+) -> async_graphql::Result<StoreRepository> {
+    state
+        .store
+        .get_repository(repo_id)
+        .await
+        .map_err(store_error)?
+        .filter(|repo| can_view(ctx, repo))
+        .ok_or_else(|| coded_error("NOT_FOUND", "Repository not found"))
+}
+
+// =============================================================================
+// Errors
+// =============================================================================
+//
+// Queries fail per field: a failing field is `null` with an error whose
+// `extensions.code` says why, and the rest of the query still resolves.
+// Lists of repositories and jobs go further and null only the item that
+// failed to load.
+
+/// A field error with `extensions.code` set.
+fn coded_error(code: &'static str, message: impl Into<String>) -> async_graphql::Error {
+    async_graphql::Error::new(message.into()).extend_with(|_, e| e.set("code", code))
+}
+
+/// A store (or upstream) failure as a coded field error.
+pub(crate) fn store_error(e: crate::Error) -> async_graphql::Error {
+    let code = match &e {
+        crate::Error::Database(_) | crate::Error::Sqlx(_) => "STORE_UNAVAILABLE",
+        crate::Error::InvalidInput(_) | crate::Error::InvalidProver(_) => "BAD_USER_INPUT",
+        crate::Error::JobNotFound(_) | crate::Error::RepoNotFound(_) => "NOT_FOUND",
+        crate::Error::Echidna(_) | crate::Error::Http(_) | crate::Error::GitHub(_) => "UPSTREAM_UNAVAILABLE",
+        _ => "INTERNAL",
+    };
+    coded_error(code, e.to_string())
+}
+
+fn parse_id(id: &ID) -> async_graphql::Result<Uuid> {
+    Uuid::parse_str(id.as_str()).map_err(|_| coded_error("BAD_USER_INPUT", format!("Invalid ID: {}", id.as_str())))
+}
+
+/// Record `error` against item `index` of the list field being resolved,
+/// which then holds `null` there.
+fn report_item_error(ctx: &Context<'_>, index: usize, error: crate::Error) {
+    let mut error = ctx.set_error_path(store_error(error).into_server_error(ctx.item.pos));
+    error.path.push(async_graphql::PathSegment::Index(index));
+    ctx.add_error(error);
+}
+
 // =============================================================================
 // Mutation Root
 // =============================================================================
@@ -376,6 +1104,29 @@ pub struct RegisterRepoInput {
     pub name: String,
     pub webhook_secret: Option<String>,
     pub enabled_provers: Option<Vec<ProverKind>>,
+    /// Path globs (`**`, `*`, `?`) whose proof files are verified.
+    pub path_include: Option<Vec<String>>,
+    pub path_exclude: Option<Vec<String>>,
+    /// Defaults to `PRIVATE`.
+    pub visibility: Option<Visibility>,
+    /// Opt in to the training-example export. Defaults to `false`.
+    pub training_export: Option<bool>,
+    /// Cron schedule for full re-verification of the default branch, in
+    /// the repository's time zone.
+    pub reverify_cron: Option<String>,
+    /// IANA time zone, e.g. `Europe/London`.
+    pub timezone: Option<String>,
+    /// Required prover versions. A job whose toolchain reports another
+    /// version fails.
+    pub prover_pins: Option<Vec<ProverPinInput>>,
+}
+
+/// A required prover version
+#[derive(async_graphql::InputObject)]
+pub struct ProverPinInput {
+    pub prover: ProverKind,
+    /// e.g. `4.7.0`, or `4.7` for any 4.7.x
+    pub version: String,
 }
 
 /// Input for repository settings
@@ -386,8 +1137,93 @@ pub struct RepoSettingsInput {
     pub check_on_push: Option<bool>,
     pub check_on_pr: Option<bool>,
     pub auto_comment: Option<bool>,
+    /// Branch patterns (`*`, `?` wildcards) verified on push. An empty
+    /// list restores the default: the default branch only.
+    pub branch_include: Option<Vec<String>>,
+    pub branch_exclude: Option<Vec<String>>,
+    /// Path globs (`**`, `*`, `?`) whose proof files are verified. An
+    /// empty include list verifies every proof file again.
+    pub path_include: Option<Vec<String>>,
+    pub path_exclude: Option<Vec<String>>,
+    /// `PUBLIC` exposes status to callers without an API token.
+    pub visibility: Option<Visibility>,
+    /// Contribute anonymized (goal, tactic) pairs from passing jobs to
+    /// the training export (`[corpus.export]`).
+    pub training_export: Option<bool>,
+    /// Cron schedule (`0 3 * * *` = nightly, in the repository's time
+    /// zone) for full re-verification of the default branch. An empty
+    /// string removes it.
+    pub reverify_cron: Option<String>,
+    /// IANA time zone (`Europe/London`) of the cron schedule and blackout
+    /// windows. An empty string inherits the org's or instance's again.
+    pub timezone: Option<String>,
+    /// Required prover versions, replacing the current pins. An empty list
+    /// removes them all.
+    pub prover_pins: Option<Vec<ProverPinInput>>,
+}
+
+/// Which repositories `bulkUpdateRepoSettings` changes. Every field set
+/// must match; an empty filter matches all of them.
+#[derive(async_graphql::InputObject, Default)]
+pub struct RepoFilterInput {
+    pub platform: Option<Platform>,
+    /// Owner, exactly.
+    pub owner: Option<String>,
+    /// Pattern (`*`, `?`) matched against `owner/name`.
+    pub name: Option<String>,
+    /// Only repositories with this prover enabled.
+    pub prover: Option<ProverKind>,
+    /// Only monitored (`true`) or disabled (`false`) repositories.
+    pub enabled: Option<bool>,
 }
 
+/// One setting changed on one repository; values are JSON.
+#[derive(SimpleObject, Clone)]
+pub struct SettingChange {
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// A repository matched by a bulk update, as it is (or, in a dry run,
+/// would be) afterwards.
+#[derive(SimpleObject, Clone)]
+pub struct RepoSettingsChange {
+    pub repository: Repository,
+    /// Empty when the repository already had the requested settings.
+    pub changes: Vec<SettingChange>,
+}
+
+/// Outcome of `bulkUpdateRepoSettings`
+#[derive(SimpleObject, Clone)]
+pub struct BulkUpdateResult {
+    /// Nothing was stored; `repositories` is a preview.
+    pub dry_run: bool,
+    /// Set when the update waits for confirmation (see `[auth]
+    /// confirm_destructive`); nothing was stored.
+    pub confirmation: Option<Confirmation>,
+    /// Repositories the filter matched.
+    pub matched: i32,
+    /// Repositories changed (or that would be, in a dry run).
+    pub changed: i32,
+    pub repositories: Vec<RepoSettingsChange>,
+}
+
+/// Outcome of `purgeRepository`
+#[derive(SimpleObject, Clone)]
+pub struct PurgeRepositoryResult {
+    /// Set when the purge waits for confirmation; nothing was deleted.
+    pub confirmation: Option<Confirmation>,
+    pub purged: bool,
+    pub jobs: i32,
+    pub results: i32,
+    pub file_timings: i32,
+}
+
+// Every mutation starts with a `require_scope` gate: `trigger` for
+// work-enqueueing operations, `admin` for repository administration.
+// Those acting on one repository then look it up with
+// `tenant_repository`, which keeps a tenant's token to its own.
 #[Object]
 impl MutationRoot {
     /// Register a repository for monitoring
@@ -396,7 +1232,14 @@ async fn register_repository(
         ctx: &Context<'_>,
         input: RegisterRepoInput,
     ) -> async_graphql::Result<Repository> {
+        require_scope(ctx, TokenScope::Admin)?;
         let state = ctx.data::<GraphQLState>()?;
+        if let Some(tenant) = tenant(ctx).filter(|tenant| *tenant != input.owner) {
+            return Err(coded_error(
+                "FORBIDDEN",
+                format!("Forbidden: this token may only register repositories of '{}'", tenant),
+            ));
+        }
 
         let mut repo = StoreRepository::new(
             map_platform(input.platform),
@@ -407,12 +1250,25 @@ async fn register_repository(
         if let Some(provers) = input.enabled_provers {
             repo.enabled_provers = provers.into_iter().map(map_prover_kind_to_core).collect();
         }
+        repo.path_include = input.path_include.unwrap_or_default();
+        repo.path_exclude = input.path_exclude.unwrap_or_default();
+        if let Some(visibility) = input.visibility {
+            repo.visibility = map_visibility(visibility);
+        }
+        repo.training_export = input.training_export.unwrap_or(false);
+        repo.reverify_cron = input.reverify_cron.map(validated_cron).transpose()?.flatten();
+        repo.timezone = input.timezone.map(validated_timezone).transpose()?.flatten();
+        if let Some(pins) = input.prover_pins {
+            repo.prover_pins = validated_pins(pins)?;
+        }
 
         state
             .store
             .create_repository(&repo)
             .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            .map_err(store_error)?;
+        let payload = crate::audit::settings_payload(&repo);
+        audit(ctx, state, AuditAction::RepoRegistered, Some(&repo), None, payload).await;
         Ok(repo.into())
     }
 
@@ -424,15 +1280,9 @@ async fn trigger_check(
         commit_sha: Option<String>,
         provers: Option<Vec<ProverKind>>,
     ) -> async_graphql::Result<ProofJob> {
+        require_scope(ctx, TokenScope::Trigger)?;
         let state = ctx.data::<GraphQLState>()?;
-        let repo_uuid = Uuid::parse_str(repo_id.as_str())
-            .map_err(|_| async_graphql::Error::new("Invalid repository ID"))?;
-        let repo = state
-            .store
-            .get_repository(repo_uuid)
-            .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?
-            .ok_or_else(|| async_graphql::Error::new("Repository not found"))?;
+        let repo = tenant_repository(ctx, state, parse_id(&repo_id)?).await?;
         let commit = commit_sha.unwrap_or_else(|| "HEAD".to_string());
 
         let provers = provers.unwrap_or_else(|| {
@@ -457,12 +1307,12 @@ async fn trigger_check(
                 .store
                 .create_job(&record)
                 .await
-                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                .map_err(store_error)?;
             let _ = state
                 .scheduler
                 .enqueue(job.clone())
                 .await
-                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                .map_err(store_error)?;
             if first_job.is_none() {
                 first_job = Some(job);
             }
@@ -472,6 +1322,29 @@ async fn trigger_check(
         Ok(ProofJobRecord::from(job).into())
     }
 
+    /// Re-run a finished job with the same parameters. The new job links
+    /// back to the original through `retryOf`; retrying while the same
+    /// repo/commit/prover is already queued is refused.
+    async fn retry_job(&self, ctx: &Context<'_>, job_id: ID) -> async_graphql::Result<ProofJob> {
+        require_scope(ctx, TokenScope::Trigger)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let job_uuid = parse_id(&job_id)?;
+        if tenant(ctx).is_some() {
+            let job = state.store.get_job(JobId(job_uuid)).await.map_err(store_error)?;
+            if let Some(job) = job {
+                tenant_repository(ctx, state, job.repo_id).await?;
+            }
+        }
+        let record = crate::scheduler::resubmit::retry_job(
+            state.store.as_ref(),
+            Some(state.scheduler.as_ref()),
+            JobId(job_uuid),
+        )
+        .await
+        .map_err(store_error)?;
+        Ok(record.into())
+    }
+
     /// Request ML-powered tactic suggestions
     async fn request_suggestions(
         &self,
@@ -480,12 +1353,13 @@ async fn request_suggestions(
         context: String,
         goal_state: String,
     ) -> async_graphql::Result<Vec<TacticSuggestion>> {
+        require_scope(ctx, TokenScope::Trigger)?;
         let state = ctx.data::<GraphQLState>()?;
         let suggestions = state
             .echidna
             .suggest_tactics(&map_prover_kind_to_core(prover), &context, &goal_state)
             .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            .map_err(store_error)?;
         Ok(suggestions.into_iter().map(map_suggestion).collect())
     }
 
@@ -496,41 +1370,170 @@ async fn update_repo_settings(
         repo_id: ID,
         settings: RepoSettingsInput,
     ) -> async_graphql::Result<Repository> {
+        require_scope(ctx, TokenScope::Admin)?;
         let state = ctx.data::<GraphQLState>()?;
-        let repo_uuid = Uuid::parse_str(repo_id.as_str())
-            .map_err(|_| async_graphql::Error::new("Invalid repository ID"))?;
-        let mut repo = state
-            .store
-            .get_repository(repo_uuid)
-            .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?
-            .ok_or_else(|| async_graphql::Error::new("Repository not found"))?;
+        let mut repo = tenant_repository(ctx, state, parse_id(&repo_id)?).await?;
 
-        if let Some(secret) = settings.webhook_secret {
-            repo.webhook_secret = Some(secret);
-        }
-        if let Some(provers) = settings.enabled_provers {
-            repo.enabled_provers = provers.into_iter().map(map_prover_kind_to_core).collect();
-        }
-        if let Some(check) = settings.check_on_push {
-            repo.check_on_push = check;
-        }
-        if let Some(check) = settings.check_on_pr {
-            repo.check_on_pr = check;
-        }
-        if let Some(auto_comment) = settings.auto_comment {
-            repo.auto_comment = auto_comment;
-        }
+        settings_patch(settings)?.apply(&mut repo);
         repo.updated_at = Utc::now();
 
         state
             .store
             .update_repository(&repo)
             .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            .map_err(store_error)?;
+        let payload = crate::audit::settings_payload(&repo);
+        audit(ctx, state, AuditAction::RepoSettingsChanged, Some(&repo), None, payload).await;
         Ok(repo.into())
     }
 
+    /// Apply the same settings to every repository `filter` matches, with
+    /// one audit entry per repository changed. `dryRun` previews the
+    /// changes without storing them. Destructive: see `confirmationToken`.
+    async fn bulk_update_repo_settings(
+        &self,
+        ctx: &Context<'_>,
+        filter: RepoFilterInput,
+        settings: RepoSettingsInput,
+        #[graphql(default = false)] dry_run: bool,
+        confirmation_token: Option<String>,
+    ) -> async_graphql::Result<BulkUpdateResult> {
+        require_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let patch = settings_patch(settings)?;
+        if patch.is_empty() {
+            return Err(coded_error("BAD_USER_INPUT", "No settings to change"));
+        }
+        let owner = match (tenant(ctx), filter.owner) {
+            (Some(tenant), Some(owner)) if owner != tenant => {
+                return Err(coded_error(
+                    "FORBIDDEN",
+                    format!("Forbidden: this token may only change repositories of '{}'", tenant),
+                ));
+            }
+            (Some(tenant), _) => Some(tenant),
+            (None, owner) => owner,
+        };
+        let filter = crate::bulk::RepoFilter {
+            platform: filter.platform.map(map_platform),
+            owner,
+            name: filter.name,
+            prover: filter.prover.map(map_prover_kind_to_core),
+            enabled: filter.enabled,
+        };
+
+        let mut plan = crate::bulk::plan(state.store.as_ref(), &filter, &patch)
+            .await
+            .map_err(store_error)?;
+        let mut confirmation = None;
+        if !dry_run {
+            let planned: Vec<_> = plan
+                .iter()
+                .filter(|c| !c.changes.is_empty())
+                .map(|c| (c.repo.id, &c.changes))
+                .collect();
+            let digest = crate::audit::digest(&serde_json::to_vec(&planned)?);
+            let names: Vec<String> = plan
+                .iter()
+                .filter(|c| !c.changes.is_empty())
+                .map(|c| c.repo.full_name())
+                .collect();
+            let impact = format!(
+                "Changes {} of {} matched repositories: {}",
+                names.len(),
+                plan.len(),
+                names.join(", ")
+            );
+            let gate = confirm::gate(
+                state.store.as_ref(),
+                ctx.data_opt::<ConfirmationPolicy>(),
+                &audit_actor(ctx),
+                "bulkUpdateRepoSettings",
+                digest,
+                impact,
+                confirmation_token.as_deref(),
+            )
+            .await?;
+            match gate {
+                Gate::Proceed => {
+                    crate::bulk::commit(state.store.as_ref(), &audit_actor(ctx), &mut plan)
+                        .await
+                        .map_err(store_error)?;
+                }
+                Gate::Pending(pending) => confirmation = Some(pending),
+            }
+        }
+        Ok(BulkUpdateResult {
+            dry_run: dry_run || confirmation.is_some(),
+            confirmation,
+            matched: plan.len() as i32,
+            changed: plan.iter().filter(|c| !c.changes.is_empty()).count() as i32,
+            repositories: plan
+                .into_iter()
+                .map(|c| RepoSettingsChange {
+                    repository: c.repo.into(),
+                    changes: c
+                        .changes
+                        .into_iter()
+                        .map(|f| SettingChange { field: f.field.to_string(), from: f.from, to: f.to })
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Delete a repository with its jobs, results, timings and
+    /// certificates. The audit log keeps its entries. Destructive: see
+    /// `confirmationToken`.
+    async fn purge_repository(
+        &self,
+        ctx: &Context<'_>,
+        repo_id: ID,
+        confirmation_token: Option<String>,
+    ) -> async_graphql::Result<PurgeRepositoryResult> {
+        require_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let repo = tenant_repository(ctx, state, parse_id(&repo_id)?).await?;
+
+        let filter = JobFilter { repo_id: Some(repo.id), ..JobFilter::default() };
+        let jobs = state.store.count_jobs(&filter).await.map_err(store_error)?;
+        let impact = format!(
+            "Deletes {} with its {} jobs and their results, timings and certificates",
+            repo.full_name(),
+            jobs
+        );
+        let gate = confirm::gate(
+            state.store.as_ref(),
+            ctx.data_opt::<ConfirmationPolicy>(),
+            &audit_actor(ctx),
+            "purgeRepository",
+            repo.id.to_string(),
+            impact,
+            confirmation_token.as_deref(),
+        )
+        .await?;
+        if let Gate::Pending(pending) = gate {
+            return Ok(PurgeRepositoryResult {
+                confirmation: Some(pending),
+                purged: false,
+                jobs: 0,
+                results: 0,
+                file_timings: 0,
+            });
+        }
+
+        let purged = state.store.purge_repository(repo.id).await.map_err(store_error)?;
+        let payload = crate::audit::settings_payload(&repo);
+        audit(ctx, state, AuditAction::RepoUnregistered, Some(&repo), None, payload).await;
+        Ok(PurgeRepositoryResult {
+            confirmation: None,
+            purged: true,
+            jobs: purged.jobs as i32,
+            results: purged.results as i32,
+            file_timings: purged.file_timings as i32,
+        })
+    }
+
     /// Enable or disable repository monitoring
     async fn set_repo_enabled(
         &self,
@@ -538,25 +1541,90 @@ async fn set_repo_enabled(
         repo_id: ID,
         enabled: bool,
     ) -> async_graphql::Result<Repository> {
+        require_scope(ctx, TokenScope::Admin)?;
         let state = ctx.data::<GraphQLState>()?;
-        let repo_uuid = Uuid::parse_str(repo_id.as_str())
-            .map_err(|_| async_graphql::Error::new("Invalid repository ID"))?;
-        let mut repo = state
-            .store
-            .get_repository(repo_uuid)
-            .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?
-            .ok_or_else(|| async_graphql::Error::new("Repository not found"))?;
+        let mut repo = tenant_repository(ctx, state, parse_id(&repo_id)?).await?;
         repo.enabled = enabled;
         repo.updated_at = Utc::now();
         state
             .store
             .update_repository(&repo)
             .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            .map_err(store_error)?;
+        let payload = crate::audit::settings_payload(&repo);
+        audit(ctx, state, AuditAction::RepoSettingsChanged, Some(&repo), None, payload).await;
         Ok(repo.into())
     }
 
+    /// Lift a quarantine so the file's failures gate checks again.
+    /// Returns `false` when the file was not quarantined.
+    async fn unquarantine_proof(
+        &self,
+        ctx: &Context<'_>,
+        repo_id: ID,
+        file_path: String,
+    ) -> async_graphql::Result<bool> {
+        require_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let repo_uuid = parse_id(&repo_id)?;
+        if tenant(ctx).is_some() {
+            tenant_repository(ctx, state, repo_uuid).await?;
+        }
+        let lifted = state
+            .store
+            .unquarantine_proof(repo_uuid, &file_path)
+            .await
+            .map_err(store_error)?;
+        if lifted {
+            let repo = state.store.get_repository(repo_uuid).await.ok().flatten();
+            let action = AuditAction::ProofUnquarantined;
+            audit(ctx, state, action, repo.as_ref(), Some(file_path.clone()), &file_path).await;
+        }
+        Ok(lifted)
+    }
+
+    /// Show `message` as a banner on every bot comment and check summary
+    /// for `ttl_secs` seconds, or until cleared when omitted. Replaces any
+    /// announcement already set.
+    async fn set_announcement(
+        &self,
+        ctx: &Context<'_>,
+        message: String,
+        ttl_secs: Option<i64>,
+    ) -> async_graphql::Result<Announcement> {
+        require_instance_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        if message.trim().is_empty() {
+            return Err(coded_error("BAD_USER_INPUT", "Announcement message is empty"));
+        }
+        if ttl_secs.is_some_and(|ttl| ttl <= 0) {
+            return Err(coded_error("BAD_USER_INPUT", "ttlSecs must be positive"));
+        }
+        let record = AnnouncementRecord::new(message, ttl_secs.map(chrono::Duration::seconds));
+        state
+            .store
+            .set_announcement(&record)
+            .await
+            .map_err(store_error)?;
+        audit(ctx, state, AuditAction::AnnouncementSet, None, None, &record.message).await;
+        Ok(record.into())
+    }
+
+    /// Remove the announcement. Returns `false` when none was set.
+    async fn clear_announcement(&self, ctx: &Context<'_>) -> async_graphql::Result<bool> {
+        require_instance_scope(ctx, TokenScope::Admin)?;
+        let state = ctx.data::<GraphQLState>()?;
+        let cleared = state
+            .store
+            .clear_announcement()
+            .await
+            .map_err(store_error)?;
+        if cleared {
+            audit(ctx, state, AuditAction::AnnouncementCleared, None, None, b"").await;
+        }
+        Ok(cleared)
+    }
+
     /// Record the outcome of a tactic attempt (double-loop feedback).
     ///
     /// Called by LLM agents (via MCP or direct GraphQL) when they observe a
@@ -568,6 +1636,8 @@ async fn record_tactic_outcome(
         ctx: &Context<'_>,
         input: RecordTacticOutcomeInput,
     ) -> async_graphql::Result<TacticOutcome> {
+        // The outcomes rank suggestions for every repository.
+        require_instance_scope(ctx, TokenScope::Trigger)?;
         let state = ctx.data::<GraphQLState>()?;
         let prover = map_prover_kind_to_core(input.prover);
         let fingerprint = goal_fingerprint(&input.goal_state);
@@ -587,7 +1657,7 @@ async fn record_tactic_outcome(
             .store
             .record_tactic_outcome(&record)
             .await
-            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+            .map_err(store_error)?;
         Ok(TacticOutcome::from(record))
     }
 }
@@ -601,10 +1671,84 @@ fn from(repo: StoreRepository) -> Self {
             name: repo.name,
             enabled_provers: repo.enabled_provers.into_iter().map(map_prover_kind).collect(),
             last_checked_commit: repo.last_checked_commit,
+            branch_include: repo.branch_include,
+            branch_exclude: repo.branch_exclude,
+            path_include: repo.path_include,
+            path_exclude: repo.path_exclude,
+            visibility: map_visibility_to_graphql(repo.visibility),
+            training_export: repo.training_export,
+            reverify_cron: repo.reverify_cron,
+            timezone: repo.timezone,
+            prover_pins: repo
+                .prover_pins
+                .into_iter()
+                .map(|(prover, version)| ProverPin {
+                    prover: map_prover_kind(CoreProverKind::new(prover)),
+                    version,
+                })
+                .collect(),
         }
     }
 }
 
+/// A `reverifyCron` input: `None` for an empty string, an error for an
+/// expression `crate::reverify` can't parse.
+fn validated_cron(expr: String) -> async_graphql::Result<Option<String>> {
+    let expr = expr.trim().to_string();
+    if expr.is_empty() {
+        return Ok(None);
+    }
+    crate::reverify::parse_schedule(&expr).map_err(|e| coded_error("BAD_USER_INPUT", e.to_string()))?;
+    Ok(Some(expr))
+}
+
+/// A `timezone` input: `None` for an empty string, an error for a name
+/// that isn't an IANA time zone.
+fn validated_timezone(name: String) -> async_graphql::Result<Option<String>> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    crate::calendar::parse_timezone(&name).map_err(|e| coded_error("BAD_USER_INPUT", e.to_string()))?;
+    Ok(Some(name))
+}
+
+/// `settings` as a [`crate::bulk::SettingsPatch`], validated.
+fn settings_patch(settings: RepoSettingsInput) -> async_graphql::Result<crate::bulk::SettingsPatch> {
+    Ok(crate::bulk::SettingsPatch {
+        webhook_secret: settings.webhook_secret,
+        enabled_provers: settings
+            .enabled_provers
+            .map(|provers| provers.into_iter().map(map_prover_kind_to_core).collect()),
+        check_on_push: settings.check_on_push,
+        check_on_pr: settings.check_on_pr,
+        auto_comment: settings.auto_comment,
+        branch_include: settings.branch_include,
+        branch_exclude: settings.branch_exclude,
+        path_include: settings.path_include,
+        path_exclude: settings.path_exclude,
+        visibility: settings.visibility.map(map_visibility),
+        training_export: settings.training_export,
+        reverify_cron: settings.reverify_cron.map(validated_cron).transpose()?,
+        timezone: settings.timezone.map(validated_timezone).transpose()?,
+        prover_pins: settings.prover_pins.map(validated_pins).transpose()?,
+    })
+}
+
+/// `proverPins` input keyed by prover slug; an error for a malformed
+/// version.
+fn validated_pins(
+    pins: Vec<ProverPinInput>,
+) -> async_graphql::Result<std::collections::BTreeMap<String, String>> {
+    pins.into_iter()
+        .map(|pin| -> async_graphql::Result<(String, String)> {
+            let prover = map_prover_kind_to_core(pin.prover);
+            let version = crate::toolchain::validate_pin(&prover, &pin.version).map_err(store_error)?;
+            Ok((prover.as_str().to_string(), version))
+        })
+        .collect()
+}
+
 impl From<ProofJobRecord> for ProofJob {
     fn from(job: ProofJobRecord) -> Self {
         Self {
@@ -616,6 +1760,28 @@ fn from(job: ProofJobRecord) -> Self {
             queued_at: job.queued_at,
             started_at: job.started_at,
             completed_at: job.completed_at,
+            retry_of: job.retry_of.map(|id| ID::from(id.to_string())),
+            base_sha: job.base_sha,
+            resolved_files: job.payload.resolved_files,
+            project_roots: job.payload.project_roots,
+            orphaned: job.payload.orphaned,
+        }
+    }
+}
+
+impl From<ProofResultRecord> for ProofResult {
+    fn from(result: ProofResultRecord) -> Self {
+        Self {
+            success: result.success,
+            status: if result.success { ProofStatus::Verified } else { ProofStatus::Failed },
+            message: result.message,
+            prover_output: result.prover_output,
+            duration_ms: result.duration_ms.clamp(0, i32::MAX as i64) as i32,
+            verified_files: result.verified_files,
+            failed_files: result.failed_files,
+            prover_version: result.prover_version,
+            prover_args: result.prover_args,
+            execution_path: result.execution_path.map(|path| path.as_str().to_string()),
         }
     }
 }
@@ -638,6 +1804,20 @@ fn map_platform_to_graphql(platform: crate::adapters::Platform) -> Platform {
     }
 }
 
+fn map_visibility(visibility: Visibility) -> StoreVisibility {
+    match visibility {
+        Visibility::Public => StoreVisibility::Public,
+        Visibility::Private => StoreVisibility::Private,
+    }
+}
+
+fn map_visibility_to_graphql(visibility: StoreVisibility) -> Visibility {
+    match visibility {
+        StoreVisibility::Public => Visibility::Public,
+        StoreVisibility::Private => Visibility::Private,
+    }
+}
+
 fn map_prover_kind(kind: CoreProverKind) -> ProverKind {
     match kind.as_str() {
         "agda" => ProverKind::Agda,
@@ -673,6 +1853,16 @@ fn map_prover_kind_to_core(kind: ProverKind) -> CoreProverKind {
     })
 }
 
+fn map_job_status_to_core(status: JobStatus) -> crate::scheduler::JobStatus {
+    match status {
+        JobStatus::Queued => crate::scheduler::JobStatus::Queued,
+        JobStatus::Running => crate::scheduler::JobStatus::Running,
+        JobStatus::Completed => crate::scheduler::JobStatus::Completed,
+        JobStatus::Failed => crate::scheduler::JobStatus::Failed,
+        JobStatus::Cancelled => crate::scheduler::JobStatus::Cancelled,
+    }
+}
+
 fn map_job_status(status: crate::scheduler::JobStatus) -> JobStatus {
     match status {
         crate::scheduler::JobStatus::Queued => JobStatus::Queued,
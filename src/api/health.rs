@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Dependency health and webhook backpressure
+//!
+//! [`HealthMonitor`] probes the store and ECHIDNA every
+//! `[health] check_interval_secs` ([`run_health_monitor`]). Once a critical
+//! dependency has failed `failure_threshold` probes in a row the instance
+//! is degraded:
+//!
+//! - webhook handlers answer `503 Service Unavailable` with
+//!   `Retry-After: [health] retry_after_secs` rather than accepting events
+//!   that would be lost, so the platform redelivers them later;
+//! - `GET /health` answers 503 naming what is down, so load balancers
+//!   route around the instance.
+//!
+//! One successful probe clears the dependency again. The store is always
+//! critical; ECHIDNA is unless `require_echidna = false`. With
+//! `[[echidna.failover]]` endpoints, ECHIDNA is down only while all of them
+//! are.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::webhooks::AppState;
+use crate::config::HealthConfig;
+use crate::dispatcher::EchidnaClient;
+use crate::shutdown::ShutdownSignal;
+use crate::store::Store;
+
+/// Upper bound on a single probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A dependency the monitor probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency {
+    Store,
+    Echidna,
+}
+
+impl Dependency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dependency::Store => "store",
+            Dependency::Echidna => "echidna",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DependencyHealth {
+    consecutive_failures: u32,
+}
+
+/// Probe results shared by the probe loop, the webhook middleware and
+/// `/health`.
+pub struct HealthMonitor {
+    failure_threshold: u32,
+    retry_after_secs: u64,
+    require_echidna: bool,
+    store: Mutex<DependencyHealth>,
+    echidna: Mutex<DependencyHealth>,
+}
+
+impl HealthMonitor {
+    pub fn new(config: &HealthConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            retry_after_secs: config.retry_after_secs,
+            require_echidna: config.require_echidna,
+            store: Mutex::new(DependencyHealth::default()),
+            echidna: Mutex::new(DependencyHealth::default()),
+        }
+    }
+
+    fn slot(&self, dependency: Dependency) -> &Mutex<DependencyHealth> {
+        match dependency {
+            Dependency::Store => &self.store,
+            Dependency::Echidna => &self.echidna,
+        }
+    }
+
+    /// Record one probe. Transitions to and from down are logged.
+    pub fn record(&self, dependency: Dependency, outcome: std::result::Result<(), String>) {
+        let mut health = self.slot(dependency).lock().expect("health mutex poisoned");
+        let was_down = health.consecutive_failures >= self.failure_threshold;
+        match outcome {
+            Ok(()) => {
+                if was_down {
+                    tracing::info!("{} is healthy again", dependency.as_str());
+                }
+                health.consecutive_failures = 0;
+            }
+            Err(error) => {
+                health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+                if !was_down && health.consecutive_failures >= self.failure_threshold {
+                    tracing::error!(
+                        "{} is down after {} failed probes: {}",
+                        dependency.as_str(),
+                        health.consecutive_failures,
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    fn is_down(&self, dependency: Dependency) -> bool {
+        self.slot(dependency).lock().expect("health mutex poisoned").consecutive_failures
+            >= self.failure_threshold
+    }
+
+    /// Critical dependencies currently down; empty when healthy.
+    pub fn unavailable(&self) -> Vec<Dependency> {
+        let mut down = Vec::new();
+        if self.is_down(Dependency::Store) {
+            down.push(Dependency::Store);
+        }
+        if self.require_echidna && self.is_down(Dependency::Echidna) {
+            down.push(Dependency::Echidna);
+        }
+        down
+    }
+
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_secs
+    }
+
+    /// Probe both dependencies once.
+    pub async fn probe(&self, store: &dyn Store, echidna: &EchidnaClient) {
+        let store_outcome = match tokio::time::timeout(PROBE_TIMEOUT, store.health_check()).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) => Err("health check returned false".to_string()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("health check timed out".to_string()),
+        };
+        self.record(Dependency::Store, store_outcome);
+
+        let echidna_outcome = match tokio::time::timeout(PROBE_TIMEOUT, echidna.health_check()).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) => Err("reported unhealthy".to_string()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("health check timed out".to_string()),
+        };
+        self.record(Dependency::Echidna, echidna_outcome);
+    }
+}
+
+/// Probe every `interval` until shutdown, starting immediately.
+pub async fn run_health_monitor(
+    monitor: Arc<HealthMonitor>,
+    store: Arc<dyn Store>,
+    echidna: Arc<EchidnaClient>,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        monitor.probe(store.as_ref(), echidna.as_ref()).await;
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Health monitor observed shutdown signal — stopping");
+                return;
+            }
+        }
+    }
+}
+
+fn degraded_response(monitor: &HealthMonitor, down: &[Dependency]) -> Response {
+    let names: Vec<&str> = down.iter().map(|d| d.as_str()).collect();
+    let retry_after = monitor.retry_after_secs().to_string();
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [("Retry-After", retry_after.as_str())],
+        format!("Degraded: {} unavailable", names.join(", ")),
+    )
+        .into_response()
+}
+
+/// Axum middleware: answer webhooks with 503 + `Retry-After` while a
+/// critical dependency is down. Applied to webhook routes only.
+pub async fn backpressure_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(ref monitor) = state.health {
+        let down = monitor.unavailable();
+        if !down.is_empty() {
+            tracing::warn!(path = %request.uri().path(), "Rejecting webhook while degraded");
+            return degraded_response(monitor, &down);
+        }
+    }
+    next.run(request).await
+}
+
+/// `GET /health`: `OK`, or 503 naming the dependencies that are down.
+pub async fn health(State(state): State<AppState>) -> Response {
+    if let Some(ref monitor) = state.health {
+        let down = monitor.unavailable();
+        if !down.is_empty() {
+            return degraded_response(monitor, &down);
+        }
+    }
+    "OK".into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(require_echidna: bool) -> HealthMonitor {
+        HealthMonitor::new(&HealthConfig {
+            failure_threshold: 2,
+            require_echidna,
+            ..HealthConfig::default()
+        })
+    }
+
+    #[test]
+    fn down_after_threshold_and_up_after_one_success() {
+        let monitor = monitor(true);
+        monitor.record(Dependency::Store, Err("locked".into()));
+        assert!(monitor.unavailable().is_empty(), "one failure is tolerated");
+        monitor.record(Dependency::Store, Err("locked".into()));
+        assert_eq!(monitor.unavailable(), vec![Dependency::Store]);
+        monitor.record(Dependency::Store, Ok(()));
+        assert!(monitor.unavailable().is_empty());
+    }
+
+    #[test]
+    fn echidna_only_critical_when_required() {
+        let lenient = monitor(false);
+        let strict = monitor(true);
+        for monitor in [&lenient, &strict] {
+            monitor.record(Dependency::Echidna, Err("connection refused".into()));
+            monitor.record(Dependency::Echidna, Err("connection refused".into()));
+        }
+        assert!(lenient.unavailable().is_empty());
+        assert_eq!(strict.unavailable(), vec![Dependency::Echidna]);
+    }
+}
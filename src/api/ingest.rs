@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Asynchronous webhook ingest
+//!
+//! Platforms give a receiver about ten seconds before treating the
+//! delivery as failed and redelivering it, and the event handlers do
+//! store lookups, directive fetches and enqueueing. With ingest enabled
+//! the HTTP handler only verifies the signature, stores the raw body in
+//! `webhook_deliveries` and answers `202 Accepted`; [`run_ingest_worker`]
+//! does the rest in the background.
+//!
+//! The worker processes due deliveries oldest-first, one at a time, so a
+//! push and the PR event that follows it are handled in order. A failed
+//! attempt is retried with exponential backoff (2s, 4s, 8s, … capped at
+//! five minutes) until `[server] webhook_max_attempts`, after which the
+//! delivery is marked `Failed` with its last error. Deliveries still
+//! pending after a restart are picked up when the worker starts, and a
+//! redelivery of an already-stored delivery id is acknowledged and
+//! dropped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use tokio::sync::Notify;
+use tracing::Instrument;
+
+use super::webhooks::{process_delivery, AppState};
+use crate::adapters::Platform;
+use crate::error::Result;
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{DeliveryStatus, WebhookDeliveryRecord};
+use crate::store::Store;
+
+/// Deliveries fetched per store round-trip.
+const BATCH_SIZE: usize = 50;
+
+/// How often the worker looks for retries that have become due when
+/// nothing new arrives.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Upper bound on the retry backoff.
+const MAX_BACKOFF_SECS: i64 = 300;
+
+/// Handle shared by the webhook handlers and the worker.
+pub struct WebhookIngest {
+    wake: Notify,
+    max_attempts: u32,
+}
+
+impl WebhookIngest {
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            wake: Notify::new(),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Store a verified delivery and wake the worker. Answers `202` once
+    /// the delivery is durable, or `500` so the platform redelivers when
+    /// it could not be stored.
+    pub async fn accept(
+        &self,
+        store: &dyn Store,
+        platform: Platform,
+        event_type: &str,
+        delivery_id: Option<String>,
+        body: &[u8],
+    ) -> (StatusCode, &'static str) {
+        let delivery =
+            WebhookDeliveryRecord::new(platform, event_type.to_string(), delivery_id, body.to_vec());
+        match store.record_webhook_delivery(&delivery).await {
+            Ok(true) => {
+                self.wake.notify_one();
+                (StatusCode::ACCEPTED, "Accepted")
+            }
+            Ok(false) => {
+                tracing::info!(
+                    "Ignoring redelivery {} ({:?} {})",
+                    delivery.delivery_id.as_deref().unwrap_or("-"),
+                    platform,
+                    event_type
+                );
+                (StatusCode::ACCEPTED, "Duplicate delivery")
+            }
+            Err(e) => {
+                tracing::error!("Failed to store {:?} {} delivery: {}", platform, event_type, e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store delivery")
+            }
+        }
+    }
+}
+
+/// Process deliveries until shutdown.
+pub async fn run_ingest_worker(state: AppState, ingest: Arc<WebhookIngest>, shutdown: ShutdownSignal) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        if let Err(e) = drain_due(&state, &ingest).await {
+            tracing::warn!("Webhook ingest pass failed: {}", e);
+        }
+        tokio::select! {
+            _ = ingest.wake.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Webhook ingest worker observed shutdown signal — stopping");
+                return;
+            }
+        }
+    }
+}
+
+/// Process every delivery that is currently due. Returns how many were
+/// attempted.
+pub async fn drain_due(state: &AppState, ingest: &WebhookIngest) -> Result<usize> {
+    let mut attempted = 0;
+    loop {
+        let batch = state
+            .store
+            .list_due_webhook_deliveries(chrono::Utc::now(), BATCH_SIZE)
+            .await?;
+        if batch.is_empty() {
+            return Ok(attempted);
+        }
+        for delivery in batch {
+            process_one(state, ingest, delivery).await?;
+            attempted += 1;
+        }
+    }
+}
+
+async fn process_one(
+    state: &AppState,
+    ingest: &WebhookIngest,
+    mut delivery: WebhookDeliveryRecord,
+) -> Result<()> {
+    let span = tracing::info_span!(
+        "webhook.process",
+        platform = ?delivery.platform,
+        event_type = %delivery.event_type,
+        delivery_id = delivery.delivery_id.as_deref().unwrap_or("-"),
+        attempt = delivery.attempts + 1,
+    );
+    crate::observability::set_parent(&span, delivery.trace_parent.as_deref());
+    let outcome = process_delivery(
+        state,
+        delivery.platform,
+        &delivery.event_type,
+        delivery.delivery_id.clone(),
+        &delivery.payload,
+    )
+    .instrument(span)
+    .await;
+
+    let now = chrono::Utc::now();
+    delivery.attempts += 1;
+    match outcome {
+        Ok(()) => {
+            delivery.status = DeliveryStatus::Processed;
+            delivery.processed_at = Some(now);
+            delivery.last_error = None;
+        }
+        Err(e) if delivery.attempts >= ingest.max_attempts => {
+            tracing::error!(
+                "Giving up on {:?} {} delivery {} after {} attempts: {}",
+                delivery.platform,
+                delivery.event_type,
+                delivery.id,
+                delivery.attempts,
+                e
+            );
+            delivery.status = DeliveryStatus::Failed;
+            delivery.processed_at = Some(now);
+            delivery.last_error = Some(e.to_string());
+        }
+        Err(e) => {
+            let delay = backoff(delivery.attempts);
+            tracing::warn!(
+                "{:?} {} delivery {} failed (attempt {}/{}), retrying in {}s: {}",
+                delivery.platform,
+                delivery.event_type,
+                delivery.id,
+                delivery.attempts,
+                ingest.max_attempts,
+                delay.num_seconds(),
+                e
+            );
+            delivery.next_attempt_at = now + delay;
+            delivery.last_error = Some(e.to_string());
+        }
+    }
+    state.store.update_webhook_delivery(&delivery).await
+}
+
+/// Delay before the attempt after `attempts` failures: 2s, 4s, 8s, …
+fn backoff(attempts: u32) -> chrono::Duration {
+    let secs = 1_i64
+        .checked_shl(attempts.min(16))
+        .unwrap_or(MAX_BACKOFF_SECS)
+        .min(MAX_BACKOFF_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff(1).num_seconds(), 2);
+        assert_eq!(backoff(3).num_seconds(), 8);
+        assert_eq!(backoff(40).num_seconds(), MAX_BACKOFF_SECS);
+    }
+}
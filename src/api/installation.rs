@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! GitHub App installation events
+//!
+//! Installing the App on an account (`installation` / `created`) or adding
+//! repositories to an installation (`installation_repositories` / `added`)
+//! registers each repository the payload lists, so no `echidnabot register`
+//! step is needed. Uninstalling (`deleted`) or removing repositories
+//! (`removed`) disables them; their jobs and results are kept, and a later
+//! installation enables them again with their settings untouched.
+//!
+//! New repositories get [`Repository::new`] defaults, with provers detected
+//! from the default branch (see [`crate::bootstrap::detect_repo_provers`])
+//! when a `[github]` section is configured, and public visibility when the
+//! repository itself is public. Turned off by `[github] auto_register`.
+
+use serde::Deserialize;
+
+use super::webhooks::AppState;
+use crate::adapters::{Platform, RepoId};
+use crate::audit::AuditAction;
+use crate::error::Result;
+use crate::store::models::{Repository, Visibility};
+
+/// One repository as listed by an installation payload.
+#[derive(Debug, Deserialize)]
+struct InstalledRepo {
+    full_name: String,
+    #[serde(default = "default_private")]
+    private: bool,
+}
+
+/// Unknown visibility is treated as private.
+fn default_private() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct InstallationPayload {
+    #[serde(default)]
+    action: String,
+    #[serde(default)]
+    repositories: Vec<InstalledRepo>,
+}
+
+#[derive(Deserialize)]
+struct InstallationRepositoriesPayload {
+    #[serde(default)]
+    repositories_added: Vec<InstalledRepo>,
+    #[serde(default)]
+    repositories_removed: Vec<InstalledRepo>,
+}
+
+/// Register or disable the repositories named by an `installation` or
+/// `installation_repositories` event. Other actions (`suspend`,
+/// `new_permissions_accepted`, ...) are ignored.
+pub async fn handle_installation_event(state: &AppState, event_type: &str, body: &[u8]) -> Result<()> {
+    if !state.config().github.as_ref().map_or(true, |g| g.auto_register) {
+        tracing::debug!("Ignoring {} event: [github] auto_register is off", event_type);
+        return Ok(());
+    }
+
+    let (added, removed) = match event_type {
+        "installation" => {
+            let payload: InstallationPayload = serde_json::from_slice(body)?;
+            match payload.action.as_str() {
+                "created" | "unsuspend" => (payload.repositories, Vec::new()),
+                "deleted" => (Vec::new(), payload.repositories),
+                _ => return Ok(()),
+            }
+        }
+        "installation_repositories" => {
+            let payload: InstallationRepositoriesPayload = serde_json::from_slice(body)?;
+            (payload.repositories_added, payload.repositories_removed)
+        }
+        _ => return Ok(()),
+    };
+
+    for installed in &added {
+        if let Err(e) = register(state, installed).await {
+            tracing::warn!("Auto-registering {} failed: {}", installed.full_name, e);
+        }
+    }
+    for installed in &removed {
+        if let Err(e) = unregister(state, installed).await {
+            tracing::warn!("Disabling {} failed: {}", installed.full_name, e);
+        }
+    }
+    Ok(())
+}
+
+/// Create the repository, or enable it again when it was disabled.
+async fn register(state: &AppState, installed: &InstalledRepo) -> Result<()> {
+    let Some((owner, name)) = installed.full_name.split_once('/') else {
+        return Ok(());
+    };
+    if let Some(mut repo) = state.store.get_repository_by_name(Platform::GitHub, owner, name).await? {
+        if !repo.enabled {
+            repo.enabled = true;
+            repo.updated_at = chrono::Utc::now();
+            state.store.update_repository(&repo).await?;
+            audit_settings(state, AuditAction::RepoSettingsChanged, &repo).await;
+            tracing::info!("Re-enabled {} on installation", installed.full_name);
+        }
+        return Ok(());
+    }
+
+    let mut repo = Repository::new(Platform::GitHub, owner.to_string(), name.to_string());
+    if !installed.private {
+        repo.visibility = Visibility::Public;
+    }
+    if state.config().github.is_some() {
+        let provers = match crate::adapters::build_adapter(&state.config(), Platform::GitHub) {
+            Ok(adapter) => {
+                let repo_id = RepoId::new(Platform::GitHub, owner.to_string(), name.to_string());
+                crate::bootstrap::detect_repo_provers(adapter.as_ref(), &repo_id).await
+            }
+            Err(e) => Err(e),
+        };
+        match provers {
+            Ok(provers) if !provers.is_empty() => repo.enabled_provers = provers,
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Prover detection for {} failed: {}", installed.full_name, e),
+        }
+    }
+
+    state.store.create_repository(&repo).await?;
+    audit_settings(state, AuditAction::RepoRegistered, &repo).await;
+    tracing::info!(
+        "Auto-registered {} on installation ({})",
+        installed.full_name,
+        repo.enabled_provers.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ")
+    );
+    Ok(())
+}
+
+/// Disable the repository, keeping its history.
+async fn unregister(state: &AppState, installed: &InstalledRepo) -> Result<()> {
+    let Some((owner, name)) = installed.full_name.split_once('/') else {
+        return Ok(());
+    };
+    if let Some(mut repo) = state.store.get_repository_by_name(Platform::GitHub, owner, name).await? {
+        if repo.enabled {
+            repo.enabled = false;
+            repo.updated_at = chrono::Utc::now();
+            state.store.update_repository(&repo).await?;
+            audit_settings(state, AuditAction::RepoSettingsChanged, &repo).await;
+            tracing::info!("Disabled {} on uninstallation", installed.full_name);
+        }
+    }
+    Ok(())
+}
+
+async fn audit_settings(state: &AppState, action: AuditAction, repo: &Repository) {
+    let payload = crate::audit::settings_payload(repo);
+    crate::audit::record(state.store.as_ref(), crate::audit::BOT, action, Some(repo), None, payload).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_installation_repositories_payload() {
+        let payload: InstallationRepositoriesPayload = serde_json::from_str(
+            r#"{"action": "added",
+                "repositories_added": [{"full_name": "acme/proofs", "private": false}],
+                "repositories_removed": [{"full_name": "acme/old"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(payload.repositories_added[0].full_name, "acme/proofs");
+        assert!(!payload.repositories_added[0].private);
+        assert!(payload.repositories_removed[0].private, "missing visibility is private");
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Batched store lookups for GraphQL resolvers
+//!
+//! A query like `repositories { jobs { result { ... } } }` resolves one
+//! nested field per parent. Resolvers go through the schema's
+//! `DataLoader<StoreLoader>` instead of the store, so the lookups made
+//! while one level resolves are collected and answered with a single
+//! `IN (...)` query per kind: one for the jobs of every repository, one
+//! for the results of every job.
+//!
+//! Nothing is cached between requests (or within one); the loader only
+//! batches. Visibility checks stay in the resolvers.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::dataloader::Loader;
+use uuid::Uuid;
+
+use super::graphql::store_error;
+use crate::scheduler::JobId;
+use crate::store::models::{ProofJobRecord, ProofResultRecord, Repository};
+use crate::store::Store;
+
+/// Loads the store records behind nested GraphQL fields, in batches.
+pub struct StoreLoader {
+    store: Arc<dyn Store>,
+}
+
+impl StoreLoader {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self { store }
+    }
+}
+
+/// Key for the result of a job (`ProofJob.result`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResultOf(pub JobId);
+
+/// Key for a repository by id (`ProofJob.repository`, visibility checks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RepoById(pub Uuid);
+
+/// Key for a repository's newest `limit` jobs (`Repository.jobs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecentJobs {
+    pub repo_id: Uuid,
+    pub limit: usize,
+}
+
+impl Loader<JobId> for StoreLoader {
+    type Value = ProofJobRecord;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[JobId]) -> Result<HashMap<JobId, Self::Value>, Self::Error> {
+        let jobs = self.store.get_jobs_by_ids(keys).await.map_err(store_error)?;
+        Ok(jobs.into_iter().map(|job| (JobId(job.id), job)).collect())
+    }
+}
+
+impl Loader<ResultOf> for StoreLoader {
+    type Value = ProofResultRecord;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[ResultOf]) -> Result<HashMap<ResultOf, Self::Value>, Self::Error> {
+        let job_ids: Vec<JobId> = keys.iter().map(|key| key.0).collect();
+        let results = self
+            .store
+            .get_results_by_job_ids(&job_ids)
+            .await
+            .map_err(store_error)?;
+        Ok(results
+            .into_iter()
+            .map(|result| (ResultOf(JobId(result.job_id)), result))
+            .collect())
+    }
+}
+
+impl Loader<RepoById> for StoreLoader {
+    type Value = Repository;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[RepoById]) -> Result<HashMap<RepoById, Self::Value>, Self::Error> {
+        let ids: Vec<Uuid> = keys.iter().map(|key| key.0).collect();
+        let repos = self
+            .store
+            .get_repositories_by_ids(&ids)
+            .await
+            .map_err(store_error)?;
+        Ok(repos.into_iter().map(|repo| (RepoById(repo.id), repo)).collect())
+    }
+}
+
+impl Loader<RecentJobs> for StoreLoader {
+    type Value = Vec<ProofJobRecord>;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[RecentJobs]) -> Result<HashMap<RecentJobs, Self::Value>, Self::Error> {
+        // One query per distinct limit; a query normally asks for one.
+        let mut by_limit: HashMap<usize, Vec<Uuid>> = HashMap::new();
+        for key in keys {
+            by_limit.entry(key.limit).or_default().push(key.repo_id);
+        }
+        let mut loaded = HashMap::new();
+        for (limit, repo_ids) in by_limit {
+            // Repositories without jobs still get an (empty) entry.
+            for repo_id in &repo_ids {
+                loaded.insert(RecentJobs { repo_id: *repo_id, limit }, Vec::new());
+            }
+            let jobs = self
+                .store
+                .list_jobs_for_repos(&repo_ids, limit)
+                .await
+                .map_err(store_error)?;
+            for job in jobs {
+                loaded
+                    .entry(RecentJobs { repo_id: job.repo_id, limit })
+                    .or_insert_with(Vec::new)
+                    .push(job);
+            }
+        }
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /jobs/{id}/logs` — a job's prover output as server-sent events
+//!
+//! Replays the lines buffered in [`crate::scheduler::JobLogs`], then, with
+//! `?follow=true` (the default), streams new ones until the job finishes.
+//! Each line is one event named after its stream (`stdout`, `stderr` or
+//! `echidnabot`) with the line's sequence number as the event id. An
+//! `end` event closes the log of a finished job. A queued job's log stays
+//! open until it starts; a job whose live log is gone is replayed from its
+//! stored result. Served behind the same bearer-token middleware as
+//! `/jobs/{id}/sarif` (read scope).
+//!
+//! [`SseDecoder`] is the client half, used by `echidnabot logs`.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use super::auth::{reject_foreign_job, AuthContext};
+use super::webhooks::AppState;
+use crate::scheduler::logs::{LogEvent, LogLine, LogStream};
+use crate::scheduler::{JobId, JobStatus};
+
+/// Name of the event that closes a finished job's log.
+pub const END_EVENT: &str = "end";
+
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    #[serde(default = "default_follow")]
+    follow: bool,
+}
+
+fn default_follow() -> bool {
+    true
+}
+
+/// What comes after the backlog.
+enum Tail {
+    Follow(broadcast::Receiver<LogEvent>),
+    End,
+    Done,
+}
+
+pub async fn job_logs(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Response {
+    let Ok(job_id) = Uuid::parse_str(&id) else {
+        return (StatusCode::BAD_REQUEST, "Invalid job id").into_response();
+    };
+    let job_id = JobId(job_id);
+    if let Some(rejected) = reject_foreign_job(&state, &auth, job_id).await {
+        return rejected;
+    }
+    let logs = state.scheduler.logs();
+
+    // A finished log with nothing in it ran elsewhere; the stored result
+    // has the output.
+    let live = logs
+        .subscribe(job_id)
+        .filter(|sub| !sub.backlog.is_empty() || sub.follow.is_some());
+    let (backlog, follow) = match live {
+        Some(sub) => (sub.backlog, sub.follow),
+        None => match state.store.get_job(job_id).await {
+            Ok(None) => return (StatusCode::NOT_FOUND, "No job with that id").into_response(),
+            Ok(Some(job)) if matches!(job.status, JobStatus::Queued | JobStatus::Running) => {
+                let sub = logs.watch(job_id);
+                (sub.backlog, sub.follow)
+            }
+            Ok(Some(_)) => match state.store.get_result_for_job(job_id).await {
+                Ok(result) => (stored_lines(result.map(|r| r.prover_output)), None),
+                Err(e) => return lookup_failed(job_id, e),
+            },
+            Err(e) => return lookup_failed(job_id, e),
+        },
+    };
+
+    let tail = match follow {
+        Some(rx) if query.follow => Tail::Follow(rx),
+        Some(_) => Tail::Done,
+        None => Tail::End,
+    };
+    let tail = stream::unfold(tail, |tail| async move {
+        match tail {
+            Tail::Done => None,
+            Tail::End => Some((end_event(), Tail::Done)),
+            Tail::Follow(mut rx) => match rx.recv().await {
+                Ok(LogEvent::Line(line)) => Some((line_event(&line), Tail::Follow(rx))),
+                Ok(LogEvent::End) | Err(RecvError::Closed) => Some((end_event(), Tail::Done)),
+                Err(RecvError::Lagged(skipped)) => Some((
+                    Event::default().comment(format!("{} line(s) skipped", skipped)),
+                    Tail::Follow(rx),
+                )),
+            },
+        }
+    });
+    let events = stream::iter(backlog.iter().map(line_event).collect::<Vec<_>>())
+        .chain(tail)
+        .map(Ok::<_, Infallible>);
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+fn line_event(line: &LogLine) -> Event {
+    Event::default()
+        .event(line.stream.as_str())
+        .id(line.seq.to_string())
+        .data(&line.text)
+}
+
+fn end_event() -> Event {
+    Event::default().event(END_EVENT).data("")
+}
+
+/// A stored result's prover output as stdout lines.
+fn stored_lines(output: Option<String>) -> Vec<LogLine> {
+    output
+        .unwrap_or_default()
+        .lines()
+        .enumerate()
+        .map(|(seq, text)| LogLine {
+            seq: seq as u64,
+            stream: LogStream::Stdout,
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+fn lookup_failed(job_id: JobId, e: crate::error::Error) -> Response {
+    tracing::error!("Log lookup for job {} failed: {}", job_id, e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Log lookup failed").into_response()
+}
+
+/// One decoded server-sent event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// `message` when the server named none.
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` parser: feed it response chunks as
+/// they arrive, however they are split, and take the complete events.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    pending: Vec<u8>,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if let Some(data) = self.data.take() {
+                    events.push(SseEvent {
+                        event: self.event.take().unwrap_or_else(|| "message".to_string()),
+                        data,
+                    });
+                }
+                self.event = None;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                },
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoder_handles_events_split_across_chunks() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b": keep-alive\n\nevent: std").is_empty());
+        let events = decoder.feed(b"out\nid: 0\ndata: Theorem foo.\r\n\nevent: end\ndata\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent { event: "stdout".into(), data: "Theorem foo.".into() },
+                SseEvent { event: END_EVENT.into(), data: String::new() },
+            ]
+        );
+    }
+
+    #[test]
+    fn stored_output_replays_as_stdout() {
+        let lines = stored_lines(Some("a\nb\n".into()));
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[1].seq, lines[1].stream, lines[1].text.as_str()), (1, LogStream::Stdout, "b"));
+        assert!(stored_lines(None).is_empty());
+    }
+}
@@ -3,8 +3,27 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! API layer - GraphQL and webhook handlers
 
+pub mod attest;
+pub mod auth;
+pub mod badge;
+pub mod check_actions;
+pub mod comment_commands;
+pub mod confirm;
+pub mod federation;
+pub mod fleet;
+pub mod feed;
 pub mod graphql;
+pub mod health;
+pub mod ingest;
+pub mod installation;
+pub mod loaders;
+pub mod logs;
+pub mod oidc;
 pub mod rate_limit;
+pub mod sarif;
+pub mod status;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod webhooks;
 
 pub use graphql::create_schema;
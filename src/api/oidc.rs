@@ -0,0 +1,536 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! OpenID Connect login for browsers (`[auth.oidc]`)
+//!
+//! API tokens suit scripts; people get a login through the organisation's
+//! identity provider instead. The flow is the authorization code flow with
+//! PKCE:
+//!
+//! 1. `GET /auth/login?next=/graphql` redirects to the provider, keeping
+//!    `state`, `nonce` and the PKCE verifier in a short-lived signed cookie.
+//! 2. `GET /auth/callback` exchanges the code at the token endpoint and
+//!    checks the ID token's issuer, audience, expiry and nonce. The token
+//!    comes straight from the provider over TLS, which OIDC Core §3.1.3.7
+//!    accepts in place of checking its signature.
+//! 3. The user's groups are mapped to a [`TokenScope`] by `[auth.oidc]
+//!    roles`, and a signed session cookie (HTTP-only, `SameSite=Lax`) is
+//!    set. [`crate::api::auth`] reads it wherever a bearer token is
+//!    accepted, so the playground's requests run with the user's scope and
+//!    admin mutations need a group mapped to `admin`.
+//!
+//! Sessions are not stored: the cookie carries the subject, scope and
+//! expiry under an HMAC-SHA256 keyed with `session_secret`, or with a
+//! random per-process key when it is unset (`serve` warns about that).
+//! `GET /auth/logout` clears it. With OIDC configured, anonymous callers
+//! are held to public data as with `[auth] required`, and the playground
+//! page asks for a login first.
+
+use std::sync::OnceLock;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::auth::TokenScope;
+use super::webhooks::AppState;
+use crate::config::OidcConfig;
+use crate::error::{Error, Result};
+
+pub const LOGIN_PATH: &str = "/auth/login";
+pub const CALLBACK_PATH: &str = "/auth/callback";
+pub const LOGOUT_PATH: &str = "/auth/logout";
+
+/// Cookie holding the signed [`Session`].
+pub const SESSION_COOKIE: &str = "echidnabot_session";
+/// Cookie holding the signed [`PendingLogin`] between login and callback.
+const LOGIN_COOKIE: &str = "echidnabot_login";
+/// How long a login may take at the provider.
+const LOGIN_TTL_SECS: i64 = 600;
+
+/// A logged-in user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// The ID token's `sub`.
+    pub subject: String,
+    /// `preferred_username`, `email` or `name`, for display.
+    pub name: Option<String>,
+    pub scope: TokenScope,
+    /// Unix seconds.
+    pub expires_at: i64,
+}
+
+/// What the callback needs to finish a login.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PendingLogin {
+    state: String,
+    nonce: String,
+    verifier: String,
+    next: String,
+    expires_at: i64,
+}
+
+/// The provider's endpoints, from its discovery document.
+#[derive(Debug, Deserialize)]
+struct Provider {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginQuery {
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The key session cookies are signed with.
+fn signing_key(config: &OidcConfig) -> Vec<u8> {
+    static RANDOM: OnceLock<[u8; 32]> = OnceLock::new();
+    match &config.session_secret {
+        Some(secret) => secret.as_bytes().to_vec(),
+        None => RANDOM
+            .get_or_init(|| {
+                let mut key = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            })
+            .to_vec(),
+    }
+}
+
+fn mac(key: &[u8], payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// `value` as `<base64url JSON>.<hex HMAC>`.
+fn sign(key: &[u8], value: &impl Serialize) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap_or_default());
+    let signature = hex::encode(mac(key, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, signature)
+}
+
+/// The value of a [`sign`]ed cookie, if the signature holds.
+fn verify<T: DeserializeOwned>(key: &[u8], cookie: &str) -> Option<T> {
+    let (payload, signature) = cookie.split_once('.')?;
+    mac(key, payload).verify_slice(&hex::decode(signature).ok()?).ok()?;
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()
+}
+
+/// The value of cookie `name` in `headers`.
+fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn set_cookie(config: &OidcConfig, name: &str, value: &str, max_age: i64) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+        name,
+        value,
+        max_age,
+        if config.secure_cookies { "; Secure" } else { "" }
+    )
+}
+
+/// The unexpired session of a request, if it has one.
+pub fn session(config: &OidcConfig, headers: &HeaderMap) -> Option<Session> {
+    let session: Session = verify(&signing_key(config), cookie(headers, SESSION_COOKIE)?)?;
+    (session.expires_at > now()).then_some(session)
+}
+
+/// The scope `groups` earn: the highest mapped one, else `default_scope`.
+pub fn scope_for(config: &OidcConfig, groups: &[String]) -> Option<TokenScope> {
+    groups
+        .iter()
+        .filter_map(|g| config.roles.get(g).copied())
+        .max()
+        .or(config.default_scope)
+}
+
+/// Where to go after logging in: a local path, else the playground.
+fn local_path(next: Option<&str>) -> String {
+    match next {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") && !path.contains('\\') => {
+            path.to_string()
+        }
+        _ => "/graphql".to_string(),
+    }
+}
+
+async fn discover(http: &reqwest::Client, config: &OidcConfig) -> Result<Provider> {
+    let issuer = config.issuer.trim_end_matches('/');
+    let url = format!("{}/.well-known/openid-configuration", issuer);
+    let provider: Provider = http.get(&url).send().await?.error_for_status()?.json().await?;
+    if provider.issuer.trim_end_matches('/') != issuer {
+        return Err(Error::Config(format!(
+            "OIDC discovery at {} names issuer {}",
+            url, provider.issuer
+        )));
+    }
+    Ok(provider)
+}
+
+async fn exchange(http: &reqwest::Client, endpoint: &str, form: &[(&str, &str)]) -> Result<TokenResponse> {
+    let response = http.post(endpoint).form(form).send().await?.error_for_status()?;
+    Ok(response.json().await?)
+}
+
+/// The claims of an ID token received from the token endpoint, checked
+/// against the provider, client and login.
+fn id_token_claims(
+    id_token: &str,
+    provider: &Provider,
+    config: &OidcConfig,
+    nonce: &str,
+) -> std::result::Result<serde_json::Value, String> {
+    let payload = id_token.split('.').nth(1).ok_or("malformed ID token")?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|_| "malformed ID token")?;
+    let claims: serde_json::Value = serde_json::from_slice(&bytes).map_err(|_| "malformed ID token")?;
+
+    if claims["iss"].as_str() != Some(provider.issuer.as_str()) {
+        return Err("ID token from another issuer".into());
+    }
+    let audience_ok = match &claims["aud"] {
+        serde_json::Value::String(aud) => *aud == config.client_id,
+        serde_json::Value::Array(auds) => auds.iter().any(|a| a.as_str() == Some(&config.client_id)),
+        _ => false,
+    };
+    if !audience_ok {
+        return Err("ID token for another client".into());
+    }
+    if claims["exp"].as_i64().is_none_or(|exp| exp <= now()) {
+        return Err("ID token expired".into());
+    }
+    if claims["nonce"].as_str() != Some(nonce) {
+        return Err("ID token nonce mismatch".into());
+    }
+    if claims["sub"].as_str().is_none() {
+        return Err("ID token without subject".into());
+    }
+    Ok(claims)
+}
+
+/// The groups in `claims[claim]`: a list, or a single string.
+fn groups(claims: &serde_json::Value, claim: &str) -> Option<Vec<String>> {
+    match claims.get(claim)? {
+        serde_json::Value::Array(items) => {
+            Some(items.iter().filter_map(|g| g.as_str()).map(str::to_string).collect())
+        }
+        serde_json::Value::String(group) => Some(vec![group.clone()]),
+        _ => None,
+    }
+}
+
+/// Text response for a failed login, logged too.
+fn login_failed(status: StatusCode, message: &str) -> Response {
+    tracing::warn!("OIDC login failed: {}", message);
+    (status, format!("Login failed: {}", message)).into_response()
+}
+
+/// `GET /auth/login`: redirect to the provider.
+pub async fn login(State(state): State<AppState>, Query(query): Query<LoginQuery>) -> Response {
+    let config = state.config();
+    let Some(oidc) = &config.auth.oidc else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let provider = match discover(&reqwest::Client::new(), oidc).await {
+        Ok(provider) => provider,
+        Err(e) => return login_failed(StatusCode::BAD_GATEWAY, &e.to_string()),
+    };
+
+    let pending = PendingLogin {
+        state: random_token(),
+        nonce: random_token(),
+        verifier: random_token(),
+        next: local_path(query.next.as_deref()),
+        expires_at: now() + LOGIN_TTL_SECS,
+    };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(pending.verifier.as_bytes()));
+    let scope = oidc.scopes.join(" ");
+    let params = [
+        ("response_type", "code"),
+        ("client_id", oidc.client_id.as_str()),
+        ("redirect_uri", oidc.redirect_url.as_str()),
+        ("scope", scope.as_str()),
+        ("state", pending.state.as_str()),
+        ("nonce", pending.nonce.as_str()),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ];
+    let query: Vec<String> = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
+        .collect();
+    let separator = if provider.authorization_endpoint.contains('?') { '&' } else { '?' };
+    let location = format!("{}{}{}", provider.authorization_endpoint, separator, query.join("&"));
+
+    let cookie = set_cookie(oidc, LOGIN_COOKIE, &sign(&signing_key(oidc), &pending), LOGIN_TTL_SECS);
+    (AppendHeaders([(header::SET_COOKIE, cookie)]), Redirect::to(&location)).into_response()
+}
+
+/// `GET /auth/callback`: finish the login and set the session cookie.
+pub async fn callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Response {
+    let config = state.config();
+    let Some(oidc) = &config.auth.oidc else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if let Some(error) = &query.error {
+        let description = query.error_description.as_deref().unwrap_or("");
+        return login_failed(StatusCode::UNAUTHORIZED, &format!("{} {}", error, description));
+    }
+    let key = signing_key(oidc);
+    let pending: Option<PendingLogin> = cookie(&headers, LOGIN_COOKIE).and_then(|c| verify(&key, c));
+    let Some(pending) = pending.filter(|p| p.expires_at > now()) else {
+        return login_failed(StatusCode::BAD_REQUEST, "no login in progress, or it expired");
+    };
+    if query.state.as_deref() != Some(pending.state.as_str()) {
+        return login_failed(StatusCode::BAD_REQUEST, "state mismatch");
+    }
+    let Some(code) = &query.code else {
+        return login_failed(StatusCode::BAD_REQUEST, "no authorization code");
+    };
+
+    let http = reqwest::Client::new();
+    let provider = match discover(&http, oidc).await {
+        Ok(provider) => provider,
+        Err(e) => return login_failed(StatusCode::BAD_GATEWAY, &e.to_string()),
+    };
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", oidc.redirect_url.as_str()),
+        ("client_id", oidc.client_id.as_str()),
+        ("code_verifier", pending.verifier.as_str()),
+    ];
+    if let Some(secret) = &oidc.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+    let tokens = match exchange(&http, &provider.token_endpoint, &form).await {
+        Ok(tokens) => tokens,
+        Err(e) => return login_failed(StatusCode::BAD_GATEWAY, &format!("token exchange: {}", e)),
+    };
+
+    let claims = match id_token_claims(&tokens.id_token, &provider, oidc, &pending.nonce) {
+        Ok(claims) => claims,
+        Err(message) => return login_failed(StatusCode::UNAUTHORIZED, &message),
+    };
+    // Providers that keep groups out of the ID token serve them here.
+    let mut user_groups = groups(&claims, &oidc.groups_claim);
+    if let (None, Some(endpoint), Some(access)) =
+        (&user_groups, &provider.userinfo_endpoint, &tokens.access_token)
+    {
+        if let Ok(response) = http.get(endpoint).bearer_auth(access).send().await {
+            if let Ok(info) = response.json::<serde_json::Value>().await {
+                user_groups = groups(&info, &oidc.groups_claim);
+            }
+        }
+    }
+    let user_groups = user_groups.unwrap_or_default();
+    let Some(scope) = scope_for(oidc, &user_groups) else {
+        return login_failed(
+            StatusCode::FORBIDDEN,
+            "none of your groups is given access by [auth.oidc] roles",
+        );
+    };
+
+    let name = ["preferred_username", "email", "name"]
+        .iter()
+        .find_map(|claim| claims[*claim].as_str())
+        .map(str::to_string);
+    let session = Session {
+        subject: claims["sub"].as_str().unwrap_or_default().to_string(),
+        name,
+        scope,
+        expires_at: now() + oidc.session_ttl_secs as i64,
+    };
+    tracing::info!(
+        "OIDC login: {} ({}) with {} scope",
+        session.subject,
+        session.name.as_deref().unwrap_or("-"),
+        session.scope
+    );
+    let ttl = oidc.session_ttl_secs as i64;
+    let cookies = [
+        (header::SET_COOKIE, set_cookie(oidc, SESSION_COOKIE, &sign(&key, &session), ttl)),
+        (header::SET_COOKIE, set_cookie(oidc, LOGIN_COOKIE, "", 0)),
+    ];
+    (AppendHeaders(cookies), Redirect::to(&pending.next)).into_response()
+}
+
+/// `GET /auth/logout`: clear the session cookie.
+pub async fn logout(State(state): State<AppState>) -> Response {
+    let config = state.config();
+    let Some(oidc) = &config.auth.oidc else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let cookie = set_cookie(oidc, SESSION_COOKIE, "", 0);
+    (AppendHeaders([(header::SET_COOKIE, cookie)]), Redirect::to("/")).into_response()
+}
+
+/// Axum middleware for browser pages: with `[auth.oidc]` set, a request
+/// without a session is sent to log in first. Applied to the playground.
+pub async fn require_login(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let config = state.config();
+    if let Some(oidc) = &config.auth.oidc {
+        if session(oidc, request.headers()).is_none() {
+            let here = request.uri().path_and_query().map_or("/", |p| p.as_str());
+            let location = format!("{}?next={}", LOGIN_PATH, urlencoding::encode(here));
+            return Redirect::to(&location).into_response();
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            issuer: "https://idp.example".into(),
+            client_id: "echidnabot".into(),
+            client_secret: None,
+            redirect_url: "https://bot.example/auth/callback".into(),
+            scopes: vec!["openid".into()],
+            groups_claim: "groups".into(),
+            roles: BTreeMap::from([
+                ("admins".to_string(), TokenScope::Admin),
+                ("devs".to_string(), TokenScope::Trigger),
+            ]),
+            default_scope: None,
+            session_ttl_secs: 3600,
+            session_secret: Some("k".repeat(32)),
+            secure_cookies: true,
+        }
+    }
+
+    #[test]
+    fn sessions_round_trip_only_when_signed_and_fresh() {
+        let config = config();
+        let session = Session {
+            subject: "u1".into(),
+            name: Some("ada".into()),
+            scope: TokenScope::Trigger,
+            expires_at: now() + 60,
+        };
+        let signed = sign(&signing_key(&config), &session);
+        let mut headers = HeaderMap::new();
+        let cookie = format!("other=1; {}={}", SESSION_COOKIE, signed);
+        headers.insert(header::COOKIE, cookie.parse().unwrap());
+        assert_eq!(super::session(&config, &headers), Some(session.clone()));
+
+        // Tampered payload, foreign key, expiry.
+        let key = signing_key(&config);
+        let forged = sign(&key, &Session { scope: TokenScope::Admin, ..session.clone() });
+        let (payload, signature) = (forged.split_once('.').unwrap().0, signed.split_once('.').unwrap().1);
+        let tampered = format!("{}.{}", payload, signature);
+        assert!(verify::<Session>(&key, &tampered).is_none());
+        assert!(verify::<Session>(b"another key", &signed).is_none());
+        let stale = sign(&key, &Session { expires_at: now() - 1, ..session });
+        headers.insert(header::COOKIE, format!("{}={}", SESSION_COOKIE, stale).parse().unwrap());
+        assert_eq!(super::session(&config, &headers), None);
+    }
+
+    #[test]
+    fn groups_map_to_the_highest_scope() {
+        let mut config = config();
+        let groups = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(scope_for(&config, &groups(&["devs", "admins"])), Some(TokenScope::Admin));
+        assert_eq!(scope_for(&config, &groups(&["devs"])), Some(TokenScope::Trigger));
+        assert_eq!(scope_for(&config, &groups(&["guests"])), None);
+        config.default_scope = Some(TokenScope::Read);
+        assert_eq!(scope_for(&config, &[]), Some(TokenScope::Read));
+    }
+
+    #[test]
+    fn id_tokens_are_checked_against_provider_client_and_nonce() {
+        let config = config();
+        let provider = Provider {
+            issuer: "https://idp.example".into(),
+            authorization_endpoint: String::new(),
+            token_endpoint: String::new(),
+            userinfo_endpoint: None,
+        };
+        let token = |claims: serde_json::Value| {
+            format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims.to_string()))
+        };
+        let good = serde_json::json!({
+            "iss": "https://idp.example", "aud": ["echidnabot"], "sub": "u1",
+            "exp": now() + 60, "nonce": "n", "groups": ["admins"],
+        });
+        let claims = id_token_claims(&token(good.clone()), &provider, &config, "n").unwrap();
+        assert_eq!(groups(&claims, "groups"), Some(vec!["admins".to_string()]));
+        assert!(id_token_claims(&token(good.clone()), &provider, &config, "other").is_err());
+
+        let mut foreign = good.clone();
+        foreign["aud"] = "someone-else".into();
+        assert!(id_token_claims(&token(foreign), &provider, &config, "n").is_err());
+        let mut expired = good;
+        expired["exp"] = (now() - 1).into();
+        assert!(id_token_claims(&token(expired), &provider, &config, "n").is_err());
+    }
+
+    #[test]
+    fn only_local_paths_are_followed_after_login() {
+        assert_eq!(local_path(Some("/graphql?x=1")), "/graphql?x=1");
+        assert_eq!(local_path(Some("//evil.example")), "/graphql");
+        assert_eq!(local_path(Some("https://evil.example")), "/graphql");
+        assert_eq!(local_path(None), "/graphql");
+    }
+}
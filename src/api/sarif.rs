@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /jobs/{id}/sarif` — a finished job's diagnostics as SARIF
+//!
+//! See [`crate::diagnostics::sarif`] for the log layout. Served behind the
+//! same bearer-token middleware as `/metrics` (read scope).
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use uuid::Uuid;
+
+use super::auth::{reject_foreign_job, AuthContext};
+use super::webhooks::AppState;
+use crate::diagnostics::sarif::{build_job_sarif, SARIF_CONTENT_TYPE};
+use crate::scheduler::JobId;
+
+pub async fn job_sarif(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Response {
+    let Ok(job_id) = Uuid::parse_str(&id) else {
+        return (StatusCode::BAD_REQUEST, "Invalid job id").into_response();
+    };
+    if let Some(rejected) = reject_foreign_job(&state, &auth, JobId(job_id)).await {
+        return rejected;
+    }
+
+    match build_job_sarif(state.store.as_ref(), JobId(job_id)).await {
+        Ok(Some(log)) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, SARIF_CONTENT_TYPE)],
+            log.to_string(),
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No completed job with that id").into_response(),
+        Err(e) => {
+            tracing::error!("SARIF export for job {} failed: {}", job_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "SARIF export failed").into_response()
+        }
+    }
+}
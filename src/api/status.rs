@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `GET /status/{platform}/{owner}/{repo}` — read-only verification status
+//!
+//! The JSON counterpart of the badge, for dashboards and scripts:
+//!
+//! ```json
+//! {
+//!   "platform": "github", "owner": "o", "name": "r",
+//!   "status": "verified",
+//!   "last_checked_commit": "3f2a…",
+//!   "provers": [
+//!     { "prover": "lean", "status": "verified", "commit": "3f2a…",
+//!       "finished_at": "2026-10-17T09:12:44Z" }
+//!   ]
+//! }
+//! ```
+//!
+//! It reports the default branch by the badge's rule: PR checks and
+//! force-pushed-out commits are left out.
+//!
+//! Like the badge it needs no token for public repositories (see
+//! [`crate::api::auth::public_auth_middleware`]). Private, unregistered and
+//! disabled repositories all answer the same 404.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::auth::AuthContext;
+use super::badge::{overall, parse_platform, prover_status, JOB_SCAN_LIMIT};
+use super::webhooks::AppState;
+use crate::error::Result;
+use crate::store::models::Repository;
+use crate::store::Store;
+
+#[derive(Debug, Serialize)]
+pub struct RepoStatus {
+    pub platform: String,
+    pub owner: String,
+    pub name: String,
+    /// `verified` when every prover's latest finished job passed,
+    /// `failing` when any failed, `unknown` with no finished job.
+    pub status: &'static str,
+    pub last_checked_commit: Option<String>,
+    pub provers: Vec<ProverStatus>,
+}
+
+/// Latest finished default-branch job of one enabled prover.
+#[derive(Debug, Serialize)]
+pub struct ProverStatus {
+    pub prover: String,
+    pub status: &'static str,
+    pub commit: Option<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+pub async fn repo_status(
+    State(state): State<AppState>,
+    auth: Option<Extension<AuthContext>>,
+    Path((platform, owner, name)): Path<(String, String, String)>,
+) -> Response {
+    let auth = auth.map_or_else(AuthContext::public, |Extension(auth)| auth);
+    let not_found = || (StatusCode::NOT_FOUND, "Repository not found").into_response();
+
+    let Some(platform) = parse_platform(&platform) else {
+        return not_found();
+    };
+    let repo = match state.store.get_repository_by_name(platform, &owner, &name).await {
+        Ok(Some(repo)) if repo.enabled && auth.can_view(&repo) => repo,
+        Ok(_) => return not_found(),
+        Err(e) => {
+            tracing::error!("Status lookup for {}/{} failed: {}", owner, name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Status lookup failed").into_response();
+        }
+    };
+
+    match build_status(state.store.as_ref(), &repo).await {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => {
+            tracing::error!("Status for {} failed: {}", repo.full_name(), e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Status lookup failed").into_response()
+        }
+    }
+}
+
+/// Per-prover status of the default branch, by the badge's rule
+/// ([`super::badge::prover_status`]): PR checks, PR baselines and
+/// orphaned jobs don't count.
+pub async fn build_status(store: &dyn Store, repo: &Repository) -> Result<RepoStatus> {
+    let jobs = store.list_jobs_for_repo(repo.id, JOB_SCAN_LIMIT).await?;
+
+    let mut provers = Vec::new();
+    let mut statuses = Vec::new();
+    for prover in &repo.enabled_provers {
+        let (status, latest) = prover_status(store, repo, &jobs, prover).await?;
+        statuses.push(status);
+        provers.push(ProverStatus {
+            prover: prover.as_str().to_string(),
+            status: status.as_str(),
+            commit: latest.map(|job| job.commit_sha.clone()),
+            finished_at: latest.and_then(|job| job.completed_at),
+        });
+    }
+    let overall = overall(&statuses);
+
+    Ok(RepoStatus {
+        platform: format!("{:?}", repo.platform).to_lowercase(),
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+        status: overall.as_str(),
+        last_checked_commit: repo.last_checked_commit.clone(),
+        provers,
+    })
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! HTTPS for `serve` (`--features tls`)
+//!
+//! With `[server.tls]` set, the listener terminates TLS itself, so the
+//! daemon can face the internet without a reverse proxy. The certificate
+//! comes from PEM files (read again on SIGHUP, so a renewed certificate
+//! needs no restart) or from an ACME CA such as Let's Encrypt, ordered and
+//! renewed in the background through TLS-ALPN-01 on the listening port.
+//! ALPN offers HTTP/2 and HTTP/1.1; plain HTTP serves both too (`h2c`).
+//!
+//! Handshakes slower than `handshake_timeout_secs` are dropped; webhook
+//! bodies are bounded by `[server] max_webhook_body_bytes` and
+//! `webhook_read_timeout_secs` (see [`crate::api::webhooks`]).
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::Router;
+use axum_server::accept::Accept;
+use axum_server::tls_rustls::{RustlsAcceptor, RustlsConfig};
+use axum_server::Handle;
+use futures::future::BoxFuture;
+use futures::StreamExt;
+
+use crate::config::{AcmeConfig, TlsConfig};
+use crate::error::{Error, Result};
+
+/// Let's Encrypt, and its staging directory for trying things out.
+const LETS_ENCRYPT: &str = "https://acme-v02.api.letsencrypt.org/directory";
+const LETS_ENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// Serve `app` over HTTPS on `listener` until `shutdown` completes, then
+/// let open connections finish, like `axum::serve(..).with_graceful_shutdown`.
+pub async fn serve_tls(
+    listener: tokio::net::TcpListener,
+    tls: &TlsConfig,
+    app: IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    tls.validate()?;
+    // reqwest and rustls-acme bring their own providers; pin one.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let handle = Handle::new();
+    let drain = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        drain.graceful_shutdown(None);
+    });
+    let timeout = Duration::from_secs(tls.handshake_timeout_secs.max(1));
+    let server = axum_server::from_tcp(listener.into_std()?);
+
+    let served = match (&tls.acme, &tls.cert_path, &tls.key_path) {
+        (Some(acme), _, _) => {
+            let acceptor = HandshakeTimeout {
+                inner: acme_acceptor(acme),
+                timeout,
+            };
+            server.acceptor(acceptor).handle(handle).serve(app).await
+        }
+        (None, Some(cert), Some(key)) => {
+            let config = RustlsConfig::from_pem_file(cert, key)
+                .await
+                .map_err(|e| Error::Config(format!("[server.tls] loading {} / {}: {}", cert, key, e)))?;
+            tokio::spawn(reload_on_hangup(config.clone(), cert.clone(), key.clone()));
+            let acceptor = HandshakeTimeout {
+                inner: RustlsAcceptor::new(config),
+                timeout,
+            };
+            server.acceptor(acceptor).handle(handle).serve(app).await
+        }
+        _ => unreachable!("validated above"),
+    };
+    served.map_err(Error::Io)
+}
+
+/// An acceptor answering TLS-ALPN-01 challenges and serving the ordered
+/// certificate, with a task driving orders and renewals.
+fn acme_acceptor(acme: &AcmeConfig) -> rustls_acme::axum::AxumAcceptor {
+    let directory = acme.directory_url.clone().unwrap_or_else(|| {
+        if acme.staging { LETS_ENCRYPT_STAGING } else { LETS_ENCRYPT }.to_string()
+    });
+    let mut state = rustls_acme::AcmeConfig::new(acme.domains.clone())
+        .contact(acme.contact.iter().map(|email| format!("mailto:{}", email)))
+        .cache_option(acme.cache_dir.clone().map(rustls_acme::caches::DirCache::new))
+        .directory(directory)
+        .state();
+
+    let mut rustls_config = (*state.default_rustls_config()).clone();
+    rustls_config
+        .alpn_protocols
+        .extend([b"h2".to_vec(), b"http/1.1".to_vec()]);
+    let acceptor = state.axum_acceptor(Arc::new(rustls_config));
+
+    tokio::spawn(async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(ok) => tracing::info!("ACME: {:?}", ok),
+                Err(e) => tracing::warn!("ACME: {}", e),
+            }
+        }
+    });
+    acceptor
+}
+
+/// Re-read the certificate files on SIGHUP, next to the configuration
+/// reload. A bad file keeps the certificate in use.
+async fn reload_on_hangup(config: RustlsConfig, cert: String, key: String) {
+    #[cfg(unix)]
+    {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("Failed to install SIGHUP handler: {}; TLS certificates will not be reloaded", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            match config.reload_from_pem_file(&cert, &key).await {
+                Ok(()) => tracing::info!("TLS certificate reloaded from {}", cert),
+                Err(e) => tracing::error!("TLS certificate reload from {} failed, keeping the current one: {}", cert, e),
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (config, cert, key);
+}
+
+/// Drops connections whose handshake takes longer than `timeout`.
+#[derive(Clone)]
+struct HandshakeTimeout<A> {
+    inner: A,
+    timeout: Duration,
+}
+
+impl<A, I, S> Accept<I, S> for HandshakeTimeout<A>
+where
+    A: Accept<I, S>,
+    A::Future: Send + 'static,
+{
+    type Stream = A::Stream;
+    type Service = A::Service;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let handshake = self.inner.accept(stream, service);
+        let timeout = self.timeout;
+        Box::pin(async move {
+            tokio::time::timeout(timeout, handshake)
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+        })
+    }
+}
@@ -4,9 +4,9 @@
 //! Webhook handlers for GitHub, GitLab, Bitbucket, and Codeberg/Forgejo
 
 use axum::{
-    body::Bytes,
+    body::{Body, Bytes},
     extract::State,
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     middleware,
     response::IntoResponse,
     routing::post,
@@ -14,23 +14,31 @@
 };
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::Deserialize;
 
-use crate::adapters::{Platform, PrId, RepoId};
+use crate::adapters::{Platform, PlatformAdapter, PrId, RepoId};
+use crate::api::comment_commands::{handle_comment_command, Commenter};
+use crate::api::health::{backpressure_middleware, HealthMonitor};
+use crate::api::ingest::WebhookIngest;
 use crate::api::rate_limit::{rate_limit_middleware, WebhookRateLimiter};
-use crate::config::Config;
+use crate::config::{Config, SharedConfig};
+use crate::depgraph::ChangedFiles;
 use crate::error::Result;
 use crate::modes::{self, ModeSelector};
-use crate::scheduler::{JobPriority, JobScheduler, ProofJob};
+use crate::scheduler::{JobPriority, JobScheduler, ProofJob, SupersedeScope};
 use crate::store::Store;
 use crate::store::models::ProofJobRecord;
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
-    pub config: Arc<Config>,
+    /// Current configuration, replaced as a whole on reload (see
+    /// [`crate::reload`]). Read it with [`AppState::config`].
+    pub config: SharedConfig,
     pub store: Arc<dyn Store>,
     pub scheduler: Arc<JobScheduler>,
     /// Per-IP sliding-window rate limiter for webhook endpoints. `None` = unlimited.
@@ -40,27 +48,63 @@ pub struct AppState {
     /// TOML config. Avoids a DB lookup for the common "no per-repo setting"
     /// case inside webhook handlers.
     pub mode_selector: ModeSelector,
+    /// Asynchronous webhook ingest. When set, handlers store verified
+    /// deliveries and answer `202 Accepted`, leaving the work to
+    /// [`crate::api::ingest::run_ingest_worker`]; when `None` they process
+    /// inline and answer `200` (tests, embedded use).
+    pub ingest: Option<Arc<WebhookIngest>>,
+    /// Dependency health. While the store or ECHIDNA is down, webhooks are
+    /// answered `503` with `Retry-After` (see [`crate::api::health`]).
+    /// `None` = never degrade.
+    pub health: Option<Arc<HealthMonitor>>,
 }
 
-/// Create webhook router with optional per-IP rate limiting.
+impl AppState {
+    /// Snapshot of the current configuration. Keep it for the length of
+    /// one request so a reload halfway through cannot mix two configs.
+    pub fn config(&self) -> Arc<Config> {
+        self.config.load_full()
+    }
+}
+
+/// Create webhook router with optional per-IP rate limiting and
+/// backpressure while a dependency is down.
 ///
-/// `state` is cloned into the rate-limit middleware so it can access the
-/// `rate_limiter` field without going through the router's state layer.
+/// `state` is cloned into both middlewares so they can access the
+/// `rate_limiter` and `health` fields without going through the router's
+/// state layer.
 pub fn webhook_router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/webhooks/github", post(handle_github_webhook))
         .route("/webhooks/gitlab", post(handle_gitlab_webhook))
         .route("/webhooks/bitbucket", post(handle_bitbucket_webhook))
         .route("/webhooks/codeberg", post(handle_codeberg_webhook))
+        .layer(middleware::from_fn_with_state(state.clone(), backpressure_middleware))
         .layer(middleware::from_fn_with_state(state, rate_limit_middleware))
 }
 
+/// Act on a stored delivery — the ingest worker's entry point.
+pub(crate) async fn process_delivery(
+    state: &AppState,
+    platform: Platform,
+    event_type: &str,
+    delivery_id: Option<String>,
+    body: &[u8],
+) -> Result<()> {
+    match platform {
+        Platform::GitHub => process_github_event(state, event_type, delivery_id, body).await,
+        Platform::GitLab => process_gitlab_event(state, event_type, delivery_id, body).await,
+        Platform::Bitbucket => process_bitbucket_event(state, event_type, delivery_id, body).await,
+        Platform::Codeberg => process_codeberg_event(state, event_type, delivery_id, body).await,
+    }
+}
+
 /// GitHub webhook handler
 #[tracing::instrument(
     name = "webhook.github",
     skip(state, headers, body),
     fields(
-        payload_bytes = body.len(),
+        payload_bytes = tracing::field::Empty,
         event_type = tracing::field::Empty,
         delivery_id = tracing::field::Empty,
     )
@@ -68,25 +112,42 @@ pub fn webhook_router(state: AppState) -> Router<AppState> {
 async fn handle_github_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
-    body: Bytes,
+    body: Body,
 ) -> impl IntoResponse {
     tracing::info!("Received GitHub webhook");
 
-    // Verify signature if secret is configured
-    if let Some(ref gh_config) = state.config.github {
-        if let Some(ref secret) = gh_config.webhook_secret {
-            if let Err(e) = verify_github_signature(&headers, &body, secret) {
-                tracing::warn!("GitHub webhook signature verification failed: {}", e);
-                return (StatusCode::UNAUTHORIZED, "Invalid signature");
-            }
-        }
-    }
-
     // Parse event type + traceability id
     let event_type = headers
         .get("X-GitHub-Event")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("unknown");
+
+    if !handles_event(Platform::GitHub, event_type) {
+        tracing::debug!("Ignoring GitHub event type: {}", event_type);
+        return (StatusCode::OK, "OK");
+    }
+    let body = match read_webhook_body(&state, &headers, body).await {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    tracing::Span::current().record("payload_bytes", body.len());
+
+    // Verify the signature against the repository's own secret, or the
+    // global one when the repository has none
+    let config = state.config();
+    let global_secret = config.github.as_ref().and_then(|c| c.webhook_secret.as_deref());
+    let secret =
+        match webhook_secret(&state, Platform::GitHub, event_type, &body, global_secret).await {
+            Ok(secret) => secret,
+            Err(response) => return response,
+        };
+    if let Some(ref secret) = secret {
+        if let Err(e) = verify_github_signature(&headers, &body, secret) {
+            tracing::warn!("GitHub webhook signature verification failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, "Invalid signature");
+        }
+    }
+
     let delivery_id = headers
         .get("X-GitHub-Delivery")
         .and_then(|v| v.to_str().ok())
@@ -101,70 +162,162 @@ async fn handle_github_webhook(
     }
     tracing::info!("GitHub event type: {}", event_type);
 
+    if let Some(ingest) = &state.ingest {
+        return ingest
+            .accept(state.store.as_ref(), Platform::GitHub, event_type, delivery_id, &body)
+            .await;
+    }
+    if let Err(e) = process_github_event(&state, event_type, delivery_id, &body).await {
+        tracing::warn!("GitHub {} event processing failed: {}", event_type, e);
+    }
+
+    (StatusCode::OK, "OK")
+}
+
+/// Act on a verified GitHub event (inline, or from the ingest worker).
+async fn process_github_event(
+    state: &AppState,
+    event_type: &str,
+    delivery_id: Option<String>,
+    body: &[u8],
+) -> Result<()> {
     match event_type {
         "push" => {
             tracing::info!("Received push event");
-            if let Ok(payload) = serde_json::from_slice::<GitHubPushPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitHubPushPayload>(body) {
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                if payload.deleted || is_null_sha(&payload.after) {
+                    return branch_deleted(state, Platform::GitHub, &owner, &name, &payload.git_ref).await;
+                }
+                let changes = push_changes(
+                    &payload.commits,
+                    None,
+                    payload.created || payload.forced || is_null_sha(&payload.before),
+                    state.config().server.max_push_files,
+                );
+                enqueue_repo_jobs(
+                    state,
                     Platform::GitHub,
                     &owner,
                     &name,
                     &payload.after,
                     JobPriority::Normal,
-                    RepoEventKind::Push,
+                    RepoEventKind::Push {
+                        git_ref: &payload.git_ref,
+                        default_branch: payload.repository.default_branch.as_deref(),
+                        changes: changes.as_ref(),
+                        forced: payload.forced,
+                        before: &payload.before,
+                        commits: &payload.commits,
+                    },
                     None,
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
         "pull_request" => {
             tracing::info!("Received pull_request event");
-            if let Ok(payload) = serde_json::from_slice::<GitHubPullRequestPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitHubPullRequestPayload>(body) {
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                // Adding an auto-merge label to an already-verified PR
+                // merges it now instead of re-verifying.
+                if let Some(label) = payload.label.as_ref().filter(|_| payload.action == "labeled") {
+                    match merge_on_label(
+                        state,
+                        Platform::GitHub,
+                        &owner,
+                        &name,
+                        payload.pull_request.number,
+                        &payload.pull_request.head.sha,
+                        &label.name,
+                    )
+                    .await
+                    {
+                        Ok(true) => return Ok(()),
+                        Ok(false) => {}
+                        Err(e) => tracing::warn!("Auto-merge on label failed: {}", e),
+                    }
+                }
+                enqueue_repo_jobs(
+                    state,
                     Platform::GitHub,
                     &owner,
                     &name,
                     &payload.pull_request.head.sha,
                     JobPriority::High,
-                    RepoEventKind::PullRequest,
+                    RepoEventKind::PullRequest {
+                        base_sha: payload.pull_request.base.as_ref().map(|b| b.sha.as_str()),
+                    },
                     Some(payload.pull_request.number),
                     delivery_id.clone(),
+                    Some(&PullRequestMeta {
+                        author: payload.pull_request.user.as_ref().map(|u| u.login.clone()),
+                        labels: payload.pull_request.labels.iter().map(|l| l.name.clone()).collect(),
+                    }),
                 )
-                .await;
+                .await?;
             }
         }
         "check_suite" => {
             tracing::info!("Received check_suite event");
-            if let Ok(payload) = serde_json::from_slice::<GitHubCheckSuitePayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitHubCheckSuitePayload>(body) {
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                enqueue_repo_jobs(
+                    state,
                     Platform::GitHub,
                     &owner,
                     &name,
                     &payload.check_suite.head_sha,
                     JobPriority::High,
-                    RepoEventKind::PullRequest,
+                    RepoEventKind::PullRequest { base_sha: None },
                     None, // check_suite payload doesn't carry the PR number directly
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
+            }
+        }
+        "check_run" => {
+            // Button clicks on our own check runs (see `check_actions`).
+            // Other check_run actions (created/completed/rerequested) are
+            // GitHub echoing our writes back and are ignored.
+            if let Ok(payload) = serde_json::from_slice::<GitHubCheckRunPayload>(body) {
+                if payload.action != "requested_action" {
+                    return Ok(());
+                }
+                let (Some(external_id), Some(requested)) =
+                    (payload.check_run.external_id, payload.requested_action)
+                else {
+                    return Ok(());
+                };
+                let (owner, name) = split_full_name(&payload.repository.full_name);
+                if let Err(e) = crate::api::check_actions::handle_requested_action(
+                    state,
+                    Platform::GitHub,
+                    &owner,
+                    &name,
+                    &external_id,
+                    &requested.identifier,
+                    payload.sender.as_ref().map(|u| u.login.as_str()),
+                )
+                .await
+                {
+                    tracing::warn!("Check-run action '{}' failed: {}", requested.identifier, e);
+                }
             }
         }
         "issue_comment" => {
-            // Consultant-mode trigger: any @echidnabot mention on a PR
-            // comment surfaces a structured Q&A response. Bare comments
-            // without a mention are ignored. Bot/system author comments
+            // `@echidnabot <command>` on a PR comment (see
+            // `api::comment_commands`); anything that isn't a known command
+            // is a Consultant-mode question. Bare comments without a
+            // mention are ignored. Bot/system author comments
             // (echidnabot's own posts) are filtered to avoid loops.
             tracing::info!("Received issue_comment event");
-            if let Ok(payload) = serde_json::from_slice::<GitHubIssueCommentPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitHubIssueCommentPayload>(body) {
                 if !modes::is_any_mention(&payload.comment.body) {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 if payload
                     .comment
@@ -176,20 +329,34 @@ async fn handle_github_webhook(
                     })
                 {
                     tracing::debug!("Ignoring own comment / bot author");
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = handle_consultant_mention(
-                    &state,
+                let commenter = Commenter {
+                    login: payload.comment.user.as_ref().map(|u| u.login.as_str()),
+                    association: payload.comment.author_association.as_deref(),
+                };
+                if let Err(e) = handle_comment_command(
+                    state,
                     Platform::GitHub,
                     &owner,
                     &name,
                     payload.issue.number,
                     &payload.comment.body,
+                    commenter,
                 )
-                .await;
+                .await
+                {
+                    tracing::warn!("Comment command failed: {}", e);
+                }
             }
         }
+        "installation" | "installation_repositories" => {
+            // App installed on / removed from repositories (see
+            // `api::installation`)
+            tracing::info!("Received {} event", event_type);
+            crate::api::installation::handle_installation_event(state, event_type, body).await?;
+        }
         "ping" => {
             tracing::info!("Received ping event - webhook configured correctly");
         }
@@ -198,42 +365,59 @@ async fn handle_github_webhook(
         }
     }
 
-    (StatusCode::OK, "OK")
+    Ok(())
 }
 
 /// GitLab webhook handler
 #[tracing::instrument(
     name = "webhook.gitlab",
     skip(state, headers, body),
-    fields(payload_bytes = body.len())
+    fields(payload_bytes = tracing::field::Empty)
 )]
 async fn handle_gitlab_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
-    body: Bytes,
+    body: Body,
 ) -> impl IntoResponse {
     tracing::info!("Received GitLab webhook");
 
-    // Verify token if configured
-    if let Some(ref gl_config) = state.config.gitlab {
-        if let Some(ref secret) = gl_config.webhook_secret {
-            let token = headers
-                .get("X-Gitlab-Token")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("");
-
-            if token != secret {
-                tracing::warn!("GitLab webhook token mismatch");
-                return (StatusCode::UNAUTHORIZED, "Invalid token");
-            }
-        }
-    }
-
     // Parse event type + traceability id
     let event_type = headers
         .get("X-Gitlab-Event")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("unknown");
+
+    if !handles_event(Platform::GitLab, event_type) {
+        tracing::debug!("Ignoring GitLab event type: {}", event_type);
+        return (StatusCode::OK, "OK");
+    }
+    let body = match read_webhook_body(&state, &headers, body).await {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    tracing::Span::current().record("payload_bytes", body.len());
+
+    // Verify the token against the project's own secret, or the global
+    // one when the project has none
+    let config = state.config();
+    let global_secret = config.gitlab.as_ref().and_then(|c| c.webhook_secret.as_deref());
+    let secret =
+        match webhook_secret(&state, Platform::GitLab, event_type, &body, global_secret).await {
+            Ok(secret) => secret,
+            Err(response) => return response,
+        };
+    if let Some(ref secret) = secret {
+        let token = headers
+            .get("X-Gitlab-Token")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+
+        if token != secret {
+            tracing::warn!("GitLab webhook token mismatch");
+            return (StatusCode::UNAUTHORIZED, "Invalid token");
+        }
+    }
+
     let delivery_id = headers
         .get("X-Gitlab-Webhook-UUID")
         .and_then(|v| v.to_str().ok())
@@ -241,29 +425,66 @@ async fn handle_gitlab_webhook(
 
     tracing::info!("GitLab event type: {}", event_type);
 
+    if let Some(ingest) = &state.ingest {
+        return ingest
+            .accept(state.store.as_ref(), Platform::GitLab, event_type, delivery_id, &body)
+            .await;
+    }
+    if let Err(e) = process_gitlab_event(&state, event_type, delivery_id, &body).await {
+        tracing::warn!("GitLab {} event processing failed: {}", event_type, e);
+    }
+
+    (StatusCode::OK, "OK")
+}
+
+/// Act on a verified GitLab event (inline, or from the ingest worker).
+async fn process_gitlab_event(
+    state: &AppState,
+    event_type: &str,
+    delivery_id: Option<String>,
+    body: &[u8],
+) -> Result<()> {
     match event_type {
         "Push Hook" => {
             tracing::info!("Received push hook");
-            if let Ok(payload) = serde_json::from_slice::<GitLabPushPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitLabPushPayload>(body) {
                 let (owner, name) = split_full_name(&payload.project.path_with_namespace);
+                if is_null_sha(&payload.after) {
+                    return branch_deleted(state, Platform::GitLab, &owner, &name, &payload.git_ref).await;
+                }
+                let changes = push_changes(
+                    &payload.commits,
+                    payload.total_commits_count,
+                    is_null_sha(&payload.before),
+                    state.config().server.max_push_files,
+                );
                 let commit = payload.checkout_sha.unwrap_or(payload.after);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                enqueue_repo_jobs(
+                    state,
                     Platform::GitLab,
                     &owner,
                     &name,
                     &commit,
                     JobPriority::Normal,
-                    RepoEventKind::Push,
+                    RepoEventKind::Push {
+                        git_ref: &payload.git_ref,
+                        default_branch: payload.project.default_branch.as_deref(),
+                        changes: changes.as_ref(),
+                        // GitLab push hooks don't flag force-pushes.
+                        forced: false,
+                        before: &payload.before,
+                        commits: &payload.commits,
+                    },
                     None,
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
         "Merge Request Hook" => {
             tracing::info!("Received merge request hook");
-            if let Ok(payload) = serde_json::from_slice::<GitLabMergeRequestPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<GitLabMergeRequestPayload>(body) {
                 let (owner, name) = split_full_name(&payload.project.path_with_namespace);
                 let mr_iid = payload.object_attributes.iid;
                 let commit = payload
@@ -271,52 +492,70 @@ async fn handle_gitlab_webhook(
                     .last_commit
                     .map(|c| c.id)
                     .unwrap_or_else(|| payload.object_attributes.last_commit_id);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                enqueue_repo_jobs(
+                    state,
                     Platform::GitLab,
                     &owner,
                     &name,
                     &commit,
                     JobPriority::High,
-                    RepoEventKind::PullRequest,
+                    RepoEventKind::PullRequest {
+                        base_sha: payload
+                            .object_attributes
+                            .diff_refs
+                            .as_ref()
+                            .and_then(|d| d.base_sha.as_deref()),
+                    },
                     mr_iid,
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
         "Note Hook" => {
-            tracing::info!("Received GitLab note hook (Consultant trigger)");
-            if let Ok(payload) = serde_json::from_slice::<GitLabNotePayload>(&body) {
+            // `@echidnabot <command>` on a merge request, as for GitHub's
+            // `issue_comment`.
+            tracing::info!("Received GitLab note hook");
+            if let Ok(payload) = serde_json::from_slice::<GitLabNotePayload>(body) {
                 if !modes::is_any_mention(&payload.object_attributes.note) {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 if payload
                     .user
                     .as_ref()
                     .is_some_and(|u| u.username.eq_ignore_ascii_case("echidnabot"))
                 {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 // Only respond on MR notes — Issue notes don't have a PR
                 // to comment back on.
                 if payload.object_attributes.noteable_type.as_deref() != Some("MergeRequest") {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 let Some(mr) = payload.merge_request.as_ref() else {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 };
                 let (owner, name) =
                     split_full_name(&payload.project.path_with_namespace);
-                let _ = handle_consultant_mention(
-                    &state,
+                // Note payloads carry no member access level.
+                let commenter = Commenter {
+                    login: payload.user.as_ref().map(|u| u.username.as_str()),
+                    association: None,
+                };
+                if let Err(e) = handle_comment_command(
+                    state,
                     Platform::GitLab,
                     &owner,
                     &name,
                     mr.iid,
                     &payload.object_attributes.note,
+                    commenter,
                 )
-                .await;
+                .await
+                {
+                    tracing::warn!("Comment command failed: {}", e);
+                }
             }
         }
         _ => {
@@ -324,19 +563,19 @@ async fn handle_gitlab_webhook(
         }
     }
 
-    (StatusCode::OK, "OK")
+    Ok(())
 }
 
 /// Bitbucket webhook handler
 #[tracing::instrument(
     name = "webhook.bitbucket",
     skip(state, headers, body),
-    fields(payload_bytes = body.len())
+    fields(payload_bytes = tracing::field::Empty)
 )]
 async fn handle_bitbucket_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
-    body: Bytes,
+    body: Body,
 ) -> impl IntoResponse {
     tracing::info!("Received Bitbucket webhook");
 
@@ -344,6 +583,29 @@ async fn handle_bitbucket_webhook(
         .get("X-Event-Key")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("unknown");
+
+    if !handles_event(Platform::Bitbucket, event_type) {
+        tracing::debug!("Ignoring Bitbucket event type: {}", event_type);
+        return (StatusCode::OK, "OK");
+    }
+    let body = match read_webhook_body(&state, &headers, body).await {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    tracing::Span::current().record("payload_bytes", body.len());
+    // Bitbucket signs with the repository's own secret only (there is no
+    // `[bitbucket]` section). Unsigned comments are run as `anyone`; see
+    // `process_bitbucket_event`.
+    let secret = match webhook_secret(&state, Platform::Bitbucket, event_type, &body, None).await {
+        Ok(secret) => secret,
+        Err(response) => return response,
+    };
+    if let Some(ref secret) = secret {
+        if let Err(e) = verify_bitbucket_signature(&headers, &body, secret) {
+            tracing::warn!("Bitbucket webhook signature verification failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, "Invalid signature");
+        }
+    }
     let delivery_id = headers
         .get("X-Hook-UUID")
         .and_then(|v| v.to_str().ok())
@@ -351,57 +613,112 @@ async fn handle_bitbucket_webhook(
 
     tracing::info!("Bitbucket event type: {}", event_type);
 
+    if let Some(ingest) = &state.ingest {
+        return ingest
+            .accept(state.store.as_ref(), Platform::Bitbucket, event_type, delivery_id, &body)
+            .await;
+    }
+    if let Err(e) = process_bitbucket_event(&state, event_type, delivery_id, &body).await {
+        tracing::warn!("Bitbucket {} event processing failed: {}", event_type, e);
+    }
+
+    (StatusCode::OK, "OK")
+}
+
+/// Act on a verified Bitbucket event (inline, or from the ingest worker).
+async fn process_bitbucket_event(
+    state: &AppState,
+    event_type: &str,
+    delivery_id: Option<String>,
+    body: &[u8],
+) -> Result<()> {
     if event_type.starts_with("repo:push") {
-        if let Ok(payload) = serde_json::from_slice::<BitbucketPushPayload>(&body) {
+        if let Ok(payload) = serde_json::from_slice::<BitbucketPushPayload>(body) {
             let (owner, name) = split_full_name(&payload.repository.full_name);
-            if let Some(commit) = payload
-                .push
-                .changes
-                .first()
-                .and_then(|c| c.new_target.as_ref())
-                .map(|t| t.hash.clone())
-            {
-                let _ = enqueue_repo_jobs(
-                    &state,
+            let change = payload.push.changes.first();
+            let forced = change.is_some_and(|c| c.forced);
+            let old = change.and_then(|c| c.old_target.as_ref());
+            if let (Some(old), None) = (old, change.and_then(|c| c.new_target.as_ref())) {
+                let git_ref = old.git_ref();
+                return branch_deleted(state, Platform::Bitbucket, &owner, &name, &git_ref).await;
+            }
+            if let Some(target) = change.and_then(|c| c.new_target.as_ref()) {
+                let git_ref = target.git_ref();
+                enqueue_repo_jobs(
+                    state,
                     Platform::Bitbucket,
                     &owner,
                     &name,
-                    &commit,
+                    &target.hash,
                     JobPriority::Normal,
-                    RepoEventKind::Push,
+                    RepoEventKind::Push {
+                        git_ref: &git_ref,
+                        default_branch: payload
+                            .repository
+                            .mainbranch
+                            .as_ref()
+                            .map(|b| b.name.as_str()),
+                        // Bitbucket push payloads carry no file or commit lists.
+                        changes: None,
+                        forced,
+                        before: old.map_or("", |old| old.hash.as_str()),
+                        commits: &[],
+                    },
                     None,
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
     } else if event_type == "pullrequest:comment_created" {
-        tracing::info!("Received Bitbucket pullrequest:comment_created (Consultant trigger)");
-        if let Ok(payload) = serde_json::from_slice::<BitbucketPRCommentPayload>(&body) {
+        tracing::info!("Received Bitbucket pullrequest:comment_created");
+        if let Ok(payload) = serde_json::from_slice::<BitbucketPRCommentPayload>(body) {
             if !modes::is_any_mention(&payload.comment.content.raw) {
-                return (StatusCode::OK, "OK");
+                return Ok(());
             }
             if payload
                 .actor
                 .as_ref()
                 .is_some_and(|u| u.username.eq_ignore_ascii_case("echidnabot"))
             {
-                return (StatusCode::OK, "OK");
+                return Ok(());
             }
             let (owner, name) = split_full_name(&payload.repository.full_name);
-            let _ = handle_consultant_mention(
-                &state,
+            // Comment payloads carry no repository permission. The actor
+            // is only trusted when the delivery was signed, i.e. the
+            // repository has a secret; otherwise anyone could claim to be
+            // the owner.
+            let signed = state
+                .store
+                .get_repository_by_name(Platform::Bitbucket, &owner, &name)
+                .await?
+                .is_some_and(|repo| repo.webhook_secret.is_some_and(|s| !s.is_empty()));
+            let commenter = Commenter {
+                login: payload
+                    .actor
+                    .as_ref()
+                    .map(|u| u.username.as_str())
+                    .filter(|login| signed && !login.is_empty()),
+                association: None,
+            };
+            if let Err(e) = handle_comment_command(
+                state,
                 Platform::Bitbucket,
                 &owner,
                 &name,
                 payload.pullrequest.id,
                 &payload.comment.content.raw,
+                commenter,
             )
-            .await;
+            .await
+            {
+                tracing::warn!("Comment command failed: {}", e);
+            }
         }
     }
 
-    (StatusCode::OK, "OK")
+    Ok(())
 }
 
 /// Codeberg / Forgejo / Gitea webhook handler (issue #62 scaffold).
@@ -423,26 +740,41 @@ async fn handle_bitbucket_webhook(
 async fn handle_codeberg_webhook(
     State(state): State<AppState>,
     headers: HeaderMap,
-    body: Bytes,
+    body: Body,
 ) -> impl IntoResponse {
     tracing::info!("Received Codeberg/Forgejo webhook");
 
-    // Verify HMAC-SHA256 signature if a secret is configured. Same
-    // primitive as GitHub but a different header name and a raw-hex
-    // (no `sha256=` prefix) value, hence its own helper.
-    if let Some(ref cb_config) = state.config.codeberg {
-        if let Some(ref secret) = cb_config.webhook_secret {
-            if let Err(e) = verify_codeberg_signature(&headers, &body, secret) {
-                tracing::warn!("Codeberg webhook signature verification failed: {}", e);
-                return (StatusCode::UNAUTHORIZED, "Invalid signature");
-            }
-        }
-    }
-
     let event_type = headers
         .get("X-Gitea-Event")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("unknown");
+
+    if !handles_event(Platform::Codeberg, event_type) {
+        tracing::debug!("Ignoring Codeberg event type: {}", event_type);
+        return (StatusCode::OK, "OK");
+    }
+    let body = match read_webhook_body(&state, &headers, body).await {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    // Verify HMAC-SHA256 signature if a secret is configured for the
+    // repository or globally. Same primitive as GitHub but a different
+    // header name and a raw-hex (no `sha256=` prefix) value, hence its
+    // own helper.
+    let config = state.config();
+    let global_secret = config.codeberg.as_ref().and_then(|c| c.webhook_secret.as_deref());
+    let secret =
+        match webhook_secret(&state, Platform::Codeberg, event_type, &body, global_secret).await {
+            Ok(secret) => secret,
+            Err(response) => return response,
+        };
+    if let Some(ref secret) = secret {
+        if let Err(e) = verify_codeberg_signature(&headers, &body, secret) {
+            tracing::warn!("Codeberg webhook signature verification failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, "Invalid signature");
+        }
+    }
+
     let delivery_id = headers
         .get("X-Gitea-Delivery")
         .and_then(|v| v.to_str().ok())
@@ -450,50 +782,90 @@ async fn handle_codeberg_webhook(
 
     tracing::info!("Codeberg event type: {}", event_type);
 
+    if let Some(ingest) = &state.ingest {
+        return ingest
+            .accept(state.store.as_ref(), Platform::Codeberg, event_type, delivery_id, &body)
+            .await;
+    }
+    if let Err(e) = process_codeberg_event(&state, event_type, delivery_id, &body).await {
+        tracing::warn!("Codeberg {} event processing failed: {}", event_type, e);
+    }
+
+    (StatusCode::OK, "OK")
+}
+
+/// Act on a verified Codeberg event (inline, or from the ingest worker).
+async fn process_codeberg_event(
+    state: &AppState,
+    event_type: &str,
+    delivery_id: Option<String>,
+    body: &[u8],
+) -> Result<()> {
     match event_type {
         "push" => {
-            if let Ok(payload) = serde_json::from_slice::<CodebergPushPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<CodebergPushPayload>(body) {
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                if is_null_sha(&payload.after) {
+                    return branch_deleted(state, Platform::Codeberg, &owner, &name, &payload.git_ref).await;
+                }
+                let changes = push_changes(
+                    &payload.commits,
+                    payload.total_commits,
+                    is_null_sha(&payload.before),
+                    state.config().server.max_push_files,
+                );
+                enqueue_repo_jobs(
+                    state,
                     Platform::Codeberg,
                     &owner,
                     &name,
                     &payload.after,
                     JobPriority::Normal,
-                    RepoEventKind::Push,
+                    RepoEventKind::Push {
+                        git_ref: &payload.git_ref,
+                        default_branch: payload.repository.default_branch.as_deref(),
+                        changes: changes.as_ref(),
+                        // Gitea push hooks don't flag force-pushes.
+                        forced: false,
+                        before: &payload.before,
+                        commits: &payload.commits,
+                    },
                     None,
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
         "pull_request" => {
-            if let Ok(payload) = serde_json::from_slice::<CodebergPullRequestPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<CodebergPullRequestPayload>(body) {
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = enqueue_repo_jobs(
-                    &state,
+                enqueue_repo_jobs(
+                    state,
                     Platform::Codeberg,
                     &owner,
                     &name,
                     &payload.pull_request.head.sha,
                     JobPriority::High,
-                    RepoEventKind::PullRequest,
+                    RepoEventKind::PullRequest {
+                        base_sha: payload.pull_request.base.as_ref().map(|b| b.sha.as_str()),
+                    },
                     Some(payload.pull_request.number),
                     delivery_id.clone(),
+                    None,
                 )
-                .await;
+                .await?;
             }
         }
         "issue_comment" => {
-            // Consultant-mode trigger — mirrors GitHub's handler.
+            // Comment commands — mirrors GitHub's handler.
             // TODO(#62): full payload-shape audit against Forgejo
             // docs; the field set below covers the happy path but
             // may need extending for edge cases (review comments
             // dispatched as `issue_comment`, etc.).
-            if let Ok(payload) = serde_json::from_slice::<CodebergIssueCommentPayload>(&body) {
+            if let Ok(payload) = serde_json::from_slice::<CodebergIssueCommentPayload>(body) {
                 if !modes::is_any_mention(&payload.comment.body) {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 if payload
                     .comment
@@ -501,18 +873,30 @@ async fn handle_codeberg_webhook(
                     .as_ref()
                     .is_some_and(|u| u.login.eq_ignore_ascii_case("echidnabot"))
                 {
-                    return (StatusCode::OK, "OK");
+                    return Ok(());
                 }
                 let (owner, name) = split_full_name(&payload.repository.full_name);
-                let _ = handle_consultant_mention(
-                    &state,
+                let commenter = Commenter {
+                    login: payload.comment.user.as_ref().map(|u| u.login.as_str()),
+                    association: payload
+                        .repository
+                        .permissions
+                        .as_ref()
+                        .and_then(CodebergPermissions::association),
+                };
+                if let Err(e) = handle_comment_command(
+                    state,
                     Platform::Codeberg,
                     &owner,
                     &name,
                     payload.issue.number,
                     &payload.comment.body,
+                    commenter,
                 )
-                .await;
+                .await
+                {
+                    tracing::warn!("Comment command failed: {}", e);
+                }
             }
         }
         _ => {
@@ -520,13 +904,35 @@ async fn handle_codeberg_webhook(
         }
     }
 
-    (StatusCode::OK, "OK")
+    Ok(())
+}
+
+/// PR attributes used to recognise dependency bumps.
+struct PullRequestMeta {
+    author: Option<String>,
+    labels: Vec<String>,
 }
 
 #[derive(Clone, Copy, Debug)]
-enum RepoEventKind {
-    Push,
-    PullRequest,
+enum RepoEventKind<'a> {
+    /// `git_ref` is the pushed ref (`refs/heads/main`); `default_branch`
+    /// comes from the payload when the platform includes it. `changes` is
+    /// set when the payload lists every file the push touched. `forced`
+    /// when the payload marks a force-push (GitHub, Bitbucket); `before`
+    /// is the branch's previous head, empty or all zeros when unknown or
+    /// new. `commits` are the pushed commits, oldest first, for the
+    /// `[push]` policy.
+    Push {
+        git_ref: &'a str,
+        default_branch: Option<&'a str>,
+        changes: Option<&'a ChangedFiles>,
+        forced: bool,
+        before: &'a str,
+        commits: &'a [PushCommit<'a>],
+    },
+    /// `base_sha` is the commit the PR targets, when the payload has it;
+    /// see `crate::baseline`.
+    PullRequest { base_sha: Option<&'a str> },
 }
 
 /// Enqueue proof jobs for a registered repository.
@@ -540,7 +946,7 @@ enum RepoEventKind {
 /// stored job can be correlated back to the exact webhook that produced it.
 #[tracing::instrument(
     name = "dispatch.job",
-    skip(state),
+    skip(state, pr_meta),
     fields(
         platform = ?platform,
         repo = %format!("{owner}/{name}"),
@@ -556,9 +962,10 @@ async fn enqueue_repo_jobs(
     name: &str,
     commit: &str,
     priority: JobPriority,
-    event_kind: RepoEventKind,
+    event_kind: RepoEventKind<'_>,
     pr_number: Option<u64>,
     delivery_id: Option<String>,
+    pr_meta: Option<&PullRequestMeta>,
 ) -> Result<()> {
     let repo = match state
         .store
@@ -579,32 +986,113 @@ async fn enqueue_repo_jobs(
 
     // Determine bot mode via cascade:
     //   1. target-repo `.machine_readable/bot_directives/echidnabot.a2ml`
-    //      (or `all.a2ml`) — fetched via PlatformAdapter::get_file_contents
+    //      (or `all.a2ml`, or `.echidnabot.toml`) — fetched via
+    //      PlatformAdapter::get_file_contents
     //   2. `repositories.mode` column (per-repo)
     //   3. `BotMode::default()` (= Verifier)
     //
     // Directive fetch is best-effort: an API error or missing file
     // returns None and the cascade falls through to the DB column.
-    let directive_content = match crate::adapters::build_adapter(&state.config, repo.platform) {
-        Ok(adapter) => {
-            let api_repo_id = RepoId {
-                platform: repo.platform,
-                owner: repo.owner.clone(),
-                name: repo.name.clone(),
-            };
-            modes::fetch_directive_via_adapter(adapter.as_ref(), &api_repo_id, None).await
-        }
+    let api_repo_id = RepoId {
+        platform: repo.platform,
+        owner: repo.owner.clone(),
+        name: repo.name.clone(),
+    };
+    let adapter = match crate::adapters::build_adapter(&state.config(), repo.platform) {
+        Ok(adapter) => Some(adapter),
         Err(e) => {
             tracing::debug!("No adapter for directive fetch ({}); using DB cascade", e);
             None
         }
     };
+    let directive_content = match adapter {
+        Some(ref adapter) => {
+            modes::fetch_directive_via_adapter(adapter.as_ref(), &api_repo_id, None).await
+        }
+        None => None,
+    };
+
+    // Unconfigured repo: offer a `.echidnabot.toml` once. Best-effort —
+    // the event is processed with the defaults either way.
+    if let (None, Some(adapter)) = (&directive_content, &adapter) {
+        if state.config().bot.config_pr && repo.config_pr_offered_at.is_none() {
+            if let Err(e) = crate::bootstrap::offer_config_pr(
+                state.store.as_ref(),
+                adapter.as_ref(),
+                &repo,
+                state.mode_selector.default_mode,
+            )
+            .await
+            {
+                tracing::warn!("Config PR for {} not offered: {}", repo.full_name(), e);
+            }
+        }
+    }
+
+    // Push branch filter: the manifest's `[branches]` table, else the
+    // repository settings; with neither, only the default branch.
+    let mut pushed_branch = None;
+    if let RepoEventKind::Push { git_ref, default_branch, .. } = event_kind {
+        let Some(branch) = git_ref.strip_prefix("refs/heads/") else {
+            tracing::info!("Push to {} is not a branch; skipping", git_ref);
+            return Ok(());
+        };
+        let filter = directive_content
+            .as_deref()
+            .and_then(modes::RepoManifest::parse)
+            .map(|m| m.branches)
+            .filter(|b| !b.is_empty())
+            .unwrap_or_else(|| repo.branch_filter());
+        let default_branch = match (default_branch, &adapter) {
+            (Some(branch), _) => Some(branch.to_string()),
+            (None, Some(adapter)) if filter.include.is_empty() => {
+                adapter.get_default_branch(&api_repo_id).await.ok()
+            }
+            _ => None,
+        };
+        if !filter.allows(branch, default_branch.as_deref()) {
+            tracing::info!(
+                "Push to branch {} of {} is filtered out; skipping",
+                branch,
+                repo.full_name()
+            );
+            return Ok(());
+        }
+        // Remembered for the results feeds, which follow the default branch.
+        if default_branch.is_some() && default_branch != repo.default_branch {
+            let mut updated = repo.clone();
+            updated.default_branch = default_branch;
+            if let Err(e) = state.store.update_repository(&updated).await {
+                tracing::debug!("Default branch of {} not recorded: {}", repo.full_name(), e);
+            }
+        }
+        pushed_branch = Some(branch.to_string());
+    }
+
+    // A force-push drops the old head and the commits below it that the
+    // new head doesn't share; their jobs stop reporting on PRs.
+    if let (RepoEventKind::Push { forced: true, before, .. }, Some(branch)) = (event_kind, &pushed_branch) {
+        if !before.is_empty() && !is_null_sha(before) && before != commit {
+            let dropped = match &adapter {
+                Some(adapter) => adapter
+                    .list_commits_between(&api_repo_id, commit, before)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::debug!("Commits dropped from {} not listed: {}", branch, e);
+                        vec![before.to_string()]
+                    }),
+                None => vec![before.to_string()],
+            };
+            crate::scheduler::supersede::force_pushed(state.store.as_ref(), repo.id, branch, commit, &dropped)
+                .await?;
+        }
+    }
     let mode = modes::resolve_mode_with_daemon_default(
         &repo,
         directive_content.as_deref(),
         state.mode_selector.default_mode,
     );
-    let is_pr = matches!(event_kind, RepoEventKind::PullRequest);
+    let is_pr = matches!(event_kind, RepoEventKind::PullRequest { .. });
 
     tracing::info!(
         "Bot mode: {} (repo: {}, event: {})",
@@ -613,8 +1101,27 @@ async fn enqueue_repo_jobs(
         if is_pr { "pull_request" } else { "push" },
     );
 
+    // Dependency bumps opted in via `[dependency_prs]` are always fully
+    // verified, whatever the mode and `check_on_pr` say. Only those a
+    // dependency bot opened may be auto-merged: anyone can add a label.
+    let dependency_pr = pr_meta.and_then(|meta| {
+        let manifest = directive_content.as_deref().and_then(modes::RepoManifest::parse)?;
+        let policy = &manifest.dependency_prs;
+        policy
+            .matches(meta.author.as_deref(), &meta.labels)
+            .then(|| policy.is_bot_author(meta.author.as_deref()))
+    });
+    let dependency_update = dependency_pr.is_some();
+    if dependency_update {
+        tracing::info!(
+            "Dependency PR #{} on {} — applying full-verification policy",
+            pr_number.unwrap_or_default(),
+            repo.full_name(),
+        );
+    }
+
     // Consultant mode only triggers on explicit @echidnabot mentions
-    if !modes::should_auto_trigger(mode, is_pr) {
+    if !dependency_update && !modes::should_auto_trigger(mode, is_pr) {
         tracing::info!(
             "Mode {} does not auto-trigger for this event; skipping",
             mode,
@@ -623,26 +1130,180 @@ async fn enqueue_repo_jobs(
     }
 
     let should_enqueue = match event_kind {
-        RepoEventKind::Push => repo.check_on_push,
-        RepoEventKind::PullRequest => repo.check_on_pr,
+        RepoEventKind::Push { .. } => repo.check_on_push,
+        RepoEventKind::PullRequest { .. } => repo.check_on_pr,
     };
 
-    if !should_enqueue {
+    if !should_enqueue && !dependency_update {
         return Ok(());
     }
 
+    // `.echidnaignore` at the pushed commit: a push that only touches
+    // ignored files has nothing to verify.
+    let mut pushed_changes = match event_kind {
+        RepoEventKind::Push { changes, .. } => changes.cloned(),
+        RepoEventKind::PullRequest { .. } => None,
+    };
+    if let (Some(changes), Some(adapter)) = (&mut pushed_changes, &adapter) {
+        if !changes.is_empty() {
+            let ignore = modes::IgnoreFile::fetch(adapter.as_ref(), &api_repo_id, commit).await;
+            changes.retain(|path| !ignore.is_ignored(path));
+            if changes.is_empty() {
+                tracing::info!(
+                    "Push to {} only touches files in {}; skipping",
+                    repo.full_name(),
+                    modes::IGNORE_FILE
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    // Incremental push: only changed files and their dependents, per the
+    // recorded dependency graph. `None` from `plan_push` means a full run.
+    let changes = pushed_changes
+        .as_ref()
+        .filter(|_| state.config().bot.incremental);
+    // PR baseline: the base commit's results, verified first if missing.
+    let base_sha = match event_kind {
+        RepoEventKind::PullRequest { base_sha } if state.config().bot.baseline => {
+            base_sha.filter(|sha| *sha != commit && !is_null_sha(sha))
+        }
+        _ => None,
+    };
+
+    // A PR's new head, or a force-pushed branch, replaces the old
+    // commit's jobs.
+    if state.config().bot.cancel_superseded {
+        let scope = match (event_kind, pr_number, &pushed_branch) {
+            (RepoEventKind::PullRequest { .. }, Some(number), _) => Some(SupersedeScope::PullRequest(number)),
+            (RepoEventKind::Push { forced: true, .. }, None, Some(branch)) => {
+                Some(SupersedeScope::Branch(branch.clone()))
+            }
+            _ => None,
+        };
+        if let Some(scope) = scope {
+            crate::scheduler::supersede::cancel_superseded(
+                state.store.as_ref(),
+                &state.scheduler,
+                repo.id,
+                scope,
+                commit,
+            )
+            .await?;
+        }
+    }
+
+    // Extension routing, with the manifest's `[provers.extensions]`.
+    let routes = changes.map(|_| {
+        directive_content
+            .as_deref()
+            .and_then(modes::RepoManifest::parse)
+            .map(|m| m.extension_table())
+            .unwrap_or_else(|| crate::dispatcher::ExtensionTable::builtin().clone())
+    });
+
+    let mut enqueued = 0;
     for prover in &repo.enabled_provers {
-        let job = ProofJob::new(repo.id, commit.to_string(), prover.clone(), Vec::new())
+        if let Some(base_sha) = base_sha {
+            if crate::baseline::needs_baseline(state.store.as_ref(), repo.id, prover, base_sha).await {
+                let job = ProofJob::new(repo.id, base_sha.to_string(), prover.clone(), Vec::new())
+                    .with_priority(priority)
+                    .with_context(None, delivery_id.clone());
+                let mut record = ProofJobRecord::from(job.clone());
+                state.store.create_job(&record).await?;
+                if state.scheduler.enqueue(job).await?.is_some() {
+                    enqueued += 1;
+                } else {
+                    // A record left queued would count as the baseline
+                    // (`needs_baseline`) and stop later PRs queuing one.
+                    record.status = crate::scheduler::JobStatus::Cancelled;
+                    record.completed_at = Some(chrono::Utc::now());
+                    record.error_message =
+                        Some("Not queued: duplicate, or the queue was full".to_string());
+                    state.store.update_job(&record).await?;
+                }
+            }
+        }
+        let mut file_paths = Vec::new();
+        let mut decision = None;
+        if let (Some(changes), Some(routes)) = (changes, &routes) {
+            match crate::depgraph::plan_push(state.store.as_ref(), repo.id, prover, routes, changes).await {
+                Some(files) if files.is_empty() => {
+                    tracing::info!(
+                        "Push to {} touches no {} proofs; skipping",
+                        repo.full_name(),
+                        prover.display_name(),
+                    );
+                    continue;
+                }
+                Some(files) => {
+                    decision = Some(crate::decision::DecisionTrace {
+                        cache_hits: vec![format!(
+                            "incremental: {} file(s) affected by the push",
+                            files.len()
+                        )],
+                        ..Default::default()
+                    });
+                    file_paths = files;
+                }
+                None => {}
+            }
+        }
+        let job = ProofJob::new(repo.id, commit.to_string(), prover.clone(), file_paths)
             .with_priority(priority)
-            .with_context(pr_number, delivery_id.clone());
-        let record = ProofJobRecord::from(job.clone());
+            .with_context(pr_number, delivery_id.clone())
+            .with_base(base_sha.map(str::to_string))
+            .with_branch(pushed_branch.clone())
+            .with_dependency_update(dependency_pr == Some(true));
+        let mut record = ProofJobRecord::from(job.clone());
+        record.payload.decision = decision;
         state.store.create_job(&record).await?;
         let _ = state.scheduler.enqueue(job).await?;
+        enqueued += 1;
+    }
+
+    // `[push] commits`: earlier commits of the push, each verified in
+    // full at low priority so bisecting the branch can trust them.
+    if let RepoEventKind::Push { commits, .. } = event_kind {
+        let policy = directive_content
+            .as_deref()
+            .and_then(modes::RepoManifest::parse)
+            .map(|m| m.push)
+            .unwrap_or_default();
+        let earlier = earlier_commits(adapter.as_deref(), &api_repo_id, &policy, commits, commit).await;
+        let mut jobs = Vec::new();
+        for sha in &earlier {
+            for prover in &repo.enabled_provers {
+                jobs.push(
+                    ProofJob::new(repo.id, sha.clone(), prover.clone(), Vec::new())
+                        .with_priority(JobPriority::Low)
+                        .with_context(None, delivery_id.clone())
+                        .with_branch(pushed_branch.clone()),
+                );
+            }
+        }
+        let records: Vec<ProofJobRecord> = jobs.iter().cloned().map(ProofJobRecord::from).collect();
+        for record in &records {
+            state.store.create_job(record).await?;
+        }
+        let ids = state.scheduler.enqueue_batch(jobs).await?;
+        for (mut record, id) in records.into_iter().zip(ids) {
+            if id.is_some() {
+                enqueued += 1;
+                continue;
+            }
+            record.status = crate::scheduler::JobStatus::Cancelled;
+            record.completed_at = Some(chrono::Utc::now());
+            record.error_message =
+                Some("Not queued: duplicate, or no queue room for the push's earlier commits".to_string());
+            state.store.update_job(&record).await?;
+        }
     }
 
     tracing::info!(
         "Enqueued {} job(s) for {} in {} mode",
-        repo.enabled_provers.len(),
+        enqueued,
         repo.full_name(),
         mode,
     );
@@ -650,6 +1311,132 @@ async fn enqueue_repo_jobs(
     Ok(())
 }
 
+/// A push deleted `git_ref`. For a branch, its pending jobs are cancelled
+/// and all its jobs orphaned (see [`crate::scheduler::supersede`]); a
+/// stored default branch of that name is forgotten, so the freshness
+/// reconciler asks the platform again. Tag deletions are ignored.
+async fn branch_deleted(state: &AppState, platform: Platform, owner: &str, name: &str, git_ref: &str) -> Result<()> {
+    let Some(branch) = git_ref.strip_prefix("refs/heads/") else {
+        return Ok(());
+    };
+    let Some(mut repo) = state.store.get_repository_by_name(platform, owner, name).await? else {
+        tracing::info!("Repository not registered: {}/{}", owner, name);
+        return Ok(());
+    };
+    let cancelled =
+        crate::scheduler::supersede::branch_deleted(state.store.as_ref(), &state.scheduler, repo.id, branch).await?;
+    tracing::info!(
+        "Branch {} of {} deleted; cancelled {} job(s)",
+        branch,
+        repo.full_name(),
+        cancelled
+    );
+    if repo.default_branch.as_deref() == Some(branch) {
+        repo.default_branch = None;
+        state.store.update_repository(&repo).await?;
+    }
+    Ok(())
+}
+
+/// Earlier commits of a push to verify besides `head`, oldest first, per
+/// the `[push]` policy: none, all, or the merge commits (asked of the
+/// platform; a failed lookup counts as no merge). The newest ones win
+/// when there are more than `max_commits - 1`.
+async fn earlier_commits(
+    adapter: Option<&dyn PlatformAdapter>,
+    repo: &RepoId,
+    policy: &modes::PushSection,
+    commits: &[PushCommit<'_>],
+    head: &str,
+) -> Vec<String> {
+    if policy.commits == modes::PushCommits::Head {
+        return Vec::new();
+    }
+    let budget = policy.max_commits.saturating_sub(1);
+    let mut picked = Vec::new();
+    for commit in commits.iter().rev().filter(|c| !c.id.is_empty() && c.id != head) {
+        if picked.len() >= budget {
+            break;
+        }
+        let wanted = match (policy.commits, adapter) {
+            (modes::PushCommits::Merges, Some(adapter)) => {
+                match adapter.get_commit_parents(repo, &commit.id).await {
+                    Ok(parents) => parents.len() > 1,
+                    Err(e) => {
+                        tracing::debug!(
+                            "Parents of {} @ {:.8} not looked up: {}",
+                            repo.full_name(),
+                            commit.id,
+                            e
+                        );
+                        false
+                    }
+                }
+            }
+            (modes::PushCommits::Merges, None) => false,
+            _ => true,
+        };
+        if wanted {
+            picked.push(commit.id.to_string());
+        }
+    }
+    picked.reverse();
+    picked
+}
+
+/// Handle an auto-merge label being added to a PR.
+///
+/// Returns `Ok(true)` when the label is an `[auto_merge]` trigger and the
+/// head commit already has jobs — the merge is attempted now if they all
+/// passed, or by the last job to finish if some are still running.
+/// `Ok(false)` means the event should be processed as usual (not a
+/// trigger label, or the commit has never been verified).
+async fn merge_on_label(
+    state: &AppState,
+    platform: Platform,
+    owner: &str,
+    name: &str,
+    pr_number: u64,
+    head_sha: &str,
+    label: &str,
+) -> Result<bool> {
+    let Some(repo) = state
+        .store
+        .get_repository_by_name(platform, owner, name)
+        .await?
+    else {
+        return Ok(false);
+    };
+    let adapter = crate::adapters::build_adapter(&state.config(), repo.platform)?;
+    let api_repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let manifest = modes::fetch_directive_via_adapter(adapter.as_ref(), &api_repo_id, None)
+        .await
+        .as_deref()
+        .and_then(modes::RepoManifest::parse)
+        .unwrap_or_default();
+    if !manifest.auto_merge.is_trigger(label) {
+        return Ok(false);
+    }
+    let Some(policy) = crate::automerge::MergePolicy::labelled(&manifest) else {
+        return Ok(false);
+    };
+
+    let checks = crate::automerge::commit_checks(state.store.as_ref(), repo.id, head_sha).await?;
+    if checks.total() == 0 {
+        return Ok(false);
+    }
+    crate::automerge::merge_if_green(
+        state.store.as_ref(),
+        adapter.as_ref(),
+        &repo,
+        head_sha,
+        pr_number,
+        &policy,
+    )
+    .await;
+    Ok(true)
+}
+
 /// Phase 6 — Consultant mode Q&A handler.
 ///
 /// Triggered by `issue_comment` events that contain an `@echidnabot`
@@ -662,7 +1449,7 @@ async fn enqueue_repo_jobs(
 /// the case per echidnabot AGENTIC.a2ml [exceptions.boj-only-mcp]), the
 /// handler degrades to the local-data response only and notes the
 /// degraded state in the comment.
-async fn handle_consultant_mention(
+pub(crate) async fn handle_consultant_mention(
     state: &AppState,
     platform: Platform,
     owner: &str,
@@ -752,23 +1539,33 @@ async fn handle_consultant_mention(
         }
     };
 
-    let adapter = crate::adapters::build_adapter(&state.config, repo.platform)?;
+    let announcement = crate::announcement::active(state.store.as_ref()).await;
+    let final_body = crate::announcement::with_banner(announcement.as_ref(), &final_body);
+    let adapter = crate::adapters::build_adapter(&state.config(), repo.platform)?;
     let repo_id = RepoId {
         platform: repo.platform,
         owner: repo.owner.clone(),
         name: repo.name.clone(),
     };
     let pr_id = PrId(pr_number.to_string());
-    if let Err(err) = adapter
-        .create_comment(&repo_id, pr_id, &final_body)
-        .await
-    {
-        tracing::warn!(
+    match adapter.create_comment(&repo_id, pr_id, &final_body).await {
+        Ok(_) => {
+            crate::audit::record(
+                state.store.as_ref(),
+                crate::audit::BOT,
+                crate::audit::AuditAction::CommentPosted,
+                Some(&repo),
+                Some(format!("PR #{}", pr_number)),
+                &final_body,
+            )
+            .await
+        }
+        Err(err) => tracing::warn!(
             "Consultant create_comment failed for {} PR #{}: {}",
             repo.full_name(),
             pr_number,
             err
-        );
+        ),
     }
 
     Ok(())
@@ -825,6 +1622,163 @@ fn build_consultant_summary(
     out
 }
 
+/// The secret a delivery must be signed with: for an event about a
+/// single repository, that repository's own `webhook_secret` when it has
+/// one; otherwise the platform-wide `global` secret.
+///
+/// The repository is read from the not-yet-verified body, which is safe:
+/// naming a repository only selects the secret the sender must know.
+/// When the repository has its own secret the global one is not
+/// accepted for it, so tenants on a shared install cannot sign for each
+/// other. Account-level events (see [`is_repository_event`]) never use a
+/// repository's secret, whatever repository the body names. A secret
+/// that is still sealed (its tenant key is unavailable) is refused with
+/// 401 rather than used as the key. A store failure is answered with 500
+/// so the platform retries.
+async fn webhook_secret(
+    state: &AppState,
+    platform: Platform,
+    event_type: &str,
+    body: &[u8],
+    global: Option<&str>,
+) -> std::result::Result<Option<String>, (StatusCode, &'static str)> {
+    if !is_repository_event(platform, event_type) {
+        return Ok(global.map(String::from));
+    }
+    let full_name = serde_json::from_slice::<PayloadRepository>(body)
+        .ok()
+        .and_then(|payload| match platform {
+            Platform::GitLab => payload.project.map(|p| p.path_with_namespace),
+            Platform::GitHub | Platform::Bitbucket | Platform::Codeberg => {
+                payload.repository.map(|r| r.full_name)
+            }
+        });
+    let Some(full_name) = full_name else {
+        return Ok(global.map(String::from));
+    };
+
+    let (owner, name) = split_full_name(&full_name);
+    match state.store.get_repository_by_name(platform, &owner, &name).await {
+        Ok(Some(repo)) if repo.webhook_secret.as_deref().is_some_and(|s| !s.is_empty()) => {
+            match repo.webhook_secret {
+                Some(secret) if crate::tenancy::is_sealed(&secret) => {
+                    tracing::error!(
+                        "Webhook secret of {} can't be unsealed; refusing the delivery",
+                        full_name
+                    );
+                    Err((StatusCode::UNAUTHORIZED, "Webhook secret unavailable"))
+                }
+                secret => Ok(secret),
+            }
+        }
+        Ok(_) => Ok(global.map(String::from)),
+        Err(e) => {
+            tracing::error!("Webhook secret lookup for {} failed: {}", full_name, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to look up repository"))
+        }
+    }
+}
+
+/// Just enough of a payload to name its repository, for the secret
+/// lookup. Every other field is skipped without building a JSON tree.
+#[derive(Deserialize)]
+struct PayloadRepository<'a> {
+    #[serde(borrow, default)]
+    repository: Option<PayloadFullName<'a>>,
+    #[serde(borrow, default)]
+    project: Option<PayloadPathWithNamespace<'a>>,
+}
+
+#[derive(Deserialize)]
+struct PayloadFullName<'a> {
+    #[serde(borrow)]
+    full_name: Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+struct PayloadPathWithNamespace<'a> {
+    #[serde(borrow)]
+    path_with_namespace: Cow<'a, str>,
+}
+
+/// Whether `process_*_event` acts on `event_type` at all. Other events
+/// are answered from their headers alone, before the body is read.
+fn handles_event(platform: Platform, event_type: &str) -> bool {
+    match platform {
+        Platform::GitHub => matches!(
+            event_type,
+            "push"
+                | "pull_request"
+                | "check_suite"
+                | "check_run"
+                | "issue_comment"
+                | "installation"
+                | "installation_repositories"
+                | "ping"
+        ),
+        Platform::GitLab => matches!(event_type, "Push Hook" | "Merge Request Hook" | "Note Hook"),
+        Platform::Codeberg => matches!(event_type, "push" | "pull_request" | "issue_comment"),
+        Platform::Bitbucket => {
+            event_type.starts_with("repo:push") || event_type == "pullrequest:comment_created"
+        }
+    }
+}
+
+/// Buffer a webhook body, answering `413` when it is larger than
+/// `[server] max_webhook_body_bytes` and `408` when it takes longer than
+/// `webhook_read_timeout_secs` to arrive. A `Content-Length` over the
+/// limit is refused before anything is read.
+async fn read_webhook_body(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: Body,
+) -> std::result::Result<Bytes, (StatusCode, &'static str)> {
+    let (limit, timeout_secs) = {
+        let config = state.config();
+        (config.server.max_webhook_body_bytes, config.server.webhook_read_timeout_secs)
+    };
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let too_large = (StatusCode::PAYLOAD_TOO_LARGE, "Payload too large");
+    if declared.is_some_and(|len| len > limit) {
+        tracing::warn!("Refusing webhook body of {} bytes (limit {})", declared.unwrap_or_default(), limit);
+        return Err(too_large);
+    }
+    let read = axum::body::to_bytes(body, limit);
+    let read = if timeout_secs > 0 {
+        tokio::time::timeout(Duration::from_secs(timeout_secs), read)
+            .await
+            .map_err(|_| {
+                tracing::warn!("Webhook body not received within {}s", timeout_secs);
+                (StatusCode::REQUEST_TIMEOUT, "Request timeout")
+            })?
+    } else {
+        read.await
+    };
+    read.map_err(|e| {
+        tracing::warn!("Refusing webhook body over {} bytes: {}", limit, e);
+        too_large
+    })
+}
+
+/// Whether `event_type` is about the one repository its payload names,
+/// so that repository's secret may sign it.
+fn is_repository_event(platform: Platform, event_type: &str) -> bool {
+    match platform {
+        Platform::GitHub => matches!(
+            event_type,
+            "push" | "pull_request" | "check_suite" | "check_run" | "issue_comment" | "ping"
+        ),
+        Platform::GitLab => matches!(event_type, "Push Hook" | "Merge Request Hook" | "Note Hook"),
+        Platform::Codeberg => matches!(event_type, "push" | "pull_request" | "issue_comment"),
+        Platform::Bitbucket => {
+            event_type.starts_with("repo:") || event_type.starts_with("pullrequest:")
+        }
+    }
+}
+
 fn split_full_name(full_name: &str) -> (String, String) {
     let mut parts = full_name.splitn(2, '/');
     let owner = parts.next().unwrap_or_default().to_string();
@@ -832,14 +1786,77 @@ fn split_full_name(full_name: &str) -> (String, String) {
     (owner, name)
 }
 
+/// Files touched by a push, or `None` when the payload cannot be trusted
+/// to list all of them: history was rewritten or the branch is new
+/// (`rewritten`), no commits were listed, or the platform truncated the
+/// commit list below `total`. Also `None` past `max_files` changed files
+/// (`[server] max_push_files`): a push that large gets a full run rather
+/// than an incremental plan.
+fn push_changes(
+    commits: &[PushCommit<'_>],
+    total: Option<usize>,
+    rewritten: bool,
+    max_files: usize,
+) -> Option<ChangedFiles> {
+    if rewritten || commits.is_empty() || total.is_some_and(|t| t > commits.len()) {
+        return None;
+    }
+    let mut changes = ChangedFiles::default();
+    for commit in commits {
+        changes.apply_commit(&commit.added, &commit.modified, &commit.removed);
+        if changes.len() > max_files {
+            return None;
+        }
+    }
+    Some(changes)
+}
+
+/// All-zero SHA used as `before` when a push creates a branch, and as
+/// `after` when it deletes one.
+fn is_null_sha(sha: &str) -> bool {
+    !sha.is_empty() && sha.bytes().all(|b| b == b'0')
+}
+
+/// One commit of a GitHub, GitLab or Gitea/Forgejo push payload, oldest
+/// first. Paths borrow from the request body unless JSON escapes force a
+/// copy; the commit's other fields are skipped unread.
+#[derive(Debug, Deserialize)]
+struct PushCommit<'a> {
+    #[serde(borrow, default)]
+    id: Cow<'a, str>,
+    #[serde(borrow, default)]
+    added: Vec<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    modified: Vec<Cow<'a, str>>,
+    #[serde(borrow, default)]
+    removed: Vec<Cow<'a, str>>,
+}
+
 #[derive(Deserialize)]
-struct GitHubPushPayload {
+struct GitHubPushPayload<'a> {
+    #[serde(rename = "ref", default)]
+    git_ref: String,
+    #[serde(default)]
+    before: String,
     after: String,
+    #[serde(default)]
+    created: bool,
+    #[serde(default)]
+    forced: bool,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(borrow, default)]
+    commits: Vec<PushCommit<'a>>,
     repository: GitHubRepo,
 }
 
 #[derive(Deserialize)]
 struct GitHubPullRequestPayload {
+    #[serde(default)]
+    action: String,
+    /// The label added or removed, for `labeled` / `unlabeled`.
+    #[serde(default)]
+    label: Option<GitHubLabel>,
     pull_request: GitHubPullRequest,
     repository: GitHubRepo,
 }
@@ -853,6 +1870,33 @@ struct GitHubCheckSuitePayload {
 #[derive(Deserialize)]
 struct GitHubRepo {
     full_name: String,
+    /// Sent with push events.
+    #[serde(default)]
+    default_branch: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubCheckRunPayload {
+    action: String,
+    check_run: GitHubCheckRunRef,
+    /// Present only when `action == "requested_action"`.
+    #[serde(default)]
+    requested_action: Option<GitHubRequestedAction>,
+    repository: GitHubRepo,
+    #[serde(default)]
+    sender: Option<GitHubUser>,
+}
+
+#[derive(Deserialize)]
+struct GitHubCheckRunRef {
+    /// The job id we set when creating the check run.
+    #[serde(default)]
+    external_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubRequestedAction {
+    identifier: String,
 }
 
 #[derive(Deserialize)]
@@ -861,6 +1905,18 @@ struct GitHubPullRequest {
     /// than the commit page.
     number: u64,
     head: GitHubHead,
+    /// The target branch's commit; the baseline for the comparison.
+    #[serde(default)]
+    base: Option<GitHubHead>,
+    #[serde(default)]
+    user: Option<GitHubUser>,
+    #[serde(default)]
+    labels: Vec<GitHubLabel>,
+}
+
+#[derive(Deserialize)]
+struct GitHubLabel {
+    name: String,
 }
 
 #[derive(Deserialize)]
@@ -880,6 +1936,10 @@ struct GitHubComment {
     body: String,
     #[serde(default)]
     user: Option<GitHubUser>,
+    /// `OWNER`, `MEMBER`, `COLLABORATOR`, `CONTRIBUTOR`, … — drives the
+    /// comment-command permission map.
+    #[serde(default)]
+    author_association: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -902,9 +1962,18 @@ struct GitHubHead {
 }
 
 #[derive(Deserialize)]
-struct GitLabPushPayload {
+struct GitLabPushPayload<'a> {
+    #[serde(rename = "ref", default)]
+    git_ref: String,
+    #[serde(default)]
+    before: String,
     after: String,
     checkout_sha: Option<String>,
+    /// At most 20 commits are listed; this is the real count.
+    #[serde(default)]
+    total_commits_count: Option<usize>,
+    #[serde(borrow, default)]
+    commits: Vec<PushCommit<'a>>,
     project: GitLabProject,
 }
 
@@ -921,6 +1990,14 @@ struct GitLabMergeAttributes {
     /// GitLab's per-project MR identifier (the human-facing !N number).
     /// Equivalent to GitHub's PR number for plumbing purposes.
     iid: Option<u64>,
+    #[serde(default)]
+    diff_refs: Option<GitLabDiffRefs>,
+}
+
+#[derive(Deserialize)]
+struct GitLabDiffRefs {
+    #[serde(default)]
+    base_sha: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -931,6 +2008,8 @@ struct GitLabCommit {
 #[derive(Deserialize)]
 struct GitLabProject {
     path_with_namespace: String,
+    #[serde(default)]
+    default_branch: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -974,6 +2053,13 @@ struct BitbucketPushPayload {
 #[derive(Deserialize)]
 struct BitbucketRepo {
     full_name: String,
+    #[serde(default)]
+    mainbranch: Option<BitbucketBranch>,
+}
+
+#[derive(Deserialize)]
+struct BitbucketBranch {
+    name: String,
 }
 
 #[derive(Deserialize)]
@@ -983,13 +2069,36 @@ struct BitbucketPush {
 
 #[derive(Deserialize)]
 struct BitbucketChange {
+    /// `None` when the push deleted the branch.
     #[serde(rename = "new")]
     new_target: Option<BitbucketTarget>,
+    /// `None` when the push created it.
+    #[serde(rename = "old", default)]
+    old_target: Option<BitbucketTarget>,
+    #[serde(default)]
+    forced: bool,
 }
 
 #[derive(Deserialize)]
 struct BitbucketTarget {
     hash: String,
+    /// Branch or tag name.
+    #[serde(default)]
+    name: Option<String>,
+    /// `branch` or `tag`.
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+}
+
+impl BitbucketTarget {
+    /// Bitbucket names the ref instead of giving a `refs/…` path.
+    fn git_ref(&self) -> String {
+        match (self.kind.as_deref(), self.name.as_deref()) {
+            (Some("branch") | None, Some(branch)) => format!("refs/heads/{}", branch),
+            (Some("tag"), Some(tag)) => format!("refs/tags/{}", tag),
+            _ => String::new(),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -1037,12 +2146,48 @@ struct BitbucketActor {
 #[derive(Deserialize)]
 struct CodebergRepo {
     full_name: String,
+    #[serde(default)]
+    default_branch: Option<String>,
+    /// Gitea fills this in with the access of the user whose action
+    /// fired the webhook (the commenter, on `issue_comment`).
+    #[serde(default)]
+    permissions: Option<CodebergPermissions>,
 }
 
 #[derive(Deserialize)]
-struct CodebergPushPayload {
+struct CodebergPermissions {
+    #[serde(default)]
+    admin: bool,
+    #[serde(default)]
+    push: bool,
+}
+
+impl CodebergPermissions {
+    /// The GitHub `author_association` giving the same role: repository
+    /// admins are maintainers, users with write access collaborators.
+    fn association(&self) -> Option<&'static str> {
+        if self.admin {
+            Some("MEMBER")
+        } else if self.push {
+            Some("COLLABORATOR")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CodebergPushPayload<'a> {
+    #[serde(rename = "ref", default)]
+    git_ref: String,
+    #[serde(default)]
+    before: String,
     /// The `after` SHA — same convention as GitHub's push hook.
     after: String,
+    #[serde(default)]
+    total_commits: Option<usize>,
+    #[serde(borrow, default)]
+    commits: Vec<PushCommit<'a>>,
     repository: CodebergRepo,
 }
 
@@ -1057,6 +2202,8 @@ struct CodebergPullRequest {
     /// Per-repo PR index (Gitea's equivalent of GitHub's PR number).
     number: u64,
     head: CodebergPullRequestHead,
+    #[serde(default)]
+    base: Option<CodebergPullRequestHead>,
 }
 
 #[derive(Deserialize)]
@@ -1132,11 +2279,31 @@ fn verify_github_signature(
     headers: &HeaderMap,
     body: &Bytes,
     secret: &str,
+) -> std::result::Result<(), String> {
+    verify_hub_signature(headers, "X-Hub-Signature-256", body, secret)
+}
+
+/// Verify a Bitbucket webhook signature: the GitHub format
+/// (`sha256=<hex>`) in `X-Hub-Signature`.
+fn verify_bitbucket_signature(
+    headers: &HeaderMap,
+    body: &Bytes,
+    secret: &str,
+) -> std::result::Result<(), String> {
+    verify_hub_signature(headers, "X-Hub-Signature", body, secret)
+}
+
+/// Verify an HMAC-SHA256 `sha256=<hex>` signature in `header`.
+fn verify_hub_signature(
+    headers: &HeaderMap,
+    header: &str,
+    body: &Bytes,
+    secret: &str,
 ) -> std::result::Result<(), String> {
     let signature = headers
-        .get("X-Hub-Signature-256")
+        .get(header)
         .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| "Missing X-Hub-Signature-256 header".to_string())?;
+        .ok_or_else(|| format!("Missing {} header", header))?;
 
     // Signature format: "sha256=<hex>"
     let signature = signature
@@ -1200,6 +2367,35 @@ fn test_verify_codeberg_signature() {
         assert!(verify_codeberg_signature(&headers, &body, secret).is_ok());
     }
 
+    #[test]
+    fn test_verify_bitbucket_signature() {
+        let secret = "test-secret";
+        let body = Bytes::from(r#"{"test": "payload"}"#);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(&body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        // GitHub's header name is not Bitbucket's.
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Hub-Signature-256", signature.parse().unwrap());
+        assert!(verify_bitbucket_signature(&headers, &body, secret).is_err());
+
+        headers.insert("X-Hub-Signature", signature.parse().unwrap());
+        assert!(verify_bitbucket_signature(&headers, &body, secret).is_ok());
+        assert!(verify_bitbucket_signature(&headers, &body, "other-secret").is_err());
+    }
+
+    #[test]
+    fn test_repository_secrets_only_sign_repository_events() {
+        assert!(is_repository_event(Platform::GitHub, "push"));
+        assert!(is_repository_event(Platform::GitLab, "Merge Request Hook"));
+        assert!(!is_repository_event(Platform::GitHub, "installation"));
+        assert!(!is_repository_event(Platform::GitHub, "installation_repositories"));
+        assert!(!is_repository_event(Platform::GitHub, "organization"));
+        assert!(!is_repository_event(Platform::Codeberg, "repository"));
+    }
+
     #[test]
     fn test_verify_codeberg_signature_missing_header() {
         let body = Bytes::from(r#"{"test": "payload"}"#);
@@ -1220,4 +2416,53 @@ fn test_verify_codeberg_signature_mismatch() {
         );
         assert!(verify_codeberg_signature(&headers, &body, "secret").is_err());
     }
+
+    #[test]
+    fn test_push_changes_only_when_complete() {
+        let payload: GitHubPushPayload = serde_json::from_str(
+            r#"{"ref": "refs/heads/main", "before": "abc", "after": "def",
+                "commits": [
+                    {"added": ["New.v"], "modified": ["A.v"], "removed": []},
+                    {"added": [], "modified": [], "removed": ["A.v"]}
+                ],
+                "repository": {"full_name": "o/r"}}"#,
+        )
+        .unwrap();
+        let changes = push_changes(&payload.commits, None, false, 100).unwrap();
+        assert_eq!(changes.modified.iter().collect::<Vec<_>>(), ["New.v"]);
+        assert_eq!(changes.removed.iter().collect::<Vec<_>>(), ["A.v"]);
+
+        assert!(push_changes(&payload.commits, None, true, 100).is_none());
+        assert!(push_changes(&payload.commits, Some(21), false, 100).is_none());
+        assert!(push_changes(&[], None, false, 100).is_none());
+        assert!(push_changes(&payload.commits, None, false, 1).is_none(), "over the file cap");
+        assert!(is_null_sha("0000000000000000000000000000000000000000"));
+        assert!(!is_null_sha(&payload.before));
+    }
+
+    #[tokio::test]
+    async fn push_policy_picks_the_newest_earlier_commits() {
+        let payload: GitHubPushPayload = serde_json::from_str(
+            r#"{"ref": "refs/heads/main", "after": "c4",
+                "commits": [{"id": "c1"}, {"id": "c2"}, {"id": "c3"}, {"id": "c4"}],
+                "repository": {"full_name": "o/r"}}"#,
+        )
+        .unwrap();
+        let repo = RepoId::new(Platform::GitHub, "o", "r");
+        let policy = |commits, max_commits| modes::PushSection { commits, max_commits };
+
+        let head_only = policy(modes::PushCommits::Head, 20);
+        assert!(earlier_commits(None, &repo, &head_only, &payload.commits, "c4").await.is_empty());
+        let all = policy(modes::PushCommits::All, 3);
+        assert_eq!(earlier_commits(None, &repo, &all, &payload.commits, "c4").await, ["c2", "c3"]);
+
+        let api = std::sync::Arc::new(crate::adapters::testing::MockApi::new());
+        let method = crate::adapters::http::Method::Get;
+        api.respond(method, "/repos/o/r/commits/c2", 200, r#"{"parents": [{"sha": "c1"}, {"sha": "x"}]}"#);
+        api.respond(method, "/repos/o/r/commits/c3", 200, r#"{"parents": [{"sha": "c2"}]}"#);
+        let github = crate::adapters::github::GitHubAdapter::new("t").unwrap().with_http(api);
+        let merges = policy(modes::PushCommits::Merges, 20);
+        let github: &dyn PlatformAdapter = &github;
+        assert_eq!(earlier_commits(Some(github), &repo, &merges, &payload.commits, "c4").await, ["c2"]);
+    }
 }
@@ -0,0 +1,395 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Content-addressed archive of verified artifacts
+//!
+//! With `[archive] enabled = true`, every passing job is published as one
+//! directory to a content-addressed backend:
+//!
+//! ```text
+//! result.json          job, repository, commit, prover, outcome, file lists
+//! prover-output.txt    raw prover output
+//! attestation.json     signed envelope (see `crate::attest`), if a key is set
+//! sources/…            the verified proof files (`include_sources`)
+//! ```
+//!
+//! The returned address is stored with the result (`archive_cid`) and
+//! linked from the check run through `gateway_url`. Backends:
+//!
+//!   * `ipfs` — added and pinned through a Kubo RPC API; the address is the
+//!     CIDv1 of the wrapping directory. Needs the `ipfs` cargo feature.
+//!   * `directory` — written under a local root as `sha256-<hex>/`, where
+//!     the hash covers the directory's `SHA256SUMS` listing, so the same
+//!     artifacts always land at the same address.
+//!
+//! Archiving is best-effort: a failure is logged and the job's result and
+//! report are unaffected.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::attest::{build_job_attestation, ResultSigner};
+use crate::config::{ArchiveBackend, ArchiveConfig, Config};
+use crate::error::{Error, Result};
+use crate::scheduler::JobId;
+use crate::store::Store;
+
+/// One file of an archived directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactFile {
+    /// Path inside the directory, `/`-separated.
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+impl ArtifactFile {
+    pub fn new(path: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            path: path.into(),
+            bytes: bytes.into(),
+        }
+    }
+}
+
+/// A content-addressed store.
+#[async_trait]
+pub trait CasBackend: Send + Sync {
+    /// Store `files` as one directory and return its content address.
+    async fn put(&self, files: &[ArtifactFile]) -> Result<String>;
+}
+
+/// The backend `[archive]` selects.
+pub fn backend_from_config(config: &ArchiveConfig) -> Result<Box<dyn CasBackend>> {
+    match config.backend {
+        #[cfg(feature = "ipfs")]
+        ArchiveBackend::Ipfs => Ok(Box::new(IpfsBackend::new(&config.ipfs_api_url))),
+        #[cfg(not(feature = "ipfs"))]
+        ArchiveBackend::Ipfs => Err(Error::Unsupported(
+            "the ipfs archive backend needs echidnabot built with the `ipfs` feature".to_string(),
+        )),
+        ArchiveBackend::Directory => {
+            let root = config.directory.as_deref().ok_or_else(|| {
+                Error::Config("[archive] backend = \"directory\" needs `directory`".to_string())
+            })?;
+            Ok(Box::new(DirectoryBackend::new(root)))
+        }
+    }
+}
+
+/// Where an archived directory can be browsed, if a gateway is configured.
+pub fn archive_url(config: &ArchiveConfig, cid: &str) -> Option<String> {
+    let gateway = config.gateway_url.as_deref()?;
+    Some(format!("{}/{}", gateway.trim_end_matches('/'), cid))
+}
+
+/// Writes each directory under `root/sha256-<hex>/`.
+pub struct DirectoryBackend {
+    root: PathBuf,
+}
+
+impl DirectoryBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+/// `sha256sum`-style listing of `files`, sorted by path.
+fn checksums(files: &[ArtifactFile]) -> String {
+    let mut sorted: Vec<&ArtifactFile> = files.iter().collect();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    sorted
+        .into_iter()
+        .map(|f| format!("{}  {}\n", hex::encode(Sha256::digest(&f.bytes)), f.path))
+        .collect()
+}
+
+/// Reject paths that would escape the archived directory.
+fn safe_relative(path: &str) -> Result<&Path> {
+    let p = Path::new(path);
+    let ok = !path.is_empty()
+        && p.components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if ok {
+        Ok(p)
+    } else {
+        Err(Error::InvalidInput(format!("unsafe archive path '{}'", path)))
+    }
+}
+
+#[async_trait]
+impl CasBackend for DirectoryBackend {
+    async fn put(&self, files: &[ArtifactFile]) -> Result<String> {
+        let sums = checksums(files);
+        let cid = format!("sha256-{}", hex::encode(Sha256::digest(sums.as_bytes())));
+        let target = self.root.join(&cid);
+        if tokio::fs::try_exists(&target).await? {
+            return Ok(cid);
+        }
+
+        // Build beside the target and rename, so a directory that exists
+        // is always complete.
+        tokio::fs::create_dir_all(&self.root).await?;
+        let staging = self.root.join(format!(".{}.{}", cid, uuid::Uuid::new_v4()));
+        for file in files {
+            let dest = staging.join(safe_relative(&file.path)?);
+            if let Some(parent) = dest.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&dest, &file.bytes).await?;
+        }
+        tokio::fs::write(staging.join("SHA256SUMS"), sums).await?;
+        if let Err(e) = tokio::fs::rename(&staging, &target).await {
+            let _ = tokio::fs::remove_dir_all(&staging).await;
+            // Lost a race with an identical upload.
+            if !tokio::fs::try_exists(&target).await? {
+                return Err(e.into());
+            }
+        }
+        Ok(cid)
+    }
+}
+
+/// Adds directories through a Kubo (go-ipfs) RPC API.
+#[cfg(feature = "ipfs")]
+pub struct IpfsBackend {
+    api_url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "ipfs")]
+impl IpfsBackend {
+    pub fn new(api_url: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "ipfs")]
+#[async_trait]
+impl CasBackend for IpfsBackend {
+    async fn put(&self, files: &[ArtifactFile]) -> Result<String> {
+        #[derive(serde::Deserialize)]
+        struct Added {
+            #[serde(rename = "Name")]
+            name: String,
+            #[serde(rename = "Hash")]
+            hash: String,
+        }
+
+        let mut form = reqwest::multipart::Form::new();
+        for file in files {
+            safe_relative(&file.path)?;
+            let part = reqwest::multipart::Part::bytes(file.bytes.clone()).file_name(file.path.clone());
+            form = form.part("file", part);
+        }
+        let body = self
+            .client
+            .post(format!("{}/api/v0/add", self.api_url))
+            .query(&[("cid-version", "1"), ("wrap-with-directory", "true"), ("pin", "true")])
+            .multipart(form)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        // One JSON object per added entry; the wrapping directory has an
+        // empty name and comes last.
+        body.lines()
+            .filter_map(|line| serde_json::from_str::<Added>(line).ok())
+            .find(|added| added.name.is_empty())
+            .map(|added| added.hash)
+            .ok_or_else(|| Error::Internal("IPFS add returned no directory CID".to_string()))
+    }
+}
+
+/// `result.json` of an archived job.
+#[derive(Debug, Serialize)]
+struct ArchivedResult {
+    job_id: String,
+    platform: String,
+    repo: String,
+    commit: String,
+    prover: String,
+    success: bool,
+    message: String,
+    verified_files: Vec<String>,
+    failed_files: Vec<String>,
+    duration_ms: i64,
+    completed_at: String,
+}
+
+/// Read the verified proof files under `checkout` for the archive, as
+/// `sources/<repo-relative path>`. Files that cannot be read are skipped,
+/// and so is everything once `max_bytes` would be exceeded.
+pub async fn collect_sources(checkout: &Path, files: &[String], max_bytes: u64) -> Vec<ArtifactFile> {
+    let mut sources = Vec::new();
+    let mut total = 0u64;
+    for path in files {
+        let full = if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            checkout.join(path)
+        };
+        let Ok(rel) = full.strip_prefix(checkout) else {
+            continue;
+        };
+        let Ok(bytes) = tokio::fs::read(&full).await else {
+            continue;
+        };
+        total += bytes.len() as u64;
+        if total > max_bytes {
+            tracing::info!("Archive sources exceed {} bytes; leaving them out", max_bytes);
+            return Vec::new();
+        }
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        sources.push(ArtifactFile::new(format!("sources/{}", rel), bytes));
+    }
+    sources
+}
+
+/// The files archived for `job_id`, or `None` when the job or its result
+/// is missing.
+pub async fn build_bundle(
+    store: &dyn Store,
+    signer: Option<&ResultSigner>,
+    job_id: JobId,
+    sources: Vec<ArtifactFile>,
+) -> Result<Option<Vec<ArtifactFile>>> {
+    let Some(job) = store.get_job(job_id).await? else {
+        return Ok(None);
+    };
+    let Some(result) = store.get_result_for_job(job_id).await? else {
+        return Ok(None);
+    };
+    let repo = store
+        .get_repository(job.repo_id)
+        .await?
+        .ok_or_else(|| Error::RepoNotFound(job.repo_id.to_string()))?;
+
+    let summary = ArchivedResult {
+        job_id: job.id.to_string(),
+        platform: format!("{:?}", repo.platform).to_lowercase(),
+        repo: repo.full_name(),
+        commit: job.commit_sha.clone(),
+        prover: job.prover.as_str().to_string(),
+        success: result.success,
+        message: result.message.clone(),
+        verified_files: result.verified_files.clone(),
+        failed_files: result.failed_files.clone(),
+        duration_ms: result.duration_ms,
+        completed_at: result.created_at.to_rfc3339(),
+    };
+    let mut files = vec![
+        ArtifactFile::new("result.json", serde_json::to_vec_pretty(&summary)?),
+        ArtifactFile::new("prover-output.txt", result.prover_output.into_bytes()),
+    ];
+    if let Some(signer) = signer {
+        if let Some(envelope) = build_job_attestation(store, signer, job_id).await? {
+            files.push(ArtifactFile::new("attestation.json", serde_json::to_vec_pretty(&envelope)?));
+        }
+    }
+    files.extend(sources);
+    Ok(Some(files))
+}
+
+/// Archive job `job_id` per `[archive]` and record the address with its
+/// result. Returns the address.
+pub async fn archive_job(
+    store: &dyn Store,
+    config: &Config,
+    job_id: JobId,
+    sources: Vec<ArtifactFile>,
+) -> Result<Option<String>> {
+    let backend = backend_from_config(&config.archive)?;
+    let signer = ResultSigner::from_config(&config.attestation)?;
+    let Some(files) = build_bundle(store, signer.as_ref(), job_id, sources).await? else {
+        return Ok(None);
+    };
+    let cid = backend.put(&files).await?;
+    store.set_result_archive_cid(job_id, &cid).await?;
+    Ok(Some(cid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use crate::store::models::{ProofJobRecord, ProofResultRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[test]
+    fn rejects_escaping_paths() {
+        assert!(safe_relative("sources/Foo.lean").is_ok());
+        assert!(safe_relative("../etc/passwd").is_err());
+        assert!(safe_relative("/etc/passwd").is_err());
+        assert!(safe_relative("").is_err());
+    }
+
+    #[tokio::test]
+    async fn directory_backend_is_content_addressed() {
+        let root = tempfile::tempdir().unwrap();
+        let backend = DirectoryBackend::new(root.path());
+        let a = [ArtifactFile::new("a.txt", "one"), ArtifactFile::new("b/c.txt", "two")];
+        let reordered = [a[1].clone(), a[0].clone()];
+
+        let cid = backend.put(&a).await.unwrap();
+        assert!(cid.starts_with("sha256-"));
+        assert_eq!(backend.put(&reordered).await.unwrap(), cid);
+        assert_eq!(std::fs::read_to_string(root.path().join(&cid).join("b/c.txt")).unwrap(), "two");
+
+        let changed = [ArtifactFile::new("a.txt", "uno")];
+        assert_ne!(backend.put(&changed).await.unwrap(), cid);
+    }
+
+    #[tokio::test]
+    async fn archives_a_finished_job() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "owner".into(), "lib".into());
+        store.create_repository(&repo).await.unwrap();
+        let job = ProofJob::new(repo.id, "c0ffee".into(), ProverKind::new("lean"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+        let result = JobResult {
+            success: true,
+            message: "Verified 1 file(s)".into(),
+            prover_output: "ok".into(),
+            duration_ms: 5,
+            verified_files: vec!["Foo.lean".into()],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        store.save_result(&ProofResultRecord::new(job.id, &result)).await.unwrap();
+
+        let checkout = tempfile::tempdir().unwrap();
+        std::fs::write(checkout.path().join("Foo.lean"), "theorem t : True := trivial").unwrap();
+        let sources = collect_sources(checkout.path(), &result.verified_files, 1024).await;
+        assert_eq!(sources[0].path, "sources/Foo.lean");
+
+        let signer = ResultSigner::from_seed([7; 32]);
+        let files = build_bundle(&store, Some(&signer), job.id, sources).await.unwrap().unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            names,
+            ["result.json", "prover-output.txt", "attestation.json", "sources/Foo.lean"]
+        );
+
+        let root = tempfile::tempdir().unwrap();
+        let cid = DirectoryBackend::new(root.path()).put(&files).await.unwrap();
+        store.set_result_archive_cid(job.id, &cid).await.unwrap();
+        let stored = store.get_result_for_job(job.id).await.unwrap().unwrap();
+        assert_eq!(stored.archive_cid.as_deref(), Some(cid.as_str()));
+
+        assert!(collect_sources(checkout.path(), &result.verified_files, 4).await.is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Signed result attestations
+//!
+//! An attestation is a DSSE-style envelope: the claims about one finished
+//! job (repo, commit, prover, outcome, file lists and a hash of the prover
+//! output) serialised as a JSON string, plus ed25519 signatures over the
+//! DSSE pre-authentication encoding of that string. Signing the exact
+//! bytes means verifiers never have to canonicalise JSON.
+//!
+//! The instance publishes its public keys at [`KEYS_PATH`]; anyone holding
+//! a copy of that key set can check an envelope offline with
+//! `echidnabot attest verify <file> --keys keys.json`. Key ids are the
+//! first eight bytes of the SHA-256 of the public key, hex-encoded.
+
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::AttestationConfig;
+use crate::error::{Error, Result};
+use crate::scheduler::JobId;
+use crate::store::models::{ProofJobRecord, ProofResultRecord, Repository};
+use crate::store::Store;
+
+/// Where the key set is served.
+pub const KEYS_PATH: &str = "/.well-known/echidnabot/keys.json";
+
+/// `payloadType` of result attestations.
+pub const PAYLOAD_TYPE: &str = "application/vnd.echidnabot.result+json";
+
+/// What an attestation asserts about one job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResultClaims {
+    pub job_id: String,
+    pub platform: String,
+    pub repo: String,
+    pub commit: String,
+    pub prover: String,
+    pub success: bool,
+    pub verified_files: Vec<String>,
+    pub failed_files: Vec<String>,
+    /// SHA-256 of the raw prover output, hex.
+    pub output_sha256: String,
+    /// When the result was recorded (RFC 3339).
+    pub completed_at: String,
+}
+
+/// A signed envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub payload_type: String,
+    /// The claims as a JSON string; the signatures cover these bytes.
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    pub keyid: String,
+    /// Hex-encoded ed25519 signature.
+    pub sig: String,
+}
+
+/// One entry of `keys.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedKey {
+    pub keyid: String,
+    pub alg: String,
+    /// Hex-encoded 32-byte public key.
+    pub public_key: String,
+}
+
+/// The document served at [`KEYS_PATH`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySet {
+    pub keys: Vec<PublishedKey>,
+}
+
+impl KeySet {
+    /// The instance's current key (when configured) and its retired ones.
+    pub fn from_config(config: &AttestationConfig) -> Result<Self> {
+        let mut keys = Vec::new();
+        if let Some(signer) = ResultSigner::from_config(config)? {
+            keys.push(signer.published_key());
+        }
+        keys.extend(Self::from_public_keys(&config.retired_public_keys)?.keys);
+        Ok(Self { keys })
+    }
+
+    /// A key set from hex-encoded public keys, e.g. a federation peer's
+    /// pinned keys.
+    pub fn from_public_keys(hex_keys: &[String]) -> Result<Self> {
+        let keys = hex_keys
+            .iter()
+            .map(|hex_key| parse_public_key(hex_key).map(|key| published(&key)))
+            .collect::<Result<_>>()?;
+        Ok(Self { keys })
+    }
+}
+
+/// The instance's signing key.
+pub struct ResultSigner {
+    key: SigningKey,
+    keyid: String,
+}
+
+impl ResultSigner {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let key = SigningKey::from_bytes(&seed);
+        let keyid = key_id(&key.verifying_key());
+        Self { key, keyid }
+    }
+
+    /// A fresh random key, for `attest keygen`.
+    pub fn generate() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// Load the key named by `[attestation] signing_key_path`, if any.
+    pub fn from_config(config: &AttestationConfig) -> Result<Option<Self>> {
+        let Some(path) = config.signing_key_path.as_deref() else {
+            return Ok(None);
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!("cannot read attestation key {}: {}", path, e))
+        })?;
+        let seed: [u8; 32] = hex::decode(text.trim())
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| {
+                Error::Config(format!("attestation key {} is not a 64-digit hex seed", path))
+            })?;
+        Ok(Some(Self::from_seed(seed)))
+    }
+
+    /// Hex seed, as written to the key file.
+    pub fn seed_hex(&self) -> String {
+        hex::encode(self.key.to_bytes())
+    }
+
+    pub fn published_key(&self) -> PublishedKey {
+        published(&self.key.verifying_key())
+    }
+
+    pub fn sign(&self, claims: &ResultClaims) -> Result<Envelope> {
+        Ok(self.sign_payload(PAYLOAD_TYPE, serde_json::to_string(claims)?))
+    }
+
+    /// Sign `payload` as `payload_type`, e.g. a [`crate::certificate`].
+    pub fn sign_payload(&self, payload_type: &str, payload: String) -> Envelope {
+        let sig = self.key.sign(&pae(payload_type, &payload));
+        Envelope {
+            payload_type: payload_type.to_string(),
+            payload,
+            signatures: vec![EnvelopeSignature {
+                keyid: self.keyid.clone(),
+                sig: hex::encode(sig.to_bytes()),
+            }],
+        }
+    }
+}
+
+/// Check `envelope` against `keys` and return its claims. At least one
+/// signature must be by a listed key, and every signature by a listed key
+/// must be valid.
+pub fn verify(envelope: &Envelope, keys: &KeySet) -> Result<ResultClaims> {
+    Ok(serde_json::from_str(verify_payload(envelope, keys, PAYLOAD_TYPE)?)?)
+}
+
+/// [`verify`] for any `payload_type`, returning the payload unparsed.
+pub fn verify_payload<'a>(
+    envelope: &'a Envelope,
+    keys: &KeySet,
+    payload_type: &str,
+) -> Result<&'a str> {
+    if envelope.payload_type != payload_type {
+        return Err(Error::InvalidInput(format!(
+            "unexpected payload type '{}'",
+            envelope.payload_type
+        )));
+    }
+    let message = pae(&envelope.payload_type, &envelope.payload);
+    let mut verified = 0;
+    for signature in &envelope.signatures {
+        let Some(key) = keys.keys.iter().find(|k| k.keyid == signature.keyid) else {
+            continue;
+        };
+        let key = parse_public_key(&key.public_key)?;
+        let sig = hex::decode(&signature.sig)
+            .ok()
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .ok_or_else(|| Error::InvalidInput(format!("malformed signature by {}", signature.keyid)))?;
+        key.verify_strict(&message, &sig).map_err(|_| {
+            Error::InvalidInput(format!("signature by {} does not verify", signature.keyid))
+        })?;
+        verified += 1;
+    }
+    if verified == 0 {
+        return Err(Error::InvalidInput(
+            "no signature by a published key".to_string(),
+        ));
+    }
+    Ok(&envelope.payload)
+}
+
+/// The claims an attestation of `job`'s `result` makes.
+pub fn result_claims(
+    repo: &Repository,
+    job: &ProofJobRecord,
+    result: &ProofResultRecord,
+) -> ResultClaims {
+    ResultClaims {
+        job_id: job.id.to_string(),
+        platform: format!("{:?}", repo.platform).to_lowercase(),
+        repo: repo.full_name(),
+        commit: job.commit_sha.clone(),
+        prover: job.prover.as_str().to_string(),
+        success: result.success,
+        verified_files: result.verified_files.clone(),
+        failed_files: result.failed_files.clone(),
+        output_sha256: hex::encode(Sha256::digest(result.prover_output.as_bytes())),
+        completed_at: result.created_at.to_rfc3339(),
+    }
+}
+
+/// Sign job `job_id`'s result. `None` when the job or its result does not
+/// exist yet.
+pub async fn build_job_attestation(
+    store: &dyn Store,
+    signer: &ResultSigner,
+    job_id: JobId,
+) -> Result<Option<Envelope>> {
+    let Some(job) = store.get_job(job_id).await? else {
+        return Ok(None);
+    };
+    let Some(result) = store.get_result_for_job(job_id).await? else {
+        return Ok(None);
+    };
+    let repo = store
+        .get_repository(job.repo_id)
+        .await?
+        .ok_or_else(|| Error::RepoNotFound(job.repo_id.to_string()))?;
+    signer.sign(&result_claims(&repo, &job, &result)).map(Some)
+}
+
+/// DSSE pre-authentication encoding.
+fn pae(payload_type: &str, payload: &str) -> Vec<u8> {
+    format!(
+        "DSSEv1 {} {} {} {}",
+        payload_type.len(),
+        payload_type,
+        payload.len(),
+        payload
+    )
+    .into_bytes()
+}
+
+fn key_id(key: &VerifyingKey) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..8])
+}
+
+fn published(key: &VerifyingKey) -> PublishedKey {
+    PublishedKey {
+        keyid: key_id(key),
+        alg: "ed25519".to_string(),
+        public_key: hex::encode(key.as_bytes()),
+    }
+}
+
+fn parse_public_key(hex_key: &str) -> Result<VerifyingKey> {
+    hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|b| <[u8; 32]>::try_from(b).ok())
+        .and_then(|b| VerifyingKey::from_bytes(&b).ok())
+        .ok_or_else(|| Error::InvalidInput(format!("invalid ed25519 public key '{}'", hex_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> ResultClaims {
+        ResultClaims {
+            job_id: "00000000-0000-0000-0000-000000000001".into(),
+            platform: "github".into(),
+            repo: "o/r".into(),
+            commit: "abc123".into(),
+            prover: "coq".into(),
+            success: true,
+            verified_files: vec!["Nat.v".into()],
+            failed_files: vec![],
+            output_sha256: String::new(),
+            completed_at: "2026-01-01T00:00:00+00:00".into(),
+        }
+    }
+
+    #[test]
+    fn sign_then_verify_and_reject_tampering() {
+        let signer = ResultSigner::from_seed([7; 32]);
+        let keys = KeySet { keys: vec![signer.published_key()] };
+        let envelope = signer.sign(&claims()).unwrap();
+
+        // Round-trips through the wire format.
+        let wire = serde_json::to_string(&envelope).unwrap();
+        let parsed: Envelope = serde_json::from_str(&wire).unwrap();
+        assert_eq!(verify(&parsed, &keys).unwrap(), claims());
+
+        let mut tampered = envelope.clone();
+        tampered.payload = tampered.payload.replace("\"success\":true", "\"success\":false");
+        assert!(verify(&tampered, &keys).is_err());
+
+        let stranger = KeySet { keys: vec![ResultSigner::from_seed([8; 32]).published_key()] };
+        assert!(verify(&envelope, &stranger).is_err());
+    }
+}
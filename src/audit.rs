@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Audit log of bot actions
+//!
+//! Every externally visible action — a check run created, a comment
+//! posted, a PR merged, labelled or closed, a merge blocked, settings
+//! changed — is appended to the `audit_log` table: who did it, on which
+//! repository, when, and a SHA-256 digest of what was sent. The store has
+//! no way to change or remove a row, and the table's triggers refuse both.
+//!
+//! Read it with the `auditLog` GraphQL query (admin scope) or export it as
+//! JSON Lines with `echidnabot audit export`. Each entry is also logged on
+//! the `echidnabot::audit` tracing target.
+//!
+//! Recording is best-effort: a store error is logged, never returned, so
+//! an action already taken is not reported as failed because of it.
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::store::models::{AuditRecord, Repository};
+use crate::store::Store;
+
+/// Actor recorded for what the bot does on its own (or on a webhook's
+/// behalf).
+pub const BOT: &str = "echidnabot";
+
+/// Actor recorded for `echidnabot` subcommands.
+pub const CLI: &str = "cli";
+
+/// Actor for a forge user acting through a check-run button or command.
+pub fn user(login: &str) -> String {
+    format!("user:{}", login)
+}
+
+/// What was done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    CheckRunCreated,
+    CommentPosted,
+    ReviewCommentPosted,
+    IssueOpened,
+    /// A Regulator check failed below the coverage threshold.
+    MergeBlocked,
+    PullRequestMerged,
+    PullRequestApproved,
+    PullRequestLabeled,
+    PullRequestMarkedDraft,
+    PullRequestClosed,
+    ConfigPrOpened,
+    /// A verified proof repair was proposed as a PR.
+    RepairPrOpened,
+    /// A verification certificate was signed for a commit.
+    CertificateIssued,
+    RepoRegistered,
+    RepoUnregistered,
+    RepoSettingsChanged,
+    ProofQuarantined,
+    ProofUnquarantined,
+    AnnouncementSet,
+    AnnouncementCleared,
+    /// A destructive mutation was held for confirmation.
+    ConfirmationRequested,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CheckRunCreated => "check_run.created",
+            Self::CommentPosted => "comment.posted",
+            Self::ReviewCommentPosted => "review_comment.posted",
+            Self::IssueOpened => "issue.opened",
+            Self::MergeBlocked => "merge.blocked",
+            Self::PullRequestMerged => "pr.merged",
+            Self::PullRequestApproved => "pr.approved",
+            Self::PullRequestLabeled => "pr.labeled",
+            Self::PullRequestMarkedDraft => "pr.marked_draft",
+            Self::PullRequestClosed => "pr.closed",
+            Self::ConfigPrOpened => "config_pr.opened",
+            Self::RepairPrOpened => "repair_pr.opened",
+            Self::CertificateIssued => "certificate.issued",
+            Self::RepoRegistered => "repo.registered",
+            Self::RepoUnregistered => "repo.unregistered",
+            Self::RepoSettingsChanged => "repo.settings_changed",
+            Self::ProofQuarantined => "proof.quarantined",
+            Self::ProofUnquarantined => "proof.unquarantined",
+            Self::AnnouncementSet => "announcement.set",
+            Self::AnnouncementCleared => "announcement.cleared",
+            Self::ConfirmationRequested => "confirmation.requested",
+        }
+    }
+}
+
+/// SHA-256 of `payload`, hex.
+pub fn digest(payload: &[u8]) -> String {
+    hex::encode(Sha256::digest(payload))
+}
+
+/// The payload recorded for a settings change: the repository's settings
+/// as JSON, with the webhook secret redacted.
+pub fn settings_payload(repo: &Repository) -> Vec<u8> {
+    let mut repo = repo.clone();
+    if repo.webhook_secret.is_some() {
+        repo.webhook_secret = Some("<redacted>".to_string());
+    }
+    serde_json::to_vec(&repo).unwrap_or_default()
+}
+
+/// The entry for `action`, not yet stored.
+pub fn entry(
+    actor: &str,
+    action: AuditAction,
+    repo: Option<&Repository>,
+    target: Option<String>,
+    payload: &[u8],
+) -> AuditRecord {
+    AuditRecord {
+        id: Uuid::new_v4(),
+        at: chrono::Utc::now(),
+        actor: actor.to_string(),
+        action: action.as_str().to_string(),
+        repo_id: repo.map(|r| r.id),
+        repo: repo.map(|r| r.full_name()),
+        target,
+        payload_digest: digest(payload),
+    }
+}
+
+/// Append an entry for `action`; see the module docs.
+pub async fn record(
+    store: &dyn Store,
+    actor: &str,
+    action: AuditAction,
+    repo: Option<&Repository>,
+    target: Option<String>,
+    payload: impl AsRef<[u8]>,
+) {
+    let entry = entry(actor, action, repo, target, payload.as_ref());
+    tracing::info!(
+        target: "echidnabot::audit",
+        action = %entry.action,
+        actor = %entry.actor,
+        repo = entry.repo.as_deref().unwrap_or(""),
+        on = entry.target.as_deref().unwrap_or(""),
+        digest = %entry.payload_digest,
+        "Audit: {} by {}",
+        entry.action,
+        entry.actor
+    );
+    if let Err(e) = store.append_audit(&entry).await {
+        tracing::warn!("Recording audit entry {} ({}) failed: {}", entry.id, entry.action, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+
+    #[test]
+    fn entries_carry_the_repository_and_a_digest() {
+        let repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        let entry = entry(BOT, AuditAction::CommentPosted, Some(&repo), Some("PR #7".into()), b"hello");
+        assert_eq!(entry.action, "comment.posted");
+        assert_eq!(entry.repo_id, Some(repo.id));
+        assert_eq!(entry.repo.as_deref(), Some("acme/proofs"));
+        assert_eq!(
+            entry.payload_digest,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+}
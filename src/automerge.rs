@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Auto-merge of PRs whose proofs all pass
+//!
+//! Two manifest policies end here: `[auto_merge]` (PRs carrying an
+//! `automerge`-style label) and `[dependency_prs] auto_merge` (dependency
+//! bumps). Both go through [`merge_if_green`], which:
+//!
+//! 1. requires the newest job of every prover at the commit to have
+//!    passed — a failed job later re-run successfully counts as green,
+//!    a queued or running one does not;
+//! 2. re-reads the PR from the platform and refuses if it is closed, a
+//!    draft, no longer labelled, or its head has moved past the verified
+//!    commit (a push after verification is never merged unverified);
+//! 3. merges pinned to the verified SHA, so a push racing the merge call
+//!    is rejected by the platform too.
+//!
+//! Every decision that reaches step 2 is written to the `echidnabot::audit`
+//! tracing target with the repo, PR, commit, policy and outcome.
+
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use crate::adapters::{MergeMethod, PlatformAdapter, PrId, RepoId};
+use crate::audit::AuditAction;
+use crate::error::Result;
+use crate::modes::RepoManifest;
+use crate::scheduler::{JobId, JobStatus};
+use crate::store::models::{ProofJobRecord, Repository};
+use crate::store::Store;
+
+/// Recent jobs scanned when deciding whether a commit is green.
+const JOB_SCAN_LIMIT: usize = 200;
+
+/// Which policy asked for the merge and how to perform it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePolicy {
+    /// `"automerge label"` or `"dependency update"`, for the audit log.
+    pub reason: &'static str,
+    pub method: MergeMethod,
+    /// Submit an approving review before merging.
+    pub approve: bool,
+    /// The PR must still carry one of these; empty means no label gate.
+    pub required_labels: Vec<String>,
+}
+
+impl MergePolicy {
+    /// The policy that applies to a finished job's PR, if any. The
+    /// dependency policy wins for jobs tagged as dependency updates.
+    pub fn for_job(manifest: &RepoManifest, dependency_update: bool) -> Option<Self> {
+        if dependency_update && manifest.dependency_prs.auto_merge {
+            return Some(Self {
+                reason: "dependency update",
+                method: manifest.dependency_prs.merge_method,
+                approve: manifest.dependency_prs.approve,
+                required_labels: Vec::new(),
+            });
+        }
+        Self::labelled(manifest)
+    }
+
+    /// The `[auto_merge]` label policy, if enabled.
+    pub fn labelled(manifest: &RepoManifest) -> Option<Self> {
+        manifest.auto_merge.enabled.then(|| Self {
+            reason: "automerge label",
+            method: manifest.auto_merge.merge_method,
+            approve: false,
+            required_labels: manifest.auto_merge.labels.clone(),
+        })
+    }
+}
+
+/// Proof status of one commit across provers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CommitChecks {
+    pub passed: usize,
+    pub failed: usize,
+    pub pending: usize,
+}
+
+impl CommitChecks {
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.pending
+    }
+
+    /// At least one prover ran and every prover's newest job passed.
+    pub fn is_green(&self) -> bool {
+        self.passed > 0 && self.failed == 0 && self.pending == 0
+    }
+}
+
+/// Tally the newest job of each prover at `commit_sha`. Cancelled jobs
+/// are ignored.
+pub async fn commit_checks(store: &dyn Store, repo_id: Uuid, commit_sha: &str) -> Result<CommitChecks> {
+    let jobs = store.list_jobs_for_repo(repo_id, JOB_SCAN_LIMIT).await?;
+    let jobs: Vec<&ProofJobRecord> = jobs.iter().filter(|job| job.commit_sha == commit_sha).collect();
+    tally_jobs(store, &jobs).await
+}
+
+/// Tally jobs of a single commit, given newest first: the first
+/// non-cancelled job per prover is the one that counts.
+pub async fn tally_jobs(store: &dyn Store, jobs: &[&ProofJobRecord]) -> Result<CommitChecks> {
+    let mut checks = CommitChecks::default();
+    let mut seen = HashSet::new();
+    for &job in jobs {
+        if job.status == JobStatus::Cancelled || !seen.insert(job.prover.clone()) {
+            continue;
+        }
+        match job.status {
+            JobStatus::Queued | JobStatus::Running => checks.pending += 1,
+            _ => match store.get_result_for_job(JobId(job.id)).await? {
+                Some(result) if result.success => checks.passed += 1,
+                Some(_) => checks.failed += 1,
+                None if job.status == JobStatus::Completed => checks.passed += 1,
+                None => checks.failed += 1,
+            },
+        }
+    }
+    Ok(checks)
+}
+
+/// What [`merge_if_green`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    Merged,
+    /// Some prover has not passed (yet).
+    NotGreen(CommitChecks),
+    /// A safety check failed; nothing was merged.
+    Refused(String),
+    /// The platform rejected or failed the merge.
+    Failed(String),
+}
+
+/// Merge PR `pr_number` if `commit_sha` is green and the PR is still at
+/// that commit. Errors are folded into the outcome — auto-merge never
+/// fails the job that triggered it.
+pub async fn merge_if_green(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    commit_sha: &str,
+    pr_number: u64,
+    policy: &MergePolicy,
+) -> MergeOutcome {
+    let checks = match commit_checks(store, repo.id, commit_sha).await {
+        Ok(checks) => checks,
+        Err(e) => return MergeOutcome::Failed(format!("job lookup failed: {}", e)),
+    };
+    if !checks.is_green() {
+        tracing::debug!(
+            "PR #{} on {}: {}/{} provers passed at {:.8}, not merging yet",
+            pr_number,
+            repo.full_name(),
+            checks.passed,
+            checks.total(),
+            commit_sha
+        );
+        return MergeOutcome::NotGreen(checks);
+    }
+
+    let outcome = merge_verified(store, adapter, repo, commit_sha, pr_number, policy).await;
+    audit(repo, commit_sha, pr_number, policy, &outcome);
+    if matches!(outcome, MergeOutcome::Merged) {
+        let payload = format!("{} {}", policy.method.as_str(), commit_sha);
+        crate::audit::record(
+            store,
+            crate::audit::BOT,
+            AuditAction::PullRequestMerged,
+            Some(repo),
+            Some(format!("PR #{}", pr_number)),
+            payload,
+        )
+        .await;
+    }
+    outcome
+}
+
+async fn merge_verified(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    commit_sha: &str,
+    pr_number: u64,
+    policy: &MergePolicy,
+) -> MergeOutcome {
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let pr_id = PrId(pr_number.to_string());
+
+    let pr = match adapter.get_pull_request(&repo_id, pr_id.clone()).await {
+        Ok(pr) => pr,
+        Err(e) => return MergeOutcome::Refused(format!("could not re-read the PR: {}", e)),
+    };
+    if !pr.open {
+        return MergeOutcome::Refused("PR is no longer open".to_string());
+    }
+    if pr.draft {
+        return MergeOutcome::Refused("PR is a draft".to_string());
+    }
+    if pr.head_sha != commit_sha {
+        return MergeOutcome::Refused(format!(
+            "PR head moved to {:.8} after verification",
+            pr.head_sha
+        ));
+    }
+    if !policy.required_labels.is_empty()
+        && !pr
+            .labels
+            .iter()
+            .any(|l| policy.required_labels.iter().any(|r| r.eq_ignore_ascii_case(l)))
+    {
+        return MergeOutcome::Refused("auto-merge label was removed".to_string());
+    }
+
+    if policy.approve {
+        let body = format!("✅ All proofs verified at `{:.8}`. Approving.", commit_sha);
+        match adapter.approve_pull_request(&repo_id, pr_id.clone(), &body).await {
+            Ok(()) => {
+                let target = Some(format!("PR #{}", pr_number));
+                crate::audit::record(store, crate::audit::BOT, AuditAction::PullRequestApproved, Some(repo), target, &body)
+                    .await
+            }
+            Err(e) => tracing::warn!("PR #{} approval failed: {}", pr_number, e),
+        }
+    }
+    match adapter
+        .merge_pull_request(&repo_id, pr_id, commit_sha, policy.method)
+        .await
+    {
+        Ok(()) => MergeOutcome::Merged,
+        Err(e) => MergeOutcome::Failed(e.to_string()),
+    }
+}
+
+fn audit(
+    repo: &Repository,
+    commit_sha: &str,
+    pr_number: u64,
+    policy: &MergePolicy,
+    outcome: &MergeOutcome,
+) {
+    let (result, detail) = match outcome {
+        MergeOutcome::Merged => ("merged", String::new()),
+        MergeOutcome::NotGreen(c) => ("not_green", format!("{}/{} passed", c.passed, c.total())),
+        MergeOutcome::Refused(why) => ("refused", why.clone()),
+        MergeOutcome::Failed(why) => ("failed", why.clone()),
+    };
+    tracing::info!(
+        target: "echidnabot::audit",
+        action = "auto_merge",
+        repo = %repo.full_name(),
+        pr = pr_number,
+        commit = commit_sha,
+        policy = policy.reason,
+        method = policy.method.as_str(),
+        result,
+        detail = %detail,
+        "Auto-merge {} for {} PR #{}",
+        result,
+        repo.full_name(),
+        pr_number
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use crate::store::models::ProofResultRecord;
+    use crate::store::SqliteStore;
+
+    async fn finish(store: &SqliteStore, repo: Uuid, prover: &str, success: bool) {
+        let job = ProofJob::new(repo, "abc".into(), ProverKind::new(prover), vec![]);
+        let mut record = ProofJobRecord::from(job.clone());
+        record.status = if success { JobStatus::Completed } else { JobStatus::Failed };
+        store.create_job(&record).await.unwrap();
+        let result = JobResult {
+            success,
+            message: String::new(),
+            prover_output: String::new(),
+            duration_ms: 1,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        store
+            .save_result(&ProofResultRecord::new(job.id, &result))
+            .await
+            .unwrap();
+        // list_jobs_for_repo orders by queued_at; keep jobs distinct.
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+
+    #[tokio::test]
+    async fn newest_job_per_prover_decides() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-automerge-test-{}.db", Uuid::new_v4()));
+        let store = SqliteStore::new(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        assert!(!commit_checks(&store, repo.id, "abc").await.unwrap().is_green());
+
+        finish(&store, repo.id, "coq", true).await;
+        finish(&store, repo.id, "lean", false).await;
+        let checks = commit_checks(&store, repo.id, "abc").await.unwrap();
+        assert_eq!((checks.passed, checks.failed), (1, 1));
+
+        // A passing re-run supersedes the failure.
+        finish(&store, repo.id, "lean", true).await;
+        assert!(commit_checks(&store, repo.id, "abc").await.unwrap().is_green());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn dependency_policy_wins_for_dependency_jobs() {
+        let manifest = RepoManifest::parse(
+            "[auto_merge]\nenabled = true\n[dependency_prs]\nenabled = true\nauto_merge = true\napprove = true",
+        )
+        .unwrap();
+        let dep = MergePolicy::for_job(&manifest, true).unwrap();
+        assert_eq!(dep.reason, "dependency update");
+        assert!(dep.required_labels.is_empty());
+        let labelled = MergePolicy::for_job(&manifest, false).unwrap();
+        assert_eq!(labelled.required_labels, vec!["automerge".to_string()]);
+        assert!(MergePolicy::for_job(&RepoManifest::default(), false).is_none());
+    }
+}
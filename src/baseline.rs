@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Baseline comparison for PR checks
+//!
+//! A PR job records its base commit ([`ProofJob::base_sha`]). When the PR
+//! arrives, [`needs_baseline`] decides whether the base commit still has to
+//! be verified for each prover; if so the webhook handler queues that job
+//! ahead of the PR's own. The per-file results both jobs record in
+//! `file_timings` are then compared when the PR job reports:
+//!
+//!   * **introduced** — fails on the PR, passed (or did not exist) on base
+//!   * **pre-existing** — fails on both
+//!   * **fixed** — passes on the PR, failed on base
+//!
+//! so the check summary can tell "this PR broke proof X" apart from "proof
+//! X was already failing on main". The comparison is informational: it
+//! never changes the check conclusion. If the base results are not in yet
+//! when the PR job finishes, the summary says so instead.
+
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use crate::dispatcher::ProverKind;
+use crate::scheduler::{JobStatus, ProofJob};
+use crate::store::models::FileTimingRecord;
+use crate::store::Store;
+
+/// Recent jobs scanned for an existing run of the base commit.
+const JOB_SCAN_LIMIT: usize = 200;
+
+/// How the PR's per-file results differ from the base commit's.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    /// Failing on the PR, passing or absent on base.
+    pub introduced: Vec<String>,
+    /// Failing on both.
+    pub preexisting: Vec<String>,
+    /// Passing on the PR, failing on base.
+    pub fixed: Vec<String>,
+}
+
+impl Comparison {
+    pub fn is_empty(&self) -> bool {
+        self.introduced.is_empty() && self.preexisting.is_empty() && self.fixed.is_empty()
+    }
+}
+
+/// Latest outcome per file. `timings` may hold several runs of one file
+/// (a retried job); the newest wins.
+fn outcomes(timings: &[FileTimingRecord]) -> BTreeMap<&str, bool> {
+    let mut newest: BTreeMap<&str, &FileTimingRecord> = BTreeMap::new();
+    for t in timings {
+        let entry = newest.entry(t.file_path.as_str()).or_insert(t);
+        if t.created_at > entry.created_at {
+            *entry = t;
+        }
+    }
+    newest.into_iter().map(|(path, t)| (path, t.success)).collect()
+}
+
+/// Compare the PR's results (`head`) against the base commit's. Files the
+/// PR did not verify are left out.
+pub fn compare(base: &[FileTimingRecord], head: &[FileTimingRecord]) -> Comparison {
+    let base = outcomes(base);
+    let mut comparison = Comparison::default();
+    for (path, ok) in outcomes(head) {
+        let path = path.to_string();
+        match (base.get(path.as_str()).copied(), ok) {
+            (Some(false), false) => comparison.preexisting.push(path),
+            (_, false) => comparison.introduced.push(path),
+            (Some(false), true) => comparison.fixed.push(path),
+            (_, true) => {}
+        }
+    }
+    comparison
+}
+
+/// Whether `base_sha` still has to be verified for `prover`: it has no
+/// per-file results and no queued, running or finished job. Errors count
+/// as "no" — the comparison is best-effort.
+pub async fn needs_baseline(
+    store: &dyn Store,
+    repo_id: Uuid,
+    prover: &ProverKind,
+    base_sha: &str,
+) -> bool {
+    match store
+        .list_file_timings_for_commit(repo_id, prover.clone(), base_sha)
+        .await
+    {
+        Ok(timings) if timings.is_empty() => {}
+        Ok(_) => return false,
+        Err(e) => {
+            tracing::debug!("Baseline lookup for {} failed: {}", base_sha, e);
+            return false;
+        }
+    }
+    match store.list_jobs_for_repo(repo_id, JOB_SCAN_LIMIT).await {
+        Ok(jobs) => !jobs.iter().any(|job| {
+            job.commit_sha == base_sha
+                && job.prover == *prover
+                && job.status != JobStatus::Cancelled
+        }),
+        Err(e) => {
+            tracing::debug!("Baseline job lookup for {} failed: {}", base_sha, e);
+            false
+        }
+    }
+}
+
+/// Markdown section comparing a finished PR job with its base commit, or
+/// an empty string for jobs without a base.
+pub async fn report_for_job(store: &dyn Store, job: &ProofJob) -> String {
+    let Some(base_sha) = job.base_sha.as_deref() else {
+        return String::new();
+    };
+    let base = store
+        .list_file_timings_for_commit(job.repo_id, job.prover.clone(), base_sha)
+        .await;
+    let head = store.list_file_timings_for_job(job.id).await;
+    match (base, head) {
+        (Ok(base), Ok(head)) if !base.is_empty() => {
+            format_report(base_sha, Some(&compare(&base, &head)))
+        }
+        (Ok(_), Ok(_)) => format_report(base_sha, None),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::debug!("Baseline comparison for job {} skipped: {}", job.id, e);
+            String::new()
+        }
+    }
+}
+
+/// Render a comparison against `base_sha`; `None` means the base commit
+/// has no results yet.
+pub fn format_report(base_sha: &str, comparison: Option<&Comparison>) -> String {
+    let short = &base_sha[..base_sha.len().min(7)];
+    let mut out = format!("### 🔀 Compared with base `{}`\n\n", short);
+    let Some(comparison) = comparison else {
+        out.push_str(
+            "The base commit has not been verified yet, so failures cannot be \
+             attributed to this PR. Re-run the check once it has.\n",
+        );
+        return out;
+    };
+    if comparison.is_empty() {
+        out.push_str("No proof changed status relative to the base commit.\n");
+        return out;
+    }
+
+    out.push_str("| Change | File |\n");
+    out.push_str("|--------|------|\n");
+    for (label, files) in [
+        ("🔴 broken by this PR", &comparison.introduced),
+        ("🟡 already failing on base", &comparison.preexisting),
+        ("🟢 fixed by this PR", &comparison.fixed),
+    ] {
+        for file in files {
+            out.push_str(&format!("| {} | `{}` |\n", label, file));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::JobId;
+
+    fn timing(path: &str, success: bool) -> FileTimingRecord {
+        FileTimingRecord::new(
+            JobId::new(),
+            Uuid::nil(),
+            ProverKind::new("coq"),
+            path.into(),
+            "sha".into(),
+            10,
+            success,
+        )
+    }
+
+    #[test]
+    fn classifies_against_base() {
+        let base = [
+            timing("A.v", true),
+            timing("B.v", false),
+            timing("C.v", false),
+            timing("D.v", true),
+        ];
+        let head = [
+            timing("A.v", false),
+            timing("B.v", false),
+            timing("C.v", true),
+            timing("D.v", true),
+            timing("New.v", false),
+        ];
+        let c = compare(&base, &head);
+        assert_eq!(c.introduced, vec!["A.v", "New.v"]);
+        assert_eq!(c.preexisting, vec!["B.v"]);
+        assert_eq!(c.fixed, vec!["C.v"]);
+    }
+
+    #[test]
+    fn newest_base_run_wins() {
+        let old = timing("A.v", false);
+        let mut retried = timing("A.v", true);
+        retried.created_at = old.created_at + chrono::Duration::seconds(5);
+        let c = compare(&[retried, old], &[timing("A.v", false)]);
+        assert_eq!(c.introduced, vec!["A.v"]);
+        assert!(c.preexisting.is_empty());
+    }
+
+    #[test]
+    fn report_covers_pending_and_clean_bases() {
+        assert!(format_report("0123456789", None).contains("not been verified yet"));
+        assert!(format_report("0123456789", Some(&Comparison::default()))
+            .contains("No proof changed status"));
+
+        let report = format_report(
+            "0123456789",
+            Some(&Comparison {
+                introduced: vec!["A.v".into()],
+                preexisting: vec!["B.v".into()],
+                fixed: vec![],
+            }),
+        );
+        assert!(report.contains("`0123456`"));
+        assert!(report.contains("| 🔴 broken by this PR | `A.v` |"));
+        assert!(report.contains("| 🟡 already failing on base | `B.v` |"));
+    }
+}
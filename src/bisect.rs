@@ -0,0 +1,405 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Historian: bisecting default-branch breakages (`[bisect]` in the manifest)
+//!
+//! When a proof file that passed on the default branch fails on a later
+//! push, the commits in between are bisected to the one that broke it:
+//!
+//! 1. The failing push job's file is compared with the branch's previous
+//!    run for the same prover. Only a pass followed by a failure starts a
+//!    bisection; one per prover and file at a time.
+//! 2. The platform lists the commits between the two
+//!    (`list_commits_between`). Ranges over `[bisect] max_commits` are
+//!    left alone.
+//! 3. The midpoint is checked — from a result already stored for that
+//!    commit and file when there is one (`[push] commits = "all"`,
+//!    baselines, earlier bisections), else by a low-priority job checking
+//!    only that file. Its verdict halves the range; repeat.
+//! 4. With no commits left, the first failing commit broke the proof. An
+//!    issue names it, the file and the first failing theorem the prover
+//!    output named.
+//!
+//! Progress is kept in `bisections`, so a restart resumes where the last
+//! probe left off. Probe jobs report on their commit only, never on PRs
+//! (see [`crate::pr_association`]). A bisection still waiting on a probe
+//! after [`STALE_AFTER_HOURS`] no longer blocks a new one.
+
+use chrono::Utc;
+
+use crate::adapters::{build_adapter, NewIssue, PlatformAdapter, RepoId};
+use crate::audit::AuditAction;
+use crate::config::Config;
+use crate::error::Result;
+use crate::modes::{fetch_directive_via_adapter, BisectSection, RepoManifest};
+use crate::proof_items::ItemStatus;
+use crate::scheduler::{JobId, JobPriority, JobResult, JobScheduler, JobStatus, ProofJob};
+use crate::store::models::{BisectStatus, BisectionRecord, ProofJobRecord, Repository};
+use crate::store::Store;
+
+/// Recent jobs scanned for the branch's previous run.
+const JOB_SCAN_LIMIT: usize = 200;
+
+/// Recent bisections scanned for one already running.
+const BISECTION_SCAN_LIMIT: usize = 50;
+
+/// Hours after which a running bisection no longer blocks a new one.
+pub const STALE_AFTER_HOURS: i64 = 24;
+
+/// The commit to check next: the middle of the untested range.
+pub fn next_probe(bisection: &BisectionRecord) -> Option<&str> {
+    bisection.pending.get(bisection.pending.len() / 2).map(String::as_str)
+}
+
+/// Narrow the range with `sha`'s verdict: a pass moves the good end up
+/// to it, a failure the bad end down.
+pub fn narrow(bisection: &mut BisectionRecord, sha: &str, passed: bool) {
+    let Some(at) = bisection.pending.iter().position(|c| c == sha) else {
+        return;
+    };
+    if passed {
+        bisection.good_sha = sha.to_string();
+        bisection.pending.drain(..=at);
+    } else {
+        bisection.bad_sha = sha.to_string();
+        bisection.pending.truncate(at);
+    }
+}
+
+/// The issue naming the commit a finished bisection found.
+pub fn issue(bisection: &BisectionRecord, labels: &[String]) -> NewIssue {
+    let theorem = bisection
+        .theorem
+        .as_deref()
+        .map(|t| format!(", first at theorem `{}`", t))
+        .unwrap_or_default();
+    let decided = bisection.probes + bisection.reused;
+    NewIssue {
+        title: format!(
+            "Proof regression: {} broken by {:.8}",
+            bisection.file_path, bisection.bad_sha
+        ),
+        body: format!(
+            "`{}` ({}) fails on `{}` since commit {}{}.\n\n\
+             | | Commit |\n|---|---|\n\
+             | Last passing | {} |\n\
+             | First failing | {} |\n\n\
+             Found by bisection: {} commit(s) decided, {} by a check run for the \
+             bisection and {} from earlier results.\n",
+            bisection.file_path,
+            bisection.prover.display_name(),
+            bisection.branch,
+            bisection.bad_sha,
+            theorem,
+            bisection.good_sha,
+            bisection.bad_sha,
+            decided,
+            bisection.probes,
+            bisection.reused,
+        ),
+        labels: labels.to_vec(),
+    }
+}
+
+/// Advance or start a bisection with a finished job. Best-effort: errors
+/// are logged, never returned.
+pub async fn on_job_finished(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    config: &Config,
+    job: &ProofJob,
+    result: &JobResult,
+) {
+    if let Err(e) = handle(store, scheduler, config, job, result).await {
+        tracing::warn!("Bisection step for job {} failed: {}", job.id, e);
+    }
+}
+
+async fn handle(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    config: &Config,
+    job: &ProofJob,
+    result: &JobResult,
+) -> Result<()> {
+    let Some(repo) = store.get_repository(job.repo_id).await? else {
+        return Ok(());
+    };
+    let probe_of = store.get_job(job.id).await?.and_then(|r| r.payload.bisection);
+    let bisections = store.list_bisections(repo.id, BISECTION_SCAN_LIMIT).await?;
+
+    // A running bisection waiting on this commit. Another job for the
+    // commit only counts when it checked the file.
+    let waiting = bisections.into_iter().find(|b| {
+        b.status == BisectStatus::Running
+            && b.prover == job.prover
+            && next_probe(b) == Some(job.commit_sha.as_str())
+    });
+    if let Some(bisection) = waiting {
+        let passed = match file_verdict(store, job.id, &bisection.file_path).await {
+            Some(passed) => passed,
+            None if probe_of == Some(bisection.id) => result.success,
+            None => return Ok(()),
+        };
+        return drive(store, scheduler, config, &repo, bisection, Some(passed)).await;
+    }
+
+    if result.success || job.pr_number.is_some() || probe_of.is_some() {
+        return Ok(());
+    }
+    let (Some(branch), Some(file)) = (job.branch.as_deref(), result.failed_files.first()) else {
+        return Ok(());
+    };
+
+    let adapter = build_adapter(config, repo.platform)?;
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let policy = policy(adapter.as_ref(), &repo_id).await;
+    if !policy.enabled || adapter.get_default_branch(&repo_id).await? != branch {
+        return Ok(());
+    }
+
+    let Some(good_sha) = last_pass(store, job, branch, file).await? else {
+        return Ok(());
+    };
+    if already_bisecting(store, &repo, job, file).await? {
+        return Ok(());
+    }
+
+    let commits = adapter
+        .list_commits_between(&repo_id, &good_sha, &job.commit_sha)
+        .await?;
+    let pending: Vec<String> = commits
+        .into_iter()
+        .filter(|c| *c != good_sha && *c != job.commit_sha)
+        .collect();
+    if pending.len() > policy.max_commits as usize {
+        tracing::info!(
+            "Not bisecting {} in {}: {} commits since it last passed (max {})",
+            file,
+            repo.full_name(),
+            pending.len(),
+            policy.max_commits
+        );
+        return Ok(());
+    }
+
+    let mut bisection = BisectionRecord::new(
+        repo.id,
+        job.prover.clone(),
+        branch.to_string(),
+        file.clone(),
+        good_sha,
+        job.commit_sha.clone(),
+        pending,
+    );
+    bisection.theorem = store
+        .list_proof_items(job.id)
+        .await?
+        .into_iter()
+        .find(|i| i.file_path == *file && i.theorem.is_some() && i.status == ItemStatus::Failed)
+        .and_then(|i| i.theorem);
+    tracing::info!(
+        "Bisecting {} in {}: {} commit(s) between {:.8} and {:.8}",
+        file,
+        repo.full_name(),
+        bisection.pending.len(),
+        bisection.good_sha,
+        bisection.bad_sha
+    );
+    drive(store, scheduler, config, &repo, bisection, None).await
+}
+
+/// Decide midpoints from stored results until one needs a probe job, or
+/// the range is exhausted. `probed` is the verdict of the job that just
+/// checked the current midpoint.
+async fn drive(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    config: &Config,
+    repo: &Repository,
+    mut bisection: BisectionRecord,
+    mut probed: Option<bool>,
+) -> Result<()> {
+    while let Some(sha) = next_probe(&bisection).map(str::to_string) {
+        let verdict = match probed.take() {
+            Some(passed) => Some(passed),
+            None => stored_verdict(store, &bisection, &sha).await?,
+        };
+        match verdict {
+            Some(passed) => {
+                if bisection.probe_job.take().is_none() {
+                    bisection.reused += 1;
+                }
+                narrow(&mut bisection, &sha, passed);
+            }
+            None => {
+                let job = ProofJob::new(
+                    bisection.repo_id,
+                    sha.clone(),
+                    bisection.prover.clone(),
+                    vec![bisection.file_path.clone()],
+                )
+                .with_priority(JobPriority::Low);
+                let mut record = ProofJobRecord::from(job.clone());
+                record.payload.bisection = Some(bisection.id);
+                store.create_job(&record).await?;
+                // `None`: a job for this commit is already queued; its
+                // result decides the midpoint just the same.
+                if scheduler.enqueue(job.clone()).await?.is_some() {
+                    bisection.probe_job = Some(job.id.0);
+                    bisection.probes += 1;
+                }
+                bisection.updated_at = Utc::now();
+                return store.upsert_bisection(&bisection).await;
+            }
+        }
+    }
+
+    bisection.status = BisectStatus::Found;
+    bisection.updated_at = Utc::now();
+    store.upsert_bisection(&bisection).await?;
+    tracing::info!(
+        "{} in {} was broken by {}",
+        bisection.file_path,
+        repo.full_name(),
+        bisection.bad_sha
+    );
+
+    let adapter = build_adapter(config, repo.platform)?;
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let issue = issue(&bisection, &policy(adapter.as_ref(), &repo_id).await.labels);
+    let body = issue.body.clone();
+    let id = adapter.create_issue(&repo_id, issue).await?;
+    crate::audit::record(
+        store,
+        crate::audit::BOT,
+        AuditAction::IssueOpened,
+        Some(repo),
+        Some(format!("issue #{}", id.0)),
+        &body,
+    )
+    .await;
+    bisection.issue = Some(id.0);
+    store.upsert_bisection(&bisection).await
+}
+
+async fn policy(adapter: &dyn PlatformAdapter, repo: &RepoId) -> BisectSection {
+    fetch_directive_via_adapter(adapter, repo, None)
+        .await
+        .as_deref()
+        .and_then(RepoManifest::parse)
+        .unwrap_or_default()
+        .bisect
+}
+
+/// How `file` fared in job `job_id`, from its per-file results.
+async fn file_verdict(store: &dyn Store, job_id: JobId, file: &str) -> Option<bool> {
+    let timings = store.list_file_timings_for_job(job_id).await.ok()?;
+    timings.iter().find(|t| t.file_path == file).map(|t| t.success)
+}
+
+/// The newest stored result for the bisected file at `sha`.
+async fn stored_verdict(
+    store: &dyn Store,
+    bisection: &BisectionRecord,
+    sha: &str,
+) -> Result<Option<bool>> {
+    let timings = store
+        .list_file_timings_for_commit(bisection.repo_id, bisection.prover.clone(), sha)
+        .await?;
+    Ok(timings
+        .iter()
+        .filter(|t| t.file_path == bisection.file_path)
+        .max_by_key(|t| t.created_at)
+        .map(|t| t.success))
+}
+
+/// The commit of `branch`'s previous run of `job`'s prover, when `file`
+/// passed there.
+async fn last_pass(
+    store: &dyn Store,
+    job: &ProofJob,
+    branch: &str,
+    file: &str,
+) -> Result<Option<String>> {
+    let jobs = store.list_jobs_for_repo(job.repo_id, JOB_SCAN_LIMIT).await?;
+    let previous = jobs.into_iter().find(|j| {
+        j.id != job.id.0
+            && j.prover == job.prover
+            && j.branch.as_deref() == Some(branch)
+            && j.pr_number.is_none()
+            && j.payload.bisection.is_none()
+            && j.commit_sha != job.commit_sha
+            && j.queued_at < job.queued_at
+            && matches!(j.status, JobStatus::Completed | JobStatus::Failed)
+    });
+    let Some(previous) = previous else {
+        return Ok(None);
+    };
+    let passed = file_verdict(store, JobId(previous.id), file)
+        .await
+        .unwrap_or(previous.status == JobStatus::Completed);
+    Ok(passed.then_some(previous.commit_sha))
+}
+
+/// Whether `file` is already being bisected for `job`'s prover.
+async fn already_bisecting(
+    store: &dyn Store,
+    repo: &Repository,
+    job: &ProofJob,
+    file: &str,
+) -> Result<bool> {
+    let stale = Utc::now() - chrono::Duration::hours(STALE_AFTER_HOURS);
+    Ok(store
+        .list_bisections(repo.id, BISECTION_SCAN_LIMIT)
+        .await?
+        .iter()
+        .any(|b| {
+            b.status == BisectStatus::Running
+                && b.prover == job.prover
+                && b.file_path == file
+                && b.updated_at > stale
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::ProverKind;
+    use uuid::Uuid;
+
+    fn bisection(pending: &[&str]) -> BisectionRecord {
+        BisectionRecord::new(
+            Uuid::new_v4(),
+            ProverKind::new("coq"),
+            "main".into(),
+            "theories/A.v".into(),
+            "c0".into(),
+            "c9".into(),
+            pending.iter().map(|c| c.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn halving_finds_the_first_failing_commit() {
+        // c1..c8 between a good c0 and a bad c9; c6 broke the proof.
+        let mut b = bisection(&["c1", "c2", "c3", "c4", "c5", "c6", "c7", "c8"]);
+        let broken = |sha: &str| sha >= "c6";
+        let mut checked = Vec::new();
+        while let Some(sha) = next_probe(&b).map(str::to_string) {
+            narrow(&mut b, &sha, !broken(&sha));
+            checked.push(sha);
+        }
+        assert_eq!(checked, ["c5", "c7", "c6"]);
+        assert_eq!((b.good_sha.as_str(), b.bad_sha.as_str()), ("c5", "c6"));
+
+        b.theorem = Some("plus_comm".into());
+        b.probes = 2;
+        b.reused = 1;
+        let issue = issue(&b, &["proof-regression".into()]);
+        assert_eq!(issue.title, "Proof regression: theories/A.v broken by c6");
+        assert!(issue.body.contains("since commit c6, first at theorem `plus_comm`"));
+        assert!(issue.body.contains("| Last passing | c5 |"));
+        assert!(issue.body.contains("3 commit(s) decided, 2 by a check run"));
+        assert_eq!(issue.labels, ["proof-regression"]);
+    }
+}
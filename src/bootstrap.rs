@@ -0,0 +1,325 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! First-event bootstrap PR
+//!
+//! A repo registered through an App install usually has no directive yet,
+//! so everything falls back to the daemon defaults. With `[bot] config_pr`
+//! enabled, the first event from such a repo makes the bot offer a PR
+//! adding `.echidnabot.toml`: the provers detected from the file
+//! extensions on the default branch (or the repo's enabled provers when
+//! none are recognised), their proof globs, the usual escape hatches to
+//! forbid, and the resolved mode.
+//!
+//! The same detection backs `echidnabot register --detect-provers`
+//! ([`detect_repo_provers`]), which lists the tree through the platform
+//! API or, where the adapter cannot, a shallow clone.
+//!
+//! "Unconfigured" means every directive path answered "not found"; a
+//! lookup error leaves the question open for the next event. The offer is
+//! recorded on the repository before the PR is opened, so a repo gets at
+//! most one such PR even if it is closed unmerged. The outcome is written
+//! to the `echidnabot::audit` tracing target.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::adapters::{NewPullRequest, PlatformAdapter, RepoId};
+use crate::dispatcher::ProverKind;
+use crate::error::Result;
+use crate::modes::directives::{DIRECTIVE_PATHS, DIRECTIVE_PATH_DOTFILE};
+use crate::modes::BotMode;
+use crate::store::models::Repository;
+use crate::store::Store;
+use crate::trust::escape_hatch::placeholders;
+
+/// Branch the template is committed to.
+pub const CONFIG_PR_BRANCH: &str = "echidnabot/configure";
+
+/// Extensions shared with ordinary source code (OCaml, Lisp, SML); a
+/// file with one of these says nothing about which prover the repo uses.
+const AMBIGUOUS_EXTENSIONS: &[&str] = &[".ml", ".lisp", ".sml"];
+
+/// Project files that name their prover whatever the proof files are
+/// called: Lake, `coq_makefile`, Isabelle sessions, Agda libraries.
+const PROJECT_MANIFESTS: &[(&str, &str)] = &[
+    ("lakefile.lean", "lean"),
+    ("lakefile.toml", "lean"),
+    ("lean-toolchain", "lean"),
+    ("_CoqProject", "coq"),
+    ("ROOT", "isabelle"),
+    ("ROOTS", "isabelle"),
+];
+
+/// Files listed when scanning a clone, so a huge monorepo cannot stall
+/// registration.
+const MAX_SCANNED_FILES: usize = 100_000;
+
+/// Provers with at least one recognised proof file or project manifest
+/// among `paths`, in slug order.
+pub fn detect_provers<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<ProverKind> {
+    let mut found = BTreeSet::new();
+    for path in paths {
+        let file = path.rsplit('/').next().unwrap_or(path);
+        if let Some((_, slug)) = PROJECT_MANIFESTS.iter().find(|(name, _)| *name == file) {
+            found.insert(slug.to_string());
+            continue;
+        }
+        if file.ends_with(".agda-lib") {
+            found.insert("agda".to_string());
+            continue;
+        }
+        let Some(dot) = file.rfind('.') else { continue };
+        let ext = file[dot..].to_lowercase();
+        if AMBIGUOUS_EXTENSIONS.contains(&ext.as_str()) {
+            continue;
+        }
+        if let Some(prover) = ProverKind::from_extension(&ext) {
+            found.insert(prover.as_str().to_string());
+        }
+    }
+    found.into_iter().map(ProverKind::new).collect()
+}
+
+/// Provers detected on `repo`'s default branch. Lists the tree through
+/// the adapter, falling back to a shallow clone when it cannot.
+pub async fn detect_repo_provers(
+    adapter: &dyn PlatformAdapter,
+    repo: &RepoId,
+) -> Result<Vec<ProverKind>> {
+    let listed = match adapter.get_default_branch(repo).await {
+        Ok(branch) => adapter.list_files(repo, &branch).await,
+        Err(e) => Err(e),
+    };
+    let files = match listed {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::info!("Listing {} via the API failed ({}); cloning instead", repo.full_name(), e);
+            let checkout = adapter.clone_repo(repo, "HEAD").await?;
+            let root = checkout.clone();
+            let files = tokio::task::spawn_blocking(move || list_tree(&root))
+                .await
+                .map_err(|e| crate::error::Error::Internal(e.to_string()))?;
+            let _ = tokio::fs::remove_dir_all(&checkout).await;
+            files
+        }
+    };
+    Ok(detect_provers(files.iter().map(String::as_str)))
+}
+
+/// Repo-relative paths of the files under `root`, `.git` excluded.
+fn list_tree(root: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                if entry.file_name() != ".git" {
+                    pending.push(path);
+                }
+            } else if let Ok(rel) = path.strip_prefix(root) {
+                files.push(rel.to_string_lossy().replace('\\', "/"));
+                if files.len() >= MAX_SCANNED_FILES {
+                    return files;
+                }
+            }
+        }
+    }
+    files
+}
+
+/// Inline TOML array body: `"a", "b"`.
+fn toml_list<S: AsRef<str>>(items: impl IntoIterator<Item = S>) -> String {
+    items
+        .into_iter()
+        .map(|s| format!("{:?}", s.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The `.echidnabot.toml` offered to an unconfigured repo.
+pub fn render_template(provers: &[ProverKind], mode: BotMode) -> String {
+    let mode = serde_json::to_value(mode)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_else(|| "verifier".to_string());
+    let mut globs = BTreeSet::new();
+    let mut forbid = BTreeSet::new();
+    for prover in provers {
+        globs.extend(prover.file_extensions().iter().map(|ext| format!("**/*{}", ext)));
+        forbid.extend(placeholders(prover).iter().map(|s| s.to_string()));
+    }
+
+    format!(
+        r#"# echidnabot configuration. Generated from the files on the default
+# branch; adjust freely. Reference:
+# https://github.com/hyperpolymath/echidnabot/blob/main/docs/content/configuration.md
+schema_version = "2.0"
+
+[bot]
+# verifier | advisor | consultant | regulator
+mode = "{mode}"
+
+[provers]
+enabled = [{enabled}]
+
+[proofs]
+include = [{include}]
+exclude = ["vendor/**"]
+
+[axioms]
+forbid = [{forbid}]
+severity = "warning"
+"#,
+        enabled = toml_list(provers.iter().map(|p| p.as_str())),
+        include = toml_list(&globs),
+        forbid = toml_list(&forbid),
+    )
+}
+
+fn pr_body(provers: &[ProverKind], detected: bool) -> String {
+    let names = provers
+        .iter()
+        .map(|p| p.display_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let source = if detected {
+        "detected from the proof files and project manifests in this repository"
+    } else {
+        "taken from this repository's registration (no proof files were recognised)"
+    };
+    format!(
+        "This repository has no echidnabot configuration yet, so it is verified \
+         with the daemon defaults.\n\n\
+         This PR adds `{}` with the provers {} — **{}** — plus their proof \
+         globs and a list of proof escape hatches to flag.\n\n\
+         Merge it to pin these settings, or edit it first. If you close it, \
+         it will not be offered again.",
+        DIRECTIVE_PATH_DOTFILE, source, names
+    )
+}
+
+/// Offer the config PR to `repo` if it has never been offered and the
+/// repo has no directive. Returns the PR number when one was opened.
+pub async fn offer_config_pr(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    mode: BotMode,
+) -> Result<Option<u64>> {
+    if repo.config_pr_offered_at.is_some() {
+        return Ok(None);
+    }
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    for path in DIRECTIVE_PATHS {
+        if adapter.get_file_contents(&repo_id, None, path).await?.is_some() {
+            return Ok(None);
+        }
+    }
+
+    let base = adapter.get_default_branch(&repo_id).await?;
+    let files = adapter.list_files(&repo_id, &base).await.unwrap_or_else(|e| {
+        tracing::warn!("Could not list files of {}: {}", repo.full_name(), e);
+        Vec::new()
+    });
+    let detected = detect_provers(files.iter().map(String::as_str));
+    let (provers, was_detected) = if detected.is_empty() {
+        (repo.enabled_provers.clone(), false)
+    } else {
+        (detected, true)
+    };
+
+    let mut offered = repo.clone();
+    offered.config_pr_offered_at = Some(chrono::Utc::now());
+    store.update_repository(&offered).await?;
+
+    let template = render_template(&provers, mode);
+    let pr = NewPullRequest {
+        title: format!("Add {} for proof verification", DIRECTIVE_PATH_DOTFILE),
+        body: pr_body(&provers, was_detected),
+        base,
+        head: CONFIG_PR_BRANCH.to_string(),
+        commit_message: format!("Add {}", DIRECTIVE_PATH_DOTFILE),
+        files: vec![(DIRECTIVE_PATH_DOTFILE.to_string(), template.clone())],
+        changes: vec![],
+    };
+    let outcome = adapter.create_pull_request(&repo_id, pr).await;
+    let (result, detail) = match &outcome {
+        Ok(pr) => ("opened", format!("PR #{}", pr.0)),
+        Err(e) => ("failed", e.to_string()),
+    };
+    tracing::info!(
+        target: "echidnabot::audit",
+        action = "config_pr",
+        repo = %repo.full_name(),
+        provers = %provers.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(","),
+        result,
+        detail = %detail,
+        "Bootstrap config PR {} for {}",
+        result,
+        repo.full_name()
+    );
+    let opened = outcome?;
+    crate::audit::record(
+        store,
+        crate::audit::BOT,
+        crate::audit::AuditAction::ConfigPrOpened,
+        Some(repo),
+        Some(format!("PR #{}", opened.0)),
+        &template,
+    )
+    .await;
+    Ok(opened.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modes::RepoManifest;
+
+    #[test]
+    fn detects_provers_and_renders_a_valid_manifest() {
+        let provers = detect_provers([
+            "theories/Nat.v",
+            "src/Main.lean",
+            "lib/parser.ml",
+            "README.md",
+            "Makefile",
+        ]);
+        let slugs: Vec<_> = provers.iter().map(|p| p.as_str()).collect();
+        assert_eq!(slugs, ["coq", "lean"]);
+
+        let manifest = RepoManifest::parse(&render_template(&provers, BotMode::Advisor)).unwrap();
+        assert_eq!(manifest.bot.mode, Some(BotMode::Advisor));
+        assert_eq!(manifest.provers.enabled, ["coq", "lean"]);
+        assert_eq!(manifest.proofs.include, ["**/*.lean", "**/*.v"]);
+        assert!(manifest.axioms.forbid.contains(&"sorry".to_string()));
+    }
+
+    #[test]
+    fn detects_provers_from_project_manifests() {
+        let provers = detect_provers([
+            "lakefile.lean",
+            "theories/_CoqProject",
+            "HOL/ROOT",
+            "standard-library.agda-lib",
+            "docs/ROOT.md",
+        ]);
+        let slugs: Vec<_> = provers.iter().map(|p| p.as_str()).collect();
+        assert_eq!(slugs, ["agda", "coq", "isabelle", "lean"]);
+    }
+
+    #[test]
+    fn lists_a_checkout_without_git_metadata() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join(".git/objects")).unwrap();
+        std::fs::write(root.path().join(".git/objects/x.v"), "").unwrap();
+        std::fs::create_dir_all(root.path().join("src")).unwrap();
+        std::fs::write(root.path().join("src/Main.lean"), "").unwrap();
+
+        assert_eq!(list_tree(root.path()), ["src/Main.lean"]);
+    }
+}
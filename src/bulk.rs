@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Settings changes across many repositories
+//!
+//! A [`SettingsPatch`] holds the settings to change, `None` meaning "leave
+//! as is". Applied to one repository it is what `updateRepoSettings`
+//! does; [`plan`] applies it to every repository a [`RepoFilter`]
+//! matches, in memory, and reports each field that would change, and
+//! [`commit`] stores the result. `bulkUpdateRepoSettings` and
+//! `echidnabot bulk-update` show the plan as a preview (`dryRun` /
+//! `--dry-run`) or commit it.
+//!
+//! Each repository actually changed gets its own `repo.settings_changed`
+//! audit entry; matched repositories already in the requested state are
+//! left alone and not logged.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::adapters::Platform;
+use crate::audit::{self, AuditAction};
+use crate::dispatcher::ProverKind;
+use crate::error::Result;
+use crate::modes::manifest::wildcard_match;
+use crate::store::models::{Repository, Visibility};
+use crate::store::Store;
+
+/// Which repositories a bulk change applies to. Every criterion set must
+/// hold; an empty filter matches every registered repository.
+#[derive(Debug, Clone, Default)]
+pub struct RepoFilter {
+    pub platform: Option<Platform>,
+    /// Owner (user, group or organisation), exactly.
+    pub owner: Option<String>,
+    /// Pattern (`*`, `?`) matched against `owner/name`.
+    pub name: Option<String>,
+    /// Only repositories with this prover enabled.
+    pub prover: Option<ProverKind>,
+    /// Only monitored (`true`) or disabled (`false`) repositories.
+    pub enabled: Option<bool>,
+}
+
+impl RepoFilter {
+    pub fn matches(&self, repo: &Repository) -> bool {
+        self.platform.is_none_or(|p| p == repo.platform)
+            && self.owner.as_ref().is_none_or(|o| *o == repo.owner)
+            && self.name.as_ref().is_none_or(|n| wildcard_match(n, &repo.full_name()))
+            && self.prover.as_ref().is_none_or(|p| repo.enabled_provers.contains(p))
+            && self.enabled.is_none_or(|e| e == repo.enabled)
+    }
+}
+
+/// Settings to set; `None` leaves a setting unchanged. Values are
+/// expected to be validated already (cron expression, version pins).
+#[derive(Debug, Clone, Default)]
+pub struct SettingsPatch {
+    pub webhook_secret: Option<String>,
+    pub enabled_provers: Option<Vec<ProverKind>>,
+    pub check_on_push: Option<bool>,
+    pub check_on_pr: Option<bool>,
+    pub auto_comment: Option<bool>,
+    pub branch_include: Option<Vec<String>>,
+    pub branch_exclude: Option<Vec<String>>,
+    pub path_include: Option<Vec<String>>,
+    pub path_exclude: Option<Vec<String>>,
+    pub visibility: Option<Visibility>,
+    pub training_export: Option<bool>,
+    /// `Some(None)` removes the schedule.
+    pub reverify_cron: Option<Option<String>>,
+    /// `Some(None)` inherits the org's or instance's time zone again.
+    pub timezone: Option<Option<String>>,
+    pub prover_pins: Option<BTreeMap<String, String>>,
+}
+
+/// One setting changed on one repository. Values are JSON; a webhook
+/// secret shows as `"<redacted>"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+pub(crate) fn set<T: Clone + PartialEq + Serialize>(
+    changes: &mut Vec<FieldChange>,
+    field: &'static str,
+    slot: &mut T,
+    value: &Option<T>,
+) {
+    if let Some(value) = value {
+        if slot != value {
+            changes.push(FieldChange { field, from: json(slot), to: json(value) });
+            *slot = value.clone();
+        }
+    }
+}
+
+impl SettingsPatch {
+    /// Whether the patch sets nothing.
+    pub fn is_empty(&self) -> bool {
+        self.webhook_secret.is_none()
+            && self.enabled_provers.is_none()
+            && self.check_on_push.is_none()
+            && self.check_on_pr.is_none()
+            && self.auto_comment.is_none()
+            && self.branch_include.is_none()
+            && self.branch_exclude.is_none()
+            && self.path_include.is_none()
+            && self.path_exclude.is_none()
+            && self.visibility.is_none()
+            && self.training_export.is_none()
+            && self.reverify_cron.is_none()
+            && self.timezone.is_none()
+            && self.prover_pins.is_none()
+    }
+
+    /// Apply to `repo`, returning what changed. `updated_at` is left to
+    /// the caller.
+    pub fn apply(&self, repo: &mut Repository) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        if let Some(secret) = &self.webhook_secret {
+            if repo.webhook_secret.as_ref() != Some(secret) {
+                let shown = |s: &Option<String>| json(&s.as_ref().map(|_| "<redacted>"));
+                changes.push(FieldChange {
+                    field: "webhookSecret",
+                    from: shown(&repo.webhook_secret),
+                    to: shown(&Some(secret.clone())),
+                });
+                repo.webhook_secret = Some(secret.clone());
+            }
+        }
+        set(&mut changes, "enabledProvers", &mut repo.enabled_provers, &self.enabled_provers);
+        set(&mut changes, "checkOnPush", &mut repo.check_on_push, &self.check_on_push);
+        set(&mut changes, "checkOnPr", &mut repo.check_on_pr, &self.check_on_pr);
+        set(&mut changes, "autoComment", &mut repo.auto_comment, &self.auto_comment);
+        set(&mut changes, "branchInclude", &mut repo.branch_include, &self.branch_include);
+        set(&mut changes, "branchExclude", &mut repo.branch_exclude, &self.branch_exclude);
+        set(&mut changes, "pathInclude", &mut repo.path_include, &self.path_include);
+        set(&mut changes, "pathExclude", &mut repo.path_exclude, &self.path_exclude);
+        set(&mut changes, "visibility", &mut repo.visibility, &self.visibility);
+        set(&mut changes, "trainingExport", &mut repo.training_export, &self.training_export);
+        set(&mut changes, "reverifyCron", &mut repo.reverify_cron, &self.reverify_cron);
+        set(&mut changes, "timezone", &mut repo.timezone, &self.timezone);
+        set(&mut changes, "proverPins", &mut repo.prover_pins, &self.prover_pins);
+        changes
+    }
+}
+
+/// A matched repository as it would be after the patch, and what changed.
+#[derive(Debug, Clone)]
+pub struct RepoChange {
+    pub repo: Repository,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Apply `patch` to every repository `filter` matches, without storing
+/// anything. In `owner/name` order; unchanged repositories are included
+/// with no changes.
+pub async fn plan(store: &dyn Store, filter: &RepoFilter, patch: &SettingsPatch) -> Result<Vec<RepoChange>> {
+    let mut repos: Vec<Repository> = store
+        .list_repositories(filter.platform)
+        .await?
+        .into_iter()
+        .filter(|repo| filter.matches(repo))
+        .collect();
+    repos.sort_by_key(|repo| repo.full_name());
+    Ok(repos
+        .into_iter()
+        .map(|mut repo| {
+            let changes = patch.apply(&mut repo);
+            RepoChange { repo, changes }
+        })
+        .collect())
+}
+
+/// Store the planned repositories that change, with an audit entry each
+/// by `actor`. Returns how many were stored; stops at the first store
+/// error, leaving those before it changed.
+pub async fn commit(store: &dyn Store, actor: &str, plan: &mut [RepoChange]) -> Result<usize> {
+    let mut stored = 0;
+    for change in plan.iter_mut().filter(|c| !c.changes.is_empty()) {
+        change.repo.updated_at = chrono::Utc::now();
+        store.update_repository(&change.repo).await?;
+        let payload = audit::settings_payload(&change.repo);
+        audit::record(store, actor, AuditAction::RepoSettingsChanged, Some(&change.repo), None, payload).await;
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::models::AuditFilter;
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn bulk_changes_preview_then_store_with_one_audit_entry_each() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let mut repos = Vec::new();
+        for (owner, name, push) in [("org", "a", true), ("org", "b", false), ("other", "c", true)] {
+            let mut repo = Repository::new(Platform::GitHub, owner.into(), name.into());
+            repo.check_on_push = push;
+            store.create_repository(&repo).await.unwrap();
+            repos.push(repo);
+        }
+        let filter = RepoFilter { owner: Some("org".into()), ..RepoFilter::default() };
+        let patch = SettingsPatch { check_on_push: Some(false), ..SettingsPatch::default() };
+
+        let mut planned = plan(&store, &filter, &patch).await.unwrap();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(
+            planned[0].changes,
+            [FieldChange { field: "checkOnPush", from: "true".into(), to: "false".into() }]
+        );
+        assert!(planned[1].changes.is_empty(), "org/b is already off");
+        let unchanged = store.get_repository(repos[0].id).await.unwrap().unwrap();
+        assert!(unchanged.check_on_push, "planning stores nothing");
+
+        assert_eq!(commit(&store, audit::CLI, &mut planned).await.unwrap(), 1);
+        assert!(!store.get_repository(repos[0].id).await.unwrap().unwrap().check_on_push);
+        assert!(store.get_repository(repos[2].id).await.unwrap().unwrap().check_on_push);
+        let entries = store.list_audit(&AuditFilter::default(), 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].repo.as_deref(), Some("org/a"));
+
+        let glob = RepoFilter { name: Some("*/c".into()), ..RepoFilter::default() };
+        assert!(glob.matches(&repos[2]) && !glob.matches(&repos[0]));
+    }
+}
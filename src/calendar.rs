@@ -0,0 +1,291 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Repository time zones and blackout windows (`[calendar]`)
+//!
+//! Every repository has a time zone: its own `timezone` setting, else its
+//! owner's (`[calendar.orgs.<owner>] timezone`), else the instance's
+//! (`[calendar] timezone`), else UTC. `reverify_cron` schedules fire in
+//! it, so `0 3 * * *` is 03:00 where the team works.
+//!
+//! Blackout windows (`[[calendar.blackouts]]` for every repository,
+//! `[[calendar.orgs.<owner>.blackouts]]` for one owner's) are read in the
+//! same time zone: business hours, or a change freeze. While a repository
+//! is in one
+//!
+//! - a scheduled re-verification that comes due waits, and runs on the
+//!   first check after the window ends;
+//! - its queued low-priority jobs (re-verifications, bisections, replayed
+//!   webhooks) keep their place without starting. [`run_blackout_monitor`]
+//!   works out which repositories are held every `check_interval_secs`.
+//!
+//! Pushes, pull requests and manual triggers are never held.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+use crate::config::{BlackoutWindow, CalendarConfig, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::JobScheduler;
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::Repository;
+use crate::store::Store;
+
+/// Parse an IANA time zone name such as `Europe/London`.
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        Error::InvalidInput(format!(
+            "unknown time zone '{}' (expected an IANA name such as Europe/London)",
+            name
+        ))
+    })
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| Error::Config(format!("blackout time '{}' must be HH:MM", s)))
+}
+
+impl BlackoutWindow {
+    /// Reject windows with malformed times or that would never end.
+    pub fn validate(&self) -> Result<()> {
+        for time in self.start.iter().chain(&self.end) {
+            parse_time(time)?;
+        }
+        if let (Some(from), Some(until)) = (self.from, self.until) {
+            if until < from {
+                return Err(Error::Config(format!("{}: `until` is before `from`", self.label())));
+            }
+        }
+        let bounded = !self.days.is_empty()
+            || self.start.is_some()
+            || self.end.is_some()
+            || self.until.is_some();
+        if !bounded {
+            return Err(Error::Config(format!(
+                "{} never ends: set days, start / end or until",
+                self.label()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the local time `at` falls in the window.
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let date = at.date();
+        if self.from.is_some_and(|from| date < from) || self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+        if !self.days.is_empty() && !self.days.contains(&date.weekday()) {
+            return false;
+        }
+        let start = self.start.as_deref().and_then(|s| parse_time(s).ok());
+        let end = self.end.as_deref().and_then(|s| parse_time(s).ok());
+        let time = at.time();
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => start <= time && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            (Some(start), None) => time >= start,
+            (None, Some(end)) => time < end,
+            (None, None) => true,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("blackout window '{}'", name),
+            None => "blackout window".to_string(),
+        }
+    }
+}
+
+impl CalendarConfig {
+    /// Check every time zone and window; run at startup and on reload.
+    pub fn validate(&self) -> Result<()> {
+        let org_zones = self.orgs.values().filter_map(|org| org.timezone.as_ref());
+        for name in self.timezone.iter().chain(org_zones) {
+            parse_timezone(name).map_err(|e| Error::Config(e.to_string()))?;
+        }
+        let org_windows = self.orgs.values().flat_map(|org| &org.blackouts);
+        for window in self.blackouts.iter().chain(org_windows) {
+            window.validate()?;
+        }
+        Ok(())
+    }
+
+    fn has_blackouts(&self) -> bool {
+        !self.blackouts.is_empty() || self.orgs.values().any(|org| !org.blackouts.is_empty())
+    }
+
+    /// The time zone `repo`'s schedules and windows are read in. Unknown
+    /// names fall through to the next level.
+    pub fn timezone_for(&self, repo: &Repository) -> Tz {
+        let org = self.orgs.get(&repo.owner).and_then(|org| org.timezone.as_ref());
+        [repo.timezone.as_ref(), org, self.timezone.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|name| parse_timezone(name).ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    /// The window `repo` is in at `now`, if any.
+    pub fn blackout_for(&self, repo: &Repository, now: DateTime<Utc>) -> Option<&BlackoutWindow> {
+        if !self.has_blackouts() {
+            return None;
+        }
+        let local = now.with_timezone(&self.timezone_for(repo)).naive_local();
+        let org = self.orgs.get(&repo.owner).map(|org| org.blackouts.as_slice());
+        self.blackouts
+            .iter()
+            .chain(org.unwrap_or_default())
+            .find(|window| window.contains(local))
+    }
+}
+
+/// Refresh the held repositories until shutdown.
+pub async fn run_blackout_monitor(
+    store: Arc<dyn Store>,
+    scheduler: Arc<JobScheduler>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        let current = config.load_full();
+        if let Err(e) = refresh(store.as_ref(), &scheduler, &current.calendar, Utc::now()).await {
+            tracing::warn!("Blackout window check failed: {}", e);
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Blackout monitor observed shutdown signal — stopping");
+                return;
+            }
+        }
+    }
+}
+
+/// Hold the low-priority jobs of every enabled repository in a window at
+/// `now`, and release the rest. Returns how many repositories are held.
+pub async fn refresh(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    calendar: &CalendarConfig,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let mut held = HashSet::new();
+    if calendar.has_blackouts() {
+        for repo in store.list_repositories(None).await? {
+            if !repo.enabled {
+                continue;
+            }
+            if let Some(window) = calendar.blackout_for(&repo, now) {
+                tracing::debug!("{} is in {}", repo.full_name(), window.label());
+                held.insert(repo.id);
+            }
+        }
+    }
+    let count = held.len();
+    scheduler.set_held_repos(held);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::config::OrgCalendar;
+    use chrono::Weekday;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn business_hours() -> BlackoutWindow {
+        BlackoutWindow {
+            days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            start: Some("09:00".into()),
+            end: Some("18:00".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn time_zone_falls_back_from_repo_to_org_to_instance() {
+        let mut calendar = CalendarConfig {
+            timezone: Some("Europe/London".into()),
+            ..Default::default()
+        };
+        let mut repo = Repository::new(Platform::GitHub, "acme".into(), "r".into());
+        assert_eq!(calendar.timezone_for(&repo), Tz::Europe__London);
+
+        let org = OrgCalendar { timezone: Some("America/New_York".into()), ..Default::default() };
+        calendar.orgs.insert("acme".into(), org);
+        assert_eq!(calendar.timezone_for(&repo), Tz::America__New_York);
+
+        repo.timezone = Some("Asia/Tokyo".into());
+        assert_eq!(calendar.timezone_for(&repo), Tz::Asia__Tokyo);
+        repo.timezone = Some("Mars/Olympus".into());
+        assert_eq!(calendar.timezone_for(&repo), Tz::America__New_York);
+        assert_eq!(CalendarConfig::default().timezone_for(&repo), Tz::UTC);
+    }
+
+    #[test]
+    fn windows_are_read_in_local_time() {
+        let calendar = CalendarConfig {
+            timezone: Some("America/New_York".into()),
+            blackouts: vec![business_hours()],
+            ..Default::default()
+        };
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        // Friday 2026-10-16, 14:00 UTC is 10:00 in New York.
+        assert!(calendar.blackout_for(&repo, at("2026-10-16T14:00:00Z")).is_some());
+        // 12:00 UTC is 08:00 there.
+        assert!(calendar.blackout_for(&repo, at("2026-10-16T12:00:00Z")).is_none());
+        // Saturday.
+        assert!(calendar.blackout_for(&repo, at("2026-10-17T14:00:00Z")).is_none());
+    }
+
+    #[test]
+    fn overnight_windows_and_freezes() {
+        let night = BlackoutWindow {
+            start: Some("22:00".into()),
+            end: Some("06:00".into()),
+            ..Default::default()
+        };
+        let local = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        assert!(night.contains(local("2026-10-17 23:30")));
+        assert!(night.contains(local("2026-10-18 05:59")));
+        assert!(!night.contains(local("2026-10-18 06:00")));
+
+        let freeze = BlackoutWindow {
+            name: Some("year-end".into()),
+            from: "2026-12-19".parse().ok(),
+            until: "2027-01-03".parse().ok(),
+            ..Default::default()
+        };
+        assert!(freeze.contains(local("2027-01-03 23:59")));
+        assert!(!freeze.contains(local("2027-01-04 00:00")));
+        assert!(freeze.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_calendars_are_rejected() {
+        let bad_zone = CalendarConfig { timezone: Some("UTC+2".into()), ..Default::default() };
+        assert!(bad_zone.validate().is_err());
+
+        let forever = CalendarConfig {
+            blackouts: vec![BlackoutWindow { name: Some("x".into()), ..Default::default() }],
+            ..Default::default()
+        };
+        assert!(forever.validate().is_err());
+
+        let bad_time = BlackoutWindow { start: Some("9am".into()), ..Default::default() };
+        assert!(bad_time.validate().is_err());
+    }
+}
@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Auditor: signed verification certificates (`[auditor]` in the manifest)
+//!
+//! A result attestation ([`crate::attest`]) vouches for one job. A
+//! certificate vouches for a commit: once the newest job of every prover
+//! at a commit has passed, a repository with `[auditor] enabled = true`
+//! gets an envelope signed with the instance's attestation key, binding
+//!
+//! - the platform, repository and commit SHA;
+//! - per prover: its reported version, the job, the files it verified, the
+//!   SHA-256 of its raw output, and the SHA-256 of the job's result claims
+//!   (the payload `/jobs/{id}/attestation` signs);
+//! - when it was issued.
+//!
+//! One certificate is kept per commit; re-running a prover re-issues it.
+//! It is served at `GET /certificates/{platform}/{owner}/{repo}/{sha}` and
+//! checked offline like an attestation, with `echidnabot attest verify
+//! <file> --keys keys.json`.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::adapters::{build_adapter, RepoId};
+use crate::attest::{self, Envelope, KeySet, ResultSigner};
+use crate::audit::AuditAction;
+use crate::config::Config;
+use crate::error::Result;
+use crate::modes::{fetch_directive_via_adapter, RepoManifest};
+use crate::scheduler::{JobId, JobResult, JobStatus, ProofJob};
+use crate::store::models::{CertificateRecord, ProofJobRecord, Repository};
+use crate::store::Store;
+
+/// `payloadType` of verification certificates.
+pub const PAYLOAD_TYPE: &str = "application/vnd.echidnabot.certificate+json";
+
+/// Jobs scanned for a commit's newest run per prover.
+const JOB_SCAN_LIMIT: usize = 200;
+
+/// What a certificate asserts about one commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateClaims {
+    pub platform: String,
+    pub repo: String,
+    pub commit: String,
+    /// Sorted by prover slug.
+    pub provers: Vec<ProverCertificate>,
+    /// RFC 3339.
+    pub issued_at: String,
+}
+
+/// One prover's passing run of the certified commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProverCertificate {
+    pub prover: String,
+    /// As the prover reported it, when it did.
+    pub version: Option<String>,
+    pub job_id: String,
+    pub verified_files: Vec<String>,
+    /// SHA-256 of the raw prover output, hex.
+    pub output_sha256: String,
+    /// SHA-256 of the job's result claims as serialised in its
+    /// attestation payload, hex.
+    pub result_sha256: String,
+}
+
+/// Sign a certificate for `commit_sha` and store it, replacing any earlier
+/// one. `None` when some prover's newest job there has not passed, or
+/// none ran.
+pub async fn issue(
+    store: &dyn Store,
+    signer: &ResultSigner,
+    repo: &Repository,
+    commit_sha: &str,
+) -> Result<Option<CertificateRecord>> {
+    let jobs = store.list_jobs_for_repo(repo.id, JOB_SCAN_LIMIT).await?;
+    let jobs: Vec<&ProofJobRecord> =
+        jobs.iter().filter(|job| job.commit_sha == commit_sha).collect();
+    if !crate::automerge::tally_jobs(store, &jobs).await?.is_green() {
+        return Ok(None);
+    }
+
+    let mut seen = HashSet::new();
+    let mut provers = Vec::new();
+    for job in jobs {
+        if job.status == JobStatus::Cancelled || !seen.insert(job.prover.clone()) {
+            continue;
+        }
+        // A pass with no stored result has nothing to bind.
+        let Some(result) = store.get_result_for_job(JobId(job.id)).await? else {
+            return Ok(None);
+        };
+        let claims = attest::result_claims(repo, job, &result);
+        let payload = serde_json::to_string(&claims)?;
+        provers.push(ProverCertificate {
+            prover: claims.prover,
+            version: result.prover_version,
+            job_id: claims.job_id,
+            verified_files: claims.verified_files,
+            output_sha256: claims.output_sha256,
+            result_sha256: hex::encode(Sha256::digest(payload.as_bytes())),
+        });
+    }
+    provers.sort_by(|a, b| a.prover.cmp(&b.prover));
+
+    let claims = CertificateClaims {
+        platform: format!("{:?}", repo.platform).to_lowercase(),
+        repo: repo.full_name(),
+        commit: commit_sha.to_string(),
+        provers,
+        issued_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let envelope = signer.sign_payload(PAYLOAD_TYPE, serde_json::to_string(&claims)?);
+    let record = CertificateRecord::new(repo.id, commit_sha.to_string(), envelope);
+    store.save_certificate(&record).await?;
+    Ok(Some(record))
+}
+
+/// Check `envelope` against `keys` and return the certificate's claims.
+pub fn verify(envelope: &Envelope, keys: &KeySet) -> Result<CertificateClaims> {
+    Ok(serde_json::from_str(attest::verify_payload(envelope, keys, PAYLOAD_TYPE)?)?)
+}
+
+/// After a passing job: certify its commit when the repository opted in
+/// and every prover there has now passed. Best-effort: failures are
+/// logged, never returned.
+pub async fn on_job_finished(
+    store: &dyn Store,
+    config: &Config,
+    job: &ProofJob,
+    result: &JobResult,
+) {
+    if !result.success {
+        return;
+    }
+    if let Err(e) = handle(store, config, job).await {
+        tracing::warn!("Certificate for {} not issued: {}", job.commit_sha, e);
+    }
+}
+
+async fn handle(store: &dyn Store, config: &Config, job: &ProofJob) -> Result<()> {
+    let Some(signer) = ResultSigner::from_config(&config.attestation)? else {
+        return Ok(());
+    };
+    let Some(repo) = store.get_repository(job.repo_id).await? else {
+        return Ok(());
+    };
+    let adapter = build_adapter(config, repo.platform)?;
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let enabled = fetch_directive_via_adapter(adapter.as_ref(), &repo_id, None)
+        .await
+        .as_deref()
+        .and_then(RepoManifest::parse)
+        .is_some_and(|manifest| manifest.auditor.enabled);
+    if !enabled {
+        return Ok(());
+    }
+
+    let Some(certificate) = issue(store, &signer, &repo, &job.commit_sha).await? else {
+        return Ok(());
+    };
+    crate::decision::record(store, job.id, |t| {
+        t.policy("auditor", format!("certificate issued for {}", job.commit_sha))
+    })
+    .await;
+    crate::audit::record(
+        store,
+        crate::audit::BOT,
+        AuditAction::CertificateIssued,
+        Some(&repo),
+        Some(job.commit_sha.clone()),
+        certificate.envelope.payload.as_bytes(),
+    )
+    .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::store::models::ProofResultRecord;
+    use crate::store::SqliteStore;
+
+    async fn finish(store: &SqliteStore, repo: uuid::Uuid, prover: &str, success: bool) -> JobId {
+        let job = ProofJob::new(repo, "abc".into(), ProverKind::new(prover), vec![]);
+        let mut record = ProofJobRecord::from(job.clone());
+        record.status = if success { JobStatus::Completed } else { JobStatus::Failed };
+        store.create_job(&record).await.unwrap();
+        let result = JobResult {
+            success,
+            message: String::new(),
+            prover_output: format!("{} output", prover),
+            duration_ms: 1,
+            verified_files: vec![format!("{}.proof", prover)],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: Some("1.0".into()),
+            prover_args: vec![],
+            execution_path: None,
+        };
+        store.save_result(&ProofResultRecord::new(job.id, &result)).await.unwrap();
+        // list_jobs_for_repo orders by queued_at; keep jobs distinct.
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        job.id
+    }
+
+    #[tokio::test]
+    async fn certifies_a_commit_every_prover_passed() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let signer = ResultSigner::from_seed([7; 32]);
+        let keys = KeySet { keys: vec![signer.published_key()] };
+
+        finish(&store, repo.id, "z3", true).await;
+        finish(&store, repo.id, "coq", false).await;
+        assert!(issue(&store, &signer, &repo, "abc").await.unwrap().is_none());
+
+        let coq = finish(&store, repo.id, "coq", true).await;
+        let certificate = issue(&store, &signer, &repo, "abc").await.unwrap().unwrap();
+        let claims = verify(&certificate.envelope, &keys).unwrap();
+        assert_eq!((claims.repo.as_str(), claims.commit.as_str()), ("o/r", "abc"));
+        let provers: Vec<&str> = claims.provers.iter().map(|p| p.prover.as_str()).collect();
+        assert_eq!(provers, ["coq", "z3"]);
+        assert_eq!(claims.provers[0].job_id, coq.to_string());
+        assert_eq!(claims.provers[0].version.as_deref(), Some("1.0"));
+
+        // result_sha256 pins the job's own attestation payload.
+        let attestation =
+            attest::build_job_attestation(&store, &signer, coq).await.unwrap().unwrap();
+        let digest = hex::encode(Sha256::digest(attestation.payload.as_bytes()));
+        assert_eq!(claims.provers[0].result_sha256, digest);
+
+        let stored = store.get_certificate(repo.id, "abc").await.unwrap().unwrap();
+        assert_eq!(stored, certificate);
+        // Not a result attestation, and not signed by a stranger.
+        assert!(attest::verify(&certificate.envelope, &keys).is_err());
+        let stranger = KeySet { keys: vec![ResultSigner::from_seed([8; 32]).published_key()] };
+        assert!(verify(&certificate.envelope, &stranger).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Fault injection for testing behaviour under dependency failures
+//!
+//! Only compiled with `--features chaos`, which no default or release
+//! build enables. With it, `[chaos]` makes calls into ECHIDNA
+//! ([`crate::dispatcher::EchidnaClient`]), the store's job, result,
+//! repository and health paths ([`crate::store::SqliteStore`]) and the
+//! proof executor fail or stall with the configured probabilities:
+//!
+//! ```toml
+//! [chaos]
+//! enabled = true
+//! seed = 42                 # reproducible runs; random when unset
+//!
+//! [chaos.echidna]
+//! error_rate = 0.2          # fraction of calls failing
+//! latency_rate = 0.5        # fraction of calls delayed ...
+//! latency_ms = 2000         # ... by this long
+//!
+//! [chaos.store]
+//! error_rate = 0.05
+//! ```
+//!
+//! Injected store errors are `sqlx::Error::PoolTimedOut`, what a database
+//! outage looks like to callers; ECHIDNA and executor errors carry
+//! "chaos" in their message. Integration tests call [`install`] and
+//! [`clear`] directly.
+
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// A dependency faults can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Echidna,
+    Store,
+    Executor,
+}
+
+/// `[chaos]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seed for the fault RNG, for reproducible runs.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub echidna: FaultSpec,
+    #[serde(default)]
+    pub store: FaultSpec,
+    #[serde(default)]
+    pub executor: FaultSpec,
+}
+
+/// Faults for one [`Target`]. Rates are probabilities in `[0, 1]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FaultSpec {
+    #[serde(default)]
+    pub error_rate: f64,
+    #[serde(default)]
+    pub latency_rate: f64,
+    #[serde(default)]
+    pub latency_ms: u64,
+}
+
+struct Injector {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+}
+
+static INJECTOR: RwLock<Option<Injector>> = RwLock::new(None);
+
+/// Start injecting faults per `config`; replaces any earlier config.
+/// A disabled config is the same as [`clear`].
+pub fn install(config: &ChaosConfig) {
+    let injector = config.enabled.then(|| {
+        tracing::warn!("Chaos fault injection is ACTIVE: {:?}", config);
+        Injector {
+            rng: Mutex::new(match config.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            }),
+            config: config.clone(),
+        }
+    });
+    *INJECTOR.write().expect("chaos injector poisoned") = injector;
+}
+
+/// Stop injecting faults.
+pub fn clear() {
+    *INJECTOR.write().expect("chaos injector poisoned") = None;
+}
+
+/// Roll the dice for one call into `target`: maybe sleep, maybe fail.
+pub async fn inject(target: Target) -> Result<()> {
+    let (delay, fail) = {
+        let guard = INJECTOR.read().expect("chaos injector poisoned");
+        let Some(injector) = guard.as_ref() else {
+            return Ok(());
+        };
+        let spec = match target {
+            Target::Echidna => &injector.config.echidna,
+            Target::Store => &injector.config.store,
+            Target::Executor => &injector.config.executor,
+        };
+        let mut rng = injector.rng.lock().expect("chaos rng poisoned");
+        let delay = (spec.latency_ms > 0 && rng.gen_bool(spec.latency_rate.clamp(0.0, 1.0)))
+            .then(|| Duration::from_millis(spec.latency_ms));
+        (delay, rng.gen_bool(spec.error_rate.clamp(0.0, 1.0)))
+    };
+
+    if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+    }
+    if !fail {
+        return Ok(());
+    }
+    tracing::debug!("chaos: injecting {:?} fault", target);
+    Err(match target {
+        Target::Echidna => Error::Echidna("chaos: injected fault".to_string()),
+        Target::Store => Error::Database(sqlx::Error::PoolTimedOut),
+        Target::Executor => Error::Internal("chaos: injected executor fault".to_string()),
+    })
+}
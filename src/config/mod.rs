@@ -0,0 +1,2398 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Configuration management for echidnabot
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::dispatcher::ProverKind;
+use crate::error::{Error, Result};
+use crate::fleet::workers::ResourceClass;
+use crate::modes::commands::CommandPermissions;
+use crate::modes::BotMode;
+use crate::store::models::TokenScope;
+
+pub mod secrets;
+
+pub use secrets::SecretsConfig;
+
+/// A [`Config`] shared by the daemon's handlers and background tasks and
+/// replaced as a whole on reload (see [`crate::reload`]).
+pub type SharedConfig = Arc<ArcSwap<Config>>;
+
+/// Main configuration structure
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Config {
+    /// Server configuration
+    #[serde(default)]
+    pub server: ServerConfig,
+
+    /// Database configuration
+    #[serde(default)]
+    pub database: DatabaseConfig,
+
+    /// ECHIDNA Core connection
+    #[serde(default)]
+    pub echidna: EchidnaConfig,
+
+    /// GitHub integration
+    #[serde(default)]
+    pub github: Option<GitHubConfig>,
+
+    /// GitLab integration
+    #[serde(default)]
+    pub gitlab: Option<GitLabConfig>,
+
+    /// Codeberg / Forgejo / Gitea integration (issue #62 scaffold —
+    /// adapter is functional but light on features, see
+    /// `src/adapters/codeberg.rs`).
+    #[serde(default)]
+    pub codeberg: Option<CodebergConfig>,
+
+    /// Scheduler configuration
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+
+    /// Time zones and blackout windows for non-urgent jobs.
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+
+    /// Corpus-delta / retrain-trigger configuration (feedback loop).
+    #[serde(default)]
+    pub corpus: CorpusConfig,
+
+    /// Executor configuration — local isolation vs ECHIDNA delegation.
+    #[serde(default)]
+    pub executor: ExecutorConfig,
+
+    /// BoJ server endpoint for Consultant-mode Q&A (Phase 6 / Bit 6b).
+    /// Routes LLM calls through BoJ's `model-router-mcp` cartridge per
+    /// the canonical "BoJ-only MCP" estate rule. Optional — when absent
+    /// or unreachable, Consultant mode degrades to local-data-only.
+    #[serde(default)]
+    pub boj: Option<BoJConfig>,
+
+    /// Daemon-wide bot mode default. Per-repo directives and the DB column
+    /// take precedence; this is the final fallback before `BotMode::Verifier`.
+    ///
+    /// TOML: `[bot]\nmode = "advisor"` (or verifier / consultant / regulator)
+    #[serde(default)]
+    pub bot: BotConfig,
+
+    /// Lifecycle / graceful-shutdown settings.
+    ///
+    /// TOML: `[lifecycle]\nshutdown_timeout_secs = 30`
+    /// Env override: `ECHIDNABOT_SHUTDOWN_TIMEOUT_SECS` (env wins).
+    #[serde(default)]
+    pub lifecycle: LifecycleConfig,
+
+    /// OpenTelemetry observability settings. Honours the standard
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` env var (env wins over TOML).
+    #[serde(default)]
+    pub observability: ObservabilityConfig,
+
+    /// Long-term duration trend analysis. Flags proof files whose latest
+    /// verification time jumps well above their stored history.
+    ///
+    /// TOML: `[anomaly]\nratio_threshold = 3.0`
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+
+    /// API token authentication for `/graphql` and `/metrics`.
+    ///
+    /// TOML: `[auth]\nrequired = true`
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    /// Who may run which `@echidnabot` comment command.
+    ///
+    /// TOML: `[commands.permissions]\nrerun-full = "collaborator"`
+    #[serde(default)]
+    pub commands: CommandsConfig,
+
+    /// Signed result attestations and the published key set.
+    ///
+    /// TOML: `[attestation]\nsigning_key_path = "/etc/echidnabot/attest.key"`
+    #[serde(default)]
+    pub attestation: AttestationConfig,
+
+    /// Publishing signed results to, and importing them from, other
+    /// echidnabot instances.
+    ///
+    /// TOML: `[federation]\npublish = true` plus `[[federation.peers]]`
+    #[serde(default)]
+    pub federation: FederationConfig,
+
+    /// Content-addressed archive of verified artifacts (IPFS, or a local
+    /// directory).
+    ///
+    /// TOML: `[archive]\nenabled = true\nbackend = "ipfs"`
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+
+    /// Anonymous aggregate usage reports. Off unless enabled.
+    ///
+    /// TOML: `[telemetry]\nenabled = true\nendpoint = "https://…"`
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Verification events exported to analytics warehouses.
+    ///
+    /// TOML: `[[analytics.sinks]]\nname = "warehouse"\nkind = "clickhouse"`
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+
+    /// Job lifecycle events published to NATS or Kafka.
+    ///
+    /// TOML: `[events]\nenabled = true\ntransport = "kafka"`
+    #[serde(default)]
+    pub events: EventsConfig,
+
+    /// Per-tenant keys for secrets stored in the database.
+    ///
+    /// TOML: `[tenancy]\nmaster_key = "file:/run/secrets/echidnabot_master_key"`
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+
+    /// Dependency health probes and webhook backpressure.
+    ///
+    /// TOML: `[health]\nretry_after_secs = 60`
+    #[serde(default)]
+    pub health: HealthConfig,
+
+    /// Queue wait objectives per job priority, and alerts on breaches.
+    ///
+    /// TOML: `[slo]\ntarget = 0.95\n[slo.queue_wait_secs]\nhigh = 120`
+    #[serde(default)]
+    pub slo: SloConfig,
+
+    /// Verification workers: what the local dispatch loop runs and which
+    /// resource class each prover needs.
+    ///
+    /// TOML: `[fleet]\nlocal_provers = ["coq", "lean"]`
+    #[serde(default)]
+    pub fleet: FleetConfig,
+
+    /// Windows after toolchain upgrades in which Regulator mode reports
+    /// failures as neutral; see [`crate::grace`].
+    ///
+    /// TOML: `[[grace_periods]]\nprovers = ["coq"]\nexpires_at = "2026-03-15T00:00:00Z"`
+    #[serde(default)]
+    pub grace_periods: Vec<crate::grace::GracePeriod>,
+
+    /// Secret references (`vault:`, `aws-sm:`, `sops:`, `file:`) and
+    /// their refresh; see [`secrets`].
+    ///
+    /// TOML: `[secrets]\nrefresh_interval_secs = 300`
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    /// Fault injection; only read by `--features chaos` builds.
+    ///
+    /// TOML: `[chaos]\nenabled = true\n[chaos.echidna]\nerror_rate = 0.2`
+    #[cfg(feature = "chaos")]
+    #[serde(default)]
+    pub chaos: crate::chaos::ChaosConfig,
+}
+
+/// Lifecycle settings — how long to wait for in-flight work to drain
+/// during a graceful shutdown before forcing teardown.
+///
+/// ```toml
+/// [lifecycle]
+/// shutdown_timeout_secs = 30
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LifecycleConfig {
+    /// Deadline (seconds) for the in-flight-job drain phase of shutdown.
+    /// After this elapses the coordinator proceeds to subsystem teardown
+    /// (DB close, tracer flush) even if jobs are still running — they
+    /// will be aborted as the runtime stops.
+    ///
+    /// Default 30s — generous enough for most provers to finish a
+    /// single verification round, short enough that systemd/k8s won't
+    /// escalate to SIGKILL first (systemd's default `TimeoutStopSec` is
+    /// 90s, k8s's default `terminationGracePeriodSeconds` is 30s).
+    ///
+    /// Overridden by env var `ECHIDNABOT_SHUTDOWN_TIMEOUT_SECS`.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+        }
+    }
+}
+
+/// OpenTelemetry / OTLP exporter configuration.
+///
+/// ```toml
+/// [observability]
+/// otlp_endpoint = "http://localhost:4317"
+/// service_name  = "echidnabot"
+/// ```
+///
+/// `otlp_endpoint` is also picked up from `OTEL_EXPORTER_OTLP_ENDPOINT`
+/// (the standard env var); env takes precedence over the TOML value.
+/// When neither is set, span data is not exported — only the local
+/// fmt subscriber emits logs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ObservabilityConfig {
+    /// OTLP/gRPC collector endpoint (e.g. `http://localhost:4317`).
+    /// `None` disables span export; fmt-layer logs remain active.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+
+    /// `service.name` resource attribute on exported spans. Defaults
+    /// to `echidnabot` so dashboards group correctly out of the box.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+        }
+    }
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS
+}
+
+fn default_service_name() -> String {
+    "echidnabot".to_string()
+}
+
+impl ObservabilityConfig {
+    /// Resolve the effective OTLP endpoint, applying env-var override.
+    ///
+    /// Precedence (highest first):
+    ///   1. `OTEL_EXPORTER_OTLP_ENDPOINT` env var (standard OTel env)
+    ///   2. `[observability].otlp_endpoint` from TOML
+    ///   3. `None` (no export)
+    pub fn resolved_endpoint(&self) -> Option<String> {
+        std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or_else(|| self.otlp_endpoint.clone())
+    }
+}
+
+/// API token authentication settings (see `crate::api::auth`).
+///
+/// ```toml
+/// [auth]
+/// required = true
+/// confirm_destructive = true   # two-step purge / bulk update
+/// confirmation_ttl_secs = 300
+/// ```
+///
+/// Tokens are minted with `echidnabot token mint --name <label> --scope
+/// read|trigger|admin`. Defaults to `false` so existing deployments keep
+/// working after upgrade; `serve` warns loudly while it is off.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthConfig {
+    /// When true, `/metrics` and the job exports reject requests without
+    /// a valid bearer token. When false, anonymous requests to them are
+    /// admitted with admin scope, but a presented token is still
+    /// validated. Tokenless `/graphql` callers only ever see public
+    /// repositories and run no mutations, whatever this says.
+    #[serde(default)]
+    pub required: bool,
+
+    /// Browser login through an OpenID Connect provider.
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+
+    /// Destructive GraphQL mutations (`purgeRepository`,
+    /// `bulkUpdateRepoSettings`) first answer with an impact summary and a
+    /// confirmation token, and only run when called again with it (see
+    /// `crate::api::confirm`). Off by default.
+    #[serde(default)]
+    pub confirm_destructive: bool,
+
+    /// How long a confirmation token stays valid.
+    #[serde(default = "default_confirmation_ttl_secs")]
+    pub confirmation_ttl_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            required: false,
+            oidc: None,
+            confirm_destructive: false,
+            confirmation_ttl_secs: default_confirmation_ttl_secs(),
+        }
+    }
+}
+
+fn default_confirmation_ttl_secs() -> u64 {
+    300
+}
+
+impl AuthConfig {
+    /// Whether anonymous requests to `/metrics` and the job exports are
+    /// refused: with `required`, or once `[auth.oidc]` gives people a
+    /// way to log in.
+    pub fn requires_credentials(&self) -> bool {
+        self.required || self.oidc.is_some()
+    }
+}
+
+/// OpenID Connect login (see `crate::api::oidc`).
+///
+/// ```toml
+/// [auth.oidc]
+/// issuer = "https://login.example.org/realms/proofs"
+/// client_id = "echidnabot"
+/// client_secret = "file:/run/secrets/oidc_client_secret"
+/// redirect_url = "https://echidnabot.example.org/auth/callback"
+/// session_secret = "file:/run/secrets/session_key"
+///
+/// [auth.oidc.roles]
+/// proof-admins = "admin"
+/// proof-maintainers = "trigger"
+/// ```
+///
+/// People log in at `/auth/login`; their groups (the `groups_claim` of the
+/// ID token, or of the userinfo response) are mapped to a token scope by
+/// `roles`, the highest one winning. Without a mapped group they get
+/// `default_scope`, or are turned away when it is unset.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OidcConfig {
+    /// Issuer URL; `/.well-known/openid-configuration` is read from it.
+    pub issuer: String,
+
+    pub client_id: String,
+
+    /// Omitted for public clients, which rely on PKCE alone.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+
+    /// This instance's `/auth/callback`, as registered with the provider.
+    pub redirect_url: String,
+
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+
+    /// Claim listing the user's groups.
+    #[serde(default = "default_oidc_groups_claim")]
+    pub groups_claim: String,
+
+    /// Group → scope.
+    #[serde(default)]
+    pub roles: BTreeMap<String, TokenScope>,
+
+    #[serde(default)]
+    pub default_scope: Option<TokenScope>,
+
+    #[serde(default = "default_oidc_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+
+    /// Key signing session cookies. Without one a random key is drawn at
+    /// startup, so a restart logs everyone out and instances behind one
+    /// load balancer don't share sessions.
+    #[serde(default)]
+    pub session_secret: Option<String>,
+
+    /// Mark cookies `Secure`; turn off only for plain-HTTP development.
+    #[serde(default = "default_true")]
+    pub secure_cookies: bool,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    ["openid", "profile", "email"].map(String::from).to_vec()
+}
+
+fn default_oidc_groups_claim() -> String {
+    "groups".to_string()
+}
+
+fn default_oidc_session_ttl_secs() -> u64 {
+    8 * 3600
+}
+
+/// Result attestation settings (see `crate::attest`).
+///
+/// ```toml
+/// [attestation]
+/// signing_key_path = "/etc/echidnabot/attest.key"   # `echidnabot attest keygen`
+/// retired_public_keys = ["9f1c…"]                   # still published after rotation
+/// ```
+///
+/// Without a signing key, `/jobs/{id}/attestation` answers 404 and the
+/// published key set only lists the retired keys.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AttestationConfig {
+    /// File holding the hex-encoded 32-byte ed25519 seed.
+    #[serde(default)]
+    pub signing_key_path: Option<String>,
+    /// Hex-encoded public keys of earlier signing keys, kept in
+    /// `keys.json` so attestations they signed still verify.
+    #[serde(default)]
+    pub retired_public_keys: Vec<String>,
+}
+
+/// Instance federation settings (see `crate::federation`).
+///
+/// ```toml
+/// [federation]
+/// publish = true              # serve /federation/results (needs a signing key)
+/// sync_interval_secs = 900
+///
+/// [[federation.peers]]
+/// url = "https://setmm-bot.example.org"
+/// public_keys = ["3b6a…"]     # pinned; copy from the peer's keys.json
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FederationConfig {
+    /// Serve this instance's signed results for public repositories.
+    #[serde(default)]
+    pub publish: bool,
+    /// Seconds between imports from `peers`.
+    #[serde(default = "default_federation_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+    /// Instances whose results are imported.
+    #[serde(default)]
+    pub peers: Vec<FederationPeer>,
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            publish: false,
+            sync_interval_secs: default_federation_sync_interval_secs(),
+            peers: Vec::new(),
+        }
+    }
+}
+
+fn default_federation_sync_interval_secs() -> u64 {
+    900
+}
+
+/// Worker capability settings (see `crate::fleet::workers`).
+///
+/// ```toml
+/// [fleet]
+/// local_provers = ["coq", "lean", "metamath"]
+/// local_resource_class = "standard"
+/// worker_ttl_secs = 120
+/// nats_url = "nats://127.0.0.1:4222"
+///
+/// [fleet.prover_classes]
+/// isabelle = "large"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FleetConfig {
+    /// Provers the daemon verifies itself; empty = all. Jobs for other
+    /// provers wait for a remote worker that supports them.
+    #[serde(default)]
+    pub local_provers: Vec<String>,
+    /// Resource class of the daemon's own machine.
+    #[serde(default = "default_local_resource_class")]
+    pub local_resource_class: ResourceClass,
+    /// Seconds after its last registration before a remote worker is
+    /// considered gone.
+    #[serde(default = "default_worker_ttl_secs")]
+    pub worker_ttl_secs: u64,
+    /// Minimum resource class per prover slug; `standard` when unlisted.
+    #[serde(default)]
+    pub prover_classes: HashMap<String, ResourceClass>,
+    /// NATS server for the worker transport (`--features nats`); unset =
+    /// HTTP workers and the in-process scheduler only.
+    #[serde(default)]
+    pub nats_url: Option<String>,
+    #[serde(default)]
+    pub nats_token: Option<String>,
+    /// Subjects are `<prefix>.claim` and `<prefix>.results`.
+    #[serde(default = "default_nats_subject_prefix")]
+    pub nats_subject_prefix: String,
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self {
+            local_provers: Vec::new(),
+            local_resource_class: default_local_resource_class(),
+            worker_ttl_secs: default_worker_ttl_secs(),
+            prover_classes: HashMap::new(),
+            nats_url: None,
+            nats_token: None,
+            nats_subject_prefix: default_nats_subject_prefix(),
+        }
+    }
+}
+
+fn default_local_resource_class() -> ResourceClass {
+    ResourceClass::Large
+}
+
+fn default_worker_ttl_secs() -> u64 {
+    120
+}
+
+fn default_nats_subject_prefix() -> String {
+    "echidnabot.fleet".to_string()
+}
+
+/// Dependency health settings (see `crate::api::health`).
+///
+/// ```toml
+/// [health]
+/// check_interval_secs = 15
+/// failure_threshold = 2
+/// retry_after_secs = 60
+/// require_echidna = true
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HealthConfig {
+    /// Seconds between probes of the store and ECHIDNA.
+    #[serde(default = "default_health_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// Consecutive failed probes before a dependency counts as down.
+    #[serde(default = "default_health_failure_threshold")]
+    pub failure_threshold: u32,
+    /// `Retry-After` sent with webhook 503s while degraded.
+    #[serde(default = "default_health_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// Also reject webhooks while ECHIDNA is down, not only the store.
+    /// On by default.
+    #[serde(default = "default_true")]
+    pub require_echidna: bool,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: default_health_check_interval_secs(),
+            failure_threshold: default_health_failure_threshold(),
+            retry_after_secs: default_health_retry_after_secs(),
+            require_echidna: true,
+        }
+    }
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    15
+}
+
+fn default_health_failure_threshold() -> u32 {
+    2
+}
+
+fn default_health_retry_after_secs() -> u64 {
+    60
+}
+
+/// Queue wait objectives (see `crate::slo`).
+///
+/// ```toml
+/// [slo]
+/// target = 0.95                   # share of jobs that must start in time
+/// window_secs = [3600, 86400]     # rolling windows compliance is computed over
+/// check_interval_secs = 60
+/// alert_webhook_url = "https://hooks.slack.com/services/..."
+///
+/// [slo.queue_wait_secs]           # by priority: low, normal, high, critical
+/// high = 120                      # PR checks start within 2 minutes
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SloConfig {
+    /// Longest a job of each priority may wait in the queue before it
+    /// starts. Priorities without an entry have no objective.
+    #[serde(default = "default_slo_queue_wait_secs")]
+    pub queue_wait_secs: BTreeMap<String, u64>,
+    /// Share of jobs (0–1) that must start within their limit.
+    #[serde(default = "default_slo_target")]
+    pub target: f64,
+    /// Windows, in seconds back from now, compliance is computed over.
+    #[serde(default = "default_slo_window_secs")]
+    pub window_secs: Vec<u64>,
+    /// Seconds between checks for breaches. 0 disables alerting.
+    #[serde(default = "default_slo_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// Breach and recovery alerts are POSTed here as JSON; without one
+    /// they are only logged.
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            queue_wait_secs: default_slo_queue_wait_secs(),
+            target: default_slo_target(),
+            window_secs: default_slo_window_secs(),
+            check_interval_secs: default_slo_check_interval_secs(),
+            alert_webhook_url: None,
+        }
+    }
+}
+
+fn default_slo_queue_wait_secs() -> BTreeMap<String, u64> {
+    BTreeMap::from([("high".to_string(), 120)])
+}
+
+fn default_slo_target() -> f64 {
+    0.95
+}
+
+fn default_slo_window_secs() -> Vec<u64> {
+    vec![3600, 86400]
+}
+
+fn default_slo_check_interval_secs() -> u64 {
+    60
+}
+
+/// Usage telemetry settings (see `crate::telemetry`).
+///
+/// ```toml
+/// [telemetry]
+/// enabled = true
+/// endpoint = "https://telemetry.example.org/echidnabot"
+/// interval_secs = 86400
+/// ```
+///
+/// Off by default. `echidnabot telemetry preview` prints the report that
+/// would be sent, whether or not reporting is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Reports are POSTed here as JSON. Nothing is sent without one.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Seconds between reports; each covers the jobs finished since the
+    /// previous one.
+    #[serde(default = "default_telemetry_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            interval_secs: default_telemetry_interval_secs(),
+        }
+    }
+}
+
+fn default_telemetry_interval_secs() -> u64 {
+    86400
+}
+
+/// Analytics export settings (see `crate::analytics`).
+///
+/// ```toml
+/// [analytics]
+/// interval_secs = 60
+///
+/// [[analytics.sinks]]
+/// name = "warehouse"
+/// kind = "clickhouse"                      # clickhouse | bigquery | s3
+/// url = "https://clickhouse.example.org:8443"
+/// table = "echidnabot.verification_events"
+/// user = "echidnabot"
+/// password = "vault:secret/data/echidnabot#clickhouse"
+///
+/// [[analytics.sinks]]
+/// name = "lake"
+/// kind = "s3"
+/// url = "https://s3.eu-west-1.amazonaws.com"
+/// bucket = "acme-analytics"
+/// region = "eu-west-1"
+/// prefix = "echidnabot/"
+/// access_key_id = "AKIA…"
+/// secret_access_key = "aws-sm:echidnabot/analytics"
+/// ```
+///
+/// Nothing is exported without a sink. Each sink keeps its own position,
+/// so adding one later exports the whole history to it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalyticsConfig {
+    /// Seconds between export runs; `0` leaves exports to
+    /// `echidnabot analytics backfill`.
+    #[serde(default = "default_analytics_interval_secs")]
+    pub interval_secs: u64,
+    /// Events per insert.
+    #[serde(default = "default_analytics_batch_size")]
+    pub batch_size: usize,
+    #[serde(default)]
+    pub sinks: Vec<AnalyticsSink>,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: default_analytics_interval_secs(),
+            batch_size: default_analytics_batch_size(),
+            sinks: Vec::new(),
+        }
+    }
+}
+
+fn default_analytics_interval_secs() -> u64 {
+    60
+}
+
+fn default_analytics_batch_size() -> usize {
+    500
+}
+
+/// One `[[analytics.sinks]]` entry. Which fields apply depends on `kind`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalyticsSink {
+    /// Names the sink's export position; renaming it exports everything
+    /// again.
+    pub name: String,
+    pub kind: AnalyticsSinkKind,
+    /// ClickHouse HTTP interface, or the S3 endpoint. BigQuery defaults to
+    /// `https://bigquery.googleapis.com`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// ClickHouse `database.table`, or the BigQuery table id.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// ClickHouse user.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// ClickHouse password.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// BigQuery project.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// BigQuery dataset.
+    #[serde(default)]
+    pub dataset: Option<String>,
+    /// BigQuery OAuth access token.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// S3 bucket.
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 region; `us-east-1` when unset.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Prepended to S3 object keys.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+/// Job lifecycle event publishing (see `crate::events`).
+///
+/// ```toml
+/// [events]
+/// enabled = true
+/// transport = "nats"                 # nats | kafka
+/// format = "json"                    # json | avro
+/// url = "nats://nats.internal:4222"
+/// topic = "echidnabot.events"        # subject prefix, or Kafka topic
+/// jetstream = true
+/// ```
+///
+/// Every job insert and status change is recorded in the store's outbox
+/// in the same write, then published from there, so an event is never
+/// lost to a crash or an unreachable broker; it may be delivered twice.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub transport: EventTransport,
+    #[serde(default)]
+    pub format: EventFormat,
+    /// NATS server, or the Kafka REST proxy (Confluent REST Proxy,
+    /// Redpanda HTTP Proxy).
+    #[serde(default)]
+    pub url: Option<String>,
+    /// NATS subject prefix (events go to `<topic>.job.<status>`), or the
+    /// Kafka topic.
+    #[serde(default = "default_events_topic")]
+    pub topic: String,
+    /// NATS token.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Kafka REST proxy basic-auth user.
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Wait for a JetStream ack per event rather than a server flush, so
+    /// an event only leaves the outbox once a stream holds it.
+    #[serde(default)]
+    pub jetstream: bool,
+    /// Milliseconds between outbox polls.
+    #[serde(default = "default_events_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Events per publish.
+    #[serde(default = "default_events_batch_size")]
+    pub batch_size: usize,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            transport: EventTransport::default(),
+            format: EventFormat::default(),
+            url: None,
+            topic: default_events_topic(),
+            token: None,
+            username: None,
+            password: None,
+            jetstream: false,
+            poll_interval_ms: default_events_poll_interval_ms(),
+            batch_size: default_events_batch_size(),
+        }
+    }
+}
+
+fn default_events_topic() -> String {
+    "echidnabot.events".to_string()
+}
+
+fn default_events_poll_interval_ms() -> u64 {
+    1000
+}
+
+/// Tenant isolation (see `crate::tenancy`).
+///
+/// ```toml
+/// [tenancy]
+/// master_key = "file:/run/secrets/echidnabot_master_key"   # 32 bytes, hex or base64
+/// ```
+///
+/// With a master key, each repository owner gets its own data key, and
+/// per-repository webhook secrets are stored sealed with it. Existing
+/// plaintext secrets are sealed by `echidnabot tenant seal`, or as their
+/// repositories are next written.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TenancyConfig {
+    #[serde(default)]
+    pub master_key: Option<String>,
+}
+
+fn default_events_batch_size() -> usize {
+    100
+}
+
+/// Where `[events]` publishes.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventTransport {
+    /// Needs the `nats` feature.
+    #[default]
+    Nats,
+    /// Through a Kafka REST proxy (v2 API).
+    Kafka,
+}
+
+/// How `[events]` encodes events.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventFormat {
+    #[default]
+    Json,
+    /// Avro binary on NATS; on Kafka, registered with the proxy's schema
+    /// registry.
+    Avro,
+}
+
+/// Where an `[[analytics.sinks]]` entry inserts.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsSinkKind {
+    /// `INSERT … FORMAT JSONEachRow` over the HTTP interface.
+    Clickhouse,
+    /// `tabledata.insertAll` streaming inserts.
+    Bigquery,
+    /// One JSON Lines object per batch, on S3 or a compatible store.
+    S3,
+}
+
+/// Archive settings (see `crate::archive`).
+///
+/// ```toml
+/// [archive]
+/// enabled = true
+/// backend = "ipfs"                         # ipfs | directory
+/// ipfs_api_url = "http://127.0.0.1:5001"   # Kubo RPC API; needs the `ipfs` feature
+/// gateway_url = "https://ipfs.io/ipfs"     # linked from check runs
+/// # directory = "/var/lib/echidnabot/archive"
+/// ```
+///
+/// Only passing jobs are archived.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub backend: ArchiveBackend,
+    /// Kubo (go-ipfs) RPC API the `ipfs` backend adds files through.
+    #[serde(default = "default_ipfs_api_url")]
+    pub ipfs_api_url: String,
+    /// Base URL the content address is appended to for links; `None`
+    /// records the address without linking it.
+    #[serde(default = "default_archive_gateway_url")]
+    pub gateway_url: Option<String>,
+    /// Root of the `directory` backend.
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Include the verified proof sources, not just the attestation and
+    /// result. On by default.
+    #[serde(default = "default_true")]
+    pub include_sources: bool,
+    /// Sources are left out once their total size would exceed this.
+    #[serde(default = "default_archive_max_source_bytes")]
+    pub max_source_bytes: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: ArchiveBackend::default(),
+            ipfs_api_url: default_ipfs_api_url(),
+            gateway_url: default_archive_gateway_url(),
+            directory: None,
+            include_sources: true,
+            max_source_bytes: default_archive_max_source_bytes(),
+        }
+    }
+}
+
+/// Where `[archive]` publishes.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveBackend {
+    #[default]
+    Ipfs,
+    Directory,
+}
+
+fn default_ipfs_api_url() -> String {
+    "http://127.0.0.1:5001".to_string()
+}
+
+fn default_archive_gateway_url() -> Option<String> {
+    Some("https://ipfs.io/ipfs".to_string())
+}
+
+fn default_archive_max_source_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+/// Another echidnabot instance to import results from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FederationPeer {
+    /// Base URL, e.g. `https://setmm-bot.example.org`.
+    pub url: String,
+    /// Hex-encoded ed25519 keys its results must be signed with. Pinned
+    /// rather than fetched, so a compromised peer host cannot swap them.
+    pub public_keys: Vec<String>,
+}
+
+/// `@echidnabot` comment-command settings (see `crate::modes::commands`).
+///
+/// ```toml
+/// [commands.permissions]
+/// check      = "contributor"   # anyone | contributor | collaborator | maintainer
+/// rerun-full = "collaborator"
+/// ```
+///
+/// Keys are `help`, `explain`, `ask`, `check`, `rerun` and `rerun-full`;
+/// unlisted commands keep their built-in minimum role.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CommandsConfig {
+    #[serde(default)]
+    pub permissions: CommandPermissions,
+}
+
+/// Duration anomaly detection settings (see `crate::perf::anomaly`).
+///
+/// ```toml
+/// [anomaly]
+/// enabled         = true
+/// history_window  = 30     # most recent samples used as the baseline
+/// min_samples     = 5      # below this, no verdict is issued
+/// ratio_threshold = 2.0    # current / median must reach this
+/// z_threshold     = 3.5    # robust (median/MAD) z-score must reach this
+/// min_delta_ms    = 1000   # ignore jitter on very fast proofs
+/// ```
+///
+/// All three gates (ratio, z-score, absolute delta) must trip before a
+/// file is reported, so a 20 ms proof taking 60 ms never raises noise.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnomalyConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    #[serde(default = "default_anomaly_history_window")]
+    pub history_window: usize,
+
+    #[serde(default = "default_anomaly_min_samples")]
+    pub min_samples: usize,
+
+    #[serde(default = "default_anomaly_ratio_threshold")]
+    pub ratio_threshold: f64,
+
+    #[serde(default = "default_anomaly_z_threshold")]
+    pub z_threshold: f64,
+
+    #[serde(default = "default_anomaly_min_delta_ms")]
+    pub min_delta_ms: u64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history_window: default_anomaly_history_window(),
+            min_samples: default_anomaly_min_samples(),
+            ratio_threshold: default_anomaly_ratio_threshold(),
+            z_threshold: default_anomaly_z_threshold(),
+            min_delta_ms: default_anomaly_min_delta_ms(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_anomaly_history_window() -> usize {
+    30
+}
+
+fn default_anomaly_min_samples() -> usize {
+    5
+}
+
+fn default_anomaly_ratio_threshold() -> f64 {
+    2.0
+}
+
+fn default_anomaly_z_threshold() -> f64 {
+    3.5
+}
+
+fn default_anomaly_min_delta_ms() -> u64 {
+    1000
+}
+
+/// Daemon-wide bot operating mode settings.
+///
+/// ```toml
+/// [bot]
+/// mode = "advisor"   # verifier | advisor | consultant | regulator
+/// config_pr = true   # offer `.echidnabot.toml` to unconfigured repos
+/// incremental = true # on push, verify changed files + their dependents
+/// baseline = true    # on PRs, compare results with the base commit
+/// sticky_comments = true # edit the previous result comment, not a new one
+/// cancel_superseded = true # drop jobs for a PR's old head / force-pushed commits
+/// observer = false   # read-only mirror: verify and record, never post
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BotConfig {
+    /// Daemon-wide default operating mode. Used as a fallback when a repo
+    /// has no per-repo directive file and its DB column is still the
+    /// built-in default (Verifier). Overridden by per-repo directive or
+    /// explicit `register --mode` setting.
+    #[serde(default)]
+    pub mode: BotMode,
+    /// On the first event from a registered repo with no directive or
+    /// `.echidnabot.toml`, open a PR adding a config template with the
+    /// detected provers. Offered once per repo. Off by default.
+    #[serde(default)]
+    pub config_pr: bool,
+    /// On push, verify only the changed proof files and the files that
+    /// import them (see `crate::depgraph`) once a dependency graph has
+    /// been recorded for the repo. Falls back to a whole-repo run when
+    /// the push cannot be narrowed safely. On by default.
+    #[serde(default = "default_true")]
+    pub incremental: bool,
+    /// On PRs, also verify the base commit (unless already done) and
+    /// report which failures the PR introduced and which were already
+    /// there (see `crate::baseline`). On by default.
+    #[serde(default = "default_true")]
+    pub baseline: bool,
+    /// Edit the bot's earlier result comment on a PR (one per prover)
+    /// instead of posting a new one per run (see `crate::pr_comment`).
+    /// On by default.
+    #[serde(default = "default_true")]
+    pub sticky_comments: bool,
+    /// When a PR gets a new head commit, or a branch is force-pushed,
+    /// cancel the queued and running jobs for the commits it replaced
+    /// (see `crate::scheduler::supersede`). On by default.
+    #[serde(default = "default_true")]
+    pub cancel_superseded: bool,
+    /// Read-only observer, e.g. a compliance mirror of a primary
+    /// instance: webhooks are ingested and jobs verified and recorded as
+    /// usual, and the APIs serve them, but nothing is ever written to a
+    /// platform (see `crate::adapters::read_only`). Off by default.
+    #[serde(default)]
+    pub observer: bool,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            mode: BotMode::default(),
+            config_pr: false,
+            incremental: true,
+            baseline: true,
+            sticky_comments: true,
+            cancel_superseded: true,
+            observer: false,
+        }
+    }
+}
+
+/// BoJ server connection settings. Endpoint can also be overridden by
+/// the `BOJ_ENDPOINT` env var (env wins so operators can repoint
+/// without restarting the daemon's config-load path).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BoJConfig {
+    /// Base URL of the BoJ loader (e.g. `http://127.0.0.1:7700`).
+    pub url: String,
+}
+
+/// Executor configuration. Controls how proof verification is dispatched:
+/// either by delegating to a remote ECHIDNA instance over REST/GraphQL
+/// (default — `local_isolation = false`), or by spawning prover binaries
+/// locally inside an isolation sandbox (`local_isolation = true`), or by
+/// ECHIDNA with the sandbox as a fallback (see [`DispatchStrategy`]).
+///
+/// Local isolation needs `podman` (preferred) or `bubblewrap` (`bwrap`)
+/// on PATH; the executor refuses to run if neither is available
+/// (fail-safe per SONNET-TASKS Task 1).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExecutorConfig {
+    /// When true, process_job runs proof binaries locally in a sandboxed
+    /// container instead of POSTing to ECHIDNA's REST API. Useful for
+    /// air-gapped / no-ECHIDNA-available scenarios. Adds prover-binary
+    /// install requirements to the host.
+    #[serde(default)]
+    pub local_isolation: bool,
+
+    /// Where proofs are checked; unset, `prefer-local` with
+    /// `local_isolation = true` and `prefer-echidna` otherwise.
+    ///
+    /// TOML: `[executor]\ndispatch = "echidna-with-local-fallback"`
+    #[serde(default)]
+    pub dispatch: Option<DispatchStrategy>,
+
+    /// Default container image used by the Podman backend when no
+    /// per-prover override is configured. The bundled image typically
+    /// carries the full prover-binary set.
+    #[serde(default)]
+    pub container_image: Option<String>,
+
+    /// Per-prover container images. Each prover gets the specialised
+    /// image carrying just its binaries — smaller, faster cold-start,
+    /// reduced attack surface vs the full bundle. Keys are the
+    /// lowercase ProverKind variant names (`coq`, `lean`, `agda`, ...).
+    /// Falls back to `container_image` for any prover not listed here.
+    ///
+    /// TOML example:
+    ///   [executor.container_images]
+    ///   coq = "ghcr.io/hyperpolymath/echidna-provers/coq:2026.04"
+    ///   lean = "ghcr.io/hyperpolymath/echidna-provers/lean:2026.04"
+    ///   agda = "ghcr.io/hyperpolymath/echidna-provers/agda:2026.04"
+    #[serde(default)]
+    pub container_images: HashMap<ProverKind, String>,
+
+    /// Image for the prover version a project asks for in its own files
+    /// (`lean-toolchain`, `coq.opam`, `.tool-versions`; see
+    /// `crate::toolchain`), with `{prover}` and `{version}` filled in,
+    /// e.g. `echidna-provers:{prover}-{version}`. Podman only; projects
+    /// naming no version use the images above. Unset, project files are
+    /// not consulted.
+    #[serde(default)]
+    pub toolchain_image: Option<String>,
+
+    /// Memory cap for each proof container. Default `512m`.
+    #[serde(default)]
+    pub memory_limit: Option<String>,
+
+    /// CPU cap (cores). Default 2.
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+
+    /// Per-proof timeout in seconds. Default 300.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Host directory for build outputs kept between jobs, per
+    /// repository: Isabelle session heaps (see `executor::isabelle`).
+    /// Unset, every job builds them afresh.
+    #[serde(default)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// How `[executor] dispatch` sends proofs to a prover.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DispatchStrategy {
+    /// Everything through ECHIDNA.
+    PreferEchidna,
+    /// Everything in the local sandbox. With no isolation backend on PATH
+    /// this falls back to ECHIDNA, unless `local_isolation = true` — then
+    /// the executor refuses to start.
+    PreferLocal,
+    /// Through ECHIDNA, and each file ECHIDNA cannot be reached for (every
+    /// endpoint failing or behind an open circuit breaker) in the local
+    /// sandbox, so verification keeps going through an ECHIDNA outage.
+    EchidnaWithLocalFallback,
+}
+
+impl ExecutorConfig {
+    /// The dispatch strategy in effect.
+    pub fn dispatch(&self) -> DispatchStrategy {
+        self.dispatch.unwrap_or(if self.local_isolation {
+            DispatchStrategy::PreferLocal
+        } else {
+            DispatchStrategy::PreferEchidna
+        })
+    }
+
+    /// Resolve the container image for a specific prover. Per-prover map
+    /// wins over the default `container_image`; both can be unset, in
+    /// which case the executor uses its built-in default
+    /// (`PodmanExecutor::default().image`).
+    pub fn image_for(&self, prover: ProverKind) -> Option<String> {
+        self.container_images
+            .get(&prover)
+            .cloned()
+            .or_else(|| self.container_image.clone())
+    }
+}
+
+/// Corpus-delta writer + retrain-trigger settings. Disabled by default —
+/// opt-in to avoid accidentally writing into ECHIDNA's training_data from
+/// dev / CI environments.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CorpusConfig {
+    /// Master switch. When false, callers should not instantiate a CorpusDelta.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Directory where delta JSONL files are written.
+    /// Typically `{echidna_root}/training_data`.
+    #[serde(default)]
+    pub training_data_dir: Option<PathBuf>,
+
+    /// Root of the ECHIDNA repo — working directory for `just corpus-refresh`.
+    #[serde(default)]
+    pub echidna_root: Option<PathBuf>,
+
+    /// Fire the retrain trigger automatically after N successful records.
+    /// `None` requires an explicit caller (MCP tool, scheduled job).
+    #[serde(default)]
+    pub auto_trigger_threshold: Option<u32>,
+
+    /// (goal, tactic) training-example export from opted-in repositories.
+    #[serde(default)]
+    pub export: CorpusExportConfig,
+}
+
+/// Training-example export (see `crate::feedback::export`).
+///
+/// ```toml
+/// [corpus.export]
+/// enabled = true
+/// endpoint = "https://echidna.example.org/training/examples"
+/// # directory = "/var/lib/echidnabot/training-export"
+/// ```
+///
+/// Only repositories registered with `--training-export` (or
+/// `trainingExport` in the GraphQL settings) contribute examples.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CorpusExportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Batches are POSTed here as JSON Lines.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Bearer token sent to `endpoint`.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Batches are written here as `batch_<timestamp>.jsonl` files when
+    /// no `endpoint` is set.
+    #[serde(default)]
+    pub directory: Option<PathBuf>,
+    /// Examples per batch.
+    #[serde(default = "default_export_batch_size")]
+    pub batch_size: usize,
+    /// Seconds between export runs.
+    #[serde(default = "default_export_interval_secs")]
+    pub interval_secs: u64,
+    /// Goals or tactics longer than this are not exported.
+    #[serde(default = "default_export_max_example_bytes")]
+    pub max_example_bytes: usize,
+}
+
+impl Default for CorpusExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            token: None,
+            directory: None,
+            batch_size: default_export_batch_size(),
+            interval_secs: default_export_interval_secs(),
+            max_example_bytes: default_export_max_example_bytes(),
+        }
+    }
+}
+
+fn default_export_batch_size() -> usize {
+    500
+}
+
+fn default_export_interval_secs() -> u64 {
+    3600
+}
+
+fn default_export_max_example_bytes() -> usize {
+    16 * 1024
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EchidnaApiMode {
+    Auto,
+    Graphql,
+    Rest,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ServerConfig {
+    #[serde(default = "default_host")]
+    pub host: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// Maximum webhook requests per IP per minute (None = unlimited).
+    pub rate_limit_rpm: Option<u32>,
+
+    /// Processing attempts per accepted webhook delivery before it is
+    /// marked failed (see `api::ingest`).
+    #[serde(default = "default_webhook_max_attempts")]
+    pub webhook_max_attempts: u32,
+
+    /// External base URL (`https://echidnabot.example.org`), for absolute
+    /// links in the results feeds.
+    #[serde(default)]
+    pub public_url: Option<String>,
+
+    /// Largest webhook body accepted, in bytes; bigger ones are answered
+    /// `413`. Events echidnabot ignores are never read at all.
+    #[serde(default = "default_max_webhook_body_bytes")]
+    pub max_webhook_body_bytes: usize,
+
+    /// Changed files a push may list and still be verified incrementally;
+    /// larger pushes get a full run.
+    #[serde(default = "default_max_push_files")]
+    pub max_push_files: usize,
+
+    /// Seconds a client may take to send a webhook body before it is
+    /// answered `408`; 0 waits indefinitely.
+    #[serde(default = "default_webhook_read_timeout_secs")]
+    pub webhook_read_timeout_secs: u64,
+
+    /// Serve HTTPS directly (`--features tls`); unset serves plain HTTP
+    /// for a reverse proxy to terminate.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+/// `[server.tls]`: a certificate from files, or one obtained and renewed
+/// from an ACME CA (see `crate::api::tls`).
+///
+/// ```toml
+/// [server.tls]
+/// cert_path = "/etc/echidnabot/tls/fullchain.pem"
+/// key_path = "/etc/echidnabot/tls/privkey.pem"
+///
+/// # or
+/// [server.tls.acme]
+/// domains = ["echidnabot.example.org"]
+/// contact = ["ops@example.org"]
+/// cache_dir = "/var/lib/echidnabot/acme"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first.
+    #[serde(default)]
+    pub cert_path: Option<String>,
+
+    /// PEM private key for `cert_path`.
+    #[serde(default)]
+    pub key_path: Option<String>,
+
+    /// Certificates from an ACME CA instead of files.
+    #[serde(default)]
+    pub acme: Option<AcmeConfig>,
+
+    /// Seconds a client may take to complete the TLS handshake.
+    #[serde(default = "default_tls_handshake_timeout_secs")]
+    pub handshake_timeout_secs: u64,
+}
+
+/// `[server.tls.acme]`: TLS-ALPN-01 on the listening port, so that port
+/// must be the one reachable as 443 for every domain.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AcmeConfig {
+    pub domains: Vec<String>,
+
+    /// Contact e-mail addresses for the ACME account.
+    #[serde(default)]
+    pub contact: Vec<String>,
+
+    /// Where the account key and certificates are kept across restarts;
+    /// without it every start orders a new certificate.
+    #[serde(default)]
+    pub cache_dir: Option<String>,
+
+    /// ACME directory URL; defaults to Let's Encrypt.
+    #[serde(default)]
+    pub directory_url: Option<String>,
+
+    /// Use the Let's Encrypt staging directory.
+    #[serde(default)]
+    pub staging: bool,
+}
+
+impl TlsConfig {
+    /// Check that exactly one certificate source is configured.
+    pub fn validate(&self) -> Result<()> {
+        match (&self.cert_path, &self.key_path, &self.acme) {
+            (Some(_), Some(_), None) => Ok(()),
+            (None, None, Some(acme)) if !acme.domains.is_empty() => Ok(()),
+            (None, None, Some(_)) => Err(Error::Config("[server.tls.acme] domains is empty".into())),
+            (None, None, None) => Err(Error::Config(
+                "[server.tls] needs cert_path and key_path, or [server.tls.acme]".into(),
+            )),
+            (_, _, Some(_)) => Err(Error::Config(
+                "[server.tls] takes cert_path/key_path or [server.tls.acme], not both".into(),
+            )),
+            _ => Err(Error::Config("[server.tls] cert_path and key_path go together".into())),
+        }
+    }
+}
+
+fn default_tls_handshake_timeout_secs() -> u64 {
+    10
+}
+
+fn default_webhook_read_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: default_host(),
+            port: default_port(),
+            rate_limit_rpm: None,
+            webhook_max_attempts: default_webhook_max_attempts(),
+            public_url: None,
+            max_webhook_body_bytes: default_max_webhook_body_bytes(),
+            max_push_files: default_max_push_files(),
+            webhook_read_timeout_secs: default_webhook_read_timeout_secs(),
+            tls: None,
+        }
+    }
+}
+
+/// GitHub's own cap on webhook payloads.
+fn default_max_webhook_body_bytes() -> usize {
+    25 * 1024 * 1024
+}
+
+fn default_max_push_files() -> usize {
+    10_000
+}
+
+fn default_host() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    5
+}
+
+/// Connection pool and SQLite settings (see `crate::store::pool`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseConfig {
+    #[serde(default = "default_database_url")]
+    pub url: String,
+
+    /// Read connections (plus the writer when `single_writer` is set).
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+
+    /// Connections kept open while idle.
+    #[serde(default)]
+    pub min_connections: u32,
+
+    /// Seconds to wait for a free connection before failing the query.
+    #[serde(default = "default_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+
+    /// `PRAGMA journal_mode`. WAL lets reads proceed during a write.
+    #[serde(default)]
+    pub journal_mode: JournalMode,
+
+    /// `PRAGMA synchronous`. `normal` is durable across crashes in WAL
+    /// mode and only risks the last commits on power loss.
+    #[serde(default)]
+    pub synchronous: SynchronousMode,
+
+    /// `PRAGMA busy_timeout`: milliseconds a connection retries a locked
+    /// database before failing with "database is locked".
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+
+    /// Route every write through one dedicated connection, so concurrent
+    /// writers queue in-process instead of contending for SQLite's lock.
+    #[serde(default = "default_true")]
+    pub single_writer: bool,
+
+    /// Batching of high-frequency, non-critical writes.
+    #[serde(default)]
+    pub write_behind: WriteBehindConfig,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: default_database_url(),
+            max_connections: default_max_connections(),
+            min_connections: 0,
+            acquire_timeout_secs: default_acquire_timeout_secs(),
+            journal_mode: JournalMode::default(),
+            synchronous: SynchronousMode::default(),
+            busy_timeout_ms: default_busy_timeout_ms(),
+            single_writer: true,
+            write_behind: WriteBehindConfig::default(),
+        }
+    }
+}
+
+/// SQLite `journal_mode`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    #[default]
+    Wal,
+    Off,
+}
+
+/// SQLite `synchronous`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SynchronousMode {
+    Off,
+    #[default]
+    Normal,
+    Full,
+    Extra,
+}
+
+/// Write-behind batching (see `crate::store::batch`).
+///
+/// ```toml
+/// [database.write_behind]
+/// enabled = true
+/// max_rows = 500
+/// flush_interval_ms = 1000
+/// ```
+///
+/// Only per-file timings, tactic outcomes and API-token `last_used_at`
+/// touches are batched; jobs, results and repositories are always
+/// written immediately.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WriteBehindConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Buffered rows that force a flush before the interval elapses.
+    #[serde(default = "default_write_behind_max_rows")]
+    pub max_rows: usize,
+    /// Milliseconds between periodic flushes.
+    #[serde(default = "default_write_behind_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+}
+
+impl Default for WriteBehindConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_rows: default_write_behind_max_rows(),
+            flush_interval_ms: default_write_behind_flush_interval_ms(),
+        }
+    }
+}
+
+fn default_write_behind_max_rows() -> usize {
+    500
+}
+
+fn default_write_behind_flush_interval_ms() -> u64 {
+    1000
+}
+
+fn default_database_url() -> String {
+    "sqlite://echidnabot.db".to_string()
+}
+
+fn default_max_connections() -> u32 {
+    5
+}
+
+fn default_acquire_timeout_secs() -> u64 {
+    30
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    5000
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EchidnaConfig {
+    /// ECHIDNA Core GraphQL endpoint
+    #[serde(default = "default_echidna_endpoint")]
+    pub endpoint: String,
+
+    /// ECHIDNA Core REST endpoint
+    #[serde(default = "default_echidna_rest_endpoint")]
+    pub rest_endpoint: String,
+
+    /// API mode (auto, graphql, rest)
+    #[serde(default = "default_echidna_mode")]
+    pub mode: EchidnaApiMode,
+
+    /// Timeout for proof verification (seconds)
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+
+    /// Further ECHIDNA backends, tried in order while the ones before them
+    /// are down.
+    ///
+    /// TOML: `[[echidna.failover]]\nendpoint = "…/graphql"\nrest_endpoint = "…"`
+    #[serde(default)]
+    pub failover: Vec<EchidnaEndpoint>,
+
+    /// When an endpoint counts as down (see
+    /// `crate::dispatcher::circuit_breaker`).
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// Files of a job sent in one GraphQL request, one aliased
+    /// `verifyProof` each; 1 sends a request per file. REST mode always
+    /// does.
+    #[serde(default = "default_echidna_batch_size")]
+    pub batch_size: usize,
+
+    /// Requests in flight at once for one job's files.
+    #[serde(default = "default_echidna_batch_concurrency")]
+    pub batch_concurrency: usize,
+
+    /// Sending a job's project as one archive instead of file by file
+    /// (see `crate::dispatcher::project_archive`).
+    #[serde(default)]
+    pub archive: ArchiveUploadConfig,
+
+    /// Credentials presented to every ECHIDNA endpoint (see
+    /// `crate::dispatcher::echidna_auth`).
+    #[serde(default)]
+    pub auth: EchidnaAuthConfig,
+}
+
+impl Default for EchidnaConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_echidna_endpoint(),
+            rest_endpoint: default_echidna_rest_endpoint(),
+            mode: default_echidna_mode(),
+            timeout_secs: default_timeout(),
+            failover: Vec::new(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            batch_size: default_echidna_batch_size(),
+            batch_concurrency: default_echidna_batch_concurrency(),
+            archive: ArchiveUploadConfig::default(),
+            auth: EchidnaAuthConfig::default(),
+        }
+    }
+}
+
+/// One more ECHIDNA Core backend.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EchidnaEndpoint {
+    /// GraphQL endpoint
+    pub endpoint: String,
+    /// REST endpoint
+    pub rest_endpoint: String,
+}
+
+/// Per-endpoint circuit breaker for ECHIDNA calls.
+///
+/// ```toml
+/// [echidna.circuit_breaker]
+/// failure_threshold = 5   # consecutive failed calls that open it
+/// open_secs = 30          # before one probe call is let through
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CircuitBreakerConfig {
+    #[serde(default = "default_breaker_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default = "default_breaker_open_secs")]
+    pub open_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_breaker_failure_threshold(),
+            open_secs: default_breaker_open_secs(),
+        }
+    }
+}
+
+/// Project archive upload to ECHIDNA.
+///
+/// ```toml
+/// [echidna.archive]
+/// enabled = true
+/// provers = ["coq", "lean"]       # empty: every prover
+/// max_bytes = 67108864            # file content per archive, before compression
+/// max_files = 5000
+/// exclude = [".git", "_build", "*.vo"]   # replaces the defaults
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchiveUploadConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Prover slugs sent as archives; empty is every prover.
+    #[serde(default)]
+    pub provers: Vec<String>,
+    /// Larger projects are checked file by file.
+    #[serde(default = "default_archive_max_bytes")]
+    pub max_bytes: u64,
+    #[serde(default = "default_archive_max_files")]
+    pub max_files: usize,
+    /// Globs (`crate::modes::manifest::glob_match`) of files and directories
+    /// left out: VCS metadata and build outputs by default.
+    #[serde(default = "default_archive_exclude")]
+    pub exclude: Vec<String>,
+}
+
+impl Default for ArchiveUploadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provers: Vec::new(),
+            max_bytes: default_archive_max_bytes(),
+            max_files: default_archive_max_files(),
+            exclude: default_archive_exclude(),
+        }
+    }
+}
+
+impl ArchiveUploadConfig {
+    /// Whether jobs of `prover` go up as archives.
+    pub fn applies_to(&self, prover: &ProverKind) -> bool {
+        self.enabled
+            && (self.provers.is_empty()
+                || self.provers.iter().any(|p| p.eq_ignore_ascii_case(prover.as_str())))
+    }
+}
+
+fn default_archive_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_archive_max_files() -> usize {
+    5000
+}
+
+fn default_archive_exclude() -> Vec<String> {
+    [
+        ".git", ".hg", "_build", "_opam", "target", ".lake", "node_modules", "*.vo", "*.vok",
+        "*.vos", "*.glob", "*.olean", "*.ilean", "*.agdai", "*.ibc",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Authentication to ECHIDNA Core: a client certificate (mTLS) and / or
+/// an API key, for the primary and failover endpoints alike.
+///
+/// ```toml
+/// [echidna.auth]
+/// api_key = "file:/run/secrets/echidna_api_key"
+/// # api_key_header = "X-API-Key"   # default: Authorization: Bearer <key>
+/// client_cert = "/etc/echidnabot/tls/client.crt"
+/// client_key = "/etc/echidnabot/tls/client.key"
+/// ca_cert = "/etc/echidnabot/tls/echidna-ca.crt"
+/// reload_interval_secs = 60
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EchidnaAuthConfig {
+    /// Sent with every request; may be a secret reference.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Header carrying the bare key. Unset sends `Authorization: Bearer`.
+    #[serde(default)]
+    pub api_key_header: Option<String>,
+    /// PEM certificate chain presented to ECHIDNA; needs `client_key`.
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// PEM private key (PKCS#8, RSA or SEC1) of `client_cert`.
+    #[serde(default)]
+    pub client_key: Option<String>,
+    /// PEM CA certificate(s) trusted for ECHIDNA's server certificate, on
+    /// top of the built-in roots.
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+    /// How often the files above and the key are checked for changes; 0
+    /// reads them only at startup.
+    #[serde(default = "default_echidna_auth_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+impl Default for EchidnaAuthConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_key_header: None,
+            client_cert: None,
+            client_key: None,
+            ca_cert: None,
+            reload_interval_secs: default_echidna_auth_reload_interval_secs(),
+        }
+    }
+}
+
+fn default_echidna_auth_reload_interval_secs() -> u64 {
+    60
+}
+
+fn default_breaker_failure_threshold() -> u32 {
+    5
+}
+
+fn default_breaker_open_secs() -> u64 {
+    30
+}
+
+fn default_echidna_batch_size() -> usize {
+    16
+}
+
+fn default_echidna_batch_concurrency() -> usize {
+    4
+}
+
+fn default_echidna_endpoint() -> String {
+    "http://localhost:8080/graphql".to_string()
+}
+
+fn default_echidna_rest_endpoint() -> String {
+    "http://localhost:8080".to_string()
+}
+
+fn default_echidna_mode() -> EchidnaApiMode {
+    EchidnaApiMode::Auto
+}
+
+fn default_timeout() -> u64 {
+    300 // 5 minutes
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitHubConfig {
+    /// GitHub App ID
+    pub app_id: Option<u64>,
+
+    /// Path to private key file
+    pub private_key_path: Option<String>,
+
+    /// Personal access token (alternative to app auth)
+    pub token: Option<String>,
+
+    /// Webhook secret for signature verification
+    pub webhook_secret: Option<String>,
+
+    /// Register repositories when the App is installed on them, and
+    /// disable them when it is removed (see `crate::api::installation`).
+    /// On by default.
+    #[serde(default = "default_true")]
+    pub auto_register: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GitLabConfig {
+    /// GitLab instance URL
+    pub url: String,
+
+    /// Personal access token
+    pub token: String,
+
+    /// Webhook secret
+    pub webhook_secret: Option<String>,
+}
+
+/// Codeberg / Forgejo / Gitea connection settings.
+///
+/// `url` defaults to `https://codeberg.org` in the adapter when this
+/// section is absent. Point it at any self-hosted Forgejo or Gitea
+/// instance to reuse the same adapter:
+///
+/// ```toml
+/// [codeberg]
+/// url = "https://forgejo.example.org"
+/// token = "..."
+/// webhook_secret = "..."
+/// ```
+///
+/// `token` is optional — read-only endpoints (file fetch, default
+/// branch) work without it on public repos. Mutating endpoints
+/// (statuses, comments, issues) require a token; the adapter
+/// returns `Error::Config("CODEBERG_TOKEN not set")` when missing.
+/// The `CODEBERG_TOKEN` env var also works as a fallback.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CodebergConfig {
+    /// Codeberg / Forgejo / Gitea instance URL.
+    pub url: String,
+
+    /// Personal access token (PAT). Forgejo also supports app tokens
+    /// but the adapter does not yet distinguish — see TODO in
+    /// `src/adapters/codeberg.rs`.
+    pub token: Option<String>,
+
+    /// Webhook secret for `X-Gitea-Signature` HMAC-SHA256 verification.
+    /// Header name is `X-Gitea-Signature` on both Forgejo and Codeberg
+    /// (the Gitea fork name is retained for wire compatibility).
+    pub webhook_secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SchedulerConfig {
+    /// Maximum concurrent proof jobs
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+
+    /// Queue size limit
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+
+    /// Seconds between stale proof-failure sweeps (repos opt in through
+    /// `[stale_failures]` in their manifest). 0 disables the sweeper.
+    #[serde(default = "default_stale_sweep_interval_secs")]
+    pub stale_sweep_interval_secs: u64,
+
+    /// Seconds between checks of repositories' `reverify_cron` schedules
+    /// (see `crate::reverify`). 0 disables scheduled re-verification.
+    #[serde(default = "default_reverify_interval_secs")]
+    pub reverify_interval_secs: u64,
+
+    /// Seconds between freshness passes, which verify or re-report
+    /// default-branch and open-PR heads without a result (see
+    /// `crate::freshness`). 0 disables them.
+    #[serde(default = "default_freshness_interval_secs")]
+    pub freshness_interval_secs: u64,
+
+    /// Lines of prover output kept per job for `/jobs/{id}/logs`
+    /// (see `crate::scheduler::logs`); older lines are dropped.
+    #[serde(default = "default_log_buffer_lines")]
+    pub log_buffer_lines: usize,
+
+    /// Finished jobs whose logs stay in memory. Older jobs' logs come
+    /// from their stored result.
+    #[serde(default = "default_log_retain_jobs")]
+    pub log_retain_jobs: usize,
+
+    /// Jobs that must not run at the same time because they share a
+    /// build cache.
+    #[serde(default)]
+    pub exclusive: ExclusiveScope,
+}
+
+/// Which running jobs exclude each other (`[scheduler] exclusive`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExclusiveScope {
+    /// Jobs run in parallel up to `max_concurrent`.
+    #[default]
+    None,
+    /// One job per repository at a time.
+    Repository,
+    /// One job per repository and prover at a time: each prover keeps
+    /// its own cache (`.lake`, Isabelle heaps, `.vo` files).
+    Prover,
+}
+
+/// Time zones and blackout windows (see `crate::calendar`).
+///
+/// ```toml
+/// [calendar]
+/// timezone = "Europe/London"   # repositories without their own; UTC if unset
+///
+/// [[calendar.blackouts]]       # business hours
+/// days = ["Mon", "Tue", "Wed", "Thu", "Fri"]
+/// start = "09:00"
+/// end = "18:00"
+///
+/// [calendar.orgs.acme]         # repositories owned by `acme`
+/// timezone = "America/New_York"
+/// [[calendar.orgs.acme.blackouts]]
+/// name = "year-end freeze"
+/// from = "2026-12-19"
+/// until = "2027-01-03"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CalendarConfig {
+    /// IANA time zone of repositories that set none, nor their org.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Windows applying to every repository.
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutWindow>,
+
+    /// Per organisation (repository owner): a time zone and more windows.
+    #[serde(default)]
+    pub orgs: HashMap<String, OrgCalendar>,
+
+    /// Seconds between checks of which repositories are in a window. 0
+    /// disables holding queued jobs (scheduled re-verification still
+    /// waits for windows to end).
+    #[serde(default = "default_calendar_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            timezone: None,
+            blackouts: Vec::new(),
+            orgs: HashMap::new(),
+            check_interval_secs: default_calendar_check_interval_secs(),
+        }
+    }
+}
+
+fn default_calendar_check_interval_secs() -> u64 {
+    60
+}
+
+/// An organisation's `[calendar.orgs.<owner>]` settings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OrgCalendar {
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutWindow>,
+}
+
+/// When non-urgent jobs wait, in the repository's local time. Every
+/// condition set must hold: `days` and `start`..`end` (`HH:MM`, wrapping
+/// past midnight when `end` is earlier; `days` are those the time falls
+/// on) make a recurring window, `from`..`until` (dates, inclusive) a
+/// one-off freeze.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct BlackoutWindow {
+    /// Shown in logs.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `Mon` .. `Sun`; empty is every day.
+    #[serde(default)]
+    pub days: Vec<chrono::Weekday>,
+    #[serde(default)]
+    pub start: Option<String>,
+    #[serde(default)]
+    pub end: Option<String>,
+    #[serde(default)]
+    pub from: Option<chrono::NaiveDate>,
+    #[serde(default)]
+    pub until: Option<chrono::NaiveDate>,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: default_max_concurrent(),
+            queue_size: default_queue_size(),
+            stale_sweep_interval_secs: default_stale_sweep_interval_secs(),
+            reverify_interval_secs: default_reverify_interval_secs(),
+            freshness_interval_secs: default_freshness_interval_secs(),
+            log_buffer_lines: default_log_buffer_lines(),
+            log_retain_jobs: default_log_retain_jobs(),
+            exclusive: ExclusiveScope::None,
+        }
+    }
+}
+
+fn default_max_concurrent() -> usize {
+    5
+}
+
+fn default_queue_size() -> usize {
+    100
+}
+
+fn default_stale_sweep_interval_secs() -> u64 {
+    3600
+}
+
+fn default_reverify_interval_secs() -> u64 {
+    60
+}
+
+fn default_freshness_interval_secs() -> u64 {
+    900
+}
+
+fn default_log_buffer_lines() -> usize {
+    2000
+}
+
+fn default_log_retain_jobs() -> usize {
+    50
+}
+
+/// Prefix of the environment variables overriding config keys.
+pub const ENV_PREFIX: &str = "ECHIDNABOT";
+
+/// Separator between nesting levels in an override variable's name:
+/// `ECHIDNABOT_DATABASE__URL` is `[database] url`, and
+/// `ECHIDNABOT_DATABASE__WRITE_BEHIND__ENABLED` is
+/// `[database.write_behind] enabled`.
+pub const ENV_SEPARATOR: &str = "__";
+
+/// Keys whose values [`Config::to_redacted_toml`] masks.
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "webhook_secret",
+    "nats_token",
+    "client_secret",
+    "session_secret",
+    "password",
+    "secret_access_key",
+    "master_key",
+    "api_key",
+];
+
+impl Config {
+    /// Load configuration from file, with environment overrides
+    pub fn load(path: &str) -> Result<Self> {
+        Self::load_layered(path, &[])
+    }
+
+    /// Load configuration in layers, each overriding the one before: the
+    /// file at `path` (defaults when it is missing), the
+    /// `ECHIDNABOT_<SECTION>__<KEY>` environment variables, then
+    /// `overrides` (`section.key=value`, from `--set`).
+    pub fn load_layered(path: &str, overrides: &[String]) -> Result<Self> {
+        Self::load_from(path, None, overrides)
+    }
+
+    /// [`Self::load_layered`] with the environment given as a map, for
+    /// tests; `None` reads the process environment.
+    fn load_from(
+        path: &str,
+        env: Option<config::Map<String, String>>,
+        overrides: &[String],
+    ) -> Result<Self> {
+        let path = Path::new(path);
+
+        let mut builder = config::Config::builder();
+        if path.exists() {
+            builder = builder.add_source(config::File::from(path));
+        } else {
+            tracing::warn!("Config file {} not found, using defaults", path.display());
+        }
+        builder = builder.add_source(
+            config::Environment::with_prefix(ENV_PREFIX)
+                .prefix_separator("_")
+                .separator(ENV_SEPARATOR)
+                .source(env),
+        );
+        for entry in overrides {
+            let (key, value) = entry
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| Error::Config(format!("--set expects KEY=VALUE, got {:?}", entry)))?;
+            builder = builder.set_override(key, value.to_string())?;
+        }
+
+        let config = builder.build()?;
+        let parsed: Config = config.try_deserialize()?;
+
+        Ok(parsed)
+    }
+
+    /// Replace the secret references in tokens and webhook secrets with
+    /// the secrets they name (see [`secrets`]).
+    pub async fn resolve_secrets(&mut self) -> Result<()> {
+        secrets::resolve(self).await
+    }
+
+    /// The configuration as TOML, every default filled in, with tokens
+    /// and webhook secrets masked.
+    pub fn to_redacted_toml(&self) -> Result<String> {
+        fn redact(value: &mut toml::Value) {
+            match value {
+                toml::Value::Table(table) => {
+                    for (key, value) in table.iter_mut() {
+                        if SECRET_KEYS.contains(&key.as_str()) && value.is_str() {
+                            *value = toml::Value::String("<redacted>".to_string());
+                        } else {
+                            redact(value);
+                        }
+                    }
+                }
+                toml::Value::Array(items) => items.iter_mut().for_each(redact),
+                _ => {}
+            }
+        }
+
+        let mut value = toml::Value::try_from(self).map_err(|e| Error::Config(e.to_string()))?;
+        redact(&mut value);
+        toml::to_string_pretty(&value).map_err(|e| Error::Config(e.to_string()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_file_and_set_overrides_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("echidnabot.toml");
+        std::fs::write(
+            &path,
+            "[server]\nport = 9000\n\n[scheduler]\nmax_concurrent = 2\nqueue_size = 10\n\n\
+             [gitlab]\nurl = \"https://gitlab.example\"\ntoken = \"glpat\"\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap();
+        let env: config::Map<String, String> = [
+            ("ECHIDNABOT_DATABASE__URL", "sqlite:///data/bot.db"),
+            ("ECHIDNABOT_SCHEDULER__MAX_CONCURRENT", "6"),
+            ("ECHIDNABOT_SCHEDULER__QUEUE_SIZE", "40"),
+            ("ECHIDNABOT_GITLAB__WEBHOOK_SECRET", "hook"),
+            ("ECHIDNABOT_BOT__INCREMENTAL", "false"),
+            // Not config keys; ignored.
+            ("ECHIDNABOT_LOG_FORMAT", "json"),
+            ("OTHER_PORT", "1"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let config = Config::load_from(
+            path,
+            Some(env),
+            &["scheduler.queue_size=80".to_string(), "server.host = 127.0.0.1".to_string()],
+        )
+        .unwrap();
+        assert_eq!(config.server.port, 9000, "file");
+        assert_eq!(config.database.url, "sqlite:///data/bot.db", "env");
+        assert_eq!(config.scheduler.max_concurrent, 6, "env over file");
+        assert_eq!(config.scheduler.queue_size, 80, "--set over env");
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert!(!config.bot.incremental);
+        let gitlab = config.gitlab.as_ref().unwrap();
+        assert_eq!((gitlab.token.as_str(), gitlab.webhook_secret.as_deref()), ("glpat", Some("hook")));
+
+        assert!(Config::load_from(path, Some(config::Map::new()), &["no-equals".to_string()]).is_err());
+    }
+
+    #[test]
+    fn env_applies_without_a_file() {
+        let env: config::Map<String, String> = [("ECHIDNABOT_SERVER__PORT".to_string(), "7000".to_string())]
+            .into_iter()
+            .collect();
+        let config = Config::load_from("/nonexistent/echidnabot.toml", Some(env), &[]).unwrap();
+        assert_eq!(config.server.port, 7000);
+        assert_eq!(config.scheduler.max_concurrent, SchedulerConfig::default().max_concurrent);
+    }
+
+    #[test]
+    fn redacted_toml_masks_secrets_and_round_trips() {
+        let mut config = Config::default();
+        config.github = Some(GitHubConfig {
+            app_id: Some(42),
+            private_key_path: None,
+            token: Some("ghp_secret".to_string()),
+            webhook_secret: Some("shh".to_string()),
+            auto_register: true,
+        });
+        let shown = config.to_redacted_toml().unwrap();
+        assert!(!shown.contains("ghp_secret") && !shown.contains("shh"));
+        assert!(shown.contains("<redacted>"));
+
+        let reparsed: Config = toml::from_str(&shown).unwrap();
+        assert_eq!(reparsed.server.port, config.server.port);
+        assert_eq!(reparsed.github.unwrap().app_id, Some(42));
+    }
+
+    #[test]
+    fn tls_needs_exactly_one_certificate_source() {
+        let tls = |toml: &str| toml::from_str::<ServerConfig>(toml).unwrap().tls.unwrap();
+        assert!(tls("[tls]\ncert_path = \"c.pem\"\nkey_path = \"k.pem\"").validate().is_ok());
+        assert!(tls("[tls.acme]\ndomains = [\"bot.example.org\"]").validate().is_ok());
+        assert!(tls("[tls]\ncert_path = \"c.pem\"").validate().is_err());
+        assert!(tls("[tls.acme]\ndomains = []").validate().is_err());
+        assert!(tls("[tls]\nkey_path = \"k.pem\"\ncert_path = \"c.pem\"\n[tls.acme]\ndomains = [\"a\"]")
+            .validate()
+            .is_err());
+        assert_eq!(tls("[tls]").handshake_timeout_secs, 10);
+    }
+
+    #[test]
+    fn dispatch_defaults_follow_local_isolation() {
+        let executor = |toml: &str| toml::from_str::<ExecutorConfig>(toml).unwrap().dispatch();
+        assert_eq!(executor(""), DispatchStrategy::PreferEchidna);
+        assert_eq!(executor("local_isolation = true"), DispatchStrategy::PreferLocal);
+        assert_eq!(
+            executor("dispatch = \"echidna-with-local-fallback\""),
+            DispatchStrategy::EchidnaWithLocalFallback
+        );
+    }
+}
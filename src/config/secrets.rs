@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Secret references
+//!
+//! A secret in the configuration (platform tokens, webhook secrets, the
+//! NATS and training-export tokens, the OIDC client and session secrets)
+//! can name where to fetch it instead of holding it:
+//!
+//! | Reference | Resolved from |
+//! |-----------|---------------|
+//! | `file:/run/secrets/github_token` | the file, trailing newline trimmed |
+//! | `vault:secret/data/echidnabot#github_token` | a Vault KV (v2 or v1) field |
+//! | `aws-sm:prod/echidnabot#github_token` | AWS Secrets Manager, via the `aws` CLI; `#key` picks a JSON field |
+//! | `sops:/etc/echidnabot/secrets.enc.yaml#github.token` | a SOPS-encrypted file, via the `sops` CLI |
+//!
+//! Any other value is the secret itself. References are resolved when the
+//! configuration is loaded, so again on every reload, and with `[secrets]
+//! refresh_interval_secs` on a timer: a secret rotated in its backend is
+//! picked up without editing the file.
+
+use serde::{Deserialize, Serialize};
+
+use super::Config;
+use crate::error::{Error, Result};
+
+/// `[secrets]`
+///
+/// ```toml
+/// [secrets]
+/// refresh_interval_secs = 300
+/// vault_addr = "https://vault.internal:8200"
+/// vault_token_file = "/var/run/secrets/vault-token"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SecretsConfig {
+    /// Reload (and so re-resolve references) this often, in seconds; 0
+    /// resolves them only at startup and on SIGHUP.
+    #[serde(default)]
+    pub refresh_interval_secs: u64,
+
+    /// Vault address; defaults to `$VAULT_ADDR`.
+    #[serde(default)]
+    pub vault_addr: Option<String>,
+
+    /// File holding the Vault token; defaults to `$VAULT_TOKEN`.
+    #[serde(default)]
+    pub vault_token_file: Option<String>,
+}
+
+/// Where a secret comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretRef<'a> {
+    File(&'a str),
+    Vault { path: &'a str, key: &'a str },
+    AwsSecretsManager { id: &'a str, key: Option<&'a str> },
+    Sops { path: &'a str, key: &'a str },
+}
+
+impl<'a> SecretRef<'a> {
+    /// The reference in `value`, or `None` when it is a literal secret.
+    pub fn parse(value: &'a str) -> Result<Option<Self>> {
+        let Some((scheme, rest)) = value.split_once(':') else {
+            return Ok(None);
+        };
+        let keyed = |rest: &'a str| match rest.split_once('#') {
+            Some((target, key)) if !target.is_empty() && !key.is_empty() => Ok((target, key)),
+            _ => Err(Error::Config(format!("{}: reference needs <target>#<key>", scheme))),
+        };
+        let reference = match scheme {
+            "file" => Self::File(rest),
+            "vault" => keyed(rest).map(|(path, key)| Self::Vault { path, key })?,
+            "sops" => keyed(rest).map(|(path, key)| Self::Sops { path, key })?,
+            "aws-sm" => match rest.split_once('#') {
+                Some((id, key)) => Self::AwsSecretsManager { id, key: Some(key) },
+                None => Self::AwsSecretsManager { id: rest, key: None },
+            },
+            _ => return Ok(None),
+        };
+        match reference {
+            Self::File("") | Self::AwsSecretsManager { id: "", .. } => {
+                Err(Error::Config(format!("{}: reference names nothing", scheme)))
+            }
+            reference => Ok(Some(reference)),
+        }
+    }
+
+    /// Fetch the secret.
+    pub async fn fetch(&self, settings: &SecretsConfig, http: &reqwest::Client) -> Result<String> {
+        match *self {
+            Self::File(path) => Ok(tokio::fs::read_to_string(path)
+                .await?
+                .trim_end_matches(['\n', '\r'])
+                .to_string()),
+            Self::Vault { path, key } => fetch_vault(settings, http, path, key).await,
+            Self::AwsSecretsManager { id, key } => {
+                let secret = run_cli(
+                    "aws",
+                    &[
+                        "secretsmanager",
+                        "get-secret-value",
+                        "--secret-id",
+                        id,
+                        "--query",
+                        "SecretString",
+                        "--output",
+                        "text",
+                    ],
+                )
+                .await?;
+                match key {
+                    None => Ok(secret),
+                    Some(key) => {
+                        let fields: serde_json::Value = serde_json::from_str(&secret)?;
+                        string_field(&fields, key)
+                            .ok_or_else(|| Error::Config(format!("aws-sm:{} has no string field {}", id, key)))
+                    }
+                }
+            }
+            Self::Sops { path, key } => {
+                // `github.token` → `["github"]["token"]`
+                let extract: String = key.split('.').map(|part| format!("[{:?}]", part)).collect();
+                run_cli("sops", &["--decrypt", "--extract", &extract, path]).await
+            }
+        }
+    }
+}
+
+async fn fetch_vault(settings: &SecretsConfig, http: &reqwest::Client, path: &str, key: &str) -> Result<String> {
+    let addr = settings
+        .vault_addr
+        .clone()
+        .or_else(|| std::env::var("VAULT_ADDR").ok())
+        .ok_or_else(|| Error::Config("vault: references need [secrets] vault_addr or VAULT_ADDR".into()))?;
+    let token = match &settings.vault_token_file {
+        Some(file) => tokio::fs::read_to_string(file).await?.trim().to_string(),
+        None => std::env::var("VAULT_TOKEN").map_err(|_| {
+            Error::Config("vault: references need [secrets] vault_token_file or VAULT_TOKEN".into())
+        })?,
+    };
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let body: serde_json::Value = http
+        .get(&url)
+        .header("X-Vault-Token", token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    // KV v2 nests the fields one level deeper than v1.
+    let data = &body["data"];
+    data.get("data")
+        .and_then(|fields| string_field(fields, key))
+        .or_else(|| string_field(data, key))
+        .ok_or_else(|| Error::Config(format!("vault:{} has no string field {}", path, key)))
+}
+
+fn string_field(fields: &serde_json::Value, key: &str) -> Option<String> {
+    fields.get(key)?.as_str().map(str::to_string)
+}
+
+async fn run_cli(program: &str, args: &[&str]) -> Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| Error::Config(format!("running {}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(Error::Config(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(['\n', '\r'])
+        .to_string())
+}
+
+/// The secret-bearing settings of `config`, named as in the file.
+fn secret_fields(config: &mut Config) -> Vec<(&'static str, &mut String)> {
+    let mut fields = Vec::new();
+    if let Some(github) = &mut config.github {
+        fields.extend(github.token.as_mut().map(|v| ("[github] token", v)));
+        fields.extend(github.webhook_secret.as_mut().map(|v| ("[github] webhook_secret", v)));
+    }
+    if let Some(gitlab) = &mut config.gitlab {
+        fields.push(("[gitlab] token", &mut gitlab.token));
+        fields.extend(gitlab.webhook_secret.as_mut().map(|v| ("[gitlab] webhook_secret", v)));
+    }
+    if let Some(codeberg) = &mut config.codeberg {
+        fields.extend(codeberg.token.as_mut().map(|v| ("[codeberg] token", v)));
+        fields.extend(codeberg.webhook_secret.as_mut().map(|v| ("[codeberg] webhook_secret", v)));
+    }
+    fields.extend(config.fleet.nats_token.as_mut().map(|v| ("[fleet] nats_token", v)));
+    fields.extend(config.corpus.export.token.as_mut().map(|v| ("[corpus.export] token", v)));
+    fields.extend(config.events.token.as_mut().map(|v| ("[events] token", v)));
+    fields.extend(config.events.password.as_mut().map(|v| ("[events] password", v)));
+    fields.extend(config.tenancy.master_key.as_mut().map(|v| ("[tenancy] master_key", v)));
+    fields.extend(config.echidna.auth.api_key.as_mut().map(|v| ("[echidna.auth] api_key", v)));
+    for sink in &mut config.analytics.sinks {
+        fields.extend(sink.password.as_mut().map(|v| ("[[analytics.sinks]] password", v)));
+        fields.extend(sink.token.as_mut().map(|v| ("[[analytics.sinks]] token", v)));
+        let secret_key = sink.secret_access_key.as_mut();
+        fields.extend(secret_key.map(|v| ("[[analytics.sinks]] secret_access_key", v)));
+    }
+    if let Some(oidc) = &mut config.auth.oidc {
+        fields.extend(oidc.client_secret.as_mut().map(|v| ("[auth.oidc] client_secret", v)));
+        fields.extend(oidc.session_secret.as_mut().map(|v| ("[auth.oidc] session_secret", v)));
+    }
+    fields
+}
+
+/// Replace every secret reference in `config` with the secret. The first
+/// reference that cannot be resolved fails the whole load.
+pub async fn resolve(config: &mut Config) -> Result<()> {
+    let settings = config.secrets.clone();
+    let http = reqwest::Client::new();
+    for (name, value) in secret_fields(config) {
+        let Some(reference) = SecretRef::parse(value)? else {
+            continue;
+        };
+        let secret = reference
+            .fetch(&settings, &http)
+            .await
+            .map_err(|e| Error::Config(format!("resolving {}: {}", name, e)))?;
+        *value = secret;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_parse_and_literals_pass_through() {
+        assert_eq!(SecretRef::parse("ghp_plain").unwrap(), None);
+        assert_eq!(SecretRef::parse("https://example.org").unwrap(), None);
+        assert_eq!(
+            SecretRef::parse("file:/run/secrets/token").unwrap(),
+            Some(SecretRef::File("/run/secrets/token"))
+        );
+        assert_eq!(
+            SecretRef::parse("vault:secret/data/bot#gh").unwrap(),
+            Some(SecretRef::Vault {
+                path: "secret/data/bot",
+                key: "gh"
+            })
+        );
+        assert_eq!(
+            SecretRef::parse("aws-sm:prod/bot").unwrap(),
+            Some(SecretRef::AwsSecretsManager {
+                id: "prod/bot",
+                key: None
+            })
+        );
+        assert!(SecretRef::parse("vault:secret/data/bot").is_err(), "vault needs a key");
+        assert!(SecretRef::parse("sops:#key").is_err());
+        assert!(SecretRef::parse("file:").is_err());
+    }
+
+    #[tokio::test]
+    async fn file_references_resolve_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let token = dir.path().join("gh_token");
+        std::fs::write(&token, "ghp_from_file\n").unwrap();
+
+        let mut config = Config::default();
+        config.github = Some(crate::config::GitHubConfig {
+            app_id: None,
+            private_key_path: None,
+            token: Some(format!("file:{}", token.display())),
+            webhook_secret: Some("literal".to_string()),
+            auto_register: true,
+        });
+        resolve(&mut config).await.unwrap();
+        let github = config.github.as_ref().unwrap();
+        assert_eq!(github.token.as_deref(), Some("ghp_from_file"));
+        assert_eq!(github.webhook_secret.as_deref(), Some("literal"));
+
+        config.fleet.nats_token = Some(format!("file:{}", dir.path().join("missing").display()));
+        let err = resolve(&mut config).await.unwrap_err().to_string();
+        assert!(err.contains("[fleet] nats_token"), "{}", err);
+    }
+}
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Why a job was reported the way it was
+//!
+//! "Why did the bot block this PR?" is answered by a [`DecisionTrace`],
+//! stored with the job (`JobPayload::decision`) as it runs and reports:
+//!
+//! - the **mode** it reported in, and which level of the cascade chose it
+//!   ([`crate::modes::resolve_mode_traced`]);
+//! - whether the repository has a **directive** file;
+//! - the **trust level** (1–5, [`crate::trust::confidence`]) of the result;
+//! - **cache hits**: a result imported from a federation peer, or a push
+//!   narrowed to the files it touched;
+//! - every **policy** that changed the outcome — quarantined and flaky
+//!   files, `[budgets]`, `[axioms]`, the Regulator coverage gate, grace
+//!   periods, prover pins — in the order they applied;
+//! - the check run's **conclusion**, and whether it blocks merging.
+//!
+//! Served as the `explainJob(jobId)` GraphQL query and by `echidnabot
+//! status --target <job> --explain`. Jobs that ran before the trace was
+//! recorded have none.
+
+use serde::{Deserialize, Serialize};
+
+use crate::modes::{BotMode, ModeSource};
+use crate::scheduler::JobId;
+use crate::store::Store;
+
+/// The decision inputs of one job.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionTrace {
+    /// `None` until the job reports.
+    #[serde(default)]
+    pub mode: Option<BotMode>,
+    #[serde(default)]
+    pub mode_source: Option<ModeSource>,
+    /// The repository has a directive file (`.echidnabot.toml`, ...).
+    #[serde(default)]
+    pub directive: bool,
+    /// Confidence level of the result, 1–5.
+    #[serde(default)]
+    pub trust_level: Option<u8>,
+    /// Work reused rather than redone, e.g. `federated result from peer-a`.
+    #[serde(default)]
+    pub cache_hits: Vec<String>,
+    #[serde(default)]
+    pub policies: Vec<PolicyHit>,
+    /// Check run conclusion, e.g. `failure`.
+    #[serde(default)]
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub blocks_merge: bool,
+}
+
+/// A policy that applied to a job, and what it did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyHit {
+    /// e.g. `budgets`, `axioms`, `coverage_gate`.
+    pub policy: String,
+    pub effect: String,
+}
+
+impl DecisionTrace {
+    pub fn policy(&mut self, policy: &str, effect: impl Into<String>) {
+        self.policies.push(PolicyHit {
+            policy: policy.to_string(),
+            effect: effect.into(),
+        });
+    }
+
+    /// One line per input, for `echidnabot status --explain`.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match (self.mode, self.mode_source) {
+            (Some(mode), Some(source)) => {
+                lines.push(format!("mode: {} (from {})", mode, source.as_str()))
+            }
+            (Some(mode), None) => lines.push(format!("mode: {}", mode)),
+            _ => lines.push("mode: not reported yet".to_string()),
+        }
+        lines.push(format!(
+            "directive: {}",
+            if self.directive { "present" } else { "none" }
+        ));
+        if let Some(level) = self.trust_level {
+            lines.push(format!("trust level: {}", level));
+        }
+        for hit in &self.cache_hits {
+            lines.push(format!("cache hit: {}", hit));
+        }
+        for hit in &self.policies {
+            lines.push(format!("policy {}: {}", hit.policy, hit.effect));
+        }
+        if let Some(conclusion) = &self.conclusion {
+            lines.push(format!(
+                "conclusion: {}{}",
+                conclusion,
+                if self.blocks_merge { " (blocks merging)" } else { "" }
+            ));
+        }
+        lines
+    }
+}
+
+/// Apply `update` to `job`'s stored trace, starting one if it has none.
+/// Best-effort: a failure is logged, never returned.
+pub async fn record(store: &dyn Store, job: JobId, update: impl FnOnce(&mut DecisionTrace)) {
+    let result = async {
+        if let Some(mut record) = store.get_job(job).await? {
+            update(record.payload.decision.get_or_insert_with(DecisionTrace::default));
+            store.update_job(&record).await?;
+        }
+        crate::Result::Ok(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::debug!("Decision trace for job {} not recorded: {}", job, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::ProofJob;
+    use crate::store::models::{ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn trace_accumulates_on_the_job() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let job = ProofJob::new(repo.id, "abc".into(), ProverKind::new("coq"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+
+        record(&store, job.id, |t| t.policy("quarantine", "1 failure ignored: A.v")).await;
+        record(&store, job.id, |t| {
+            t.mode = Some(BotMode::Regulator);
+            t.mode_source = Some(ModeSource::Directive);
+            t.directive = true;
+            t.policy("coverage_gate", "50% below threshold 80%: failure");
+            t.conclusion = Some("failure".into());
+            t.blocks_merge = true;
+        })
+        .await;
+
+        let trace = store.get_job(job.id).await.unwrap().unwrap().payload.decision.unwrap();
+        assert_eq!(trace.policies.len(), 2);
+        assert_eq!(
+            trace.lines(),
+            [
+                "mode: Regulator (from directive)",
+                "directive: present",
+                "policy quarantine: 1 failure ignored: A.v",
+                "policy coverage_gate: 50% below threshold 80%: failure",
+                "conclusion: failure (blocks merging)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Declarative repository registrations (`echidnabot apply`)
+//!
+//! A registration file (`repos.toml`, or `repos.yaml` / `repos.yml`) lists
+//! the repositories an instance should monitor and their settings:
+//!
+//! ```toml
+//! prune = true    # disable registered repositories not listed here
+//!
+//! [[repos]]
+//! repo = "acme/proofs"
+//! platform = "github"           # default
+//! provers = ["coq", "lean"]
+//! mode = "regulator"
+//! regulator_threshold = 90
+//! include = ["theories/**"]
+//! visibility = "public"
+//! reverify_cron = "0 3 * * *"
+//! timezone = "Europe/London"
+//! pins = { lean = "4.7.0" }
+//! webhook_secret_env = "ACME_PROOFS_WEBHOOK_SECRET"
+//! ```
+//!
+//! [`plan`] compares the file with the store: unregistered repositories
+//! are created, listed ones whose settings differ are updated (and
+//! re-enabled), and with `prune = true` enabled repositories missing from
+//! the file are disabled, keeping their history as `unregister` does.
+//! Settings an entry leaves out are not managed and keep their current
+//! value, so a file naming only `repo` just keeps it registered.
+//!
+//! `echidnabot apply <file>` prints the plan and asks before [`apply`]
+//! stores it; `--dry-run` stops after the plan. Every stored change gets
+//! an audit entry. Secrets never appear in the file: `webhook_secret_env`
+//! names the environment variable holding one.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::adapters::Platform;
+use crate::audit::{self, AuditAction};
+use crate::bulk::{set, FieldChange, SettingsPatch};
+use crate::dispatcher::ProverKind;
+use crate::error::{Error, Result};
+use crate::modes::BotMode;
+use crate::store::models::{Repository, Visibility};
+use crate::store::Store;
+
+/// A parsed registration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegistrationFile {
+    /// Disable enabled repositories the file does not list.
+    #[serde(default)]
+    pub prune: bool,
+    #[serde(default)]
+    pub repos: Vec<RepoEntry>,
+}
+
+/// One `[[repos]]` entry. `None` leaves a setting as it is.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoEntry {
+    /// `owner/name`.
+    pub repo: String,
+    /// `github` (default), `gitlab`, `bitbucket` or `codeberg`.
+    pub platform: Option<String>,
+    pub provers: Option<Vec<String>>,
+    pub mode: Option<BotMode>,
+    pub regulator_threshold: Option<u8>,
+    pub check_on_push: Option<bool>,
+    pub check_on_pr: Option<bool>,
+    pub auto_comment: Option<bool>,
+    pub branch_include: Option<Vec<String>>,
+    pub branch_exclude: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub visibility: Option<Visibility>,
+    pub training_export: Option<bool>,
+    /// `""` removes the schedule.
+    pub reverify_cron: Option<String>,
+    /// IANA time zone of the schedule and blackout windows; `""` inherits
+    /// the `[calendar]` one.
+    pub timezone: Option<String>,
+    /// Prover slug to required version.
+    pub pins: Option<BTreeMap<String, String>>,
+    /// Environment variable holding the repository's webhook secret.
+    pub webhook_secret_env: Option<String>,
+}
+
+impl RegistrationFile {
+    /// Parse `text`, as YAML when `file_name` ends in `.yaml` / `.yml`,
+    /// else as TOML.
+    pub fn parse(file_name: &str, text: &str) -> Result<Self> {
+        let lower = file_name.to_lowercase();
+        if lower.ends_with(".yaml") || lower.ends_with(".yml") {
+            serde_yaml::from_str(text).map_err(|e| Error::Config(format!("{}: {}", file_name, e)))
+        } else {
+            toml::from_str(text).map_err(|e| Error::Config(format!("{}: {}", file_name, e)))
+        }
+    }
+}
+
+/// What [`apply`] does to one repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Create,
+    Update,
+    /// Monitoring stops; the history is kept.
+    Disable,
+}
+
+/// A repository as it would be after applying, and what changed. For
+/// [`Action::Create`] the changes are against a new registration's
+/// defaults.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub action: Action,
+    pub repo: Repository,
+    pub changes: Vec<FieldChange>,
+}
+
+fn invalid(entry: &RepoEntry, message: impl std::fmt::Display) -> Error {
+    Error::Config(format!("repos entry '{}': {}", entry.repo, message))
+}
+
+/// `entry`'s platform, owner and name.
+fn identity(entry: &RepoEntry) -> Result<(Platform, String, String)> {
+    let platform = entry.platform.as_deref().unwrap_or("github");
+    let platform = crate::api::badge::parse_platform(platform)
+        .ok_or_else(|| invalid(entry, format!("unknown platform '{}'", platform)))?;
+    match entry.repo.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+            Ok((platform, owner.to_string(), name.to_string()))
+        }
+        _ => Err(invalid(entry, "repo must be owner/name")),
+    }
+}
+
+fn prover(entry: &RepoEntry, slug: &str) -> Result<ProverKind> {
+    let slug = slug.trim().to_lowercase();
+    ProverKind::all()
+        .find(|kind| kind.as_str() == slug)
+        .ok_or_else(|| invalid(entry, format!("unknown prover '{}'", slug)))
+}
+
+/// `entry`'s settings as a validated [`SettingsPatch`].
+fn settings_patch(entry: &RepoEntry) -> Result<SettingsPatch> {
+    let webhook_secret = match &entry.webhook_secret_env {
+        Some(var) => Some(
+            std::env::var(var)
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .ok_or_else(|| invalid(entry, format!("${} is not set", var)))?,
+        ),
+        None => None,
+    };
+    let enabled_provers = entry
+        .provers
+        .as_ref()
+        .map(|slugs| slugs.iter().map(|slug| prover(entry, slug)).collect::<Result<Vec<_>>>())
+        .transpose()?;
+    let reverify_cron = match entry.reverify_cron.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(expr) => {
+            crate::reverify::parse_schedule(expr).map_err(|e| invalid(entry, e))?;
+            Some(Some(expr.to_string()))
+        }
+        None => None,
+    };
+    let timezone = match entry.timezone.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(name) => {
+            crate::calendar::parse_timezone(name).map_err(|e| invalid(entry, e))?;
+            Some(Some(name.to_string()))
+        }
+        None => None,
+    };
+    let prover_pins = match &entry.pins {
+        Some(pins) => {
+            let mut validated = BTreeMap::new();
+            for (slug, version) in pins {
+                let kind = prover(entry, slug)?;
+                let version =
+                    crate::toolchain::validate_pin(&kind, version).map_err(|e| invalid(entry, e))?;
+                validated.insert(kind.as_str().to_string(), version);
+            }
+            Some(validated)
+        }
+        None => None,
+    };
+    Ok(SettingsPatch {
+        webhook_secret,
+        enabled_provers,
+        check_on_push: entry.check_on_push,
+        check_on_pr: entry.check_on_pr,
+        auto_comment: entry.auto_comment,
+        branch_include: entry.branch_include.clone(),
+        branch_exclude: entry.branch_exclude.clone(),
+        path_include: entry.include.clone(),
+        path_exclude: entry.exclude.clone(),
+        visibility: entry.visibility,
+        training_export: entry.training_export,
+        reverify_cron,
+        timezone,
+        prover_pins,
+    })
+}
+
+/// Apply `entry` to `repo`, returning what changed.
+fn apply_entry(
+    entry: &RepoEntry,
+    patch: &SettingsPatch,
+    repo: &mut Repository,
+) -> Vec<FieldChange> {
+    let mut changes = patch.apply(repo);
+    set(&mut changes, "mode", &mut repo.mode, &entry.mode);
+    let threshold = entry.regulator_threshold.map(|t| t.min(100));
+    let slot = &mut repo.regulator_coverage_threshold;
+    set(&mut changes, "regulatorCoverageThreshold", slot, &threshold);
+    set(&mut changes, "enabled", &mut repo.enabled, &Some(true));
+    changes
+}
+
+/// Compare `file` with the store without storing anything. Only
+/// repositories that change are returned: creations and updates in file
+/// order, then disabled repositories in `owner/name` order.
+pub async fn plan(store: &dyn Store, file: &RegistrationFile) -> Result<Vec<Change>> {
+    let mut listed = HashSet::new();
+    let mut plan = Vec::new();
+    for entry in &file.repos {
+        let (platform, owner, name) = identity(entry)?;
+        if !listed.insert((platform, owner.clone(), name.clone())) {
+            return Err(invalid(entry, "listed twice"));
+        }
+        let patch = settings_patch(entry)?;
+        let existing = store.get_repository_by_name(platform, &owner, &name).await?;
+        let (action, mut repo) = match existing {
+            Some(repo) => (Action::Update, repo),
+            None => (Action::Create, Repository::new(platform, owner, name)),
+        };
+        let changes = apply_entry(entry, &patch, &mut repo);
+        if action == Action::Create || !changes.is_empty() {
+            plan.push(Change { action, repo, changes });
+        }
+    }
+
+    if file.prune {
+        let mut unlisted: Vec<Repository> = store
+            .list_repositories(None)
+            .await?
+            .into_iter()
+            .filter(|repo| {
+                let key = (repo.platform, repo.owner.clone(), repo.name.clone());
+                repo.enabled && !listed.contains(&key)
+            })
+            .collect();
+        unlisted.sort_by_key(|repo| repo.full_name());
+        for mut repo in unlisted {
+            let mut changes = Vec::new();
+            set(&mut changes, "enabled", &mut repo.enabled, &Some(false));
+            plan.push(Change { action: Action::Disable, repo, changes });
+        }
+    }
+    Ok(plan)
+}
+
+/// Store `plan`, with an audit entry per repository by `actor`. Returns
+/// how many were stored; stops at the first store error, leaving those
+/// before it applied.
+pub async fn apply(store: &dyn Store, actor: &str, plan: &mut [Change]) -> Result<usize> {
+    let mut stored = 0;
+    for change in plan.iter_mut() {
+        change.repo.updated_at = chrono::Utc::now();
+        let action = match change.action {
+            Action::Create => {
+                store.create_repository(&change.repo).await?;
+                AuditAction::RepoRegistered
+            }
+            Action::Update | Action::Disable => {
+                store.update_repository(&change.repo).await?;
+                AuditAction::RepoSettingsChanged
+            }
+        };
+        let payload = audit::settings_payload(&change.repo);
+        audit::record(store, actor, action, Some(&change.repo), None, payload).await;
+        stored += 1;
+    }
+    Ok(stored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SqliteStore;
+
+    #[test]
+    fn toml_and_yaml_read_the_same() {
+        let toml =
+            "prune = true\n[[repos]]\nrepo = \"o/a\"\nprovers = [\"coq\"]\nmode = \"advisor\"\n";
+        let yaml = "prune: true\nrepos:\n  - repo: o/a\n    provers: [coq]\n    mode: advisor\n";
+        for (name, text) in [("repos.toml", toml), ("repos.yaml", yaml)] {
+            let file = RegistrationFile::parse(name, text).unwrap();
+            assert!(file.prune);
+            assert_eq!(file.repos[0].repo, "o/a");
+            assert_eq!(file.repos[0].mode, Some(BotMode::Advisor));
+        }
+        let typo = "[[repos]]\nrepo = \"o/a\"\nmod = 1";
+        assert!(RegistrationFile::parse("repos.toml", typo).is_err());
+    }
+
+    #[tokio::test]
+    async fn plan_creates_updates_and_prunes_then_applies() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let kept = Repository::new(Platform::GitHub, "o".into(), "kept".into());
+        let changed = Repository::new(Platform::GitHub, "o".into(), "changed".into());
+        let unlisted = Repository::new(Platform::GitHub, "o".into(), "unlisted".into());
+        for repo in [&kept, &changed, &unlisted] {
+            store.create_repository(repo).await.unwrap();
+        }
+        let file = RegistrationFile::parse(
+            "repos.toml",
+            r#"
+            prune = true
+            [[repos]]
+            repo = "o/kept"
+            [[repos]]
+            repo = "o/changed"
+            check_on_push = false
+            [[repos]]
+            repo = "o/new"
+            provers = ["lean"]
+            mode = "regulator"
+            "#,
+        )
+        .unwrap();
+
+        let mut planned = plan(&store, &file).await.unwrap();
+        let summary: Vec<(Action, String)> =
+            planned.iter().map(|c| (c.action, c.repo.full_name())).collect();
+        assert_eq!(
+            summary,
+            [
+                (Action::Update, "o/changed".to_string()),
+                (Action::Create, "o/new".to_string()),
+                (Action::Disable, "o/unlisted".to_string()),
+            ]
+        );
+        assert_eq!(
+            planned[0].changes,
+            [FieldChange { field: "checkOnPush", from: "true".into(), to: "false".into() }]
+        );
+        assert!(store.get_repository(unlisted.id).await.unwrap().unwrap().enabled, "plan only");
+
+        assert_eq!(apply(&store, audit::CLI, &mut planned).await.unwrap(), 3);
+        let new = store.get_repository_by_name(Platform::GitHub, "o", "new").await.unwrap();
+        let new = new.unwrap();
+        assert_eq!(new.mode, BotMode::Regulator);
+        assert_eq!(new.enabled_provers, [ProverKind::new("lean")]);
+        assert!(!store.get_repository(unlisted.id).await.unwrap().unwrap().enabled);
+        assert!(plan(&store, &file).await.unwrap().is_empty(), "converged");
+
+        let twice = "[[repos]]\nrepo = \"o/a\"\n[[repos]]\nrepo = \"o/a\"";
+        let twice = RegistrationFile::parse("repos.toml", twice).unwrap();
+        assert!(plan(&store, &twice).await.is_err());
+    }
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Proof dependency graph for incremental push verification
+//!
+//! Every job for a prover with a known import convention rebuilds the
+//! graph of which proof file imports which from its checkout and stores it
+//! per `(repo, prover)`:
+//!
+//!   * Coq — `Require Import A.B.`, `From A Require B.`, `Load "x".`
+//!   * Lean — `import A.B` at the top of the file
+//!   * Metamath — `$[ file.mm $]` inclusions
+//!
+//! On push, [`plan_push`] narrows each prover's job to the changed files
+//! plus every file that transitively imports one of them. Whenever that
+//! cannot be done safely — no graph recorded yet, a prover without an
+//! import convention, a build or bot configuration file touched, or a
+//! push whose file list is incomplete — it returns `None` and the caller
+//! verifies the whole repository as before.
+//!
+//! Module names are resolved against the known files by path suffix
+//! (`A.B.C` → `…/A/B/C.v`), dropping leading components until something
+//! matches so that `-Q theories Lib` style logical prefixes still resolve.
+//! An ambiguous name links to every candidate, which can only widen a
+//! run. Imports that match no file (the standard library, external
+//! packages) are ignored.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::dispatcher::{ExtensionTable, ProverKind};
+use crate::store::Store;
+
+/// Repo-relative files whose change invalidates the whole graph: prover
+/// project files and the bot's own configuration.
+const PROJECT_FILES: &[&str] = &[
+    "_CoqProject",
+    "dune-project",
+    "lakefile.lean",
+    "lakefile.toml",
+    "lake-manifest.json",
+    "lean-toolchain",
+    ".echidnabot.toml",
+    ".echidnaignore",
+];
+
+/// Files changed by a push, accumulated commit by commit. Paths are
+/// repo-relative, `/`-separated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedFiles {
+    /// Added or modified, and present after the push.
+    pub modified: BTreeSet<String>,
+    /// Deleted by the push.
+    pub removed: BTreeSet<String>,
+}
+
+impl ChangedFiles {
+    /// Fold in one commit. Later commits win, so a file deleted and then
+    /// re-added counts as modified.
+    pub fn apply_commit<S: AsRef<str>>(&mut self, added: &[S], modified: &[S], removed: &[S]) {
+        for path in added.iter().chain(modified).map(AsRef::as_ref) {
+            self.removed.remove(path);
+            self.modified.insert(path.to_string());
+        }
+        for path in removed.iter().map(AsRef::as_ref) {
+            self.modified.remove(path);
+            self.removed.insert(path.to_string());
+        }
+    }
+
+    /// Keep only the paths `keep` accepts.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.modified.retain(|path| keep(path));
+        self.removed.retain(|path| keep(path));
+    }
+
+    /// Number of distinct files changed so far.
+    pub fn len(&self) -> usize {
+        self.modified.len() + self.removed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    fn all(&self) -> impl Iterator<Item = &str> {
+        self.modified.iter().chain(&self.removed).map(String::as_str)
+    }
+}
+
+/// Which proof file imports which, for one prover. Every known proof
+/// file has an entry, possibly with no imports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub imports: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from `(repo-relative path, content)` pairs.
+    pub fn build(prover: &ProverKind, files: &[(String, String)]) -> Self {
+        let known: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+        let imports = files
+            .iter()
+            .map(|(path, content)| {
+                let deps = parse_imports(prover, content)
+                    .iter()
+                    .flat_map(|import| resolve(prover, path, import, &known))
+                    .filter(|dep| dep != path)
+                    .collect();
+                (path.clone(), deps)
+            })
+            .collect();
+        Self { imports }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+    }
+
+    /// `changed` plus every file that transitively imports one of them.
+    pub fn affected<'a>(&self, changed: impl IntoIterator<Item = &'a str>) -> BTreeSet<String> {
+        let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (file, deps) in &self.imports {
+            for dep in deps {
+                dependents.entry(dep.as_str()).or_default().push(file.as_str());
+            }
+        }
+
+        let mut affected = BTreeSet::new();
+        let mut pending: Vec<&str> = changed.into_iter().collect();
+        while let Some(file) = pending.pop() {
+            if affected.insert(file.to_string()) {
+                pending.extend(dependents.get(file).into_iter().flatten().copied());
+            }
+        }
+        affected
+    }
+}
+
+/// True for provers whose import statements [`parse_imports`] understands.
+pub fn supports(prover: &ProverKind) -> bool {
+    matches!(prover.as_str(), "coq" | "lean" | "metamath")
+}
+
+/// True when `path` is a project or configuration file (see
+/// [`PROJECT_FILES`]) or a bot directive.
+pub fn is_project_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    PROJECT_FILES.contains(&name) || path.starts_with(".machine_readable/bot_directives/")
+}
+
+/// Module names or paths imported by one proof file, as written.
+pub fn parse_imports(prover: &ProverKind, content: &str) -> Vec<String> {
+    match prover.as_str() {
+        "coq" => coq_imports(content),
+        "lean" => lean_imports(content),
+        "metamath" => metamath_includes(content),
+        _ => Vec::new(),
+    }
+}
+
+fn coq_imports(content: &str) -> Vec<String> {
+    // Sentences end at a `.` followed by whitespace; qualified names
+    // (`Coq.Lists.List`) keep their dots.
+    let source = strip_nested_comments(content, "(*", "*)")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut imports = Vec::new();
+    for sentence in source.split(". ") {
+        let tokens: Vec<&str> = sentence
+            .split_whitespace()
+            .map(|t| t.trim_end_matches('.'))
+            .collect();
+        match tokens.as_slice() {
+            ["Load", file, ..] => imports.push(file.trim_matches('"').to_string()),
+            ["From", prefix, "Require", rest @ ..] => {
+                imports.extend(required_modules(rest).map(|m| format!("{prefix}.{m}")));
+            }
+            ["Require", rest @ ..] => imports.extend(required_modules(rest).map(String::from)),
+            _ => {}
+        }
+    }
+    imports
+}
+
+fn required_modules<'a>(tokens: &'a [&'a str]) -> impl Iterator<Item = &'a str> {
+    tokens
+        .iter()
+        .copied()
+        .filter(|t| !matches!(*t, "Import" | "Export" | "-" | ""))
+        .filter(|t| !t.starts_with('('))
+}
+
+fn lean_imports(content: &str) -> Vec<String> {
+    let source = strip_nested_comments(content, "/-", "-/");
+    let mut imports = Vec::new();
+    for line in source.lines() {
+        let line = line.split("--").next().unwrap_or_default().trim();
+        if line.is_empty() || line == "prelude" {
+            continue;
+        }
+        // Imports must come first; stop at the first other command.
+        let Some(rest) = line.strip_prefix("import ") else {
+            break;
+        };
+        imports.extend(rest.split_whitespace().map(String::from));
+    }
+    imports
+}
+
+fn metamath_includes(content: &str) -> Vec<String> {
+    let mut includes = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("$[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("$]") else {
+            break;
+        };
+        let file = after[..end].trim();
+        if !file.is_empty() {
+            includes.push(file.to_string());
+        }
+        rest = &after[end + 2..];
+    }
+    includes
+}
+
+/// Remove (possibly nested) block comments.
+pub(crate) fn strip_nested_comments(content: &str, open: &str, close: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut depth = 0usize;
+    let mut rest = content;
+    while !rest.is_empty() {
+        if rest.starts_with(open) {
+            depth += 1;
+            rest = &rest[open.len()..];
+        } else if depth > 0 && rest.starts_with(close) {
+            depth -= 1;
+            rest = &rest[close.len()..];
+            out.push(' ');
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            if depth == 0 {
+                out.push(ch);
+            }
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// Known files an import may refer to.
+fn resolve(prover: &ProverKind, importer: &str, import: &str, known: &[&str]) -> Vec<String> {
+    if prover.as_str() == "metamath" {
+        // `$[ … $]` paths are relative to the including file, else the root.
+        let dir = importer.rsplit_once('/').map(|(dir, _)| dir);
+        let candidates = [dir.map(|d| format!("{d}/{import}")), Some(import.to_string())];
+        if let Some(hit) = candidates
+            .into_iter()
+            .flatten()
+            .map(|c| c.trim_start_matches("./").to_string())
+            .find(|c| known.contains(&c.as_str()))
+        {
+            return vec![hit];
+        }
+        return Vec::new();
+    }
+
+    let ext = match prover.as_str() {
+        "coq" => ".v",
+        _ => ".lean",
+    };
+    let components: Vec<&str> = import.split('.').collect();
+    for skip in 0..components.len() {
+        let suffix = format!("{}{ext}", components[skip..].join("/"));
+        let matches: Vec<String> = known
+            .iter()
+            .filter(|path| **path == suffix || path.ends_with(&format!("/{suffix}")))
+            .map(|path| path.to_string())
+            .collect();
+        if !matches.is_empty() {
+            return matches;
+        }
+    }
+    Vec::new()
+}
+
+/// Files to verify for `prover` after a push, or `None` to verify the
+/// whole repository. An empty list means the push touched nothing this
+/// prover needs to re-check. `routes` decides which files are `prover`'s.
+pub async fn plan_push(
+    store: &dyn Store,
+    repo_id: Uuid,
+    prover: &ProverKind,
+    routes: &ExtensionTable,
+    changes: &ChangedFiles,
+) -> Option<Vec<String>> {
+    if !supports(prover) || changes.all().any(is_project_file) {
+        return None;
+    }
+    let graph = match store.get_dependency_graph(repo_id, prover.clone()).await {
+        Ok(Some(record)) if !record.graph.is_empty() => record.graph,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::debug!("Dependency graph unavailable for {}: {}", repo_id, e);
+            return None;
+        }
+    };
+    Some(
+        graph
+            .affected(changes.all())
+            .into_iter()
+            .filter(|path| !changes.removed.contains(path))
+            .filter(|path| routes.routes_to(path, prover))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(prover: &str, files: &[(&str, &str)]) -> DependencyGraph {
+        let files: Vec<(String, String)> = files
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.to_string()))
+            .collect();
+        DependencyGraph::build(&ProverKind::new(prover), &files)
+    }
+
+    fn set(items: &[&str]) -> BTreeSet<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn coq_imports_resolve_through_logical_prefixes() {
+        let g = graph(
+            "coq",
+            &[
+                ("theories/Base.v", "(* Require Import Ignored. *)\nRequire Import Coq.Lists.List."),
+                ("theories/Nat.v", "From MyLib Require Import Base.\nDefinition x := 1."),
+                ("theories/Main.v", "Require Export MyLib.Nat Coq.Arith.Arith.\n"),
+                ("extra/Scratch.v", "Load \"theories/Main\"."),
+            ],
+        );
+        assert_eq!(g.imports["theories/Base.v"], set(&[]));
+        assert_eq!(g.imports["theories/Nat.v"], set(&["theories/Base.v"]));
+        assert_eq!(g.imports["theories/Main.v"], set(&["theories/Nat.v"]));
+        assert_eq!(g.imports["extra/Scratch.v"], set(&["theories/Main.v"]));
+    }
+
+    #[test]
+    fn lean_imports_stop_at_the_first_command() {
+        assert_eq!(
+            lean_imports("/- header -/\nimport Mathlib.Data.Nat Lib.Util -- deps\nimport Lib.More\n\ntheorem t : True := trivial\nimport Not.This"),
+            vec!["Mathlib.Data.Nat", "Lib.Util", "Lib.More"],
+        );
+    }
+
+    #[test]
+    fn metamath_includes_are_relative_to_the_includer() {
+        let g = graph(
+            "metamath",
+            &[("db/set.mm", "$[ logic.mm $]\n$[ missing.mm $]"), ("db/logic.mm", "$c ( ) $.")],
+        );
+        assert_eq!(g.imports["db/set.mm"], set(&["db/logic.mm"]));
+    }
+
+    #[test]
+    fn affected_follows_reverse_dependencies_transitively() {
+        let g = graph(
+            "lean",
+            &[
+                ("Lib/A.lean", ""),
+                ("Lib/B.lean", "import Lib.A"),
+                ("Lib/C.lean", "import Lib.B"),
+                ("Lib/D.lean", ""),
+            ],
+        );
+        assert_eq!(
+            g.affected(["Lib/A.lean"]),
+            set(&["Lib/A.lean", "Lib/B.lean", "Lib/C.lean"]),
+        );
+        assert_eq!(g.affected(["Lib/D.lean"]), set(&["Lib/D.lean"]));
+        assert_eq!(g.affected(["New.lean"]), set(&["New.lean"]), "unknown files still count");
+    }
+
+    #[test]
+    fn later_commits_win_in_changed_files() {
+        let mut changes = ChangedFiles::default();
+        let (none, a) = (Vec::<String>::new(), vec!["A.v".to_string()]);
+        changes.apply_commit(&none, &none, &a);
+        changes.apply_commit(&a, &none, &none);
+        assert_eq!(changes.modified, set(&["A.v"]));
+        assert!(changes.removed.is_empty());
+
+        assert!(is_project_file("_CoqProject"));
+        assert!(is_project_file("sub/lakefile.lean"));
+        assert!(!is_project_file("theories/Nat.v"));
+    }
+}
@@ -0,0 +1,404 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Structured diagnostics from raw prover output
+//!
+//! Provers report errors as free text. This module recovers
+//! `(file, line, column, severity, message)` tuples from the formats we
+//! see in practice, so results can be rendered per-line (SARIF, inline
+//! review comments) instead of as one opaque blob:
+//!
+//! | Prover            | Header shape                                             |
+//! |-------------------|----------------------------------------------------------|
+//! | Coq / Rocq        | `File "a.v", line 12, characters 4-10:` then `Error: …`  |
+//! | Lean 4, Idris 2   | `a.lean:12:4: error: …`                                  |
+//! | Agda              | `/abs/A.agda:12,5-10` then the message lines             |
+//! | Isabelle          | `*** msg` … `*** At command "by" (line 12 of "A.thy")`   |
+//! | SMT-LIB (Z3/CVC5) | `(error "line 3 column 10: unknown constant x")`         |
+//!
+//! Multi-line messages are collected until a blank line or the next
+//! header. Anything unparseable is skipped — an empty result just means
+//! the output carried no recognisable locations.
+
+pub mod sarif;
+
+use std::path::Path;
+
+/// How serious a diagnostic is. Ordered low → high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn from_keyword(word: &str) -> Option<Self> {
+        match word.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "info" | "information" | "note" => Some(Severity::Note),
+            _ => None,
+        }
+    }
+}
+
+/// One located message from a prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Path as the prover printed it; see [`attribute_to_files`] for
+    /// mapping onto repo-relative paths.
+    pub file: Option<String>,
+    /// 1-based line.
+    pub line: Option<u32>,
+    /// 1-based column.
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Parse every recognisable diagnostic out of `output`.
+pub fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let mut current: Option<Diagnostic> = None;
+
+    for raw in output.lines() {
+        let line = raw.trim_end();
+
+        if let Some(d) = parse_header(line) {
+            flush(&mut current, &mut out);
+            current = Some(d);
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            flush(&mut current, &mut out);
+            continue;
+        }
+
+        if let Some(d) = current.as_mut() {
+            let text = line.trim();
+            // Coq puts the severity on the first body line.
+            if d.message.is_empty() {
+                if let Some((kw, rest)) = text.split_once(':') {
+                    if let Some(sev) = Severity::from_keyword(kw) {
+                        d.severity = sev;
+                        d.message = rest.trim().to_string();
+                        continue;
+                    }
+                }
+            }
+            if !d.message.is_empty() {
+                d.message.push('\n');
+            }
+            d.message.push_str(text);
+        } else if let Some(d) = parse_isabelle_error(line) {
+            out.push(d);
+        }
+    }
+    flush(&mut current, &mut out);
+
+    merge_isabelle_locations(out)
+}
+
+fn flush(current: &mut Option<Diagnostic>, out: &mut Vec<Diagnostic>) {
+    if let Some(mut d) = current.take() {
+        if d.message.is_empty() {
+            d.message = "(no message)".to_string();
+        }
+        out.push(d);
+    }
+}
+
+/// Recognise the location-bearing first line of a diagnostic.
+fn parse_header(line: &str) -> Option<Diagnostic> {
+    parse_coq_header(line)
+        .or_else(|| parse_smtlib_error(line))
+        .or_else(|| parse_colon_header(line))
+        .or_else(|| parse_agda_header(line))
+}
+
+/// `File "path", line N, characters A-B:`
+fn parse_coq_header(line: &str) -> Option<Diagnostic> {
+    let rest = line.strip_prefix("File \"")?;
+    let (file, rest) = rest.split_once('"')?;
+    let rest = rest.strip_prefix(", line ")?;
+    let (line_no, rest) = rest.split_once(',').unwrap_or((rest.trim_end_matches(':'), ""));
+    let column = rest
+        .trim()
+        .strip_prefix("characters ")
+        .and_then(|c| c.split('-').next())
+        .and_then(|c| c.trim().parse::<u32>().ok())
+        .map(|c| c + 1); // Coq character offsets are 0-based
+    Some(Diagnostic {
+        file: Some(file.to_string()),
+        line: line_no.trim().parse().ok(),
+        column,
+        severity: Severity::Error,
+        message: String::new(),
+    })
+}
+
+/// `path:LINE:COL: severity: message` (Lean 4, Idris 2, GCC-style).
+fn parse_colon_header(line: &str) -> Option<Diagnostic> {
+    let mut parts = line.splitn(5, ':');
+    let file = parts.next()?.trim();
+    let line_no: u32 = parts.next()?.trim().parse().ok()?;
+    let column: u32 = parts.next()?.trim().parse().ok()?;
+    let severity = Severity::from_keyword(parts.next()?)?;
+    if file.is_empty() || !looks_like_path(file) {
+        return None;
+    }
+    Some(Diagnostic {
+        file: Some(file.to_string()),
+        line: Some(line_no),
+        // Lean reports 0-based columns.
+        column: Some(column + 1),
+        severity,
+        message: parts.next().unwrap_or("").trim().to_string(),
+    })
+}
+
+/// `path:LINE,COL-COL` (Agda). The message follows on later lines.
+fn parse_agda_header(line: &str) -> Option<Diagnostic> {
+    let (file, loc) = line.trim().rsplit_once(':')?;
+    if !file.ends_with(".agda") && !file.ends_with(".lagda") && !file.ends_with(".lagda.md") {
+        return None;
+    }
+    let (line_no, cols) = loc.split_once(',')?;
+    Some(Diagnostic {
+        file: Some(file.to_string()),
+        line: Some(line_no.trim().parse().ok()?),
+        column: cols.split('-').next().and_then(|c| c.trim().parse().ok()),
+        severity: Severity::Error,
+        message: String::new(),
+    })
+}
+
+/// `(error "line N column M: message")`
+fn parse_smtlib_error(line: &str) -> Option<Diagnostic> {
+    let inner = line.trim().strip_prefix("(error \"")?.strip_suffix("\")")?;
+    let (loc, message) = inner.split_once(':').unwrap_or(("", inner));
+    let mut words = loc.split_whitespace();
+    let (line_no, column) = match (words.next(), words.next(), words.next(), words.next()) {
+        (Some("line"), Some(l), Some("column"), Some(c)) => (l.parse().ok(), c.parse().ok()),
+        _ => (None, None),
+    };
+    let message = if line_no.is_some() { message } else { inner };
+    Some(Diagnostic {
+        file: None,
+        line: line_no,
+        column,
+        severity: Severity::Error,
+        message: message.trim().to_string(),
+    })
+}
+
+/// `*** message` line from Isabelle (location arrives on a later line).
+fn parse_isabelle_error(line: &str) -> Option<Diagnostic> {
+    let msg = line.strip_prefix("*** ")?.trim();
+    if msg.is_empty() {
+        return None;
+    }
+    Some(Diagnostic {
+        file: None,
+        line: None,
+        column: None,
+        severity: Severity::Error,
+        message: msg.to_string(),
+    })
+}
+
+/// Fold Isabelle's trailing `At command "…" (line N of "file")` into the
+/// preceding `***` messages of the same block.
+fn merge_isabelle_locations(diags: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut out: Vec<Diagnostic> = Vec::with_capacity(diags.len());
+    let mut pending: Vec<Diagnostic> = Vec::new();
+
+    for d in diags {
+        if d.file.is_some() || d.line.is_some() {
+            out.append(&mut pending);
+            out.push(d);
+            continue;
+        }
+        if let Some((line_no, file)) = parse_isabelle_location(&d.message) {
+            let block = std::mem::take(&mut pending);
+            if block.is_empty() {
+                continue;
+            }
+            let message = block
+                .into_iter()
+                .map(|b| b.message)
+                .collect::<Vec<_>>()
+                .join("\n");
+            out.push(Diagnostic {
+                file: Some(file),
+                line: Some(line_no),
+                column: None,
+                severity: Severity::Error,
+                message,
+            });
+        } else {
+            pending.push(d);
+        }
+    }
+    out.append(&mut pending);
+    out
+}
+
+fn parse_isabelle_location(message: &str) -> Option<(u32, String)> {
+    let rest = message.strip_prefix("At command ")?;
+    let (_, rest) = rest.split_once("(line ")?;
+    let (line_no, rest) = rest.split_once(" of \"")?;
+    let (file, _) = rest.split_once('"')?;
+    Some((line_no.trim().parse().ok()?, file.to_string()))
+}
+
+fn looks_like_path(s: &str) -> bool {
+    !s.contains(' ') && (s.contains('.') || s.contains('/'))
+}
+
+/// Map each diagnostic's `file` onto one of the job's repo-relative
+/// `files`.
+///
+/// A diagnostic path matches when it ends with the repo-relative path
+/// (absolute clone paths) or the repo-relative path ends with it (bare
+/// file names), whole components at a time. Diagnostics without a path
+/// are attributed to the single failing file when there is exactly one.
+/// Anything else — including paths outside the job's files, such as a
+/// dependency or the standard library — ends up with `file = None`.
+pub fn attribute_to_files(diags: &mut [Diagnostic], files: &[String], failing: &[String]) {
+    for d in diags.iter_mut() {
+        let matched = d.file.as_deref().and_then(|raw| {
+            let raw = raw.trim_start_matches("./");
+            files
+                .iter()
+                .find(|f| Path::new(raw).ends_with(f) || Path::new(f).ends_with(raw))
+                .cloned()
+        });
+        d.file = match (matched, failing) {
+            (Some(f), _) => Some(f),
+            (None, [only]) if d.file.is_none() => Some(only.clone()),
+            (None, _) => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coq_error_block() {
+        let out = "File \"./theories/Foo.v\", line 12, characters 4-10:\nError: The reference bar was not found\nin the current environment.\n";
+        let d = parse_diagnostics(out);
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].file.as_deref(), Some("./theories/Foo.v"));
+        assert_eq!(d[0].line, Some(12));
+        assert_eq!(d[0].column, Some(5));
+        assert_eq!(d[0].severity, Severity::Error);
+        assert!(d[0].message.starts_with("The reference bar"));
+        assert!(d[0].message.contains("current environment"));
+    }
+
+    #[test]
+    fn coq_warning_severity() {
+        let out = "File \"A.v\", line 3, characters 0-5:\nWarning: Notation overridden.\n";
+        assert_eq!(parse_diagnostics(out)[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn lean_errors_and_warnings() {
+        let out = "Main.lean:4:2: error: unsolved goals\n⊢ 1 = 2\nMain.lean:9:0: warning: declaration uses 'sorry'\n";
+        let d = parse_diagnostics(out);
+        assert_eq!(d.len(), 2);
+        assert_eq!((d[0].line, d[0].column), (Some(4), Some(3)));
+        assert!(d[0].message.contains("⊢ 1 = 2"));
+        assert_eq!(d[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn agda_header() {
+        let out = "/tmp/x/src/Nat.agda:7,14-19\nzero != suc n of type ℕ\nwhen checking that the expression refl has type zero ≡ suc n\n";
+        let d = parse_diagnostics(out);
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].line, Some(7));
+        assert_eq!(d[0].column, Some(14));
+        assert!(d[0].message.starts_with("zero != suc n"));
+    }
+
+    #[test]
+    fn isabelle_block_gets_location() {
+        let out = "*** Failed to finish proof:\n*** goal (1 subgoal):\n*** At command \"by\" (line 23 of \"/work/Foo.thy\")\n";
+        let d = parse_diagnostics(out);
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].file.as_deref(), Some("/work/Foo.thy"));
+        assert_eq!(d[0].line, Some(23));
+        assert!(d[0].message.contains("Failed to finish proof"));
+    }
+
+    #[test]
+    fn smtlib_error() {
+        let d = parse_diagnostics("(error \"line 3 column 10: unknown constant x\")");
+        assert_eq!(d.len(), 1);
+        assert_eq!((d[0].line, d[0].column), (Some(3), Some(10)));
+        assert_eq!(d[0].message, "unknown constant x");
+    }
+
+    #[test]
+    fn prose_is_not_a_diagnostic() {
+        assert!(parse_diagnostics("Verified 3 files\nAll goals closed: 42").is_empty());
+    }
+
+    #[test]
+    fn attribution_by_suffix_and_single_failure() {
+        let files = vec!["theories/Foo.v".to_string(), "theories/Bar.v".to_string()];
+        let mut d = vec![
+            Diagnostic {
+                file: Some("/tmp/clone/theories/Foo.v".into()),
+                line: Some(1),
+                column: None,
+                severity: Severity::Error,
+                message: "x".into(),
+            },
+            Diagnostic {
+                file: None,
+                line: Some(2),
+                column: None,
+                severity: Severity::Error,
+                message: "y".into(),
+            },
+        ];
+        attribute_to_files(&mut d, &files, &["theories/Bar.v".to_string()]);
+        assert_eq!(d[0].file.as_deref(), Some("theories/Foo.v"));
+        assert_eq!(d[1].file.as_deref(), Some("theories/Bar.v"));
+    }
+
+    #[test]
+    fn attribution_matches_whole_components_only() {
+        let files = vec!["Foo.v".to_string()];
+        let mut d = vec![Diagnostic {
+            file: Some("/clone/theories/MyFoo.v".into()),
+            line: Some(3),
+            column: None,
+            severity: Severity::Error,
+            message: "x".into(),
+        }];
+        attribute_to_files(&mut d, &files, &[]);
+        assert_eq!(d[0].file, None);
+    }
+
+    #[test]
+    fn paths_outside_the_job_are_not_given_to_the_failing_file() {
+        let files = vec!["theories/Foo.v".to_string()];
+        let mut d = vec![Diagnostic {
+            file: Some("/usr/lib/coq/theories/Init/Logic.v".into()),
+            line: Some(40),
+            column: None,
+            severity: Severity::Error,
+            message: "x".into(),
+        }];
+        attribute_to_files(&mut d, &files, &["theories/Foo.v".to_string()]);
+        assert_eq!(d[0].file, None);
+    }
+}
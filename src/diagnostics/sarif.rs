@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! SARIF 2.1.0 rendering of a job's proof diagnostics
+//!
+//! The log has one run per job. Locations use the `%SRCROOT%` base id and
+//! repo-relative URIs, which is what GitHub Code Scanning expects:
+//!
+//! ```text
+//! echidnabot sarif --job <id> -o proofs.sarif
+//! gh api repos/{owner}/{repo}/code-scanning/sarifs \
+//!     -f commit_sha=<sha> -f ref=refs/heads/main \
+//!     -f sarif="$(gzip -c proofs.sarif | base64 -w0)"
+//! ```
+//!
+//! Rules:
+//!
+//! | Rule id         | Level     | Emitted for                                   |
+//! |-----------------|-----------|-----------------------------------------------|
+//! | `proof-error`   | `error`   | Located prover error                          |
+//! | `proof-warning` | `warning` | Located prover warning                        |
+//! | `proof-note`    | `note`    | Located informational message                 |
+//! | `proof-failed`  | `error`   | Failing file with no located diagnostic (line 1) |
+//!
+//! Diagnostics that cannot be attributed to one of the job's files are
+//! reported as tool execution notifications rather than dropped.
+
+use serde_json::{json, Value};
+
+use super::{attribute_to_files, parse_diagnostics, Diagnostic, Severity};
+use crate::error::Result;
+use crate::scheduler::JobId;
+use crate::store::models::{FileTimingRecord, ProofJobRecord, ProofResultRecord};
+use crate::store::Store;
+
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Media type for HTTP responses.
+pub const SARIF_CONTENT_TYPE: &str = "application/sarif+json";
+
+const RULE_ERROR: &str = "proof-error";
+const RULE_WARNING: &str = "proof-warning";
+const RULE_NOTE: &str = "proof-note";
+const RULE_FAILED: &str = "proof-failed";
+
+/// Load a job, its result and file timings, and render them as SARIF.
+///
+/// `Ok(None)` when the job does not exist or has not produced a result yet.
+pub async fn build_job_sarif(store: &dyn Store, job_id: JobId) -> Result<Option<Value>> {
+    let Some(job) = store.get_job(job_id).await? else {
+        return Ok(None);
+    };
+    let Some(result) = store.get_result_for_job(job_id).await? else {
+        return Ok(None);
+    };
+    let timings = store.list_file_timings_for_job(job_id).await?;
+    Ok(Some(render_sarif(&job, &result, &timings)))
+}
+
+/// Render one job as a SARIF log.
+///
+/// File paths are taken from the job's `file_timings` (repo-relative) when
+/// present, falling back to the result's verified/failed lists.
+pub fn render_sarif(
+    job: &ProofJobRecord,
+    result: &ProofResultRecord,
+    timings: &[FileTimingRecord],
+) -> Value {
+    let (files, failing): (Vec<String>, Vec<String>) = if timings.is_empty() {
+        let mut files = result.verified_files.clone();
+        files.extend(result.failed_files.iter().cloned());
+        (files, result.failed_files.clone())
+    } else {
+        (
+            timings.iter().map(|t| t.file_path.clone()).collect(),
+            timings
+                .iter()
+                .filter(|t| !t.success)
+                .map(|t| t.file_path.clone())
+                .collect(),
+        )
+    };
+
+    let mut diags = parse_diagnostics(&result.prover_output);
+    attribute_to_files(&mut diags, &files, &failing);
+
+    let mut results: Vec<Value> = Vec::new();
+    let mut notifications: Vec<Value> = Vec::new();
+    for d in &diags {
+        match d.file {
+            Some(_) => results.push(diagnostic_result(d)),
+            None => notifications.push(json!({
+                "level": level(d.severity),
+                "message": { "text": d.message },
+            })),
+        }
+    }
+
+    // A failing file whose output we could not locate still deserves an
+    // alert, otherwise Code Scanning would show the file as clean.
+    for file in &failing {
+        let located = diags
+            .iter()
+            .any(|d| d.severity == Severity::Error && d.file.as_deref() == Some(file.as_str()));
+        if !located {
+            results.push(json!({
+                "ruleId": RULE_FAILED,
+                "level": "error",
+                "message": { "text": format!("{} failed to verify: {}", file, result.message) },
+                "locations": [location(file, Some(1), None)],
+            }));
+        }
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "echidnabot",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/hyperpolymath/echidnabot",
+                    "rules": rules(),
+                }
+            },
+            "automationDetails": {
+                "id": format!("echidnabot/{}/{}", job.prover.as_str(), job.id),
+            },
+            "invocations": [{
+                "executionSuccessful": result.success,
+                "toolExecutionNotifications": notifications,
+            }],
+            "properties": {
+                "commitSha": job.commit_sha,
+                "prover": job.prover.display_name(),
+                "durationMs": result.duration_ms,
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn diagnostic_result(d: &Diagnostic) -> Value {
+    let rule = match d.severity {
+        Severity::Error => RULE_ERROR,
+        Severity::Warning => RULE_WARNING,
+        Severity::Note => RULE_NOTE,
+    };
+    json!({
+        "ruleId": rule,
+        "level": level(d.severity),
+        "message": { "text": d.message },
+        "locations": [location(d.file.as_deref().unwrap_or_default(), d.line, d.column)],
+    })
+}
+
+fn location(file: &str, line: Option<u32>, column: Option<u32>) -> Value {
+    let mut region = serde_json::Map::new();
+    // SARIF requires startLine >= 1 when present.
+    region.insert("startLine".into(), json!(line.unwrap_or(1).max(1)));
+    if let Some(col) = column {
+        region.insert("startColumn".into(), json!(col.max(1)));
+    }
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": file, "uriBaseId": "%SRCROOT%" },
+            "region": region,
+        }
+    })
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    }
+}
+
+fn rules() -> Value {
+    json!([
+        {
+            "id": RULE_ERROR,
+            "shortDescription": { "text": "Proof error reported by the prover" },
+            "defaultConfiguration": { "level": "error" },
+        },
+        {
+            "id": RULE_WARNING,
+            "shortDescription": { "text": "Prover warning" },
+            "defaultConfiguration": { "level": "warning" },
+        },
+        {
+            "id": RULE_NOTE,
+            "shortDescription": { "text": "Prover information message" },
+            "defaultConfiguration": { "level": "note" },
+        },
+        {
+            "id": RULE_FAILED,
+            "shortDescription": { "text": "Proof file failed to verify" },
+            "defaultConfiguration": { "level": "error" },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use uuid::Uuid;
+
+    fn job() -> ProofJobRecord {
+        ProofJobRecord::from(ProofJob::new(
+            Uuid::new_v4(),
+            "abc123".to_string(),
+            ProverKind::new("coq"),
+            vec![],
+        ))
+    }
+
+    fn result(job: &ProofJobRecord, output: &str, failed: Vec<String>) -> ProofResultRecord {
+        ProofResultRecord::new(
+            JobId(job.id),
+            &JobResult {
+                success: failed.is_empty(),
+                message: "1 file failed".to_string(),
+                prover_output: output.to_string(),
+                duration_ms: 10,
+                verified_files: vec!["theories/Ok.v".to_string()],
+                failed_files: failed,
+                confidence: None,
+                axioms: None,
+                prover_version: None,
+                prover_args: vec![],
+                execution_path: None,
+            },
+        )
+    }
+
+    #[test]
+    fn located_error_becomes_result() {
+        let job = job();
+        let res = result(
+            &job,
+            "File \"/tmp/clone/theories/Bad.v\", line 7, characters 2-5:\nError: No such goal.\n",
+            vec!["theories/Bad.v".to_string()],
+        );
+        let sarif = render_sarif(&job, &res, &[]);
+        assert_eq!(sarif["version"], "2.1.0");
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1, "no fallback proof-failed when located");
+        assert_eq!(results[0]["ruleId"], "proof-error");
+        let loc = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(loc["artifactLocation"]["uri"], "theories/Bad.v");
+        assert_eq!(loc["region"]["startLine"], 7);
+        assert_eq!(loc["region"]["startColumn"], 3);
+    }
+
+    #[test]
+    fn unlocated_failure_gets_file_level_result() {
+        let job = job();
+        let res = result(&job, "Anomaly: uncaught exception.", vec!["theories/Bad.v".to_string()]);
+        let sarif = render_sarif(&job, &res, &[]);
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], "proof-failed");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            1
+        );
+    }
+}
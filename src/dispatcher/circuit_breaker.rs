@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Circuit breaker for one ECHIDNA endpoint
+//!
+//! Every ECHIDNA endpoint ([`crate::dispatcher::EchidnaClient`] talks to the
+//! primary `[echidna]` one and each `[[echidna.failover]]` in order) has a
+//! breaker:
+//!
+//! - **closed**: calls go through. `failure_threshold` failed calls in a
+//!   row open it;
+//! - **open**: calls skip the endpoint and go to the next one, for
+//!   `open_secs`;
+//! - **half-open**: after that, one probe call goes through. Success closes
+//!   the breaker, failure opens it for another `open_secs`.
+//!
+//! The health monitor's probes (`crate::api::health`) reach every endpoint
+//! whatever its state and count like calls, so a recovered backend closes
+//! without waiting for traffic. An outage of one backend then costs a
+//! handful of failed calls instead of every job queued behind it.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::CircuitBreakerConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// A probe call has been let through at `since`.
+    HalfOpen { since: Instant },
+}
+
+/// What a breaker is doing, for logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// One endpoint's breaker. Shared by every copy of the client.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &CircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            open_for: Duration::from_secs(config.open_secs),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match *self.state.lock().expect("breaker mutex poisoned") {
+            State::Closed { .. } => BreakerState::Closed,
+            State::Open { .. } => BreakerState::Open,
+            State::HalfOpen { .. } => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a call may go to the endpoint now. Past the open period this
+    /// lets one probe through; a probe that never reports back (its job was
+    /// cancelled) is replaced after another open period.
+    pub fn allow(&self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    fn allow_at(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().expect("breaker mutex poisoned");
+        let next_probe = match *state {
+            State::Closed { .. } => return true,
+            State::Open { until } => until,
+            State::HalfOpen { since } => since + self.open_for,
+        };
+        if now < next_probe {
+            return false;
+        }
+        *state = State::HalfOpen { since: now };
+        true
+    }
+
+    /// A call succeeded. `true` when this closed the breaker.
+    pub fn record_success(&self) -> bool {
+        let mut state = self.state.lock().expect("breaker mutex poisoned");
+        let was_closed = matches!(*state, State::Closed { .. });
+        *state = State::Closed { failures: 0 };
+        !was_closed
+    }
+
+    /// A call failed. `true` when this opened the breaker.
+    pub fn record_failure(&self) -> bool {
+        self.record_failure_at(Instant::now())
+    }
+
+    fn record_failure_at(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().expect("breaker mutex poisoned");
+        match *state {
+            State::Closed { failures } if failures + 1 < self.failure_threshold => {
+                *state = State::Closed { failures: failures + 1 };
+                false
+            }
+            State::Closed { .. } | State::HalfOpen { .. } => {
+                *state = State::Open { until: now + self.open_for };
+                true
+            }
+            State::Open { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(&CircuitBreakerConfig { failure_threshold: 3, open_secs: 30 })
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_and_probes_once() {
+        let breaker = breaker();
+        let t0 = Instant::now();
+        assert!(!breaker.record_failure_at(t0));
+        breaker.record_success();
+        assert!(!breaker.record_failure_at(t0));
+        assert!(!breaker.record_failure_at(t0));
+        assert!(breaker.allow_at(t0), "a success reset the count");
+        assert!(breaker.record_failure_at(t0));
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.allow_at(t0 + Duration::from_secs(29)));
+
+        // Half-open: exactly one probe.
+        let t1 = t0 + Duration::from_secs(30);
+        assert!(breaker.allow_at(t1));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(!breaker.allow_at(t1));
+
+        // A failed probe opens it again; a good one closes it.
+        assert!(breaker.record_failure_at(t1));
+        assert!(!breaker.allow_at(t1 + Duration::from_secs(1)));
+        assert!(breaker.allow_at(t1 + Duration::from_secs(30)));
+        assert!(breaker.record_success());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(!breaker.record_success());
+    }
+
+    #[test]
+    fn a_lost_probe_is_replaced() {
+        let breaker = breaker();
+        let t0 = Instant::now();
+        for _ in 0..3 {
+            breaker.record_failure_at(t0);
+        }
+        let t1 = t0 + Duration::from_secs(30);
+        assert!(breaker.allow_at(t1));
+        assert!(!breaker.allow_at(t1 + Duration::from_secs(29)));
+        assert!(breaker.allow_at(t1 + Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Project roots of discovered proof files
+//!
+//! A job without a file list verifies every file of its prover in the
+//! checkout. Besides the files, discovery records which project each one
+//! belongs to: the nearest directory above it holding one of the prover's
+//! project files ([`PROJECT_MARKERS`]), such as `_CoqProject` or
+//! `lakefile.lean`. Files outside any project have no root.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use super::ProverKind;
+
+/// Files marking a project root, per prover slug. A marker starting with
+/// `.` is a suffix (`Foo.agda-lib`), anything else an exact name.
+pub const PROJECT_MARKERS: &[(&str, &[&str])] = &[
+    ("agda", &[".agda-lib"]),
+    ("coq", &["_CoqProject", "dune-project"]),
+    ("lean", &["lakefile.lean", "lakefile.toml", "leanpkg.toml"]),
+    ("isabelle", &["ROOT", "ROOTS"]),
+    ("hol4", &["Holmakefile"]),
+];
+
+/// Project markers for `prover`; empty when it has no notion of one.
+pub fn markers_for(prover: &ProverKind) -> &'static [&'static str] {
+    PROJECT_MARKERS
+        .iter()
+        .find(|(slug, _)| *slug == prover.as_str())
+        .map(|(_, markers)| *markers)
+        .unwrap_or(&[])
+}
+
+fn is_marker(name: &str, markers: &[&str]) -> bool {
+    markers.iter().any(|marker| {
+        if marker.starts_with('.') {
+            name.len() > marker.len() && name.ends_with(marker)
+        } else {
+            name == *marker
+        }
+    })
+}
+
+fn has_marker(dir: &Path, markers: &[&str]) -> bool {
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_str().is_some_and(|name| is_marker(name, markers)))
+    })
+}
+
+/// The project root of the file at `file` (relative to `root`,
+/// `/`-separated): the nearest directory at or below `root` with one of
+/// `prover`'s markers, relative to `root` (`""` for `root` itself).
+pub fn project_root(root: &Path, file: &str, prover: &ProverKind) -> Option<String> {
+    let markers = markers_for(prover);
+    if markers.is_empty() {
+        return None;
+    }
+    let mut dir = Path::new(file).parent();
+    while let Some(rel) = dir {
+        if has_marker(&root.join(rel), markers) {
+            return Some(rel.to_string_lossy().replace('\\', "/"));
+        }
+        dir = rel.parent();
+    }
+    None
+}
+
+/// The distinct project roots of `files`, sorted.
+pub fn project_roots(root: &Path, files: &[String], prover: &ProverKind) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|file| project_root(root, file, prover))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_marked_directory_is_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("theories/sub")).unwrap();
+        std::fs::create_dir_all(root.join("vendor/lib")).unwrap();
+        std::fs::create_dir_all(root.join("agda/src")).unwrap();
+        std::fs::write(root.join("_CoqProject"), "-R theories T\n").unwrap();
+        std::fs::write(root.join("vendor/lib/_CoqProject"), "").unwrap();
+        std::fs::write(root.join("agda/stdlib.agda-lib"), "name: stdlib\n").unwrap();
+
+        let coq = ProverKind::new("coq");
+        assert_eq!(project_root(root, "theories/sub/A.v", &coq).as_deref(), Some(""));
+        assert_eq!(project_root(root, "vendor/lib/B.v", &coq).as_deref(), Some("vendor/lib"));
+        assert_eq!(
+            project_roots(
+                root,
+                &["theories/A.v".into(), "vendor/lib/B.v".into(), "theories/sub/C.v".into()],
+                &coq
+            ),
+            vec!["".to_string(), "vendor/lib".to_string()]
+        );
+
+        let agda = ProverKind::new("agda");
+        assert_eq!(project_root(root, "agda/src/Nat.agda", &agda).as_deref(), Some("agda"));
+        assert_eq!(project_root(root, "Top.agda", &agda), None);
+        assert_eq!(project_root(root, "goal.smt2", &ProverKind::new("z3")), None);
+    }
+}
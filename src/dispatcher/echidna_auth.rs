@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Authentication to ECHIDNA Core (`[echidna.auth]`)
+//!
+//! ECHIDNA is reached over plain HTTP(S), so anything that can reach its
+//! port could use it. `[echidna.auth]` makes [`super::EchidnaClient`]
+//! identify itself, to the primary and every failover endpoint alike:
+//!
+//! - `client_cert` / `client_key`: a PEM certificate and key presented in
+//!   the TLS handshake (mTLS), with `ca_cert` to trust a private CA for
+//!   ECHIDNA's own certificate;
+//! - `api_key`: sent as `Authorization: Bearer <key>`, or bare in
+//!   `api_key_header`. It may be a secret reference
+//!   ([`crate::config::secrets`]).
+//!
+//! Certificates and keys get rotated under a running daemon (cert-manager,
+//! Vault agent, a renewed file secret), so `serve` runs
+//! [`run_credential_refresh`]: every `reload_interval_secs` the files are
+//! read again and the key taken from the current configuration (refreshed
+//! on SIGHUP and by `[secrets] refresh_interval_secs`). When anything
+//! changed a new HTTP client is built and swapped in; requests in flight
+//! finish on the old one. Credentials that fail to load — say a
+//! certificate replaced before its key — leave the previous ones in use
+//! until the next check.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Client, Identity};
+
+use super::EchidnaClient;
+use crate::config::{EchidnaAuthConfig, EchidnaConfig, SharedConfig};
+use crate::error::{Error, Result};
+use crate::shutdown::ShutdownSignal;
+
+/// What the credentials were built from; a change means a rebuild.
+#[derive(PartialEq, Eq)]
+struct Source {
+    api_key: Option<String>,
+    api_key_header: Option<String>,
+    /// Certificate chain followed by its key, as [`Identity::from_pem`]
+    /// takes them.
+    identity: Option<Vec<u8>>,
+    ca: Option<Vec<u8>>,
+}
+
+fn read(setting: &str, path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| Error::Config(format!("[echidna.auth] {} {}: {}", setting, path, e)))
+}
+
+impl Source {
+    fn read(auth: &EchidnaAuthConfig) -> Result<Self> {
+        let identity = match (&auth.client_cert, &auth.client_key) {
+            (Some(cert), Some(key)) => {
+                let mut pem = read("client_cert", cert)?;
+                pem.push(b'\n');
+                pem.extend(read("client_key", key)?);
+                Some(pem)
+            }
+            (None, None) => None,
+            _ => {
+                return Err(Error::Config(
+                    "[echidna.auth] client_cert and client_key must be set together".to_string(),
+                ))
+            }
+        };
+        let ca = auth.ca_cert.as_deref().map(|path| read("ca_cert", path)).transpose()?;
+        Ok(Self {
+            api_key: auth.api_key.clone(),
+            api_key_header: auth.api_key_header.clone(),
+            identity,
+            ca,
+        })
+    }
+
+    fn header(&self) -> Result<Option<(HeaderName, HeaderValue)>> {
+        let Some(key) = &self.api_key else {
+            return Ok(None);
+        };
+        let invalid =
+            |what: &str| Error::Config(format!("[echidna.auth] {} is not a valid header", what));
+        let (name, value) = match &self.api_key_header {
+            Some(header) => (
+                HeaderName::from_bytes(header.as_bytes()).map_err(|_| invalid("api_key_header"))?,
+                HeaderValue::from_str(key),
+            ),
+            None => (AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", key))),
+        };
+        let mut value = value.map_err(|_| invalid("api_key"))?;
+        value.set_sensitive(true);
+        Ok(Some((name, value)))
+    }
+
+    fn client(&self, timeout: Duration) -> Result<Client> {
+        let mut builder = Client::builder().timeout(timeout);
+        if let Some(pem) = &self.identity {
+            let identity = Identity::from_pem(pem).map_err(|e| {
+                Error::Config(format!("[echidna.auth] client_cert / client_key: {}", e))
+            })?;
+            builder = builder.identity(identity);
+        }
+        if let Some(pem) = &self.ca {
+            let roots = Certificate::from_pem_bundle(pem)
+                .map_err(|e| Error::Config(format!("[echidna.auth] ca_cert: {}", e)))?;
+            if roots.is_empty() {
+                return Err(Error::Config("[echidna.auth] ca_cert holds no certificate".into()));
+            }
+            for root in roots {
+                builder = builder.add_root_certificate(root);
+            }
+        }
+        builder.build().map_err(|e| Error::Config(format!("ECHIDNA HTTP client: {}", e)))
+    }
+}
+
+struct Loaded {
+    source: Source,
+    client: Client,
+    header: Option<(HeaderName, HeaderValue)>,
+}
+
+impl Loaded {
+    fn build(source: Source, timeout: Duration) -> Result<Self> {
+        Ok(Self { header: source.header()?, client: source.client(timeout)?, source })
+    }
+}
+
+/// The HTTP client and API key ECHIDNA requests go out with. Shared by
+/// every copy of an [`EchidnaClient`].
+pub struct Credentials {
+    timeout: Duration,
+    current: ArcSwap<Loaded>,
+}
+
+impl Credentials {
+    /// Load `config.auth`, with `config.timeout_secs` for every request.
+    pub fn load(config: &EchidnaConfig) -> Result<Self> {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let loaded = Loaded::build(Source::read(&config.auth)?, timeout)?;
+        Ok(Self { timeout, current: ArcSwap::from_pointee(loaded) })
+    }
+
+    /// No client certificate and no key.
+    pub fn none(timeout_secs: u64) -> Self {
+        Self::load(&EchidnaConfig { timeout_secs, ..EchidnaConfig::default() })
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Re-read `auth`, swapping in new credentials if anything changed.
+    /// Returns whether it did; on error the current ones stay.
+    pub fn refresh(&self, auth: &EchidnaAuthConfig) -> Result<bool> {
+        let source = Source::read(auth)?;
+        if source == self.current.load().source {
+            return Ok(false);
+        }
+        self.current.store(Arc::new(Loaded::build(source, self.timeout)?));
+        Ok(true)
+    }
+
+    /// A request on the current client, carrying the API key if any.
+    pub fn request(
+        &self,
+        method: reqwest::Method,
+        url: impl reqwest::IntoUrl,
+    ) -> reqwest::RequestBuilder {
+        let current = self.current.load();
+        let request = current.client.request(method, url);
+        match &current.header {
+            Some((name, value)) => request.header(name.clone(), value.clone()),
+            None => request,
+        }
+    }
+}
+
+impl EchidnaAuthConfig {
+    /// Load the files and check the key; run at startup and on reload.
+    pub fn validate(&self) -> Result<()> {
+        let source = Source::read(self)?;
+        source.header()?;
+        source.client(Duration::from_secs(1)).map(|_| ())
+    }
+}
+
+/// Pick up rotated certificates and keys until shutdown.
+pub async fn run_credential_refresh(
+    echidna: Arc<EchidnaClient>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("ECHIDNA credential refresh observed shutdown signal — stopping");
+                return;
+            }
+        }
+        let current = config.load_full();
+        match echidna.refresh_credentials(&current.echidna.auth) {
+            Ok(true) => tracing::info!("Reloaded ECHIDNA credentials"),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Keeping the current ECHIDNA credentials: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(credentials: &Credentials, name: &str) -> Option<String> {
+        let request = credentials.request(reqwest::Method::POST, "http://echidna.test/graphql");
+        let request = request.build().unwrap();
+        request.headers().get(name).map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn api_key_goes_in_the_configured_header() {
+        let mut config = EchidnaConfig::default();
+        assert_eq!(header(&Credentials::load(&config).unwrap(), "authorization"), None);
+
+        config.auth.api_key = Some("k1".into());
+        let credentials = Credentials::load(&config).unwrap();
+        assert_eq!(header(&credentials, "authorization").as_deref(), Some("Bearer k1"));
+
+        config.auth.api_key_header = Some("X-API-Key".into());
+        assert!(credentials.refresh(&config.auth).unwrap());
+        assert_eq!(header(&credentials, "x-api-key").as_deref(), Some("k1"));
+        assert_eq!(header(&credentials, "authorization"), None);
+        assert!(!credentials.refresh(&config.auth).unwrap(), "unchanged");
+    }
+
+    #[test]
+    fn broken_credentials_are_rejected_and_keep_the_old_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("client.crt");
+        std::fs::write(&cert, "not a certificate").unwrap();
+        let cert = cert.to_string_lossy().to_string();
+
+        let unpaired = EchidnaAuthConfig { client_cert: Some(cert.clone()), ..Default::default() };
+        assert!(unpaired.validate().is_err());
+        let missing =
+            EchidnaAuthConfig { ca_cert: Some("/nonexistent.pem".into()), ..Default::default() };
+        assert!(missing.validate().is_err());
+        let garbage = EchidnaAuthConfig {
+            client_cert: Some(cert.clone()),
+            client_key: Some(cert),
+            ..Default::default()
+        };
+        assert!(garbage.validate().is_err());
+        let bad_header = EchidnaAuthConfig {
+            api_key: Some("k".into()),
+            api_key_header: Some("bad header".into()),
+            ..Default::default()
+        };
+        assert!(bad_header.validate().is_err());
+
+        let mut config = EchidnaConfig::default();
+        config.auth.api_key = Some("k1".into());
+        let credentials = Credentials::load(&config).unwrap();
+        assert!(credentials.refresh(&garbage).is_err());
+        assert_eq!(header(&credentials, "authorization").as_deref(), Some("Bearer k1"));
+    }
+}
@@ -2,46 +2,162 @@
 // Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Client for communicating with ECHIDNA Core
-
-use reqwest::Client;
+//!
+//! Calls go to the `[echidna]` endpoint, or to the first
+//! `[[echidna.failover]]` endpoint after it whose circuit breaker is
+//! closed (see [`super::circuit_breaker`]). A call that fails on one
+//! endpoint is retried on the next before its error is returned.
+//!
+//! [`EchidnaClient::verify_proofs`] checks many files of a job at once:
+//! `[echidna] batch_size` files to a GraphQL request (one aliased
+//! `verifyProof` each, so any ECHIDNA serving `verifyProof` takes it) and
+//! `batch_concurrency` requests in flight. Over REST it sends a request per
+//! file, `batch_concurrency` at a time.
+//!
+//! [`EchidnaClient::verify_archive`] instead sends a whole project, packed
+//! by [`super::project_archive`], for ECHIDNA to verify the listed files
+//! in: `verifyArchive` over GraphQL, `POST /api/verify/archive` over REST,
+//! the archive base64-encoded either way.
+//!
+//! Every request carries the `[echidna.auth]` client certificate and API
+//! key, if set (see [`super::echidna_auth`]).
+
+use base64::Engine;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
+use super::circuit_breaker::CircuitBreaker;
+use super::echidna_auth::Credentials;
+use super::project_archive::ProjectArchive;
 use super::{ProofResult, ProofStatus, ProverKind, TacticSuggestion};
-use crate::config::{EchidnaApiMode, EchidnaConfig};
+use crate::config::{EchidnaApiMode, EchidnaAuthConfig, EchidnaConfig};
 use crate::error::{Error, Result};
 use crate::trust::{
     axiom_tracker::AxiomTracker,
     confidence::assess_confidence,
 };
-use tracing::warn;
+use tracing::{info, warn};
 
-/// Client for ECHIDNA Core GraphQL API
-pub struct EchidnaClient {
-    client: Client,
+/// One ECHIDNA Core backend and its breaker.
+struct Backend {
     endpoint: String,
     rest_endpoint: String,
+    breaker: CircuitBreaker,
+}
+
+impl Backend {
+    fn rest_url(&self, path: &str) -> String {
+        let base = self.rest_endpoint.trim_end_matches('/');
+        format!("{}{}", base, path)
+    }
+
+    /// Feed one call's outcome to the breaker, logging transitions.
+    fn record(&self, ok: bool) {
+        if ok {
+            if self.breaker.record_success() {
+                info!("ECHIDNA at {} is back: circuit closed", self.endpoint);
+            }
+        } else if self.breaker.record_failure() {
+            warn!("ECHIDNA at {} is failing: circuit open, failing over", self.endpoint);
+        }
+    }
+}
+
+/// Client for ECHIDNA Core GraphQL API
+pub struct EchidnaClient {
+    /// Shared by copies, so a refresh reaches all of them.
+    credentials: Arc<Credentials>,
+    /// The primary endpoint, then the failovers. Copies made with
+    /// `with_timeout` / `with_flags` share the breakers.
+    backends: Arc<Vec<Backend>>,
     timeout: Duration,
     mode: EchidnaApiMode,
+    /// Repository prover flags sent with each verify call.
+    flags: Vec<String>,
+    batch_size: usize,
+    batch_concurrency: usize,
 }
 
 impl EchidnaClient {
     /// Create a new ECHIDNA client
     pub fn new(config: &EchidnaConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
-            .expect("Failed to create HTTP client");
+        // `serve` validates `[echidna.auth]` first; elsewhere a broken
+        // setting is logged and requests go out without credentials, for
+        // ECHIDNA to refuse.
+        let credentials = Credentials::load(config).unwrap_or_else(|e| {
+            warn!("ECHIDNA credentials not loaded: {}", e);
+            Credentials::none(config.timeout_secs)
+        });
+
+        let primary = (config.endpoint.clone(), config.rest_endpoint.clone());
+        let failover = config
+            .failover
+            .iter()
+            .map(|backend| (backend.endpoint.clone(), backend.rest_endpoint.clone()));
+        let backends = std::iter::once(primary)
+            .chain(failover)
+            .map(|(endpoint, rest_endpoint)| Backend {
+                endpoint,
+                rest_endpoint,
+                breaker: CircuitBreaker::new(&config.circuit_breaker),
+            })
+            .collect();
 
         Self {
-            client,
-            endpoint: config.endpoint.clone(),
-            rest_endpoint: config.rest_endpoint.clone(),
+            credentials: Arc::new(credentials),
+            backends: Arc::new(backends),
             timeout: Duration::from_secs(config.timeout_secs),
             mode: config.mode,
+            flags: Vec::new(),
+            batch_size: config.batch_size.max(1),
+            batch_concurrency: config.batch_concurrency.max(1),
+        }
+    }
+
+    /// Copy of this client with a different verification timeout. Shares
+    /// the connection pool; the per-request timeout overrides the
+    /// client-wide one.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            credentials: self.credentials.clone(),
+            backends: self.backends.clone(),
+            timeout,
+            mode: self.mode,
+            flags: self.flags.clone(),
+            batch_size: self.batch_size,
+            batch_concurrency: self.batch_concurrency,
         }
     }
 
+    /// Copy of this client that sends `flags` (checked with
+    /// [`crate::executor::prover_args::validate`]) with each verify call.
+    pub fn with_flags(&self, flags: Vec<String>) -> Self {
+        Self {
+            credentials: self.credentials.clone(),
+            backends: self.backends.clone(),
+            timeout: self.timeout,
+            mode: self.mode,
+            flags,
+            batch_size: self.batch_size,
+            batch_concurrency: self.batch_concurrency,
+        }
+    }
+
+    /// A request carrying the current trace context, so ECHIDNA's spans
+    /// join the job's trace.
+    fn request(&self, method: reqwest::Method, url: impl reqwest::IntoUrl) -> reqwest::RequestBuilder {
+        crate::observability::propagate(self.credentials.request(method, url))
+    }
+
+    /// Re-read `[echidna.auth]`; see [`Credentials::refresh`].
+    pub fn refresh_credentials(&self, auth: &EchidnaAuthConfig) -> Result<bool> {
+        self.credentials.refresh(auth)
+    }
+
     /// Verify a proof using ECHIDNA Core
     #[tracing::instrument(
         name = "echidna.verify",
@@ -53,19 +169,137 @@ pub fn new(config: &EchidnaConfig) -> Self {
         )
     )]
     pub async fn verify_proof(&self, prover: &ProverKind, content: &str) -> Result<ProofResult> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        self.failover(|at| self.verify_proof_at(at, prover, content)).await
+    }
+
+    async fn verify_proof_at(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        content: &str,
+    ) -> Result<ProofResult> {
         match self.mode {
-            EchidnaApiMode::Graphql => self.verify_proof_graphql(prover, content).await,
-            EchidnaApiMode::Rest => self.verify_proof_rest(prover, content).await,
-            EchidnaApiMode::Auto => match self.verify_proof_graphql(prover, content).await {
+            EchidnaApiMode::Graphql => self.verify_proof_graphql(at, prover, content).await,
+            EchidnaApiMode::Rest => self.verify_proof_rest(at, prover, content).await,
+            EchidnaApiMode::Auto => match self.verify_proof_graphql(at, prover, content).await {
                 Ok(result) => Ok(result),
                 Err(err) => {
                     warn!("GraphQL verify failed, falling back to REST: {}", err);
-                    self.verify_proof_rest(prover, content).await
+                    self.verify_proof_rest(at, prover, content).await
                 }
             },
         }
     }
 
+    /// Verify several files of one prover, batched (see the module docs).
+    /// One result per file, in the order of `contents`; every file of a
+    /// batch that failed gets its error.
+    #[tracing::instrument(
+        name = "echidna.verify_batch",
+        skip(self, contents),
+        fields(prover = %prover, files = contents.len(), api_mode = ?self.mode)
+    )]
+    pub async fn verify_proofs(
+        &self,
+        prover: &ProverKind,
+        contents: &[&str],
+    ) -> Vec<Result<ProofResult>> {
+        if matches!(self.mode, EchidnaApiMode::Rest) || self.batch_size == 1 {
+            return stream::iter(contents)
+                .map(|content| self.verify_proof(prover, content))
+                .buffered(self.batch_concurrency)
+                .collect()
+                .await;
+        }
+        let batches: Vec<Vec<Result<ProofResult>>> = stream::iter(contents.chunks(self.batch_size))
+            .map(|batch| self.verify_batch(prover, batch))
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await;
+        batches.into_iter().flatten().collect()
+    }
+
+    async fn verify_batch(&self, prover: &ProverKind, batch: &[&str]) -> Vec<Result<ProofResult>> {
+        #[cfg(feature = "chaos")]
+        if let Err(err) = crate::chaos::inject(crate::chaos::Target::Echidna).await {
+            let message = err.to_string();
+            return batch.iter().map(|_| Err(Error::Echidna(message.clone()))).collect();
+        }
+        match self.failover(|at| self.verify_batch_graphql(at, prover, batch)).await {
+            Ok(results) => results.into_iter().map(Ok).collect(),
+            // Auto mode: file by file, so each can still fall back to REST.
+            Err(err) if matches!(self.mode, EchidnaApiMode::Auto) => {
+                warn!("Batched verify failed, verifying {} file(s) singly: {}", batch.len(), err);
+                let mut results = Vec::with_capacity(batch.len());
+                for content in batch {
+                    results.push(self.verify_proof(prover, content).await);
+                }
+                results
+            }
+            Err(err) => {
+                let message = err.to_string();
+                batch.iter().map(|_| Err(Error::Echidna(message.clone()))).collect()
+            }
+        }
+    }
+
+    /// Verify the job's files of a packed project server-side. One result
+    /// per file of `archive.files`, in order; an answer missing any of them
+    /// is an error.
+    #[tracing::instrument(
+        name = "echidna.verify_archive",
+        skip(self, archive),
+        fields(
+            prover = %prover,
+            archive_bytes = archive.bytes.len(),
+            files = archive.files.len(),
+            api_mode = ?self.mode,
+        )
+    )]
+    pub async fn verify_archive(
+        &self,
+        prover: &ProverKind,
+        archive: &ProjectArchive,
+    ) -> Result<Vec<ProofResult>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&archive.bytes);
+        let files: Vec<&str> = archive.files.iter().map(|(_, inner)| inner.as_str()).collect();
+        self.failover(|at| self.verify_archive_at(at, prover, &encoded, &files)).await
+    }
+
+    async fn verify_archive_at(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        archive: &str,
+        files: &[&str],
+    ) -> Result<Vec<ProofResult>> {
+        let graphql = || self.verify_archive_graphql(at, prover, archive, files);
+        let rest = || self.verify_archive_rest(at, prover, archive, files);
+        let mut answered = match self.mode {
+            EchidnaApiMode::Graphql => graphql().await?,
+            EchidnaApiMode::Rest => rest().await?,
+            EchidnaApiMode::Auto => match graphql().await {
+                Ok(results) => results,
+                Err(err) => {
+                    warn!("GraphQL verifyArchive failed, falling back to REST: {}", err);
+                    rest().await?
+                }
+            },
+        };
+        files
+            .iter()
+            .map(|file| {
+                answered
+                    .remove(*file)
+                    .ok_or_else(|| Error::Echidna(format!("No result for {} in the archive", file)))
+            })
+            .collect()
+    }
+
     /// Request tactic suggestions from ECHIDNA's Julia ML component
     #[tracing::instrument(
         name = "echidna.suggest",
@@ -82,37 +316,61 @@ pub async fn suggest_tactics(
         prover: &ProverKind,
         context: &str,
         goal_state: &str,
+    ) -> Result<Vec<TacticSuggestion>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        self.failover(|at| self.suggest_tactics_at(at, prover, context, goal_state))
+            .await
+    }
+
+    async fn suggest_tactics_at(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        context: &str,
+        goal_state: &str,
     ) -> Result<Vec<TacticSuggestion>> {
         match self.mode {
             EchidnaApiMode::Graphql => {
-                self.suggest_tactics_graphql(prover, context, goal_state).await
+                self.suggest_tactics_graphql(at, prover, context, goal_state).await
+            }
+            EchidnaApiMode::Rest => {
+                self.suggest_tactics_rest(at, prover, context, goal_state).await
             }
-            EchidnaApiMode::Rest => self.suggest_tactics_rest(prover, context, goal_state).await,
             EchidnaApiMode::Auto => {
                 match self
-                    .suggest_tactics_graphql(prover, context, goal_state)
+                    .suggest_tactics_graphql(at, prover, context, goal_state)
                     .await
                 {
                     Ok(result) => Ok(result),
                     Err(err) => {
                         warn!("GraphQL suggest failed, falling back to REST: {}", err);
-                        self.suggest_tactics_rest(prover, context, goal_state).await
+                        self.suggest_tactics_rest(at, prover, context, goal_state).await
                     }
                 }
             }
         }
     }
 
-    /// Check if ECHIDNA Core is available and healthy
+    /// Check if ECHIDNA Core is available and healthy: `true` when any
+    /// endpoint is. Every endpoint is probed whatever its breaker says, and
+    /// the outcome feeds the breaker, so a recovered one closes again.
     pub async fn health_check(&self) -> Result<bool> {
-        match self.mode {
-            EchidnaApiMode::Graphql => self.health_check_graphql().await,
-            EchidnaApiMode::Rest => self.health_check_rest().await,
-            EchidnaApiMode::Auto => match self.health_check_graphql().await {
-                Ok(true) => Ok(true),
-                _ => self.health_check_rest().await,
-            },
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        let mut healthy = false;
+        for at in self.backends.iter() {
+            let up = match self.mode {
+                EchidnaApiMode::Graphql => self.health_check_graphql(at).await?,
+                EchidnaApiMode::Rest => self.health_check_rest(at).await?,
+                EchidnaApiMode::Auto => {
+                    self.health_check_graphql(at).await? || self.health_check_rest(at).await?
+                }
+            };
+            at.record(up);
+            healthy |= up;
         }
+        Ok(healthy)
     }
 
     /// Check prover availability
@@ -122,51 +380,126 @@ pub async fn health_check(&self) -> Result<bool> {
         fields(prover = %prover, api_mode = ?self.mode)
     )]
     pub async fn prover_status(&self, prover: &ProverKind) -> Result<ProverStatus> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        self.failover(|at| self.prover_status_at(at, prover)).await
+    }
+
+    async fn prover_status_at(&self, at: &Backend, prover: &ProverKind) -> Result<ProverStatus> {
         match self.mode {
-            EchidnaApiMode::Graphql => self.prover_status_graphql(prover).await,
-            EchidnaApiMode::Rest => self.prover_status_rest(prover).await,
-            EchidnaApiMode::Auto => match self.prover_status_graphql(prover).await {
+            EchidnaApiMode::Graphql => self.prover_status_graphql(at, prover).await,
+            EchidnaApiMode::Rest => self.prover_status_rest(at, prover).await,
+            EchidnaApiMode::Auto => match self.prover_status_graphql(at, prover).await {
                 Ok(result) => Ok(result),
                 Err(err) => {
                     warn!("GraphQL prover_status failed, falling back to REST: {}", err);
-                    self.prover_status_rest(prover).await
+                    self.prover_status_rest(at, prover).await
                 }
             },
         }
     }
 
-    fn rest_url(&self, path: &str) -> String {
-        let base = self.rest_endpoint.trim_end_matches('/');
-        format!("{}{}", base, path)
+    /// The version of `prover` ECHIDNA runs, if it reports one (see
+    /// `crate::toolchain`).
+    #[tracing::instrument(
+        name = "echidna.version",
+        skip(self),
+        fields(prover = %prover, api_mode = ?self.mode)
+    )]
+    pub async fn prover_version(&self, prover: &ProverKind) -> Result<Option<String>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Echidna).await?;
+        self.failover(|at| self.prover_version_at(at, prover)).await
+    }
+
+    async fn prover_version_at(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+    ) -> Result<Option<String>> {
+        match self.mode {
+            EchidnaApiMode::Graphql => self.prover_version_graphql(at, prover).await,
+            EchidnaApiMode::Rest => self.prover_version_rest(at, prover).await,
+            EchidnaApiMode::Auto => match self.prover_version_graphql(at, prover).await {
+                Ok(Some(version)) => Ok(Some(version)),
+                Ok(None) => self.prover_version_rest(at, prover).await,
+                Err(err) => {
+                    warn!("GraphQL prover_version failed, falling back to REST: {}", err);
+                    self.prover_version_rest(at, prover).await
+                }
+            },
+        }
+    }
+
+    /// Run `call` against the first endpoint whose breaker lets it through,
+    /// moving on to the next when it fails. The last error is returned when
+    /// every endpoint failed, or a breaker error when none was tried.
+    async fn failover<'a, T, F, Fut>(&'a self, call: F) -> Result<T>
+    where
+        F: Fn(&'a Backend) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for at in self.backends.iter() {
+            if !at.breaker.allow() {
+                continue;
+            }
+            match call(at).await {
+                Ok(value) => {
+                    at.record(true);
+                    return Ok(value);
+                }
+                Err(err) => {
+                    warn!("ECHIDNA call to {} failed: {}", at.endpoint, err);
+                    at.record(false);
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            Error::Echidna("no ECHIDNA endpoint available: every circuit breaker is open".into())
+        }))
     }
 
     async fn verify_proof_graphql(
         &self,
+        at: &Backend,
         prover: &ProverKind,
         content: &str,
     ) -> Result<ProofResult> {
-        let query = GraphQLRequest {
-            query: r#"
-                mutation VerifyProof($prover: String!, $content: String!) {
-                    verifyProof(prover: $prover, content: $content) {
+        // `flags` is only sent when there are some, so servers without
+        // the argument keep working.
+        let (flags_param, flags_arg) = if self.flags.is_empty() {
+            ("", "")
+        } else {
+            (", $flags: [String!]", ", flags: $flags")
+        };
+        let mut query = GraphQLRequest {
+            query: format!(
+                r#"
+                mutation VerifyProof($prover: String!, $content: String!{}) {{
+                    verifyProof(prover: $prover, content: $content{}) {{
                         status
                         message
                         proverOutput
                         durationMs
                         artifacts
-                    }
-                }
-            "#
-            .to_string(),
+                    }}
+                }}
+            "#,
+                flags_param, flags_arg
+            ),
             variables: serde_json::json!({
                 "prover": format!("{:?}", prover).to_lowercase(),
                 "content": content
             }),
         };
+        if !self.flags.is_empty() {
+            query.variables["flags"] = serde_json::json!(self.flags);
+        }
 
         let response = self
-            .client
-            .post(&self.endpoint)
+            .request(reqwest::Method::POST, &at.endpoint)
             .json(&query)
             .timeout(self.timeout)
             .send()
@@ -192,31 +525,145 @@ async fn verify_proof_graphql(
         let data = gql_response
             .data
             .ok_or_else(|| Error::Echidna("No data in response".to_string()))?;
+        Ok(proof_result(prover, data.verify_proof))
+    }
 
-        let status = parse_proof_status(&data.verify_proof.status);
-        let prover_output = data.verify_proof.prover_output;
-        let artifacts = data.verify_proof.artifacts;
-        let has_cert = artifacts.iter().any(|a| {
-            a.ends_with(".alethe")
-                || a.ends_with(".lrat")
-                || a.ends_with(".drat")
-                || a.ends_with(".tstp")
+    /// One request verifying every file of `batch`, as `f0`, `f1`, ...
+    async fn verify_batch_graphql(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        batch: &[&str],
+    ) -> Result<Vec<ProofResult>> {
+        let mut variables = serde_json::json!({
+            "prover": format!("{:?}", prover).to_lowercase(),
         });
-        let axioms = AxiomTracker::scan(prover, &prover_output);
-        let confidence = assess_confidence(prover, status, has_cert, 1);
-        Ok(ProofResult {
-            status,
-            message: data.verify_proof.message,
-            prover_output,
-            duration_ms: data.verify_proof.duration_ms,
-            artifacts,
-            confidence: Some(confidence),
-            axioms: Some(axioms),
-        })
+        for (i, content) in batch.iter().enumerate() {
+            variables[format!("c{}", i)] = serde_json::json!(content);
+        }
+        if !self.flags.is_empty() {
+            variables["flags"] = serde_json::json!(self.flags);
+        }
+        let query = GraphQLRequest {
+            query: batch_query(batch.len(), !self.flags.is_empty()),
+            variables,
+        };
+
+        // ECHIDNA may check the files one after another.
+        let timeout = self.timeout.saturating_mul(batch.len() as u32);
+        let response = self
+            .request(reqwest::Method::POST, &at.endpoint)
+            .json(&query)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(Error::Http)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Echidna(format!(
+                "ECHIDNA returned status {}",
+                response.status()
+            )));
+        }
+
+        let gql_response: GraphQLResponse<HashMap<String, VerifyProofData>> =
+            response.json().await.map_err(Error::Http)?;
+
+        if let Some(errors) = gql_response.errors {
+            return Err(Error::Echidna(
+                errors.into_iter().map(|e| e.message).collect::<Vec<_>>().join(", "),
+            ));
+        }
+
+        let mut data = gql_response
+            .data
+            .ok_or_else(|| Error::Echidna("No data in response".to_string()))?;
+        (0..batch.len())
+            .map(|i| {
+                data.remove(&format!("f{}", i))
+                    .map(|result| proof_result(prover, result))
+                    .ok_or_else(|| Error::Echidna(format!("No result for file {} of the batch", i)))
+            })
+            .collect()
+    }
+
+    async fn verify_archive_graphql(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        archive: &str,
+        files: &[&str],
+    ) -> Result<HashMap<String, ProofResult>> {
+        let (flags_param, flags_arg) = if self.flags.is_empty() {
+            ("", "")
+        } else {
+            (", $flags: [String!]", ", flags: $flags")
+        };
+        let mut query = GraphQLRequest {
+            query: format!(
+                r#"
+                mutation VerifyArchive($prover: String!, $archive: String!, $files: [String!]!{}) {{
+                    verifyArchive(prover: $prover, archive: $archive, files: $files{}) {{
+                        path
+                        status
+                        message
+                        proverOutput
+                        durationMs
+                        artifacts
+                    }}
+                }}
+            "#,
+                flags_param, flags_arg
+            ),
+            variables: serde_json::json!({
+                "prover": format!("{:?}", prover).to_lowercase(),
+                "archive": archive,
+                "files": files,
+            }),
+        };
+        if !self.flags.is_empty() {
+            query.variables["flags"] = serde_json::json!(self.flags);
+        }
+
+        // The files may be checked one after another.
+        let timeout = self.timeout.saturating_mul(files.len().max(1) as u32);
+        let response = self
+            .request(reqwest::Method::POST, &at.endpoint)
+            .json(&query)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(Error::Http)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Echidna(format!(
+                "ECHIDNA returned status {}",
+                response.status()
+            )));
+        }
+
+        let gql_response: GraphQLResponse<VerifyArchiveResponse> =
+            response.json().await.map_err(Error::Http)?;
+
+        if let Some(errors) = gql_response.errors {
+            return Err(Error::Echidna(
+                errors.into_iter().map(|e| e.message).collect::<Vec<_>>().join(", "),
+            ));
+        }
+
+        let data = gql_response
+            .data
+            .ok_or_else(|| Error::Echidna("No data in response".to_string()))?;
+        Ok(data
+            .verify_archive
+            .into_iter()
+            .map(|file| (file.path, proof_result(prover, file.result)))
+            .collect())
     }
 
     async fn suggest_tactics_graphql(
         &self,
+        at: &Backend,
         prover: &ProverKind,
         context: &str,
         goal_state: &str,
@@ -240,8 +687,7 @@ async fn suggest_tactics_graphql(
         };
 
         let response = self
-            .client
-            .post(&self.endpoint)
+            .request(reqwest::Method::POST, &at.endpoint)
             .json(&query)
             .timeout(self.timeout)
             .send()
@@ -279,15 +725,14 @@ async fn suggest_tactics_graphql(
             .collect())
     }
 
-    async fn health_check_graphql(&self) -> Result<bool> {
+    async fn health_check_graphql(&self, at: &Backend) -> Result<bool> {
         let query = GraphQLRequest {
             query: "{ __typename }".to_string(),
             variables: serde_json::json!({}),
         };
 
         let response = self
-            .client
-            .post(&self.endpoint)
+            .request(reqwest::Method::POST, &at.endpoint)
             .json(&query)
             .timeout(Duration::from_secs(5))
             .send()
@@ -299,7 +744,11 @@ async fn health_check_graphql(&self) -> Result<bool> {
         }
     }
 
-    async fn prover_status_graphql(&self, prover: &ProverKind) -> Result<ProverStatus> {
+    async fn prover_status_graphql(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+    ) -> Result<ProverStatus> {
         let query = GraphQLRequest {
             query: r#"
                 query ProverStatus($prover: String!) {
@@ -316,8 +765,7 @@ async fn prover_status_graphql(&self, prover: &ProverKind) -> Result<ProverStatu
         };
 
         let response = self
-            .client
-            .post(&self.endpoint)
+            .request(reqwest::Method::POST, &at.endpoint)
             .json(&query)
             .timeout(Duration::from_secs(10))
             .send()
@@ -338,15 +786,69 @@ async fn prover_status_graphql(&self, prover: &ProverKind) -> Result<ProverStatu
         }
     }
 
-    async fn verify_proof_rest(&self, prover: &ProverKind, content: &str) -> Result<ProofResult> {
+    async fn prover_version_graphql(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+    ) -> Result<Option<String>> {
+        let query = GraphQLRequest {
+            query: r#"
+                query ProverVersion($prover: String!) {
+                    proverStatus(prover: $prover) {
+                        version
+                    }
+                }
+            "#
+            .to_string(),
+            variables: serde_json::json!({
+                "prover": format!("{:?}", prover).to_lowercase()
+            }),
+        };
+
+        let response = self
+            .request(reqwest::Method::POST, &at.endpoint)
+            .json(&query)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(Error::Http)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Echidna(format!(
+                "ECHIDNA returned status {}",
+                response.status()
+            )));
+        }
+
+        let gql_response: GraphQLResponse<ProverVersionResponse> =
+            response.json().await.map_err(Error::Http)?;
+
+        if let Some(errors) = gql_response.errors {
+            return Err(Error::Echidna(
+                errors.into_iter().map(|e| e.message).collect::<Vec<_>>().join(", "),
+            ));
+        }
+
+        Ok(gql_response
+            .data
+            .and_then(|data| data.prover_status.version)
+            .filter(|version| !version.trim().is_empty()))
+    }
+
+    async fn verify_proof_rest(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        content: &str,
+    ) -> Result<ProofResult> {
         let request = RestVerifyRequest {
             prover: prover_to_echidna_name(prover),
             content: content.to_string(),
+            flags: self.flags.clone(),
         };
 
         let response = self
-            .client
-            .post(self.rest_url("/api/verify"))
+            .request(reqwest::Method::POST, at.rest_url("/api/verify"))
             .json(&request)
             .timeout(self.timeout)
             .send()
@@ -361,28 +863,50 @@ async fn verify_proof_rest(&self, prover: &ProverKind, content: &str) -> Result<
         }
 
         let data: RestVerifyResponse = response.json().await.map_err(Error::Http)?;
-        let status = if data.valid { ProofStatus::Verified } else { ProofStatus::Failed };
         // REST endpoint returns no raw output; axiom scan over empty string = clean.
-        let prover_output = String::new();
-        let axioms = AxiomTracker::scan(prover, &prover_output);
-        let confidence = assess_confidence(prover, status, false, 1);
-        Ok(ProofResult {
-            status,
-            message: if data.valid {
-                "Proof verified successfully".to_string()
-            } else {
-                "Proof verification failed".to_string()
-            },
-            prover_output,
-            duration_ms: 0,
-            artifacts: Vec::new(),
-            confidence: Some(confidence),
-            axioms: Some(axioms),
-        })
+        Ok(rest_result(prover, data.valid, String::new()))
+    }
+
+    async fn verify_archive_rest(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+        archive: &str,
+        files: &[&str],
+    ) -> Result<HashMap<String, ProofResult>> {
+        let request = RestArchiveRequest {
+            prover: prover_to_echidna_name(prover),
+            archive: archive.to_string(),
+            files: files.iter().map(|file| file.to_string()).collect(),
+            flags: self.flags.clone(),
+        };
+
+        let response = self
+            .request(reqwest::Method::POST, at.rest_url("/api/verify/archive"))
+            .json(&request)
+            .timeout(self.timeout.saturating_mul(files.len().max(1) as u32))
+            .send()
+            .await
+            .map_err(Error::Http)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Echidna(format!(
+                "ECHIDNA REST returned status {}",
+                response.status()
+            )));
+        }
+
+        let data: RestArchiveResponse = response.json().await.map_err(Error::Http)?;
+        Ok(data
+            .results
+            .into_iter()
+            .map(|file| (file.path, rest_result(prover, file.valid, file.output)))
+            .collect())
     }
 
     async fn suggest_tactics_rest(
         &self,
+        at: &Backend,
         prover: &ProverKind,
         context: &str,
         goal_state: &str,
@@ -400,8 +924,7 @@ async fn suggest_tactics_rest(
         };
 
         let response = self
-            .client
-            .post(self.rest_url("/api/suggest"))
+            .request(reqwest::Method::POST, at.rest_url("/api/suggest"))
             .json(&request)
             .timeout(self.timeout)
             .send()
@@ -427,10 +950,9 @@ async fn suggest_tactics_rest(
             .collect())
     }
 
-    async fn health_check_rest(&self) -> Result<bool> {
+    async fn health_check_rest(&self, at: &Backend) -> Result<bool> {
         let response = self
-            .client
-            .get(self.rest_url("/api/health"))
+            .request(reqwest::Method::GET, at.rest_url("/api/health"))
             .timeout(Duration::from_secs(5))
             .send()
             .await;
@@ -441,10 +963,9 @@ async fn health_check_rest(&self) -> Result<bool> {
         }
     }
 
-    async fn prover_status_rest(&self, prover: &ProverKind) -> Result<ProverStatus> {
+    async fn prover_status_rest(&self, at: &Backend, prover: &ProverKind) -> Result<ProverStatus> {
         let response = self
-            .client
-            .get(self.rest_url("/api/provers"))
+            .request(reqwest::Method::GET, at.rest_url("/api/provers"))
             .timeout(Duration::from_secs(10))
             .send()
             .await
@@ -467,6 +988,35 @@ async fn prover_status_rest(&self, prover: &ProverKind) -> Result<ProverStatus>
             ProverStatus::Unavailable
         })
     }
+
+    async fn prover_version_rest(
+        &self,
+        at: &Backend,
+        prover: &ProverKind,
+    ) -> Result<Option<String>> {
+        let response = self
+            .request(reqwest::Method::GET, at.rest_url("/api/provers"))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(Error::Http)?;
+
+        if !response.status().is_success() {
+            return Err(Error::Echidna(format!(
+                "ECHIDNA REST returned status {}",
+                response.status()
+            )));
+        }
+
+        let data: RestProversResponse = response.json().await.map_err(Error::Http)?;
+        let target = prover_to_echidna_name(prover).to_lowercase();
+        Ok(data
+            .provers
+            .into_iter()
+            .find(|info| info.name.to_lowercase() == target)
+            .and_then(|info| info.version)
+            .filter(|version| !version.trim().is_empty()))
+    }
 }
 
 // =============================================================================
@@ -477,6 +1027,8 @@ async fn prover_status_rest(&self, prover: &ProverKind) -> Result<ProverStatus>
 struct RestVerifyRequest {
     prover: String,
     content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -488,6 +1040,28 @@ struct RestVerifyResponse {
     tactics_used: usize,
 }
 
+#[derive(Serialize)]
+struct RestArchiveRequest {
+    prover: String,
+    archive: String,
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RestArchiveResponse {
+    results: Vec<RestArchiveResult>,
+}
+
+#[derive(Deserialize)]
+struct RestArchiveResult {
+    path: String,
+    valid: bool,
+    #[serde(default)]
+    output: String,
+}
+
 #[derive(Serialize)]
 struct RestSuggestRequest {
     prover: String,
@@ -512,12 +1086,76 @@ struct RestProverInfo {
     tier: u8,
     #[allow(dead_code)]
     complexity: u8,
+    #[serde(default)]
+    version: Option<String>,
 }
 
 fn prover_to_echidna_name(prover: &ProverKind) -> String {
     prover.display_name().to_string()
 }
 
+/// A `verifyProof` answer, with the trust-bridge data assessed.
+fn proof_result(prover: &ProverKind, data: VerifyProofData) -> ProofResult {
+    let status = parse_proof_status(&data.status);
+    let has_cert = data.artifacts.iter().any(|a| {
+        a.ends_with(".alethe")
+            || a.ends_with(".lrat")
+            || a.ends_with(".drat")
+            || a.ends_with(".tstp")
+    });
+    let axioms = AxiomTracker::scan(prover, &data.prover_output);
+    let confidence = assess_confidence(prover, status, has_cert, 1);
+    ProofResult {
+        status,
+        message: data.message,
+        prover_output: data.prover_output,
+        duration_ms: data.duration_ms,
+        artifacts: data.artifacts,
+        confidence: Some(confidence),
+        axioms: Some(axioms),
+    }
+}
+
+/// A REST verdict, with the trust-bridge data assessed.
+fn rest_result(prover: &ProverKind, valid: bool, prover_output: String) -> ProofResult {
+    let status = if valid { ProofStatus::Verified } else { ProofStatus::Failed };
+    let axioms = AxiomTracker::scan(prover, &prover_output);
+    let confidence = assess_confidence(prover, status, false, 1);
+    ProofResult {
+        status,
+        message: if valid {
+            "Proof verified successfully".to_string()
+        } else {
+            "Proof verification failed".to_string()
+        },
+        prover_output,
+        duration_ms: 0,
+        artifacts: Vec::new(),
+        confidence: Some(confidence),
+        axioms: Some(axioms),
+    }
+}
+
+/// The mutation verifying `files` files in one request: `$c<i>` is the
+/// content of file `i`, answered as `f<i>`.
+fn batch_query(files: usize, flags: bool) -> String {
+    let (flags_param, flags_arg) =
+        if flags { (", $flags: [String!]", ", flags: $flags") } else { ("", "") };
+    let params: String = (0..files).map(|i| format!(", $c{}: String!", i)).collect();
+    let fields: String = (0..files)
+        .map(|i| {
+            format!(
+                "f{i}: verifyProof(prover: $prover, content: $c{i}{flags_arg}) \
+                 {{ status message proverOutput durationMs artifacts }}\n"
+            )
+        })
+        .collect();
+    format!(
+        "mutation VerifyProofs($prover: String!{}{}) {{\n{}}}",
+        params, flags_param, fields
+    )
+}
+
 // =============================================================================
 // GraphQL Types
 // =============================================================================
@@ -556,6 +1194,19 @@ struct VerifyProofData {
     artifacts: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct VerifyArchiveResponse {
+    #[serde(rename = "verifyArchive")]
+    verify_archive: Vec<ArchiveFileData>,
+}
+
+#[derive(Deserialize)]
+struct ArchiveFileData {
+    path: String,
+    #[serde(flatten)]
+    result: VerifyProofData,
+}
+
 #[derive(Deserialize)]
 struct SuggestTacticsResponse {
     #[serde(rename = "suggestTactics")]
@@ -582,6 +1233,18 @@ struct ProverStatusData {
     message: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct ProverVersionResponse {
+    #[serde(rename = "proverStatus")]
+    prover_status: ProverVersionData,
+}
+
+#[derive(Deserialize)]
+struct ProverVersionData {
+    #[serde(default)]
+    version: Option<String>,
+}
+
 /// Prover availability status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProverStatus {
@@ -619,6 +1282,74 @@ fn test_prover_from_extension() {
         assert_eq!(ProverKind::from_extension(".xyz"), None);
     }
 
+    #[tokio::test]
+    async fn an_open_circuit_skips_a_dead_endpoint() {
+        let config = EchidnaConfig {
+            endpoint: "http://127.0.0.1:1/graphql".into(),
+            rest_endpoint: "http://127.0.0.1:1".into(),
+            mode: EchidnaApiMode::Rest,
+            circuit_breaker: crate::config::CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_secs: 60,
+            },
+            ..EchidnaConfig::default()
+        };
+        let client = EchidnaClient::new(&config);
+        let z3 = ProverKind::new("z3");
+        for _ in 0..2 {
+            assert!(matches!(client.prover_version(&z3).await, Err(Error::Http(_))));
+        }
+        let err = client.prover_version(&z3).await.unwrap_err();
+        assert!(err.to_string().contains("every circuit breaker is open"), "{}", err);
+        // Copies share the breaker.
+        let copy = client.with_timeout(Duration::from_secs(1));
+        assert!(copy.prover_version(&z3).await.unwrap_err().to_string().contains("circuit"));
+        assert!(!client.health_check().await.unwrap());
+    }
+
+    #[test]
+    fn a_batch_aliases_one_verify_per_file() {
+        let query = batch_query(2, true);
+        assert!(query.starts_with(
+            "mutation VerifyProofs($prover: String!, $c0: String!, $c1: String!, $flags: [String!])"
+        ));
+        assert!(query.contains("f0: verifyProof(prover: $prover, content: $c0, flags: $flags)"));
+        assert!(query.contains("f1: verifyProof(prover: $prover, content: $c1, flags: $flags)"));
+        assert!(!batch_query(1, false).contains("flags"));
+    }
+
+    #[test]
+    fn archive_answers_carry_their_path() {
+        let data: VerifyArchiveResponse = serde_json::from_value(serde_json::json!({
+            "verifyArchive": [{
+                "path": "sub/B.v",
+                "status": "VERIFIED",
+                "message": "ok",
+                "proverOutput": "",
+                "durationMs": 12,
+                "artifacts": []
+            }]
+        }))
+        .unwrap();
+        let file = &data.verify_archive[0];
+        assert_eq!((file.path.as_str(), file.result.duration_ms), ("sub/B.v", 12));
+    }
+
+    #[tokio::test]
+    async fn every_file_of_a_failed_batch_gets_an_error() {
+        let config = EchidnaConfig {
+            endpoint: "http://127.0.0.1:1/graphql".into(),
+            rest_endpoint: "http://127.0.0.1:1".into(),
+            mode: EchidnaApiMode::Graphql,
+            batch_size: 2,
+            ..EchidnaConfig::default()
+        };
+        let client = EchidnaClient::new(&config);
+        let results = client.verify_proofs(&ProverKind::new("lean"), &["a", "b", "c"]).await;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.is_err()));
+    }
+
     #[test]
     fn test_prover_tier() {
         assert_eq!(ProverKind::new("metamath").tier(), 2);
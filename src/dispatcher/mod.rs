@@ -3,9 +3,15 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Prover dispatcher - communicates with ECHIDNA Core
 
+pub mod circuit_breaker;
+pub mod discovery;
+pub mod echidna_auth;
 pub mod echidna_client;
+pub mod project_archive;
+pub mod routing;
 
 pub use echidna_client::EchidnaClient;
+pub use routing::ExtensionTable;
 
 use serde::{Deserialize, Serialize};
 
@@ -40,6 +46,43 @@ pub enum ProofStatus {
     Unknown,
 }
 
+/// Where a job's proofs were checked (`[executor] dispatch`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecutionPath {
+    /// By ECHIDNA Core.
+    Echidna,
+    /// In the local sandbox.
+    Local,
+    /// In the local sandbox because ECHIDNA could not be reached.
+    LocalFallback,
+}
+
+impl ExecutionPath {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Echidna => "echidna",
+            Self::Local => "local",
+            Self::LocalFallback => "local-fallback",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "echidna" => Some(Self::Echidna),
+            "local" => Some(Self::Local),
+            "local-fallback" => Some(Self::LocalFallback),
+            _ => None,
+        }
+    }
+
+    /// The path recorded for a job so far at `job` once a file took
+    /// `self`: a job that fell back for any file is `LocalFallback`.
+    pub fn merge(self, job: Option<Self>) -> Self {
+        job.map_or(self, |path| self.max(path))
+    }
+}
+
 /// Prover slug referencing ECHIDNA's full 113-prover backend set.
 ///
 /// # Migration from enum to slug-based addressing (ADR-CART-003, 2026-04-25)
@@ -73,19 +116,11 @@ pub fn as_str(&self) -> &str {
         &self.0
     }
 
-    /// Detect prover from file extension (classic 12 only; others return None)
+    /// Detect prover from file extension (classic 12 only; others return
+    /// None). Of two provers sharing an extension, the first in slug
+    /// order; see [`ExtensionTable`] for all of them.
     pub fn from_extension(ext: &str) -> Option<Self> {
-        let ext = ext.to_lowercase();
-        let ext = if ext.starts_with('.') { ext } else { format!(".{}", ext) };
-
-        CLASSIC_PROVERS.iter().find_map(|(slug, _)| {
-            let prover = ProverSlug::new(*slug);
-            if prover.file_extensions().contains(&ext.as_str()) {
-                Some(prover)
-            } else {
-                None
-            }
-        })
+        ExtensionTable::builtin().provers_for_extension(ext).first().cloned()
     }
 
     /// Human-readable name for classic provers (classic 12), others return slug
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Project archives for ECHIDNA (`[echidna.archive]`)
+//!
+//! `verifyProof` takes one file's content, so a file importing its
+//! siblings fails on ECHIDNA however sound the development is. With
+//! `[echidna.archive] enabled`, a job's files instead go up as one gzipped
+//! tar of their project, verified server-side with
+//! [`EchidnaClient::verify_archive`]:
+//!
+//! - the project is the nearest directory above all of the job's files
+//!   holding a project file ([`PROJECT_FILES`], or an `*.agda-lib` /
+//!   `*.ipkg`), else the repository root;
+//! - files and directories matching an `exclude` glob (VCS metadata and
+//!   build outputs by default) are left out, as are symlinks;
+//! - a project over `max_bytes` of file content or `max_files` files is
+//!   not sent: its files are checked one by one as before.
+//!
+//! Entries are added in path order with fixed metadata, so the same
+//! checkout always gives the same archive.
+//!
+//! [`EchidnaClient::verify_archive`]: super::EchidnaClient::verify_archive
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::config::ArchiveUploadConfig;
+use crate::error::{Error, Result};
+use crate::modes::manifest::glob_match;
+
+/// Files marking a project's root.
+pub const PROJECT_FILES: &[&str] = &[
+    "_CoqProject",
+    "dune-project",
+    "lakefile.lean",
+    "lakefile.toml",
+    "ROOT",
+    "ROOTS",
+];
+
+/// A job's project, packed for upload.
+#[derive(Debug, Clone)]
+pub struct ProjectArchive {
+    /// The packed directory, relative to the checkout; `""` is the root.
+    pub root: String,
+    /// Gzipped tar.
+    pub bytes: Vec<u8>,
+    /// Files in the archive.
+    pub entries: usize,
+    /// The job's files: as listed, and as named in the archive.
+    pub files: Vec<(String, String)>,
+}
+
+fn is_project_dir(dir: &Path) -> bool {
+    if PROJECT_FILES.iter().any(|name| dir.join(name).is_file()) {
+        return true;
+    }
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.ends_with(".agda-lib") || name.ends_with(".ipkg")
+        })
+    })
+}
+
+/// The directory packed for `files` (checkout-relative): the nearest one
+/// above all of them holding a project file, else the root (`""`).
+pub fn project_root(checkout: &Path, files: &[String]) -> String {
+    let mut common: Option<PathBuf> = None;
+    for file in files {
+        let parent = Path::new(file).parent().unwrap_or(Path::new("")).to_path_buf();
+        common = Some(match common {
+            None => parent,
+            Some(dir) => dir
+                .components()
+                .zip(parent.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    let mut dir = common.as_deref();
+    while let Some(rel) = dir.filter(|rel| !rel.as_os_str().is_empty()) {
+        if is_project_dir(&checkout.join(rel)) {
+            return rel.to_string_lossy().replace('\\', "/");
+        }
+        dir = rel.parent();
+    }
+    String::new()
+}
+
+/// Checkout-relative files under `root` to pack, in path order. Fails
+/// past the configured limits.
+fn collect(checkout: &Path, root: &str, config: &ArchiveUploadConfig) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut bytes = 0u64;
+    let mut pending = vec![root.to_string()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(checkout.join(&dir))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let rel = if dir.is_empty() { name } else { format!("{}/{}", dir, name) };
+            if config.exclude.iter().any(|pattern| glob_match(pattern, &rel)) {
+                continue;
+            }
+            let kind = entry.file_type()?;
+            if kind.is_dir() {
+                pending.push(rel);
+            } else if kind.is_file() {
+                bytes += entry.metadata()?.len();
+                files.push(rel);
+            }
+        }
+        if files.len() > config.max_files {
+            return Err(Error::InvalidInput(format!(
+                "project ./{} has more than {} files",
+                root, config.max_files
+            )));
+        }
+        if bytes > config.max_bytes {
+            return Err(Error::InvalidInput(format!(
+                "project ./{} holds more than {} bytes",
+                root, config.max_bytes
+            )));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Pack the project of `files` (checkout-relative) under `checkout`.
+pub fn build(
+    checkout: &Path,
+    files: &[String],
+    config: &ArchiveUploadConfig,
+) -> Result<ProjectArchive> {
+    if let Some(file) = files.iter().find(|file| Path::new(file).is_absolute()) {
+        return Err(Error::InvalidInput(format!("{} is outside the checkout", file)));
+    }
+    let root = project_root(checkout, files);
+    let packed = collect(checkout, &root, config)?;
+
+    let within = |file: &str| -> String {
+        if root.is_empty() {
+            file.to_string()
+        } else {
+            file.strip_prefix(root.as_str())
+                .and_then(|f| f.strip_prefix('/'))
+                .unwrap_or(file)
+                .to_string()
+        }
+    };
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    tar.mode(tar::HeaderMode::Deterministic);
+    for file in &packed {
+        tar.append_path_with_name(checkout.join(file), within(file))?;
+    }
+    let mut gz = tar.into_inner()?;
+    gz.flush()?;
+    let bytes = gz.finish()?;
+
+    Ok(ProjectArchive {
+        entries: packed.len(),
+        files: files.iter().map(|file| (file.clone(), within(file))).collect(),
+        root,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+
+    fn checkout(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn names(archive: &ProjectArchive) -> Vec<String> {
+        let mut tar = tar::Archive::new(GzDecoder::new(archive.bytes.as_slice()));
+        tar.entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn packs_the_nearest_project_without_excluded_files() {
+        let dir = checkout(&[
+            ("README.md", "x"),
+            ("theories/_CoqProject", "-R . T"),
+            ("theories/A.v", "Definition a := 1."),
+            ("theories/sub/B.v", "Require Import T.A."),
+            ("theories/sub/B.vo", "compiled"),
+            ("theories/_build/C.v", "stale"),
+        ]);
+        let config = ArchiveUploadConfig::default();
+        let files = vec!["theories/sub/B.v".to_string()];
+        let archive = build(dir.path(), &files, &config).unwrap();
+
+        assert_eq!(archive.root, "theories");
+        assert_eq!(names(&archive), ["A.v", "_CoqProject", "sub/B.v"]);
+        assert_eq!(archive.files, [("theories/sub/B.v".to_string(), "sub/B.v".to_string())]);
+        let again = build(dir.path(), &files, &config).unwrap();
+        assert_eq!(archive.bytes, again.bytes, "deterministic");
+    }
+
+    #[test]
+    fn falls_back_to_the_root_and_enforces_limits() {
+        let dir = checkout(&[("a/X.v", "x"), ("b/Y.v", "yy")]);
+        let files = vec!["a/X.v".to_string(), "b/Y.v".to_string()];
+        assert_eq!(project_root(dir.path(), &files), "");
+
+        let small = ArchiveUploadConfig { max_bytes: 2, ..Default::default() };
+        assert!(build(dir.path(), &files, &small).is_err());
+        let few = ArchiveUploadConfig { max_files: 1, ..Default::default() };
+        assert!(build(dir.path(), &files, &few).is_err());
+    }
+}
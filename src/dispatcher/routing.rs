@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! File extension → prover routing
+//!
+//! Which prover checks a file is decided by its extension. The built-in
+//! [`ExtensionTable`] is built once, on first use, from every classic
+//! prover's extensions and shared by all handlers; a repository extends or
+//! overrides it from its manifest with [`ExtensionTable::for_repo`].
+//!
+//! Some extensions are claimed twice. `.smt2` belongs to both Z3 and CVC5,
+//! and each of them checks it. `.v` is also Verilog, and `.ml`, `.lisp`
+//! and `.sml` are ordinary OCaml, Lisp and Standard ML source, so a file
+//! with one of these is only verified if its content looks like the
+//! prover's ([`ExtensionTable::accepts`], see [`SNIFFERS`]). A repository
+//! can decide instead, which also turns the sniffing off for that
+//! extension:
+//!
+//! ```toml
+//! [provers.extensions]
+//! ".v" = "none"        # Verilog here, not Coq
+//! ".ml" = "hol-light"  # every .ml file is HOL Light
+//! ".smt2" = "cvc5"     # CVC5 only
+//! ".tla" = "tlaps"     # a prover without built-in extensions
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
+
+use super::ProverKind;
+
+/// Override value that takes an extension away from every prover.
+pub const NO_PROVER: &str = "none";
+
+/// Bytes of a file [`Sniffer`]s look at.
+const SNIFF_BYTES: usize = 64 * 1024;
+
+/// Content check for an extension a prover shares with another language.
+#[derive(Debug)]
+pub struct Sniffer {
+    pub extension: &'static str,
+    pub prover: &'static str,
+    /// The other language using the extension.
+    pub language: &'static str,
+    /// Any of these means the file is the prover's.
+    pub prover_markers: &'static [&'static str],
+    /// Without a prover marker, any of these means it is not.
+    pub language_markers: &'static [&'static str],
+    /// Verdict when neither kind of marker is present.
+    pub default: bool,
+}
+
+impl Sniffer {
+    /// Whether `content` looks like a proof for [`Self::prover`].
+    pub fn matches(&self, content: &str) -> bool {
+        let mut end = content.len().min(SNIFF_BYTES);
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let head = &content[..end];
+        if self.prover_markers.iter().any(|m| head.contains(m)) {
+            return true;
+        }
+        if self.language_markers.iter().any(|m| head.contains(m)) {
+            return false;
+        }
+        self.default
+    }
+}
+
+/// Sniffers for the shared extensions. `.v` is mostly Coq, so it stays
+/// Coq unless it reads as Verilog; an OCaml, SML or Lisp file has to show
+/// signs of HOL Light, HOL4 or ACL2 to be verified.
+pub const SNIFFERS: &[Sniffer] = &[
+    Sniffer {
+        extension: ".v",
+        prover: "coq",
+        language: "Verilog",
+        prover_markers: &[
+            "Qed.",
+            "Defined.",
+            "Admitted.",
+            "Proof.",
+            "Require Import",
+            "Require Export",
+        ],
+        language_markers: &["endmodule", "`timescale", "always @", "posedge", "negedge"],
+        default: true,
+    },
+    Sniffer {
+        extension: ".ml",
+        prover: "hol-light",
+        language: "OCaml",
+        prover_markers: &[
+            "prove(",
+            "prove (",
+            "_TAC",
+            "new_definition",
+            "new_recursive_definition",
+            "needs \"",
+        ],
+        language_markers: &[],
+        default: false,
+    },
+    Sniffer {
+        extension: ".sml",
+        prover: "hol4",
+        language: "Standard ML",
+        prover_markers: &[
+            "new_theory",
+            "export_theory",
+            "store_thm",
+            "save_thm",
+            "open HolKernel",
+            "QED",
+        ],
+        language_markers: &[],
+        default: false,
+    },
+    Sniffer {
+        extension: ".lisp",
+        prover: "acl2",
+        language: "Common Lisp",
+        prover_markers: &[
+            "(defthm",
+            "(DEFTHM",
+            "(in-package \"ACL2\")",
+            "(include-book",
+            "(in-theory",
+        ],
+        language_markers: &[],
+        default: false,
+    },
+];
+
+static BUILTIN: LazyLock<ExtensionTable> = LazyLock::new(|| {
+    let mut table = ExtensionTable::default();
+    for prover in ProverKind::classic_all() {
+        for ext in prover.file_extensions() {
+            table.routes.entry(ext.to_string()).or_default().push(prover.clone());
+        }
+    }
+    table.recount();
+    table
+});
+
+/// Lowercased extension (leading dot included) → the provers that check
+/// files ending in it.
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionTable {
+    routes: HashMap<String, Vec<ProverKind>>,
+    /// Dots in the longest extension (2 for `.lagda.md`), bounding the
+    /// suffixes a lookup tries.
+    max_dots: usize,
+    /// Extensions the repository routed itself; never sniffed.
+    decided: HashSet<String>,
+}
+
+impl ExtensionTable {
+    /// The classic provers' table, shared process-wide.
+    pub fn builtin() -> &'static ExtensionTable {
+        &BUILTIN
+    }
+
+    /// The built-in table with a repository's `[provers.extensions]`
+    /// applied: each entry routes its extension to that one prover only,
+    /// or to none with [`NO_PROVER`].
+    pub fn for_repo(overrides: &BTreeMap<String, String>) -> Self {
+        let mut table = Self::builtin().clone();
+        if overrides.is_empty() {
+            return table;
+        }
+        for (ext, slug) in overrides {
+            let ext = ext.trim().to_lowercase();
+            let ext = if ext.starts_with('.') { ext } else { format!(".{}", ext) };
+            let slug = slug.trim();
+            let provers = if slug.is_empty() || slug.eq_ignore_ascii_case(NO_PROVER) {
+                Vec::new()
+            } else {
+                vec![ProverKind::new(slug)]
+            };
+            table.routes.insert(ext.clone(), provers);
+            table.decided.insert(ext);
+        }
+        table.recount();
+        table
+    }
+
+    fn recount(&mut self) {
+        self.max_dots = self
+            .routes
+            .keys()
+            .map(|ext| ext.matches('.').count())
+            .max()
+            .unwrap_or(0);
+    }
+
+    /// Provers for an extension (`.v` or `v`, any case).
+    pub fn provers_for_extension(&self, ext: &str) -> &[ProverKind] {
+        let ext = ext.to_lowercase();
+        let key = if ext.starts_with('.') { ext } else { format!(".{}", ext) };
+        self.routes.get(&key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Provers for the file at `path`. The longest known extension wins,
+    /// so `Foo.lagda.md` is Agda whatever `.md` routes to.
+    pub fn provers_for(&self, path: &str) -> &[ProverKind] {
+        self.lookup(path).map_or(&[], |(_, provers)| provers)
+    }
+
+    /// The known extension of `path` and its provers.
+    fn lookup(&self, path: &str) -> Option<(&str, &[ProverKind])> {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_lowercase();
+        let dots = name.matches('.').count();
+        for (skipped, (i, _)) in name.match_indices('.').enumerate() {
+            if dots - skipped > self.max_dots {
+                continue;
+            }
+            if let Some((ext, provers)) = self.routes.get_key_value(&name[i..]) {
+                return Some((ext.as_str(), provers.as_slice()));
+            }
+        }
+        None
+    }
+
+    /// Whether `prover` checks the file at `path`.
+    pub fn routes_to(&self, path: &str, prover: &ProverKind) -> bool {
+        self.provers_for(path).contains(prover)
+    }
+
+    /// The sniffer deciding whether the file at `path` is really
+    /// `prover`'s, if its extension is shared and the repository has not
+    /// routed it. Only then is the content worth reading.
+    pub fn sniffer(&self, path: &str, prover: &ProverKind) -> Option<&'static Sniffer> {
+        let (ext, provers) = self.lookup(path)?;
+        if self.decided.contains(ext) || !provers.contains(prover) {
+            return None;
+        }
+        SNIFFERS
+            .iter()
+            .find(|s| s.extension == ext && s.prover == prover.as_str())
+    }
+
+    /// Whether `prover` should verify the file at `path`, given its
+    /// content.
+    pub fn accepts(&self, path: &str, prover: &ProverKind, content: &str) -> bool {
+        self.routes_to(path, prover)
+            && self.sniffer(path, prover).is_none_or(|sniffer| sniffer.matches(content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_routes_by_longest_extension() {
+        let table = ExtensionTable::builtin();
+        assert_eq!(table.provers_for("theories/Nat.V"), &[ProverKind::new("coq")]);
+        assert_eq!(table.provers_for("src/Everything.lagda.md"), &[ProverKind::new("agda")]);
+        assert!(table.provers_for("README.md").is_empty());
+        assert!(table.provers_for("Makefile").is_empty());
+        assert_eq!(
+            table.provers_for("goal.smt2"),
+            &[ProverKind::new("z3"), ProverKind::new("cvc5")],
+            "both SMT solvers check .smt2"
+        );
+    }
+
+    #[test]
+    fn repo_overrides_disambiguate_and_add_extensions() {
+        let overrides = BTreeMap::from([
+            (".v".to_string(), NO_PROVER.to_string()),
+            ("smt2".to_string(), "cvc5".to_string()),
+            (".tla".to_string(), "tlaps".to_string()),
+        ]);
+        let table = ExtensionTable::for_repo(&overrides);
+        assert!(table.provers_for("rtl/alu.v").is_empty());
+        assert!(table.routes_to("goal.smt2", &ProverKind::new("cvc5")));
+        assert!(!table.routes_to("goal.smt2", &ProverKind::new("z3")));
+        assert!(table.routes_to("specs/Paxos.tla", &ProverKind::new("tlaps")));
+        // The shared table is untouched.
+        assert!(ExtensionTable::builtin().routes_to("rtl/alu.v", &ProverKind::new("coq")));
+    }
+
+    #[test]
+    fn shared_extensions_are_sniffed() {
+        let table = ExtensionTable::builtin();
+        let coq = ProverKind::new("coq");
+        let verilog = "`timescale 1ns/1ps\nmodule alu(input clk);\n  always @(posedge clk) begin end\nendmodule\n";
+        assert!(!table.accepts("rtl/alu.v", &coq, verilog));
+        assert!(table.accepts("theories/Nat.v", &coq, "Lemma x : True.\nProof. exact I. Qed.\n"));
+        assert!(table.accepts("theories/Defs.v", &coq, "Definition two := 2.\n"), ".v is Coq by default");
+
+        let hol = ProverKind::new("hol-light");
+        assert!(!table.accepts("src/parser.ml", &hol, "let parse s = String.trim s\n"));
+        assert!(table.accepts("Arith/num.ml", &hol, "let NUM_ADD = prove(`1 + 1 = 2`, ARITH_TAC);;\n"));
+
+        // Non-shared extensions are never read.
+        assert!(table.sniffer("Foo.lean", &ProverKind::new("lean")).is_none());
+    }
+
+    #[test]
+    fn a_repo_decision_replaces_sniffing() {
+        let overrides = BTreeMap::from([(".ml".to_string(), "hol-light".to_string())]);
+        let table = ExtensionTable::for_repo(&overrides);
+        let hol = ProverKind::new("hol-light");
+        assert!(table.sniffer("lib/util.ml", &hol).is_none());
+        assert!(table.accepts("lib/util.ml", &hol, "let id x = x\n"));
+    }
+}
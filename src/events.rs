@@ -0,0 +1,691 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Job lifecycle events on NATS or Kafka (`[events]`)
+//!
+//! With `[events] enabled`, every job that is queued, starts, finishes or
+//! is cancelled becomes a [`JobEvent`] for other services to consume:
+//!
+//! - on NATS, one message per event on `<topic>.job.<status>` (for
+//!   example `echidnabot.events.job.completed`), carrying a `Nats-Msg-Id`
+//!   header. Needs the `nats` feature;
+//! - on Kafka, records on `topic` keyed by job id, so one job's events
+//!   stay in order, produced through a REST proxy's v2 API.
+//!
+//! Events are JSON, or Avro ([`avro_schema`], printed by `echidnabot
+//! events schema`): binary on NATS, and registered with the proxy's schema
+//! registry on Kafka.
+//!
+//! Delivery goes through an outbox. The store records each job insert and
+//! status change in `job_event_outbox` in the same write as the change
+//! ([`Store::set_job_event_outbox`]), and [`run_event_publisher`] sends
+//! the outbox in order, deleting events once the broker has them — a
+//! JetStream ack with `jetstream = true`, else a flush or the proxy's
+//! reply. A crash or an unreachable broker delays events but never loses
+//! them; an event may arrive twice, with the same `event_id`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::config::{EventFormat, EventTransport, EventsConfig, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::{JobId, JobStatus};
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{JobEventRecord, ProofJobRecord, ProofResultRecord, Repository};
+use crate::store::Store;
+
+/// Bumped whenever a field changes meaning or is removed.
+pub const EVENT_SCHEMA: i64 = 1;
+
+/// A finished job's event waits this long for its result to be saved.
+const RESULT_WAIT_SECS: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    String,
+    Long,
+    Boolean,
+}
+
+/// Fields of an event, in Avro order: name, type, and whether it can be
+/// null.
+const FIELDS: &[(&str, Kind, bool)] = &[
+    ("schema_version", Kind::Long, false),
+    ("event_id", Kind::String, false),
+    ("type", Kind::String, false),
+    ("occurred_at", Kind::String, false),
+    ("job_id", Kind::String, false),
+    ("repo_id", Kind::String, false),
+    ("platform", Kind::String, true),
+    ("owner", Kind::String, true),
+    ("repo", Kind::String, true),
+    ("commit_sha", Kind::String, false),
+    ("branch", Kind::String, true),
+    ("pr_number", Kind::Long, true),
+    ("prover", Kind::String, false),
+    ("priority", Kind::String, false),
+    ("status", Kind::String, false),
+    ("success", Kind::Boolean, true),
+    ("duration_ms", Kind::Long, true),
+    ("verified_files", Kind::Long, true),
+    ("failed_files", Kind::Long, true),
+    ("error_message", Kind::String, true),
+    ("retry_of", Kind::String, true),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    String(String),
+    Long(i64),
+    Boolean(bool),
+}
+
+impl Value {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Null => serde_json::Value::Null,
+            Value::String(s) => s.clone().into(),
+            Value::Long(n) => (*n).into(),
+            Value::Boolean(b) => (*b).into(),
+        }
+    }
+}
+
+fn avro_type(kind: Kind) -> &'static str {
+    match kind {
+        Kind::String => "string",
+        Kind::Long => "long",
+        Kind::Boolean => "boolean",
+    }
+}
+
+/// The Avro schema of [`JobEvent`]. Nullable fields default to null.
+pub fn avro_schema() -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = FIELDS
+        .iter()
+        .map(|(name, kind, nullable)| {
+            if *nullable {
+                serde_json::json!({
+                    "name": name,
+                    "type": ["null", avro_type(*kind)],
+                    "default": null,
+                })
+            } else {
+                serde_json::json!({ "name": name, "type": avro_type(*kind) })
+            }
+        })
+        .collect();
+    serde_json::json!({
+        "type": "record",
+        "name": "JobEvent",
+        "namespace": "org.hyperpolymath.echidnabot",
+        "fields": fields,
+    })
+}
+
+/// `queued`, `running`, `completed`, `failed` or `cancelled`.
+fn status_name(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "queued",
+        JobStatus::Running => "running",
+        JobStatus::Completed => "completed",
+        JobStatus::Failed => "failed",
+        JobStatus::Cancelled => "cancelled",
+    }
+}
+
+/// One job status change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobEvent {
+    pub event_id: String,
+    /// `job.<status>`.
+    pub event_type: String,
+    pub occurred_at: DateTime<Utc>,
+    pub job_id: Uuid,
+    pub repo_id: Uuid,
+    /// `None` once the repository has been removed.
+    pub platform: Option<String>,
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    pub commit_sha: String,
+    pub branch: Option<String>,
+    pub pr_number: Option<u64>,
+    pub prover: String,
+    pub priority: String,
+    /// The status the job changed to.
+    pub status: JobStatus,
+    /// Set once the job has a result.
+    pub success: Option<bool>,
+    pub duration_ms: Option<i64>,
+    pub verified_files: Option<i64>,
+    pub failed_files: Option<i64>,
+    /// Why a failed job failed.
+    pub error_message: Option<String>,
+    pub retry_of: Option<Uuid>,
+}
+
+impl JobEvent {
+    pub fn new(
+        record: &JobEventRecord,
+        job: &ProofJobRecord,
+        result: Option<&ProofResultRecord>,
+        repo: Option<&Repository>,
+    ) -> Self {
+        // Only a finished event reports the outcome; the job may have been
+        // re-queued since.
+        let finished = matches!(record.status, JobStatus::Completed | JobStatus::Failed);
+        let result = result.filter(|_| finished);
+        Self {
+            event_id: record.event_id.clone(),
+            event_type: format!("job.{}", status_name(record.status)),
+            occurred_at: record.occurred_at,
+            job_id: job.id,
+            repo_id: job.repo_id,
+            platform: repo.map(|r| format!("{:?}", r.platform).to_lowercase()),
+            owner: repo.map(|r| r.owner.clone()),
+            repo: repo.map(|r| r.name.clone()),
+            commit_sha: job.commit_sha.clone(),
+            branch: job.branch.clone(),
+            pr_number: job.pr_number,
+            prover: job.prover.as_str().to_string(),
+            priority: format!("{:?}", job.priority).to_lowercase(),
+            status: record.status,
+            success: result.map(|r| r.success),
+            duration_ms: result.map(|r| r.duration_ms),
+            verified_files: result.map(|r| r.verified_files.len() as i64),
+            failed_files: result.map(|r| r.failed_files.len() as i64),
+            error_message: job.error_message.clone().filter(|_| record.status == JobStatus::Failed),
+            retry_of: job.retry_of,
+        }
+    }
+
+    /// Values in [`FIELDS`] order.
+    fn values(&self) -> Vec<Value> {
+        let string = |s: &Option<String>| s.clone().map_or(Value::Null, Value::String);
+        let long = |n: Option<i64>| n.map_or(Value::Null, Value::Long);
+        vec![
+            Value::Long(EVENT_SCHEMA),
+            Value::String(self.event_id.clone()),
+            Value::String(self.event_type.clone()),
+            Value::String(self.occurred_at.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            Value::String(self.job_id.to_string()),
+            Value::String(self.repo_id.to_string()),
+            string(&self.platform),
+            string(&self.owner),
+            string(&self.repo),
+            Value::String(self.commit_sha.clone()),
+            string(&self.branch),
+            long(self.pr_number.map(|n| n as i64)),
+            Value::String(self.prover.clone()),
+            Value::String(self.priority.clone()),
+            Value::String(status_name(self.status).to_string()),
+            self.success.map_or(Value::Null, Value::Boolean),
+            long(self.duration_ms),
+            long(self.verified_files),
+            long(self.failed_files),
+            string(&self.error_message),
+            string(&self.retry_of.map(|id| id.to_string())),
+        ]
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let fields = FIELDS.iter().zip(self.values());
+        let object = fields.map(|((name, _, _), value)| (name.to_string(), value.to_json()));
+        serde_json::Value::Object(object.collect())
+    }
+
+    /// Avro's JSON encoding, where a non-null union value is wrapped in
+    /// its type's name; what a Kafka REST proxy takes.
+    pub fn to_avro_json(&self) -> serde_json::Value {
+        let fields = FIELDS.iter().zip(self.values());
+        let object = fields.map(|((name, kind, nullable), value)| {
+            let json = match value {
+                Value::Null => serde_json::Value::Null,
+                value if *nullable => {
+                    let mut branch = serde_json::Map::new();
+                    branch.insert(avro_type(*kind).to_string(), value.to_json());
+                    serde_json::Value::Object(branch)
+                }
+                value => value.to_json(),
+            };
+            (name.to_string(), json)
+        });
+        serde_json::Value::Object(object.collect())
+    }
+
+    /// Avro binary encoding, without framing.
+    pub fn to_avro(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for ((_, _, nullable), value) in FIELDS.iter().zip(self.values()) {
+            if *nullable {
+                write_long(&mut out, i64::from(value != Value::Null));
+            }
+            match value {
+                Value::Null => {}
+                Value::String(s) => {
+                    write_long(&mut out, s.len() as i64);
+                    out.extend_from_slice(s.as_bytes());
+                }
+                Value::Long(n) => write_long(&mut out, n),
+                Value::Boolean(b) => out.push(u8::from(b)),
+            }
+        }
+        out
+    }
+
+    #[cfg(feature = "nats")]
+    fn encode(&self, format: EventFormat) -> Result<Vec<u8>> {
+        Ok(match format {
+            EventFormat::Json => serde_json::to_vec(&self.to_json())?,
+            EventFormat::Avro => self.to_avro(),
+        })
+    }
+}
+
+/// Zig-zag varint, as Avro writes `int` and `long`.
+fn write_long(out: &mut Vec<u8>, n: i64) {
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        out.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+#[cfg(feature = "nats")]
+fn content_type(format: EventFormat) -> &'static str {
+    match format {
+        EventFormat::Json => "application/json",
+        EventFormat::Avro => "avro/binary",
+    }
+}
+
+impl EventsConfig {
+    /// Check the settings the transport needs; run at startup and on
+    /// reload.
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if !self.url.as_deref().is_some_and(|url| !url.trim().is_empty()) {
+            return Err(Error::Config("[events] needs a url".into()));
+        }
+        if self.topic.trim().is_empty() {
+            return Err(Error::Config("[events] topic is empty".into()));
+        }
+        Ok(())
+    }
+}
+
+/// A connection to the broker.
+pub enum Publisher {
+    Kafka(reqwest::Client),
+    #[cfg(feature = "nats")]
+    Nats(async_nats::Client),
+}
+
+#[cfg(feature = "nats")]
+fn nats_error(e: impl std::fmt::Display) -> Error {
+    Error::Internal(format!("NATS: {}", e))
+}
+
+impl Publisher {
+    pub async fn connect(config: &EventsConfig) -> Result<Self> {
+        let url = config.url.as_deref().unwrap_or_default();
+        match config.transport {
+            EventTransport::Kafka => Ok(Publisher::Kafka(reqwest::Client::new())),
+            #[cfg(feature = "nats")]
+            EventTransport::Nats => {
+                let mut options = async_nats::ConnectOptions::new().name("echidnabot-events");
+                if let Some(token) = &config.token {
+                    options = options.token(token.clone());
+                }
+                Ok(Publisher::Nats(options.connect(url).await.map_err(nats_error)?))
+            }
+            #[cfg(not(feature = "nats"))]
+            EventTransport::Nats => Err(Error::Unsupported(format!(
+                "[events] transport = \"nats\" ({}) needs a build with the `nats` feature",
+                url
+            ))),
+        }
+    }
+
+    /// Send `events` in order; `Ok` once the broker holds all of them.
+    async fn publish(&self, config: &EventsConfig, events: &[JobEvent]) -> Result<()> {
+        match self {
+            Publisher::Kafka(client) => publish_kafka(client, config, events).await,
+            #[cfg(feature = "nats")]
+            Publisher::Nats(client) => {
+                let jetstream = async_nats::jetstream::new(client.clone());
+                for event in events {
+                    let subject = format!("{}.{}", config.topic, event.event_type);
+                    let mut headers = async_nats::HeaderMap::new();
+                    headers.insert("Nats-Msg-Id", event.event_id.as_str());
+                    headers.insert("Content-Type", content_type(config.format));
+                    let payload = event.encode(config.format)?.into();
+                    if config.jetstream {
+                        jetstream
+                            .publish_with_headers(subject, headers, payload)
+                            .await
+                            .map_err(nats_error)?
+                            .await
+                            .map_err(nats_error)?;
+                    } else {
+                        client
+                            .publish_with_headers(subject, headers, payload)
+                            .await
+                            .map_err(nats_error)?;
+                    }
+                }
+                if !config.jetstream {
+                    client.flush().await.map_err(nats_error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Produce through a Kafka REST proxy (`POST /topics/<topic>`, v2 API).
+async fn publish_kafka(
+    client: &reqwest::Client,
+    config: &EventsConfig,
+    events: &[JobEvent],
+) -> Result<()> {
+    let base = config.url.as_deref().unwrap_or_default().trim_end_matches('/');
+    let body = match config.format {
+        EventFormat::Json => serde_json::json!({
+            "records": events
+                .iter()
+                .map(|e| serde_json::json!({ "key": e.job_id.to_string(), "value": e.to_json() }))
+                .collect::<Vec<_>>(),
+        }),
+        EventFormat::Avro => serde_json::json!({
+            "key_schema": "\"string\"",
+            "value_schema": avro_schema().to_string(),
+            "records": events
+                .iter()
+                .map(|e| {
+                    serde_json::json!({ "key": e.job_id.to_string(), "value": e.to_avro_json() })
+                })
+                .collect::<Vec<_>>(),
+        }),
+    };
+    let media_type = match config.format {
+        EventFormat::Json => "application/vnd.kafka.json.v2+json",
+        EventFormat::Avro => "application/vnd.kafka.avro.v2+json",
+    };
+    let mut request = client
+        .post(format!("{}/topics/{}", base, config.topic))
+        .header(reqwest::header::CONTENT_TYPE, media_type)
+        .header(reqwest::header::ACCEPT, "application/vnd.kafka.v2+json")
+        .body(serde_json::to_vec(&body)?);
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, config.password.as_ref());
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    let answer: serde_json::Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(Error::Internal(format!("Kafka REST proxy answered {}: {}", status, answer)));
+    }
+    // Records can fail one by one in a successful response.
+    let offsets = answer["offsets"].as_array().map(Vec::as_slice).unwrap_or_default();
+    if let Some(failed) = offsets.iter().find(|offset| !offset["error"].is_null()) {
+        return Err(Error::Internal(format!("Kafka rejected a record: {}", failed["error"])));
+    }
+    Ok(())
+}
+
+/// Publish the oldest outbox events, up to `batch_size`, and return how
+/// many went out. Events of jobs since removed are dropped. On failure
+/// nothing is removed from the outbox and the next run starts over from
+/// the same event.
+pub async fn publish_pending(
+    store: &dyn Store,
+    publisher: &Publisher,
+    config: &EventsConfig,
+) -> Result<usize> {
+    let records = store.list_job_events(config.batch_size.max(1)).await?;
+    if records.is_empty() {
+        return Ok(0);
+    }
+    let mut ids: Vec<JobId> = records.iter().map(|r| JobId(r.job_id)).collect();
+    ids.dedup();
+    let jobs: HashMap<Uuid, ProofJobRecord> =
+        store.get_jobs_by_ids(&ids).await?.into_iter().map(|job| (job.id, job)).collect();
+    let results: HashMap<Uuid, ProofResultRecord> = store
+        .get_results_by_job_ids(&ids)
+        .await?
+        .into_iter()
+        .map(|result| (result.job_id, result))
+        .collect();
+    let mut repos: HashMap<Uuid, Option<Repository>> = HashMap::new();
+    for job in jobs.values() {
+        if !repos.contains_key(&job.repo_id) {
+            repos.insert(job.repo_id, store.get_repository(job.repo_id).await?);
+        }
+    }
+
+    let now = Utc::now();
+    let mut gone = Vec::new();
+    let mut ready: Vec<(&JobEventRecord, JobEvent)> = Vec::new();
+    for record in &records {
+        let Some(job) = jobs.get(&record.job_id) else {
+            gone.push(record.seq);
+            continue;
+        };
+        let result = results.get(&job.id);
+        let finished = matches!(record.status, JobStatus::Completed | JobStatus::Failed);
+        let waited = (now - record.occurred_at).num_seconds();
+        if finished && result.is_none() && waited < RESULT_WAIT_SECS {
+            break;
+        }
+        let repo = repos.get(&job.repo_id).and_then(Option::as_ref);
+        ready.push((record, JobEvent::new(record, job, result, repo)));
+    }
+    store.delete_job_events(&gone).await?;
+    let Some((first, _)) = ready.first() else {
+        return Ok(0);
+    };
+
+    let events: Vec<JobEvent> = ready.iter().map(|(_, event)| event.clone()).collect();
+    if let Err(e) = publisher.publish(config, &events).await {
+        if first.attempts == 0 {
+            tracing::warn!("Publishing job events failed, retrying every poll: {}", e);
+        }
+        store.record_job_event_failure(first.seq, &e.to_string()).await?;
+        return Err(e);
+    }
+    let seqs: Vec<i64> = ready.iter().map(|(record, _)| record.seq).collect();
+    store.delete_job_events(&seqs).await?;
+    Ok(events.len())
+}
+
+/// Drain the outbox every `poll_interval_ms` until shutdown, connecting
+/// (again) whenever there is no connection.
+pub async fn run_event_publisher(
+    store: Arc<dyn Store>,
+    config: SharedConfig,
+    shutdown: ShutdownSignal,
+) {
+    let mut publisher: Option<Publisher> = None;
+    let mut unreachable = false;
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        let events = config.load_full().events.clone();
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(events.poll_interval_ms.max(10))) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Event publisher observed shutdown signal — stopping");
+                return;
+            }
+        }
+        if publisher.is_none() {
+            match Publisher::connect(&events).await {
+                Ok(connected) => {
+                    publisher = Some(connected);
+                    unreachable = false;
+                }
+                Err(Error::Unsupported(e)) => {
+                    tracing::error!("Event publishing disabled: {}", e);
+                    return;
+                }
+                Err(e) if !unreachable => {
+                    tracing::warn!("Event publisher cannot connect, retrying: {}", e);
+                    unreachable = true;
+                    continue;
+                }
+                Err(_) => continue,
+            }
+        }
+        let Some(connected) = &publisher else {
+            continue;
+        };
+        loop {
+            match publish_pending(store.as_ref(), connected, &events).await {
+                Ok(n) if n >= events.batch_size.max(1) => {}
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::debug!("Job event publishing failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use crate::store::SqliteStore;
+
+    fn sample(status: JobStatus) -> JobEvent {
+        let repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        let mut job = ProofJobRecord::from(ProofJob::new(
+            repo.id,
+            "abc".into(),
+            ProverKind::new("coq"),
+            vec![],
+        ));
+        job.status = status;
+        job.error_message = Some("boom".into());
+        let record = JobEventRecord {
+            seq: 1,
+            event_id: "e1".into(),
+            job_id: job.id,
+            status,
+            occurred_at: Utc::now(),
+            attempts: 0,
+            last_error: None,
+        };
+        JobEvent::new(&record, &job, None, Some(&repo))
+    }
+
+    #[test]
+    fn events_follow_the_avro_schema() {
+        let event = sample(JobStatus::Failed);
+        assert_eq!(event.event_type, "job.failed");
+        assert_eq!(event.error_message.as_deref(), Some("boom"));
+        assert_eq!(sample(JobStatus::Running).error_message, None);
+
+        let values = event.values();
+        assert_eq!(values.len(), FIELDS.len());
+        for ((name, kind, nullable), value) in FIELDS.iter().zip(&values) {
+            let actual = match value {
+                Value::Null => {
+                    assert!(*nullable, "{} is required", name);
+                    continue;
+                }
+                Value::String(_) => Kind::String,
+                Value::Long(_) => Kind::Long,
+                Value::Boolean(_) => Kind::Boolean,
+            };
+            assert_eq!(actual, *kind, "{}", name);
+        }
+        let json = event.to_json();
+        assert_eq!(json["type"], "job.failed");
+        assert_eq!(json["platform"], "github");
+        assert_eq!(json["success"], serde_json::Value::Null);
+        let avro_json = event.to_avro_json();
+        assert_eq!(avro_json["platform"], serde_json::json!({ "string": "github" }));
+        assert_eq!(avro_json["commit_sha"], "abc");
+        assert_eq!(avro_schema()["fields"].as_array().unwrap().len(), FIELDS.len());
+    }
+
+    #[test]
+    fn avro_binary_encoding() {
+        let mut out = Vec::new();
+        for n in [0, -1, 1, 64, -65] {
+            write_long(&mut out, n);
+        }
+        assert_eq!(out, [0x00, 0x01, 0x02, 0x80, 0x01, 0x81, 0x01]);
+
+        let encoded = sample(JobStatus::Queued).to_avro();
+        // schema_version 1, then "e1".
+        assert_eq!(&encoded[..4], &[0x02, 0x04, b'e', b'1']);
+        // Ends with the null branch of `retry_of`.
+        assert_eq!(encoded.last(), Some(&0x00));
+    }
+
+    #[tokio::test]
+    async fn unpublished_events_stay_in_the_outbox() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        store.set_job_event_outbox(true).await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        store.create_repository(&repo).await.unwrap();
+        let mut job = ProofJobRecord::from(ProofJob::new(
+            repo.id,
+            "abc".into(),
+            ProverKind::new("coq"),
+            vec![],
+        ));
+        store.create_job(&job).await.unwrap();
+        job.status = JobStatus::Completed;
+        job.completed_at = Some(Utc::now());
+        store.update_job(&job).await.unwrap();
+
+        // Nothing listens here, so publishing fails and the outbox keeps
+        // both events; the finished one also waits for its result.
+        let config = EventsConfig {
+            enabled: true,
+            transport: EventTransport::Kafka,
+            url: Some("http://127.0.0.1:9".into()),
+            ..EventsConfig::default()
+        };
+        let publisher = Publisher::connect(&config).await.unwrap();
+        assert!(publish_pending(&store, &publisher, &config).await.is_err());
+        let pending = store.list_job_events(10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].attempts, 1);
+
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec!["A.v".into()],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        store.save_result(&ProofResultRecord::new(JobId(job.id), &result)).await.unwrap();
+        let results = store.get_results_by_job_ids(&[JobId(job.id)]).await.unwrap();
+        let event = JobEvent::new(&pending[1], &job, results.first(), Some(&repo));
+        assert_eq!(event.event_type, "job.completed");
+        assert_eq!(event.success, Some(true));
+        assert_eq!(event.verified_files, Some(1));
+
+    }
+}
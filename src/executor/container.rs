@@ -26,8 +26,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
 use tokio::process::Command;
 use tracing::{debug, info, warn};
 
@@ -42,6 +43,17 @@ pub enum IsolationBackend {
     None,
 }
 
+/// Which pipe a line of prover output came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Receives prover output line by line while a proof runs (see
+/// [`PodmanExecutor::with_output`]).
+pub type OutputSink = Arc<dyn Fn(OutputStream, &str) + Send + Sync>;
+
 /// Container execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionResult {
@@ -81,6 +93,14 @@ pub struct PodmanExecutor {
     network: bool,
     /// Detected isolation backend
     backend: IsolationBackend,
+    /// Live output, as it is produced
+    output: Option<OutputSink>,
+    /// Host directory kept between runs and its mount point in the
+    /// sandbox, for project builds
+    cache: Option<(std::path::PathBuf, String)>,
+    /// Repository flags appended to every single-file check (see
+    /// [`super::prover_args`])
+    prover_args: Vec<String>,
 }
 
 impl Default for PodmanExecutor {
@@ -92,6 +112,9 @@ fn default() -> Self {
             cpu_limit: 2.0,
             network: false, // No network for proof checking
             backend: IsolationBackend::None, // Detect on init
+            output: None,
+            cache: None,
+            prover_args: Vec::new(),
         }
     }
 }
@@ -153,6 +176,28 @@ pub fn with_network(mut self, enabled: bool) -> Self {
         self
     }
 
+    /// Pass each line of stdout / stderr to `sink` as the prover writes
+    /// it. The full output is still returned in the [`ExecutionResult`].
+    pub fn with_output(mut self, sink: OutputSink) -> Self {
+        self.output = Some(sink);
+        self
+    }
+
+    /// Mount host directory `dir` read-write at `target` in
+    /// [`Self::execute_in_workspace`] runs, for build caches kept between
+    /// jobs (see [`super::isabelle::heap_cache`]). Created if missing.
+    pub fn with_cache(mut self, dir: std::path::PathBuf, target: impl Into<String>) -> Self {
+        self.cache = Some((dir, target.into()));
+        self
+    }
+
+    /// Append `args`, already checked with [`super::prover_args::validate`],
+    /// to the arguments of [`Self::execute_proof_with_args`] runs.
+    pub fn with_prover_args(mut self, args: Vec<String>) -> Self {
+        self.prover_args = args;
+        self
+    }
+
     /// Override the isolation backend (for testing)
     pub fn with_backend(mut self, backend: IsolationBackend) -> Self {
         self.backend = backend;
@@ -228,12 +273,28 @@ pub async fn execute_proof(
         proof_content: &str,
         _additional_files: Option<HashMap<String, String>>,
     ) -> Result<ExecutionResult> {
+        self.execute_proof_with_args(prover, proof_content, &[]).await
+    }
+
+    /// [`Self::execute_proof`] with `args` passed to the prover before the
+    /// proof file, e.g. solver flags derived from an SMT-LIB script (see
+    /// [`super::smtlib`]), then the repository's flags
+    /// ([`Self::with_prover_args`]). Each is quoted for the shell.
+    pub async fn execute_proof_with_args(
+        &self,
+        prover: ProverKind,
+        proof_content: &str,
+        args: &[String],
+    ) -> Result<ExecutionResult> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Executor).await?;
+        let args: Vec<String> = args.iter().chain(&self.prover_args).cloned().collect();
         match self.backend {
             IsolationBackend::Podman => {
-                self.execute_with_podman(prover, proof_content).await
+                self.execute_with_podman(prover, proof_content, &args).await
             }
             IsolationBackend::Bubblewrap => {
-                self.execute_with_bubblewrap(prover, proof_content).await
+                self.execute_with_bubblewrap(prover, proof_content, &args).await
             }
             IsolationBackend::None => {
                 Err(Error::Internal(
@@ -251,6 +312,7 @@ async fn execute_with_podman(
         &self,
         prover: ProverKind,
         proof_content: &str,
+        args: &[String],
     ) -> Result<ExecutionResult> {
         let start = std::time::Instant::now();
 
@@ -292,9 +354,10 @@ async fn execute_with_podman(
 
         // Command to execute inside container: save proof, run prover
         let container_cmd = format!(
-            "cat > /tmp/proof{ext} && {cmd} /tmp/proof{ext}",
+            "cat > /tmp/proof{ext} && {cmd}{args} /tmp/proof{ext}",
             ext = prover_extension(&prover),
             cmd = prover_command(&prover),
+            args = shell_args(args),
         );
         cmd.arg(&container_cmd);
 
@@ -311,6 +374,9 @@ async fn execute_with_podman(
             self.cpu_limit,
         );
 
+        // A superseded job is abandoned mid-run; take the sandbox with it.
+        cmd.kill_on_drop(true);
+
         let mut child = cmd.spawn().map_err(|e| {
             Error::Internal(format!("Failed to spawn Podman container: {}", e))
         })?;
@@ -326,19 +392,20 @@ async fn execute_with_podman(
             stdin.shutdown().await.ok(); // Close stdin
         }
 
+        let (stdout_task, stderr_task) = self.capture_output(&mut child);
+
         // Wait for completion with timeout
         let wait_result =
-            tokio::time::timeout(self.timeout + Duration::from_secs(5), child.wait_with_output())
-                .await;
+            tokio::time::timeout(self.timeout + Duration::from_secs(5), child.wait()).await;
 
         let duration = start.elapsed();
 
         match wait_result {
-            Ok(Ok(output)) => {
-                let success = output.status.success();
-                let exit_code = output.status.code();
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            Ok(Ok(status)) => {
+                let success = status.success();
+                let exit_code = status.code();
+                let stdout = stdout_task.await.unwrap_or_default();
+                let stderr = stderr_task.await.unwrap_or_default();
 
                 debug!(
                     "Podman container finished: exit={:?}, stdout={}B, stderr={}B",
@@ -365,15 +432,14 @@ async fn execute_with_podman(
             Err(_) => {
                 // Timeout exceeded even the grace period
                 warn!(
-                    "Podman container timed out after {}s",
+                    "Podman container timed out after {}s, killing",
                     self.timeout.as_secs()
                 );
-                // child is consumed by wait_with_output, so we cannot kill it
-                // here. Podman's --timeout flag handles the kill for us.
+                let _ = child.kill().await;
 
                 Ok(ExecutionResult {
                     success: false,
-                    stdout: String::new(),
+                    stdout: stdout_task.await.unwrap_or_default(),
                     stderr: format!(
                         "Execution timed out after {}s",
                         self.timeout.as_secs()
@@ -396,6 +462,7 @@ async fn execute_with_bubblewrap(
         &self,
         prover: ProverKind,
         proof_content: &str,
+        args: &[String],
     ) -> Result<ExecutionResult> {
         let start = std::time::Instant::now();
 
@@ -454,9 +521,10 @@ async fn execute_with_bubblewrap(
         cmd.arg("sh")
             .arg("-c")
             .arg(format!(
-                "cp /workspace/proof{ext} /tmp/proof{ext} && {cmd} /tmp/proof{ext}",
+                "cp /workspace/proof{ext} /tmp/proof{ext} && {cmd}{args} /tmp/proof{ext}",
                 ext = prover_extension(&prover),
                 cmd = prover_cmd,
+                args = shell_args(args),
             ));
 
         cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
@@ -467,10 +535,14 @@ async fn execute_with_bubblewrap(
             self.timeout.as_secs(),
         );
 
+        cmd.kill_on_drop(true);
+
         let mut child = cmd.spawn().map_err(|e| {
             Error::Internal(format!("Failed to spawn bubblewrap sandbox: {}", e))
         })?;
 
+        let (stdout_task, stderr_task) = self.capture_output(&mut child);
+
         // Wait with timeout. We use wait() instead of wait_with_output()
         // so we can kill the child on timeout.
         let wait_result =
@@ -490,8 +562,8 @@ async fn execute_with_bubblewrap(
 
                 Ok(ExecutionResult {
                     success,
-                    stdout: String::new(),
-                    stderr: String::new(),
+                    stdout: stdout_task.await.unwrap_or_default(),
+                    stderr: stderr_task.await.unwrap_or_default(),
                     exit_code,
                     duration_ms: duration.as_millis() as u64,
                     timed_out: false,
@@ -512,7 +584,7 @@ async fn execute_with_bubblewrap(
 
                 Ok(ExecutionResult {
                     success: false,
-                    stdout: String::new(),
+                    stdout: stdout_task.await.unwrap_or_default(),
                     stderr: format!(
                         "Execution timed out after {}s",
                         self.timeout.as_secs()
@@ -527,6 +599,199 @@ async fn execute_with_bubblewrap(
         }
     }
 
+    /// Run `command` with a checkout mounted writable at `/workspace`,
+    /// from its `workdir` subdirectory (`""` for the root).
+    ///
+    /// For project builds (`lake build`, `isabelle build`; see
+    /// [`super::lake`], [`super::isabelle`]) that read many files and
+    /// write build outputs next to them. The checkout is a
+    /// per-job clone, so writes there are thrown away with it; everything
+    /// else is as locked down as [`Self::execute_proof`].
+    #[tracing::instrument(
+        name = "executor.run_workspace",
+        skip(self, workspace),
+        fields(prover = %prover, backend = ?self.backend)
+    )]
+    pub async fn execute_in_workspace(
+        &self,
+        prover: ProverKind,
+        workspace: &std::path::Path,
+        workdir: &str,
+        command: &str,
+    ) -> Result<ExecutionResult> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Executor).await?;
+        let dir = if workdir.is_empty() {
+            "/workspace".to_string()
+        } else {
+            format!("/workspace/{}", workdir)
+        };
+        let mut cmd = match self.backend {
+            IsolationBackend::Podman => {
+                let mut cmd = Command::new("podman");
+                cmd.args(["run", "--rm"]);
+                if !self.network {
+                    cmd.arg("--network=none");
+                }
+                cmd.arg(format!("--memory={}", self.memory_limit))
+                    .arg(format!("--cpus={}", self.cpu_limit))
+                    .arg("--pids-limit=100")
+                    .arg("--read-only")
+                    .arg("--tmpfs=/tmp:rw,noexec,nosuid,size=100m")
+                    .arg("--security-opt=no-new-privileges")
+                    .arg("--cap-drop=ALL")
+                    .arg(format!("--timeout={}", self.timeout.as_secs()))
+                    .arg("-v")
+                    .arg(format!("{}:/workspace:Z", workspace.display()));
+                if let Some((cache, target)) = &self.cache {
+                    cmd.arg("-v").arg(format!("{}:{}:Z", cache.display(), target));
+                }
+                cmd.arg("-w")
+                    .arg(&dir)
+                    .arg("-e")
+                    .arg(format!("PROVER={}", prover_to_env_name(&prover)))
+                    .arg("-e")
+                    .arg("HOME=/tmp")
+                    .arg(&self.image)
+                    .args(["sh", "-c", command]);
+                cmd
+            }
+            IsolationBackend::Bubblewrap => {
+                let mut cmd = Command::new("bwrap");
+                for dir in ["/usr", "/lib", "/lib64", "/bin", "/sbin"] {
+                    cmd.args(["--ro-bind", dir, dir]);
+                }
+                cmd.args(["--tmpfs", "/tmp", "--proc", "/proc", "--dev", "/dev"])
+                    .arg("--bind")
+                    .arg(workspace)
+                    .arg("/workspace");
+                if let Some((cache, target)) = &self.cache {
+                    cmd.arg("--bind").arg(cache).arg(target);
+                }
+                cmd.args(["--chdir", &dir])
+                    .args(["--unshare-all", "--die-with-parent", "--new-session"])
+                    .args(["--setenv", "PROVER", &prover_to_env_name(&prover)])
+                    .args(["--setenv", "HOME", "/tmp"])
+                    .args(["sh", "-c", command]);
+                cmd
+            }
+            IsolationBackend::None => {
+                return Err(Error::Internal(
+                    "No isolation backend available; refusing to run a project build \
+                     without isolation (fail-safe policy)."
+                        .to_string(),
+                ))
+            }
+        };
+        if let Some((cache, _)) = &self.cache {
+            tokio::fs::create_dir_all(cache).await?;
+        }
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        info!(
+            "Running `{}` for {} in {} (timeout: {}s)",
+            command,
+            prover.display_name(),
+            dir,
+            self.timeout.as_secs(),
+        );
+        let start = std::time::Instant::now();
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| Error::Internal(format!("Failed to spawn sandbox for `{}`: {}", command, e)))?;
+        let (stdout_task, stderr_task) = self.capture_output(&mut child);
+
+        let wait_result =
+            tokio::time::timeout(self.timeout + Duration::from_secs(5), child.wait()).await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+        match wait_result {
+            Ok(Ok(status)) => {
+                let exit_code = status.code();
+                Ok(ExecutionResult {
+                    success: status.success(),
+                    stdout: stdout_task.await.unwrap_or_default(),
+                    stderr: stderr_task.await.unwrap_or_default(),
+                    exit_code,
+                    duration_ms,
+                    timed_out: false,
+                    oom_killed: exit_code == Some(137),
+                    backend: self.backend,
+                })
+            }
+            Ok(Err(e)) => Err(Error::Internal(format!("`{}` failed to run: {}", command, e))),
+            Err(_) => {
+                warn!("`{}` timed out after {}s, killing", command, self.timeout.as_secs());
+                let _ = child.kill().await;
+                Ok(ExecutionResult {
+                    success: false,
+                    stdout: stdout_task.await.unwrap_or_default(),
+                    stderr: format!("Execution timed out after {}s", self.timeout.as_secs()),
+                    exit_code: None,
+                    duration_ms,
+                    timed_out: true,
+                    oom_killed: false,
+                    backend: self.backend,
+                })
+            }
+        }
+    }
+
+    /// Read the child's stdout and stderr to completion in the
+    /// background, feeding [`Self::with_output`]'s sink as lines arrive.
+    fn capture_output(
+        &self,
+        child: &mut tokio::process::Child,
+    ) -> (tokio::task::JoinHandle<String>, tokio::task::JoinHandle<String>) {
+        (
+            tokio::spawn(capture(child.stdout.take(), OutputStream::Stdout, self.output.clone())),
+            tokio::spawn(capture(child.stderr.take(), OutputStream::Stderr, self.output.clone())),
+        )
+    }
+
+    /// The version of `prover` in the sandbox, from its `--version` output
+    /// (see `crate::toolchain`). Runs under the same isolation as a proof,
+    /// with a short timeout.
+    pub async fn prover_version(&self, prover: ProverKind) -> Result<Option<String>> {
+        let version_cmd = prover_version_command(&prover);
+        let mut cmd = match self.backend {
+            IsolationBackend::Podman => {
+                let mut cmd = Command::new("podman");
+                cmd.args(["run", "--rm", "--network=none", "--read-only"])
+                    .arg("--security-opt=no-new-privileges")
+                    .arg("--cap-drop=ALL")
+                    .arg(&self.image)
+                    .args(["sh", "-c", &version_cmd]);
+                cmd
+            }
+            IsolationBackend::Bubblewrap => {
+                let mut cmd = Command::new("bwrap");
+                for dir in ["/usr", "/lib", "/lib64", "/bin", "/sbin"] {
+                    cmd.args(["--ro-bind", dir, dir]);
+                }
+                cmd.args(["--tmpfs", "/tmp", "--proc", "/proc", "--dev", "/dev"])
+                    .args(["--unshare-all", "--die-with-parent", "--new-session"])
+                    .args(["sh", "-c", &version_cmd]);
+                cmd
+            }
+            IsolationBackend::None => return Ok(None),
+        };
+        cmd.stdin(Stdio::null()).kill_on_drop(true);
+
+        let output = tokio::time::timeout(Duration::from_secs(30), cmd.output())
+            .await
+            .map_err(|_| Error::Timeout)?
+            .map_err(|e| Error::Internal(format!("Failed to run `{}`: {}", version_cmd, e)))?;
+        let text = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(crate::toolchain::parse_version(&text))
+    }
+
     /// Pull the container image if not already present (Podman only).
     pub async fn ensure_image(&self) -> Result<()> {
         if self.backend != IsolationBackend::Podman {
@@ -612,6 +877,35 @@ pub fn build_podman_args(&self, prover: ProverKind) -> Vec<String> {
     }
 }
 
+/// Everything `pipe` yields, lossily decoded, with each line also passed
+/// to `sink`.
+async fn capture<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    stream: OutputStream,
+    sink: Option<OutputSink>,
+) -> String {
+    let Some(pipe) = pipe else {
+        return String::new();
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut captured = String::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                if let Some(sink) = &sink {
+                    sink(stream, line.trim_end_matches(['\n', '\r']));
+                }
+                captured.push_str(&line);
+            }
+        }
+    }
+    captured
+}
+
 // =============================================================================
 // Prover Mapping Helpers
 // =============================================================================
@@ -664,6 +958,13 @@ fn prover_extension(prover: &ProverKind) -> String {
     }
 }
 
+/// `args` single-quoted for `sh -c`, each preceded by a space.
+fn shell_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| format!(" '{}'", arg.replace('\'', "'\\''")))
+        .collect()
+}
+
 /// Get the shell command to invoke a prover.
 fn prover_command(prover: &ProverKind) -> String {
     match prover.as_str() {
@@ -683,6 +984,15 @@ fn prover_command(prover: &ProverKind) -> String {
     }
 }
 
+/// Shell command printing a prover's version.
+fn prover_version_command(prover: &ProverKind) -> String {
+    match prover.as_str() {
+        "isabelle" => "isabelle version".to_string(),
+        "hol4" => "hol --version".to_string(),
+        _ => format!("{} --version", prover_command(prover)),
+    }
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -726,6 +1036,13 @@ fn test_prover_commands() {
         assert_eq!(prover_command(&ProverKind::new("z3")), "z3");
     }
 
+    #[test]
+    fn test_prover_version_commands() {
+        assert_eq!(prover_version_command(&ProverKind::new("lean")), "lean --version");
+        assert_eq!(prover_version_command(&ProverKind::new("coq")), "coqc --version");
+        assert_eq!(prover_version_command(&ProverKind::new("isabelle")), "isabelle version");
+    }
+
     #[test]
     fn test_default_executor() {
         let executor = PodmanExecutor::default();
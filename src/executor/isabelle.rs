@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Isabelle sessions
+//!
+//! An Isabelle theory is checked as part of its session, as declared in
+//! the `ROOT` file of its directory or one above: checking it alone with
+//! `isabelle build` of a bare file misses its session's parent and
+//! imports. Theories listed in a `ROOT` session are instead built with
+//! `isabelle build -d . <session>...` in that directory, with the checkout
+//! mounted into the sandbox
+//! ([`PodmanExecutor::execute_in_workspace`]). Only sessions holding the
+//! job's theories are built, so a push building one changed theory builds
+//! its session, not every session of the repository.
+//!
+//! Sessions build on heaps (`HOL`, or a parent session of the
+//! repository's own). The image's heaps are used as they are; heaps built
+//! in the sandbox are written under `$HOME/.isabelle` and, with
+//! `[executor] cache_dir` set, kept there between jobs
+//! ([`heap_cache`], mounted at [`HEAP_CACHE_MOUNT`]). The cache is per
+//! repository so one repository's heaps are never loaded for another's.
+//!
+//! [`parse_output`] maps the build's verdict per session back to the
+//! job's theory files: a theory passes when its session finished and
+//! fails when its session failed or never ran.
+//!
+//! `[provers.isabelle] sessions = false` in the repository manifest turns
+//! this off; the theories are then checked one by one.
+//!
+//! [`PodmanExecutor::execute_in_workspace`]: super::PodmanExecutor::execute_in_workspace
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+/// The file declaring a directory's sessions.
+pub const ROOT_FILE: &str = "ROOT";
+
+/// Where [`heap_cache`] is mounted in the sandbox: Isabelle's user
+/// directory under the sandbox's `HOME`.
+pub const HEAP_CACHE_MOUNT: &str = "/tmp/.isabelle";
+
+/// The host directory caching `repo`'s session heaps under `cache_dir`.
+pub fn heap_cache(cache_dir: &Path, repo: Uuid) -> PathBuf {
+    cache_dir.join("isabelle").join(repo.to_string())
+}
+
+/// A session declared in a `ROOT` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub name: String,
+    /// Its theory files, repo-relative.
+    pub theories: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Sym(char),
+}
+
+const SYMBOLS: &[char] = &['=', '+', '(', ')', '[', ']', ','];
+
+const KEYWORDS: &[&str] = &[
+    "session",
+    "description",
+    "options",
+    "sessions",
+    "directories",
+    "theories",
+    "document_theories",
+    "document_files",
+    "export_files",
+    "export_classpath",
+    "chapter",
+];
+
+fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with("(*") {
+            // Comments nest.
+            let mut depth = 0;
+            let mut end = rest.len();
+            let mut i = 0;
+            while i < rest.len() {
+                if rest[i..].starts_with("(*") {
+                    depth += 1;
+                    i += 2;
+                } else if rest[i..].starts_with("*)") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        end = i;
+                        break;
+                    }
+                } else {
+                    i += rest[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            rest = &rest[end..];
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            tokens.push(Token::Quoted(quoted[..end].to_string()));
+            rest = quoted.get(end + 1..).unwrap_or_default();
+        } else if let Some(text) = rest.strip_prefix("\\<open>") {
+            let end = text.find("\\<close>").unwrap_or(text.len());
+            tokens.push(Token::Quoted(text[..end].to_string()));
+            rest = text.get(end + "\\<close>".len()..).unwrap_or_default();
+        } else if SYMBOLS.contains(&c) {
+            tokens.push(Token::Sym(c));
+            rest = &rest[1..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '"' || SYMBOLS.contains(&c))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+/// Index just past the bracket group opened at `tokens[start]`.
+fn skip_group(tokens: &[Token], start: usize, open: char, close: char) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::Sym(c) if *c == open => depth += 1,
+            Token::Sym(c) if *c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// `a/b` + `../c` → `a/c`; `""` is the checkout root.
+fn join(base: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in base.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// The theory file a `theories` entry names, relative to `dir`. `None`
+/// for theories of other sessions (`HOL-Library.Multiset`).
+fn theory_file(dir: &str, name: &str) -> Option<String> {
+    let name = name.strip_suffix(".thy").unwrap_or(name);
+    let base = name.rsplit('/').next().unwrap_or(name);
+    (!base.is_empty() && !base.contains('.')).then(|| format!("{}.thy", join(dir, name)))
+}
+
+/// The sessions of the `ROOT` file in directory `root` (repo-relative).
+pub fn parse_root(text: &str, root: &str) -> Vec<Session> {
+    let tokens = tokens(text);
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut dir = root.to_string();
+    let mut in_theories = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Word(word) if word == "session" => {
+                in_theories = false;
+                let name = match tokens.get(i + 1) {
+                    Some(Token::Word(name) | Token::Quoted(name)) => name.clone(),
+                    _ => break,
+                };
+                i += 2;
+                if tokens.get(i) == Some(&Token::Sym('(')) {
+                    i = skip_group(&tokens, i, '(', ')');
+                }
+                dir = root.to_string();
+                if matches!(tokens.get(i), Some(Token::Word(w)) if w == "in") {
+                    if let Some(Token::Word(d) | Token::Quoted(d)) = tokens.get(i + 1) {
+                        dir = join(root, d);
+                    }
+                    i += 2;
+                }
+                sessions.push(Session { name, theories: Vec::new() });
+            }
+            Token::Word(word) if KEYWORDS.contains(&word.as_str()) => {
+                in_theories = word == "theories";
+                i += 1;
+            }
+            Token::Sym('[') => i = skip_group(&tokens, i, '[', ']'),
+            Token::Sym('(') => i = skip_group(&tokens, i, '(', ')'),
+            Token::Word(name) | Token::Quoted(name) if in_theories => {
+                if let (Some(session), Some(file)) = (sessions.last_mut(), theory_file(&dir, name)) {
+                    session.theories.push(file);
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    sessions
+}
+
+/// Whether `name` can go on a command line unquoted.
+fn plain_session_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// The directory holding the `ROOT` file nearest above `file`, within
+/// `checkout`; `""` is the root.
+fn root_dir(checkout: &Path, file: &str) -> Option<String> {
+    let mut dir = Path::new(file).parent();
+    while let Some(rel) = dir {
+        if checkout.join(rel).join(ROOT_FILE).is_file() {
+            return Some(rel.to_string_lossy().replace('\\', "/"));
+        }
+        dir = rel.parent();
+    }
+    None
+}
+
+/// `files` grouped by `ROOT` directory, then by the session listing them.
+/// Files in no session are left out.
+pub fn sessions(checkout: &Path, files: &[String]) -> BTreeMap<String, BTreeMap<String, Vec<String>>> {
+    let mut parsed: BTreeMap<String, Vec<Session>> = BTreeMap::new();
+    let mut grouped: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for file in files {
+        if !file.ends_with(".thy") || Path::new(file).is_absolute() {
+            continue;
+        }
+        let Some(root) = root_dir(checkout, file) else {
+            continue;
+        };
+        let declared = parsed.entry(root.clone()).or_insert_with(|| {
+            std::fs::read_to_string(checkout.join(&root).join(ROOT_FILE))
+                .map(|text| parse_root(&text, &root))
+                .unwrap_or_default()
+        });
+        if let Some(session) = declared
+            .iter()
+            .find(|s| plain_session_name(&s.name) && s.theories.contains(file))
+        {
+            grouped
+                .entry(root)
+                .or_default()
+                .entry(session.name.clone())
+                .or_default()
+                .push(file.clone());
+        }
+    }
+    grouped
+}
+
+/// The build command for `sessions` of the `ROOT` in the working
+/// directory.
+pub fn build_command<'a>(sessions: impl IntoIterator<Item = &'a String>) -> String {
+    let names: Vec<&str> = sessions.into_iter().map(String::as_str).collect();
+    format!("isabelle build -d . -v {}", names.join(" "))
+}
+
+/// Per-file verdicts of an `isabelle build`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionOutcome {
+    pub verified: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// Read the output of `isabelle build` for `sessions` (session → its
+/// job files), which exited with `success`.
+pub fn parse_output(output: &str, sessions: &BTreeMap<String, Vec<String>>, success: bool) -> SessionOutcome {
+    let mut finished = BTreeSet::new();
+    let mut failed = BTreeSet::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("Finished ") {
+            finished.extend(rest.split_whitespace().next().map(str::to_string));
+        } else if let Some(list) = line.strip_prefix("Unfinished session(s):") {
+            failed.extend(list.split(',').map(|s| s.trim().to_string()));
+        } else if line.ends_with(" FAILED") || line.contains(" FAILED (") {
+            failed.extend(line.split_whitespace().next().map(str::to_string));
+        }
+    }
+
+    let mut outcome = SessionOutcome::default();
+    for (session, files) in sessions {
+        let passed = !failed.contains(session) && (success || finished.contains(session));
+        if passed {
+            outcome.verified.extend(files.iter().cloned());
+        } else {
+            outcome.failed.extend(files.iter().cloned());
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = r#"
+chapter AFP
+
+(* The main development (* nested *) *)
+session Graphs (AFP) in "src" = "HOL-Library" +
+  description \<open>Graphs and their walks\<close>
+  options [timeout = 600]
+  sessions "HOL-Combinatorics"
+  theories [document = false]
+    Basic
+    "Walks/Paths"
+    "HOL-Library.Multiset"
+  document_files "root.tex"
+
+session Graphs_Examples = Graphs +
+  theories Examples (global)
+"#;
+
+    #[test]
+    fn root_files_declare_sessions_and_their_theories() {
+        let sessions = parse_root(ROOT, "afp");
+        assert_eq!(
+            sessions,
+            vec![
+                Session {
+                    name: "Graphs".into(),
+                    theories: vec!["afp/src/Basic.thy".into(), "afp/src/Walks/Paths.thy".into()],
+                },
+                Session { name: "Graphs_Examples".into(), theories: vec!["afp/Examples.thy".into()] },
+            ]
+        );
+    }
+
+    #[test]
+    fn changed_theories_select_their_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("afp/src")).unwrap();
+        std::fs::write(dir.path().join("afp/ROOT"), ROOT).unwrap();
+
+        let files: Vec<String> = ["afp/src/Basic.thy", "afp/Scratch.thy", "Other.thy"].map(String::from).to_vec();
+        let grouped = sessions(dir.path(), &files);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped["afp"]["Graphs"], vec!["afp/src/Basic.thy".to_string()]);
+        assert_eq!(build_command(grouped["afp"].keys()), "isabelle build -d . -v Graphs");
+    }
+
+    #[test]
+    fn session_verdicts_map_back_to_theory_files() {
+        let sessions: BTreeMap<String, Vec<String>> = [
+            ("Graphs".to_string(), vec!["src/Basic.thy".to_string()]),
+            ("Graphs_Examples".to_string(), vec!["Examples.thy".to_string()]),
+            ("Other".to_string(), vec!["other/O.thy".to_string()]),
+        ]
+        .into();
+        let output = "\
+Building Graphs ...
+Finished Graphs (0:01:12 elapsed time, 0:03:40 cpu time, factor 3.05)
+Building Graphs_Examples ...
+Graphs_Examples: theory Graphs_Examples.Examples
+*** Failed to finish proof (line 12 of \"/workspace/afp/Examples.thy\"):
+Graphs_Examples FAILED (see also \"isabelle build_log -H Error Graphs_Examples\")
+Unfinished session(s): Graphs_Examples";
+        let outcome = parse_output(output, &sessions, false);
+        assert_eq!(outcome.verified, vec!["src/Basic.thy".to_string()]);
+        // Other never ran: the failed build says nothing for it.
+        assert_eq!(outcome.failed, vec!["Examples.thy".to_string(), "other/O.thy".to_string()]);
+        assert_eq!(parse_output("", &sessions, true).verified.len(), 3);
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Lean 4 Lake projects
+//!
+//! A file of a Lean 4 package imports its siblings and dependencies, so
+//! checking it alone with `lean` fails. Files under a directory holding
+//! a Lake project file ([`LAKE_FILES`]) are instead built with
+//! `lake build` in that directory, with the checkout mounted into the
+//! sandbox ([`PodmanExecutor::execute_in_workspace`]). A job narrowed to
+//! changed files builds only their modules (`lake build Foo.Bar`); a full
+//! run builds the package's default targets.
+//!
+//! [`parse_output`] turns the build log into a verdict per file: a file
+//! fails if an error is reported in it or its module is listed as failed,
+//! and passes if the build succeeded or its module was built. The time
+//! Lake prints for a built module and `declaration uses 'sorry'` warnings
+//! are kept for the per-file results ([`crate::proof_items`]). Lake fetches
+//! nothing with the sandbox's network off, so dependencies must already
+//! be in the checkout (`.lake/packages`) or the image.
+//!
+//! `[provers.lean4] lake = false` in the repository manifest turns this
+//! off; the files are then checked one by one.
+//!
+//! [`PodmanExecutor::execute_in_workspace`]: super::PodmanExecutor::execute_in_workspace
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Files marking a Lake project's root.
+pub const LAKE_FILES: &[&str] = &["lakefile.lean", "lakefile.toml", "lake-manifest.json"];
+
+/// The Lake project `file` (repo-relative) belongs to: the nearest
+/// directory above it, within `checkout`, holding one of [`LAKE_FILES`].
+/// Relative to `checkout`; `""` is the root.
+pub fn project_root(checkout: &Path, file: &str) -> Option<String> {
+    let mut dir = Path::new(file).parent();
+    while let Some(rel) = dir {
+        if LAKE_FILES.iter().any(|name| checkout.join(rel).join(name).is_file()) {
+            return Some(rel.to_string_lossy().replace('\\', "/"));
+        }
+        dir = rel.parent();
+    }
+    None
+}
+
+/// `files` grouped by Lake project root; files outside any project are
+/// left out.
+pub fn projects(checkout: &Path, files: &[String]) -> BTreeMap<String, Vec<String>> {
+    let mut projects: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for file in files {
+        if !file.ends_with(".lean") || Path::new(file).is_absolute() {
+            continue;
+        }
+        if let Some(root) = project_root(checkout, file) {
+            projects.entry(root).or_default().push(file.clone());
+        }
+    }
+    projects
+}
+
+/// `file` relative to the project at `root`.
+fn within<'a>(root: &str, file: &'a str) -> &'a str {
+    if root.is_empty() {
+        file
+    } else {
+        file.strip_prefix(root).and_then(|f| f.strip_prefix('/')).unwrap_or(file)
+    }
+}
+
+/// The module a project file defines: `Foo/Bar.lean` → `Foo.Bar`. `None`
+/// for names that aren't plain identifiers, which never reach the shell.
+pub fn module_name(root: &str, file: &str) -> Option<String> {
+    let path = within(root, file).strip_suffix(".lean")?;
+    let plain = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with(|c: char| c.is_ascii_digit())
+            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    path.split('/').all(plain).then(|| path.replace('/', "."))
+}
+
+/// The build command for `files` of the project at `root`: their modules
+/// when `narrow`, else (or when a module name can't be formed) the
+/// default targets.
+pub fn build_command(root: &str, files: &[String], narrow: bool) -> String {
+    let targets: Option<Vec<String>> = if narrow {
+        files.iter().map(|file| module_name(root, file)).collect()
+    } else {
+        None
+    };
+    match targets {
+        Some(targets) if !targets.is_empty() => format!("lake build {}", targets.join(" ")),
+        _ => "lake build".to_string(),
+    }
+}
+
+/// Per-file verdicts of a `lake build`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LakeOutcome {
+    pub verified: Vec<String>,
+    pub failed: Vec<String>,
+    /// Build time of a file's module, when Lake built it and printed one.
+    pub durations_ms: BTreeMap<String, i64>,
+    /// Files with a declaration using `sorry`.
+    pub sorry: BTreeSet<String>,
+}
+
+/// A diagnostic's file, if `line` is a warning or error reported in a
+/// `.lean` file: `error: ./././Foo/Bar.lean:3:8: ...` (Lake) or
+/// `./Foo/Bar.lean:3:8: error: ...` (Lean), with the message.
+fn located(line: &str) -> Option<(String, &str, &str)> {
+    let (severity, located) = match line.split_once(": ") {
+        Some((severity @ ("error" | "warning"), rest)) => (Some(severity), rest),
+        _ => (None, line),
+    };
+    let mut parts = located.splitn(4, ':');
+    let path = parts.next()?;
+    let (line_no, col) = (parts.next()?, parts.next()?);
+    let rest = parts.next().unwrap_or_default().trim_start();
+    if !path.ends_with(".lean") || line_no.trim().parse::<u32>().is_err() || col.trim().parse::<u32>().is_err() {
+        return None;
+    }
+    let (severity, message) = match severity {
+        Some(severity) => (severity, rest),
+        None => rest.split_once(": ")?,
+    };
+    let mut path = path.trim();
+    while let Some(stripped) = path.strip_prefix("./") {
+        path = stripped;
+    }
+    Some((path.to_string(), severity, message))
+}
+
+/// `(1.2s)` or `(350ms)` at the end of a build line, in milliseconds.
+fn duration_ms(line: &str) -> Option<i64> {
+    let time = line.strip_suffix(')')?.rsplit_once(" (")?.1;
+    if let Some(ms) = time.strip_suffix("ms") {
+        return ms.parse::<f64>().ok().map(|ms| ms.round() as i64);
+    }
+    time.strip_suffix('s')?.parse::<f64>().ok().map(|s| (s * 1000.0).round() as i64)
+}
+
+/// Read the output of `lake build` run in the project at `root` for
+/// `files` (repo-relative), which exited with `success`.
+pub fn parse_output(output: &str, root: &str, files: &[String], success: bool) -> LakeOutcome {
+    let mut error_files = BTreeSet::new();
+    let mut failed_modules = BTreeSet::new();
+    let mut built_modules = BTreeMap::new();
+    let mut sorry_files = BTreeSet::new();
+    let mut in_failure_list = false;
+    for line in output.lines() {
+        let line = line.trim_end();
+        match located(line) {
+            Some((file, "error", _)) => {
+                error_files.insert(file);
+            }
+            Some((file, _, message)) if message.starts_with("declaration uses 'sorry'") => {
+                sorry_files.insert(file);
+            }
+            _ => {}
+        }
+        // The module is the last word before the time, if any.
+        let duration = duration_ms(line);
+        let module = || line.split_whitespace().rev().nth(duration.is_some() as usize).map(str::to_string);
+        if line.starts_with('✖') {
+            failed_modules.extend(module());
+        } else if line.starts_with(['✔', '⚠']) && (line.contains(" Built ") || line.contains(" Replayed ")) {
+            // A replayed module's time is the cache's, not its proofs'.
+            if let Some(module) = module() {
+                built_modules.insert(module, duration.filter(|_| line.contains(" Built ")));
+            }
+        }
+        // "Some required builds logged failures:" then "- Foo.Bar" lines.
+        if line.ends_with("logged failures:") {
+            in_failure_list = true;
+        } else if in_failure_list {
+            match line.strip_prefix("- ") {
+                Some(module) => {
+                    failed_modules.insert(module.trim().to_string());
+                }
+                None => in_failure_list = false,
+            }
+        }
+    }
+
+    let mut outcome = LakeOutcome::default();
+    for file in files {
+        let module = module_name(root, file);
+        let failed = error_files.contains(within(root, file))
+            || module.as_ref().is_some_and(|m| failed_modules.contains(m));
+        let built = module.as_ref().and_then(|m| built_modules.get(m));
+        if let Some(ms) = built.copied().flatten() {
+            outcome.durations_ms.insert(file.clone(), ms);
+        }
+        if sorry_files.contains(within(root, file)) {
+            outcome.sorry.insert(file.clone());
+        }
+        if !failed && (success || built.is_some()) {
+            outcome.verified.push(file.clone());
+        } else {
+            outcome.failed.push(file.clone());
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_group_under_the_nearest_lakefile() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("pkg/Pkg")).unwrap();
+        std::fs::create_dir_all(root.join("scratch")).unwrap();
+        std::fs::write(root.join("pkg/lakefile.toml"), "name = \"pkg\"\n").unwrap();
+
+        let files: Vec<String> = vec!["pkg/Pkg/Basic.lean".into(), "pkg/Pkg.lean".into(), "scratch/T.lean".into()];
+        let projects = projects(root, &files);
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects["pkg"], vec!["pkg/Pkg/Basic.lean".to_string(), "pkg/Pkg.lean".to_string()]);
+        assert_eq!(module_name("pkg", "pkg/Pkg/Basic.lean").as_deref(), Some("Pkg.Basic"));
+        assert_eq!(module_name("", "Foo/$(rm -rf).lean"), None);
+    }
+
+    #[test]
+    fn narrowed_builds_name_modules_and_fall_back_to_default_targets() {
+        let files = vec!["Pkg/A.lean".to_string(), "Pkg/B.lean".to_string()];
+        assert_eq!(build_command("", &files, true), "lake build Pkg.A Pkg.B");
+        assert_eq!(build_command("", &files, false), "lake build");
+        assert_eq!(build_command("", &["Pkg/a b.lean".to_string()], true), "lake build");
+    }
+
+    #[test]
+    fn build_output_becomes_per_file_verdicts() {
+        let output = "\
+✔ [1/4] Built Pkg.A (1.2s)
+✖ [2/4] Building Pkg.B
+trace: .> LEAN_PATH=./.lake/build/lib lean ./././Pkg/B.lean -R ./././. -o ./.lake/build/lib/Pkg/B.olean
+error: ./././Pkg/B.lean:3:8: unknown identifier 'x'
+error: Lean exited with code 1
+⚠ [3/4] Built Pkg.C
+warning: ./././Pkg/C.lean:1:0: declaration uses 'sorry'
+Some required builds logged failures:
+- Pkg.B
+error: build failed";
+        let files: Vec<String> = ["sub/Pkg/A.lean", "sub/Pkg/B.lean", "sub/Pkg/C.lean", "sub/Pkg/D.lean"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        let outcome = parse_output(output, "sub", &files, false);
+        assert_eq!(outcome.verified, vec!["sub/Pkg/A.lean".to_string(), "sub/Pkg/C.lean".to_string()]);
+        // D was never built: the failed build says nothing for it.
+        assert_eq!(outcome.failed, vec!["sub/Pkg/B.lean".to_string(), "sub/Pkg/D.lean".to_string()]);
+        assert_eq!(outcome.durations_ms.get("sub/Pkg/A.lean"), Some(&1200));
+        assert_eq!(outcome.sorry.iter().collect::<Vec<_>>(), ["sub/Pkg/C.lean"]);
+
+        let clean = parse_output("Build completed successfully.", "sub", &files, true);
+        assert_eq!(clean.verified.len(), 4);
+        assert!(clean.failed.is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Metamath databases
+//!
+//! A Metamath file may pull others in with `$[ file.mm $]`, and a file
+//! included elsewhere usually can't be verified alone: it uses constants
+//! and axioms its includer declares. [`databases`] takes the job's files
+//! that no other job file includes as roots and inlines their includes
+//! from the checkout into one text ([`Database::text`]), as Metamath does:
+//! each file once, paths relative to the including file, else to the
+//! repository root, never outside it. The database is verified with
+//! `verify proof *` ([`verify_command`]) in the sandbox, or sent whole to
+//! ECHIDNA.
+//!
+//! [`parse_output`] maps each `?Error` back through the inlining to the
+//! file and line it came from — by the statement label it names, else by
+//! line — and reports it as `file:line:0: error: label: message` for the
+//! check output and the diagnostics. `verify proof *` checks every proof
+//! on its own, so a file passes when no error is attributed to it, and
+//! each `$p` statement when no error names it ([`MetamathOutcome::theorems`]).
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Where inlined databases are written in the checkout for the sandbox.
+pub const WORK_DIR: &str = ".echidnabot/metamath";
+
+/// A whitespace-separated token outside comments.
+struct Token<'a> {
+    text: &'a str,
+    offset: usize,
+    line: u32,
+}
+
+fn tokens(content: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let (mut line, mut start_line) = (1, 1);
+    let mut start = None;
+    let mut in_comment = false;
+    for (i, c) in content.char_indices().chain(std::iter::once((content.len(), ' '))) {
+        if c.is_whitespace() {
+            if let Some(s) = start.take() {
+                let text = &content[s..i];
+                if in_comment {
+                    in_comment = text != "$)";
+                } else if text == "$(" {
+                    in_comment = true;
+                } else {
+                    tokens.push(Token { text, offset: s, line: start_line });
+                }
+            }
+            if c == '\n' {
+                line += 1;
+            }
+        } else if start.is_none() {
+            start = Some(i);
+            start_line = line;
+        }
+    }
+    tokens
+}
+
+/// `dir` + `path`, normalized; `None` when it leaves the checkout.
+fn join(dir: &str, path: &str) -> Option<String> {
+    if path.starts_with('/') {
+        return None;
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    for part in dir.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// The checkout file `$[ name $]` in `includer` refers to.
+fn resolve(checkout: &Path, includer: &str, name: &str) -> Option<String> {
+    let dir = includer.rsplit_once('/').map_or("", |(dir, _)| dir);
+    [join(dir, name), join("", name)]
+        .into_iter()
+        .flatten()
+        .find(|path| checkout.join(path).is_file())
+}
+
+/// A root file with its includes inlined.
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    pub root: String,
+    /// Every file inlined, the root first.
+    pub files: Vec<String>,
+    /// The job's files it covers.
+    pub listed: Vec<String>,
+    pub text: String,
+    lines: u32,
+    /// (first line in `text`, file, its line there), in order.
+    segments: Vec<(u32, String, u32)>,
+    /// Statement label → file and line declaring it.
+    labels: HashMap<String, (String, u32)>,
+    /// Labels of the `$p` statements, in order.
+    proofs: Vec<String>,
+    /// Problems found while inlining: file, line, message.
+    errors: Vec<(String, u32, String)>,
+}
+
+impl Database {
+    /// `root` (repo-relative) with its includes inlined from `checkout`.
+    pub fn load(checkout: &Path, root: &str) -> Self {
+        let mut db = Database {
+            root: root.to_string(),
+            files: vec![root.to_string()],
+            ..Database::default()
+        };
+        db.inline(checkout, root);
+        db
+    }
+
+    /// The file and line that line `line` of [`Self::text`] came from.
+    pub fn locate(&self, line: u32) -> Option<(&str, u32)> {
+        let (start, file, first) = self.segments.iter().rev().find(|(start, _, _)| *start <= line)?;
+        Some((file, first + (line - start)))
+    }
+
+    fn inline(&mut self, checkout: &Path, file: &str) {
+        let Ok(content) = std::fs::read_to_string(checkout.join(file)) else {
+            self.errors.push((file.to_string(), 1, "cannot be read".to_string()));
+            return;
+        };
+        let tokens = tokens(&content);
+        let mut copied = 0;
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            if matches!(token.text, "$a" | "$p" | "$e" | "$f") && i > 0 {
+                let label = &tokens[i - 1];
+                if token.text == "$p" && !self.labels.contains_key(label.text) {
+                    self.proofs.push(label.text.to_string());
+                }
+                self.labels
+                    .entry(label.text.to_string())
+                    .or_insert_with(|| (file.to_string(), label.line));
+            }
+            if token.text != "$[" {
+                i += 1;
+                continue;
+            }
+            let name = tokens.get(i + 1).map_or("", |t| t.text);
+            let end = tokens.get(i + 2).filter(|t| t.text == "$]");
+            self.copy(&content, copied, token.offset, file);
+            copied = end.map_or(token.offset + 2, |end| end.offset + 2);
+            match resolve(checkout, file, name) {
+                Some(path) if self.files.contains(&path) => {}
+                Some(path) => {
+                    self.files.push(path.clone());
+                    self.inline(checkout, &path);
+                }
+                None => self.errors.push((
+                    file.to_string(),
+                    token.line,
+                    format!("included file \"{}\" is not in the repository", name),
+                )),
+            }
+            i += if end.is_some() { 3 } else { 1 };
+        }
+        self.copy(&content, copied, content.len(), file);
+    }
+
+    fn copy(&mut self, content: &str, from: usize, to: usize, file: &str) {
+        if from >= to {
+            return;
+        }
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+            self.lines += 1;
+        }
+        let line = content[..from].matches('\n').count() as u32 + 1;
+        self.segments.push((self.lines + 1, file.to_string(), line));
+        let chunk = &content[from..to];
+        self.text.push_str(chunk);
+        self.lines += chunk.matches('\n').count() as u32;
+    }
+}
+
+/// The databases verifying the Metamath files among `files`
+/// (repo-relative). A file inlined into another's database is verified
+/// there, not as a root of its own.
+pub fn databases(checkout: &Path, files: &[String]) -> Vec<Database> {
+    let listed: Vec<&String> = files
+        .iter()
+        .filter(|f| f.ends_with(".mm") && !Path::new(f).is_absolute())
+        .collect();
+    let loaded: Vec<Database> = listed.iter().map(|f| Database::load(checkout, f)).collect();
+    let included: BTreeSet<&String> = loaded.iter().flat_map(|db| db.files.iter().skip(1)).collect();
+    let (roots, rest): (Vec<&Database>, Vec<&Database>) =
+        loaded.iter().partition(|db| !included.contains(&db.root));
+
+    // Files including each other are left over; keep the first of each.
+    let mut covered = BTreeSet::new();
+    let mut kept = Vec::new();
+    for db in roots.into_iter().chain(rest) {
+        if covered.contains(&db.root) {
+            continue;
+        }
+        covered.extend(db.files.iter().cloned());
+        let mut db = db.clone();
+        db.listed = listed.iter().filter(|f| db.files.contains(f)).map(|f| f.to_string()).collect();
+        kept.push(db);
+    }
+    kept
+}
+
+/// Shell command verifying the database written at `path` (from the
+/// working directory; a path this module chose, never user input).
+pub fn verify_command(path: &str) -> String {
+    format!("metamath 'read \"{}\"' 'verify proof *' 'exit'", path)
+}
+
+/// Per-file verdicts of verifying a [`Database`], with its errors located.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetamathOutcome {
+    pub verified: Vec<String>,
+    pub failed: Vec<String>,
+    /// One `file:line:0: error: ...` line per error (0-based column, as
+    /// the diagnostics parser reads Lean's).
+    pub report: Vec<String>,
+    /// Every `$p` statement when the run completed, else those an error
+    /// names.
+    pub theorems: Vec<TheoremVerdict>,
+}
+
+/// Whether a `$p` statement's proof verified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TheoremVerdict {
+    pub label: String,
+    pub file: String,
+    pub line: u32,
+    pub verified: bool,
+}
+
+fn quoted_after<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = &line[line.find(prefix)? + prefix.len()..];
+    rest.split('"').next()
+}
+
+fn number_after(line: &str, prefix: &str) -> Option<u32> {
+    let rest = &line[line.find(prefix)? + prefix.len()..];
+    rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+/// Read the output of verifying `db`, which ran to completion when `ran`.
+pub fn parse_output(db: &Database, output: &str, ran: bool) -> MetamathOutcome {
+    let mut outcome = MetamathOutcome::default();
+    let mut blamed = BTreeSet::new();
+    let mut failed_labels = BTreeSet::new();
+    for (file, line, message) in &db.errors {
+        outcome.report.push(format!("{}:{}:0: error: {}", file, line, message));
+        blamed.insert(file.clone());
+    }
+
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    for (i, header) in lines.iter().enumerate() {
+        if !header.starts_with("?Error") {
+            continue;
+        }
+        // The block after the header: the offending text, a `^^^` marker
+        // and the explanation, which comes last.
+        let message = lines[i + 1..]
+            .iter()
+            .take_while(|l| !l.is_empty() && !l.starts_with('?'))
+            .filter(|l| !l.chars().all(|c| c == '^' || c.is_whitespace()))
+            .last()
+            .copied()
+            .unwrap_or(header);
+        let label = quoted_after(header, "label \"");
+        let (file, line) = label
+            .and_then(|label| db.labels.get(label))
+            .map(|(file, line)| (file.as_str(), *line))
+            .or_else(|| number_after(header, "on line ").and_then(|line| db.locate(line)))
+            .unwrap_or((db.root.as_str(), 1));
+        outcome.report.push(match label {
+            Some(label) => format!("{}:{}:0: error: {}: {}", file, line, label, message),
+            None => format!("{}:{}:0: error: {}", file, line, message),
+        });
+        blamed.insert(file.to_string());
+        failed_labels.extend(label);
+    }
+
+    for label in &db.proofs {
+        let failed = failed_labels.contains(label.as_str());
+        if let (Some((file, line)), true) = (db.labels.get(label), ran || failed) {
+            outcome.theorems.push(TheoremVerdict {
+                label: label.clone(),
+                file: file.clone(),
+                line: *line,
+                verified: !failed,
+            });
+        }
+    }
+
+    let mut files: Vec<String> = db.listed.clone();
+    files.extend(blamed.iter().filter(|f| !db.listed.contains(f)).cloned());
+    for file in files {
+        if !blamed.contains(&file) && (ran || !blamed.is_empty()) {
+            outcome.verified.push(file);
+        } else {
+            outcome.failed.push(file);
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkout(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn includes_are_inlined_once_and_lines_map_back() {
+        let dir = checkout(&[
+            ("db/main.mm", "$[ logic.mm $]\n$[ logic.mm $]\n$( $[ ignored.mm $] $)\nth1 $p wff p $= ? $.\n$[ missing.mm $]\n"),
+            ("db/logic.mm", "$c wff $.\n\nax1 $a wff p $.\n"),
+        ]);
+        let db = Database::load(dir.path(), "db/main.mm");
+        assert_eq!(db.files, vec!["db/main.mm".to_string(), "db/logic.mm".to_string()]);
+        assert!(db.text.contains("ax1 $a") && db.text.contains("th1 $p"));
+        assert_eq!(db.labels["ax1"], ("db/logic.mm".to_string(), 3));
+        assert_eq!(db.labels["th1"], ("db/main.mm".to_string(), 4));
+        let ax1_line = db.text.lines().position(|l| l.starts_with("ax1")).unwrap() as u32 + 1;
+        assert_eq!(db.locate(ax1_line), Some(("db/logic.mm", 3)));
+        assert_eq!(db.errors.len(), 1, "{:?}", db.errors);
+
+        assert!(join("db", "../../etc/passwd").is_none());
+    }
+
+    #[test]
+    fn included_files_are_verified_through_their_includer() {
+        let dir = checkout(&[
+            ("a.mm", "$[ b.mm $]\n"),
+            ("b.mm", "$c x $.\n"),
+            ("c.mm", "$[ d.mm $]\n"),
+            ("d.mm", "$[ c.mm $]\n"),
+        ]);
+        let files: Vec<String> = ["b.mm", "a.mm", "c.mm", "d.mm"].map(String::from).to_vec();
+        let dbs = databases(dir.path(), &files);
+        let roots: Vec<&str> = dbs.iter().map(|db| db.root.as_str()).collect();
+        assert_eq!(roots, ["a.mm", "c.mm"]);
+        assert_eq!(dbs[0].listed, vec!["b.mm".to_string(), "a.mm".to_string()]);
+    }
+
+    #[test]
+    fn errors_are_attributed_to_the_declaring_file_and_label() {
+        let dir = checkout(&[
+            ("set.mm", "$[ logic.mm $]\nmain1 $p wff p $= ax1 $.\n"),
+            ("logic.mm", "$c wff $.\nax1 $a wff p $.\nbad $p wff p $= ax1 $.\n"),
+        ]);
+        let mut db = Database::load(dir.path(), "set.mm");
+        db.listed = vec!["set.mm".into(), "logic.mm".into()];
+        let output = "\
+MM> verify proof *
+?Error on line 4 of file \".echidnabot/metamath/0.mm\" at statement 5, label \"bad\", type \"$p\":
+      ax1 $.
+      ^^^
+The hypotheses of statement \"ax1\" at proof step 1 are not satisfied.
+
+MM> exit";
+        let outcome = parse_output(&db, output, true);
+        assert_eq!(outcome.verified, vec!["set.mm".to_string()]);
+        assert_eq!(outcome.failed, vec!["logic.mm".to_string()]);
+        assert_eq!(
+            outcome.report,
+            vec!["logic.mm:3:0: error: bad: The hypotheses of statement \"ax1\" at proof step 1 are not satisfied."]
+        );
+        let verdicts: Vec<(&str, u32, bool)> =
+            outcome.theorems.iter().map(|t| (t.label.as_str(), t.line, t.verified)).collect();
+        assert_eq!(verdicts, [("bad", 3, false), ("main1", 2, true)]);
+        assert!(crate::diagnostics::parse_diagnostics(&outcome.report[0])
+            .iter()
+            .any(|d| d.file.as_deref() == Some("logic.mm") && d.line == Some(3)));
+    }
+}
@@ -4,5 +4,11 @@
 //! Secure execution environment for prover verification
 
 pub mod container;
+pub mod isabelle; // Isabelle sessions: `isabelle build` of `ROOT` sessions, heaps cached per repo
+pub mod lake; // Lean 4 Lake projects: `lake build` and its per-file results
+pub mod metamath; // Metamath databases: `$[ $]` includes inlined, errors traced to file and label
+pub mod normalize; // `[normalize]`: CRLF, byte order marks and UTF-16 rewritten in the checkout
+pub mod prover_args; // `[provers.<slug>] flags` from repositories, held to a per-prover allowlist
+pub mod smtlib; // SMT-LIB scripts: logic and options to solver flags, answers, models and unsat cores
 
 pub use container::{ExecutionResult, IsolationBackend, PodmanExecutor};
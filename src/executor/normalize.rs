@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Line-ending and encoding normalization (`[normalize]` in the manifest)
+//!
+//! Proofs saved on Windows can carry CRLF line endings, a UTF-8 byte order
+//! mark, or be UTF-16 altogether, and some provers' parsers reject them.
+//! With `[normalize] enabled = true` the checkout is rewritten before any
+//! prover runs, for every file of the job's prover under `[proofs]`:
+//!
+//! - UTF-16 with a byte order mark is re-encoded as UTF-8 (`encoding`);
+//! - a leading UTF-8 byte order mark is removed (`bom`);
+//! - CRLF and lone CR line endings become LF (`line_endings`).
+//!
+//! Each rewritten file is reported — in the job log, its decision trace
+//! and at the top of the prover output — so a pass that needed it is never
+//! silent. Files that are neither UTF-8 nor marked UTF-16 are left as they
+//! are and reported too. Only the checkout changes; the repository never
+//! does.
+
+use std::path::Path;
+
+use crate::modes::NormalizeSection;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// What normalizing one file changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// The encoding the file was re-encoded from, e.g. `UTF-16LE`.
+    pub reencoded: Option<&'static str>,
+    pub bom_removed: bool,
+    pub crlf: usize,
+    /// Lone CR line endings.
+    pub cr: usize,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// e.g. `UTF-8 byte order mark removed, 12 CRLF line endings → LF`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(encoding) = self.reencoded {
+            parts.push(format!("re-encoded from {} to UTF-8", encoding));
+        }
+        if self.bom_removed {
+            parts.push("UTF-8 byte order mark removed".to_string());
+        }
+        if self.crlf > 0 {
+            parts.push(format!("{} CRLF line endings → LF", self.crlf));
+        }
+        if self.cr > 0 {
+            parts.push(format!("{} CR line endings → LF", self.cr));
+        }
+        parts.join(", ")
+    }
+}
+
+fn decode_utf16(bytes: &[u8], big_endian: bool) -> Option<String> {
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            let pair = [pair[0], pair[1]];
+            if big_endian {
+                u16::from_be_bytes(pair)
+            } else {
+                u16::from_le_bytes(pair)
+            }
+        })
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// `bytes` normalized per `section`. `None` when they are not text this
+/// can decode: neither UTF-8 nor UTF-16 with a byte order mark.
+pub fn normalize(bytes: &[u8], section: &NormalizeSection) -> Option<(String, Changes)> {
+    let mut changes = Changes::default();
+    let utf16 = match bytes {
+        [0xFF, 0xFE, rest @ ..] => Some((rest, false, "UTF-16LE")),
+        [0xFE, 0xFF, rest @ ..] => Some((rest, true, "UTF-16BE")),
+        _ => None,
+    };
+    let mut text = match utf16 {
+        Some(_) if !section.encoding => return None,
+        Some((rest, big_endian, name)) => {
+            changes.reencoded = Some(name);
+            decode_utf16(rest, big_endian)?
+        }
+        None => String::from_utf8(bytes.to_vec()).ok()?,
+    };
+    if section.bom && text.as_bytes().starts_with(UTF8_BOM) {
+        text.drain(..UTF8_BOM.len());
+        changes.bom_removed = true;
+    }
+    if section.line_endings && text.contains('\r') {
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\r' {
+                out.push(c);
+            } else if chars.next_if_eq(&'\n').is_some() {
+                changes.crlf += 1;
+                out.push('\n');
+            } else {
+                changes.cr += 1;
+                out.push('\n');
+            }
+        }
+        text = out;
+    }
+    Some((text, changes))
+}
+
+/// Normalize `files` (relative to `root`) in place. One report line per
+/// file rewritten or left undecoded; none for files already clean.
+pub fn normalize_files(root: &Path, files: &[String], section: &NormalizeSection) -> Vec<String> {
+    let mut report = Vec::new();
+    for file in files {
+        let path = root.join(file);
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        match normalize(&bytes, section) {
+            Some((_, changes)) if changes.is_empty() => {}
+            Some((text, changes)) => match std::fs::write(&path, text) {
+                Ok(()) => report.push(format!("{}: {}", file, changes.describe())),
+                Err(e) => report.push(format!("{}: not normalized: {}", file, e)),
+            },
+            None => report.push(format!("{}: left as is: not UTF-8 or marked UTF-16 text", file)),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> NormalizeSection {
+        NormalizeSection { enabled: true, ..NormalizeSection::default() }
+    }
+
+    #[test]
+    fn crlf_bom_and_utf16_become_plain_utf8() {
+        let windows = b"\xEF\xBB\xBFLemma a.\r\nProof.\rQed.\r\n";
+        let (text, changes) = normalize(windows, &all()).unwrap();
+        assert_eq!(text, "Lemma a.\nProof.\nQed.\n");
+        assert_eq!(
+            changes.describe(),
+            "UTF-8 byte order mark removed, 2 CRLF line endings → LF, 1 CR line endings → LF"
+        );
+
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("théorème\r\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let (text, changes) = normalize(&utf16, &all()).unwrap();
+        assert_eq!(text, "théorème\n");
+        assert_eq!(changes.reencoded, Some("UTF-16LE"));
+
+        let (_, clean) = normalize(b"Qed.\n", &all()).unwrap();
+        assert!(clean.is_empty());
+        assert_eq!(normalize(b"\xFF\x00latin-1?", &all()), None);
+    }
+
+    #[test]
+    fn each_transformation_can_be_turned_off() {
+        let keep_crlf = NormalizeSection { line_endings: false, ..all() };
+        let (text, changes) = normalize(b"\xEF\xBB\xBFa\r\n", &keep_crlf).unwrap();
+        assert_eq!((text.as_str(), changes.crlf), ("a\r\n", 0));
+
+        let keep_bom = NormalizeSection { bom: false, ..all() };
+        let (text, _) = normalize(b"\xEF\xBB\xBFa\r\n", &keep_bom).unwrap();
+        assert_eq!(text, "\u{FEFF}a\n");
+
+        let no_utf16 = NormalizeSection { encoding: false, ..all() };
+        assert_eq!(normalize(&[0xFF, 0xFE, b'a', 0], &no_utf16), None);
+    }
+}
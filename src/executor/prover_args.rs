@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Repository-supplied prover flags (`[provers.<slug>] flags`)
+//!
+//! A repository can ask for extra prover arguments, e.g.
+//! `[provers.z3] flags = ["-T:60", "smt.arith.solver=2"]`. They end up on
+//! a command line run by `sh -c` in the sandbox, and in ECHIDNA's verify
+//! call, so [`validate`] holds them to an allowlist first:
+//!
+//! - every flag is a single token of letters, digits and `_ . : = , + -`:
+//!   no whitespace, quotes, shell metacharacters or path separators. The
+//!   one exception is the directory of Coq's `-R` / `-Q`, which may hold
+//!   `/` but must stay inside the repository;
+//! - it is an option the prover's allowlist names. Options that load code,
+//!   read or write files, or weaken the checker are never on it, and
+//!   provers without an allowlist take no flags at all;
+//! - at most [`MAX_FLAGS`] flags of at most [`MAX_FLAG_LEN`] bytes.
+//!
+//! A job whose flags are rejected fails with the reason before any prover
+//! runs. Accepted flags follow the ones an SMT-LIB script implies on every
+//! single-file check, and are stored with the result
+//! (`ProofResult.proverArgs`). Lake projects, Isabelle sessions and
+//! Metamath databases are built without them.
+
+use crate::dispatcher::ProverKind;
+
+/// Most flags a repository may pass one prover.
+pub const MAX_FLAGS: usize = 16;
+
+/// Longest flag accepted, in bytes.
+pub const MAX_FLAG_LEN: usize = 128;
+
+/// How an allowlisted option may be written.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Exactly this token, e.g. `-time`.
+    Exact(&'static str),
+    /// This prefix and a non-empty value, e.g. `-T:` in `-T:60`.
+    Value(&'static str),
+    /// This token, its value being the next flag, e.g. `-w` `-deprecated`.
+    Next(&'static str),
+    /// This token, then a repo-relative directory and a name, e.g.
+    /// `-R` `theories` `MyLib`.
+    Dir(&'static str),
+}
+
+use self::Rule::{Dir, Exact, Next, Value};
+
+/// The values following an option, `true` for a directory.
+const NO_VALUES: &[bool] = &[];
+const ONE_VALUE: &[bool] = &[false];
+const DIR_AND_NAME: &[bool] = &[true, false];
+
+const COQ: &[Rule] = &[Exact("-time"), Exact("-q"), Next("-w"), Dir("-R"), Dir("-Q")];
+
+const LEAN: &[Rule] = &[
+    Next("-D"),
+    Value("-D"),
+    Value("--threads="),
+    Value("--memory="),
+    Value("--timeout="),
+    Value("--tstack="),
+];
+
+const AGDA: &[Rule] = &[
+    Exact("--safe"),
+    Exact("--without-K"),
+    Exact("--guardedness"),
+    Exact("--sized-types"),
+    Exact("--cubical"),
+    Exact("--prop"),
+];
+
+const IDRIS2: &[Rule] = &[Exact("--check"), Exact("--total"), Exact("--no-color")];
+
+const Z3: &[Rule] = &[Value("-T:"), Value("-t:"), Value("-memory:"), Value("-v:"), Exact("-st")];
+
+const CVC5: &[Rule] = &[
+    Value("--tlimit="),
+    Value("--tlimit-per="),
+    Value("--rlimit="),
+    Value("--rlimit-per="),
+    Value("--seed="),
+    Value("--nl-ext="),
+    Exact("--incremental"),
+    Exact("--produce-models"),
+    Exact("--produce-unsat-cores"),
+    Exact("--strings-exp"),
+    Exact("--finite-model-find"),
+    Exact("--mbqi"),
+    Exact("--enum-inst"),
+    Exact("--full-saturate-quant"),
+];
+
+/// Z3 parameters that write files or traces.
+const Z3_PARAM_DENY: &[&str] = &["file", "log", "dump", "trace"];
+
+fn rules(prover: &ProverKind) -> &'static [Rule] {
+    match prover.as_str() {
+        "coq" => COQ,
+        "lean" => LEAN,
+        "agda" => AGDA,
+        "idris2" => IDRIS2,
+        "z3" => Z3,
+        "cvc5" => CVC5,
+        _ => &[],
+    }
+}
+
+fn safe_chars(flag: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || "_.:=,+-".contains(c);
+    !flag.is_empty() && flag.chars().all(allowed)
+}
+
+/// A directory inside the repository: relative, without `..`.
+fn relative_dir(value: &str) -> bool {
+    !value.starts_with('/')
+        && value.split('/').all(|part| part != ".." && safe_chars(part))
+}
+
+/// A Z3 `module.param=value` setting, e.g. `smt.arith.solver=2`.
+fn z3_param(flag: &str) -> bool {
+    let Some((key, value)) = flag.split_once('=') else {
+        return false;
+    };
+    let word = |part: &str| {
+        !part.is_empty()
+            && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    };
+    let key_ok = key.starts_with(|c: char| c.is_ascii_lowercase()) && key.split('.').all(word);
+    key_ok && !value.is_empty() && !Z3_PARAM_DENY.iter().any(|word| key.contains(word))
+}
+
+/// Check `flags` for `prover` against its allowlist. `Err` names the first
+/// flag rejected and why.
+pub fn validate(prover: &ProverKind, flags: &[String]) -> Result<(), String> {
+    let section = format!("[provers.{}] flags", prover.as_str());
+    if flags.len() > MAX_FLAGS {
+        return Err(format!("{}: {} given, at most {} allowed", section, flags.len(), MAX_FLAGS));
+    }
+    let rules = rules(prover);
+    // Values the last option still takes.
+    let mut owed = NO_VALUES;
+    for flag in flags {
+        if flag.len() > MAX_FLAG_LEN {
+            return Err(format!("{}: a flag is longer than {} bytes", section, MAX_FLAG_LEN));
+        }
+        let value = match owed.split_first() {
+            Some((&dir, rest)) => {
+                owed = rest;
+                Some(dir)
+            }
+            None => None,
+        };
+        match value {
+            Some(true) if !relative_dir(flag) => {
+                return Err(format!(
+                    "{}: `{}` must be a directory inside the repository",
+                    section, flag
+                ));
+            }
+            Some(true) => continue,
+            _ if !safe_chars(flag) => {
+                return Err(format!(
+                    "{}: `{}` may only contain letters, digits and `_ . : = , + -`",
+                    section, flag
+                ));
+            }
+            Some(false) => continue,
+            None => {}
+        }
+        if rules.is_empty() {
+            return Err(format!("{}: {} takes no flags", section, prover.display_name()));
+        }
+        let allowed = rules.iter().find_map(|rule| match *rule {
+            Exact(option) => (flag == option).then_some(NO_VALUES),
+            Next(option) => (flag == option).then_some(ONE_VALUE),
+            Dir(option) => (flag == option).then_some(DIR_AND_NAME),
+            Value(prefix) => flag
+                .strip_prefix(prefix)
+                .filter(|value| !value.is_empty())
+                .map(|_| NO_VALUES),
+        });
+        match allowed {
+            Some(values) => owed = values,
+            None if prover.as_str() == "z3" && z3_param(flag) => {}
+            None => {
+                return Err(format!(
+                    "{}: `{}` is not an allowed {} option",
+                    section,
+                    flag,
+                    prover.display_name()
+                ));
+            }
+        }
+    }
+    if !owed.is_empty() {
+        return Err(format!("{}: the last option is missing its value", section));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(prover: &str, flags: &[&str]) -> Result<(), String> {
+        let flags: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        validate(&ProverKind::new(prover), &flags)
+    }
+
+    #[test]
+    fn allowlisted_flags_pass() {
+        assert_eq!(check("z3", &["-T:60", "smt.arith.solver=2", "sat.random_seed=3"]), Ok(()));
+        assert_eq!(check("cvc5", &["--tlimit=60000", "--strings-exp"]), Ok(()));
+        assert_eq!(check("coq", &["-time", "-w", "-notation-overridden"]), Ok(()));
+        assert_eq!(check("coq", &["-R", "formal/theories", "Ephapax", "-Q", ".", "Top"]), Ok(()));
+        assert_eq!(check("lean", &["-DmaxHeartbeats=400000", "-D", "autoImplicit=false"]), Ok(()));
+        assert_eq!(check("isabelle", &[]), Ok(()));
+    }
+
+    #[test]
+    fn injection_and_unlisted_options_are_rejected() {
+        let rejected = [
+            ("z3", vec!["-T:60; rm -rf /"]),
+            ("z3", vec!["$(curl evil)"]),
+            ("z3", vec!["smt.trace_file_name=x"]),
+            ("z3", vec!["-T:"]),
+            ("coq", vec!["-load-ml-object", "x"]),
+            ("coq", vec!["-R", "..", "X"]),
+            ("coq", vec!["-Q", "/etc", "X"]),
+            ("coq", vec!["-R", "a/../../b", "X"]),
+            ("coq", vec!["-R", "theories"]),
+            ("coq", vec!["-w"]),
+            ("lean", vec!["--plugin=evil.so"]),
+            ("lean", vec!["-t", "0"]),
+            ("cvc5", vec!["--dump-models"]),
+            ("metamath", vec!["-v"]),
+        ];
+        for (prover, flags) in rejected {
+            assert!(check(prover, &flags).is_err(), "{} {:?} accepted", prover, flags);
+        }
+        let err = check("z3", &["-T:60", "--o=/etc/passwd"]).unwrap_err();
+        assert!(err.starts_with("[provers.z3] flags: `--o=/etc/passwd`"), "{}", err);
+        assert!(check("z3", &["-st"; MAX_FLAGS + 1]).is_err());
+    }
+}
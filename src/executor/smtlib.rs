@@ -0,0 +1,456 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! SMT-LIB scripts for Z3 and CVC5
+//!
+//! A `.smt2` file says how it wants to be solved: `(set-logic ...)`,
+//! `(set-option ...)` and, for benchmarks, the expected answer
+//! `(set-info :status unsat)`. [`Script::parse`] reads those, and
+//! [`Script::solver_args`] turns them into flags so the solver prints a
+//! model after `sat` and an unsat core after `unsat` when the script
+//! doesn't ask for them itself (Z3 `-model`; CVC5 `--dump-models`,
+//! `--dump-unsat-cores`, and `--force-logic=ALL` without a logic).
+//!
+//! [`parse_output`] reads the answers back — one `sat` / `unsat` /
+//! `unknown` per `(check-sat)`, models, unsat cores and `(error ...)`
+//! responses — and [`Script::judge`] decides: the last answer must be the
+//! expected status, or without one every answer must be `unsat` (the
+//! negated goal has no model). [`header`] states the answer ahead of the
+//! solver's output in the job output, where [`counterexamples`] finds it
+//! again, with the model or core, for the PR comment.
+
+use crate::dispatcher::ProverKind;
+
+/// Marks the line [`header`] writes.
+const HEADER: &str = ";; echidnabot smt: ";
+
+/// Model text shown in a comment, at most.
+const MAX_MODEL_CHARS: usize = 1500;
+
+/// Whether `prover` reads SMT-LIB scripts.
+pub fn handles(prover: &ProverKind) -> bool {
+    matches!(prover.as_str(), "z3" | "cvc5")
+}
+
+/// A `(check-sat)` answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+impl Answer {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sat" => Some(Answer::Sat),
+            "unsat" => Some(Answer::Unsat),
+            "unknown" => Some(Answer::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Answer::Sat => "sat",
+            Answer::Unsat => "unsat",
+            Answer::Unknown => "unknown",
+        }
+    }
+}
+
+/// A token of SMT-LIB text; parentheses carry their byte offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open(usize),
+    Close(usize),
+    Atom(&'a str),
+}
+
+/// Parentheses and atoms; strings (`"..."`) and quoted symbols (`|...|`)
+/// are single atoms, `;` comments are skipped.
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'(' => tokens.push(Token::Open(i)),
+            b')' => tokens.push(Token::Close(i)),
+            b';' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'"' | b'|' => {
+                let quote = bytes[i];
+                i += 1;
+                // `""` inside a string is an escaped quote.
+                while i < bytes.len() && (bytes[i] != quote || (quote == b'"' && bytes.get(i + 1) == Some(&b'"'))) {
+                    i += if bytes[i] == quote { 2 } else { 1 };
+                }
+                tokens.push(Token::Atom(&text[start..(i + 1).min(text.len())]));
+            }
+            c if c.is_ascii_whitespace() => {}
+            _ => {
+                while i + 1 < bytes.len() && !bytes[i + 1].is_ascii_whitespace() && !b"();\"|".contains(&bytes[i + 1]) {
+                    i += 1;
+                }
+                tokens.push(Token::Atom(&text[start..=i]));
+            }
+        }
+        i += 1;
+    }
+    tokens
+}
+
+/// A top-level item: an atom, or a list with its atoms (flattened) and
+/// its text.
+enum Item<'a> {
+    Atom(&'a str),
+    List { atoms: Vec<&'a str>, nested: bool, text: &'a str },
+}
+
+fn items(text: &str) -> Vec<Item<'_>> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let (mut start, mut atoms, mut nested) = (0, Vec::new(), false);
+    for token in tokens(text) {
+        match token {
+            Token::Open(at) => {
+                if depth == 0 {
+                    (start, nested) = (at, false);
+                    atoms.clear();
+                } else {
+                    nested = true;
+                }
+                depth += 1;
+            }
+            Token::Close(at) if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    items.push(Item::List { atoms: std::mem::take(&mut atoms), nested, text: &text[start..=at] });
+                }
+            }
+            Token::Close(_) => {}
+            Token::Atom(atom) if depth == 0 => items.push(Item::Atom(atom)),
+            Token::Atom(atom) => atoms.push(atom),
+        }
+    }
+    items
+}
+
+fn unquote(s: &str) -> &str {
+    s.trim_matches(|c| c == '"' || c == '|')
+}
+
+/// What an SMT-LIB script asks of the solver.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub logic: Option<String>,
+    /// `(set-option :key value)` in order, keys without the colon.
+    pub options: Vec<(String, String)>,
+    /// `(set-info :status ...)`.
+    pub expected: Option<Answer>,
+    pub check_sats: usize,
+    /// Assertions named with `:named`, which an unsat core lists.
+    pub named: usize,
+    /// The script asks for models / unsat cores itself.
+    pub gets_model: bool,
+    pub gets_unsat_core: bool,
+}
+
+impl Script {
+    pub fn parse(content: &str) -> Self {
+        let mut script = Script::default();
+        for item in items(content) {
+            let Item::List { atoms, .. } = item else { continue };
+            let Some((command, args)) = atoms.split_first() else { continue };
+            match *command {
+                "set-logic" => script.logic = args.first().map(|l| unquote(l).to_string()),
+                "set-option" => {
+                    if let Some((key, value)) = args.split_first() {
+                        script
+                            .options
+                            .push((key.trim_start_matches(':').to_string(), value.join(" ")));
+                    }
+                }
+                "set-info" if args.first() == Some(&":status") => {
+                    script.expected = args.get(1).and_then(|s| Answer::parse(s));
+                }
+                "check-sat" | "check-sat-assuming" => script.check_sats += 1,
+                "get-model" => script.gets_model = true,
+                "get-unsat-core" => script.gets_unsat_core = true,
+                "assert" => script.named += args.iter().filter(|a| **a == ":named").count(),
+                _ => {}
+            }
+        }
+        script
+    }
+
+    /// The value of the last `(set-option :key ...)`.
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// The answer a pass needs from the last `(check-sat)`.
+    pub fn expected_answer(&self) -> Answer {
+        self.expected.unwrap_or(Answer::Unsat)
+    }
+
+    /// Flags for `prover`, placed before the script's path.
+    pub fn solver_args(&self, prover: &ProverKind) -> Vec<String> {
+        let off = |key: &str| self.option(key) == Some("false");
+        let models = self.check_sats > 0 && !self.gets_model && !off("produce-models");
+        let cores = self.named > 0 && !self.gets_unsat_core && !off("produce-unsat-cores");
+        let mut args = Vec::new();
+        match prover.as_str() {
+            "z3" => {
+                args.push("-smt2");
+                if models {
+                    args.push("-model");
+                }
+            }
+            "cvc5" => {
+                args.push("--lang=smt2");
+                if self.logic.is_none() {
+                    args.push("--force-logic=ALL");
+                }
+                if self.check_sats > 1 {
+                    args.push("--incremental");
+                }
+                if models {
+                    args.push("--dump-models");
+                }
+                if cores {
+                    args.push("--dump-unsat-cores");
+                }
+            }
+            _ => {}
+        }
+        args.into_iter().map(String::from).collect()
+    }
+
+    /// Whether `output` passes; `fallback` (the exit status) decides when
+    /// the solver gave no answer.
+    pub fn judge(&self, output: &SolverOutput, fallback: bool) -> bool {
+        if !output.errors.is_empty() {
+            return false;
+        }
+        match (self.expected, output.answers.last()) {
+            (_, None) => fallback,
+            (Some(expected), Some(last)) => *last == expected,
+            (None, Some(_)) => output.answers.iter().all(|a| *a == Answer::Unsat),
+        }
+    }
+}
+
+/// What a solver printed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverOutput {
+    pub answers: Vec<Answer>,
+    /// Models, as printed.
+    pub models: Vec<String>,
+    /// Unsat cores: the names of the assertions in each.
+    pub cores: Vec<Vec<String>>,
+    /// `(error "...")` messages.
+    pub errors: Vec<String>,
+}
+
+pub fn parse_output(output: &str) -> SolverOutput {
+    let mut parsed = SolverOutput::default();
+    for item in items(output) {
+        match item {
+            Item::Atom(atom) => parsed.answers.extend(Answer::parse(atom)),
+            Item::List { atoms, text, .. } if atoms.first() == Some(&"error") => {
+                parsed.errors.push(atoms.get(1).map_or(text, |m| unquote(m)).to_string());
+            }
+            Item::List { atoms, text, .. } if atoms.contains(&"define-fun") || atoms.first() == Some(&"model") => {
+                parsed.models.push(text.to_string());
+            }
+            Item::List { atoms, nested: false, .. } if parsed.answers.last() == Some(&Answer::Unsat) => {
+                parsed.cores.push(atoms.iter().map(|a| unquote(a).to_string()).collect());
+            }
+            Item::List { .. } => {}
+        }
+    }
+    parsed
+}
+
+/// The line put ahead of the solver output of `path`:
+/// `;; echidnabot smt: <path>: <answer> (expected <answer>, logic <logic>)`.
+pub fn header(path: &str, script: &Script, output: &SolverOutput) -> String {
+    let answer = match (output.errors.is_empty(), output.answers.last()) {
+        (false, _) => "error",
+        (true, Some(answer)) => answer.as_str(),
+        (true, None) => "no answer",
+    };
+    let mut line = format!(
+        "{}{}: {} (expected {}",
+        HEADER,
+        path,
+        answer,
+        script.expected_answer().as_str()
+    );
+    if let Some(logic) = &script.logic {
+        line.push_str(&format!(", logic {}", logic));
+    }
+    line.push(')');
+    line
+}
+
+/// An SMT file whose answer was not the expected one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub path: String,
+    /// `sat`, `unsat`, `unknown`, `error` or `no answer`.
+    pub answer: String,
+    pub expected: String,
+    pub logic: Option<String>,
+    pub model: Option<String>,
+    pub core: Option<Vec<String>>,
+    pub error: Option<String>,
+}
+
+/// The files in a job's output (see [`header`]) that got an answer other
+/// than the expected one, with what the solver printed after it.
+pub fn counterexamples(job_output: &str) -> Vec<Counterexample> {
+    let mut found = Vec::new();
+    for section in job_output.split(HEADER).skip(1) {
+        let (line, body) = section.split_once('\n').unwrap_or((section, ""));
+        let Some((path, rest)) = line.rsplit_once(": ") else { continue };
+        let Some((answer, detail)) = rest.split_once(" (expected ") else { continue };
+        let detail = detail.trim_end_matches(')');
+        let (expected, logic) = match detail.split_once(", logic ") {
+            Some((expected, logic)) => (expected, Some(logic.to_string())),
+            None => (detail, None),
+        };
+        if answer == expected {
+            continue;
+        }
+        let output = parse_output(body);
+        found.push(Counterexample {
+            path: path.to_string(),
+            answer: answer.to_string(),
+            expected: expected.to_string(),
+            logic,
+            model: output.models.last().cloned(),
+            core: output.cores.last().cloned(),
+            error: output.errors.first().cloned(),
+        });
+    }
+    found
+}
+
+/// Markdown section for the PR comment; empty without counterexamples.
+pub fn format_report(found: &[Counterexample]) -> String {
+    if found.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("### 🔍 SMT counterexamples\n");
+    for c in found {
+        out.push_str(&format!(
+            "\n**`{}`**: `{}`, expected `{}`",
+            c.path, c.answer, c.expected
+        ));
+        if let Some(logic) = &c.logic {
+            out.push_str(&format!(" (logic `{}`)", logic));
+        }
+        out.push('\n');
+        match c.answer.as_str() {
+            "sat" => {
+                out.push_str("\nThe solver found a model satisfying every assertion");
+                match &c.model {
+                    Some(model) => {
+                        let shown: String = model.chars().take(MAX_MODEL_CHARS).collect();
+                        out.push_str(":\n\n```smt2\n");
+                        out.push_str(&shown);
+                        if shown.len() < model.len() {
+                            out.push_str("\n; ... (truncated)");
+                        }
+                        out.push_str("\n```\n");
+                    }
+                    None => out.push_str(" (no model was printed).\n"),
+                }
+            }
+            "unsat" => match &c.core {
+                Some(core) if !core.is_empty() => out.push_str(&format!(
+                    "\nUnsat core: {}\n",
+                    core.iter().map(|n| format!("`{}`", n)).collect::<Vec<_>>().join(", ")
+                )),
+                _ => out.push_str("\nThe assertions are contradictory; name them (`:named`) to see an unsat core.\n"),
+            },
+            "unknown" => out.push_str("\nThe solver gave up: a timeout, or the logic is beyond its decision procedures.\n"),
+            _ => {
+                if let Some(error) = &c.error {
+                    out.push_str(&format!("\n> {}\n", error));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BENCHMARK: &str = r#"
+; regressed after the arithmetic rewrite
+(set-info :status unsat)
+(set-option :produce-unsat-cores true)
+(set-logic QF_LIA)
+(declare-const x Int)
+(assert (! (> x 0) :named pos))
+(assert (! (< x 10) :named small))
+(check-sat)
+"#;
+
+    #[test]
+    fn script_settings_become_solver_flags() {
+        let script = Script::parse(BENCHMARK);
+        assert_eq!(script.logic.as_deref(), Some("QF_LIA"));
+        assert_eq!(script.option("produce-unsat-cores"), Some("true"));
+        assert_eq!((script.expected, script.check_sats, script.named), (Some(Answer::Unsat), 1, 2));
+        assert_eq!(script.solver_args(&ProverKind::new("z3")), ["-smt2", "-model"]);
+        assert_eq!(
+            script.solver_args(&ProverKind::new("cvc5")),
+            ["--lang=smt2", "--dump-models", "--dump-unsat-cores"]
+        );
+        let bare = Script::parse("(check-sat)\n(get-model)");
+        assert_eq!(bare.solver_args(&ProverKind::new("cvc5")), ["--lang=smt2", "--force-logic=ALL"]);
+    }
+
+    #[test]
+    fn a_sat_answer_is_reported_with_its_model() {
+        let script = Script::parse(BENCHMARK);
+        let raw = "sat\n(\n  (define-fun x () Int\n    1)\n)\n";
+        let output = parse_output(raw);
+        assert_eq!(output.answers, [Answer::Sat]);
+        assert_eq!(output.models.len(), 1);
+        assert!(!script.judge(&output, true));
+
+        let job_output = format!("{}\n{}", header("bench/lia.smt2", &script, &output), raw);
+        let found = counterexamples(&job_output);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].answer.as_str(), found[0].expected.as_str()), ("sat", "unsat"));
+        assert_eq!(found[0].logic.as_deref(), Some("QF_LIA"));
+        let report = format_report(&found);
+        assert!(report.contains("**`bench/lia.smt2`**: `sat`, expected `unsat`"), "{}", report);
+        assert!(report.contains("(define-fun x () Int"));
+    }
+
+    #[test]
+    fn unsat_cores_errors_and_defaults() {
+        let output = parse_output("unsat\n(pos small)\n");
+        assert_eq!(output.cores, [vec!["pos".to_string(), "small".to_string()]]);
+        // Without `:status`, every answer must be unsat.
+        let script = Script::parse("(check-sat)");
+        assert!(script.judge(&output, false));
+        assert!(!script.judge(&parse_output("unsat\nsat\n"), true));
+        let error = parse_output("(error \"line 3 column 8: unknown constant y\")\n");
+        assert_eq!(error.errors, ["line 3 column 8: unknown constant y"]);
+        assert!(!script.judge(&error, true));
+        assert!(script.judge(&parse_output(""), true), "no answer: the exit status decides");
+    }
+}
@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Instance federation: mirroring results from another echidnabot
+//!
+//! An instance with `[federation] publish = true` and a signing key serves
+//! [`FEED_PATH`]: signed result attestations (see [`crate::attest`]) for
+//! its public repositories, oldest first, paged by a `since` timestamp
+//! and the `after` id of the last result at it.
+//!
+//! Subscribers list such instances under `[[federation.peers]]` with their
+//! public keys pinned. [`run_federation_sync`] polls each peer, checks
+//! every envelope against those keys and stores the results for locally
+//! registered repositories in `federated_results`, envelope included.
+//!
+//! A later job for the same repository, commit and prover takes a passing
+//! imported result instead of running the provers ([`imported_result`]):
+//! a commit names its content exactly, so the peer checked the same files.
+//! Only whole-repository jobs are substituted, and failures are always
+//! re-verified locally — a peer's failure may be its own environment.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::attest::{self, build_job_attestation, Envelope, KeySet, ResultSigner};
+use crate::config::{FederationPeer, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::{JobId, JobResult, ProofJob};
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{FederatedResultRecord, Repository, Visibility};
+use crate::store::Store;
+
+/// Where the feed is served.
+pub const FEED_PATH: &str = "/federation/results";
+
+/// Results per page unless the subscriber asks for fewer.
+pub const DEFAULT_FEED_LIMIT: usize = 100;
+
+/// Upper bound on a page, whatever the subscriber asks for.
+pub const MAX_FEED_LIMIT: usize = 500;
+
+/// Pages fetched from one peer per sync; the rest waits for the next one.
+const MAX_PAGES_PER_SYNC: usize = 20;
+
+/// One page of the feed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedPage {
+    pub results: Vec<Envelope>,
+    /// Pass as `since` for the next page; absent on the last one. Results
+    /// of private repositories are skipped but still advance it.
+    #[serde(default)]
+    pub next: Option<DateTime<Utc>>,
+    /// Pass as `after` with `next`: the id of the last result scanned, so
+    /// the next page resumes among results recorded at the same instant.
+    #[serde(default)]
+    pub next_after: Option<Uuid>,
+}
+
+/// Where a feed page starts: results recorded after `since`, and those
+/// recorded at `since` with an id after `after`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FeedCursor {
+    pub since: DateTime<Utc>,
+    pub after: Option<Uuid>,
+}
+
+impl FeedCursor {
+    /// Resume after everything recorded up to `since`.
+    pub fn at(since: DateTime<Utc>) -> Self {
+        Self { since, after: None }
+    }
+}
+
+/// Results recorded after `cursor`, signed by `signer`.
+pub async fn build_feed(
+    store: &dyn Store,
+    signer: &ResultSigner,
+    cursor: FeedCursor,
+    limit: usize,
+) -> Result<FeedPage> {
+    let limit = limit.clamp(1, MAX_FEED_LIMIT);
+    let scanned = store.list_results_since(cursor.since, cursor.after, limit).await?;
+    let last = (scanned.len() == limit).then(|| scanned.last()).flatten();
+    let (next, next_after) = (last.map(|r| r.created_at), last.map(|r| r.id));
+
+    let mut results = Vec::new();
+    for result in scanned {
+        let job_id = JobId(result.job_id);
+        let Some(job) = store.get_job(job_id).await? else {
+            continue;
+        };
+        match store.get_repository(job.repo_id).await? {
+            Some(repo) if repo.visibility == Visibility::Public => {}
+            _ => continue,
+        }
+        if let Some(envelope) = build_job_attestation(store, signer, job_id).await? {
+            results.push(envelope);
+        }
+    }
+    Ok(FeedPage {
+        results,
+        next,
+        next_after,
+    })
+}
+
+/// Verify `envelope` against `keys` and store it if it concerns a locally
+/// registered repository. Returns whether it was stored.
+pub async fn import_envelope(
+    store: &dyn Store,
+    peer: &str,
+    keys: &KeySet,
+    envelope: &Envelope,
+) -> Result<bool> {
+    let claims = attest::verify(envelope, keys)?;
+    let platform = crate::api::badge::parse_platform(&claims.platform)
+        .ok_or_else(|| Error::InvalidInput(format!("unknown platform '{}'", claims.platform)))?;
+    let (owner, name) = claims
+        .repo
+        .split_once('/')
+        .ok_or_else(|| Error::InvalidInput(format!("malformed repository '{}'", claims.repo)))?;
+    if store.get_repository_by_name(platform, owner, name).await?.is_none() {
+        return Ok(false);
+    }
+    let completed_at = DateTime::parse_from_rfc3339(&claims.completed_at)
+        .map_err(|e| Error::InvalidInput(format!("bad completed_at: {}", e)))?
+        .with_timezone(&Utc);
+
+    store
+        .upsert_federated_result(&FederatedResultRecord {
+            id: Uuid::new_v4(),
+            peer: peer.to_string(),
+            platform,
+            repo: claims.repo,
+            commit_sha: claims.commit,
+            prover: crate::dispatcher::ProverKind::new(&claims.prover),
+            success: claims.success,
+            verified_files: claims.verified_files,
+            failed_files: claims.failed_files,
+            completed_at,
+            envelope: serde_json::to_string(envelope)?,
+            imported_at: Utc::now(),
+        })
+        .await?;
+    Ok(true)
+}
+
+/// What one [`sync_peer`] call did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncOutcome {
+    pub imported: usize,
+    /// Envelopes that failed verification or were malformed.
+    pub rejected: usize,
+    /// Where the next sync should resume.
+    pub cursor: Option<FeedCursor>,
+}
+
+/// Fetch and import `peer`'s results after `cursor`.
+pub async fn sync_peer(
+    store: &dyn Store,
+    client: &reqwest::Client,
+    peer: &FederationPeer,
+    cursor: Option<FeedCursor>,
+) -> Result<SyncOutcome> {
+    let keys = KeySet::from_public_keys(&peer.public_keys)?;
+    let url = format!("{}{}", peer.url.trim_end_matches('/'), FEED_PATH);
+    let mut outcome = SyncOutcome {
+        cursor,
+        ..SyncOutcome::default()
+    };
+
+    for _ in 0..MAX_PAGES_PER_SYNC {
+        let cursor = outcome.cursor.unwrap_or(FeedCursor::at(DateTime::UNIX_EPOCH));
+        let mut query = vec![
+            ("since", cursor.since.to_rfc3339()),
+            ("limit", DEFAULT_FEED_LIMIT.to_string()),
+        ];
+        if let Some(after) = cursor.after {
+            query.push(("after", after.to_string()));
+        }
+        let page: FeedPage = client
+            .get(&url)
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        for envelope in &page.results {
+            match import_envelope(store, &peer.url, &keys, envelope).await {
+                Ok(true) => outcome.imported += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("Rejected federated result from {}: {}", peer.url, e);
+                    outcome.rejected += 1;
+                }
+            }
+        }
+        let next = page.next.map(|since| FeedCursor {
+            since,
+            after: page.next_after,
+        });
+        match next {
+            Some(next) if outcome.cursor.is_none_or(|c| next > c) => outcome.cursor = Some(next),
+            _ => {
+                // Last page: resume after the newest result imported.
+                if let Some(latest) = store.latest_federated_result_at(&peer.url).await? {
+                    outcome.cursor = outcome.cursor.max(Some(FeedCursor::at(latest)));
+                }
+                break;
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+/// Sync every configured peer every `interval` until shutdown. Cursors
+/// start from the newest result already imported from each peer.
+pub async fn run_federation_sync(
+    store: Arc<dyn Store>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let client = reqwest::Client::new();
+    let mut cursors: HashMap<String, Option<FeedCursor>> = HashMap::new();
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Federation sync observed shutdown signal — stopping");
+                return;
+            }
+        }
+        let config = config.load_full();
+        for peer in &config.federation.peers {
+            let cursor = match cursors.get(&peer.url) {
+                Some(cursor) => *cursor,
+                None => store
+                    .latest_federated_result_at(&peer.url)
+                    .await
+                    .unwrap_or(None)
+                    .map(FeedCursor::at),
+            };
+            match sync_peer(store.as_ref(), &client, peer, cursor).await {
+                Ok(outcome) => {
+                    if outcome.imported > 0 || outcome.rejected > 0 {
+                        tracing::info!(
+                            "Federation sync from {}: {} imported, {} rejected",
+                            peer.url,
+                            outcome.imported,
+                            outcome.rejected
+                        );
+                    }
+                    cursors.insert(peer.url.clone(), outcome.cursor);
+                }
+                Err(e) => tracing::warn!("Federation sync from {} failed: {}", peer.url, e),
+            }
+        }
+    }
+}
+
+/// A passing imported result standing in for `job`, if there is one.
+pub async fn imported_result(store: &dyn Store, repo: &Repository, job: &ProofJob) -> Option<JobResult> {
+    if !job.file_paths.is_empty() {
+        return None;
+    }
+    let imported = match store
+        .get_federated_result(repo.platform, &repo.full_name(), &job.commit_sha, job.prover.clone())
+        .await
+    {
+        Ok(Some(imported)) if imported.success => imported,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::debug!("Federated result lookup for job {} failed: {}", job.id, e);
+            return None;
+        }
+    };
+    Some(JobResult {
+        success: true,
+        message: format!(
+            "Verified {} file(s) (signed result imported from {})",
+            imported.verified_files.len(),
+            imported.peer
+        ),
+        prover_output: String::new(),
+        duration_ms: 0,
+        verified_files: imported.verified_files,
+        failed_files: imported.failed_files,
+        confidence: None,
+        axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::store::models::{ProofJobRecord, ProofResultRecord};
+    use crate::store::SqliteStore;
+
+    async fn seed(store: &SqliteStore, visibility: Visibility, name: &str) -> ProofJob {
+        seed_at(store, visibility, name, Utc::now()).await
+    }
+
+    async fn seed_at(
+        store: &SqliteStore,
+        visibility: Visibility,
+        name: &str,
+        created_at: DateTime<Utc>,
+    ) -> ProofJob {
+        let mut repo = Repository::new(Platform::GitHub, "metamath".into(), name.into());
+        repo.visibility = visibility;
+        store.create_repository(&repo).await.unwrap();
+        let job = ProofJob::new(repo.id, "c0ffee".into(), ProverKind::new("metamath"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec!["set.mm".into()],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        let mut record = ProofResultRecord::new(job.id, &result);
+        record.created_at = created_at;
+        store.save_result(&record).await.unwrap();
+        job
+    }
+
+    #[tokio::test]
+    async fn feed_round_trips_into_a_subscriber() {
+        let publisher = SqliteStore::new("sqlite::memory:").await.unwrap();
+        seed(&publisher, Visibility::Public, "set.mm").await;
+        seed(&publisher, Visibility::Private, "secret").await;
+
+        let signer = ResultSigner::from_seed([3; 32]);
+        let page = build_feed(&publisher, &signer, FeedCursor::at(DateTime::UNIX_EPOCH), 10)
+            .await
+            .unwrap();
+        assert_eq!(page.results.len(), 1, "private repositories are not published");
+        assert!(page.next.is_none());
+
+        let subscriber = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let local = Repository::new(Platform::GitHub, "metamath".into(), "set.mm".into());
+        subscriber.create_repository(&local).await.unwrap();
+        let peer = "https://peer.example.org";
+        let keys = KeySet { keys: vec![signer.published_key()] };
+        assert!(import_envelope(&subscriber, peer, &keys, &page.results[0]).await.unwrap());
+
+        let job = ProofJob::new(local.id, "c0ffee".into(), ProverKind::new("metamath"), vec![]);
+        let result = imported_result(&subscriber, &local, &job).await.expect("substituted");
+        assert!(result.success);
+        assert_eq!(result.verified_files, vec!["set.mm".to_string()]);
+
+        let partial = ProofJob::new(local.id, "c0ffee".into(), ProverKind::new("metamath"), vec!["a.mm".into()]);
+        assert!(imported_result(&subscriber, &local, &partial).await.is_none());
+
+        let stranger = KeySet { keys: vec![ResultSigner::from_seed([4; 32]).published_key()] };
+        assert!(import_envelope(&subscriber, peer, &stranger, &page.results[0]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pages_split_results_recorded_at_the_same_instant() {
+        let publisher = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let instant = Utc::now();
+        for name in ["a", "b", "c"] {
+            seed_at(&publisher, Visibility::Public, name, instant).await;
+        }
+        let signer = ResultSigner::from_seed([3; 32]);
+
+        let mut cursor = FeedCursor::at(DateTime::UNIX_EPOCH);
+        let mut repos = Vec::new();
+        loop {
+            let page = build_feed(&publisher, &signer, cursor, 2).await.unwrap();
+            let keys = KeySet { keys: vec![signer.published_key()] };
+            for envelope in &page.results {
+                repos.push(attest::verify(envelope, &keys).unwrap().repo);
+            }
+            let Some(since) = page.next else { break };
+            assert_eq!(since, instant);
+            cursor = FeedCursor {
+                since,
+                after: page.next_after,
+            };
+        }
+        repos.sort();
+        assert_eq!(repos, ["metamath/a", "metamath/b", "metamath/c"]);
+    }
+}
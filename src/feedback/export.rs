@@ -0,0 +1,472 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Training-example export (`[corpus.export]`)
+//!
+//! ECHIDNA's ML team trains on (goal, tactic) pairs. For repositories that
+//! opted in (`register --training-export`), passing jobs harvest those
+//! pairs from their verified files ([`extract_examples`]) into a queue in
+//! the store; [`run_training_export`] ships the queue in batches of
+//! `batch_size`, as JSON Lines POSTed to `endpoint` or written as files
+//! under `directory`.
+//!
+//! Examples are anonymized: comments and theorem names are dropped, and
+//! nothing naming the repository, file or commit is kept. A pair is queued
+//! once however often, and wherever, it is verified.
+//!
+//! Harvested: Coq (`Theorem ... Proof. ... Qed.`) and Lean 4
+//! (`theorem ... := by ...`). Term-mode proofs have no tactic script and
+//! are skipped, as are proofs ending in `Admitted` or using `sorry`.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::{CorpusExportConfig, SharedConfig};
+use crate::depgraph::strip_nested_comments;
+use crate::dispatcher::ProverKind;
+use crate::error::{Error, Result};
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::TrainingExampleRecord;
+use crate::store::Store;
+
+/// Coq commands that open a proof of a named statement.
+const COQ_THEOREM_KEYWORDS: &[&str] = &[
+    "Theorem",
+    "Lemma",
+    "Corollary",
+    "Proposition",
+    "Fact",
+    "Remark",
+    "Example",
+];
+
+/// One harvested pair, before it is queued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// The statement, with the theorem's binders turned into a `forall`.
+    pub goal: String,
+    /// The tactic script that proved it.
+    pub tactic: String,
+}
+
+/// Provers examples can be harvested for.
+pub fn supports(prover: &ProverKind) -> bool {
+    matches!(prover.as_str(), "coq" | "lean")
+}
+
+/// (goal, tactic) pairs of the tactic proofs in one file.
+pub fn extract_examples(prover: &ProverKind, content: &str) -> Vec<Example> {
+    let examples = match prover.as_str() {
+        "coq" => coq_examples(content),
+        "lean" => lean_examples(content),
+        _ => Vec::new(),
+    };
+    examples
+        .into_iter()
+        .filter(|e| !e.goal.is_empty() && !e.tactic.is_empty())
+        .filter(|e| !e.tactic.split(|c: char| !c.is_alphanumeric()).any(|w| w == "sorry" || w == "admit"))
+        .collect()
+}
+
+fn coq_examples(content: &str) -> Vec<Example> {
+    // Sentences end at a `.` followed by whitespace (see `depgraph`).
+    let source = strip_nested_comments(content, "(*", "*)")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut sentences = source.split(". ").map(|s| s.trim().trim_end_matches('.').trim());
+
+    let mut examples = Vec::new();
+    while let Some(sentence) = sentences.next() {
+        let Some((keyword, rest)) = sentence.split_once(' ') else {
+            continue;
+        };
+        if !COQ_THEOREM_KEYWORDS.contains(&keyword) {
+            continue;
+        }
+        let goal = binders_to_goal(drop_name(rest), "forall");
+
+        let mut script = Vec::new();
+        let mut closed = false;
+        for sentence in sentences.by_ref() {
+            if script.is_empty() && (sentence == "Proof" || sentence.starts_with("Proof ")) {
+                continue;
+            }
+            // `} Qed` / `- Qed`: the structure before the closing command
+            // belongs to the script.
+            let core = sentence.trim_start_matches(|c: char| matches!(c, '}' | '-' | '+' | '*' | ' '));
+            let prefix = sentence[..sentence.len() - core.len()].trim();
+            match core {
+                "Qed" | "Defined" => {
+                    if !prefix.is_empty() {
+                        script.push(prefix.to_string());
+                    }
+                    closed = true;
+                    break;
+                }
+                "Admitted" | "Abort" => break,
+                _ => script.push(format!("{sentence}.")),
+            }
+        }
+        if closed {
+            examples.push(Example {
+                goal,
+                tactic: script.join(" "),
+            });
+        }
+    }
+    examples
+}
+
+fn lean_examples(content: &str) -> Vec<Example> {
+    let source = strip_nested_comments(content, "/-", "-/");
+    let lines: Vec<&str> = source
+        .lines()
+        .map(|l| l.split("--").next().unwrap_or_default().trim_end())
+        .collect();
+
+    // A declaration runs from an unindented line to the next one.
+    let mut examples = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = i;
+        i += 1;
+        while i < lines.len() && (lines[i].is_empty() || lines[i].starts_with(char::is_whitespace)) {
+            i += 1;
+        }
+        if let Some(example) = lean_declaration(&lines[start..i]) {
+            examples.push(example);
+        }
+    }
+    examples
+}
+
+fn lean_declaration(block: &[&str]) -> Option<Example> {
+    let text = block.join("\n");
+    let mut header = text.trim_start();
+    if header.starts_with("@[") {
+        header = header[header.find(']')? + 1..].trim_start();
+    }
+    for modifier in ["private ", "protected ", "noncomputable "] {
+        header = header.strip_prefix(modifier).unwrap_or(header).trim_start();
+    }
+    let rest = header
+        .strip_prefix("theorem ")
+        .or_else(|| header.strip_prefix("lemma "))?;
+
+    let assign = rest.find(":=")?;
+    let body = rest[assign + 2..].trim_start();
+    let script = body.strip_prefix("by")?;
+    if script.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+
+    let spec = rest[..assign].split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(Example {
+        goal: binders_to_goal(drop_name(&spec), "∀"),
+        tactic: dedent(script),
+    })
+}
+
+/// A tactic block with its common indentation removed.
+fn dedent(script: &str) -> String {
+    let mut lines = script.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut out: Vec<&str> = Vec::new();
+    if !first.is_empty() {
+        out.push(first);
+    }
+    out.extend(rest.iter().map(|l| l.get(indent..).unwrap_or("")));
+    while out.last().is_some_and(|l| l.trim().is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+/// The declaration after its name: binders and statement.
+fn drop_name(rest: &str) -> &str {
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| c.is_whitespace() || matches!(c, ':' | '(' | '{' | '[' | '⦃'))
+        .unwrap_or(rest.len());
+    rest[end..].trim()
+}
+
+/// `(n : nat) : n + 0 = n` as `forall (n : nat), n + 0 = n`.
+fn binders_to_goal(spec: &str, forall: &str) -> String {
+    let spec = spec.trim();
+    if let Some(statement) = spec.strip_prefix(':') {
+        return statement.trim().to_string();
+    }
+    let mut depth = 0i32;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' | '{' | '[' | '⦃' => depth += 1,
+            ')' | '}' | ']' | '⦄' => depth -= 1,
+            ':' if depth == 0 && !spec[i + 1..].starts_with('=') => {
+                return format!("{forall} {}, {}", spec[..i].trim(), spec[i + 1..].trim());
+            }
+            _ => {}
+        }
+    }
+    String::new()
+}
+
+/// Harvest the verified files of a passing job and queue their examples.
+/// Returns how many were new; unreadable files are skipped.
+pub async fn queue_from_checkout(
+    store: &dyn Store,
+    prover: &ProverKind,
+    checkout: &Path,
+    files: &[String],
+    config: &CorpusExportConfig,
+) -> Result<usize> {
+    if !supports(prover) {
+        return Ok(0);
+    }
+    let mut records = Vec::new();
+    for path in files {
+        let full = if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            checkout.join(path)
+        };
+        let Ok(content) = tokio::fs::read_to_string(&full).await else {
+            continue;
+        };
+        for example in extract_examples(prover, &content) {
+            if example.goal.len() > config.max_example_bytes
+                || example.tactic.len() > config.max_example_bytes
+            {
+                continue;
+            }
+            records.push(TrainingExampleRecord::new(prover.clone(), example.goal, example.tactic));
+        }
+    }
+    if records.is_empty() {
+        return Ok(0);
+    }
+    store.queue_training_examples(&records).await
+}
+
+/// One exported line.
+#[derive(Serialize)]
+struct ExportLine<'a> {
+    /// The example's digest; stable across instances and runs.
+    id: &'a str,
+    prover: &'a str,
+    goal: &'a str,
+    tactic: &'a str,
+}
+
+/// A batch as JSON Lines.
+pub fn render_batch(batch: &[TrainingExampleRecord]) -> Result<String> {
+    let mut out = String::new();
+    for example in batch {
+        out.push_str(&serde_json::to_string(&ExportLine {
+            id: &example.digest,
+            prover: example.prover.as_str(),
+            goal: &example.goal,
+            tactic: &example.tactic,
+        })?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Ship every pending example, `batch_size` at a time, and return how
+/// many were exported. Stops at the first batch that cannot be shipped;
+/// it stays queued for the next run.
+pub async fn export_pending(
+    store: &dyn Store,
+    config: &CorpusExportConfig,
+    client: &reqwest::Client,
+) -> Result<usize> {
+    let batch_size = config.batch_size.max(1);
+    let mut exported = 0;
+    loop {
+        let batch = store.list_pending_training_examples(batch_size).await?;
+        if batch.is_empty() {
+            break;
+        }
+        ship(config, client, render_batch(&batch)?).await?;
+        let ids: Vec<Uuid> = batch.iter().map(|e| e.id).collect();
+        store.mark_training_examples_exported(&ids, Utc::now()).await?;
+        exported += batch.len();
+        if batch.len() < batch_size {
+            break;
+        }
+    }
+    Ok(exported)
+}
+
+async fn ship(config: &CorpusExportConfig, client: &reqwest::Client, body: String) -> Result<()> {
+    if let Some(endpoint) = &config.endpoint {
+        let mut request = client
+            .post(endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body);
+        if let Some(token) = &config.token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await?.error_for_status()?;
+        return Ok(());
+    }
+    let Some(dir) = &config.directory else {
+        return Err(Error::Config(
+            "[corpus.export] needs an endpoint or a directory".to_string(),
+        ));
+    };
+    tokio::fs::create_dir_all(dir).await?;
+    let name = format!(
+        "batch_{}_{}.jsonl",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        &Uuid::new_v4().simple().to_string()[..8]
+    );
+    // Written aside and renamed, so readers never see half a batch.
+    let staging = dir.join(format!(".{name}.tmp"));
+    tokio::fs::write(&staging, body).await?;
+    tokio::fs::rename(&staging, dir.join(name)).await?;
+    Ok(())
+}
+
+/// Periodically ship the queue until shutdown.
+pub async fn run_training_export(
+    store: Arc<dyn Store>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let client = reqwest::Client::new();
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Training export observed shutdown signal — stopping");
+                return;
+            }
+        }
+        match export_pending(store.as_ref(), &config.load_full().corpus.export, &client).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Exported {} training example(s)", n),
+            Err(e) => tracing::warn!("Training export failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::SqliteStore;
+
+    #[test]
+    fn coq_examples_drop_names_and_comments() {
+        let source = r#"
+(* Author: someone@example.org *)
+Require Import Arith.
+
+Lemma add_zero (n : nat) : n + 0 = n.
+Proof.
+  (* the usual *)
+  induction n; simpl.
+  - reflexivity.
+  - rewrite IHn. reflexivity.
+Qed.
+
+Theorem skipped : 1 = 1.
+Proof. Admitted.
+
+Example one: 0 + 1 = 1.
+Proof. { reflexivity. } Qed.
+"#;
+        let examples = extract_examples(&ProverKind::new("coq"), source);
+        assert_eq!(
+            examples,
+            vec![
+                Example {
+                    goal: "forall (n : nat), n + 0 = n".into(),
+                    tactic: "induction n; simpl. - reflexivity. - rewrite IHn. reflexivity.".into(),
+                },
+                Example {
+                    goal: "0 + 1 = 1".into(),
+                    tactic: "{ reflexivity. }".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lean_examples_keep_tactic_blocks() {
+        let source = r#"
+import Mathlib
+
+/-- Addition of zero. -/
+theorem add_zero' (n : Nat) :
+    n + 0 = n := by
+  induction n with
+  | zero => rfl
+  | succ k ih => simp
+
+-- term proofs have no tactic script
+theorem term_mode : 1 = 1 := rfl
+
+@[simp] lemma todo : 2 = 2 := by sorry
+"#;
+        let examples = extract_examples(&ProverKind::new("lean"), source);
+        assert_eq!(
+            examples,
+            vec![Example {
+                goal: "∀ (n : Nat), n + 0 = n".into(),
+                tactic: "induction n with\n| zero => rfl\n| succ k ih => simp".into(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn export_writes_batches_to_directory() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let checkout = tempfile::tempdir().unwrap();
+        std::fs::write(
+            checkout.path().join("A.v"),
+            "Lemma a : True. Proof. exact I. Qed.\nLemma b : 1 = 1. Proof. reflexivity. Qed.\n",
+        )
+        .unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let config = CorpusExportConfig {
+            enabled: true,
+            directory: Some(out.path().to_path_buf()),
+            batch_size: 1,
+            ..CorpusExportConfig::default()
+        };
+
+        let coq = ProverKind::new("coq");
+        let files = vec!["A.v".to_string()];
+        assert_eq!(queue_from_checkout(&store, &coq, checkout.path(), &files, &config).await.unwrap(), 2);
+        assert_eq!(queue_from_checkout(&store, &coq, checkout.path(), &files, &config).await.unwrap(), 0);
+
+        let client = reqwest::Client::new();
+        assert_eq!(export_pending(&store, &config, &client).await.unwrap(), 2);
+        assert_eq!(export_pending(&store, &config, &client).await.unwrap(), 0);
+
+        let batches: Vec<_> = std::fs::read_dir(out.path()).unwrap().flatten().collect();
+        assert_eq!(batches.len(), 2);
+        let line = std::fs::read_to_string(batches[0].path()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(parsed["prover"], "coq");
+        assert!(parsed.get("file").is_none() && parsed.get("repo").is_none());
+    }
+}
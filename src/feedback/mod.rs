@@ -7,9 +7,11 @@
 //! This module is the *local* half of the double loop — consulting the
 //! `tactic_outcomes` store to reweight ECHIDNA's ML-produced suggestions —
 //! plus the corpus-delta trigger that feeds successful proofs back to
-//! `echidna/training_data/` for the retrainer (Package 5 / `just corpus-refresh`).
+//! `echidna/training_data/` for the retrainer (Package 5 / `just corpus-refresh`),
+//! and the opt-in (goal, tactic) export for ECHIDNA's ML team (`export`).
 
 pub mod corpus_delta;
+pub mod export;
 pub mod reranker;
 
 pub use corpus_delta::{CorpusDelta, DeltaRow, DeltaSource, RefreshStatus};
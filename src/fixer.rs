@@ -0,0 +1,451 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Fixer: proposing verified proof repairs (`[fixer]` in the manifest)
+//!
+//! Advisor output lists ECHIDNA's tactic suggestions for a failure. With
+//! `[fixer] enabled = true` the bot goes one step further:
+//!
+//! 1. The first failing file is read at the job's commit, and the line the
+//!    prover output blames is replaced by each of the top
+//!    `[fixer] max_attempts` suggestions in turn, keeping its indentation.
+//! 2. Each patched file is sent to ECHIDNA (`verify_proof`). Only a patch
+//!    that verifies is proposed; the rest are dropped silently.
+//! 3. A PR job gets a review comment with a suggested change on that line,
+//!    for the author to accept or not. A push job gets a PR from
+//!    `echidnabot/repair/<commit>-<file>` into its branch, labelled with
+//!    `[fixer] labels`, opened only while the branch still points at the
+//!    job's commit.
+//!
+//! Both say they are machine-generated and need human review. Nothing is
+//! merged or committed to an existing branch.
+//!
+//! Without `[fixer]`, an Advisor or Regulator report on a PR still puts
+//! the top suggestion that maps to an edit of the failing line on the diff
+//! as a suggested change ([`suggest_inline`]), marked unverified, so the
+//! author can apply it in one click.
+
+use crate::adapters::{NewPullRequest, PlatformAdapter, PrId, RepoId, ReviewCommentLocation};
+use crate::audit::AuditAction;
+use crate::dispatcher::{EchidnaClient, ProofStatus, ProverKind, TacticSuggestion};
+use crate::modes::FixerSection;
+use crate::scheduler::ProofJob;
+use crate::store::models::Repository;
+use crate::store::Store;
+
+/// Branch prefix of repair PRs.
+pub const BRANCH_PREFIX: &str = "echidnabot/repair/";
+
+/// A suggestion that made a failing file verify.
+#[derive(Debug, Clone)]
+pub struct Repair {
+    pub file_path: String,
+    /// 1-based line replaced.
+    pub line: u32,
+    pub original: String,
+    pub replacement: String,
+    /// The whole file with the replacement in place.
+    pub patched: String,
+    pub suggestion: TacticSuggestion,
+}
+
+/// `source` with line `line` (1-based) replaced by `tactic`, indented as
+/// the line it replaces. `None` when the line doesn't exist or already
+/// reads `tactic`.
+pub fn patch(source: &str, line: u32, tactic: &str) -> Option<(String, String, String)> {
+    let index = (line as usize).checked_sub(1)?;
+    let original = source.split('\n').nth(index)?.trim_end_matches('\r');
+    let indent = &original[..original.len() - original.trim_start().len()];
+    let replacement = format!("{}{}", indent, tactic.trim());
+    if replacement == original || tactic.trim().is_empty() {
+        return None;
+    }
+    let patched: Vec<&str> = source
+        .split('\n')
+        .enumerate()
+        .map(|(i, l)| if i == index { replacement.as_str() } else { l })
+        .collect();
+    Some((patched.join("\n"), original.to_string(), replacement))
+}
+
+/// Try the first `max_attempts` of `suggestions` at `line` of `source`,
+/// returning the first whose patch ECHIDNA verifies.
+pub async fn find_repair(
+    echidna: &EchidnaClient,
+    prover: &ProverKind,
+    file_path: &str,
+    source: &str,
+    line: u32,
+    suggestions: &[TacticSuggestion],
+    max_attempts: u32,
+) -> Option<Repair> {
+    for suggestion in suggestions.iter().take(max_attempts as usize) {
+        let Some((patched, original, replacement)) = patch(source, line, &suggestion.tactic) else {
+            continue;
+        };
+        match echidna.verify_proof(prover, &patched).await {
+            Ok(result) if result.status == ProofStatus::Verified => {
+                return Some(Repair {
+                    file_path: file_path.to_string(),
+                    line,
+                    original,
+                    replacement,
+                    patched,
+                    suggestion: suggestion.clone(),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::debug!("Fixer: verifying a patch of {} failed: {}", file_path, e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+fn why(repair: &Repair, prover: &ProverKind) -> String {
+    let mut out = format!(
+        "Replacing line {} of `{}` with ECHIDNA's suggestion `{}` ({:.0}% confidence) \
+         makes the file verify with {}.",
+        repair.line,
+        repair.file_path,
+        repair.suggestion.tactic.trim(),
+        repair.suggestion.confidence * 100.0,
+        prover.display_name()
+    );
+    if let Some(explanation) = &repair.suggestion.explanation {
+        out.push_str(&format!("\n\n> {}", explanation));
+    }
+    out
+}
+
+const REVIEW_NOTE: &str = "⚠️ **Machine-generated repair — needs human review.** The patched \
+     file verifies, but check that the proof still says what you meant before accepting it.";
+
+/// Review comment body with a suggested change for `repair.line`.
+pub fn suggestion_comment(repair: &Repair, prover: &ProverKind) -> String {
+    format!(
+        "## 🔧 echidnabot: suggested repair\n\n{}\n\n```suggestion\n{}\n```\n\n{}",
+        why(repair, prover),
+        repair.replacement,
+        REVIEW_NOTE
+    )
+}
+
+/// Review comment body offering `suggestion` for line `line` of
+/// `file_path`, as `replacement`, without having verified it.
+pub fn unverified_suggestion_comment(
+    file_path: &str,
+    line: u32,
+    replacement: &str,
+    suggestion: &TacticSuggestion,
+    prover: &ProverKind,
+) -> String {
+    let mut out = format!(
+        "## 💡 echidnabot: tactic suggestion\n\n{} failed at line {} of `{}`. ECHIDNA \
+         suggests `{}` ({:.0}% confidence).",
+        prover.display_name(),
+        line,
+        file_path,
+        suggestion.tactic.trim(),
+        suggestion.confidence * 100.0
+    );
+    if let Some(explanation) = &suggestion.explanation {
+        out.push_str(&format!("\n\n> {}", explanation));
+    }
+    out.push_str(&format!(
+        "\n\n```suggestion\n{}\n```\n\n_Not verified: committing it re-runs the check._",
+        replacement
+    ));
+    out
+}
+
+/// Post the first of `suggestions` that is an edit of `line` in
+/// `failed_file` as a suggested change on each of `prs`. Returns the PRs
+/// it was posted on; a line outside a PR's diff is refused by the platform
+/// and leaves that PR with its plain result comment. Best-effort.
+#[allow(clippy::too_many_arguments)]
+pub async fn suggest_inline(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    job: &ProofJob,
+    failed_file: &str,
+    line: u32,
+    suggestions: &[TacticSuggestion],
+    prs: &[u64],
+) -> Vec<u64> {
+    if prs.is_empty() || !adapter.capabilities().review_comments {
+        return vec![];
+    }
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let source = adapter.get_file_contents(&repo_id, Some(&job.commit_sha), failed_file).await;
+    let source = match source {
+        Ok(Some(source)) => source,
+        Ok(None) => return vec![],
+        Err(e) => {
+            tracing::debug!("Inline suggestion: {} not read: {}", failed_file, e);
+            return vec![];
+        }
+    };
+    let edit = suggestions.iter().find_map(|suggestion| {
+        let (_, _, replacement) = patch(&source, line, &suggestion.tactic)?;
+        Some((suggestion, replacement))
+    });
+    let Some((suggestion, replacement)) = edit else {
+        return vec![];
+    };
+    let body =
+        unverified_suggestion_comment(failed_file, line, &replacement, suggestion, &job.prover);
+
+    let mut posted = Vec::new();
+    for &pr_number in prs {
+        let location = ReviewCommentLocation {
+            commit_sha: job.commit_sha.clone(),
+            path: failed_file.to_string(),
+            line,
+        };
+        let pr = PrId(pr_number.to_string());
+        match adapter.create_review_comment(&repo_id, pr, &body, location).await {
+            Ok(_) => {
+                crate::audit::record(
+                    store,
+                    crate::audit::BOT,
+                    AuditAction::ReviewCommentPosted,
+                    Some(repo),
+                    Some(format!("PR #{}", pr_number)),
+                    &body,
+                )
+                .await;
+                posted.push(pr_number);
+            }
+            Err(e) => tracing::debug!(
+                "Inline suggestion not posted on {} PR #{}: {}",
+                repo.full_name(),
+                pr_number,
+                e
+            ),
+        }
+    }
+    posted
+}
+
+/// Head branch for repairing `file_path` at `commit_sha`.
+pub fn branch_name(commit_sha: &str, file_path: &str) -> String {
+    let slug: String = file_path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let short = &commit_sha[..commit_sha.len().min(7)];
+    format!("{}{}-{}", BRANCH_PREFIX, short, slug.trim_matches('-'))
+}
+
+/// The repair PR into `base`, whose head commit is `commit_sha`.
+pub fn pull_request(
+    repair: &Repair,
+    prover: &ProverKind,
+    base: &str,
+    commit_sha: &str,
+) -> NewPullRequest {
+    let body = format!(
+        "{}\n\n```diff\n-{}\n+{}\n```\n\nThe proof failed on {} at `{}`.\n\n{}",
+        why(repair, prover),
+        repair.original,
+        repair.replacement,
+        base,
+        commit_sha,
+        REVIEW_NOTE
+    );
+    NewPullRequest {
+        title: format!("Repair proof in {}", repair.file_path),
+        body,
+        base: base.to_string(),
+        head: branch_name(commit_sha, &repair.file_path),
+        commit_message: format!(
+            "Repair {} with `{}`",
+            repair.file_path,
+            repair.suggestion.tactic.trim()
+        ),
+        files: vec![],
+        changes: vec![(repair.file_path.clone(), repair.patched.clone())],
+    }
+}
+
+/// Propose a repair for `job`'s first failing file: a suggested change on
+/// each of `prs`, or a repair PR for a push. `line` is where the prover
+/// output put the error. Best-effort: failures are logged, never returned.
+#[allow(clippy::too_many_arguments)]
+pub async fn propose(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    echidna: &EchidnaClient,
+    repo: &Repository,
+    job: &ProofJob,
+    failed_file: &str,
+    line: u32,
+    suggestions: &[TacticSuggestion],
+    section: &FixerSection,
+    prs: &[u64],
+) {
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let source = adapter.get_file_contents(&repo_id, Some(&job.commit_sha), failed_file).await;
+    let source = match source {
+        Ok(Some(source)) => source,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::debug!("Fixer: {} not read from {}: {}", failed_file, repo.full_name(), e);
+            return;
+        }
+    };
+    let Some(repair) = find_repair(
+        echidna,
+        &job.prover,
+        failed_file,
+        &source,
+        line,
+        suggestions,
+        section.max_attempts,
+    )
+    .await
+    else {
+        return;
+    };
+    crate::decision::record(store, job.id, |t| {
+        t.policy("fixer", format!("line {} of {} repaired", repair.line, repair.file_path))
+    })
+    .await;
+
+    if !prs.is_empty() {
+        let body = suggestion_comment(&repair, &job.prover);
+        for pr_number in prs {
+            let location = ReviewCommentLocation {
+                commit_sha: job.commit_sha.clone(),
+                path: repair.file_path.clone(),
+                line: repair.line,
+            };
+            let pr = PrId(pr_number.to_string());
+            match adapter.create_review_comment(&repo_id, pr, &body, location).await {
+                Ok(_) => {
+                    crate::audit::record(
+                        store,
+                        crate::audit::BOT,
+                        AuditAction::ReviewCommentPosted,
+                        Some(repo),
+                        Some(format!("PR #{}", pr_number)),
+                        &body,
+                    )
+                    .await
+                }
+                Err(e) => tracing::debug!(
+                    "Fixer: suggestion not posted on {} PR #{}: {}",
+                    repo.full_name(),
+                    pr_number,
+                    e
+                ),
+            }
+        }
+        return;
+    }
+
+    // A push: the repair targets the branch only while it still points
+    // at the commit the patch was verified against.
+    let Some(branch) = job.branch.as_deref() else {
+        return;
+    };
+    if job.pr_number.is_some() || !adapter.capabilities().pull_requests {
+        return;
+    }
+    match adapter.get_branch_head(&repo_id, branch).await {
+        Ok(head) if head == job.commit_sha => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::debug!("Fixer: head of {} unknown: {}", branch, e);
+            return;
+        }
+    }
+    let pr = pull_request(&repair, &job.prover, branch, &job.commit_sha);
+    let body = pr.body.clone();
+    match adapter.create_pull_request(&repo_id, pr).await {
+        Ok(id) => {
+            crate::audit::record(
+                store,
+                crate::audit::BOT,
+                AuditAction::RepairPrOpened,
+                Some(repo),
+                Some(format!("PR #{}", id.0)),
+                &body,
+            )
+            .await;
+            if !section.labels.is_empty() {
+                if let Err(e) = adapter.add_labels(&repo_id, id.clone(), &section.labels).await {
+                    tracing::debug!("Fixer: repair PR #{} not labelled: {}", id.0, e);
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Repair PR for {} not opened: {}", repo.full_name(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repair() -> Repair {
+        let source = "Lemma a : 1 + 1 = 2.\nProof.\n  auto.\nQed.\n";
+        let (patched, original, replacement) = patch(source, 3, "reflexivity.").unwrap();
+        Repair {
+            file_path: "theories/Arith.v".into(),
+            line: 3,
+            original,
+            replacement,
+            patched,
+            suggestion: TacticSuggestion {
+                tactic: "reflexivity.".into(),
+                confidence: 0.82,
+                explanation: None,
+            },
+        }
+    }
+
+    #[test]
+    fn patch_replaces_the_failing_line_keeping_indentation() {
+        let r = repair();
+        assert_eq!(r.patched, "Lemma a : 1 + 1 = 2.\nProof.\n  reflexivity.\nQed.\n");
+        assert_eq!((r.original.as_str(), r.replacement.as_str()), ("  auto.", "  reflexivity."));
+        assert_eq!(patch("Qed.\n", 9, "auto."), None, "no such line");
+        assert_eq!(patch("  auto.\n", 1, "auto."), None, "no change");
+    }
+
+    #[test]
+    fn unverified_suggestion_is_a_one_click_change() {
+        let r = repair();
+        let body = unverified_suggestion_comment(
+            &r.file_path,
+            r.line,
+            &r.replacement,
+            &r.suggestion,
+            &ProverKind::new("coq"),
+        );
+        assert!(body.contains("line 3 of `theories/Arith.v`"));
+        assert!(body.contains("```suggestion\n  reflexivity.\n```"));
+        assert!(body.contains("Not verified"));
+    }
+
+    #[test]
+    fn proposals_are_labelled_for_review() {
+        let r = repair();
+        let coq = ProverKind::new("coq");
+        let comment = suggestion_comment(&r, &coq);
+        assert!(comment.contains("```suggestion\n  reflexivity.\n```"));
+        assert!(comment.contains("needs human review"));
+        assert!(comment.contains("82% confidence"));
+
+        let pr = pull_request(&r, &coq, "main", "0123456789abcdef");
+        assert_eq!(pr.head, "echidnabot/repair/0123456-theories-arith-v");
+        assert_eq!(pr.base, "main");
+        assert!(pr.files.is_empty());
+        assert_eq!(pr.changes, vec![("theories/Arith.v".to_string(), r.patched.clone())]);
+        assert!(pr.body.contains("-  auto.\n+  reflexivity."));
+        assert!(pr.body.contains("needs human review"));
+    }
+}
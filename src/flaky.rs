@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Flaky proof detection (`[flaky]` in the repo's manifest)
+//!
+//! SMT calls and proofs that barely fit their timeout can fail on one run
+//! and pass on the next. With `[flaky] reruns = N`, a file that fails is
+//! checked again, up to `N` more times, until a run passes:
+//!
+//! - every run failed: a **deterministic** failure, reported as before;
+//! - a later run passed: the file is **flaky**. A passing check is a
+//!   proof, so the file counts as verified, and the job's message names
+//!   it. When a failed run hit the timeout the file is *timeout-marginal*:
+//!   raising its timeout is the usual fix.
+//!
+//! Only files checked one at a time are re-run; Lake projects, Isabelle
+//! sessions and Metamath databases are built once. Each classified file
+//! is stored in `flaky_runs`, and `echidnabot status <repo>` lists the
+//! files that were flaky, with how often.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dispatcher::ProverKind;
+use crate::store::models::FlakyRunRecord;
+
+/// One check of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    pub verified: bool,
+    pub timed_out: bool,
+}
+
+impl Attempt {
+    /// An attempt from the verdict and output of a check.
+    pub fn new(verified: bool, output: &str) -> Self {
+        Self {
+            verified,
+            timed_out: !verified && timed_out(output),
+        }
+    }
+}
+
+/// What re-running a failed file showed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlakyVerdict {
+    /// No run passed.
+    Deterministic,
+    /// A run passed after a failure.
+    Flaky,
+}
+
+/// Whether prover or sandbox output reports a timeout.
+pub fn timed_out(output: &str) -> bool {
+    let output = output.to_ascii_lowercase();
+    output.contains("timed out") || output.contains("timeout")
+}
+
+/// Classify the attempts at one file, the first being the failure that
+/// started the re-runs. `None` when the first attempt passed.
+pub fn classify(attempts: &[Attempt]) -> Option<FlakyVerdict> {
+    let (first, reruns) = attempts.split_first()?;
+    if first.verified {
+        return None;
+    }
+    Some(if reruns.iter().any(|a| a.verified) {
+        FlakyVerdict::Flaky
+    } else {
+        FlakyVerdict::Deterministic
+    })
+}
+
+/// A file's flakiness over the stored history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakyHistory {
+    pub prover: ProverKind,
+    pub file_path: String,
+    /// Classified failures: flaky and deterministic.
+    pub runs: usize,
+    pub flaky: usize,
+    /// Flaky runs where a failed attempt hit the timeout.
+    pub timeout_marginal: usize,
+    pub last_flaky: DateTime<Utc>,
+}
+
+impl FlakyHistory {
+    /// "flaky 3/5 (2 timeout-marginal)".
+    pub fn describe(&self) -> String {
+        let mut out = format!("flaky {}/{}", self.flaky, self.runs);
+        if self.timeout_marginal > 0 {
+            out.push_str(&format!(" ({} timeout-marginal)", self.timeout_marginal));
+        }
+        out
+    }
+}
+
+/// The files that were flaky at least once in `runs`, most flaky first.
+pub fn summarize(runs: &[FlakyRunRecord]) -> Vec<FlakyHistory> {
+    let mut files: BTreeMap<(String, String), FlakyHistory> = BTreeMap::new();
+    for run in runs {
+        let key = (run.prover.as_str().to_string(), run.file_path.clone());
+        let entry = files.entry(key).or_insert_with(|| FlakyHistory {
+            prover: run.prover.clone(),
+            file_path: run.file_path.clone(),
+            runs: 0,
+            flaky: 0,
+            timeout_marginal: 0,
+            last_flaky: DateTime::<Utc>::MIN_UTC,
+        });
+        entry.runs += 1;
+        if run.verdict == FlakyVerdict::Flaky {
+            entry.flaky += 1;
+            entry.timeout_marginal += usize::from(run.timed_out);
+            entry.last_flaky = entry.last_flaky.max(run.created_at);
+        }
+    }
+    let mut flaky: Vec<FlakyHistory> = files.into_values().filter(|h| h.flaky > 0).collect();
+    flaky.sort_by(|a, b| b.flaky.cmp(&a.flaky).then(b.last_flaky.cmp(&a.last_flaky)));
+    flaky
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::JobId;
+    use uuid::Uuid;
+
+    #[test]
+    fn reruns_classify_and_summarize() {
+        let fail = Attempt::new(false, "Execution timed out after 300s");
+        let error = Attempt::new(false, "Error: Unable to unify");
+        let pass = Attempt::new(true, "");
+        assert!(fail.timed_out && !error.timed_out);
+        assert_eq!(classify(&[pass]), None);
+        assert_eq!(classify(&[error, error, error]), Some(FlakyVerdict::Deterministic));
+        assert_eq!(classify(&[fail, pass]), Some(FlakyVerdict::Flaky));
+
+        let (job, repo, smt) = (JobId::new(), Uuid::new_v4(), ProverKind::new("z3"));
+        let run = |file: &str, attempts: &[Attempt]| {
+            let verdict = classify(attempts).unwrap();
+            FlakyRunRecord::new(job, repo, smt.clone(), file.into(), "abc".into(), attempts, verdict)
+        };
+        let history = summarize(&[
+            run("a.smt2", &[fail, pass]),
+            run("a.smt2", &[error, error]),
+            run("b.smt2", &[error, pass]),
+            run("b.smt2", &[error, error, pass]),
+            run("c.smt2", &[error, error]),
+        ]);
+        let described: Vec<(&str, String)> =
+            history.iter().map(|h| (h.file_path.as_str(), h.describe())).collect();
+        assert_eq!(
+            described,
+            [
+                ("b.smt2", "flaky 2/2".to_string()),
+                ("a.smt2", "flaky 1/2 (1 timeout-marginal)".to_string()),
+            ]
+        );
+    }
+}
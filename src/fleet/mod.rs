@@ -9,6 +9,13 @@
 //! - Publishing findings after proof verification
 //! - Coordinating with other bots in the fleet
 //! - Consuming findings from rhodibot (if needed)
+//!
+//! Verification workers and capability-based routing live in [`workers`];
+//! the optional NATS transport for them in `nats` (`--features nats`).
+
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod workers;
 
 use crate::error::{Error, Result};
 use crate::scheduler::{JobResult, ProofJob};
@@ -195,6 +202,11 @@ fn test_publish_finding_success() {
             result: None,
             pr_number: None,
             delivery_id: None,
+            timeout_secs: None,
+            dependency_update: false,
+            base_sha: None,
+            branch: None,
+            trace_parent: None,
         };
 
         let result = JobResult {
@@ -206,6 +218,9 @@ fn test_publish_finding_success() {
             failed_files: vec![],
             confidence: None,
             axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
         };
 
         coordinator.publish_finding(&job, &result).unwrap();
@@ -235,6 +250,11 @@ fn test_publish_finding_failure() {
             result: None,
             pr_number: None,
             delivery_id: None,
+            timeout_secs: None,
+            dependency_update: false,
+            base_sha: None,
+            branch: None,
+            trace_parent: None,
         };
 
         let result = JobResult {
@@ -246,6 +266,9 @@ fn test_publish_finding_failure() {
             failed_files: vec!["test.lean".to_string()],
             confidence: None,
             axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
         };
 
         coordinator.publish_finding(&job, &result).unwrap();
@@ -274,6 +297,11 @@ fn test_publish_without_connection() {
             result: None,
             pr_number: None,
             delivery_id: None,
+            timeout_secs: None,
+            dependency_update: false,
+            base_sha: None,
+            branch: None,
+            trace_parent: None,
         };
 
         let result = JobResult {
@@ -285,6 +313,9 @@ fn test_publish_without_connection() {
             failed_files: vec![],
             confidence: None,
             axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
         };
 
         // Should not error when not connected
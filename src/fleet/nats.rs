@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! NATS transport for remote workers (`--features nats`)
+//!
+//! The same protocol as `/fleet` over HTTP (`crate::api::fleet`), for
+//! workers that can reach a NATS server but not the echidnabot node.
+//! Enabled by `[fleet] nats_url`; without it only HTTP workers and the
+//! in-process scheduler run jobs. Subjects, under `nats_subject_prefix`:
+//!
+//! | Subject            | Pattern         | Payload                                   |
+//! |--------------------|-----------------|-------------------------------------------|
+//! | `<prefix>.claim`   | request / reply | `{"id", "provers", "resource_class"}` → `ClaimedJob`, or empty when nothing fits |
+//! | `<prefix>.results` | publish         | `{"worker_id", "job_id", "result"}`       |
+//!
+//! A claim request also registers (or refreshes) the worker, so a worker
+//! that keeps polling stays live; one that stops is expired after
+//! `worker_ttl_secs` and its jobs are requeued. Each job's queue lives in
+//! one echidnabot process, so a prefix must be served by a single node.
+
+use async_nats::{Client, Message};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::api::fleet::{claim_next, RegisterWorker};
+use crate::api::webhooks::AppState;
+use crate::error::{Error, Result};
+use crate::fleet::workers::LOCAL_WORKER;
+use crate::scheduler::{JobId, JobResult};
+use crate::shutdown::ShutdownSignal;
+
+/// A worker's report for a job it claimed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultReport {
+    pub worker_id: String,
+    pub job_id: JobId,
+    pub result: JobResult,
+}
+
+fn nats_error(e: impl std::fmt::Display) -> Error {
+    Error::Internal(format!("NATS: {}", e))
+}
+
+async fn connect(state: &AppState, url: &str) -> Result<Client> {
+    let mut options = async_nats::ConnectOptions::new().name("echidnabot");
+    if let Some(token) = &state.config().fleet.nats_token {
+        options = options.token(token.clone());
+    }
+    options.connect(url).await.map_err(nats_error)
+}
+
+/// Serve claims and results on NATS until shutdown.
+pub async fn run_nats_transport(state: AppState, shutdown: ShutdownSignal) {
+    let Some(url) = state.config().fleet.nats_url.clone() else {
+        return;
+    };
+    let prefix = state.config().fleet.nats_subject_prefix.clone();
+    let subscribed = async {
+        let client = connect(&state, &url).await?;
+        let claims = client.subscribe(format!("{}.claim", prefix)).await.map_err(nats_error)?;
+        let results = client.subscribe(format!("{}.results", prefix)).await.map_err(nats_error)?;
+        Ok::<_, Error>((client, claims, results))
+    };
+    let (client, mut claims, mut results) = match subscribed.await {
+        Ok(subscribed) => subscribed,
+        Err(e) => {
+            tracing::error!("Fleet NATS transport disabled: {}", e);
+            return;
+        }
+    };
+    tracing::info!("Fleet NATS transport serving {}.claim and {}.results on {}", prefix, prefix, url);
+
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            Some(message) = claims.next() => {
+                if let Err(e) = handle_claim(&state, &client, message).await {
+                    tracing::warn!("NATS claim failed: {}", e);
+                }
+            }
+            Some(message) = results.next() => handle_result(&state, message).await,
+            _ = &mut shutdown_fut => {
+                tracing::info!("Fleet NATS transport observed shutdown signal — stopping");
+                return;
+            }
+            else => {
+                tracing::error!("Fleet NATS subscriptions closed");
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_claim(state: &AppState, client: &Client, message: Message) -> Result<()> {
+    let Some(reply) = message.reply else {
+        return Ok(());
+    };
+    let request: RegisterWorker = serde_json::from_slice(&message.payload)?;
+    if request.id.trim().is_empty() || request.id == LOCAL_WORKER {
+        return Err(Error::InvalidInput(format!("invalid worker id {:?}", request.id)));
+    }
+    state.scheduler.workers().register(&request.id, request.capabilities);
+
+    let payload = match claim_next(state, &request.id).await {
+        Ok(Some(claimed)) => serde_json::to_vec(&claimed)?,
+        Ok(None) | Err(_) => Vec::new(),
+    };
+    client.publish(reply, payload.into()).await.map_err(nats_error)
+}
+
+async fn handle_result(state: &AppState, message: Message) {
+    let report: ResultReport = match serde_json::from_slice(&message.payload) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::warn!("Ignoring malformed NATS result: {}", e);
+            return;
+        }
+    };
+    if !state
+        .scheduler
+        .submit_remote_result(&report.worker_id, report.job_id, report.result)
+        .await
+    {
+        tracing::warn!(
+            "Ignoring NATS result for job {}: not claimed by {}",
+            report.job_id,
+            report.worker_id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_report_round_trips() {
+        let json = r#"{"worker_id": "isabelle-1",
+            "job_id": "6f1c1a52-7d38-4d6e-9a39-1c8e0f0b6a11",
+            "result": {"success": true, "message": "ok", "prover_output": "",
+                       "duration_ms": 12, "verified_files": ["A.thy"], "failed_files": []}}"#;
+        let report: ResultReport = serde_json::from_str(json).unwrap();
+        assert_eq!(report.worker_id, "isabelle-1");
+        assert!(report.result.success);
+    }
+}
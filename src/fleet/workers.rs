@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Verification workers and their capabilities
+//!
+//! Not every worker carries every prover image (Isabelle's is huge), so
+//! workers advertise the provers they support and a [`ResourceClass`] when
+//! they register. The scheduler only hands a job to a worker that supports
+//! its prover and whose class is at least the one the prover needs
+//! (`[fleet] prover_classes`).
+//!
+//! The daemon's own dispatch loop is the permanent [`LOCAL_WORKER`];
+//! remote workers register over HTTP (`crate::api::fleet`) and drop out
+//! when they stop re-registering for `[fleet] worker_ttl_secs`. A queued
+//! job no live worker can run is *unschedulable*: it stays queued and is
+//! counted in [`crate::scheduler::job_queue::QueueStats`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::FleetConfig;
+use crate::dispatcher::ProverKind;
+
+/// Worker id of the daemon's own dispatch loop.
+pub const LOCAL_WORKER: &str = "local";
+
+/// Size of the machine a worker runs on, smallest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceClass {
+    Small,
+    #[default]
+    Standard,
+    Large,
+}
+
+/// What a worker advertises on registration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerCapabilities {
+    /// Supported provers; empty = any.
+    #[serde(default)]
+    pub provers: Vec<ProverKind>,
+    #[serde(default)]
+    pub resource_class: ResourceClass,
+}
+
+impl WorkerCapabilities {
+    /// Any prover, on the largest machines.
+    pub fn unrestricted() -> Self {
+        Self {
+            provers: Vec::new(),
+            resource_class: ResourceClass::Large,
+        }
+    }
+
+    pub fn can_run(&self, prover: &ProverKind, required: ResourceClass) -> bool {
+        (self.provers.is_empty() || self.provers.contains(prover)) && self.resource_class >= required
+    }
+}
+
+/// A registered worker.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerInfo {
+    pub id: String,
+    pub capabilities: WorkerCapabilities,
+    pub registered_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Registered workers and the resource class each prover needs.
+pub struct WorkerRegistry {
+    workers: Mutex<HashMap<String, WorkerInfo>>,
+    prover_classes: HashMap<ProverKind, ResourceClass>,
+    ttl: Duration,
+}
+
+impl WorkerRegistry {
+    /// Registry holding only an unrestricted [`LOCAL_WORKER`].
+    pub fn new(config: &FleetConfig) -> Self {
+        let registry = Self {
+            workers: Mutex::new(HashMap::new()),
+            prover_classes: config
+                .prover_classes
+                .iter()
+                .map(|(prover, class)| (ProverKind::new(prover.as_str()), *class))
+                .collect(),
+            ttl: Duration::from_secs(config.worker_ttl_secs),
+        };
+        registry.register(LOCAL_WORKER, WorkerCapabilities::unrestricted());
+        registry
+    }
+
+    /// Register a worker, or refresh its capabilities and last-seen time.
+    pub fn register(&self, id: &str, capabilities: WorkerCapabilities) -> WorkerInfo {
+        let now = Utc::now();
+        let mut workers = self.workers.lock().expect("worker registry poisoned");
+        let info = workers
+            .entry(id.to_string())
+            .and_modify(|w| {
+                w.capabilities = capabilities.clone();
+                w.last_seen = now;
+            })
+            .or_insert_with(|| {
+                tracing::info!("Worker {} registered ({:?})", id, capabilities);
+                WorkerInfo {
+                    id: id.to_string(),
+                    capabilities: capabilities.clone(),
+                    registered_at: now,
+                    last_seen: now,
+                }
+            });
+        info.clone()
+    }
+
+    pub fn deregister(&self, id: &str) -> bool {
+        self.workers.lock().expect("worker registry poisoned").remove(id).is_some()
+    }
+
+    /// Capabilities of a live worker, refreshing its last-seen time.
+    /// `None` for unknown or expired workers, which must register again.
+    pub fn touch(&self, id: &str) -> Option<WorkerCapabilities> {
+        let mut workers = self.workers.lock().expect("worker registry poisoned");
+        let worker = workers.get_mut(id)?;
+        if !self.is_live(worker) {
+            return None;
+        }
+        worker.last_seen = Utc::now();
+        Some(worker.capabilities.clone())
+    }
+
+    fn is_live(&self, worker: &WorkerInfo) -> bool {
+        worker.id == LOCAL_WORKER
+            || Utc::now()
+                .signed_duration_since(worker.last_seen)
+                .to_std()
+                .map_or(true, |age| age <= self.ttl)
+    }
+
+    /// Live workers, sorted by id.
+    pub fn live(&self) -> Vec<WorkerInfo> {
+        let workers = self.workers.lock().expect("worker registry poisoned");
+        let mut live: Vec<WorkerInfo> = workers.values().filter(|w| self.is_live(w)).cloned().collect();
+        live.sort_by(|a, b| a.id.cmp(&b.id));
+        live
+    }
+
+    /// Resource class `prover` needs; `standard` unless configured.
+    pub fn required_class(&self, prover: &ProverKind) -> ResourceClass {
+        self.prover_classes.get(prover).copied().unwrap_or_default()
+    }
+
+    /// Whether `capabilities` may run a `prover` job.
+    pub fn accepts(&self, capabilities: &WorkerCapabilities, prover: &ProverKind) -> bool {
+        capabilities.can_run(prover, self.required_class(prover))
+    }
+
+    /// Whether any live worker can run a `prover` job.
+    pub fn is_schedulable(&self, prover: &ProverKind) -> bool {
+        self.live().iter().any(|w| self.accepts(&w.capabilities, prover))
+    }
+}
+
+impl Default for WorkerRegistry {
+    fn default() -> Self {
+        Self::new(&FleetConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> WorkerRegistry {
+        let mut config = FleetConfig {
+            worker_ttl_secs: 60,
+            ..FleetConfig::default()
+        };
+        config.prover_classes.insert("isabelle".into(), ResourceClass::Large);
+        WorkerRegistry::new(&config)
+    }
+
+    #[test]
+    fn routes_by_prover_and_resource_class() {
+        let registry = registry();
+        let coq_only = WorkerCapabilities {
+            provers: vec![ProverKind::new("coq")],
+            resource_class: ResourceClass::Large,
+        };
+        let small = WorkerCapabilities {
+            provers: vec![],
+            resource_class: ResourceClass::Small,
+        };
+        assert!(registry.accepts(&coq_only, &ProverKind::new("coq")));
+        assert!(!registry.accepts(&coq_only, &ProverKind::new("lean")));
+        assert!(!registry.accepts(&small, &ProverKind::new("lean")), "lean needs standard");
+        assert!(!registry.accepts(&small, &ProverKind::new("isabelle")));
+    }
+
+    #[test]
+    fn restricted_local_worker_leaves_jobs_unschedulable() {
+        let registry = registry();
+        registry.register(
+            LOCAL_WORKER,
+            WorkerCapabilities {
+                provers: vec![ProverKind::new("coq"), ProverKind::new("isabelle")],
+                resource_class: ResourceClass::Standard,
+            },
+        );
+        assert!(registry.is_schedulable(&ProverKind::new("coq")));
+        assert!(!registry.is_schedulable(&ProverKind::new("isabelle")));
+
+        registry.register(
+            "isabelle-1",
+            WorkerCapabilities {
+                provers: vec![ProverKind::new("isabelle")],
+                resource_class: ResourceClass::Large,
+            },
+        );
+        assert!(registry.is_schedulable(&ProverKind::new("isabelle")));
+        assert!(registry.touch("isabelle-1").is_some());
+        assert!(registry.touch("unknown").is_none());
+    }
+}
@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Verification freshness for branch protection
+//!
+//! Branch protection trusts whatever check the head commit shows, and a
+//! lost webhook or a force-push racing the queue can leave a head that
+//! was never verified — or verified, but with the check run never
+//! reaching the platform — while reviewers still remember the old head's
+//! "success". [`reconcile`] compares where each enabled repository's
+//! default branch and open PRs point now with the store, per enabled
+//! prover:
+//!
+//! - no job at the head (or only cancelled ones): one is queued, at high
+//!   priority for PRs;
+//! - the newest job finished but the platform shows no check for it: the
+//!   stored result is handed to the dispatch loop to be reported again
+//!   ([`JobScheduler::request_report`]);
+//! - a job still queued or running: nothing to do.
+//!
+//! [`run_freshness_reconciler`] runs it every `[scheduler]
+//! freshness_interval_secs`. Repositories whose platform can't list
+//! branch and PR heads are skipped; re-posting needs the platform to list
+//! a commit's checks, so results on comment-only platforms are never
+//! re-posted.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::adapters::{build_adapter, PlatformAdapter, RepoId};
+use crate::config::{Config, SharedConfig};
+use crate::error::Result;
+use crate::reporting::check_name;
+use crate::scheduler::{JobId, JobPriority, JobResult, JobScheduler, JobStatus, ProofJob};
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{JobFilter, JobOrder, ProofJobRecord, ProofResultRecord, Repository};
+use crate::store::Store;
+
+/// Jobs looked at per head and prover to find one that wasn't cancelled.
+const JOBS_PER_HEAD: usize = 10;
+
+/// A commit a branch or PR points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Head {
+    pub sha: String,
+    /// Set for the default branch.
+    pub branch: Option<String>,
+    /// Set for open PRs.
+    pub pr: Option<u64>,
+}
+
+/// What one [`reconcile`] pass did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Heads looked at.
+    pub heads: usize,
+    /// Jobs queued for heads without one.
+    pub enqueued: usize,
+    /// Stored results handed back for reporting.
+    pub reposted: usize,
+}
+
+/// Reconcile until shutdown.
+pub async fn run_freshness_reconciler(
+    store: Arc<dyn Store>,
+    scheduler: Arc<JobScheduler>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Freshness reconciler observed shutdown signal — stopping");
+                return;
+            }
+        }
+        match reconcile(store.as_ref(), &scheduler, &config.load_full()).await {
+            Ok(r) if r.enqueued + r.reposted > 0 => tracing::info!(
+                "Freshness: {} head(s) checked, {} job(s) queued, {} result(s) re-posted",
+                r.heads,
+                r.enqueued,
+                r.reposted
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Freshness reconciliation failed: {}", e),
+        }
+    }
+}
+
+/// Check the default-branch and open-PR heads of every enabled
+/// repository.
+pub async fn reconcile(store: &dyn Store, scheduler: &JobScheduler, config: &Config) -> Result<Reconciliation> {
+    let mut report = Reconciliation::default();
+    for mut repo in store.list_repositories(None).await? {
+        if !repo.enabled || repo.enabled_provers.is_empty() {
+            continue;
+        }
+        let adapter = match build_adapter(config, repo.platform) {
+            Ok(adapter) => adapter,
+            Err(e) => {
+                tracing::debug!("Freshness: skipping {}: {}", repo.full_name(), e);
+                continue;
+            }
+        };
+        for head in heads(store, adapter.as_ref(), &mut repo, config).await {
+            report.heads += 1;
+            reconcile_head(store, scheduler, adapter.as_ref(), &repo, &head, &mut report).await?;
+        }
+    }
+    Ok(report)
+}
+
+/// The heads `repo` is checked at: its default branch when pushes are
+/// checked, its open PRs when PRs are. Lookups that fail are logged and
+/// left out.
+async fn heads(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &mut Repository,
+    config: &Config,
+) -> Vec<Head> {
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let mut heads = Vec::new();
+    if repo.check_on_push {
+        let known = repo.default_branch.is_some();
+        if let Some(branch) = crate::reverify::default_branch(repo, config).await {
+            if !known {
+                if let Err(e) = store.update_repository(repo).await {
+                    tracing::debug!("Default branch of {} not stored: {}", repo.full_name(), e);
+                }
+            }
+            match adapter.get_branch_head(&repo_id, &branch).await {
+                Ok(sha) => heads.push(Head { sha, branch: Some(branch), pr: None }),
+                Err(e) => {
+                    tracing::debug!("Freshness: no head for {}@{}: {}", repo.full_name(), branch, e)
+                }
+            }
+        }
+    }
+    if repo.check_on_pr {
+        match adapter.list_open_pull_requests(&repo_id).await {
+            Ok(prs) => heads.extend(prs.into_iter().filter_map(|(pr, info)| {
+                Some(Head {
+                    sha: info.head_sha,
+                    branch: None,
+                    pr: Some(pr.0.parse().ok()?),
+                })
+            })),
+            Err(e) => tracing::debug!("Freshness: no PRs for {}: {}", repo.full_name(), e),
+        }
+    }
+    heads.retain(|head| !head.sha.is_empty());
+    heads
+}
+
+/// Queue or re-post what `head` is missing, prover by prover.
+async fn reconcile_head(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    head: &Head,
+    report: &mut Reconciliation,
+) -> Result<()> {
+    // Fetched on the first finished job; `None` inside when the platform
+    // can't list checks.
+    let mut posted: Option<Option<Vec<String>>> = None;
+    for prover in &repo.enabled_provers {
+        let filter = JobFilter {
+            repo_id: Some(repo.id),
+            prover: Some(prover.clone()),
+            commit_prefix: Some(head.sha.clone()),
+            ..JobFilter::default()
+        };
+        let latest = store
+            .search_jobs(&filter, JobOrder::NewestFirst, 0, JOBS_PER_HEAD)
+            .await?
+            .into_iter()
+            .find(|job| job.commit_sha == head.sha && job.status != JobStatus::Cancelled);
+        let job = match latest {
+            None => {
+                let priority = if head.pr.is_some() { JobPriority::High } else { JobPriority::Normal };
+                let job = ProofJob::new(repo.id, head.sha.clone(), prover.clone(), Vec::new())
+                    .with_priority(priority)
+                    .with_context(head.pr, None)
+                    .with_branch(head.branch.clone());
+                let mut record = ProofJobRecord::from(job.clone());
+                store.create_job(&record).await?;
+                if scheduler.enqueue(job).await?.is_some() {
+                    report.enqueued += 1;
+                } else {
+                    // A queued record would hide the head from the next pass.
+                    record.status = JobStatus::Cancelled;
+                    record.completed_at = Some(chrono::Utc::now());
+                    record.error_message = Some("Rejected by the scheduler (duplicate or queue full)".to_string());
+                    store.update_job(&record).await?;
+                }
+                continue;
+            }
+            Some(job) if matches!(job.status, JobStatus::Queued | JobStatus::Running) => continue,
+            Some(job) => job,
+        };
+
+        if posted.is_none() {
+            let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+            posted = Some(match adapter.list_commit_checks(&repo_id, &head.sha).await {
+                Ok(checks) => Some(checks),
+                Err(e) => {
+                    tracing::debug!(
+                        "Freshness: checks on {} @ {:.8} not listed: {}",
+                        repo.full_name(),
+                        head.sha,
+                        e
+                    );
+                    None
+                }
+            });
+        }
+        let Some(Some(checks)) = &posted else {
+            continue;
+        };
+        if checks.contains(&check_name(prover)) {
+            continue;
+        }
+        if let Some(result) = store.get_result_for_job(JobId(job.id)).await? {
+            scheduler.request_report(job.to_job(), job_result(&result)).await;
+            report.reposted += 1;
+        }
+    }
+    Ok(())
+}
+
+/// A stored result as the reporter takes it. Confidence and axiom data
+/// aren't stored, so the re-posted check goes without them.
+fn job_result(result: &ProofResultRecord) -> JobResult {
+    JobResult {
+        success: result.success,
+        message: result.message.clone(),
+        prover_output: result.prover_output.clone(),
+        duration_ms: result.duration_ms.max(0) as u64,
+        verified_files: result.verified_files.clone(),
+        failed_files: result.failed_files.clone(),
+        confidence: None,
+        axioms: None,
+        prover_version: result.prover_version.clone(),
+        prover_args: result.prover_args.clone(),
+        execution_path: result.execution_path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::github::GitHubAdapter;
+    use crate::adapters::http::Method;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn missing_heads_are_queued_and_lost_checks_reposted() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let scheduler = JobScheduler::new(4, 16);
+        let mut repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        repo.enabled_provers = vec![ProverKind::new("coq")];
+        store.create_repository(&repo).await.unwrap();
+
+        // The PR head was verified, but its check run never arrived.
+        let verified = ProofJob::new(repo.id, "pr-head".into(), ProverKind::new("coq"), vec![]);
+        let mut record = ProofJobRecord::from(verified.clone());
+        record.status = JobStatus::Completed;
+        store.create_job(&record).await.unwrap();
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec!["A.v".into()],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        let saved = ProofResultRecord::new(verified.id, &result);
+        store.save_result(&saved).await.unwrap();
+
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/repos/o/r/git/ref/heads/main", 200, r#"{"object": {"sha": "main-head"}}"#);
+        api.respond(
+            Method::Get,
+            "/repos/o/r/pulls",
+            200,
+            r#"[{"number": 4, "state": "open", "head": {"sha": "pr-head"}}]"#,
+        );
+        api.respond(Method::Get, "/repos/o/r/commits/pr-head/check-runs", 200, r#"{"check_runs": []}"#);
+        api.respond(
+            Method::Get,
+            "/repos/o/r/commits/pr-head/status",
+            200,
+            r#"{"statuses": [{"context": "ci/other"}]}"#,
+        );
+        let github = GitHubAdapter::new("t").unwrap().with_http(api);
+        repo.default_branch = Some("main".into());
+
+        let mut report = Reconciliation::default();
+        for head in heads(&store, &github, &mut repo, &Config::default()).await {
+            report.heads += 1;
+            reconcile_head(&store, &scheduler, &github, &repo, &head, &mut report).await.unwrap();
+        }
+        assert_eq!(report, Reconciliation { heads: 2, enqueued: 1, reposted: 1 });
+
+        // The default branch head got a job; the PR head's result is
+        // waiting for the dispatch loop.
+        let queued = scheduler.try_start_next().await.unwrap();
+        assert_eq!((queued.commit_sha.as_str(), queued.branch.as_deref()), ("main-head", Some("main")));
+        let reports = scheduler.take_reports().await;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].0.id, verified.id);
+        assert!(reports[0].1.success);
+
+        // Once the check shows, nothing is re-posted.
+        let api = Arc::new(MockApi::new());
+        api.respond(
+            Method::Get,
+            "/repos/o/r/commits/pr-head/check-runs",
+            200,
+            serde_json::json!({"check_runs": [{"name": check_name(&ProverKind::new("coq"))}]}).to_string(),
+        );
+        api.respond(Method::Get, "/repos/o/r/commits/pr-head/status", 200, r#"{"statuses": []}"#);
+        let github = GitHubAdapter::new("t").unwrap().with_http(api);
+        let head = Head { sha: "pr-head".into(), branch: None, pr: Some(4) };
+        let mut report = Reconciliation::default();
+        reconcile_head(&store, &scheduler, &github, &repo, &head, &mut report).await.unwrap();
+        assert_eq!(report.reposted, 0);
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Grace periods after toolchain upgrades
+//!
+//! Upgrading a prover instance-wide breaks proofs that relied on the old
+//! version, through no fault of their authors. While a grace period covers
+//! a job's prover, Regulator mode reports a failing check as neutral
+//! instead of blocking the merge, and says so in a banner on the check run
+//! and PR comment. The period ends at `expires_at` with nothing to undo.
+//!
+//! ```toml
+//! [[grace_periods]]
+//! reason = "Coq 8.19 → 8.20 upgrade"
+//! provers = ["coq"]                      # empty: every prover
+//! starts_at = "2026-03-01T00:00:00Z"     # optional; default: already started
+//! expires_at = "2026-03-15T00:00:00Z"
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::dispatcher::ProverKind;
+
+/// One `[[grace_periods]]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GracePeriod {
+    /// Shown in the banner, e.g. "Lean 4.12 upgrade".
+    #[serde(default)]
+    pub reason: String,
+    /// Prover slugs covered; empty covers every prover.
+    #[serde(default)]
+    pub provers: Vec<String>,
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl GracePeriod {
+    /// Whether the period covers `prover` at `now`.
+    pub fn covers(&self, prover: &ProverKind, now: DateTime<Utc>) -> bool {
+        self.starts_at.is_none_or(|start| start <= now)
+            && now < self.expires_at
+            && (self.provers.is_empty()
+                || self.provers.iter().any(|p| ProverKind::new(p.as_str()) == *prover))
+    }
+
+    /// Markdown banner for check runs and PR comments.
+    pub fn banner(&self) -> String {
+        let reason = if self.reason.trim().is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", self.reason.trim())
+        };
+        format!(
+            "⏳ **Grace period{}** until {}: proof failures are reported as warnings and do not block merging.",
+            reason,
+            self.expires_at.format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+}
+
+/// The period covering `prover` at `now`, if any. With several, the one
+/// lasting longest.
+pub fn active<'a>(periods: &'a [GracePeriod], prover: &ProverKind, now: DateTime<Utc>) -> Option<&'a GracePeriod> {
+    periods
+        .iter()
+        .filter(|period| period.covers(prover, now))
+        .max_by_key(|period| period.expires_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn period(provers: &[&str], starts_in_days: Option<i64>, expires_in_days: i64) -> GracePeriod {
+        let now = Utc::now();
+        GracePeriod {
+            reason: "Coq 8.20 upgrade".into(),
+            provers: provers.iter().map(|p| p.to_string()).collect(),
+            starts_at: starts_in_days.map(|d| now + Duration::days(d)),
+            expires_at: now + Duration::days(expires_in_days),
+        }
+    }
+
+    #[test]
+    fn covers_listed_provers_until_expiry() {
+        let now = Utc::now();
+        let coq = ProverKind::new("coq");
+        let lean = ProverKind::new("lean");
+
+        let upgrade = period(&["Coq"], None, 7);
+        assert!(upgrade.covers(&coq, now));
+        assert!(!upgrade.covers(&lean, now));
+        assert!(!upgrade.covers(&coq, now + Duration::days(8)), "expired");
+        assert!(!period(&[], Some(1), 7).covers(&lean, now), "not started yet");
+        assert!(period(&[], Some(-1), 7).covers(&lean, now), "empty list covers every prover");
+
+        let periods = [period(&["coq"], None, 3), period(&[], None, 10), period(&["coq"], None, -1)];
+        assert_eq!(
+            active(&periods, &coq, now).map(|p| p.provers.is_empty()),
+            Some(true),
+            "the longest-lasting active period wins"
+        );
+        assert!(active(&periods[2..], &coq, now).is_none());
+        assert!(upgrade.banner().contains("Coq 8.20 upgrade"));
+    }
+}
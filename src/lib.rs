@@ -16,20 +16,59 @@
 
 pub mod api;
 pub mod adapters;
+pub mod adhoc; // `check --adhoc`: unregistered repositories cloned by URL, verified, never stored
+pub mod analytics; // Verification events exported to ClickHouse / BigQuery / S3 warehouses
+pub mod announcement; // Instance-wide maintenance banner on bot comments and checks
+pub mod archive; // Content-addressed (IPFS / directory) archive of verified artifacts
+pub mod attest; // Signed result attestations + published key set
+pub mod audit; // Append-only audit log of externally visible bot actions
+pub mod automerge; // Merge labelled / dependency PRs once every proof passes
+pub mod baseline; // PR results compared with the base commit
+pub mod bisect; // Historian: bisect default-branch breakages to the commit, open an issue
+pub mod bootstrap; // First-event PR adding `.echidnabot.toml` to unconfigured repos
+pub mod bulk; // Settings changes across repositories matching a filter, with preview
+pub mod calendar; // Repository time zones; blackout windows that hold non-urgent jobs
+pub mod certificate; // Auditor: signed verification certificates for commits every prover passed
+#[cfg(feature = "chaos")]
+pub mod chaos; // Fault injection for dependency-failure testing (`--features chaos`)
 pub mod config;
+pub mod decision; // Per-job trace of the inputs behind its reported conclusion
+pub mod declarative; // `echidnabot apply`: registrations reconciled with a repos.toml / repos.yaml
+pub mod depgraph; // Proof import graph for incremental push verification
+pub mod diagnostics; // Structured prover diagnostics (file/line) + SARIF export
 pub mod dispatcher;
 pub mod error;
+pub mod events; // Job lifecycle events published to NATS / Kafka through a store outbox
 pub mod executor; // Container isolation for secure prover execution
+pub mod federation; // Signed results feed + import from peer instances
+pub mod fixer; // Fixer: verified tactic suggestions proposed as suggested changes / repair PRs
+pub mod flaky; // Re-runs of failing proofs, classified deterministic vs flaky (timeout-marginal)
 pub mod feedback; // Double-loop: proof-history reranker + corpus delta (Package 7b)
 pub mod fleet; // gitbot-fleet coordination layer
+pub mod freshness; // Periodic check that branch and PR heads have a verified, reported result
+pub mod grace; // Regulator grace periods after toolchain upgrades
 pub mod llm; // BoJ-mediated LLM client (Consultant-mode Q&A)
 pub mod modes; // Bot operating modes (Verifier/Advisor/Consultant/Regulator)
 pub mod observability; // Structured logging + OpenTelemetry distributed tracing (OTLP)
+pub mod perf; // Verification-duration analytics (trend anomaly detection)
+pub mod pr_association; // PRs containing a push-triggered job's commit, for its comments and labels
+pub mod pr_comment; // Sticky PR result comments, edited in place by later runs
+pub mod proof_items; // Per-file and per-theorem statuses and timings parsed from prover output
+pub mod reporting; // Check run → commit status → comment, by adapter capabilities
+pub mod reload; // SIGHUP reload of the shared, swappable configuration
 pub mod result_formatter; // Bridge between dispatcher results and bot modes
+pub mod reverify; // Cron-scheduled full re-verification of default branches
 pub mod scheduler;
 pub mod shutdown; // Graceful-shutdown coordinator (drain in-flight + close DB + flush observability)
+pub mod slo; // Queue wait objectives per priority: compliance, /metrics, breach alerts
+pub mod stale; // Stale proof-failure reminders, escalation, draft and close
+pub mod stats; // Instance usage statistics (jobs/day, queue wait, utilization) for capacity planning
 pub mod store;
+pub mod telemetry; // Opt-in anonymous aggregate usage reports
+pub mod tenancy; // Tenant-scoped tokens, owner-partitioned queries, per-tenant secret keys
+pub mod toolchain; // Prover version discovery and per-repository version pins
 pub mod trust; // ECHIDNA Trust Bridge (confidence, integrity, axiom tracking)
+pub mod watch; // `echidnabot watch` terminal dashboard
 
 pub use config::Config;
 pub use error::{Error, Result};
@@ -68,7 +68,7 @@ pub async fn query_boj_q_and_a(
     // restart of echidnabot's config-load path.
     let endpoint = std::env::var("BOJ_ENDPOINT")
         .ok()
-        .or_else(|| state.config.boj.as_ref().map(|b| b.url.clone()))
+        .or_else(|| state.config().boj.as_ref().map(|b| b.url.clone()))
         .unwrap_or_else(|| "http://127.0.0.1:7700".to_string());
 
     let client = reqwest::Client::builder()
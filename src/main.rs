@@ -3,8 +3,11 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! echidnabot CLI and server entry point
 
+use arc_swap::ArcSwap;
 use clap::{Parser, Subcommand};
 use echidnabot::{Config, Result};
+use echidnabot::config::{DispatchStrategy, SharedConfig};
+use echidnabot::reload::ConfigReloader;
 use echidnabot::adapters::{
     CheckConclusion, CheckRun, CheckStatus as AdapterCheckStatus, Platform,
     PlatformAdapter, PrId, RepoId,
@@ -12,27 +15,39 @@
 use echidnabot::adapters::bitbucket::BitbucketAdapter;
 use echidnabot::adapters::github::GitHubAdapter;
 use echidnabot::adapters::gitlab::GitLabAdapter;
+use echidnabot::api::auth::{AuthContext, TokenScope};
 use echidnabot::api::graphql::GraphQLState;
 use echidnabot::api::{create_schema, webhook_router};
-use echidnabot::dispatcher::{EchidnaClient, ProofResult, ProofStatus, ProverKind};
+use echidnabot::audit::AuditAction;
+use echidnabot::depgraph::{self, DependencyGraph};
+use echidnabot::dispatcher::{
+    discovery, EchidnaClient, ExecutionPath, ExtensionTable, ProofResult, ProofStatus, ProverKind,
+};
 use echidnabot::dispatcher::echidna_client::ProverStatus;
-use echidnabot::modes::{self, BotMode, ModeSelector};
+use echidnabot::executor::container::OutputStream;
+use echidnabot::executor::{isabelle, lake, metamath, smtlib};
+use echidnabot::fleet::workers::{WorkerCapabilities, WorkerRegistry, LOCAL_WORKER};
+use echidnabot::modes::{self, BotMode, IgnoreFile, ModeSelector, ProofFilter, ProofsSection};
 use echidnabot::result_formatter;
-use echidnabot::scheduler::{JobScheduler, ProofJob};
+use echidnabot::scheduler::{JobLogs, JobScheduler, LogStream, ProofJob};
 use echidnabot::shutdown::{
     resolve_shutdown_timeout, wait_for_termination, ShutdownCoordinator, ShutdownSignal,
 };
 use echidnabot::store::{SqliteStore, Store};
 use echidnabot::feedback::corpus_delta::{CorpusDelta, DeltaRow, DeltaSource};
 use echidnabot::store::models::{
-    ProofResultRecord, Repository as StoreRepository, TacticOutcomeRecord,
+    ApiTokenRecord, DependencyGraphRecord, EscapeHatchRecord, FileTimingRecord, FlakyRunRecord, ProofItemRecord, ProofResultRecord, Repository as StoreRepository, TacticOutcomeRecord,
 };
 use echidnabot::store::models::goal_fingerprint;
+use echidnabot::flaky;
+use echidnabot::proof_items::{self, ProofItem};
+use echidnabot::trust::escape_hatch;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::fs;
 use tokio::time::{sleep, Duration};
+use tracing::Instrument;
 
 #[derive(Parser)]
 #[command(name = "echidnabot")]
@@ -43,9 +58,15 @@ struct Cli {
     command: Commands,
 
     /// Path to configuration file
-    #[arg(short, long, default_value = "echidnabot.toml")]
+    #[arg(short, long, default_value = "echidnabot.toml", env = "ECHIDNABOT_CONFIG")]
     config: String,
 
+    /// Override a config key, after the file and the
+    /// `ECHIDNABOT_<SECTION>__<KEY>` environment variables (repeatable),
+    /// e.g. `--set scheduler.max_concurrent=8`
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    overrides: Vec<String>,
+
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
@@ -80,6 +101,13 @@ enum Commands {
         #[arg(long, default_value = "metamath")]
         provers: String,
 
+        /// Enable the provers whose proof files or project manifests
+        /// (`lakefile.lean`, `_CoqProject`, `ROOT`, ...) are on the default
+        /// branch, instead of `--provers`. Falls back to the `--provers`
+        /// default when nothing is recognised.
+        #[arg(long, conflicts_with = "provers")]
+        detect_provers: bool,
+
         /// Bot operating mode for this repo. Overrides the daemon-wide
         /// default but is itself overridden by a target-repo directive
         /// at `.machine_readable/bot_directives/echidnabot.a2ml`.
@@ -95,6 +123,79 @@ enum Commands {
         /// Ignored for non-Regulator modes. Default: 100.
         #[arg(long, default_value = "100", value_parser = clap::value_parser!(u8))]
         regulator_threshold: u8,
+
+        /// Webhook secret for this repository. Deliveries for it must be
+        /// signed with this instead of the platform-wide secret.
+        #[arg(long)]
+        webhook_secret: Option<String>,
+
+        /// Only verify proof files matching this glob (`proofs/**`).
+        /// Repeatable. A `[proofs]` table in the repo manifest overrides it.
+        #[arg(long = "include", value_name = "GLOB")]
+        path_include: Vec<String>,
+
+        /// Never verify proof files matching this glob (`vendor/**`).
+        /// Repeatable; wins over `--include`.
+        #[arg(long = "exclude", value_name = "GLOB")]
+        path_exclude: Vec<String>,
+
+        /// `public` exposes the badge, `/status` and GraphQL status queries
+        /// without a token; `private` (default) requires one.
+        #[arg(long, default_value = "private")]
+        visibility: String,
+
+        /// Contribute anonymized (goal, tactic) pairs from passing jobs to
+        /// the `[corpus.export]` training export.
+        #[arg(long)]
+        training_export: bool,
+
+        /// Fully re-verify the default branch on this cron schedule (in the
+        /// `[calendar]` time zone, UTC by default), e.g. `"0 3 * * *"` for
+        /// nightly at 03:00.
+        #[arg(long, value_name = "CRON")]
+        reverify_cron: Option<String>,
+    },
+
+    /// Stop monitoring a repository
+    Unregister {
+        /// Repository in format owner/name
+        #[arg(short, long)]
+        repo: String,
+
+        /// Platform (github, gitlab, bitbucket)
+        #[arg(short, long, default_value = "github")]
+        platform: String,
+
+        /// Delete the repository with its jobs, results and timings instead
+        /// of only disabling it.
+        #[arg(long)]
+        purge_jobs: bool,
+
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Change settings on every registered repository matching a filter,
+    /// e.g. `--owner acme --check-on-push false`. Prints the changes and
+    /// asks before applying them.
+    BulkUpdate(BulkUpdateArgs),
+
+    /// Reconcile registrations with a declarative `repos.toml` /
+    /// `repos.yaml`: create, update and (with `prune = true`) disable
+    /// repositories to match it. Prints the plan and asks before applying.
+    Apply {
+        /// Registration file; `.yaml` / `.yml` is read as YAML, anything
+        /// else as TOML
+        file: String,
+
+        /// Print the plan and stop
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// Manually trigger a proof check
@@ -110,6 +211,13 @@ enum Commands {
         /// Specific prover to use
         #[arg(short, long)]
         prover: Option<String>,
+
+        /// Treat `--repo` as a public repository URL: clone it (at
+        /// `--commit`, default HEAD), verify every proof file and print the
+        /// results, without registering it or touching the database.
+        /// Exits with status 1 when any file fails.
+        #[arg(long)]
+        adhoc: bool,
     },
 
     /// Show status of a repository or job
@@ -117,10 +225,365 @@ enum Commands {
         /// Repository in format owner/name, or job ID
         #[arg(short, long)]
         target: String,
+        /// For a job: print why it was reported the way it was (mode,
+        /// policies, cache hits, trust level)
+        #[arg(long)]
+        explain: bool,
     },
 
     /// Initialize the database
     InitDb,
+
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Manage API tokens for the GraphQL and admin endpoints
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+
+    /// Manage per-tenant secret keys (`[tenancy]`)
+    Tenant {
+        #[command(subcommand)]
+        action: TenantAction,
+    },
+
+    /// Read the audit log of bot actions
+    Audit {
+        #[command(subcommand)]
+        action: AuditLogAction,
+    },
+
+    /// Create and check signed result attestations
+    Attest {
+        #[command(subcommand)]
+        action: AttestAction,
+    },
+
+    /// Export a completed job's diagnostics as SARIF 2.1.0
+    Sarif {
+        /// Job ID
+        #[arg(short, long)]
+        job: String,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Summarize how the instance was used: jobs per day, queue wait,
+    /// utilization per prover and the busiest repositories
+    Stats {
+        /// `day`, `week` or `month`
+        #[arg(long, default_value = "week")]
+        period: String,
+
+        /// How many of the busiest repositories to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+
+    /// Re-run a finished job with the same parameters. A running daemon
+    /// picks the new job up from the database.
+    Retry {
+        /// Job ID of the job to re-run
+        job: String,
+    },
+
+    /// Print a job's prover output from a running daemon, as it is
+    /// written with `--follow`
+    Logs {
+        /// Job ID
+        job: String,
+
+        /// Keep streaming until the job finishes
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Daemon base URL (defaults to `http://127.0.0.1:<[server].port>`)
+        #[arg(long, env = "ECHIDNABOT_URL")]
+        server: Option<String>,
+
+        /// API token with read scope
+        #[arg(long, env = "ECHIDNABOT_TOKEN")]
+        token: Option<String>,
+    },
+
+    /// Terminal dashboard: queue, running jobs, recent results and
+    /// ECHIDNA / prover health. Reads the daemon's database, so run it on
+    /// the server.
+    Watch {
+        /// Seconds between refreshes
+        #[arg(short, long, default_value = "2")]
+        interval: u64,
+    },
+
+    /// The Avro schema of the job events `[events]` publishes
+    Events {
+        #[command(subcommand)]
+        action: EventsAction,
+    },
+
+    /// Warehouse tables for, and re-exports to, `[[analytics.sinks]]`
+    Analytics {
+        #[command(subcommand)]
+        action: AnalyticsAction,
+    },
+
+    /// Inspect the anonymous usage reports sent when `[telemetry]` is on
+    Telemetry {
+        #[command(subcommand)]
+        action: TelemetryAction,
+    },
+
+    /// Verify a local proof file or directory once and print the result.
+    /// Needs no database, registered repository or webhook; exits with
+    /// status 1 when any file fails, for use in CI pipelines.
+    Verify {
+        /// Proof file, or a directory to search for proof files
+        path: PathBuf,
+
+        /// Prover to use. Detected from file extensions when omitted; in
+        /// a directory, only this prover's files are verified.
+        #[arg(short, long)]
+        prover: Option<String>,
+
+        /// Run in the local Podman / bubblewrap sandbox instead of
+        /// ECHIDNA, as with `[executor] local_isolation = true`
+        #[arg(long)]
+        local: bool,
+
+        /// Per-file timeout in seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// `text`, `markdown` (the PR comment for `[bot] mode`) or `json`
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct BulkUpdateArgs {
+    /// Only repositories on this platform (github, gitlab, bitbucket,
+    /// codeberg)
+    #[arg(long)]
+    platform: Option<String>,
+
+    /// Only repositories of this owner
+    #[arg(long)]
+    owner: Option<String>,
+
+    /// Only repositories whose `owner/name` matches this pattern (`*`, `?`)
+    #[arg(long, value_name = "PATTERN")]
+    name: Option<String>,
+
+    /// Only repositories with this prover enabled
+    #[arg(long = "with-prover", value_name = "PROVER")]
+    with_prover: Option<String>,
+
+    /// Only monitored (`true`) or disabled (`false`) repositories
+    #[arg(long)]
+    enabled: Option<bool>,
+
+    /// Set the enabled provers (comma-separated)
+    #[arg(long)]
+    provers: Option<String>,
+
+    #[arg(long, value_name = "BOOL")]
+    check_on_push: Option<bool>,
+
+    #[arg(long, value_name = "BOOL")]
+    check_on_pr: Option<bool>,
+
+    #[arg(long, value_name = "BOOL")]
+    auto_comment: Option<bool>,
+
+    /// Set the path globs whose proof files are verified (repeatable)
+    #[arg(long = "include", value_name = "GLOB")]
+    path_include: Vec<String>,
+
+    /// Set the path globs never verified (repeatable)
+    #[arg(long = "exclude", value_name = "GLOB")]
+    path_exclude: Vec<String>,
+
+    /// `public` or `private`
+    #[arg(long)]
+    visibility: Option<String>,
+
+    #[arg(long, value_name = "BOOL")]
+    training_export: Option<bool>,
+
+    /// Cron schedule (in the repository's time zone) for full
+    /// re-verification; `""` removes it
+    #[arg(long, value_name = "CRON")]
+    reverify_cron: Option<String>,
+
+    /// IANA time zone of the schedule and blackout windows, e.g.
+    /// `Europe/London`; `""` inherits the `[calendar]` one again
+    #[arg(long, value_name = "TZ")]
+    timezone: Option<String>,
+
+    /// Require a prover version, e.g. `lean=4.7.0` (repeatable). Replaces
+    /// the repositories' pins.
+    #[arg(long = "pin", value_name = "PROVER=VERSION")]
+    pins: Vec<String>,
+
+    /// Print what would change and stop
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Do not ask for confirmation
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Subcommand)]
+enum EventsAction {
+    /// Print the Avro schema of job events, for consumers and registries
+    Schema,
+}
+
+#[derive(Subcommand)]
+enum AnalyticsAction {
+    /// Print the table a sink inserts into: a ClickHouse `CREATE TABLE`,
+    /// or the BigQuery schema (also for loading S3 objects)
+    Schema {
+        /// Sink name
+        sink: String,
+    },
+    /// Re-send every job finished since `--since` (all of them without
+    /// it) to a sink, then carry on from where it was
+    Backfill {
+        /// Sink name
+        sink: String,
+
+        /// Jobs finished at or after this time (RFC 3339)
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TelemetryAction {
+    /// Print the report covering the last `--hours`, exactly as it would
+    /// be sent. Nothing is sent.
+    Preview {
+        #[arg(long, default_value = "24")]
+        hours: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the configuration file. With `--resolved`, print the
+    /// effective configuration instead: file, environment and `--set`
+    /// overrides merged over the defaults, secrets masked.
+    Show {
+        #[arg(long)]
+        resolved: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenAction {
+    /// Mint a new token. The plaintext is printed once and never stored.
+    Mint {
+        /// Human-readable label (e.g. "ci-dashboard")
+        #[arg(short, long)]
+        name: String,
+
+        /// Scope: `read`, `trigger`, or `admin` (each implies the ones before it)
+        #[arg(short, long, default_value = "read")]
+        scope: String,
+
+        /// Confine the token to one repository owner's repositories
+        #[arg(long)]
+        tenant: Option<String>,
+    },
+
+    /// Revoke a token by ID
+    Revoke {
+        /// Token ID as printed by `token mint` / `token list`
+        id: String,
+    },
+
+    /// List tokens (hashes are never shown)
+    List,
+}
+
+#[derive(Subcommand)]
+enum TenantAction {
+    /// List the tenants that have a data key
+    Keys,
+
+    /// Seal every stored webhook secret still in plaintext, after setting
+    /// `[tenancy] master_key`
+    Seal,
+
+    /// Delete a tenant's data key; its sealed secrets can't be read again
+    Forget {
+        /// Repository owner
+        owner: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditLogAction {
+    /// Write entries as JSON Lines, oldest first
+    Export {
+        /// Only this repository (owner/name)
+        #[arg(short, long)]
+        repo: Option<String>,
+
+        /// Platform of `--repo`
+        #[arg(short, long, default_value = "github")]
+        platform: String,
+
+        /// Only this action, e.g. `comment.posted`
+        #[arg(short, long)]
+        action: Option<String>,
+
+        /// Entries at or after this time (RFC 3339)
+        #[arg(long)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Entries before this time (RFC 3339)
+        #[arg(long)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AttestAction {
+    /// Check an attestation's or certificate's signatures against a
+    /// published key set and print the verified claims. Works offline with
+    /// a saved `keys.json`.
+    Verify {
+        /// Envelope, as served by `/jobs/{id}/attestation` or
+        /// `/certificates/{platform}/{owner}/{repo}/{sha}`
+        file: String,
+
+        /// Key set: a saved `keys.json`, or the instance's
+        /// `https://…/.well-known/echidnabot/keys.json` URL
+        #[arg(short, long)]
+        keys: String,
+    },
+
+    /// Generate a signing key for `[attestation] signing_key_path`
+    Keygen {
+        /// Where to write the key; must not exist yet
+        #[arg(short, long)]
+        output: String,
+    },
 }
 
 #[tokio::main]
@@ -146,8 +609,14 @@ async fn main() -> Result<()> {
         // anyway — no tokio runtime yet, no other threads spawned.
         std::env::set_var("RUST_LOG", "debug");
     }
+    // Log lines would scribble over the dashboard; same single-threaded
+    // startup as above.
+    if matches!(cli.command, Commands::Watch { .. }) && std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "off");
+    }
 
-    let config = Config::load(&cli.config)?;
+    let mut config = Config::load_layered(&cli.config, &cli.overrides)?;
+    config.resolve_secrets().await?;
 
     // Initialise tracing via the observability module. Returns a
     // TracerShutdown handle we must keep alive until the application
@@ -160,6 +629,7 @@ async fn main() -> Result<()> {
     let mut tracer_guard = echidnabot::observability::init_tracing(otlp_endpoint, false)
         .map_err(|e| echidnabot::Error::Config(format!("tracing init failed: {e}")))?;
 
+    let mut exit_code = 0;
     let result = match cli.command {
         Commands::Serve { host, port } => {
             // CLI flag wins; otherwise honour the TOML [server] section.
@@ -172,14 +642,21 @@ async fn main() -> Result<()> {
             // was configured, the hook is `None` and the coordinator
             // skips registration.
             let tracer_hook = tracer_guard.into_coordinator_hook();
-            serve(&config, &host, port, tracer_hook).await
+            serve(&config, &cli.config, &cli.overrides, &host, port, tracer_hook).await
         }
         Commands::Register {
             repo,
             platform,
             provers,
+            detect_provers,
             mode,
             regulator_threshold,
+            webhook_secret,
+            path_include,
+            path_exclude,
+            visibility,
+            training_export,
+            reverify_cron,
         } => {
             tracing::info!(
                 "Registering {} on {} with provers: {} (mode: {}, regulator_threshold: {})",
@@ -194,27 +671,88 @@ async fn main() -> Result<()> {
                 &repo,
                 &platform,
                 &provers,
+                detect_provers,
                 &mode,
                 regulator_threshold,
+                webhook_secret,
+                ProofsSection {
+                    include: path_include,
+                    exclude: path_exclude,
+                },
+                &visibility,
+                training_export,
+                reverify_cron,
             )
             .await
         }
+        Commands::Unregister {
+            repo,
+            platform,
+            purge_jobs,
+            yes,
+        } => unregister(&config, &repo, &platform, purge_jobs, yes).await,
+        Commands::BulkUpdate(args) => bulk_update(&config, args).await,
+        Commands::Apply { file, dry_run, yes } => apply(&config, &file, dry_run, yes).await,
+        Commands::Check {
+            repo,
+            commit,
+            prover,
+            adhoc: true,
+        } => check_adhoc(&config, &repo, commit.as_deref(), prover.as_deref())
+            .await
+            .map(|passed| {
+                if !passed {
+                    exit_code = 1;
+                }
+            }),
         Commands::Check {
             repo,
             commit,
             prover,
+            adhoc: false,
         } => {
             tracing::info!("Triggering check for {} at {:?}", repo, commit);
             check(&config, &repo, commit.as_deref(), prover.as_deref()).await
         }
-        Commands::Status { target } => {
+        Commands::Status { target, explain } => {
             tracing::info!("Getting status for {}", target);
-            status(&config, &target).await
+            status(&config, &target, explain).await
         }
         Commands::InitDb => {
             tracing::info!("Initializing database");
             init_db(&config).await
         }
+        Commands::Config { action } => show_config(&config, &cli.config, action).await,
+        Commands::Token { action } => token(&config, action).await,
+        Commands::Tenant { action } => tenant(&config, action).await,
+        Commands::Audit { action } => audit(&config, action).await,
+        Commands::Attest { action } => attest(action).await,
+        Commands::Sarif { job, output } => sarif(&config, &job, output.as_deref()).await,
+        Commands::Stats { period, top } => stats(&config, &period, top).await,
+        Commands::Retry { job } => retry(&config, &job).await,
+        Commands::Logs {
+            job,
+            follow,
+            server,
+            token,
+        } => logs(&config, &job, follow, server.as_deref(), token.as_deref()).await,
+        Commands::Watch { interval } => watch(&config, interval).await,
+        Commands::Events { action } => events(action),
+        Commands::Analytics { action } => analytics(&config, action).await,
+        Commands::Telemetry { action } => telemetry(&config, action).await,
+        Commands::Verify {
+            path,
+            prover,
+            local,
+            timeout,
+            format,
+        } => verify(&config, &path, prover.as_deref(), local, timeout, &format)
+            .await
+            .map(|passed| {
+                if !passed {
+                    exit_code = 1;
+                }
+            }),
     };
 
     // Flush any in-flight OTel spans before the process exits.
@@ -226,6 +764,9 @@ async fn main() -> Result<()> {
     // gives us a chance to surface errors that `Drop` would silently log.
     tracer_guard.shutdown();
 
+    if result.is_ok() && exit_code != 0 {
+        std::process::exit(exit_code);
+    }
     result
 }
 
@@ -241,17 +782,26 @@ async fn main() -> Result<()> {
 
 async fn serve(
     config: &Config,
+    config_path: &str,
+    overrides: &[String],
     host: &str,
     port: u16,
     tracer_hook: Option<TracerFlushHook>,
 ) -> Result<()> {
-    use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-    use axum::{Extension, routing::get, routing::post, Router};
+    use axum::{handler::Handler, middleware, routing::get, routing::post, Extension, Router};
 
-    // Webhook signature verification is per-integration (handled in
-    // src/api/webhooks.rs). When no `webhook_secret` is configured for a
-    // platform, the receiver still returns 200 on POST — fine for local
-    // testing but unsafe in any deployment reachable from a network.
+    // A mistyped time zone or blackout window would otherwise never apply.
+    config.calendar.validate()?;
+    config.tenancy.validate()?;
+    config.echidna.auth.validate()?;
+    config.analytics.validate()?;
+    config.events.validate()?;
+
+    // Webhook signature verification is per-repository, falling back to
+    // the per-integration secret (handled in src/api/webhooks.rs). When
+    // neither is configured, the receiver still returns 200 on POST —
+    // fine for local testing but unsafe in any deployment reachable from
+    // a network.
     // Surface the gap loudly at startup so an operator can't miss it.
     let gh_unsecured = config
         .github
@@ -271,23 +821,66 @@ async fn serve(
         if gh_unsecured {
             tracing::warn!(
                 "[github].webhook_secret not set — /webhooks/github accepts \
-                 any POST without HMAC verification. Set webhook_secret in \
-                 echidnabot.toml before exposing this daemon."
+                 any POST for repositories without their own secret, with no \
+                 HMAC verification. Set webhook_secret in echidnabot.toml \
+                 before exposing this daemon."
             );
         }
         if gl_unsecured {
             tracing::warn!(
                 "[gitlab].webhook_secret not set — /webhooks/gitlab accepts \
-                 any POST without signature verification."
+                 any POST for projects without their own secret, with no \
+                 token verification."
             );
         }
     }
 
-    let store = Arc::new(SqliteStore::new(&config.database.url).await?);
-    let scheduler = Arc::new(JobScheduler::new(
-        config.scheduler.max_concurrent,
-        config.scheduler.queue_size,
-    ));
+    if !config.auth.requires_credentials() {
+        tracing::warn!(
+            "[auth].required is false — /metrics and the job exports admit \
+             anonymous requests with admin scope. Mint a token with `echidnabot token mint` \
+             and set required = true before exposing this daemon."
+        );
+    }
+    if config.auth.oidc.as_ref().is_some_and(|o| o.session_secret.is_none()) {
+        tracing::warn!(
+            "[auth.oidc].session_secret not set — login sessions are signed \
+             with a random per-process key: a restart logs everyone out, and \
+             instances behind one load balancer reject each other's sessions."
+        );
+    }
+
+    #[cfg(feature = "chaos")]
+    echidnabot::chaos::install(&config.chaos);
+
+    let store = Arc::new(
+        SqliteStore::connect(&config.database)
+            .await?
+            .with_write_behind(&config.database.write_behind),
+    );
+    // Job events are recorded from here on only while `[events]` is on.
+    store.set_job_event_outbox(config.events.enabled).await?;
+    store.set_master_key(config.tenancy.master_key()?).await?;
+    // Jobs go only to workers carrying their prover: this process (limited
+    // by `[fleet] local_provers`) and remote workers registered at /fleet.
+    let workers = Arc::new(WorkerRegistry::new(&config.fleet));
+    workers.register(
+        LOCAL_WORKER,
+        WorkerCapabilities {
+            provers: config.fleet.local_provers.iter().map(|p| ProverKind::new(p.as_str())).collect(),
+            resource_class: config.fleet.local_resource_class,
+        },
+    );
+    let scheduler = Arc::new(
+        JobScheduler::new(config.scheduler.max_concurrent, config.scheduler.queue_size)
+            .with_workers(workers)
+            .with_logs(JobLogs::new(
+                config.scheduler.log_buffer_lines,
+                config.scheduler.log_retain_jobs,
+            )),
+    );
+    scheduler.set_slo(config.slo.clone());
+    scheduler.set_exclusive(config.scheduler.exclusive);
     let echidna = Arc::new(EchidnaClient::new(&config.echidna));
 
     let graphql_state = GraphQLState {
@@ -305,29 +898,90 @@ async fn serve(
         tracing::warn!("Webhook rate limiting is disabled — set [server] rate_limit_rpm to enable");
     }
 
+    let ingest = Arc::new(echidnabot::api::ingest::WebhookIngest::new(
+        config.server.webhook_max_attempts,
+    ));
+    // Store / ECHIDNA probes behind webhook backpressure and /health;
+    // `[health] check_interval_secs = 0` turns them off.
+    let health_monitor = (config.health.check_interval_secs > 0)
+        .then(|| Arc::new(echidnabot::api::health::HealthMonitor::new(&config.health)));
+    // Handlers and background tasks read the configuration through this,
+    // so a SIGHUP reload reaches them without a restart.
+    let shared_config: SharedConfig = Arc::new(ArcSwap::from_pointee(config.clone()));
     let app_state = echidnabot::api::webhooks::AppState {
-        config: Arc::new(config.clone()),
+        config: shared_config.clone(),
         store: store.clone(),
         scheduler: scheduler.clone(),
         rate_limiter,
         mode_selector: ModeSelector::new(config.bot.mode),
+        ingest: Some(ingest.clone()),
+        health: health_monitor.clone(),
     };
 
+    // Bearer-token auth guards /metrics, the job exports and job logs. /badge,
+    // /status, /feeds and /graphql admit tokenless callers for public
+    // repositories only. /health stays open, and so does the
+    // playground page unless `[auth.oidc]` asks for a login (see
+    // `echidnabot::api::auth` and `echidnabot::api::oidc`).
+    let auth_layer = middleware::from_fn_with_state(
+        app_state.clone(),
+        echidnabot::api::auth::auth_middleware,
+    );
+    let public_layer = middleware::from_fn_with_state(
+        app_state.clone(),
+        echidnabot::api::auth::public_auth_middleware,
+    );
+    let login_layer = middleware::from_fn_with_state(
+        app_state.clone(),
+        echidnabot::api::oidc::require_login,
+    );
+
     let app = Router::new()
-        .route("/health", get(health))
-        .route("/metrics", get(metrics))
+        .route("/health", get(echidnabot::api::health::health))
+        .route(
+            "/badge/{platform}/{owner}/{repo}",
+            get(echidnabot::api::badge::repo_badge.layer(public_layer.clone())),
+        )
+        .route(
+            "/status/{platform}/{owner}/{repo}",
+            get(echidnabot::api::status::repo_status.layer(public_layer.clone())),
+        )
+        .route(
+            "/certificates/{platform}/{owner}/{repo}/{sha}",
+            get(echidnabot::api::attest::commit_certificate.layer(public_layer.clone())),
+        )
+        .route(
+            "/feeds/{platform}/{owner}/{feed}",
+            get(echidnabot::api::feed::repo_feed.layer(public_layer.clone())),
+        )
+        .route("/metrics", get(metrics.layer(auth_layer.clone())))
+        .route(
+            "/jobs/{id}/sarif",
+            get(echidnabot::api::sarif::job_sarif.layer(auth_layer.clone())),
+        )
+        .route(
+            "/jobs/{id}/logs",
+            get(echidnabot::api::logs::job_logs.layer(auth_layer.clone())),
+        )
+        .route(
+            "/jobs/{id}/attestation",
+            get(echidnabot::api::attest::job_attestation.layer(auth_layer.clone())),
+        )
+        .route(echidnabot::attest::KEYS_PATH, get(echidnabot::api::attest::keys))
+        .route(
+            echidnabot::federation::FEED_PATH,
+            get(echidnabot::api::federation::results),
+        )
         .route("/", get(root))
         .route(
             "/graphql",
-            post(
-                |Extension(schema): Extension<echidnabot::api::graphql::EchidnabotSchema>,
-                 req: GraphQLRequest| async move {
-                    GraphQLResponse::from(schema.execute(req.into_inner()).await)
-                },
-            )
-            .get(graphql_playground),
+            post(graphql_handler.layer(public_layer)).get(graphql_playground.layer(login_layer)),
         )
+        .route(echidnabot::api::oidc::LOGIN_PATH, get(echidnabot::api::oidc::login))
+        .route(echidnabot::api::oidc::CALLBACK_PATH, get(echidnabot::api::oidc::callback))
+        .route(echidnabot::api::oidc::LOGOUT_PATH, get(echidnabot::api::oidc::logout))
         .merge(webhook_router(app_state.clone()))
+        .merge(echidnabot::api::fleet::fleet_router(app_state.clone()))
         .layer(Extension(schema))
         .with_state(app_state.clone());
 
@@ -348,6 +1002,23 @@ async fn serve(
     let timeout = resolve_shutdown_timeout(config.lifecycle.shutdown_timeout_secs);
     let mut coordinator = ShutdownCoordinator::new(timeout);
     let scheduler_signal = coordinator.signal();
+    let ingest_signal = coordinator.signal();
+    let stale_signal = coordinator.signal();
+    let reverify_signal = coordinator.signal();
+    let calendar_signal = coordinator.signal();
+    let freshness_signal = coordinator.signal();
+    let federation_signal = coordinator.signal();
+    let export_signal = coordinator.signal();
+    let telemetry_signal = coordinator.signal();
+    let analytics_signal = coordinator.signal();
+    let events_signal = coordinator.signal();
+    let slo_signal = coordinator.signal();
+    let health_signal = coordinator.signal();
+    let write_behind_signal = coordinator.signal();
+    let credential_signal = coordinator.signal();
+    let reload_signal = coordinator.signal();
+    #[cfg(feature = "nats")]
+    let nats_signal = coordinator.signal();
     let axum_signal = coordinator.signal();
     // Standalone trigger handle for the signal-listener task; using a
     // separate handle avoids capturing the coordinator by move (which
@@ -378,10 +1049,166 @@ async fn serve(
         app_state.config.clone(),
         scheduler_signal,
     ));
+    // Webhook handlers only store deliveries; this worker acts on them.
+    tokio::spawn(echidnabot::api::ingest::run_ingest_worker(
+        app_state.clone(),
+        ingest,
+        ingest_signal,
+    ));
+    // Stale proof-failure reminders; repos opt in via their manifest.
+    if config.scheduler.stale_sweep_interval_secs > 0 {
+        tokio::spawn(echidnabot::stale::run_stale_sweeper(
+            store.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.scheduler.stale_sweep_interval_secs),
+            stale_signal,
+        ));
+    }
+    // Scheduled full re-verification (per-repo `reverify_cron`).
+    if config.scheduler.reverify_interval_secs > 0 {
+        tokio::spawn(echidnabot::reverify::run_reverify_scheduler(
+            store.clone(),
+            scheduler.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.scheduler.reverify_interval_secs),
+            reverify_signal,
+        ));
+    }
+    // Low-priority jobs of repositories in a `[calendar]` blackout window
+    // wait for it to end.
+    if config.calendar.check_interval_secs > 0 {
+        tokio::spawn(echidnabot::calendar::run_blackout_monitor(
+            store.clone(),
+            scheduler.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.calendar.check_interval_secs),
+            calendar_signal,
+        ));
+    }
+    // Rotated `[echidna.auth]` certificates and keys are picked up
+    // without a restart.
+    if config.echidna.auth.reload_interval_secs > 0 {
+        tokio::spawn(echidnabot::dispatcher::echidna_auth::run_credential_refresh(
+            echidna.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.echidna.auth.reload_interval_secs),
+            credential_signal,
+        ));
+    }
+    // Default-branch and PR heads without a result get verified or
+    // re-reported.
+    if config.scheduler.freshness_interval_secs > 0 {
+        tokio::spawn(echidnabot::freshness::run_freshness_reconciler(
+            store.clone(),
+            scheduler.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.scheduler.freshness_interval_secs),
+            freshness_signal,
+        ));
+    }
+    // Results mirrored from peer instances (`[[federation.peers]]`).
+    if !config.federation.peers.is_empty() && config.federation.sync_interval_secs > 0 {
+        tokio::spawn(echidnabot::federation::run_federation_sync(
+            store.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.federation.sync_interval_secs),
+            federation_signal,
+        ));
+    }
+    // Opt-in (goal, tactic) training export (`[corpus.export]`).
+    if config.corpus.export.enabled && config.corpus.export.interval_secs > 0 {
+        tokio::spawn(echidnabot::feedback::export::run_training_export(
+            store.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.corpus.export.interval_secs),
+            export_signal,
+        ));
+    }
+    // Verification events to the `[[analytics.sinks]]` warehouses.
+    if !config.analytics.sinks.is_empty() && config.analytics.interval_secs > 0 {
+        tokio::spawn(echidnabot::analytics::run_analytics_export(
+            store.clone(),
+            app_state.config.clone(),
+            Duration::from_secs(config.analytics.interval_secs),
+            analytics_signal,
+        ));
+    }
+    // Job lifecycle events from the outbox to NATS / Kafka.
+    if config.events.enabled {
+        tokio::spawn(echidnabot::events::run_event_publisher(
+            store.clone(),
+            app_state.config.clone(),
+            events_signal,
+        ));
+    }
+    // Anonymous usage reports; off unless `[telemetry] enabled`.
+    if config.telemetry.enabled && config.telemetry.interval_secs > 0 {
+        tokio::spawn(echidnabot::telemetry::run_telemetry(
+            store.clone(),
+            app_state.config(),
+            Duration::from_secs(config.telemetry.interval_secs),
+            telemetry_signal,
+        ));
+    }
+    // Queue wait objectives; `[slo] check_interval_secs = 0` keeps it idle.
+    tokio::spawn(echidnabot::slo::run_slo_monitor(
+        store.clone(),
+        scheduler.clone(),
+        slo_signal,
+    ));
+
+    // Remote workers over NATS (`[fleet] nats_url`), alongside /fleet.
+    #[cfg(feature = "nats")]
+    if config.fleet.nats_url.is_some() {
+        tokio::spawn(echidnabot::fleet::nats::run_nats_transport(
+            app_state.clone(),
+            nats_signal,
+        ));
+    }
+    #[cfg(not(feature = "nats"))]
+    if config.fleet.nats_url.is_some() {
+        tracing::warn!("[fleet] nats_url is set but this build lacks the `nats` feature; ignoring it");
+    }
+    if let Some(monitor) = health_monitor {
+        tokio::spawn(echidnabot::api::health::run_health_monitor(
+            monitor,
+            store.clone(),
+            echidna.clone(),
+            Duration::from_secs(config.health.check_interval_secs),
+            health_signal,
+        ));
+    }
+
+    // Batched timings, outcomes and token touches; `close()` in the
+    // db-pool-close hook flushes whatever is left.
+    if config.database.write_behind.enabled {
+        tokio::spawn(echidnabot::store::run_write_behind(
+            store.clone(),
+            Duration::from_millis(config.database.write_behind.flush_interval_ms.max(1)),
+            write_behind_signal,
+        ));
+    }
+
+    // `kill -HUP` re-reads the configuration file (see `echidnabot::reload`).
+    tokio::spawn(echidnabot::reload::run_config_reloader(
+        ConfigReloader::new(config_path, shared_config, scheduler.clone())
+            .with_overrides(overrides.to_vec()),
+        reload_signal,
+    ));
+
+    // Refuse to fall back to plain HTTP when HTTPS was asked for.
+    #[cfg(not(feature = "tls"))]
+    if config.server.tls.is_some() {
+        return Err(echidnabot::Error::Config(
+            "[server.tls] is set but this build lacks the `tls` feature".into(),
+        ));
+    }
+    let scheme = if config.server.tls.is_some() { "https" } else { "http" };
 
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", host, port)).await?;
     tracing::info!(
-        "Listening on http://{}:{} (shutdown timeout: {}s)",
+        "Listening on {}://{}:{} (shutdown timeout: {}s)",
+        scheme,
         host,
         port,
         timeout.as_secs()
@@ -401,15 +1228,19 @@ async fn serve(
     // future has fired AND all in-flight HTTP connections have
     // drained — so by the time we get past this await the HTTP plane
     // is fully quiesced.
-    let serve_result = axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    )
-    .with_graceful_shutdown(async move {
+    let app = app.into_make_service_with_connect_info::<std::net::SocketAddr>();
+    let drain = async move {
         axum_signal.triggered().await;
         tracing::info!("Axum graceful shutdown triggered — draining HTTP connections");
-    })
-    .await;
+    };
+    let serve_result = match &config.server.tls {
+        #[cfg(feature = "tls")]
+        Some(tls) => echidnabot::api::tls::serve_tls(listener, tls, app, drain).await,
+        _ => axum::serve(listener, app)
+            .with_graceful_shutdown(drain)
+            .await
+            .map_err(echidnabot::Error::from),
+    };
     if let Err(e) = serve_result {
         tracing::error!("axum::serve error: {}", e);
         // Server died without a signal — fire shutdown so hooks still
@@ -432,6 +1263,25 @@ async fn serve(
     Ok(())
 }
 
+/// GraphQL POST handler. Forwards the authenticated principal into the
+/// schema context so mutations can enforce their scope, and with
+/// `[auth] confirm_destructive` the confirmation policy.
+async fn graphql_handler(
+    axum::extract::State(state): axum::extract::State<echidnabot::api::webhooks::AppState>,
+    axum::Extension(schema): axum::Extension<echidnabot::api::graphql::EchidnabotSchema>,
+    axum::Extension(auth): axum::Extension<AuthContext>,
+    req: async_graphql_axum::GraphQLRequest,
+) -> async_graphql_axum::GraphQLResponse {
+    let mut request = req.into_inner().data(auth);
+    let config = state.config();
+    if config.auth.confirm_destructive {
+        request = request.data(echidnabot::api::confirm::ConfirmationPolicy {
+            ttl: Duration::from_secs(config.auth.confirmation_ttl_secs),
+        });
+    }
+    schema.execute(request).await.into()
+}
+
 async fn graphql_playground() -> &'static str {
     r#"<!DOCTYPE html>
 <html>
@@ -446,66 +1296,118 @@ async fn graphql_playground() -> &'static str {
     <div id="root"></div>
     <script>
       window.addEventListener("load", function () {
-        GraphQLPlayground.init(document.getElementById("root"), { endpoint: "/graphql" });
+        // Same-origin credentials carry an OIDC session cookie along.
+        GraphQLPlayground.init(document.getElementById("root"), {
+          endpoint: "/graphql",
+          settings: { "request.credentials": "same-origin" },
+        });
       });
     </script>
   </body>
 </html>"#
 }
 
-async fn health() -> &'static str {
-    "OK"
-}
-
 /// Prometheus-compatible text exposition of key counters.
 ///
-/// Exposes scheduler queue depth and build metadata. Full Prometheus
-/// integration (using `prometheus` or `metrics-exporter-prometheus` crates)
-/// is a future hardening item; this endpoint provides the shape and format
+/// Exposes scheduler queue depth, queue wait objectives, database
+/// connection and lock-wait figures, and build metadata. Full Prometheus integration (using
+/// `prometheus` or `metrics-exporter-prometheus` crates) is a future
+/// hardening item; this endpoint provides the shape and format
 /// that operators expect so dashboards and alerts can be wired now.
 async fn metrics(
     axum::extract::State(state): axum::extract::State<echidnabot::api::webhooks::AppState>,
+    axum::Extension(auth): axum::Extension<echidnabot::api::auth::AuthContext>,
 ) -> (axum::http::StatusCode, String) {
+    // Instance-wide figures: not for a token confined to one tenant.
+    if auth.tenant.is_some() {
+        return (axum::http::StatusCode::FORBIDDEN, "Token is confined to a tenant".to_string());
+    }
     let queued = state.scheduler.queue_depth();
     let running = state.scheduler.running_count();
-    let body = format!(
+    let unschedulable = state.scheduler.unschedulable_jobs().await.len();
+    let workers = state.scheduler.workers().live().len();
+    let mut body = format!(
         "# HELP echidnabot_jobs_queued Number of jobs waiting in the proof queue\n\
          # TYPE echidnabot_jobs_queued gauge\n\
          echidnabot_jobs_queued {queued}\n\
          # HELP echidnabot_jobs_running Number of jobs currently being verified\n\
          # TYPE echidnabot_jobs_running gauge\n\
          echidnabot_jobs_running {running}\n\
+         # HELP echidnabot_jobs_unschedulable Queued jobs no live worker can run\n\
+         # TYPE echidnabot_jobs_unschedulable gauge\n\
+         echidnabot_jobs_unschedulable {unschedulable}\n\
+         # HELP echidnabot_workers Live verification workers, including this process\n\
+         # TYPE echidnabot_workers gauge\n\
+         echidnabot_workers {workers}\n\
          # HELP echidnabot_build_info Static build metadata\n\
          # TYPE echidnabot_build_info gauge\n\
-         echidnabot_build_info{{version=\"{version}\"}} 1\n",
+         echidnabot_build_info{{version=\"{version}\"}} 1\n\
+         {database}",
         queued = queued,
         running = running,
+        unschedulable = unschedulable,
+        workers = workers,
         version = env!("CARGO_PKG_VERSION"),
+        database = state.store.pool_stats().map(|s| s.to_prometheus()).unwrap_or_default(),
     );
+    let slo = state.scheduler.slo();
+    match echidnabot::slo::evaluate(state.store.as_ref(), &slo, chrono::Utc::now()).await {
+        Ok(statuses) => body.push_str(&echidnabot::slo::to_prometheus(&statuses)),
+        Err(e) => tracing::debug!("Queue wait SLOs unavailable for /metrics: {}", e),
+    }
     (axum::http::StatusCode::OK, body)
 }
 
 async fn root() -> &'static str {
-    "echidnabot - Proof-aware CI bot\n\nEndpoints:\n  GET  /health\n  GET  /graphql\n  POST /graphql\n  POST /webhooks/github\n  POST /webhooks/gitlab\n  POST /webhooks/bitbucket"
+    "echidnabot - Proof-aware CI bot\n\nEndpoints:\n  GET  /health\n  GET  /badge/{platform}/{owner}/{repo}.svg\n  GET  /status/{platform}/{owner}/{repo}\n  GET  /certificates/{platform}/{owner}/{repo}/{sha}\n  GET  /feeds/{platform}/{owner}/{repo}.atom|.rss\n  GET  /graphql\n  POST /graphql\n  GET  /auth/login\n  GET  /auth/callback\n  GET  /auth/logout\n  GET  /jobs/{id}/sarif\n  GET  /jobs/{id}/logs\n  GET  /jobs/{id}/attestation\n  GET  /.well-known/echidnabot/keys.json\n  GET  /federation/results\n  GET  /fleet/workers\n  POST /fleet/workers\n  POST /webhooks/github\n  POST /webhooks/gitlab\n  POST /webhooks/bitbucket"
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn register(
     config: &Config,
     repo: &str,
     platform: &str,
     provers: &str,
+    detect_provers: bool,
     mode: &str,
     regulator_threshold: u8,
+    webhook_secret: Option<String>,
+    paths: ProofsSection,
+    visibility: &str,
+    training_export: bool,
+    reverify_cron: Option<String>,
 ) -> Result<()> {
-    let store = SqliteStore::new(&config.database.url).await?;
+    let store = SqliteStore::connect(&config.database).await?;
+    store.set_master_key(config.tenancy.master_key()?).await?;
     let platform = parse_platform(platform)?;
     let (owner, name) = split_repo_name(repo)?;
 
     let mut repo_record = StoreRepository::new(platform, owner, name);
-    let enabled = parse_prover_list(provers)?;
-    if !enabled.is_empty() {
-        repo_record.enabled_provers = enabled;
-    }
+    let detected = if detect_provers {
+        let adapter = echidnabot::adapters::build_adapter(config, platform)?;
+        let repo_id = RepoId::new(platform, repo_record.owner.clone(), repo_record.name.clone());
+        let detected = echidnabot::bootstrap::detect_repo_provers(adapter.as_ref(), &repo_id).await?;
+        if detected.is_empty() {
+            tracing::warn!("No proof files recognised in {}; using --provers {}", repo, provers);
+        } else {
+            tracing::info!(
+                "Detected provers for {}: {}",
+                repo,
+                detected.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ")
+            );
+        }
+        detected
+    } else {
+        Vec::new()
+    };
+    let enabled = if detected.is_empty() {
+        parse_prover_list(provers)?
+    } else {
+        detected
+    };
+    if !enabled.is_empty() {
+        repo_record.enabled_provers = enabled;
+    }
 
     // Parse the mode flag — accepts the four lowercase strings.
     repo_record.mode = serde_json::from_value(serde_json::Value::String(mode.to_lowercase()))
@@ -519,8 +1421,26 @@ async fn register(
     // Clamp threshold to 0..=100 (clap's u8 parser already enforces u8
     // bounds, but we don't want 200% to silently become valid here).
     repo_record.regulator_coverage_threshold = regulator_threshold.min(100);
+    repo_record.webhook_secret = webhook_secret.filter(|s| !s.is_empty());
+    repo_record.path_include = paths.include;
+    repo_record.path_exclude = paths.exclude;
+    repo_record.visibility = visibility.parse()?;
+    repo_record.training_export = training_export;
+    if let Some(expr) = reverify_cron {
+        echidnabot::reverify::parse_schedule(&expr)?;
+        repo_record.reverify_cron = Some(expr);
+    }
 
     store.create_repository(&repo_record).await?;
+    echidnabot::audit::record(
+        &store,
+        echidnabot::audit::CLI,
+        AuditAction::RepoRegistered,
+        Some(&repo_record),
+        None,
+        echidnabot::audit::settings_payload(&repo_record),
+    )
+    .await;
     tracing::info!(
         "Registered repository {} on {:?} in {} mode (regulator threshold {}%)",
         repo_record.full_name(),
@@ -531,6 +1451,205 @@ async fn register(
     Ok(())
 }
 
+async fn unregister(
+    config: &Config,
+    repo: &str,
+    platform: &str,
+    purge_jobs: bool,
+    yes: bool,
+) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+    let platform = parse_platform(platform)?;
+    let (owner, name) = split_repo_name(repo)?;
+    let mut record = store
+        .get_repository_by_name(platform, &owner, &name)
+        .await?
+        .ok_or_else(|| echidnabot::Error::RepoNotFound(repo.to_string()))?;
+
+    let prompt = if purge_jobs {
+        format!(
+            "Delete {} and all of its jobs, results and timings? This cannot be undone.",
+            record.full_name()
+        )
+    } else {
+        format!("Stop monitoring {}? Its history is kept.", record.full_name())
+    };
+    if !yes && !confirm(&prompt)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    if purge_jobs {
+        let purged = store.purge_repository(record.id).await?;
+        let payload = echidnabot::audit::settings_payload(&record);
+        echidnabot::audit::record(&store, echidnabot::audit::CLI, AuditAction::RepoUnregistered, Some(&record), None, payload)
+            .await;
+        println!(
+            "Removed {}: {} jobs, {} results, {} file timings",
+            record.full_name(),
+            purged.jobs,
+            purged.results,
+            purged.file_timings
+        );
+    } else {
+        record.enabled = false;
+        record.updated_at = chrono::Utc::now();
+        store.update_repository(&record).await?;
+        let payload = echidnabot::audit::settings_payload(&record);
+        echidnabot::audit::record(&store, echidnabot::audit::CLI, AuditAction::RepoSettingsChanged, Some(&record), None, payload)
+            .await;
+        println!(
+            "Disabled {}; `echidnabot unregister -r {} --purge-jobs` deletes its history",
+            record.full_name(),
+            repo
+        );
+    }
+    // A running daemon may already hold the repository's queued jobs in
+    // its in-memory queue.
+    println!("Jobs already queued in a running daemon still run; cancel them there if needed.");
+    Ok(())
+}
+
+async fn bulk_update(config: &Config, args: BulkUpdateArgs) -> Result<()> {
+    use echidnabot::bulk::{RepoFilter, SettingsPatch};
+
+    let filter = RepoFilter {
+        platform: args.platform.as_deref().map(parse_platform).transpose()?,
+        owner: args.owner,
+        name: args.name,
+        prover: args
+            .with_prover
+            .as_deref()
+            .map(|p| parse_prover_arg(p).ok_or_else(|| echidnabot::Error::InvalidProver(p.to_string())))
+            .transpose()?,
+        enabled: args.enabled,
+    };
+    let reverify_cron = match args.reverify_cron.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(expr) => {
+            echidnabot::reverify::parse_schedule(expr)?;
+            Some(Some(expr.to_string()))
+        }
+        None => None,
+    };
+    let timezone = match args.timezone.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(name) => {
+            echidnabot::calendar::parse_timezone(name)?;
+            Some(Some(name.to_string()))
+        }
+        None => None,
+    };
+    let prover_pins = if args.pins.is_empty() {
+        None
+    } else {
+        let mut pins = std::collections::BTreeMap::new();
+        for pin in &args.pins {
+            let (prover, version) = pin.split_once('=').ok_or_else(|| {
+                echidnabot::Error::Config(format!("Pin '{}' must be PROVER=VERSION", pin))
+            })?;
+            let prover = parse_prover_arg(prover)
+                .ok_or_else(|| echidnabot::Error::InvalidProver(prover.to_string()))?;
+            let version = echidnabot::toolchain::validate_pin(&prover, version)?;
+            pins.insert(prover.as_str().to_string(), version);
+        }
+        Some(pins)
+    };
+    let patch = SettingsPatch {
+        enabled_provers: args.provers.as_deref().map(parse_prover_list).transpose()?,
+        check_on_push: args.check_on_push,
+        check_on_pr: args.check_on_pr,
+        auto_comment: args.auto_comment,
+        path_include: (!args.path_include.is_empty()).then_some(args.path_include),
+        path_exclude: (!args.path_exclude.is_empty()).then_some(args.path_exclude),
+        visibility: args
+            .visibility
+            .as_deref()
+            .map(str::parse::<echidnabot::store::models::Visibility>)
+            .transpose()?,
+        training_export: args.training_export,
+        reverify_cron,
+        timezone,
+        prover_pins,
+        ..SettingsPatch::default()
+    };
+    if patch.is_empty() {
+        return Err(echidnabot::Error::Config(
+            "Nothing to change: give at least one setting".to_string(),
+        ));
+    }
+
+    let store = SqliteStore::connect(&config.database).await?;
+    let mut plan = echidnabot::bulk::plan(&store, &filter, &patch).await?;
+    let changed = plan.iter().filter(|c| !c.changes.is_empty()).count();
+    for change in plan.iter().filter(|c| !c.changes.is_empty()) {
+        println!("{}", change.repo.full_name());
+        for field in &change.changes {
+            println!("  {}: {} -> {}", field.field, field.from, field.to);
+        }
+    }
+    println!(
+        "{} of {} matching repositories {}",
+        changed,
+        plan.len(),
+        if args.dry_run { "would change" } else { "change" }
+    );
+    if args.dry_run || changed == 0 {
+        return Ok(());
+    }
+    if !args.yes && !confirm(&format!("Apply to {} repositories?", changed))? {
+        println!("Aborted.");
+        return Ok(());
+    }
+    let stored = echidnabot::bulk::commit(&store, echidnabot::audit::CLI, &mut plan).await?;
+    println!("Updated {} repositories.", stored);
+    Ok(())
+}
+
+async fn apply(config: &Config, file: &str, dry_run: bool, yes: bool) -> Result<()> {
+    use echidnabot::declarative::{Action, RegistrationFile};
+
+    let registrations = RegistrationFile::parse(file, &fs::read_to_string(file).await?)?;
+    let store = SqliteStore::connect(&config.database).await?;
+    let mut plan = echidnabot::declarative::plan(&store, &registrations).await?;
+    let count = |action: Action| plan.iter().filter(|c| c.action == action).count();
+    let (create, update) = (count(Action::Create), count(Action::Update));
+    let disable = count(Action::Disable);
+    for change in &plan {
+        let (sign, verb) = match change.action {
+            Action::Create => ('+', "register"),
+            Action::Update => ('~', "update"),
+            Action::Disable => ('-', "disable"),
+        };
+        println!("{} {} ({:?}): {}", sign, change.repo.full_name(), change.repo.platform, verb);
+        for field in &change.changes {
+            println!("    {}: {} -> {}", field.field, field.from, field.to);
+        }
+    }
+    println!("Plan: {} to register, {} to update, {} to disable.", create, update, disable);
+    if dry_run || plan.is_empty() {
+        return Ok(());
+    }
+    if !yes && !confirm("Apply this plan?")? {
+        println!("Aborted.");
+        return Ok(());
+    }
+    let stored = echidnabot::declarative::apply(&store, echidnabot::audit::CLI, &mut plan).await?;
+    println!("Applied {} change(s).", stored);
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; anything but `y`/`yes` is no.
+fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 async fn check(config: &Config, repo: &str, commit: Option<&str>, prover: Option<&str>) -> Result<()> {
     let client = EchidnaClient::new(&config.echidna);
     let health = client.health_check().await?;
@@ -590,6 +1709,17 @@ async fn check(config: &Config, repo: &str, commit: Option<&str>, prover: Option
     Ok(())
 }
 
+/// `echidnabot check --adhoc`: clone a public repository and verify it
+/// as `verify` would a local checkout (see [`echidnabot::adhoc`]).
+/// Returns whether every file verified.
+async fn check_adhoc(config: &Config, url: &str, commit: Option<&str>, prover: Option<&str>) -> Result<bool> {
+    echidnabot::adhoc::validate_url(url)?;
+    echidnabot::adhoc::check(url, commit, |clone| async move {
+        verify(config, &clone, prover, false, None, "text").await
+    })
+    .await
+}
+
 fn parse_prover_arg(prover: &str) -> Option<ProverKind> {
     match prover.to_lowercase().as_str() {
         "agda" => Some(ProverKind::new("agda")),
@@ -609,13 +1739,8 @@ fn parse_prover_arg(prover: &str) -> Option<ProverKind> {
 }
 
 fn detect_prover_from_filename(path: &Path) -> Option<ProverKind> {
-    let filename = path.file_name()?.to_str()?.to_lowercase();
-    ProverKind::all().find(|prover| {
-        prover
-            .file_extensions()
-            .iter()
-            .any(|ext| filename.ends_with(ext))
-    })
+    let filename = path.file_name()?.to_str()?;
+    ExtensionTable::builtin().provers_for(filename).first().cloned()
 }
 
 fn format_prover_status(status: ProverStatus) -> &'static str {
@@ -665,8 +1790,14 @@ fn parse_prover_list(provers: &str) -> Result<Vec<ProverKind>> {
     Ok(results)
 }
 
-async fn status(config: &Config, target: &str) -> Result<()> {
-    let store = SqliteStore::new(&config.database.url).await?;
+/// Re-runs of a repository `echidnabot status` looks at for flaky files.
+const FLAKY_HISTORY_LIMIT: usize = 200;
+
+/// Bisections `echidnabot status` lists for a repository.
+const BISECTION_HISTORY_LIMIT: usize = 10;
+
+async fn status(config: &Config, target: &str, explain: bool) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
 
     if let Ok(job_id) = uuid::Uuid::parse_str(target) {
         if let Some(job) = store.get_job(echidnabot::scheduler::JobId(job_id)).await? {
@@ -678,6 +1809,12 @@ async fn status(config: &Config, target: &str) -> Result<()> {
                 job.prover,
                 job.status
             );
+            if explain {
+                match &job.payload.decision {
+                    Some(trace) => trace.lines().iter().for_each(|line| println!("{}", line)),
+                    None => println!("No decision trace recorded for this job"),
+                }
+            }
             return Ok(());
         }
     }
@@ -695,6 +1832,30 @@ async fn status(config: &Config, target: &str) -> Result<()> {
             );
             let jobs = store.list_jobs_for_repo(repo.id, 20).await?;
             tracing::info!("Recent jobs: {}", jobs.len());
+            // Files `[flaky] reruns` caught passing after a failure.
+            let runs = store.list_flaky_runs(repo.id, FLAKY_HISTORY_LIMIT).await?;
+            for file in flaky::summarize(&runs) {
+                tracing::info!(
+                    "Flaky: {} ({}) {}, last {}",
+                    file.file_path,
+                    file.prover.display_name(),
+                    file.describe(),
+                    file.last_flaky.to_rfc3339()
+                );
+            }
+            // `[bisect]`: breakages traced to a commit, and those in progress.
+            for b in store.list_bisections(repo.id, BISECTION_HISTORY_LIMIT).await? {
+                tracing::info!(
+                    "Bisection: {} ({}) {:?} good={:.8} bad={:.8} pending={}{}",
+                    b.file_path,
+                    b.prover.display_name(),
+                    b.status,
+                    b.good_sha,
+                    b.bad_sha,
+                    b.pending.len(),
+                    b.issue.map(|i| format!(" issue=#{}", i)).unwrap_or_default()
+                );
+            }
             return Ok(());
         }
     }
@@ -703,17 +1864,577 @@ async fn status(config: &Config, target: &str) -> Result<()> {
     Ok(())
 }
 
+async fn token(config: &Config, action: TokenAction) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+
+    match action {
+        TokenAction::Mint { name, scope, tenant } => {
+            let scope: TokenScope = scope.parse()?;
+            let plaintext = echidnabot::api::auth::generate_token();
+            let mut record = ApiTokenRecord::new(
+                name,
+                echidnabot::api::auth::hash_token(&plaintext),
+                scope,
+            );
+            record.tenant = tenant;
+            store.create_api_token(&record).await?;
+            let covers = match &record.tenant {
+                Some(tenant) => format!("tenant {}", tenant),
+                None => "the whole instance".to_string(),
+            };
+            tracing::info!(
+                "Minted {} token '{}' ({}) for {}",
+                record.scope,
+                record.name,
+                record.id,
+                covers
+            );
+            // Printed to stdout (not the log) so it can be piped into a
+            // secret store. This is the only time the plaintext exists.
+            println!("{}", plaintext);
+        }
+        TokenAction::Revoke { id } => {
+            let id = uuid::Uuid::parse_str(&id)
+                .map_err(|_| echidnabot::Error::InvalidInput(format!("invalid token id '{}'", id)))?;
+            if store.revoke_api_token(id).await? {
+                tracing::info!("Revoked token {}", id);
+            } else {
+                tracing::warn!("No active token with id {}", id);
+            }
+        }
+        TokenAction::List => {
+            for t in store.list_api_tokens().await? {
+                println!(
+                    "{}  {:<8} {:<24} tenant={} created={} last_used={} {}",
+                    t.id,
+                    t.scope,
+                    t.name,
+                    t.tenant.as_deref().unwrap_or("-"),
+                    t.created_at.to_rfc3339(),
+                    t.last_used_at.map(|d| d.to_rfc3339()).unwrap_or_else(|| "never".into()),
+                    if t.revoked_at.is_some() { "REVOKED" } else { "" },
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn tenant(config: &Config, action: TenantAction) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+
+    match action {
+        TenantAction::Keys => {
+            for key in store.list_tenant_keys().await? {
+                println!("{:<32} created={}", key.owner, key.created_at.to_rfc3339());
+            }
+        }
+        TenantAction::Seal => {
+            let master = config.tenancy.master_key()?.ok_or_else(|| {
+                echidnabot::Error::Config("[tenancy] master_key is not set".to_string())
+            })?;
+            store.set_master_key(Some(master)).await?;
+            let mut sealed = 0;
+            for repo in store.list_repositories(None).await? {
+                let plaintext = repo
+                    .webhook_secret
+                    .as_deref()
+                    .is_some_and(|secret| !echidnabot::tenancy::is_sealed(secret));
+                if plaintext {
+                    store.update_repository(&repo).await?;
+                    sealed += 1;
+                }
+            }
+            tracing::info!("Sealed {} webhook secret(s)", sealed);
+        }
+        TenantAction::Forget { owner } => {
+            if store.delete_tenant_key(&owner).await? {
+                tracing::info!("Deleted the data key of tenant {}", owner);
+            } else {
+                tracing::warn!("Tenant {} has no data key", owner);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn sarif(config: &Config, job: &str, output: Option<&str>) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+    let job_id = uuid::Uuid::parse_str(job)
+        .map_err(|_| echidnabot::Error::InvalidInput(format!("invalid job id '{}'", job)))?;
+
+    let log = echidnabot::diagnostics::sarif::build_job_sarif(
+        &store,
+        echidnabot::scheduler::JobId(job_id),
+    )
+    .await?
+    .ok_or_else(|| echidnabot::Error::InvalidInput(format!("no completed job with id {}", job_id)))?;
+    let rendered = serde_json::to_string_pretty(&log)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered).await?;
+            tracing::info!("Wrote SARIF for job {} to {}", job_id, path);
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
+
+async fn audit(config: &Config, action: AuditLogAction) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+    match action {
+        AuditLogAction::Export {
+            repo,
+            platform,
+            action,
+            since,
+            until,
+            output,
+        } => {
+            let repo_id = match repo {
+                Some(repo) => {
+                    let (owner, name) = split_repo_name(&repo)?;
+                    let record = store
+                        .get_repository_by_name(parse_platform(&platform)?, &owner, &name)
+                        .await?
+                        .ok_or_else(|| echidnabot::Error::RepoNotFound(repo.clone()))?;
+                    Some(record.id)
+                }
+                None => None,
+            };
+            let filter = echidnabot::store::models::AuditFilter {
+                repo_id,
+                action,
+                actor: None,
+                since,
+                until,
+                owner: None,
+            };
+            let mut entries = store.list_audit(&filter, i64::MAX as usize).await?;
+            entries.reverse();
+            let mut rendered = String::new();
+            for entry in &entries {
+                rendered.push_str(&serde_json::to_string(entry)?);
+                rendered.push('\n');
+            }
+            match output {
+                Some(path) => {
+                    fs::write(&path, rendered).await?;
+                    tracing::info!("Wrote {} audit entries to {}", entries.len(), path);
+                }
+                None => print!("{}", rendered),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn show_config(config: &Config, path: &str, action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Show { resolved: true } => print!("{}", config.to_redacted_toml()?),
+        ConfigAction::Show { resolved: false } => match fs::read_to_string(path).await {
+            Ok(content) => print!("{}", content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                eprintln!("{} not found; the defaults apply (see `config show --resolved`).", path);
+            }
+            Err(e) => return Err(e.into()),
+        },
+    }
+    Ok(())
+}
+
+fn events(action: EventsAction) -> Result<()> {
+    match action {
+        EventsAction::Schema => {
+            println!("{}", serde_json::to_string_pretty(&echidnabot::events::avro_schema())?);
+        }
+    }
+    Ok(())
+}
+
+async fn analytics(config: &Config, action: AnalyticsAction) -> Result<()> {
+    config.analytics.validate()?;
+    match action {
+        AnalyticsAction::Schema { sink } => {
+            print!("{}", echidnabot::analytics::ddl(config.analytics.sink(&sink)?)?);
+        }
+        AnalyticsAction::Backfill { sink, since } => {
+            let sink = config.analytics.sink(&sink)?;
+            let store = SqliteStore::connect(&config.database).await?;
+            echidnabot::analytics::rewind(&store, sink, since).await?;
+            let client = reqwest::Client::new();
+            let exported = echidnabot::analytics::export_pending(
+                &store,
+                sink,
+                config.analytics.batch_size,
+                &client,
+            )
+            .await?;
+            println!("Exported {} event(s) to '{}'", exported, sink.name);
+        }
+    }
+    Ok(())
+}
+
+async fn telemetry(config: &Config, action: TelemetryAction) -> Result<()> {
+    match action {
+        TelemetryAction::Preview { hours } => {
+            let store = SqliteStore::connect(&config.database).await?;
+            let until = chrono::Utc::now();
+            let since = until - chrono::Duration::hours(i64::from(hours));
+            let report = echidnabot::telemetry::build_report(&store, since, until).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !config.telemetry.enabled {
+                eprintln!("Telemetry is off; enable it with [telemetry] enabled = true.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `echidnabot verify`: check local proof files without a database,
+/// repository or webhook. Returns whether every file verified.
+async fn verify(
+    config: &Config,
+    path: &Path,
+    prover: Option<&str>,
+    local: bool,
+    timeout: Option<u64>,
+    format: &str,
+) -> Result<bool> {
+    if !matches!(format, "text" | "markdown" | "json") {
+        return Err(echidnabot::Error::InvalidInput(format!(
+            "unknown format '{}': expected text, markdown or json",
+            format
+        )));
+    }
+    let prover = prover
+        .map(|p| parse_prover_arg(p).ok_or_else(|| echidnabot::Error::InvalidProver(p.to_string())))
+        .transpose()?;
+    let groups = local_proof_files(path, prover)?;
+    if groups.is_empty() {
+        return Err(echidnabot::Error::InvalidInput(format!(
+            "no proof files found at {}",
+            path.display()
+        )));
+    }
+
+    let mut config = config.clone();
+    if local {
+        config.executor.local_isolation = true;
+        config.executor.dispatch = Some(DispatchStrategy::PreferLocal);
+    }
+    let echidna = EchidnaClient::new(&config.echidna);
+    let echidna = match timeout {
+        Some(secs) => echidna.with_timeout(Duration::from_secs(secs)),
+        None => echidna,
+    };
+    let root = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+
+    let mut passed = true;
+    let mut reports = Vec::new();
+    for (prover, files) in groups {
+        let executor = build_local_executor(&config, &prover, timeout).await?;
+        let (executor, fallback) = match config.executor.dispatch() {
+            DispatchStrategy::EchidnaWithLocalFallback => (None, executor),
+            _ => (executor, None),
+        };
+        let mut outcomes = Vec::new();
+        for file in &files {
+            let content = fs::read_to_string(file).await?;
+            let started = Instant::now();
+            let path = relative_proof_path(root, file);
+            let (local, fallback) = (executor.as_ref(), fallback.as_ref());
+            let (verified, output, _) =
+                verify_content(local, fallback, &echidna, &prover, &path, &content).await?;
+            passed &= verified;
+            outcomes.push(serde_json::json!({
+                "path": path,
+                "verified": verified,
+                "duration_ms": started.elapsed().as_millis() as u64,
+                "output": output,
+            }));
+        }
+        reports.push((prover, outcomes));
+    }
+
+    match format {
+        "json" => {
+            let json: Vec<_> = reports
+                .iter()
+                .map(|(prover, files)| serde_json::json!({ "prover": prover.as_str(), "files": files }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        "markdown" => {
+            for (prover, files) in &reports {
+                let success = files.iter().all(|f| f["verified"] == true);
+                let output: Vec<&str> = files.iter().filter_map(|f| f["output"].as_str()).collect();
+                let formatted =
+                    config.bot.mode.format_result(success, prover.display_name(), &output.join("\n"), vec![]);
+                println!("{}", result_formatter::generate_pr_comment(&formatted, config.bot.mode));
+            }
+        }
+        _ => {
+            for (prover, files) in &reports {
+                let failed = files.iter().filter(|f| f["verified"] == false).count();
+                println!(
+                    "{}: {} verified, {} failed",
+                    prover.display_name(),
+                    files.len() - failed,
+                    failed
+                );
+                for file in files {
+                    let verified = file["verified"] == true;
+                    println!(
+                        "  {} {} ({} ms)",
+                        if verified { "✓" } else { "✗" },
+                        file["path"].as_str().unwrap_or_default(),
+                        file["duration_ms"]
+                    );
+                    let output = file["output"].as_str().unwrap_or_default().trim();
+                    if !verified && !output.is_empty() {
+                        for line in output.lines() {
+                            println!("      {}", line);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(passed)
+}
+
+/// Proof files at `path` grouped by prover: the file itself, or every
+/// recognised file under a directory. `prover` restricts a directory to
+/// that prover's files and overrides detection for a single file.
+fn local_proof_files(path: &Path, prover: Option<ProverKind>) -> Result<Vec<(ProverKind, Vec<PathBuf>)>> {
+    if !path.is_dir() {
+        let prover = match prover {
+            Some(prover) => prover,
+            None => detect_prover_from_filename(path).ok_or_else(|| {
+                echidnabot::Error::InvalidInput(format!(
+                    "cannot tell the prover for {}; pass --prover",
+                    path.display()
+                ))
+            })?,
+        };
+        return Ok(vec![(prover, vec![path.to_path_buf()])]);
+    }
+
+    let provers: Vec<ProverKind> = match prover {
+        Some(prover) => vec![prover],
+        None => ProverKind::all().collect(),
+    };
+    let routes = ExtensionTable::builtin();
+    let ignore = std::fs::read_to_string(path.join(modes::IGNORE_FILE))
+        .map(|content| IgnoreFile::parse(&content))
+        .unwrap_or_default();
+    let filter = ProofFilter::new(ProofsSection::default(), ignore);
+    let mut files = collect_proof_files(path, routes, &provers, &filter);
+    files.sort();
+
+    let mut groups: Vec<(ProverKind, Vec<PathBuf>)> = Vec::new();
+    for file in files {
+        let name = file.to_string_lossy();
+        let Some(kind) = routes.provers_for(&name).iter().find(|p| provers.contains(p)) else {
+            continue;
+        };
+        if let Some(sniffer) = routes.sniffer(&name, kind) {
+            let content = std::fs::read_to_string(&file).unwrap_or_default();
+            if !sniffer.matches(&content) {
+                continue;
+            }
+        }
+        match groups.iter_mut().find(|(p, _)| p == kind) {
+            Some((_, group)) => group.push(file),
+            None => groups.push((kind.clone(), vec![file])),
+        }
+    }
+    Ok(groups)
+}
+
+async fn attest(action: AttestAction) -> Result<()> {
+    use echidnabot::attest::{verify, Envelope, KeySet, ResultSigner};
+
+    match action {
+        AttestAction::Verify { file, keys } => {
+            let envelope: Envelope = serde_json::from_str(&fs::read_to_string(&file).await?)?;
+            let key_set: KeySet = if keys.starts_with("https://") || keys.starts_with("http://") {
+                reqwest::get(&keys)
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| echidnabot::Error::Internal(format!("fetching {}: {}", keys, e)))?
+                    .json()
+                    .await
+                    .map_err(|e| echidnabot::Error::InvalidInput(format!("{}: {}", keys, e)))?
+            } else {
+                serde_json::from_str(&fs::read_to_string(&keys).await?)?
+            };
+            let claims = if envelope.payload_type == echidnabot::certificate::PAYLOAD_TYPE {
+                let claims = echidnabot::certificate::verify(&envelope, &key_set)?;
+                tracing::info!("Certificate {} verified against {}", file, keys);
+                serde_json::to_string_pretty(&claims)?
+            } else {
+                let claims = verify(&envelope, &key_set)?;
+                tracing::info!("Attestation {} verified against {}", file, keys);
+                serde_json::to_string_pretty(&claims)?
+            };
+            println!("{}", claims);
+        }
+        AttestAction::Keygen { output } => {
+            let signer = ResultSigner::generate();
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            let mut file = options.open(&output)?;
+            std::io::Write::write_all(&mut file, format!("{}\n", signer.seed_hex()).as_bytes())?;
+            let key = signer.published_key();
+            tracing::info!("Wrote attestation signing key {} to {}", key.keyid, output);
+            println!("{}", serde_json::to_string_pretty(&key)?);
+        }
+    }
+    Ok(())
+}
+
+async fn stats(config: &Config, period: &str, top: usize) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+    let period: echidnabot::stats::Period = period.parse()?;
+    let stats =
+        echidnabot::stats::collect(&store, period, config.scheduler.max_concurrent, top.max(1)).await?;
+    let hours = |ms: u64| ms as f64 / 3_600_000.0;
+    let wait = |ms: Option<f64>| ms.map_or_else(|| "-".to_string(), |ms| format!("{:.1}s", ms / 1000.0));
+
+    println!(
+        "{} to {} ({} job slots)",
+        stats.since.format("%Y-%m-%d %H:%M"),
+        stats.until.format("%Y-%m-%d %H:%M UTC"),
+        stats.capacity
+    );
+    println!(
+        "Jobs: {} ({} failed), {:.1}/day; mean queue wait {}; utilization {:.1}%; {} escape hatch(es)",
+        stats.jobs,
+        stats.failed,
+        stats.jobs_per_day,
+        wait(stats.avg_wait_ms),
+        stats.utilization * 100.0,
+        stats.escape_hatches
+    );
+    if !stats.daily.is_empty() {
+        println!("\nPer day:");
+        for (day, jobs) in &stats.daily {
+            println!("  {}  {:>6}", day, jobs);
+        }
+    }
+    if !stats.provers.is_empty() {
+        println!("\n  {:<12} {:>6} {:>6} {:>9} {:>9} {:>6}", "PROVER", "JOBS", "FAILED", "WAIT", "RUN (h)", "UTIL");
+        for p in &stats.provers {
+            println!(
+                "  {:<12} {:>6} {:>6} {:>9} {:>9.1} {:>5.1}%",
+                p.prover,
+                p.jobs,
+                p.failed,
+                wait(p.avg_wait_ms),
+                hours(p.busy_ms),
+                p.utilization * 100.0
+            );
+        }
+    }
+    if !stats.top_repos.is_empty() {
+        println!("\n  {:<40} {:>6} {:>9} {:>7}", "REPOSITORY", "JOBS", "RUN (h)", "HATCHES");
+        for r in &stats.top_repos {
+            let name = r.name.clone().unwrap_or_else(|| format!("(deleted {})", r.repo_id));
+            println!("  {:<40} {:>6} {:>9.1} {:>7}", name, r.jobs, hours(r.busy_ms), r.escape_hatches);
+        }
+    }
+    Ok(())
+}
+
+async fn retry(config: &Config, job: &str) -> Result<()> {
+    let store = SqliteStore::connect(&config.database).await?;
+    let job_id = uuid::Uuid::parse_str(job)
+        .map_err(|_| echidnabot::Error::InvalidInput(format!("invalid job id '{}'", job)))?;
+
+    let record = echidnabot::scheduler::resubmit::retry_job(
+        &store,
+        None,
+        echidnabot::scheduler::JobId(job_id),
+    )
+    .await?;
+    println!("Queued job {} (retry of {})", record.id, job_id);
+    Ok(())
+}
+
+async fn logs(
+    config: &Config,
+    job: &str,
+    follow: bool,
+    server: Option<&str>,
+    token: Option<&str>,
+) -> Result<()> {
+    let job_id = uuid::Uuid::parse_str(job)
+        .map_err(|_| echidnabot::Error::InvalidInput(format!("invalid job id '{}'", job)))?;
+    let base = server
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("http://127.0.0.1:{}", config.server.port));
+    let url = format!("{}/jobs/{}/logs?follow={}", base.trim_end_matches('/'), job_id, follow);
+
+    let mut request = reqwest::Client::new()
+        .get(&url)
+        .header(reqwest::header::ACCEPT, "text/event-stream");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let mut response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(echidnabot::Error::InvalidInput(format!(
+            "{} answered {}: {}",
+            url,
+            status,
+            body.trim()
+        )));
+    }
+
+    // Prover output to stdout, so it can be piped; stderr and
+    // echidnabot's own notes to stderr.
+    let mut decoder = echidnabot::api::logs::SseDecoder::new();
+    while let Some(chunk) = response.chunk().await? {
+        for event in decoder.feed(&chunk) {
+            match event.event.as_str() {
+                echidnabot::api::logs::END_EVENT => return Ok(()),
+                "stderr" => eprintln!("{}", event.data),
+                "echidnabot" => eprintln!("==> {}", event.data),
+                _ => println!("{}", event.data),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn watch(config: &Config, interval: u64) -> Result<()> {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::connect(&config.database).await?);
+    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    echidnabot::watch::run(store, echidna, Duration::from_secs(interval.max(1))).await
+}
+
 async fn init_db(config: &Config) -> Result<()> {
-    let _store = SqliteStore::new(&config.database.url).await?;
+    let _store = SqliteStore::connect(&config.database).await?;
     tracing::info!("Database initialized");
     Ok(())
 }
 
+/// Idle scheduler ticks (250ms each) between retry-adoption sweeps.
+const RETRY_ADOPT_EVERY_TICKS: u32 = 20;
+
 async fn run_scheduler_loop(
     scheduler: Arc<JobScheduler>,
     store: Arc<dyn Store>,
     echidna: Arc<EchidnaClient>,
-    config: Arc<Config>,
+    config: SharedConfig,
     shutdown: ShutdownSignal,
 ) {
     // Pin a single shutdown future for the loop. Each iteration races
@@ -724,16 +2445,52 @@ async fn run_scheduler_loop(
     // configured deadline).
     let shutdown_fut = shutdown.triggered();
     tokio::pin!(shutdown_fut);
+    let mut idle_ticks: u32 = 0;
     loop {
+        // Each job runs with the configuration current when it starts.
+        let config = config.load_full();
+
+        // Results reported by remote workers (`/fleet`).
+        for (job, result) in scheduler.take_remote_results().await {
+            finish_job(&scheduler, &store, echidna.as_ref(), &config, &job, result, Vec::new())
+                .instrument(job_span(&job))
+                .await;
+        }
+
+        // Stored results the freshness reconciler found missing on the
+        // platform.
+        for (job, result) in scheduler.take_reports().await {
+            if let Err(err) = report_to_platform(store.clone(), echidna.as_ref(), &config, &job, &result)
+                .instrument(job_span(&job))
+                .await
+            {
+                tracing::warn!("Re-report of job {} skipped: {}", job.id, err);
+            }
+        }
+
         if let Some(job) = scheduler.try_start_next().await {
+            // Child of the webhook's trace, when it had one.
+            let span = job_span(&job);
             if let Err(err) = mark_job_running(store.as_ref(), &job).await {
                 tracing::warn!("Failed to mark job {} running: {}", job.id, err);
             }
 
-            let result = match process_job(&job, store.as_ref(), echidna.as_ref(), &config).await {
+            // A newer head commit can supersede the job mid-run
+            // (`[bot] cancel_superseded`); its record is already marked
+            // cancelled, so only the scheduler slot needs releasing.
+            let mut sources = Vec::new();
+            let outcome = tokio::select! {
+                outcome = process_job(&job, store.as_ref(), echidna.as_ref(), &config, scheduler.logs(), &mut sources)
+                    .instrument(span.clone()) => outcome,
+                _ = scheduler.cancelled(job.id) => {
+                    scheduler.release_cancelled(job.id).await;
+                    continue;
+                }
+            };
+            let result = match outcome {
                 Ok(result) => result,
                 Err(err) => {
-                    tracing::error!("Job {} failed: {}", job.id, err);
+                    span.in_scope(|| tracing::error!("Job {} failed: {}", job.id, err));
                     echidnabot::scheduler::JobResult {
                         success: false,
                         message: err.to_string(),
@@ -743,44 +2500,46 @@ async fn run_scheduler_loop(
                         failed_files: vec![],
                         confidence: None,
                         axioms: None,
+                        prover_version: None,
+                        prover_args: vec![],
+                        execution_path: None,
                     }
                 }
             };
 
-            if let Err(err) = finalize_job(store.as_ref(), &job, &result).await {
-                tracing::warn!("Failed to finalize job {}: {}", job.id, err);
-            }
-
-            // Phase 2b: record double-loop feedback — tactic outcomes + corpus delta.
-            // Best-effort: errors are logged and swallowed so they never stall the
-            // scheduler. Both writes are gated by `config.corpus.enabled`.
-            record_feedback(&job, &result, store.clone(), &config).await;
-
-            // Phase 3: report the outcome back to the originating platform
-            // (check run + optional PR comment) per the resolved bot mode.
-            // Errors here are logged but never block the scheduler — the DB
-            // is the source of truth, and a missing GitHub token / 503 from
-            // the platform shouldn't cascade.
-            if let Err(err) = report_to_platform(
-                store.clone(),
-                echidna.as_ref(),
-                &config,
-                &job,
-                &result,
-            )
-            .await
-            {
-                tracing::warn!("Platform report skipped for job {}: {}", job.id, err);
-            }
-
-            scheduler
-                .complete_job(job.id, result)
+            finish_job(&scheduler, &store, echidna.as_ref(), &config, &job, result, sources)
+                .instrument(span)
                 .await;
         } else {
             // Idle — wait briefly for either the next polling tick or
             // the shutdown signal. Whichever fires first wins; on
             // shutdown we return immediately rather than burning
             // another 250ms before noticing.
+            //
+            // Every ~5s of idling, pick up retries queued in the store by
+            // `echidnabot retry` (or left over from a restart).
+            idle_ticks = idle_ticks.wrapping_add(1);
+            if idle_ticks.is_multiple_of(RETRY_ADOPT_EVERY_TICKS) {
+                match echidnabot::scheduler::resubmit::adopt_queued_retries(
+                    store.as_ref(),
+                    scheduler.as_ref(),
+                )
+                .await
+                {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!("Adopted {} queued retry job(s) from the store", n),
+                    Err(err) => tracing::warn!("Retry adoption sweep failed: {}", err),
+                }
+                // Jobs held by remote workers that stopped re-registering.
+                if let Err(err) = echidnabot::scheduler::supersede::requeue_orphaned(
+                    store.as_ref(),
+                    scheduler.as_ref(),
+                )
+                .await
+                {
+                    tracing::warn!("Requeueing orphaned jobs failed: {}", err);
+                }
+            }
             tokio::select! {
                 _ = sleep(Duration::from_millis(250)) => {}
                 _ = &mut shutdown_fut => {
@@ -792,6 +2551,76 @@ async fn run_scheduler_loop(
     }
 }
 
+/// The `job.run` span of `job`, parented on the trace it was created in.
+/// `repo` is filled in once `process_job` has looked the repository up.
+fn job_span(job: &ProofJob) -> tracing::Span {
+    let span = tracing::info_span!(
+        "job.run",
+        job_id = %job.id,
+        repo_id = %job.repo_id,
+        repo = tracing::field::Empty,
+        prover = %job.prover,
+        commit = %job.commit_sha,
+        pr_number = job.pr_number,
+        delivery_id = job.delivery_id.as_deref().unwrap_or("-"),
+    );
+    echidnabot::observability::set_parent(&span, job.trace_parent.as_deref());
+    span
+}
+
+/// Everything after verification, for local and remote jobs alike:
+/// persist, feedback, archive, report, then release the scheduler slot.
+async fn finish_job(
+    scheduler: &JobScheduler,
+    store: &Arc<dyn Store>,
+    echidna: &EchidnaClient,
+    config: &Config,
+    job: &ProofJob,
+    result: echidnabot::scheduler::JobResult,
+    sources: Vec<echidnabot::archive::ArtifactFile>,
+) {
+    if let Err(err) = finalize_job(store.as_ref(), job, &result).await {
+        tracing::warn!("Failed to finalize job {}: {}", job.id, err);
+    }
+
+    // Phase 2b: record double-loop feedback — tactic outcomes + corpus delta.
+    // Best-effort: errors are logged and swallowed so they never stall the
+    // scheduler. Both writes are gated by `config.corpus.enabled`.
+    record_feedback(job, &result, store.clone(), config).await;
+
+    // Content-addressed archive of passing jobs, before the report
+    // so the check run can link it. Best-effort.
+    if config.archive.enabled && result.success {
+        match echidnabot::archive::archive_job(store.as_ref(), config, job.id, sources).await {
+            Ok(Some(cid)) => tracing::info!("Archived job {} as {}", job.id, cid),
+            Ok(None) => {}
+            Err(err) => tracing::warn!("Archiving job {} failed: {}", job.id, err),
+        }
+    }
+
+    // Phase 3: report the outcome back to the originating platform
+    // (check run + optional PR comment) per the resolved bot mode.
+    // Errors here are logged but never block the scheduler — the DB
+    // is the source of truth, and a missing GitHub token / 503 from
+    // the platform shouldn't cascade.
+    let report_span = tracing::info_span!("job.report", job_id = %job.id, success = result.success);
+    if let Err(err) = report_to_platform(store.clone(), echidna, config, job, &result)
+        .instrument(report_span)
+        .await
+    {
+        tracing::warn!("Platform report skipped for job {}: {}", job.id, err);
+    }
+
+    // `[bisect]`: a default-branch breakage starts a bisection; a probe
+    // of one advances it.
+    echidnabot::bisect::on_job_finished(store.as_ref(), scheduler, config, job, &result).await;
+
+    // `[auditor]`: certify the commit once every prover has passed it.
+    echidnabot::certificate::on_job_finished(store.as_ref(), config, job, &result).await;
+
+    scheduler.complete_job(job.id, result).await;
+}
+
 /// Phase 3: post a job's outcome back to the originating platform.
 ///
 /// Cascade:
@@ -833,11 +2662,16 @@ async fn report_to_platform(
     } else {
         None
     };
-    let mode = modes::resolve_mode_with_daemon_default(
-        &repo,
-        directive_content.as_deref(),
-        config.bot.mode,
-    );
+    let (mode, mode_source) =
+        modes::resolve_mode_traced(&repo, directive_content.as_deref(), config.bot.mode);
+    // Why the check run concludes as it does; see `echidnabot::decision`.
+    let mut trace = echidnabot::decision::DecisionTrace {
+        mode: Some(mode),
+        mode_source: Some(mode_source),
+        directive: directive_content.is_some(),
+        trust_level: job_result.confidence.as_ref().map(|c| c.level.value()),
+        ..Default::default()
+    };
 
     // Verifier mode is silent on PRs but still posts a check run.
     let proof_result = ProofResult {
@@ -858,7 +2692,7 @@ async fn report_to_platform(
     // doesn't show suggestions, so we skip the network round-trip there.
     // Suggestions are reranked through the local feedback store
     // (Package 7b-2 Reranker) so historical success informs the order.
-    let suggestions = if matches!(
+    let suggestions: Vec<echidnabot::dispatcher::TacticSuggestion> = if matches!(
         mode,
         BotMode::Advisor | BotMode::Consultant | BotMode::Regulator
     ) && !job_result.success
@@ -896,8 +2730,12 @@ async fn report_to_platform(
         vec![]
     };
 
-    let formatted =
-        result_formatter::format_proof_result(mode, &proof_result, job.prover.clone(), suggestions);
+    let formatted = result_formatter::format_proof_result(
+        mode,
+        &proof_result,
+        job.prover.clone(),
+        suggestions.clone(),
+    );
 
     let repo_id = RepoId {
         platform: repo.platform,
@@ -924,7 +2762,17 @@ async fn report_to_platform(
                 // configured threshold; tolerate single-job flake when
                 // the rest of the commit is solid.
                 if let Some(c) = coverage_for_regulator {
-                    if c.percent() >= repo.regulator_coverage_threshold {
+                    let passing = c.percent() >= repo.regulator_coverage_threshold;
+                    trace.policy(
+                        "coverage_gate",
+                        format!(
+                            "coverage {}% vs threshold {}%: {}",
+                            c.percent(),
+                            repo.regulator_coverage_threshold,
+                            if passing { "neutral" } else { "failure" }
+                        ),
+                    );
+                    if passing {
                         CheckConclusion::Neutral
                     } else {
                         CheckConclusion::Failure
@@ -932,17 +2780,164 @@ async fn report_to_platform(
                 } else {
                     // Couldn't compute coverage — fall back to strict
                     // block-on-any-failure to be safe.
+                    trace.policy("coverage_gate", "coverage unavailable: failure");
                     CheckConclusion::Failure
                 }
             }
-            _ => CheckConclusion::Neutral,
+            _ => {
+                trace.policy("mode", format!("{} reports failures as neutral", mode));
+                CheckConclusion::Neutral
+            }
         },
         echidnabot::modes::CheckStatus::Neutral => CheckConclusion::Neutral,
     };
 
+    let manifest = directive_content
+        .as_deref()
+        .and_then(modes::RepoManifest::parse)
+        .unwrap_or_default();
+
+    // Long-term duration trend check, per file and per timed theorem.
+    // Runs in every mode — a 5× slowdown is worth surfacing even when the
+    // proof passes.
+    let anomalies = if config.anomaly.enabled {
+        let mut detector = echidnabot::perf::AnomalyDetector::from_config(&config.anomaly);
+        if let Some(ratio) = manifest.budgets.slowdown_ratio {
+            detector = detector.with_ratio_threshold(ratio);
+        }
+        echidnabot::perf::detect_for_job(
+            store.as_ref(),
+            &detector,
+            job.id,
+            config.anomaly.history_window,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            tracing::debug!("Anomaly detection skipped for job {}: {}", job.id, e);
+            vec![]
+        })
+    } else {
+        vec![]
+    };
+    let anomaly_report = echidnabot::perf::format_anomaly_report(&anomalies);
+
+    // Absolute duration budgets from the manifest's `[budgets]` table.
+    // With `action = "fail"` any violation fails the check run outright.
+    let budgets = manifest.budgets.clone();
+    let budget_violations = if budgets.is_empty() {
+        vec![]
+    } else {
+        let file_timings = store
+            .list_file_timings_for_job(job.id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::debug!("Budget check: no file timings for job {}: {}", job.id, e);
+                vec![]
+            });
+        let theorem_timings = echidnabot::perf::parse_theorem_timings(&job_result.prover_output);
+        echidnabot::perf::evaluate_budgets(&budgets, &file_timings, &theorem_timings)
+    };
+    let budget_report = echidnabot::perf::format_budget_report(&budget_violations, budgets.action);
+
+    // `[axioms]`: escape hatches found in the job's files. With
+    // `severity = "error"` any of them fails the check run.
+    let axiom_severity = manifest.axioms.severity.unwrap_or_default();
+    let hatches = store.list_escape_hatches(job.id).await.unwrap_or_else(|e| {
+        tracing::debug!("No escape hatches for job {}: {}", job.id, e);
+        vec![]
+    });
+    let hatch_report = escape_hatch::format_report(&hatches, axiom_severity);
+
+    // PR jobs: which failures the PR introduced and which the base commit
+    // already had. Informational only — the conclusion is unchanged.
+    let baseline_report = if config.bot.baseline {
+        echidnabot::baseline::report_for_job(store.as_ref(), job).await
+    } else {
+        String::new()
+    };
+    let budgets_fail = !budget_violations.is_empty() && budgets.action == modes::BudgetAction::Fail;
+    if !budget_violations.is_empty() {
+        trace.policy(
+            "budgets",
+            format!(
+                "{} violation(s){}",
+                budget_violations.len(),
+                if budgets_fail { ": failure" } else { ", warning only" }
+            ),
+        );
+    }
+    let hatches_fail = escape_hatch::fails(&hatches, axiom_severity);
+    if !hatches.is_empty() {
+        trace.policy(
+            "axioms",
+            format!(
+                "{} escape hatch(es){}",
+                hatches.len(),
+                if hatches_fail { ": failure" } else { ", warning only" }
+            ),
+        );
+    }
+    let conclusion = if budgets_fail || hatches_fail {
+        CheckConclusion::Failure
+    } else {
+        conclusion
+    };
+    // `[[grace_periods]]`: after a toolchain upgrade, Regulator failures
+    // are warnings until the period expires.
+    let grace = if mode == BotMode::Regulator {
+        echidnabot::grace::active(&config.grace_periods, &job.prover, chrono::Utc::now())
+    } else {
+        None
+    };
+    let conclusion = match (grace, conclusion) {
+        (Some(grace), CheckConclusion::Failure) => {
+            trace.policy(
+                "grace_period",
+                format!("failure reported as neutral until {}", grace.expires_at.to_rfc3339()),
+            );
+            CheckConclusion::Neutral
+        }
+        (_, conclusion) => conclusion,
+    };
+    // A toolchain other than the pinned one fails the check in every mode.
+    let pin_mismatch =
+        echidnabot::toolchain::check(&repo, &job.prover, job_result.prover_version.as_deref()).err();
+    let conclusion = if pin_mismatch.is_some() { CheckConclusion::Failure } else { conclusion };
+    if let Some(mismatch) = &pin_mismatch {
+        trace.policy("prover_pin", format!("{}: failure", mismatch));
+    }
+    trace.conclusion = Some(format!("{:?}", conclusion).to_lowercase());
+    trace.blocks_merge =
+        mode == BotMode::Regulator && matches!(conclusion, CheckConclusion::Failure);
+    echidnabot::decision::record(store.as_ref(), job.id, |t| {
+        // Keep what the run recorded (cache hits, quarantine, flaky).
+        trace.cache_hits.append(&mut t.cache_hits);
+        let mut policies = std::mem::take(&mut t.policies);
+        policies.append(&mut trace.policies);
+        trace.policies = policies;
+        *t = trace;
+    })
+    .await;
+
+    // `[bot] observer`: the result and its decision trace are recorded;
+    // everything below posts to the platform.
+    if config.bot.observer {
+        tracing::debug!("Observer mode: job {} not reported to {}", job.id, repo.full_name());
+        return Ok(());
+    }
+
     // Augment the per-mode summary with coverage detail for Regulator,
     // so the GitHub Checks UI shows the threshold context inline.
     let mut summary = result_formatter::check_run_summary(&formatted, mode);
+    if let Some(mismatch) = &pin_mismatch {
+        summary = format!("🔒 **Toolchain mismatch.** {}\n\n{}", mismatch, summary);
+    }
+    if let Some(grace) = grace {
+        summary = format!("{}\n\n{}", grace.banner(), summary);
+    }
+    // An incident announcement goes above everything else.
+    let announcement = echidnabot::announcement::active(store.as_ref()).await;
+    summary = echidnabot::announcement::with_banner(announcement.as_ref(), &summary);
     if let Some(c) = coverage_for_regulator {
         summary.push_str(&format!(
             "\n\nCoverage: **{}/{}** ({}%) vs threshold **{}%** — {}",
@@ -952,32 +2947,149 @@ async fn report_to_platform(
             repo.regulator_coverage_threshold,
             if c.percent() >= repo.regulator_coverage_threshold {
                 "passing"
+            } else if grace.is_some() {
+                "below threshold; not blocking during the grace period"
             } else {
                 "below threshold; merge blocked"
             },
         ));
     }
 
+    if !anomaly_report.is_empty() {
+        summary.push_str("\n\n");
+        summary.push_str(&anomaly_report);
+    }
+    if !budget_report.is_empty() {
+        summary.push_str("\n\n");
+        summary.push_str(&budget_report);
+    }
+    if !hatch_report.is_empty() {
+        summary.push_str("\n\n");
+        summary.push_str(&hatch_report);
+    }
+    if !baseline_report.is_empty() {
+        summary.push_str("\n\n");
+        summary.push_str(&baseline_report);
+    }
+    if let Some(version) = &job_result.prover_version {
+        summary.push_str(&format!("\n\nToolchain: {} `{}`", job.prover.display_name(), version));
+    }
+
+    // `[archive]`: where this run's artifacts were published.
+    let archive_cid = if config.archive.enabled {
+        store
+            .get_result_for_job(job.id)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|r| r.archive_cid)
+    } else {
+        None
+    };
+    let details_url = archive_cid
+        .as_deref()
+        .and_then(|cid| echidnabot::archive::archive_url(&config.archive, cid));
+    if let Some(cid) = &archive_cid {
+        summary.push_str(&match &details_url {
+            Some(url) => format!("\n\n📦 Artifacts archived as [`{}`]({})", cid, url),
+            None => format!("\n\n📦 Artifacts archived as `{}`", cid),
+        });
+    }
+
+    // Failed runs get re-run / suggest / quarantine buttons (GitHub only;
+    // other adapters ignore them). The job id rides along as external_id
+    // so the `requested_action` webhook can find its way back.
+    let actions = if job_result.success {
+        vec![]
+    } else {
+        echidnabot::api::check_actions::failure_actions()
+    };
     let check = CheckRun {
-        name: format!("echidnabot/{:?}", job.prover),
+        name: echidnabot::reporting::check_name(&job.prover),
         head_sha: job.commit_sha.clone(),
         status: AdapterCheckStatus::Completed {
             conclusion,
-            summary,
+            summary: summary.clone(),
         },
-        details_url: None,
+        details_url,
+        external_id: Some(job.id.to_string()),
+        actions,
+        annotations: escape_hatch::annotations(&hatches, axiom_severity)
+            .into_iter()
+            .chain(echidnabot::perf::anomaly_annotations(&anomalies))
+            .collect(),
     };
 
     let adapter = echidnabot::adapters::build_adapter(config, repo.platform)?;
 
-    if let Err(err) = adapter.create_check_run(&repo_id, check).await {
-        tracing::warn!(
-            "create_check_run failed for {} (mode {}): {}",
-            repo.full_name(),
-            mode,
-            err
-        );
-        // Don't return — comment may still succeed.
+    // The job's PRs: the one that triggered it, or those containing its
+    // pushed commit. The check falls back to commenting there when the
+    // platform can't show it otherwise; mode comments go there too.
+    let prs = echidnabot::pr_association::for_job(store.as_ref(), adapter.as_ref(), &repo_id, job).await;
+
+    let check_target = format!("{} @ {}", check.name, job.commit_sha);
+    match echidnabot::reporting::publish(adapter.as_ref(), &repo_id, check, &prs).await {
+        Ok(channel) => {
+            let bot = echidnabot::audit::BOT;
+            let action = if channel == echidnabot::reporting::Channel::Comment {
+                AuditAction::CommentPosted
+            } else {
+                AuditAction::CheckRunCreated
+            };
+            echidnabot::audit::record(
+                store.as_ref(),
+                bot,
+                action,
+                Some(&repo),
+                Some(check_target.clone()),
+                &summary,
+            )
+            .await;
+            // A comment informs but blocks nothing.
+            if mode == BotMode::Regulator
+                && matches!(conclusion, CheckConclusion::Failure)
+                && channel != echidnabot::reporting::Channel::Comment
+            {
+                echidnabot::audit::record(
+                    store.as_ref(),
+                    bot,
+                    AuditAction::MergeBlocked,
+                    Some(&repo),
+                    Some(check_target),
+                    &summary,
+                )
+                .await;
+            }
+        }
+        Err(err) => {
+            tracing::warn!(
+                "Result of {} not reported on {} (mode {}): {}",
+                check_target,
+                repo.full_name(),
+                mode,
+                err
+            );
+            // Don't return — mode comments may still succeed.
+        }
+    }
+
+    // `[auto_merge]` / `[dependency_prs]`: the last job to pass for the
+    // head commit finds every prover green and merges.
+    if matches!(conclusion, CheckConclusion::Success) {
+        if let (Some(pr_number), Some(policy)) = (
+            job.pr_number,
+            echidnabot::automerge::MergePolicy::for_job(&manifest, job.dependency_update),
+        ) {
+            echidnabot::automerge::merge_if_green(
+                store.as_ref(),
+                adapter.as_ref(),
+                &repo,
+                &job.commit_sha,
+                pr_number,
+                &policy,
+            )
+            .await;
+        }
     }
 
     // Modes that want PR comments: Advisor (suggestions), Consultant
@@ -987,31 +3099,95 @@ async fn report_to_platform(
         mode,
         BotMode::Advisor | BotMode::Consultant | BotMode::Regulator
     );
-    if wants_comment {
-        if let Some(pr_number) = job.pr_number {
-            let mut body = result_formatter::generate_pr_comment(&formatted, mode);
-            // For Regulator, append the coverage stanza so the PR comment
-            // tells the reviewer exactly where the commit sits relative to
-            // the configured threshold.
-            if let Some(c) = coverage_for_regulator {
-                body.push_str(&format!(
-                    "\n\n### 🎯 Coverage gate\n\n\
-                     Provers passing: **{}/{}** (**{}%**)  \n\
-                     Threshold: **{}%**  \n\
-                     Status: **{}**\n",
-                    c.proven,
-                    c.total,
-                    c.percent(),
-                    repo.regulator_coverage_threshold,
-                    if c.percent() >= repo.regulator_coverage_threshold {
-                        "✅ passing"
-                    } else {
-                        "🚫 below threshold — merge blocked"
-                    },
-                ));
+    if wants_comment && !prs.is_empty() && adapter.capabilities().comments {
+        let mut body = result_formatter::generate_pr_comment(&formatted, mode);
+        if let Some(grace) = grace {
+            body = format!("> {}\n\n{}", grace.banner(), body);
+        }
+        body = echidnabot::announcement::with_banner(announcement.as_ref(), &body);
+        // For Regulator, append the coverage stanza so the PR comment
+        // tells the reviewer exactly where the commit sits relative to
+        // the configured threshold.
+        if let Some(c) = coverage_for_regulator {
+            body.push_str(&format!(
+                "\n\n### 🎯 Coverage gate\n\n\
+                 Provers passing: **{}/{}** (**{}%**)  \n\
+                 Threshold: **{}%**  \n\
+                 Status: **{}**\n",
+                c.proven,
+                c.total,
+                c.percent(),
+                repo.regulator_coverage_threshold,
+                if c.percent() >= repo.regulator_coverage_threshold {
+                    "✅ passing"
+                } else if grace.is_some() {
+                    "⏳ below threshold — not blocking during the grace period"
+                } else {
+                    "🚫 below threshold — merge blocked"
+                },
+            ));
+        }
+        if !anomaly_report.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&anomaly_report);
+        }
+        if !budget_report.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&budget_report);
+        }
+        if !hatch_report.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&hatch_report);
+        }
+        if !hatch_report.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&hatch_report);
+        }
+        if !baseline_report.is_empty() {
+            body.push_str("\n\n");
+            body.push_str(&baseline_report);
+        }
+        // Z3 / CVC5: the model (or unsat core) behind each wrong answer.
+        if smtlib::handles(&job.prover) && !job_result.success {
+            let report = smtlib::format_report(&smtlib::counterexamples(&job_result.prover_output));
+            if !report.is_empty() {
+                body.push_str("\n\n");
+                body.push_str(&report);
+            }
+        }
+        // A suggestion that edits the failing line goes on the diff as a
+        // one-click suggested change. Consultant already comments on that
+        // line; `[fixer]` posts only verified ones, itself.
+        let mut inline = Vec::new();
+        if mode != BotMode::Consultant && !manifest.fixer.enabled && !suggestions.is_empty() {
+            if let (Some(failed_file), Some(line)) = (
+                job_result.failed_files.first(),
+                extract_error_line(&job_result.prover_output),
+            ) {
+                inline = echidnabot::fixer::suggest_inline(
+                    store.as_ref(),
+                    adapter.as_ref(),
+                    &repo,
+                    job,
+                    failed_file,
+                    line,
+                    &suggestions,
+                    &prs,
+                )
+                .await;
             }
+        }
+        for &pr_number in &prs {
             let pr_id = PrId(pr_number.to_string());
-
+            let body = if inline.contains(&pr_number) {
+                format!(
+                    "{}\n\n💡 The top suggestion is on the diff as a suggested change, \
+                     ready to apply.",
+                    body
+                )
+            } else {
+                body.clone()
+            };
             // Consultant mode: attempt an inline review comment on the first
             // failing proof file so the annotation lands next to the code.
             // Falls back to a general PR comment when the file is not in the
@@ -1024,21 +3200,32 @@ async fn report_to_platform(
                         path: failed_file.clone(),
                         line: extract_error_line(&job_result.prover_output).unwrap_or(1),
                     };
-                    match adapter.create_review_comment(&repo_id, pr_id.clone(), &body, location).await {
-                        Ok(id) => Ok(id),
+                    match adapter.create_review_comment(&repo_id, pr_id, &body, location).await {
+                        Ok(id) => {
+                            echidnabot::audit::record(
+                                store.as_ref(),
+                                echidnabot::audit::BOT,
+                                AuditAction::ReviewCommentPosted,
+                                Some(&repo),
+                                Some(format!("PR #{}", pr_number)),
+                                &body,
+                            )
+                            .await;
+                            Ok(id)
+                        }
                         Err(review_err) => {
                             tracing::debug!(
                                 "Review comment failed for {} PR #{} ({}); falling back to PR comment",
                                 repo.full_name(), pr_number, review_err
                             );
-                            adapter.create_comment(&repo_id, pr_id, &body).await
+                            post_result_comment(store.as_ref(), adapter.as_ref(), config, &repo, job, pr_number, &body).await
                         }
                     }
                 } else {
-                    adapter.create_comment(&repo_id, pr_id, &body).await
+                    post_result_comment(store.as_ref(), adapter.as_ref(), config, &repo, job, pr_number, &body).await
                 }
             } else {
-                adapter.create_comment(&repo_id, pr_id, &body).await
+                post_result_comment(store.as_ref(), adapter.as_ref(), config, &repo, job, pr_number, &body).await
             };
 
             if let Err(err) = comment_result {
@@ -1053,9 +3240,62 @@ async fn report_to_platform(
         }
     }
 
+    // `[fixer]`: a suggestion that makes the failing file verify is
+    // proposed as a patch for a human to accept.
+    if manifest.fixer.enabled && !suggestions.is_empty() {
+        if let (Some(failed_file), Some(line)) = (
+            job_result.failed_files.first(),
+            extract_error_line(&job_result.prover_output),
+        ) {
+            echidnabot::fixer::propose(
+                store.as_ref(),
+                adapter.as_ref(),
+                echidna,
+                &repo,
+                job,
+                failed_file,
+                line,
+                &suggestions,
+                &manifest.fixer,
+                &prs,
+            )
+            .await;
+        }
+    }
+
     Ok(())
 }
 
+/// Post `body` as the job's comment on PR `pr_number`: edits the previous
+/// result comment for the same PR and prover unless `[bot]
+/// sticky_comments` is off.
+async fn post_result_comment(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    config: &Config,
+    repo: &StoreRepository,
+    job: &ProofJob,
+    pr_number: u64,
+    body: &str,
+) -> Result<echidnabot::adapters::CommentId> {
+    let id = if config.bot.sticky_comments {
+        echidnabot::pr_comment::upsert(store, adapter, repo, pr_number, &job.prover, body).await?
+    } else {
+        let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+        adapter.create_comment(&repo_id, PrId(pr_number.to_string()), body).await?
+    };
+    echidnabot::audit::record(
+        store,
+        echidnabot::audit::BOT,
+        AuditAction::CommentPosted,
+        Some(repo),
+        Some(format!("PR #{}", pr_number)),
+        body,
+    )
+    .await;
+    Ok(id)
+}
+
 async fn mark_job_running(store: &dyn Store, job: &ProofJob) -> Result<()> {
     let mut record = store
         .get_job(job.id)
@@ -1170,61 +3410,140 @@ async fn record_feedback(
     }
 }
 
+/// `sources` receives the verified proof files for `[archive]` while the
+/// checkout is at hand (passing jobs only).
 async fn process_job(
     job: &ProofJob,
     store: &dyn Store,
     echidna: &EchidnaClient,
     config: &Config,
+    logs: &Arc<JobLogs>,
+    sources: &mut Vec<echidnabot::archive::ArtifactFile>,
 ) -> Result<echidnabot::scheduler::JobResult> {
     let start = Instant::now();
-    let healthy = echidna.health_check().await?;
-    if !healthy {
-        return Err(echidnabot::Error::Echidna(
-            "ECHIDNA core reported unhealthy status".to_string(),
-        ));
-    }
-
-    let status = echidna.prover_status(&job.prover).await?;
-    if status != ProverStatus::Available {
-        return Err(echidnabot::Error::Echidna(format!(
-            "Prover {} not available (status: {})",
-            job.prover.display_name(),
-            format_prover_status(status)
-        )));
-    }
-
     let repo = store
         .get_repository(job.repo_id)
         .await?
         .ok_or_else(|| echidnabot::Error::RepoNotFound(job.repo_id.to_string()))?;
+    tracing::Span::current().record("repo", repo.full_name().as_str());
+
+    // A peer instance already verified this commit; don't repeat it —
+    // unless the repository pins this prover, since the peer's toolchain
+    // is unknown.
+    if !config.federation.peers.is_empty() && !repo.prover_pins.contains_key(job.prover.as_str()) {
+        if let Some(result) = echidnabot::federation::imported_result(store, &repo, job).await {
+            tracing::info!("Job {} satisfied by a federated result", job.id);
+            let hit = format!("federated: {}", result.message);
+            echidnabot::decision::record(store, job.id, |t| t.cache_hits.push(hit)).await;
+            return Ok(result);
+        }
+    }
+
+    // Jobs that can run in the sandbox don't wait on ECHIDNA's health; see
+    // `[executor] dispatch`.
+    if config.executor.dispatch() == DispatchStrategy::PreferEchidna {
+        let healthy = echidna.health_check().await?;
+        if !healthy {
+            return Err(echidnabot::Error::Echidna(
+                "ECHIDNA core reported unhealthy status".to_string(),
+            ));
+        }
+
+        let status = echidna.prover_status(&job.prover).await?;
+        if status != ProverStatus::Available {
+            return Err(echidnabot::Error::Echidna(format!(
+                "Prover {} not available (status: {})",
+                job.prover.display_name(),
+                format_prover_status(status)
+            )));
+        }
+    }
 
     let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
     let repo_path = clone_repo(config, &repo_id, &job.commit_sha).await?;
 
-    let mut file_paths = job.file_paths.clone();
-    if file_paths.is_empty() {
-        let extensions: Vec<String> = job
-            .prover
-            .file_extensions()
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        let repo_path_clone = repo_path.clone();
-        file_paths = tokio::task::spawn_blocking(move || {
-            collect_files_by_extension(&repo_path_clone, &extensions)
+    // Path globs: the manifest's `[proofs]` table, else the repository
+    // settings, narrowed by `.echidnaignore`. Applied to listed and
+    // discovered files alike.
+    let directive = match echidnabot::adapters::build_adapter(config, repo.platform) {
+        Ok(adapter) => modes::fetch_directive_via_adapter(adapter.as_ref(), &repo_id, None).await,
+        Err(_) => None,
+    };
+    let manifest = directive.as_deref().and_then(modes::RepoManifest::parse);
+    // Which files are this prover's: `[provers.extensions]` settles
+    // extensions shared with other provers or languages.
+    let routes = Arc::new(
+        manifest
+            .as_ref()
+            .map(|m| m.extension_table())
+            .unwrap_or_else(|| ExtensionTable::builtin().clone()),
+    );
+    // Lean 4 packages build with Lake unless `[provers.lean4] lake = false`.
+    let lake_enabled = manifest
+        .as_ref()
+        .and_then(|m| m.provers.for_prover(&ProverKind::new("lean")))
+        .and_then(|p| p.lake)
+        .unwrap_or(true);
+    // Isabelle theories build by session unless `[provers.isabelle] sessions = false`.
+    let sessions_enabled = manifest
+        .as_ref()
+        .and_then(|m| m.provers.per_prover.get("isabelle"))
+        .and_then(|p| p.sessions)
+        .unwrap_or(true);
+    // `[provers.<slug>] flags`, checked below before anything runs.
+    let prover_args = manifest
+        .as_ref()
+        .and_then(|m| m.provers.for_prover(&job.prover))
+        .map(|p| p.flags.clone())
+        .unwrap_or_default();
+    let axiom_policy = manifest.as_ref().map(|m| m.axioms.clone()).unwrap_or_default();
+    let reruns = manifest.as_ref().map(|m| m.flaky.reruns).unwrap_or(0);
+    let normalize = manifest.as_ref().map(|m| m.normalize.clone()).filter(|n| n.enabled);
+    let proofs = manifest
+        .map(|m| m.proofs)
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| repo.path_filter());
+    // `.echidnaignore` as of this checkout, i.e. the job's commit.
+    let path_filter = ProofFilter::new(proofs, IgnoreFile::load(&repo_path).await);
+
+    // `[normalize]`: CRLF, byte order marks and UTF-16 out of the checkout
+    // before anything reads it.
+    let mut normalized = Vec::new();
+    if let Some(section) = normalize {
+        let (root, walk_routes, filter) = (repo_path.clone(), routes.clone(), path_filter.clone());
+        let prover = job.prover.clone();
+        normalized = tokio::task::spawn_blocking(move || {
+            let files: Vec<String> = collect_proof_files(&root, &walk_routes, &[prover], &filter)
+                .iter()
+                .map(|path| relative_proof_path(&root, path))
+                .collect();
+            echidnabot::executor::normalize::normalize_files(&root, &files, &section)
         })
         .await
-        .unwrap_or_default()
-        .into_iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect();
-
-        if let Some(mut record) = store.get_job(job.id).await? {
-            record.file_paths = file_paths.clone();
-            store.update_job(&record).await?;
+        .unwrap_or_default();
+        for line in &normalized {
+            logs.push(job.id, LogStream::Echidnabot, &format!("Normalized {}", line));
+        }
+        if !normalized.is_empty() {
+            let effect = format!("{} file(s): {}", normalized.len(), normalized.join("; "));
+            echidnabot::decision::record(store, job.id, |t| t.policy("normalize", effect)).await;
         }
     }
 
+    // Refresh the import graph from this checkout so later pushes can be
+    // narrowed to changed files and their dependents. Best-effort.
+    if config.bot.incremental && depgraph::supports(&job.prover) {
+        record_dependency_graph(store, job, &repo_path, &routes, &path_filter).await;
+    }
+
+    let file_paths = if job.file_paths.is_empty() {
+        discover_proof_files(store, job, &repo_path, &routes, path_filter, logs).await?
+    } else {
+        let listed = filter_proof_files(&repo_path, job.file_paths.clone(), &path_filter);
+        // `.v` may be Verilog, `.ml` plain OCaml: keep what reads as a proof.
+        sniff_proof_files(&repo_path, listed, &routes, &job.prover).await
+    };
+
     if file_paths.is_empty() {
         return Ok(echidnabot::scheduler::JobResult {
             success: false,
@@ -1235,6 +3554,9 @@ async fn process_job(
             failed_files: vec![],
             confidence: None,
             axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
         });
     }
 
@@ -1243,81 +3565,508 @@ async fn process_job(
     let mut verified = Vec::new();
     let mut failed = Vec::new();
     let mut prover_output = String::new();
+    if !normalized.is_empty() {
+        prover_output.push_str("echidnabot normalized the checkout before verifying:\n");
+        for line in &normalized {
+            prover_output.push_str(&format!("  {}\n", line));
+        }
+    }
 
-    // Build the local sandboxed executor once (only when configured).
-    // When `executor.local_isolation = false` (default), proofs delegate
-    // to ECHIDNA's REST API, which runs them in its own process. When
-    // `true`, each proof runs in a Podman / bubblewrap sandbox locally
-    // — needed for air-gapped or no-ECHIDNA setups.
-    let local_executor = if config.executor.local_isolation {
-        let mut ex = echidnabot::executor::container::PodmanExecutor::new().await;
-        // Per-prover image fan-out — each prover gets the image
-        // specialised for its binaries (smaller, faster cold-start,
-        // narrower attack surface). Falls back to the default
-        // container_image when no per-prover entry exists.
-        if let Some(img) = config.executor.image_for(job.prover.clone()) {
-            ex = ex.with_image(img);
-        }
-        if let Some(ref mem) = config.executor.memory_limit {
-            ex = ex.with_memory_limit(mem.clone());
-        }
-        if let Some(cpus) = config.executor.cpu_limit {
-            ex = ex.with_cpu_limit(cpus);
-        }
-        if let Some(secs) = config.executor.timeout_secs {
-            ex = ex.with_timeout(std::time::Duration::from_secs(secs));
-        }
-        // Refuse to start if the operator opted in but neither podman
-        // nor bubblewrap is available (fail-safe per SONNET-TASKS Task 1).
-        if matches!(
-            ex.backend(),
-            echidnabot::executor::container::IsolationBackend::None
-        ) {
-            return Err(echidnabot::Error::Config(
-                "executor.local_isolation = true but no isolation backend (podman or bubblewrap) was found on PATH. Refusing to run proofs without isolation.".to_string()
-            ));
+    // The sandbox streams output into the job's log as the prover runs.
+    let local_executor = build_local_executor(config, &job.prover, job.timeout_secs)
+        .await?
+        .map(|ex| {
+            let (logs, job_id) = (logs.clone(), job.id);
+            let ex = ex.with_output(Arc::new(move |stream: OutputStream, line: &str| {
+                logs.push(job_id, stream.into(), line)
+            }));
+            match &config.executor.cache_dir {
+                Some(dir) if job.prover.as_str() == "isabelle" => {
+                    ex.with_cache(isabelle::heap_cache(dir, job.repo_id), isabelle::HEAP_CACHE_MOUNT)
+                }
+                _ => ex,
+            }
+        });
+    // `echidna-with-local-fallback`: the sandbox only takes files ECHIDNA
+    // cannot be reached for; everything else goes through ECHIDNA.
+    let (local_executor, fallback_executor) = match config.executor.dispatch() {
+        DispatchStrategy::EchidnaWithLocalFallback => (None, local_executor),
+        _ => (local_executor, None),
+    };
+
+    // `[executor] toolchain_image`: the image for the prover version the
+    // project asks for (`lean-toolchain`, `coq.opam`, ...).
+    let local_executor = match (local_executor, config.executor.toolchain_image.as_deref()) {
+        (Some(ex), Some(template)) => {
+            match echidnabot::toolchain::select_image(ex, template, &repo_path, &job.prover).await {
+                Ok(ex) => Some(ex),
+                Err(message) => {
+                    logs.push(job.id, LogStream::Echidnabot, &message);
+                    return Ok(echidnabot::scheduler::JobResult {
+                        success: false,
+                        message,
+                        prover_output: String::new(),
+                        duration_ms: start.elapsed().as_millis() as u64,
+                        verified_files: vec![],
+                        failed_files: vec![],
+                        confidence: None,
+                        axioms: None,
+                        prover_version: None,
+                        prover_args: vec![],
+                        execution_path: None,
+                    });
+                }
+            }
         }
-        Some(ex)
-    } else {
-        None
+        (ex, _) => ex,
     };
 
-    for path in &file_paths {
-        let full_path = if Path::new(path).is_absolute() {
+    // Which toolchain runs this job: recorded with the result, and held
+    // to the repository's `proverPins`.
+    let prover_version =
+        echidnabot::toolchain::discover(local_executor.as_ref(), echidna, &job.prover).await;
+    if let Err(mismatch) = echidnabot::toolchain::check(&repo, &job.prover, prover_version.as_deref()) {
+        logs.push(job.id, LogStream::Echidnabot, &mismatch);
+        return Ok(echidnabot::scheduler::JobResult {
+            success: false,
+            message: mismatch,
+            prover_output: String::new(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version,
+            prover_args: vec![],
+            execution_path: None,
+        });
+    }
+
+    // Repository flags off the prover's allowlist fail the job unrun.
+    if let Err(reason) = echidnabot::executor::prover_args::validate(&job.prover, &prover_args) {
+        logs.push(job.id, LogStream::Echidnabot, &reason);
+        return Ok(echidnabot::scheduler::JobResult {
+            success: false,
+            message: reason,
+            prover_output: String::new(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version,
+            prover_args: vec![],
+            execution_path: None,
+        });
+    }
+    if !prover_args.is_empty() {
+        logs.push(
+            job.id,
+            LogStream::Echidnabot,
+            &format!("Prover flags: {}", prover_args.join(" ")),
+        );
+    }
+    let local_executor = local_executor.map(|ex| ex.with_prover_args(prover_args.clone()));
+    let fallback_executor = fallback_executor.map(|ex| ex.with_prover_args(prover_args.clone()));
+    let fallback = fallback_executor.as_ref();
+    // Where the files were checked, for the result.
+    let mut execution_path: Option<ExecutionPath> = None;
+
+    // "Re-run with larger timeout" jobs carry their own ECHIDNA timeout.
+    let echidna_override = job
+        .timeout_secs
+        .map(|secs| echidna.with_timeout(std::time::Duration::from_secs(secs)));
+    let echidna = echidna_override.as_ref().unwrap_or(echidna);
+    let echidna_flagged =
+        (!prover_args.is_empty()).then(|| echidna.with_flags(prover_args.clone()));
+    let echidna = echidna_flagged.as_ref().unwrap_or(echidna);
+
+    // Quarantined files still run and report, but don't fail the job.
+    let quarantined: std::collections::HashSet<String> = store
+        .list_quarantined_proofs(job.repo_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|q| q.file_path)
+        .collect();
+    let mut quarantined_failures = Vec::new();
+    // Failed files that passed on a `[flaky]` re-run.
+    let mut flaky_files = Vec::new();
+    // Per-file and per-theorem results, for `ProofJob.items`.
+    let mut items: Vec<ProofItem> = Vec::new();
+
+    // Files of a Lake project are built together, in the sandbox with the
+    // checkout mounted, and skip the file-by-file loop below.
+    let mut single_files = file_paths.clone();
+    if let Some(ex) = local_executor
+        .as_ref()
+        .filter(|_| lake_enabled && job.prover.as_str() == "lean")
+    {
+        let (root, listed) = (repo_path.clone(), file_paths.clone());
+        let projects = tokio::task::spawn_blocking(move || lake::projects(&root, &listed))
+            .await
+            .unwrap_or_default();
+        for (root, files) in projects {
+            let command = lake::build_command(&root, &files, !job.file_paths.is_empty());
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!("Running `{}` in ./{}", command, root),
+            );
+            let (built, output) = match ex
+                .execute_in_workspace(job.prover.clone(), &repo_path, &root, &command)
+                .await
+            {
+                Ok(exec) => (exec.exit_code == Some(0), format!("{}\n{}", exec.stdout, exec.stderr)),
+                Err(e) => (false, format!("Local executor error: {}", e)),
+            };
+            execution_path = Some(ExecutionPath::Local.merge(execution_path));
+            let outcome = lake::parse_output(&output, &root, &files, built);
+            items.extend(proof_items::lake(&outcome));
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!(
+                    "Lake project ./{}: {} verified, {} failed",
+                    root,
+                    outcome.verified.len(),
+                    outcome.failed.len()
+                ),
+            );
+            single_files.retain(|file| !files.contains(file));
+            // Module build times feed the trend history like single files'.
+            for (file, ms) in &outcome.durations_ms {
+                let timing = FileTimingRecord::new(
+                    job.id,
+                    job.repo_id,
+                    job.prover.clone(),
+                    file.clone(),
+                    job.commit_sha.clone(),
+                    *ms,
+                    outcome.verified.contains(file),
+                );
+                if let Err(e) = store.record_file_timing(&timing).await {
+                    tracing::debug!("record_file_timing failed for job {}: {}", job.id, e);
+                }
+            }
+            verified.extend(outcome.verified);
+            for file in outcome.failed {
+                if quarantined.contains(&file) {
+                    quarantined_failures.push(file);
+                } else {
+                    failed.push(file);
+                }
+            }
+            if !output.trim().is_empty() && prover_output.len() < MAX_OUTPUT_BYTES {
+                let remaining = MAX_OUTPUT_BYTES - prover_output.len();
+                prover_output.push_str(&output[..output.len().min(remaining)]);
+                prover_output.push('\n');
+            }
+        }
+    }
+
+    // Theories of a `ROOT` session are built with their session, likewise.
+    if let Some(ex) = local_executor
+        .as_ref()
+        .filter(|_| sessions_enabled && job.prover.as_str() == "isabelle")
+    {
+        let (root, listed) = (repo_path.clone(), single_files.clone());
+        let grouped = tokio::task::spawn_blocking(move || isabelle::sessions(&root, &listed))
+            .await
+            .unwrap_or_default();
+        for (root, sessions) in grouped {
+            let command = isabelle::build_command(sessions.keys());
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!("Running `{}` in ./{}", command, root),
+            );
+            let (built, output) = match ex
+                .execute_in_workspace(job.prover.clone(), &repo_path, &root, &command)
+                .await
+            {
+                Ok(exec) => (exec.exit_code == Some(0), format!("{}\n{}", exec.stdout, exec.stderr)),
+                Err(e) => (false, format!("Local executor error: {}", e)),
+            };
+            execution_path = Some(ExecutionPath::Local.merge(execution_path));
+            let outcome = isabelle::parse_output(&output, &sessions, built);
+            items.extend(proof_items::files(&outcome.verified, &outcome.failed));
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!(
+                    "Isabelle sessions in ./{}: {} theories verified, {} failed",
+                    root,
+                    outcome.verified.len(),
+                    outcome.failed.len()
+                ),
+            );
+            for files in sessions.values() {
+                single_files.retain(|file| !files.contains(file));
+            }
+            verified.extend(outcome.verified);
+            for file in outcome.failed {
+                if quarantined.contains(&file) {
+                    quarantined_failures.push(file);
+                } else {
+                    failed.push(file);
+                }
+            }
+            if !output.trim().is_empty() && prover_output.len() < MAX_OUTPUT_BYTES {
+                let remaining = MAX_OUTPUT_BYTES - prover_output.len();
+                prover_output.push_str(&output[..output.len().min(remaining)]);
+                prover_output.push('\n');
+            }
+        }
+    }
+
+    // Metamath files are verified as whole databases, includes inlined.
+    if job.prover.as_str() == "metamath" {
+        let (root, listed) = (repo_path.clone(), single_files.clone());
+        let databases = tokio::task::spawn_blocking(move || metamath::databases(&root, &listed))
+            .await
+            .unwrap_or_default();
+        for (n, db) in databases.iter().enumerate() {
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!("Verifying Metamath database {} ({} file(s))", db.root, db.files.len()),
+            );
+            let (ran, output) = match local_executor.as_ref() {
+                Some(ex) => {
+                    execution_path = Some(ExecutionPath::Local.merge(execution_path));
+                    let path = format!("{}/{}.mm", metamath::WORK_DIR, n);
+                    fs::create_dir_all(repo_path.join(metamath::WORK_DIR)).await?;
+                    fs::write(repo_path.join(&path), &db.text).await?;
+                    let command = metamath::verify_command(&path);
+                    match ex.execute_in_workspace(job.prover.clone(), &repo_path, "", &command).await {
+                        Ok(exec) => (exec.exit_code == Some(0), format!("{}\n{}", exec.stdout, exec.stderr)),
+                        Err(e) => (false, format!("Local executor error: {}", e)),
+                    }
+                }
+                None => {
+                    let (ok, output, taken) =
+                        verify_content(None, fallback, echidna, &job.prover, &db.root, &db.text).await?;
+                    execution_path = Some(taken.merge(execution_path));
+                    if taken == ExecutionPath::Echidna {
+                        logs.push_output(job.id, LogStream::Stdout, &output);
+                    }
+                    (ok, output)
+                }
+            };
+            let outcome = metamath::parse_output(db, &output, ran);
+            items.extend(proof_items::metamath(&outcome));
+            for line in &outcome.report {
+                logs.push(job.id, LogStream::Echidnabot, line);
+            }
+            logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!(
+                    "Metamath database {}: {} file(s) verified, {} failed",
+                    db.root,
+                    outcome.verified.len(),
+                    outcome.failed.len()
+                ),
+            );
+            single_files.retain(|file| !db.listed.contains(file));
+            verified.extend(outcome.verified);
+            for file in outcome.failed {
+                if quarantined.contains(&file) {
+                    quarantined_failures.push(file);
+                } else {
+                    failed.push(file);
+                }
+            }
+            // Located errors first, so the check output leads with them.
+            let output = outcome.report.iter().map(|l| format!("{}\n", l)).collect::<String>() + &output;
+            if !output.trim().is_empty() && prover_output.len() < MAX_OUTPUT_BYTES {
+                let remaining = MAX_OUTPUT_BYTES - prover_output.len();
+                prover_output.push_str(&output[..output.len().min(remaining)]);
+                prover_output.push('\n');
+            }
+        }
+    }
+
+    let full_path_of = |path: &str| {
+        if Path::new(path).is_absolute() {
             PathBuf::from(path)
         } else {
             repo_path.join(path)
+        }
+    };
+
+    // Through ECHIDNA, the files' first checks go out together: their whole
+    // project as one archive (`[echidna.archive]`), else in batches
+    // (`[echidna] batch_size`). A file neither answered is checked on its
+    // own below, where it can still fall back to the sandbox.
+    let mut batched = std::collections::HashMap::new();
+    if local_executor.is_none()
+        && !single_files.is_empty()
+        && config.echidna.archive.applies_to(&job.prover)
+    {
+        let (root, listed) = (repo_path.clone(), single_files.clone());
+        let archive_config = config.echidna.archive.clone();
+        let packed = tokio::task::spawn_blocking(move || {
+            echidnabot::dispatcher::project_archive::build(&root, &listed, &archive_config)
+        })
+        .await
+        .unwrap_or_else(|e| Err(echidnabot::Error::Internal(format!("archive task: {}", e))));
+        let answered = match packed {
+            Ok(archive) => {
+                logs.push(
+                    job.id,
+                    LogStream::Echidnabot,
+                    &format!(
+                        "Uploading ./{} to ECHIDNA ({} files, {} bytes)",
+                        archive.root,
+                        archive.entries,
+                        archive.bytes.len()
+                    ),
+                );
+                echidna.verify_archive(&job.prover, &archive).await
+            }
+            Err(e) => Err(e),
         };
+        match answered {
+            Ok(results) => {
+                for (path, result) in single_files.iter().zip(results) {
+                    batched.insert(path.as_str(), result);
+                }
+            }
+            Err(e) => logs.push(
+                job.id,
+                LogStream::Echidnabot,
+                &format!("Not verified as a project archive ({}); checking file by file", e),
+            ),
+        }
+    }
+    let unanswered: Vec<&String> =
+        single_files.iter().filter(|path| !batched.contains_key(path.as_str())).collect();
+    if local_executor.is_none() && unanswered.len() > 1 {
+        let mut contents = Vec::with_capacity(unanswered.len());
+        for path in &unanswered {
+            contents.push(fs::read_to_string(full_path_of(path)).await?);
+        }
+        let contents: Vec<&str> = contents.iter().map(String::as_str).collect();
+        let results = echidna.verify_proofs(&job.prover, &contents).await;
+        for (path, result) in unanswered.into_iter().zip(results) {
+            match result {
+                Ok(result) => {
+                    batched.insert(path.as_str(), result);
+                }
+                Err(e) => {
+                    tracing::debug!("Batched check of {} failed for job {}: {}", path, job.id, e)
+                }
+            }
+        }
+    }
+
+    for path in &single_files {
+        let full_path = full_path_of(path);
         let content = fs::read_to_string(&full_path).await?;
+        let rel_path = full_path
+            .strip_prefix(&repo_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string());
 
-        let (verified_ok, output_chunk) = if let Some(ref ex) = local_executor {
-            // Local sandboxed path. ExecutionResult is success on
-            // exit_code == 0; non-zero (including timeout-kill) is
-            // treated as failure with the captured stderr.
-            match ex.execute_proof(job.prover.clone(), &content, None).await {
-                Ok(exec) => {
-                    let combined = if exec.stdout.trim().is_empty() {
-                        exec.stderr.clone()
-                    } else if exec.stderr.trim().is_empty() {
-                        exec.stdout.clone()
-                    } else {
-                        format!("{}\n--- stderr ---\n{}", exec.stdout, exec.stderr)
-                    };
-                    (exec.exit_code == Some(0), combined)
-                }
-                Err(e) => (false, format!("Local executor error: {}", e)),
+        logs.push(job.id, LogStream::Echidnabot, &format!("Verifying {}", rel_path));
+        let file_start = Instant::now();
+        // Timed before any re-run, so trends compare single checks; a
+        // batched file by ECHIDNA's own report.
+        let checked = batched.remove(path.as_str());
+        let (mut verified_ok, mut output_chunk, taken, duration_ms) = match checked {
+            Some(result) => {
+                let duration_ms = result.duration_ms as i64;
+                let (ok, output) = echidna_verdict(&job.prover, &rel_path, &content, result);
+                (ok, output, ExecutionPath::Echidna, duration_ms)
+            }
+            None => {
+                let local = local_executor.as_ref();
+                let (ok, output, taken) =
+                    verify_content(local, fallback, echidna, &job.prover, &rel_path, &content).await?;
+                (ok, output, taken, file_start.elapsed().as_millis() as i64)
             }
-        } else {
-            // ECHIDNA-delegated path (default).
-            let result = echidna.verify_proof(&job.prover, &content).await?;
-            (
-                result.status == echidnabot::dispatcher::ProofStatus::Verified,
-                result.prover_output,
-            )
         };
+        execution_path = Some(taken.merge(execution_path));
+        // ECHIDNA answers with the whole output at once.
+        if taken == ExecutionPath::Echidna {
+            logs.push_output(job.id, LogStream::Stdout, &output_chunk);
+        }
+        logs.push(
+            job.id,
+            LogStream::Echidnabot,
+            &format!("{} {}", rel_path, if verified_ok { "verified" } else { "failed" }),
+        );
+
+        // `[flaky] reruns`: check a failure again until a run passes.
+        if !verified_ok && reruns > 0 {
+            let mut attempts = vec![flaky::Attempt::new(false, &output_chunk)];
+            while attempts.len() <= reruns as usize && !verified_ok {
+                logs.push(
+                    job.id,
+                    LogStream::Echidnabot,
+                    &format!("Re-running {} ({}/{})", rel_path, attempts.len(), reruns),
+                );
+                let local = local_executor.as_ref();
+                let (ok, output, taken) =
+                    verify_content(local, fallback, echidna, &job.prover, &rel_path, &content).await?;
+                execution_path = Some(taken.merge(execution_path));
+                if taken == ExecutionPath::Echidna {
+                    logs.push_output(job.id, LogStream::Stdout, &output);
+                }
+                attempts.push(flaky::Attempt::new(ok, &output));
+                if ok {
+                    (verified_ok, output_chunk) = (true, output);
+                }
+            }
+            if let Some(verdict) = flaky::classify(&attempts) {
+                logs.push(
+                    job.id,
+                    LogStream::Echidnabot,
+                    &format!("{} {:?} after {} run(s)", rel_path, verdict, attempts.len()),
+                );
+                let run = FlakyRunRecord::new(
+                    job.id,
+                    job.repo_id,
+                    job.prover.clone(),
+                    rel_path.clone(),
+                    job.commit_sha.clone(),
+                    &attempts,
+                    verdict,
+                );
+                if let Err(e) = store.record_flaky_run(&run).await {
+                    tracing::warn!("Recording re-runs of {} failed for job {}: {}", rel_path, job.id, e);
+                }
+            }
+            if verified_ok {
+                flaky_files.push(rel_path.clone());
+            }
+        }
+
+        let theorems = proof_items::theorems(&rel_path, &content, &output_chunk);
+        items.push(ProofItem::file(&rel_path, verified_ok, &theorems, Some(duration_ms)));
+        items.extend(theorems);
+
+        // Per-file timing for long-term trend analysis (`echidnabot::perf`).
+        // Stored repo-relative so history lines up across clones. Best-effort.
+        let timing = FileTimingRecord::new(
+            job.id,
+            job.repo_id,
+            job.prover.clone(),
+            rel_path,
+            job.commit_sha.clone(),
+            duration_ms,
+            verified_ok,
+        );
+        if let Err(e) = store.record_file_timing(&timing).await {
+            tracing::debug!("record_file_timing failed for job {}: {}", job.id, e);
+        }
 
         if verified_ok {
             verified.push(path.to_string());
+        } else if quarantined.contains(&timing.file_path) {
+            quarantined_failures.push(timing.file_path.clone());
         } else {
             failed.push(path.to_string());
         }
@@ -1329,12 +4078,69 @@ async fn process_job(
         }
     }
 
+    // `[axioms]`: escape hatches in the checked files, whatever the prover
+    // made of them. Recorded here, judged when the result is reported.
+    let tokens = escape_hatch::tokens(&axiom_policy, &job.prover);
+    let hatches: Vec<EscapeHatchRecord> = escape_hatch::scan_files(&job.prover, &repo_path, &file_paths, &tokens)
+        .await
+        .into_iter()
+        .map(|hatch| EscapeHatchRecord::new(job.id, hatch))
+        .collect();
+    for hatch in &hatches {
+        logs.push(
+            job.id,
+            LogStream::Echidnabot,
+            &format!("{}:{}: escape hatch `{}`", hatch.file_path, hatch.line, hatch.token),
+        );
+    }
+    if let Err(e) = store.record_escape_hatches(job.id, &hatches).await {
+        tracing::warn!("Recording escape hatches of job {} failed: {}", job.id, e);
+    }
+    let items: Vec<ProofItemRecord> = items.into_iter().map(|item| ProofItemRecord::new(job.id, item)).collect();
+    if let Err(e) = store.record_proof_items(job.id, &items).await {
+        tracing::warn!("Recording per-theorem results of job {} failed: {}", job.id, e);
+    }
+
     let success = failed.is_empty();
-    let message = if success {
+    let mut message = if success {
         format!("Verified {} file(s)", verified.len())
     } else {
         format!("Failed {} file(s)", failed.len())
     };
+    if !quarantined_failures.is_empty() {
+        message.push_str(&format!(
+            "; {} quarantined failure(s) ignored: {}",
+            quarantined_failures.len(),
+            quarantined_failures.join(", ")
+        ));
+    }
+    if !flaky_files.is_empty() {
+        message.push_str(&format!(
+            "; {} flaky file(s) passed on a re-run: {}",
+            flaky_files.len(),
+            flaky_files.join(", ")
+        ));
+    }
+    if execution_path == Some(ExecutionPath::LocalFallback) {
+        message.push_str("; checked in the local sandbox while ECHIDNA was unreachable");
+        echidnabot::decision::record(store, job.id, |t| {
+            t.policy("dispatch", "ECHIDNA unreachable: local sandbox fallback")
+        })
+        .await;
+    }
+    if !quarantined_failures.is_empty() || !flaky_files.is_empty() {
+        echidnabot::decision::record(store, job.id, |t| {
+            if !quarantined_failures.is_empty() {
+                let files = quarantined_failures.join(", ");
+                t.policy("quarantine", format!("failures ignored: {}", files));
+            }
+            if !flaky_files.is_empty() {
+                let files = flaky_files.join(", ");
+                t.policy("flaky", format!("passed on a re-run: {}", files));
+            }
+        })
+        .await;
+    }
 
     let final_status = if success {
         echidnabot::dispatcher::ProofStatus::Verified
@@ -1343,6 +4149,29 @@ async fn process_job(
     };
     let axioms = echidnabot::trust::axiom_tracker::AxiomTracker::scan(&job.prover, &prover_output);
     let confidence = echidnabot::trust::confidence::assess_confidence(&job.prover, final_status, false, 1);
+    if success && config.corpus.export.enabled && repo.training_export {
+        match echidnabot::feedback::export::queue_from_checkout(
+            store,
+            &job.prover,
+            &repo_path,
+            &verified,
+            &config.corpus.export,
+        )
+        .await
+        {
+            Ok(0) => {}
+            Ok(n) => tracing::debug!("Queued {} training example(s) from job {}", n, job.id),
+            Err(e) => tracing::warn!("Queueing training examples for job {} failed: {}", job.id, e),
+        }
+    }
+    if success && config.archive.enabled && config.archive.include_sources {
+        *sources = echidnabot::archive::collect_sources(
+            &repo_path,
+            &verified,
+            config.archive.max_source_bytes,
+        )
+        .await;
+    }
     Ok(echidnabot::scheduler::JobResult {
         success,
         message,
@@ -1352,9 +4181,57 @@ async fn process_job(
         failed_files: failed,
         confidence: Some(confidence),
         axioms: Some(axioms),
+        prover_version,
+        prover_args,
+        execution_path,
     })
 }
 
+/// Discovery stage of a job without a file list: the prover's files in
+/// the checkout after extension routing, `filter` and content sniffing,
+/// repo-relative and sorted. They are recorded on the job with their
+/// project roots, so what a full run verified can be reproduced.
+async fn discover_proof_files(
+    store: &dyn Store,
+    job: &ProofJob,
+    repo_path: &Path,
+    routes: &Arc<ExtensionTable>,
+    filter: ProofFilter,
+    logs: &Arc<JobLogs>,
+) -> Result<Vec<String>> {
+    let (root, walk_routes, prover) = (repo_path.to_path_buf(), routes.clone(), job.prover.clone());
+    let found = tokio::task::spawn_blocking(move || collect_proof_files(&root, &walk_routes, &[prover], &filter))
+        .await
+        .unwrap_or_default();
+    let mut files: Vec<String> = found.iter().map(|p| relative_proof_path(repo_path, p)).collect();
+    files.sort();
+    // `.v` may be Verilog, `.ml` plain OCaml: keep what reads as a proof.
+    let files = sniff_proof_files(repo_path, files, routes, &job.prover).await;
+
+    let (root, prover, listed) = (repo_path.to_path_buf(), job.prover.clone(), files.clone());
+    let project_roots =
+        tokio::task::spawn_blocking(move || discovery::project_roots(&root, &listed, &prover))
+            .await
+            .unwrap_or_default();
+    logs.push(
+        job.id,
+        LogStream::Echidnabot,
+        &format!(
+            "Discovered {} {} file(s) in {} project root(s)",
+            files.len(),
+            job.prover.display_name(),
+            project_roots.len()
+        ),
+    );
+
+    if let Some(mut record) = store.get_job(job.id).await? {
+        record.payload.resolved_files = files.clone();
+        record.payload.project_roots = project_roots;
+        store.update_job(&record).await?;
+    }
+    Ok(files)
+}
+
 async fn clone_repo(config: &Config, repo: &RepoId, commit: &str) -> Result<PathBuf> {
     match repo.platform {
         Platform::GitHub => {
@@ -1379,65 +4256,259 @@ async fn clone_repo(config: &Config, repo: &RepoId, commit: &str) -> Result<Path
 }
 
 async fn clone_repo_via_git(base_url: &str, repo: &RepoId, commit: &str) -> Result<PathBuf> {
-    let temp_dir = tempfile::tempdir()?;
-    let clone_path = temp_dir.keep();
     let url = format!("{}/{}/{}.git", base_url.trim_end_matches('/'), repo.owner, repo.name);
+    Ok(echidnabot::adhoc::clone_git_url(&url, commit).await?.path)
+}
 
-    let status = if commit == "HEAD" {
-        tokio::process::Command::new("git")
-            .args(["clone", "--depth", "1", &url, &*clone_path.to_string_lossy()])
-            .status()
-            .await?
-    } else {
-        tokio::process::Command::new("git")
-            .args([
-                "clone",
-                "--depth",
-                "1",
-                "--branch",
-                commit,
-                &url,
-                &*clone_path.to_string_lossy(),
-            ])
-            .status()
-            .await?
-    };
+const MAX_PROOF_FILES: usize = 10_000;
 
-    if !status.success() && commit != "HEAD" {
-        let status = tokio::process::Command::new("git")
-            .args(["clone", "--depth", "1", &url, &*clone_path.to_string_lossy()])
-            .status()
-            .await?;
+/// Files under `root` that `routes` gives to one of `provers` and
+/// `filter` allows.
+fn collect_proof_files(
+    root: &Path,
+    routes: &ExtensionTable,
+    provers: &[ProverKind],
+    filter: &ProofFilter,
+) -> Vec<PathBuf> {
+    let mut results = Vec::new();
+    collect_files_inner(root, root, routes, provers, filter, &mut results);
+    results
+}
 
-        if !status.success() {
-            return Err(echidnabot::Error::Internal(format!(
-                "Failed to clone {}",
-                repo.full_name()
-            )));
+/// `path` relative to the checkout at `root`, `/`-separated, for glob
+/// matching. Paths outside `root` are returned as given.
+fn relative_proof_path(root: &Path, path: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Rebuild and store the dependency graph of `job.prover`'s proof files
+/// in the checkout at `root` (see `echidnabot::depgraph`).
+async fn record_dependency_graph(
+    store: &dyn Store,
+    job: &ProofJob,
+    root: &Path,
+    routes: &Arc<ExtensionTable>,
+    filter: &ProofFilter,
+) {
+    let (root, routes, filter, prover) =
+        (root.to_path_buf(), routes.clone(), filter.clone(), job.prover.clone());
+    let graph = tokio::task::spawn_blocking(move || {
+        let provers = std::slice::from_ref(&prover);
+        let files: Vec<(String, String)> = collect_proof_files(&root, &routes, provers, &filter)
+            .into_iter()
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                let rel = relative_proof_path(&root, &path);
+                routes.accepts(&rel, &prover, &content).then_some((rel, content))
+            })
+            .collect();
+        DependencyGraph::build(&prover, &files)
+    })
+    .await;
+    let Ok(graph) = graph else {
+        return;
+    };
+    let record = DependencyGraphRecord::new(
+        job.repo_id,
+        job.prover.clone(),
+        job.commit_sha.clone(),
+        graph,
+    );
+    if let Err(e) = store.upsert_dependency_graph(&record).await {
+        tracing::debug!("upsert_dependency_graph failed for job {}: {}", job.id, e);
+    }
+}
+
+/// Drop the files with a shared extension whose content does not look
+/// like `prover`'s (see `echidnabot::dispatcher::routing`). Unreadable
+/// files are kept for verification to report.
+async fn sniff_proof_files(
+    root: &Path,
+    files: Vec<String>,
+    routes: &ExtensionTable,
+    prover: &ProverKind,
+) -> Vec<String> {
+    let mut kept = Vec::with_capacity(files.len());
+    for file in files {
+        if let Some(sniffer) = routes.sniffer(&file, prover) {
+            if let Ok(content) = fs::read_to_string(root.join(&file)).await {
+                if !sniffer.matches(&content) {
+                    tracing::info!(
+                        "Skipping {}: reads as {}, not {}",
+                        file,
+                        sniffer.language,
+                        prover.display_name()
+                    );
+                    continue;
+                }
+            }
         }
+        kept.push(file);
+    }
+    kept
+}
 
-        tokio::process::Command::new("git")
-            .current_dir(&clone_path)
-            .args(["fetch", "--depth", "1", "origin", commit])
-            .status()
-            .await?;
+/// Drop the files `filter` excludes. `files` may be absolute (inside
+/// `root`) or relative to it.
+fn filter_proof_files(root: &Path, files: Vec<String>, filter: &ProofFilter) -> Vec<String> {
+    if filter.is_empty() {
+        return files;
+    }
+    files
+        .into_iter()
+        .filter(|f| filter.allows(&relative_proof_path(root, Path::new(f))))
+        .collect()
+}
 
-        tokio::process::Command::new("git")
-            .current_dir(&clone_path)
-            .args(["checkout", commit])
-            .status()
-            .await?;
+/// Build the local sandboxed executor for `prover` (only when
+/// configured). When `executor.local_isolation = false` (default),
+/// proofs delegate to ECHIDNA's REST API, which runs them in its own
+/// process, and this returns `None`. When `true`, each proof runs in a
+/// Podman / bubblewrap sandbox locally — needed for air-gapped or
+/// no-ECHIDNA setups.
+async fn build_local_executor(
+    config: &Config,
+    prover: &ProverKind,
+    timeout_secs: Option<u64>,
+) -> Result<Option<echidnabot::executor::container::PodmanExecutor>> {
+    if config.executor.dispatch() == DispatchStrategy::PreferEchidna {
+        return Ok(None);
+    }
+    let mut ex = echidnabot::executor::container::PodmanExecutor::new().await;
+    // Per-prover image fan-out — each prover gets the image
+    // specialised for its binaries (smaller, faster cold-start,
+    // narrower attack surface). Falls back to the default
+    // container_image when no per-prover entry exists.
+    if let Some(img) = config.executor.image_for(prover.clone()) {
+        ex = ex.with_image(img);
+    }
+    if let Some(ref mem) = config.executor.memory_limit {
+        ex = ex.with_memory_limit(mem.clone());
+    }
+    if let Some(cpus) = config.executor.cpu_limit {
+        ex = ex.with_cpu_limit(cpus);
+    }
+    if let Some(secs) = timeout_secs.or(config.executor.timeout_secs) {
+        ex = ex.with_timeout(std::time::Duration::from_secs(secs));
+    }
+    // Refuse to start if the operator opted in but neither podman
+    // nor bubblewrap is available (fail-safe per SONNET-TASKS Task 1).
+    // A `dispatch` strategy alone leaves the proofs to ECHIDNA instead.
+    if matches!(
+        ex.backend(),
+        echidnabot::executor::container::IsolationBackend::None
+    ) {
+        if !config.executor.local_isolation {
+            tracing::warn!(
+                "No isolation backend (podman or bubblewrap) on PATH; verifying through ECHIDNA only"
+            );
+            return Ok(None);
+        }
+        return Err(echidnabot::Error::Config(
+            "executor.local_isolation = true but no isolation backend (podman or bubblewrap) was found on PATH. Refusing to run proofs without isolation.".to_string()
+        ));
     }
+    Ok(Some(ex))
+}
 
-    Ok(clone_path)
+/// Verify one proof file's `content`, in the local sandbox when given,
+/// else through ECHIDNA — and in `fallback` when ECHIDNA cannot be
+/// reached. Returns whether it verified, the prover output and where it
+/// was checked.
+async fn verify_content(
+    local_executor: Option<&echidnabot::executor::container::PodmanExecutor>,
+    fallback: Option<&echidnabot::executor::container::PodmanExecutor>,
+    echidna: &EchidnaClient,
+    prover: &ProverKind,
+    path: &str,
+    content: &str,
+) -> Result<(bool, String, ExecutionPath)> {
+    // SMT-LIB: solver flags from the script, and the verdict from the
+    // answers rather than the exit status (Z3 exits 0 on `sat`).
+    let script = smtlib::handles(prover).then(|| smtlib::Script::parse(content));
+    let args = script.as_ref().map(|s| s.solver_args(prover)).unwrap_or_default();
+    let (verified, output, taken) = if let Some(ex) = local_executor {
+        let (verified, output) = run_locally(ex, prover, content, &args).await;
+        (verified, output, ExecutionPath::Local)
+    } else {
+        // ECHIDNA-delegated path (default).
+        match echidna.verify_proof(prover, content).await {
+            Ok(result) => (
+                result.status == echidnabot::dispatcher::ProofStatus::Verified,
+                result.prover_output,
+                ExecutionPath::Echidna,
+            ),
+            Err(e) => {
+                let Some(ex) = fallback else {
+                    return Err(e);
+                };
+                tracing::warn!("ECHIDNA unreachable for {} ({}); checking it locally", path, e);
+                let (verified, output) = run_locally(ex, prover, content, &args).await;
+                (verified, output, ExecutionPath::LocalFallback)
+            }
+        }
+    };
+    let (verified, output) = judged(script, path, verified, output);
+    Ok((verified, output, taken))
 }
 
-const MAX_PROOF_FILES: usize = 10_000;
+/// The verdict on `content` from a result ECHIDNA returned for it, as
+/// [`verify_content`] gives it.
+fn echidna_verdict(
+    prover: &ProverKind,
+    path: &str,
+    content: &str,
+    result: ProofResult,
+) -> (bool, String) {
+    let script = smtlib::handles(prover).then(|| smtlib::Script::parse(content));
+    let verified = result.status == echidnabot::dispatcher::ProofStatus::Verified;
+    judged(script, path, verified, result.prover_output)
+}
 
-fn collect_files_by_extension(root: &Path, extensions: &[String]) -> Vec<PathBuf> {
-    let mut results = Vec::new();
-    collect_files_inner(root, extensions, &mut results);
-    results
+/// SMT-LIB scripts are judged by the solver's answers, with a header
+/// summarising them; anything else by whether the prover succeeded.
+fn judged(
+    script: Option<smtlib::Script>,
+    path: &str,
+    verified: bool,
+    output: String,
+) -> (bool, String) {
+    match script {
+        Some(script) => {
+            let answers = smtlib::parse_output(&output);
+            let header = smtlib::header(path, &script, &answers);
+            (script.judge(&answers, verified), format!("{}\n{}", header, output))
+        }
+        None => (verified, output),
+    }
+}
+
+/// Run one proof in the local sandbox. ExecutionResult is success on
+/// exit_code == 0; non-zero (including timeout-kill) is treated as
+/// failure with the captured stderr.
+async fn run_locally(
+    ex: &echidnabot::executor::container::PodmanExecutor,
+    prover: &ProverKind,
+    content: &str,
+    args: &[String],
+) -> (bool, String) {
+    match ex.execute_proof_with_args(prover.clone(), content, args).await {
+        Ok(exec) => {
+            let combined = if exec.stdout.trim().is_empty() {
+                exec.stderr.clone()
+            } else if exec.stderr.trim().is_empty() {
+                exec.stdout.clone()
+            } else {
+                format!("{}\n--- stderr ---\n{}", exec.stdout, exec.stderr)
+            };
+            (exec.exit_code == Some(0), combined)
+        }
+        Err(e) => (false, format!("Local executor error: {}", e)),
+    }
 }
 
 /// Extract the first line number from a prover error message.
@@ -1476,11 +4547,18 @@ fn extract_error_line(prover_output: &str) -> Option<u32> {
     None
 }
 
-fn collect_files_inner(root: &Path, extensions: &[String], results: &mut Vec<PathBuf>) {
+fn collect_files_inner(
+    root: &Path,
+    dir: &Path,
+    routes: &ExtensionTable,
+    provers: &[ProverKind],
+    filter: &ProofFilter,
+    results: &mut Vec<PathBuf>,
+) {
     if results.len() >= MAX_PROOF_FILES {
         return;
     }
-    let Ok(entries) = std::fs::read_dir(root) else {
+    let Ok(entries) = std::fs::read_dir(dir) else {
         return;
     };
     for entry in entries.flatten() {
@@ -1494,9 +4572,16 @@ fn collect_files_inner(root: &Path, extensions: &[String], results: &mut Vec<Pat
                     continue;
                 }
             }
-            collect_files_inner(&path, extensions, results);
+            if filter.skips_dir(&relative_proof_path(root, &path)) {
+                continue;
+            }
+            collect_files_inner(root, &path, routes, provers, filter, results);
         } else if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-            if extensions.iter().any(|ext| name.ends_with(ext)) {
+            // Excluded files are skipped here rather than afterwards so
+            // a large vendored tree cannot use up MAX_PROOF_FILES.
+            if routes.provers_for(name).iter().any(|p| provers.contains(p))
+                && filter.allows(&relative_proof_path(root, &path))
+            {
                 results.push(path);
             }
         }
@@ -0,0 +1,349 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `@echidnabot <command>` comment commands and who may run them
+//!
+//! A comment mentioning the bot is parsed into a [`BotCommand`]. The
+//! first word after the mention selects the command; anything that is
+//! not a known command is a free-form question ([`BotCommand::Ask`],
+//! answered by Consultant mode).
+//!
+//! | Command                        | Effect                                   | Default role   |
+//! |--------------------------------|------------------------------------------|----------------|
+//! | `help`                         | List commands and required roles         | anyone         |
+//! | `explain`                      | Explain the PR's latest failure          | anyone         |
+//! | *(anything else)*              | Consultant Q&A                           | anyone         |
+//! | `check` / `verify` / `run`     | Verify the PR's latest commit            | collaborator   |
+//! | `rerun`                        | Re-verify the latest job's failing files | collaborator   |
+//! | `rerun --full`                 | Re-verify every proof file               | maintainer     |
+//!
+//! Commands are read from GitHub and Codeberg issue comments, GitLab
+//! merge request notes and Bitbucket pull request comments. The
+//! commenter's [`Role`] comes from the platform payload — GitHub's
+//! `author_association`, or the commenter's repository permissions on
+//! Codeberg/Gitea (admin: maintainer, write: collaborator) — with the
+//! repository owner always treated as a maintainer. GitLab and Bitbucket
+//! payloads carry neither, so there everyone but the owner is `anyone`.
+//! Defaults can be overridden per command key in `[commands.permissions]`:
+//!
+//! ```toml
+//! [commands.permissions]
+//! check = "contributor"
+//! rerun-full = "collaborator"
+//! ```
+//!
+//! Enforcement lives in [`parse_authorized`] so every caller gets the
+//! same answer, and denied commands produce a reply explaining what role
+//! is needed ([`permission_denied_reply`]) rather than silence.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The bot handle commands are addressed to.
+pub const BOT_MENTION: &str = "@echidnabot";
+
+/// Commenter trust level, ordered low → high.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// No relationship to the repository (including first-time contributors).
+    Anyone,
+    /// Has had a commit merged before.
+    Contributor,
+    /// Has been granted access to the repository.
+    Collaborator,
+    /// Repository owner or organisation member.
+    Maintainer,
+}
+
+impl Role {
+    /// Map GitHub's `author_association` (`OWNER`, `MEMBER`,
+    /// `COLLABORATOR`, `CONTRIBUTOR`, `FIRST_TIME_CONTRIBUTOR`,
+    /// `FIRST_TIMER`, `MANNEQUIN`, `NONE`).
+    pub fn from_github_association(association: &str) -> Self {
+        match association.to_ascii_uppercase().as_str() {
+            "OWNER" | "MEMBER" => Role::Maintainer,
+            "COLLABORATOR" => Role::Collaborator,
+            "CONTRIBUTOR" => Role::Contributor,
+            _ => Role::Anyone,
+        }
+    }
+
+    /// Role of a commenter, given what the payload tells us. The
+    /// repository owner is a maintainer regardless of association.
+    pub fn resolve(association: Option<&str>, actor: Option<&str>, repo_owner: &str) -> Self {
+        if actor.is_some_and(|a| a.eq_ignore_ascii_case(repo_owner)) {
+            return Role::Maintainer;
+        }
+        association
+            .map(Self::from_github_association)
+            .unwrap_or(Role::Anyone)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Anyone => "anyone",
+            Role::Contributor => "contributor",
+            Role::Collaborator => "collaborator",
+            Role::Maintainer => "maintainer",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed comment command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotCommand {
+    Help,
+    Explain,
+    Check,
+    Rerun { full: bool },
+    /// Free-form question for Consultant mode.
+    Ask(String),
+}
+
+impl BotCommand {
+    /// Key used in `[commands.permissions]`.
+    pub fn permission_key(&self) -> &'static str {
+        match self {
+            BotCommand::Help => "help",
+            BotCommand::Explain => "explain",
+            BotCommand::Check => "check",
+            BotCommand::Rerun { full: false } => "rerun",
+            BotCommand::Rerun { full: true } => "rerun-full",
+            BotCommand::Ask(_) => "ask",
+        }
+    }
+
+    /// How the command is written in a comment.
+    pub fn usage(&self) -> &'static str {
+        match self {
+            BotCommand::Help => "help",
+            BotCommand::Explain => "explain",
+            BotCommand::Check => "check",
+            BotCommand::Rerun { full: false } => "rerun",
+            BotCommand::Rerun { full: true } => "rerun --full",
+            BotCommand::Ask(_) => "<question>",
+        }
+    }
+
+    fn default_role(&self) -> Role {
+        match self {
+            BotCommand::Help | BotCommand::Explain | BotCommand::Ask(_) => Role::Anyone,
+            BotCommand::Check | BotCommand::Rerun { full: false } => Role::Collaborator,
+            BotCommand::Rerun { full: true } => Role::Maintainer,
+        }
+    }
+
+    /// Every command, for `help` output.
+    pub fn all() -> [BotCommand; 6] {
+        [
+            BotCommand::Help,
+            BotCommand::Explain,
+            BotCommand::Ask(String::new()),
+            BotCommand::Check,
+            BotCommand::Rerun { full: false },
+            BotCommand::Rerun { full: true },
+        ]
+    }
+}
+
+/// Per-command minimum roles, keyed by [`BotCommand::permission_key`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandPermissions {
+    #[serde(flatten)]
+    overrides: HashMap<String, Role>,
+}
+
+impl CommandPermissions {
+    pub fn with_override(mut self, key: &str, role: Role) -> Self {
+        self.overrides.insert(key.to_string(), role);
+        self
+    }
+
+    /// Minimum role needed to run `command`.
+    pub fn required_role(&self, command: &BotCommand) -> Role {
+        self.overrides
+            .get(command.permission_key())
+            .copied()
+            .unwrap_or_else(|| command.default_role())
+    }
+}
+
+/// Result of parsing a comment against the permission map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandOutcome {
+    /// The comment does not address the bot.
+    NotACommand,
+    /// Parsed and permitted.
+    Allowed(BotCommand),
+    /// Parsed, but the commenter's role is too low.
+    Denied {
+        command: BotCommand,
+        required: Role,
+        actual: Role,
+    },
+}
+
+/// Parse the first `@echidnabot …` line of `body`.
+pub fn parse_command(body: &str) -> Option<BotCommand> {
+    let line = body
+        .lines()
+        .find(|l| l.to_ascii_lowercase().contains(BOT_MENTION))?;
+    let start = line.to_ascii_lowercase().find(BOT_MENTION)? + BOT_MENTION.len();
+    let rest = line[start..].trim_start_matches([',', ':']).trim();
+    let mut words = rest.split_whitespace();
+
+    let command = match words.next().map(str::to_lowercase).as_deref() {
+        Some("help") => BotCommand::Help,
+        Some("explain") => BotCommand::Explain,
+        Some("check") | Some("verify") | Some("run") => BotCommand::Check,
+        Some("rerun") | Some("re-run") => BotCommand::Rerun {
+            full: words.any(|w| w == "--full" || w == "full"),
+        },
+        _ => BotCommand::Ask(super::extract_question(body)),
+    };
+    Some(command)
+}
+
+/// Parse `body` and check it against `permissions` for a commenter with
+/// `role`.
+pub fn parse_authorized(body: &str, role: Role, permissions: &CommandPermissions) -> CommandOutcome {
+    let Some(command) = parse_command(body) else {
+        return CommandOutcome::NotACommand;
+    };
+    let required = permissions.required_role(&command);
+    if role >= required {
+        CommandOutcome::Allowed(command)
+    } else {
+        CommandOutcome::Denied {
+            command,
+            required,
+            actual: role,
+        }
+    }
+}
+
+/// Reply posted when a command is refused.
+pub fn permission_denied_reply(
+    command: &BotCommand,
+    required: Role,
+    actual: Role,
+    actor: Option<&str>,
+) -> String {
+    let who = actor.map(|a| format!("@{}", a)).unwrap_or_else(|| "You".to_string());
+    format!(
+        "🔒 {who}, `{mention} {usage}` needs the **{required}** role on this repository \
+         (you are recognised as **{actual}**).\n\n\
+         A maintainer can run it for you, or the repository's echidnabot operator can \
+         relax this in `[commands.permissions]`. Commands open to you are listed by \
+         `{mention} help`.\n",
+        who = who,
+        mention = BOT_MENTION,
+        usage = command.usage(),
+        required = required,
+        actual = actual,
+    )
+}
+
+/// Reply to `@echidnabot help`, marking which commands `role` may run.
+pub fn help_reply(role: Role, permissions: &CommandPermissions) -> String {
+    let mut out = String::from("### 🦔 echidnabot commands\n\n| Command | Who can run it | |\n|---|---|---|\n");
+    for command in BotCommand::all() {
+        let required = permissions.required_role(&command);
+        out.push_str(&format!(
+            "| `{} {}` | {} | {} |\n",
+            BOT_MENTION,
+            command.usage(),
+            required,
+            if role >= required { "✅" } else { "🔒" },
+        ));
+    }
+    out.push_str(&format!("\nYou are recognised as **{}**.\n", role));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_flags() {
+        assert_eq!(parse_command("@echidnabot explain"), Some(BotCommand::Explain));
+        assert_eq!(parse_command("LGTM\n@EchidnaBot: Verify please"), Some(BotCommand::Check));
+        assert_eq!(
+            parse_command("@echidnabot rerun --full"),
+            Some(BotCommand::Rerun { full: true })
+        );
+        assert_eq!(
+            parse_command("@echidnabot rerun"),
+            Some(BotCommand::Rerun { full: false })
+        );
+        assert_eq!(
+            parse_command("@echidnabot why does this fail?"),
+            Some(BotCommand::Ask("why does this fail?".into()))
+        );
+        assert_eq!(parse_command("no mention here"), None);
+    }
+
+    #[test]
+    fn roles_from_association_and_owner() {
+        assert_eq!(Role::from_github_association("OWNER"), Role::Maintainer);
+        assert_eq!(Role::from_github_association("FIRST_TIME_CONTRIBUTOR"), Role::Anyone);
+        assert_eq!(Role::resolve(None, Some("Alice"), "alice"), Role::Maintainer);
+        assert_eq!(Role::resolve(Some("COLLABORATOR"), Some("bob"), "alice"), Role::Collaborator);
+        assert_eq!(Role::resolve(None, Some("bob"), "alice"), Role::Anyone);
+    }
+
+    #[test]
+    fn default_map_gates_full_rerun() {
+        let perms = CommandPermissions::default();
+        assert_eq!(
+            parse_authorized("@echidnabot explain", Role::Anyone, &perms),
+            CommandOutcome::Allowed(BotCommand::Explain)
+        );
+        assert_eq!(
+            parse_authorized("@echidnabot rerun --full", Role::Collaborator, &perms),
+            CommandOutcome::Denied {
+                command: BotCommand::Rerun { full: true },
+                required: Role::Maintainer,
+                actual: Role::Collaborator,
+            }
+        );
+        assert!(matches!(
+            parse_authorized("@echidnabot rerun --full", Role::Maintainer, &perms),
+            CommandOutcome::Allowed(_)
+        ));
+    }
+
+    #[test]
+    fn overrides_apply_per_key() {
+        let perms: CommandPermissions = toml::from_str(r#"check = "anyone""#).unwrap();
+        assert!(matches!(
+            parse_authorized("@echidnabot check", Role::Anyone, &perms),
+            CommandOutcome::Allowed(BotCommand::Check)
+        ));
+        let perms = CommandPermissions::default().with_override("explain", Role::Contributor);
+        assert!(matches!(
+            parse_authorized("@echidnabot explain", Role::Anyone, &perms),
+            CommandOutcome::Denied { .. }
+        ));
+    }
+
+    #[test]
+    fn denied_reply_is_helpful() {
+        let reply =
+            permission_denied_reply(&BotCommand::Rerun { full: true }, Role::Maintainer, Role::Anyone, Some("eve"));
+        assert!(reply.contains("@eve"));
+        assert!(reply.contains("`@echidnabot rerun --full`"));
+        assert!(reply.contains("**maintainer**"));
+        assert!(reply.contains("@echidnabot help"));
+    }
+}
@@ -8,8 +8,9 @@
 //!
 //!   1. `.machine_readable/bot_directives/echidnabot.a2ml` (if present)
 //!   2. `.machine_readable/bot_directives/all.a2ml`        (if present)
-//!   3. `repositories.mode` column                         (per-repo DB)
-//!   4. `BotMode::default()` = Verifier
+//!   3. `.echidnabot.toml` at the repo root                (if present)
+//!   4. `repositories.mode` column                         (per-repo DB)
+//!   5. `BotMode::default()` = Verifier
 //!
 //! The directive content can be either A2ML (TOML-flavoured, post-2026-04-12
 //! migration) or Scheme (legacy `.scm`). Both formats look for `(mode "X")`
@@ -21,6 +22,8 @@
 //! are responsible for providing the directive content; if they pass `None`,
 //! the cascade falls through to DB → default.
 
+use serde::{Deserialize, Serialize};
+
 use crate::adapters::{PlatformAdapter, RepoId};
 use crate::modes::BotMode;
 use crate::store::models::Repository;
@@ -32,9 +35,21 @@
 /// Fleet-wide directive path, walked second.
 const DIRECTIVE_PATH_ALL: &str = ".machine_readable/bot_directives/all.a2ml";
 
+/// Root-level config file, walked last. This is where the first-event
+/// bootstrap PR puts its template.
+pub const DIRECTIVE_PATH_DOTFILE: &str = ".echidnabot.toml";
+
+/// Every directive path, in cascade order.
+pub const DIRECTIVE_PATHS: [&str; 3] = [
+    DIRECTIVE_PATH_ECHIDNABOT,
+    DIRECTIVE_PATH_ALL,
+    DIRECTIVE_PATH_DOTFILE,
+];
+
 /// Fetch a directive from the target repo via the platform API. Walks
-/// `echidnabot.a2ml` first, then `all.a2ml`. Returns `None` if neither
-/// exists (the resolver then falls back to the DB column).
+/// `echidnabot.a2ml` first, then `all.a2ml`, then `.echidnabot.toml`.
+/// Returns `None` if none exists (the resolver then falls back to the DB
+/// column).
 ///
 /// Errors from the underlying API are logged and treated as "no
 /// directive" so a 502/rate-limit doesn't crash the webhook handler —
@@ -44,7 +59,7 @@ pub async fn fetch_directive_via_adapter(
     repo: &RepoId,
     branch: Option<&str>,
 ) -> Option<String> {
-    for path in [DIRECTIVE_PATH_ECHIDNABOT, DIRECTIVE_PATH_ALL] {
+    for path in DIRECTIVE_PATHS {
         match adapter.get_file_contents(repo, branch, path).await {
             Ok(Some(content)) => {
                 tracing::debug!("Fetched directive from {}", path);
@@ -110,11 +125,43 @@ pub fn resolve_mode_with_daemon_default(
     directive_content: Option<&str>,
     daemon_default: BotMode,
 ) -> BotMode {
+    resolve_mode_traced(repo, directive_content, daemon_default).0
+}
+
+/// Which level of the cascade settled a repository's mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeSource {
+    /// The repository's directive file.
+    Directive,
+    /// The `repositories.mode` column.
+    Repository,
+    /// `[bot] mode` in the daemon config.
+    DaemonDefault,
+}
+
+impl ModeSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Directive => "directive",
+            Self::Repository => "repository",
+            Self::DaemonDefault => "daemon_default",
+        }
+    }
+}
+
+/// [`resolve_mode_with_daemon_default`], also saying where the mode came
+/// from (see [`crate::decision`]).
+pub fn resolve_mode_traced(
+    repo: &Repository,
+    directive_content: Option<&str>,
+    daemon_default: BotMode,
+) -> (BotMode, ModeSource) {
     if let Some(content) = directive_content {
         // Try A2ML first (post-2026-04-12 canonical), then legacy Scheme.
         if let Some(mode) = parse_a2ml_directive(content) {
             tracing::debug!("Mode {} resolved from A2ML directive", mode);
-            return mode;
+            return (mode, ModeSource::Directive);
         }
         let scheme_mode = super::parse_mode_from_directive(content);
         // parse_mode_from_directive returns Verifier on parse failure. To
@@ -122,20 +169,20 @@ pub fn resolve_mode_with_daemon_default(
         // anything mode-shaped at all.
         if content.to_lowercase().contains("mode") {
             tracing::debug!("Mode {} resolved from Scheme directive", scheme_mode);
-            return scheme_mode;
+            return (scheme_mode, ModeSource::Directive);
         }
     }
     // Use the per-repo DB setting when it differs from the built-in default.
     if repo.mode != BotMode::default() {
         tracing::debug!("Mode {} resolved from repository.mode (DB)", repo.mode);
-        return repo.mode;
+        return (repo.mode, ModeSource::Repository);
     }
     // Fall back to the daemon-wide configured default.
     tracing::debug!(
         "Mode {} resolved from daemon-wide config default (repo.mode is default)",
         daemon_default
     );
-    daemon_default
+    (daemon_default, ModeSource::DaemonDefault)
 }
 
 #[cfg(test)]
@@ -277,4 +324,22 @@ fn daemon_default_verifier_leaves_default_unchanged() {
             BotMode::Verifier,
         );
     }
+
+    #[test]
+    fn traced_resolution_names_the_deciding_level() {
+        let directive = "[bot]\nmode = \"advisor\"";
+        let repo = fixture_repo(BotMode::Regulator);
+        assert_eq!(
+            resolve_mode_traced(&repo, Some(directive), BotMode::Verifier),
+            (BotMode::Advisor, ModeSource::Directive),
+        );
+        assert_eq!(
+            resolve_mode_traced(&repo, None, BotMode::Verifier),
+            (BotMode::Regulator, ModeSource::Repository),
+        );
+        assert_eq!(
+            resolve_mode_traced(&fixture_repo(BotMode::Verifier), None, BotMode::Consultant),
+            (BotMode::Consultant, ModeSource::DaemonDefault),
+        );
+    }
 }
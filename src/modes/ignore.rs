@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! `.echidnaignore`: paths the bot never looks at.
+//!
+//! The file sits at the repository root and uses `.gitignore` syntax:
+//!
+//! ```text
+//! # generated, and huge
+//! vendor/
+//! /build
+//! **/Scratch*.v
+//! generated/**
+//! !generated/*.lean
+//! ```
+//!
+//! - blank lines and `#` comments are skipped; `\#` and `\!` escape a
+//!   leading `#` or `!`;
+//! - a pattern without a `/` (other than a trailing one) matches a file or
+//!   directory name at any depth; one with a `/` is relative to the root;
+//! - a trailing `/` matches directories only;
+//! - `*` and `?` stay within a path segment, `**` spans any number;
+//! - `!` re-includes what an earlier pattern ignored. The last matching
+//!   pattern wins, and as in git, nothing inside an ignored directory can
+//!   be re-included.
+//!
+//! Character classes (`[abc]`) are not supported.
+//!
+//! The file is read at the job's commit: from the checkout when verifying,
+//! and through the platform API when a push is filtered (see
+//! [`IgnoreFile::fetch`]). It applies on top of the manifest's `[proofs]`
+//! globs ([`ProofFilter`]).
+
+use std::path::Path;
+
+use super::manifest::{anchored_glob_match, glob_match, ProofsSection};
+use crate::adapters::{PlatformAdapter, RepoId};
+
+/// Name of the ignore file, at the repository root.
+pub const IGNORE_FILE: &str = ".echidnaignore";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Self {
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        // `dir/**` is everything inside `dir`, not `dir` itself, so a
+        // later `!dir/*.v` can still re-include files directly in it.
+        if let Some(dir) = self.pattern.strip_suffix("/**") {
+            if anchored_glob_match(dir, path) {
+                return false;
+            }
+        }
+        if self.anchored {
+            anchored_glob_match(&self.pattern, path)
+        } else {
+            glob_match(&self.pattern, path)
+        }
+    }
+}
+
+/// A parsed `.echidnaignore`. The default ignores nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreFile {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    pub fn parse(content: &str) -> Self {
+        Self {
+            rules: content.lines().filter_map(Rule::parse).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// True when the file at `path` (relative, `/`-separated) is ignored,
+    /// itself or through a directory above it.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.ignored(path, false)
+    }
+
+    /// True when the directory at `path` is ignored, and with it
+    /// everything inside.
+    pub fn is_dir_ignored(&self, path: &str) -> bool {
+        self.ignored(path, true)
+    }
+
+    fn ignored(&self, path: &str, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let segments: Vec<&str> = path
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|seg| !seg.is_empty())
+            .collect();
+        let Some((_, parents)) = segments.split_last() else {
+            return false;
+        };
+        (1..=parents.len()).any(|end| self.decide(&segments[..end].join("/"), true))
+            || self.decide(&segments.join("/"), is_dir)
+    }
+
+    /// The last rule matching `path` decides.
+    fn decide(&self, path: &str, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Fetch the repository's ignore file at `commit`. Best-effort: an
+    /// API error counts as no file, so nothing is filtered.
+    pub async fn fetch(adapter: &dyn PlatformAdapter, repo: &RepoId, commit: &str) -> Self {
+        match adapter.get_file_contents(repo, Some(commit), IGNORE_FILE).await {
+            Ok(Some(content)) => Self::parse(&content),
+            Ok(None) => Self::default(),
+            Err(e) => {
+                tracing::warn!(
+                    "{} fetch failed for {}/{} at {}: {}",
+                    IGNORE_FILE,
+                    repo.owner,
+                    repo.name,
+                    commit,
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Read the ignore file of the checkout at `root`, if it has one.
+    pub async fn load(root: &Path) -> Self {
+        tokio::fs::read_to_string(root.join(IGNORE_FILE))
+            .await
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+}
+
+/// The files a job considers: allowed by the `[proofs]` globs and not
+/// ignored by `.echidnaignore`.
+#[derive(Debug, Clone, Default)]
+pub struct ProofFilter {
+    pub proofs: ProofsSection,
+    pub ignore: IgnoreFile,
+}
+
+impl ProofFilter {
+    pub fn new(proofs: ProofsSection, ignore: IgnoreFile) -> Self {
+        Self { proofs, ignore }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty() && self.ignore.is_empty()
+    }
+
+    /// True when the file at `path` (relative, `/`-separated) should be
+    /// verified.
+    pub fn allows(&self, path: &str) -> bool {
+        !self.ignore.is_ignored(path) && self.proofs.allows(path)
+    }
+
+    /// True when nothing under the directory at `path` can be allowed,
+    /// so file discovery need not walk it.
+    pub fn skips_dir(&self, path: &str) -> bool {
+        self.ignore.is_dir_ignored(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_match_at_any_depth_and_slashes_anchor() {
+        let ignore = IgnoreFile::parse("# comment\n\n*.tmp.v\n/build\ndocs/examples\n");
+        assert!(ignore.is_ignored("a/b/Foo.tmp.v"));
+        assert!(ignore.is_ignored("build/Out.v"));
+        assert!(!ignore.is_ignored("src/build/Out.v"), "/build is anchored to the root");
+        assert!(ignore.is_ignored("docs/examples/Ex.lean"));
+        assert!(!ignore.is_ignored("src/docs/examples/Ex.lean"));
+        assert!(!ignore.is_ignored("theories/Nat.v"));
+    }
+
+    #[test]
+    fn trailing_slash_matches_directories_only() {
+        let ignore = IgnoreFile::parse("vendor/\n");
+        assert!(ignore.is_ignored("vendor/lib/A.v"));
+        assert!(ignore.is_ignored("third_party/vendor/B.v"));
+        assert!(ignore.is_dir_ignored("vendor"));
+        assert!(!ignore.is_ignored("vendor"), "a file named vendor is kept");
+    }
+
+    #[test]
+    fn negation_reincludes_but_not_inside_ignored_directories() {
+        let ignore = IgnoreFile::parse("*.v\n!Keep.v\nold/\n!old/Important.v\n\\!bang.v\n");
+        assert!(ignore.is_ignored("src/Drop.v"));
+        assert!(!ignore.is_ignored("src/Keep.v"));
+        assert!(ignore.is_ignored("old/Important.v"), "its directory stays ignored");
+        assert!(ignore.is_ignored("!bang.v"));
+
+        let generated = IgnoreFile::parse("generated/**\n!generated/**/*.lean\n");
+        assert!(!generated.is_ignored("generated/Basic.lean"));
+        assert!(generated.is_ignored("generated/Basic.v"));
+    }
+
+    #[test]
+    fn proof_filter_combines_globs_and_ignore_file() {
+        let filter = ProofFilter::new(
+            ProofsSection {
+                include: vec!["proofs/**".into()],
+                exclude: vec![],
+            },
+            IgnoreFile::parse("proofs/scratch/\n"),
+        );
+        assert!(filter.allows("proofs/Main.v"));
+        assert!(!filter.allows("proofs/scratch/Try.v"));
+        assert!(!filter.allows("src/Other.v"));
+        assert!(filter.skips_dir("proofs/scratch"));
+        assert!(!filter.skips_dir("proofs"));
+        assert!(ProofFilter::default().is_empty());
+    }
+}
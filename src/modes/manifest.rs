@@ -5,12 +5,22 @@
 //! Extends the v1.0 directive (which carried only `[bot] mode`) to cover
 //! the surfaces required for estate-scale opt-in:
 //!
-//!   * which provers apply (whitelist / blacklist)
-//!   * proof-file globs (include / exclude)
+//!   * which provers apply (whitelist / blacklist), and which prover owns
+//!     an extension claimed twice (`.v`: Coq or Verilog)
+//!   * proof-file globs (include / exclude), falling back to the repo's
+//!     own path settings
 //!   * per-prover timeout and flags
 //!   * axiom policy (forbid list + severity)
 //!   * merge-block thresholds (confidence + axiom severity)
 //!   * blocked-on labels (upstream gating)
+//!   * per-file / per-theorem duration budgets
+//!   * stale proof-failure reminders, escalation, draft and close
+//!   * which pushed branches are verified
+//!   * re-runs of failing files to tell flaky proofs from broken ones
+//!   * bisection of default-branch breakages to the commit that caused them
+//!   * proof repairs proposed from verified tactic suggestions
+//!   * signed verification certificates for commits that pass
+//!   * CRLF / byte order mark / UTF-16 normalization of the checkout
 //!
 //! Canonical path: `.machine_readable/bot_directives/echidnabot.a2ml`.
 //! v1.0 directives (mode-only) continue to parse via [`directives::parse_a2ml_directive`]
@@ -22,6 +32,8 @@
 //!
 //! Estate-side examples live under `tests/fixtures/manifest/`.
 
+use crate::adapters::MergeMethod;
+use crate::dispatcher::{ExtensionTable, ProverKind};
 use crate::modes::BotMode;
 use serde::{Deserialize, Serialize};
 
@@ -53,6 +65,39 @@ pub struct RepoManifest {
 
     #[serde(default)]
     pub blocked_on: BlockedOnSection,
+
+    #[serde(default)]
+    pub budgets: BudgetsSection,
+
+    #[serde(default)]
+    pub dependency_prs: DependencyPrsSection,
+
+    #[serde(default)]
+    pub auto_merge: AutoMergeSection,
+
+    #[serde(default)]
+    pub stale_failures: StaleFailuresSection,
+
+    #[serde(default)]
+    pub branches: BranchesSection,
+
+    #[serde(default)]
+    pub push: PushSection,
+
+    #[serde(default)]
+    pub flaky: FlakySection,
+
+    #[serde(default)]
+    pub bisect: BisectSection,
+
+    #[serde(default)]
+    pub fixer: FixerSection,
+
+    #[serde(default)]
+    pub auditor: AuditorSection,
+
+    #[serde(default)]
+    pub normalize: NormalizeSection,
 }
 
 /// `[bot]` table: operating mode and master enable flag.
@@ -91,16 +136,35 @@ pub struct ProversSection {
     #[serde(default)]
     pub disabled: Vec<String>,
 
+    /// Extension routing, `[provers.extensions]`: extension → the one
+    /// prover that checks it, or `"none"`. Settles shared extensions and
+    /// routes files to provers with no built-in extensions; see
+    /// [`crate::dispatcher::routing`].
+    #[serde(default)]
+    pub extensions: std::collections::BTreeMap<String, String>,
+
     /// Per-prover overrides, keyed by prover slug (`coq`, `lean4`, ...).
     /// Flattened in TOML as `[provers.coq]`, `[provers.lean4]`, ...
     #[serde(flatten)]
     pub per_prover: std::collections::BTreeMap<String, ProverConfig>,
 }
 
+impl ProversSection {
+    /// The `[provers.<slug>]` table for `prover`; Lean also reads
+    /// `[provers.lean4]`.
+    pub fn for_prover(&self, prover: &ProverKind) -> Option<&ProverConfig> {
+        let slug = prover.as_str();
+        self.per_prover
+            .get(slug)
+            .or_else(|| if slug == "lean" { self.per_prover.get("lean4") } else { None })
+    }
+}
+
 /// Per-prover knobs (`[provers.<slug>]`).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProverConfig {
-    /// CLI flags appended to the prover invocation.
+    /// CLI flags appended to single-file checks, held to the prover's
+    /// allowlist ([`crate::executor::prover_args`]).
     #[serde(default)]
     pub flags: Vec<String>,
 
@@ -113,10 +177,28 @@ pub struct ProverConfig {
     /// Ignored for non-Lean provers.
     #[serde(default)]
     pub lake: Option<bool>,
+
+    /// Isabelle-specific: build `ROOT` sessions with `isabelle build`
+    /// instead of checking theories one by one. Ignored for other provers.
+    #[serde(default)]
+    pub sessions: Option<bool>,
 }
 
 /// `[proofs]` table: file globs.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+///
+/// Patterns are matched against paths relative to the repo root (see
+/// [`glob_match`]). They narrow the files found by extension-based
+/// detection; they never add files a prover would not pick up anyway.
+///
+/// ```toml
+/// [proofs]
+/// include = ["proofs/**"]
+/// exclude = ["vendor/**", "**/Scratch*.v"]
+/// ```
+///
+/// When the manifest sets neither list, the repository's
+/// `pathInclude` / `pathExclude` settings apply instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProofsSection {
     /// Glob patterns the bot considers proof-bearing.
     /// If empty, extension-based auto-detection (`.v`, `.lean`, ...) is used.
@@ -128,6 +210,55 @@ pub struct ProofsSection {
     pub exclude: Vec<String>,
 }
 
+impl ProofsSection {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// True when the file at `path` (relative, `/`-separated) should be
+    /// verified.
+    pub fn allows(&self, path: &str) -> bool {
+        if self.exclude.iter().any(|p| glob_match(p, path)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|p| glob_match(p, path))
+    }
+}
+
+/// Match a repo-relative `path` against a glob. `**` spans any number of
+/// directories, `*` and `?` stay within one path segment. A pattern with
+/// no `/` matches the file name at any depth, as in `.gitignore`.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let mut pattern = glob_segments(pattern);
+    if pattern.len() == 1 && pattern[0] != "**" {
+        pattern.insert(0, "**");
+    }
+    segments_match(&pattern, &glob_segments(path))
+}
+
+/// Like [`glob_match`], but the pattern always starts at the repository
+/// root, even without a `/`.
+pub fn anchored_glob_match(pattern: &str, path: &str) -> bool {
+    segments_match(&glob_segments(pattern), &glob_segments(path))
+}
+
+fn glob_segments(s: &str) -> Vec<&str> {
+    s.trim_start_matches("./")
+        .split('/')
+        .filter(|seg| !seg.is_empty())
+        .collect()
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| segments_match(rest, &path[i..])),
+        Some((seg, rest)) => {
+            !path.is_empty() && wildcard_match(seg, path[0]) && segments_match(rest, &path[1..])
+        }
+    }
+}
+
 /// `[axioms]` table: forbidden constructs and severity.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AxiomsSection {
@@ -187,6 +318,553 @@ pub struct BlockedOnSection {
     pub labels: Vec<String>,
 }
 
+/// `[budgets]` table: verification-duration ceilings.
+///
+/// Evaluated after every job by [`crate::perf::budget`]. File budgets are
+/// checked against the measured per-file wall time; theorem budgets need
+/// the prover to emit per-sentence timing (e.g. `flags = ["-time"]` for
+/// Coq) so individual proofs can be attributed.
+///
+/// ```toml
+/// [budgets]
+/// action = "fail"          # or "warn" (default)
+/// file_seconds = 300       # every proof file
+/// theorem_seconds = 60     # every theorem
+///
+/// [budgets.files]
+/// "theories/Heavy.v" = 900 # overrides file_seconds
+///
+/// [budgets.theorems]
+/// big_reflection_lemma = 240
+/// ```
+///
+/// `slowdown_ratio` is not a budget but tunes the trend check of
+/// [`crate::perf::anomaly`] for the repository: how many times slower than
+/// its history a file or theorem must get to be flagged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetsSection {
+    /// Default ceiling for any proof file, in seconds.
+    #[serde(default)]
+    pub file_seconds: Option<u64>,
+
+    /// Default ceiling for any single theorem, in seconds.
+    #[serde(default)]
+    pub theorem_seconds: Option<u64>,
+
+    /// Per-file overrides keyed by repo-relative path.
+    #[serde(default)]
+    pub files: std::collections::BTreeMap<String, u64>,
+
+    /// Per-theorem overrides keyed by theorem name.
+    #[serde(default)]
+    pub theorems: std::collections::BTreeMap<String, u64>,
+
+    /// What a violation does to the check run.
+    #[serde(default)]
+    pub action: BudgetAction,
+
+    /// Overrides the instance's `[anomaly] ratio_threshold`.
+    #[serde(default)]
+    pub slowdown_ratio: Option<f64>,
+}
+
+impl BudgetsSection {
+    /// True when no budget of any kind is configured.
+    pub fn is_empty(&self) -> bool {
+        self.file_seconds.is_none()
+            && self.theorem_seconds.is_none()
+            && self.files.is_empty()
+            && self.theorems.is_empty()
+    }
+
+    /// Effective budget for a proof file, in milliseconds.
+    pub fn file_budget_ms(&self, path: &str) -> Option<u64> {
+        self.files
+            .get(path)
+            .copied()
+            .or(self.file_seconds)
+            .map(|s| s.saturating_mul(1000))
+    }
+
+    /// Effective budget for a theorem, in milliseconds.
+    pub fn theorem_budget_ms(&self, name: &str) -> Option<u64> {
+        self.theorems
+            .get(name)
+            .copied()
+            .or(self.theorem_seconds)
+            .map(|s| s.saturating_mul(1000))
+    }
+}
+
+/// Reaction to a budget violation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// Report the violation; the check-run conclusion is unchanged.
+    #[default]
+    Warn,
+    /// Report the violation and fail the check run.
+    Fail,
+}
+
+/// `[dependency_prs]` table: policy for bot-authored dependency bumps
+/// (Dependabot / Renovate, e.g. a mathlib pin update).
+///
+/// Opt-in. A matching PR is always fully verified — every enabled prover,
+/// regardless of mode or `check_on_pr` — and, with `auto_merge`, merged
+/// once every job for its head commit has passed.
+///
+/// ```toml
+/// [dependency_prs]
+/// enabled = true
+/// auto_merge = true
+/// merge_method = "squash"          # merge | squash (default) | rebase
+/// approve = true                   # submit an approving review first
+/// authors = ["dependabot[bot]", "renovate[bot]"]   # default
+/// labels = ["dependencies"]                         # default
+/// ```
+///
+/// A PR matches when its author is in `authors` **or** it carries one of
+/// `labels`. Matching is case-insensitive. Only PRs opened by one of
+/// `authors` are auto-merged: anyone who can label a PR gets it fully
+/// verified, but not merged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyPrsSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub auto_merge: bool,
+
+    #[serde(default)]
+    pub merge_method: MergeMethod,
+
+    #[serde(default)]
+    pub approve: bool,
+
+    #[serde(default = "default_dependency_authors")]
+    pub authors: Vec<String>,
+
+    #[serde(default = "default_dependency_labels")]
+    pub labels: Vec<String>,
+}
+
+impl Default for DependencyPrsSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_merge: false,
+            merge_method: MergeMethod::default(),
+            approve: false,
+            authors: default_dependency_authors(),
+            labels: default_dependency_labels(),
+        }
+    }
+}
+
+impl DependencyPrsSection {
+    /// True when the policy is enabled and the PR looks like a
+    /// dependency bump.
+    pub fn matches(&self, author: Option<&str>, labels: &[String]) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let by_label = labels
+            .iter()
+            .any(|l| self.labels.iter().any(|x| x.eq_ignore_ascii_case(l)));
+        self.is_bot_author(author) || by_label
+    }
+
+    /// True when `author` is one of the dependency bots in `authors`.
+    pub fn is_bot_author(&self, author: Option<&str>) -> bool {
+        author.is_some_and(|a| self.authors.iter().any(|x| x.eq_ignore_ascii_case(a)))
+    }
+}
+
+/// `[auto_merge]` table: merge labelled PRs once every proof passes.
+///
+/// Opt-in. When a PR carries one of `labels` and the newest job of every
+/// prover at its head commit has passed, echidnabot merges it — after
+/// re-reading the PR from the platform to confirm it is still open, not
+/// a draft, still labelled and still at the verified commit.
+///
+/// ```toml
+/// [auto_merge]
+/// enabled = true
+/// labels = ["automerge"]   # default
+/// merge_method = "squash"  # merge | squash (default) | rebase
+/// ```
+///
+/// Label matching is case-insensitive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoMergeSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_auto_merge_labels")]
+    pub labels: Vec<String>,
+
+    #[serde(default)]
+    pub merge_method: MergeMethod,
+}
+
+impl Default for AutoMergeSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            labels: default_auto_merge_labels(),
+            merge_method: MergeMethod::default(),
+        }
+    }
+}
+
+impl AutoMergeSection {
+    /// True when the policy is enabled and `label` is an auto-merge label.
+    pub fn is_trigger(&self, label: &str) -> bool {
+        self.enabled && self.labels.iter().any(|l| l.eq_ignore_ascii_case(label))
+    }
+}
+
+/// `[branches]` table: which pushed branches trigger verification.
+///
+/// With no `include` patterns only the default branch is verified.
+/// `exclude` always wins. `*` matches any run of characters (including
+/// `/`), `?` a single character.
+///
+/// ```toml
+/// [branches]
+/// include = ["main", "release/*"]
+/// exclude = ["release/old-*"]
+/// ```
+///
+/// When the manifest sets neither list, the repository's
+/// `branchInclude` / `branchExclude` settings apply instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchesSection {
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl BranchesSection {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// True when a push to `branch` should be verified. An unknown
+    /// `default_branch` is treated as matching, so an unset filter never
+    /// drops pushes just because the platform did not say.
+    pub fn allows(&self, branch: &str, default_branch: Option<&str>) -> bool {
+        if self.exclude.iter().any(|p| wildcard_match(p, branch)) {
+            return false;
+        }
+        if self.include.is_empty() {
+            return default_branch.is_none_or(|d| d == branch);
+        }
+        self.include.iter().any(|p| wildcard_match(p, branch))
+    }
+}
+
+/// `[push]` table: which commits of a multi-commit push are verified.
+///
+/// ```toml
+/// [push]
+/// commits = "all"     # head (default) | all | merges
+/// max_commits = 20    # default
+/// ```
+///
+/// The head is always verified. With `all`, each earlier commit of the
+/// push gets a low-priority job of its own too, so `git bisect` can trust
+/// it; with `merges`, only the merge commits among them. At most
+/// `max_commits` commits are verified per push, head included — the
+/// newest ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushSection {
+    #[serde(default)]
+    pub commits: PushCommits,
+
+    #[serde(default = "default_max_push_commits")]
+    pub max_commits: usize,
+}
+
+impl Default for PushSection {
+    fn default() -> Self {
+        Self {
+            commits: PushCommits::default(),
+            max_commits: default_max_push_commits(),
+        }
+    }
+}
+
+/// Commits of a push that get verified, besides the head.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushCommits {
+    /// Only the head.
+    #[default]
+    Head,
+    /// Every pushed commit.
+    All,
+    /// Merge commits.
+    Merges,
+}
+
+/// `[flaky]` table: re-runs of failing files (see [`crate::flaky`]).
+///
+/// ```toml
+/// [flaky]
+/// reruns = 2    # default 0: failures are not re-run
+/// ```
+///
+/// A failed file is checked up to `reruns` more times, stopping at the
+/// first pass. One that passes is flaky and counts as verified; one that
+/// never does is a deterministic failure.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlakySection {
+    #[serde(default)]
+    pub reruns: u32,
+}
+
+/// `[bisect]` table: find the commit that broke a proof on the default
+/// branch (see [`crate::bisect`]).
+///
+/// ```toml
+/// [bisect]
+/// enabled = true
+/// max_commits = 64                 # default; longer ranges are not bisected
+/// labels = ["proof-regression"]    # default; on the issue naming the commit
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BisectSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_bisect_max_commits")]
+    pub max_commits: u32,
+
+    #[serde(default = "default_bisect_labels")]
+    pub labels: Vec<String>,
+}
+
+impl Default for BisectSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_commits: default_bisect_max_commits(),
+            labels: default_bisect_labels(),
+        }
+    }
+}
+
+fn default_bisect_max_commits() -> u32 {
+    64
+}
+
+fn default_bisect_labels() -> Vec<String> {
+    vec!["proof-regression".to_string()]
+}
+
+/// `[fixer]` table: when a tactic suggestion makes a failing file verify,
+/// propose it as a suggested change on the PR, or as a repair PR for a
+/// pushed branch (see [`crate::fixer`]). Nothing is merged without review.
+///
+/// ```toml
+/// [fixer]
+/// enabled = true
+/// max_attempts = 3    # default; suggestions tried per failing file
+/// labels = ["echidnabot-repair", "needs-review"]    # default; on the repair PR
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixerSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_fixer_max_attempts")]
+    pub max_attempts: u32,
+
+    #[serde(default = "default_fixer_labels")]
+    pub labels: Vec<String>,
+}
+
+impl Default for FixerSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: default_fixer_max_attempts(),
+            labels: default_fixer_labels(),
+        }
+    }
+}
+
+fn default_fixer_max_attempts() -> u32 {
+    3
+}
+
+fn default_fixer_labels() -> Vec<String> {
+    vec!["echidnabot-repair".to_string(), "needs-review".to_string()]
+}
+
+/// `[auditor]` table: when every prover passes a commit, sign a
+/// verification certificate for it with the instance's attestation key
+/// (see [`crate::certificate`]). Needs `[attestation] signing_key_path` in
+/// the bot's config.
+///
+/// ```toml
+/// [auditor]
+/// enabled = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditorSection {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// `[normalize]` table: rewrite the job's proof files in the checkout
+/// before any prover runs, for parsers that reject files saved on Windows
+/// (see [`crate::executor::normalize`]). Every change is reported.
+///
+/// ```toml
+/// [normalize]
+/// enabled = true
+/// line_endings = true    # default; CRLF and lone CR become LF
+/// bom = true             # default; a leading UTF-8 byte order mark is removed
+/// encoding = true        # default; UTF-16 with a byte order mark becomes UTF-8
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizeSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_true")]
+    pub line_endings: bool,
+
+    #[serde(default = "default_true")]
+    pub bom: bool,
+
+    #[serde(default = "default_true")]
+    pub encoding: bool,
+}
+
+impl Default for NormalizeSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            line_endings: true,
+            bom: true,
+            encoding: true,
+        }
+    }
+}
+
+/// Match `text` against a pattern where `*` is any run of characters
+/// and `?` is exactly one.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ti = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// `[stale_failures]` table: nag PRs whose proofs keep failing.
+///
+/// Opt-in. Once a PR's head has been failing for `remind_after_days`,
+/// echidnabot comments on it, then again every `remind_every_days`
+/// while the failure persists. The optional later steps each fire once
+/// per failure streak; a green head resets everything.
+///
+/// ```toml
+/// [stale_failures]
+/// enabled = true
+/// remind_after_days = 3                 # default
+/// remind_every_days = 3                 # default
+/// escalate_after_days = 7               # add escalate_labels (unset = never)
+/// escalate_labels = ["proofs-stale"]    # default
+/// draft_after_days = 14                 # convert to draft (unset = never)
+/// close_after_days = 30                 # close the PR (unset = never)
+/// ```
+///
+/// "Days" count from when the PR's current run of failing commits began,
+/// so pushing another failing commit does not reset the clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleFailuresSection {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_remind_days")]
+    pub remind_after_days: u32,
+
+    #[serde(default = "default_remind_days")]
+    pub remind_every_days: u32,
+
+    #[serde(default)]
+    pub escalate_after_days: Option<u32>,
+
+    #[serde(default = "default_escalate_labels")]
+    pub escalate_labels: Vec<String>,
+
+    #[serde(default)]
+    pub draft_after_days: Option<u32>,
+
+    #[serde(default)]
+    pub close_after_days: Option<u32>,
+}
+
+impl Default for StaleFailuresSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            remind_after_days: default_remind_days(),
+            remind_every_days: default_remind_days(),
+            escalate_after_days: None,
+            escalate_labels: default_escalate_labels(),
+            draft_after_days: None,
+            close_after_days: None,
+        }
+    }
+}
+
+fn default_remind_days() -> u32 {
+    3
+}
+
+fn default_max_push_commits() -> usize {
+    20
+}
+
+fn default_escalate_labels() -> Vec<String> {
+    vec!["proofs-stale".to_string()]
+}
+
+fn default_auto_merge_labels() -> Vec<String> {
+    vec!["automerge".to_string()]
+}
+
+fn default_dependency_authors() -> Vec<String> {
+    vec!["dependabot[bot]".to_string(), "renovate[bot]".to_string()]
+}
+
+fn default_dependency_labels() -> Vec<String> {
+    vec!["dependencies".to_string()]
+}
+
 fn default_true() -> bool {
     true
 }
@@ -206,6 +884,7 @@ pub fn is_empty(&self) -> bool {
             && self.provers.enabled.is_empty()
             && self.provers.disabled.is_empty()
             && self.provers.per_prover.is_empty()
+            && self.provers.extensions.is_empty()
             && self.proofs.include.is_empty()
             && self.proofs.exclude.is_empty()
             && self.axioms.forbid.is_empty()
@@ -213,6 +892,18 @@ pub fn is_empty(&self) -> bool {
             && self.merge_block.min_confidence.is_none()
             && self.merge_block.axiom_severity.is_none()
             && self.blocked_on.labels.is_empty()
+            && self.budgets.is_empty()
+            && self.budgets.slowdown_ratio.is_none()
+            && !self.dependency_prs.enabled
+            && !self.auto_merge.enabled
+            && !self.stale_failures.enabled
+            && self.branches.is_empty()
+            && self.push == PushSection::default()
+            && self.flaky.reruns == 0
+            && !self.bisect.enabled
+            && !self.fixer.enabled
+            && !self.auditor.enabled
+            && !self.normalize.enabled
     }
 
     /// Resolve the effective mode using the manifest's `[bot] mode`
@@ -238,6 +929,12 @@ pub fn prover_runs(&self, slug: &str) -> bool {
         }
         self.provers.enabled.iter().any(|p| p == slug)
     }
+
+    /// The extension → prover table for this repo: the built-in one with
+    /// `[provers.extensions]` applied.
+    pub fn extension_table(&self) -> ExtensionTable {
+        ExtensionTable::for_repo(&self.provers.extensions)
+    }
 }
 
 #[cfg(test)]
@@ -370,6 +1067,21 @@ fn prover_runs_empty_whitelist_means_all() {
         assert!(!m.prover_runs("mizar"));
     }
 
+    #[test]
+    fn extension_routing_is_not_a_prover_table() {
+        let content = r#"
+            [provers.extensions]
+            ".v" = "none"
+            [provers.coq]
+            flags = ["-Q", "theories", "Foo"]
+        "#;
+        let m = RepoManifest::parse(content).unwrap();
+        assert!(!m.provers.per_prover.contains_key("extensions"));
+        assert!(m.provers.per_prover.contains_key("coq"));
+        assert!(m.extension_table().provers_for("rtl/top.v").is_empty());
+        assert!(!m.is_empty());
+    }
+
     #[test]
     fn effective_mode_falls_back_to_default() {
         let content = r#"
@@ -406,6 +1118,175 @@ fn parses_disabled_bot() {
         assert!(!m.bot.enabled);
     }
 
+    #[test]
+    fn parses_budgets() {
+        let content = r#"
+            [budgets]
+            action = "fail"
+            file_seconds = 300
+            theorem_seconds = 60
+            slowdown_ratio = 1.5
+
+            [budgets.files]
+            "theories/Heavy.v" = 900
+
+            [budgets.theorems]
+            big_lemma = 240
+        "#;
+        let m = RepoManifest::parse(content).unwrap();
+        assert_eq!(m.budgets.action, BudgetAction::Fail);
+        assert_eq!(m.budgets.slowdown_ratio, Some(1.5));
+        assert_eq!(m.budgets.file_budget_ms("theories/Heavy.v"), Some(900_000));
+        assert_eq!(m.budgets.file_budget_ms("theories/Light.v"), Some(300_000));
+        assert_eq!(m.budgets.theorem_budget_ms("big_lemma"), Some(240_000));
+        assert_eq!(m.budgets.theorem_budget_ms("small_lemma"), Some(60_000));
+        assert!(!m.is_empty());
+    }
+
+    #[test]
+    fn budgets_default_to_warn_and_unset() {
+        let m = RepoManifest::parse("").unwrap();
+        assert_eq!(m.budgets.action, BudgetAction::Warn);
+        assert!(m.budgets.file_budget_ms("any.v").is_none());
+        assert!(m.budgets.theorem_budget_ms("any").is_none());
+    }
+
+    #[test]
+    fn dependency_prs_match_by_author_or_label() {
+        let m = RepoManifest::parse(
+            r#"
+            [dependency_prs]
+            enabled = true
+            auto_merge = true
+            merge_method = "rebase"
+            "#,
+        )
+        .unwrap();
+        let d = &m.dependency_prs;
+        assert_eq!(d.merge_method, MergeMethod::Rebase);
+        assert!(d.matches(Some("Dependabot[bot]"), &[]));
+        assert!(d.matches(Some("alice"), &["dependencies".to_string()]));
+        assert!(!d.matches(Some("alice"), &["bug".to_string()]));
+        assert!(d.is_bot_author(Some("renovate[bot]")));
+        assert!(!d.is_bot_author(Some("alice")));
+        assert!(!RepoManifest::parse("").unwrap().dependency_prs.matches(Some("dependabot[bot]"), &[]));
+    }
+
+    #[test]
+    fn auto_merge_labels_are_opt_in() {
+        assert!(!RepoManifest::parse("").unwrap().auto_merge.is_trigger("automerge"));
+        let m = RepoManifest::parse("[auto_merge]\nenabled = true\nlabels = [\"ship-it\"]").unwrap();
+        assert!(m.auto_merge.is_trigger("Ship-It"));
+        assert!(!m.auto_merge.is_trigger("automerge"));
+        assert_eq!(m.auto_merge.merge_method, MergeMethod::Squash);
+        assert!(!m.is_empty());
+    }
+
+    #[test]
+    fn stale_failures_defaults_and_overrides() {
+        let d = RepoManifest::parse("").unwrap().stale_failures;
+        assert!(!d.enabled);
+        assert_eq!((d.remind_after_days, d.remind_every_days), (3, 3));
+        assert!(d.close_after_days.is_none());
+
+        let m = RepoManifest::parse(
+            "[stale_failures]\nenabled = true\nescalate_after_days = 7\nclose_after_days = 30",
+        )
+        .unwrap();
+        assert_eq!(m.stale_failures.escalate_after_days, Some(7));
+        assert_eq!(m.stale_failures.escalate_labels, vec!["proofs-stale".to_string()]);
+        assert!(!m.is_empty());
+    }
+
+    #[test]
+    fn branch_filter_defaults_to_default_branch() {
+        let none = BranchesSection::default();
+        assert!(none.allows("main", Some("main")));
+        assert!(!none.allows("feature/x", Some("main")));
+        assert!(none.allows("feature/x", None), "unknown default branch is not filtered");
+
+        let m = RepoManifest::parse(
+            "[branches]\ninclude = [\"main\", \"release/*\"]\nexclude = [\"release/old-*\"]",
+        )
+        .unwrap();
+        assert!(m.branches.allows("release/2.0", Some("main")));
+        assert!(!m.branches.allows("release/old-1", Some("main")));
+        assert!(!m.branches.allows("dev", Some("dev")), "include list replaces the default");
+        assert!(!m.is_empty());
+
+        assert!(wildcard_match("v?.*", "v1.2.3"));
+        assert!(!wildcard_match("v?.*", "v10.2"));
+    }
+
+    #[test]
+    fn push_policy_defaults_to_head() {
+        assert_eq!(RepoManifest::parse("").unwrap().push.commits, PushCommits::Head);
+        let m = RepoManifest::parse("[push]\ncommits = \"merges\"\nmax_commits = 5").unwrap();
+        assert_eq!(m.push, PushSection { commits: PushCommits::Merges, max_commits: 5 });
+        assert!(!m.is_empty());
+        assert!(RepoManifest::parse("[push]\ncommits = \"some\"").is_none());
+    }
+
+    #[test]
+    fn flaky_reruns_are_opt_in() {
+        assert_eq!(RepoManifest::parse("").unwrap().flaky.reruns, 0);
+        let m = RepoManifest::parse("[flaky]\nreruns = 3").unwrap();
+        assert_eq!(m.flaky.reruns, 3);
+        assert!(!m.is_empty());
+    }
+
+    #[test]
+    fn bisect_is_opt_in() {
+        let m = RepoManifest::parse("").unwrap();
+        assert!(!m.bisect.enabled);
+        let m = RepoManifest::parse("[bisect]\nenabled = true\nmax_commits = 16").unwrap();
+        assert!(m.bisect.enabled && !m.is_empty());
+        assert_eq!(m.bisect.max_commits, 16);
+        assert_eq!(m.bisect.labels, vec!["proof-regression".to_string()]);
+    }
+
+    #[test]
+    fn fixer_is_opt_in() {
+        let m = RepoManifest::parse("").unwrap();
+        assert!(!m.fixer.enabled);
+        let m = RepoManifest::parse("[fixer]\nenabled = true").unwrap();
+        assert!(m.fixer.enabled && !m.is_empty());
+        assert_eq!(m.fixer.max_attempts, 3);
+        assert_eq!(m.fixer.labels, ["echidnabot-repair", "needs-review"]);
+    }
+
+    #[test]
+    fn auditor_is_opt_in() {
+        assert!(!RepoManifest::parse("").unwrap().auditor.enabled);
+        let m = RepoManifest::parse("[auditor]\nenabled = true").unwrap();
+        assert!(m.auditor.enabled && !m.is_empty());
+    }
+
+    #[test]
+    fn normalize_is_opt_in_with_every_step_on() {
+        assert!(!RepoManifest::parse("").unwrap().normalize.enabled);
+        let m = RepoManifest::parse("[normalize]\nenabled = true\nbom = false").unwrap();
+        assert!(m.normalize.enabled && !m.is_empty());
+        assert!(m.normalize.line_endings && m.normalize.encoding && !m.normalize.bom);
+    }
+
+    #[test]
+    fn proof_path_globs() {
+        let m = RepoManifest::parse(
+            "[proofs]\ninclude = [\"proofs/**\"]\nexclude = [\"proofs/vendor/**\", \"Scratch*.v\"]",
+        )
+        .unwrap();
+        assert!(m.proofs.allows("proofs/Nat.v"));
+        assert!(m.proofs.allows("proofs/deep/er/List.v"));
+        assert!(!m.proofs.allows("src/Main.lean"));
+        assert!(!m.proofs.allows("proofs/vendor/std/Lib.v"));
+        assert!(!m.proofs.allows("proofs/a/Scratch1.v"), "bare pattern matches at any depth");
+        assert!(ProofsSection::default().allows("anything/at/all.v"));
+
+        assert!(glob_match("**/*.v", "Top.v"));
+        assert!(!glob_match("src/*.v", "src/a/b.v"), "* stays within a segment");
+    }
+
     #[test]
     fn invalid_toml_returns_none() {
         assert!(RepoManifest::parse("this is not toml [[[").is_none());
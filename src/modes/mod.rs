@@ -10,15 +10,21 @@
 //! - **Consultant**: Interactive Q&A about proof state
 //! - **Regulator**: Blocks PR merges when proofs fail
 
+pub mod commands;
 pub mod directives;
+pub mod ignore;
 pub mod manifest;
 pub use directives::{
-    fetch_directive_via_adapter, parse_a2ml_directive, resolve_mode,
-    resolve_mode_with_daemon_default,
+    fetch_directive_via_adapter, parse_a2ml_directive, resolve_mode, resolve_mode_traced,
+    resolve_mode_with_daemon_default, ModeSource,
 };
+pub use ignore::{IgnoreFile, ProofFilter, IGNORE_FILE};
 pub use manifest::{
-    AxiomSeverity, AxiomsSection, BlockedOnSection, BotSection, MergeBlockSection,
-    ProofsSection, ProverConfig, ProversSection, RepoManifest,
+    AuditorSection, AutoMergeSection, AxiomSeverity, AxiomsSection, BisectSection,
+    BlockedOnSection, BotSection, BranchesSection, BudgetAction, BudgetsSection,
+    DependencyPrsSection, FixerSection, FlakySection, MergeBlockSection, NormalizeSection,
+    ProofsSection, ProverConfig, ProversSection, PushCommits, PushSection, RepoManifest,
+    StaleFailuresSection,
 };
 
 use serde::{Deserialize, Serialize};
@@ -19,6 +19,18 @@
 //! → feedback into any OTLP-compatible collector (Jaeger, Tempo,
 //! Honeycomb, etc.).
 //!
+//! # Trace propagation
+//!
+//! A verification crosses several asynchronous hops: the webhook is
+//! stored and processed later by the ingest worker, the job waits in the
+//! scheduler queue, and ECHIDNA runs in another process. To keep it one
+//! trace, each hop carries a W3C `traceparent` ([`current_trace_parent`]):
+//! stored deliveries and jobs record the one current when they were
+//! created, the ingest worker and scheduler parent their `webhook.process`
+//! and `job.run` spans on it ([`set_parent`]), and calls to ECHIDNA send
+//! it as a header ([`propagate`]). `job.run` carries `job_id`, `repo`,
+//! `prover` and `commit`; check-run posting is its `job.report` child.
+//!
 //! # Format selection
 //!
 //! * `ECHIDNABOT_LOG_FORMAT=text` (default) — human-friendly `fmt` layer.
@@ -48,11 +60,16 @@
 //! # }
 //! ```
 
+use std::collections::HashMap;
+
+use opentelemetry::propagation::TextMapPropagator;
 use opentelemetry::trace::TracerProvider as _;
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
 use opentelemetry_sdk::trace::TracerProvider as SdkTracerProvider;
 use opentelemetry_sdk::Resource;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Layer};
@@ -247,10 +264,66 @@ pub fn init_tracing(
     }
 }
 
+/// W3C header carrying the trace context.
+pub const TRACE_PARENT_HEADER: &str = "traceparent";
+
+/// `traceparent` of the current span, to hand to a later hop. `None`
+/// outside a span or without the OTLP layer, so nothing is recorded
+/// when tracing is not exported.
+pub fn current_trace_parent() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&context, &mut carrier);
+    carrier.remove(TRACE_PARENT_HEADER)
+}
+
+/// Make `span` a child of the span `trace_parent` was taken from.
+/// Unparseable or missing values leave it a root span.
+pub fn set_parent(span: &tracing::Span, trace_parent: Option<&str>) {
+    let Some(trace_parent) = trace_parent else {
+        return;
+    };
+    let carrier = HashMap::from([(TRACE_PARENT_HEADER.to_string(), trace_parent.to_string())]);
+    span.set_parent(TraceContextPropagator::new().extract(&carrier));
+}
+
+/// `request` with the current `traceparent` header, if any.
+pub fn propagate(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match current_trace_parent() {
+        Some(trace_parent) => request.header(TRACE_PARENT_HEADER, trace_parent),
+        None => request,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn trace_parent_links_spans_across_hops() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(current_trace_parent(), None, "no span, nothing to propagate");
+
+            let webhook = tracing::info_span!("webhook.github");
+            let parent = webhook.in_scope(current_trace_parent).expect("traceparent");
+            assert!(parent.starts_with("00-"), "{}", parent);
+
+            let job = tracing::info_span!("job.run");
+            set_parent(&job, Some(&parent));
+            let child = job.in_scope(current_trace_parent).expect("traceparent");
+            assert_eq!(child[3..35], parent[3..35], "same trace id");
+            assert_ne!(child, parent, "a span of its own");
+
+            let orphan = tracing::info_span!("job.run");
+            set_parent(&orphan, Some("garbage"));
+            let root = orphan.in_scope(current_trace_parent).expect("traceparent");
+            assert_ne!(root[3..35], parent[3..35]);
+        });
+    }
+
     // Note: each test runs in its own process under `cargo test` by
     // default ONLY when --test-threads=1; in normal runs they share a
     // process and a global subscriber. We therefore avoid calling
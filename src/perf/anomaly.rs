@@ -0,0 +1,407 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Long-term trend anomaly detection on verification durations.
+//!
+//! The baseline for a `(repo, prover, file)` tuple is the last
+//! `history_window` *successful* runs from `file_timings`. Failed runs are
+//! excluded because a proof that errors out early is not a meaningful
+//! timing sample. The current sample is judged against that baseline with
+//! three gates, all of which must trip:
+//!
+//! 1. **Ratio** — `current / median >= ratio_threshold` (default 2×).
+//! 2. **Robust z-score** — `0.6745 · (current − median) / MAD >= z_threshold`
+//!    (default 3.5, the Iglewicz–Hoaglin cut-off). Median/MAD rather than
+//!    mean/stddev so one historical outlier doesn't inflate the baseline.
+//!    When MAD is zero (perfectly stable history) this gate is skipped and
+//!    the ratio gate decides alone.
+//! 3. **Absolute delta** — `current − median >= min_delta_ms`, so a proof
+//!    moving from 20 ms to 60 ms never raises noise.
+//!
+//! Below `min_samples` of history, no verdict is issued.
+//!
+//! Theorems are judged the same way when the job's prover output timed
+//! them ([`crate::proof_items`]): against the same theorem's verified runs
+//! in earlier jobs. A repository can lower or raise the ratio gate with
+//! `[budgets] slowdown_ratio` in its manifest. Findings go into the
+//! summary and, as warning annotations on the file or theorem, onto the
+//! check run — they never change its conclusion.
+
+use std::collections::BTreeMap;
+
+use crate::adapters::{AnnotationLevel, CheckAnnotation};
+use crate::config::AnomalyConfig;
+use crate::error::Result;
+use crate::proof_items::ItemStatus;
+use crate::scheduler::JobId;
+use crate::store::models::ProofItemRecord;
+use crate::store::Store;
+
+/// Scale factor that makes MAD a consistent estimator of σ for normal data.
+const MAD_SCALE: f64 = 0.6745;
+
+/// A proof file whose latest duration is well outside its historical norm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationAnomaly {
+    /// Repo-relative path of the proof file.
+    pub file_path: String,
+    /// Set when a theorem, not the whole file, slowed down.
+    pub theorem: Option<String>,
+    /// 1-based line of the theorem, when known.
+    pub line: Option<u32>,
+    /// Duration of the run being judged.
+    pub current_ms: i64,
+    /// Median of the baseline window.
+    pub baseline_median_ms: i64,
+    /// `current / median`.
+    pub ratio: f64,
+    /// Robust z-score; `None` when the baseline had zero spread.
+    pub z_score: Option<f64>,
+    /// Number of baseline samples the verdict rests on.
+    pub samples: usize,
+}
+
+/// Stateless evaluator holding the configured thresholds.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    min_samples: usize,
+    ratio_threshold: f64,
+    z_threshold: f64,
+    min_delta_ms: u64,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::from_config(&AnomalyConfig::default())
+    }
+}
+
+impl AnomalyDetector {
+    pub fn from_config(config: &AnomalyConfig) -> Self {
+        Self {
+            min_samples: config.min_samples.max(1),
+            ratio_threshold: config.ratio_threshold,
+            z_threshold: config.z_threshold,
+            min_delta_ms: config.min_delta_ms,
+        }
+    }
+
+    /// The same detector with a repository's own ratio gate.
+    pub fn with_ratio_threshold(mut self, ratio_threshold: f64) -> Self {
+        self.ratio_threshold = ratio_threshold;
+        self
+    }
+
+    /// Judge `current_ms` against `history` (baseline durations, any order).
+    /// Returns `Some` only when every gate trips.
+    pub fn evaluate(
+        &self,
+        file_path: &str,
+        current_ms: i64,
+        history: &[i64],
+    ) -> Option<DurationAnomaly> {
+        if history.len() < self.min_samples {
+            return None;
+        }
+
+        let mut samples: Vec<f64> = history.iter().map(|&d| d.max(0) as f64).collect();
+        let baseline = median(&mut samples);
+        let current = current_ms.max(0) as f64;
+
+        let delta = current - baseline;
+        if delta < self.min_delta_ms as f64 {
+            return None;
+        }
+
+        // A zero median with a positive delta is an unbounded slowdown.
+        let ratio = if baseline > 0.0 { current / baseline } else { f64::INFINITY };
+        if ratio < self.ratio_threshold {
+            return None;
+        }
+
+        let mut deviations: Vec<f64> = samples.iter().map(|d| (d - baseline).abs()).collect();
+        let mad = median(&mut deviations);
+        let z_score = if mad > 0.0 {
+            let z = MAD_SCALE * delta / mad;
+            if z < self.z_threshold {
+                return None;
+            }
+            Some(z)
+        } else {
+            None
+        };
+
+        Some(DurationAnomaly {
+            file_path: file_path.to_string(),
+            theorem: None,
+            line: None,
+            current_ms,
+            baseline_median_ms: baseline.round() as i64,
+            ratio,
+            z_score,
+            samples: history.len(),
+        })
+    }
+}
+
+/// Evaluate every file and theorem timing recorded for `job_id` against
+/// its history.
+///
+/// The job's own rows are excluded from the baseline, as are failed
+/// runs. Returns an empty list when the job recorded no timings.
+pub async fn detect_for_job(
+    store: &dyn Store,
+    detector: &AnomalyDetector,
+    job_id: JobId,
+    history_window: usize,
+) -> Result<Vec<DurationAnomaly>> {
+    let timings = store.list_file_timings_for_job(job_id).await?;
+    let mut anomalies = Vec::new();
+
+    for timing in timings {
+        // Fetch one extra row: the newest entry is usually this job's own.
+        let history: Vec<i64> = store
+            .list_file_timings(
+                timing.repo_id,
+                timing.prover.clone(),
+                &timing.file_path,
+                history_window + 1,
+            )
+            .await?
+            .into_iter()
+            .filter(|t| t.job_id != timing.job_id && t.success)
+            .take(history_window)
+            .map(|t| t.duration_ms)
+            .collect();
+
+        if let Some(a) = detector.evaluate(&timing.file_path, timing.duration_ms, &history) {
+            tracing::warn!(
+                "Duration anomaly: {} took {} ms vs median {} ms ({:.1}×, n={})",
+                a.file_path,
+                a.current_ms,
+                a.baseline_median_ms,
+                a.ratio,
+                a.samples
+            );
+            anomalies.push(a);
+        }
+    }
+
+    anomalies.extend(detect_theorems(store, detector, job_id, history_window).await?);
+    Ok(anomalies)
+}
+
+/// The timed, verified theorems of `job_id`, each against the same
+/// theorem's times in earlier jobs.
+async fn detect_theorems(
+    store: &dyn Store,
+    detector: &AnomalyDetector,
+    job_id: JobId,
+    history_window: usize,
+) -> Result<Vec<DurationAnomaly>> {
+    let Some(job) = store.get_job(job_id).await? else {
+        return Ok(Vec::new());
+    };
+    let items = store.list_proof_items(job_id).await?;
+    let mut by_file: BTreeMap<&str, Vec<&ProofItemRecord>> = BTreeMap::new();
+    for item in &items {
+        if item.theorem.is_some() && item.duration_ms.is_some() && item.status == ItemStatus::Verified {
+            by_file.entry(item.file_path.as_str()).or_default().push(item);
+        }
+    }
+
+    let mut anomalies = Vec::new();
+    for (file, theorems) in by_file {
+        // One query per file; the job's own rows come back too.
+        let limit = (history_window + 1) * theorems.len();
+        let history = store
+            .list_theorem_timings(job.repo_id, job.prover.clone(), file, limit)
+            .await?;
+        for item in theorems {
+            let (Some(name), Some(current_ms)) = (&item.theorem, item.duration_ms) else {
+                continue;
+            };
+            let samples: Vec<i64> = history
+                .iter()
+                .filter(|h| h.job_id != job_id.0 && h.theorem.as_ref() == Some(name))
+                .filter_map(|h| h.duration_ms)
+                .take(history_window)
+                .collect();
+            if let Some(mut a) = detector.evaluate(file, current_ms, &samples) {
+                tracing::warn!(
+                    "Duration anomaly: {} in {} took {} ms vs median {} ms ({:.1}×, n={})",
+                    name,
+                    file,
+                    a.current_ms,
+                    a.baseline_median_ms,
+                    a.ratio,
+                    a.samples
+                );
+                a.theorem = Some(name.clone());
+                a.line = item.line;
+                anomalies.push(a);
+            }
+        }
+    }
+    Ok(anomalies)
+}
+
+fn change(a: &DurationAnomaly) -> String {
+    if a.ratio.is_finite() {
+        format!("{:.1}×", a.ratio)
+    } else {
+        "new cost".to_string()
+    }
+}
+
+/// Warning annotations for `anomalies`, on the theorem's line or the
+/// file's first.
+pub fn anomaly_annotations(anomalies: &[DurationAnomaly]) -> Vec<CheckAnnotation> {
+    anomalies
+        .iter()
+        .map(|a| CheckAnnotation {
+            path: a.file_path.clone(),
+            line: a.line.unwrap_or(1),
+            level: AnnotationLevel::Warning,
+            message: format!(
+                "{} took {} against a median of {} over {} run(s) ({})",
+                match &a.theorem {
+                    Some(theorem) => format!("`{}`", theorem),
+                    None => "This file".to_string(),
+                },
+                format_ms(a.current_ms),
+                format_ms(a.baseline_median_ms),
+                a.samples,
+                change(a),
+            ),
+        })
+        .collect()
+}
+
+/// Render anomalies as a Markdown section for check-run summaries and PR
+/// comments. Returns an empty string for an empty slice so callers can
+/// append unconditionally.
+pub fn format_anomaly_report(anomalies: &[DurationAnomaly]) -> String {
+    if anomalies.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("### 🐢 Verification slowdowns\n\n");
+    out.push_str("| Proof | Now | Baseline (median) | Change |\n");
+    out.push_str("|-------|-----|-------------------|--------|\n");
+    for a in anomalies {
+        let name = match &a.theorem {
+            Some(theorem) => format!("`{}` (`{}`)", theorem, a.file_path),
+            None => format!("`{}`", a.file_path),
+        };
+        out.push_str(&format!(
+            "| {} | {} | {} (n={}) | {} |\n",
+            name,
+            format_ms(a.current_ms),
+            format_ms(a.baseline_median_ms),
+            a.samples,
+            change(a),
+        ));
+    }
+    out.push_str(
+        "\nThese proofs still ran, but markedly slower than their history. \
+         Check for tactic search blow-ups or changed upstream lemmas.\n",
+    );
+    out
+}
+
+fn format_ms(ms: i64) -> String {
+    if ms >= 1000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> AnomalyDetector {
+        AnomalyDetector::default()
+    }
+
+    #[test]
+    fn stable_history_no_anomaly() {
+        let history = [10_000, 10_200, 9_800, 10_100, 9_900];
+        assert!(detector().evaluate("A.v", 10_300, &history).is_none());
+    }
+
+    #[test]
+    fn five_fold_slowdown_flagged() {
+        let history = [10_000, 10_200, 9_800, 10_100, 9_900];
+        let a = detector().evaluate("A.v", 50_000, &history).expect("anomaly");
+        assert_eq!(a.baseline_median_ms, 10_000);
+        assert!(a.ratio > 4.9);
+        assert!(a.z_score.unwrap() > 3.5);
+        assert_eq!(a.samples, 5);
+    }
+
+    #[test]
+    fn too_few_samples_no_verdict() {
+        let history = [1_000, 1_000];
+        assert!(detector().evaluate("A.v", 50_000, &history).is_none());
+    }
+
+    #[test]
+    fn fast_proofs_ignore_jitter() {
+        // 20 ms → 100 ms is 5× but well under the 1 s absolute floor.
+        let history = [20, 21, 19, 20, 22];
+        assert!(detector().evaluate("A.v", 100, &history).is_none());
+    }
+
+    #[test]
+    fn zero_mad_uses_ratio_only() {
+        let history = [2_000; 6];
+        let a = detector().evaluate("A.v", 6_000, &history).expect("anomaly");
+        assert!(a.z_score.is_none());
+        assert!((a.ratio - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn noisy_history_raises_bar() {
+        // Wide spread: 2× the median is within normal variation.
+        let history = [5_000, 15_000, 2_000, 20_000, 10_000, 8_000];
+        assert!(detector().evaluate("A.v", 19_000, &history).is_none());
+    }
+
+    #[test]
+    fn report_renders_table() {
+        let a = DurationAnomaly {
+            file_path: "theories/Big.v".into(),
+            theorem: Some("big_lemma".into()),
+            line: Some(40),
+            current_ms: 62_000,
+            baseline_median_ms: 12_000,
+            ratio: 62.0 / 12.0,
+            z_score: Some(9.0),
+            samples: 12,
+        };
+        let md = format_anomaly_report(&[a.clone()]);
+        assert!(md.contains("theories/Big.v"));
+        assert!(md.contains("62.0s"));
+        assert!(md.contains("5.2×"));
+        assert!(md.contains("`big_lemma` (`theories/Big.v`)"));
+        assert!(format_anomaly_report(&[]).is_empty());
+        let annotations = anomaly_annotations(&[a]);
+        assert_eq!((annotations[0].line, annotations[0].level), (40, AnnotationLevel::Warning));
+    }
+}
@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Per-file and per-theorem duration budgets.
+//!
+//! Where [`super::anomaly`] compares a run against its own history, a
+//! budget is an absolute ceiling set by the repo owner in the manifest's
+//! `[budgets]` table (see [`BudgetsSection`]) — "no theorem may take more
+//! than 60 s".
+//!
+//! File durations come from the `file_timings` rows recorded for the job.
+//! Theorem durations are recovered from the prover output, which must
+//! carry structured timing. Two formats are recognised:
+//!
+//! - **Coq `-time`**: `Chars 0 - 42 [Lemma~foo~:~...] 0.512 secs (...)`.
+//!   Every sentence from the theorem statement up to its closing
+//!   `Qed`/`Defined`/`Admitted`/`Abort` is charged to that theorem, so
+//!   the (usually dominant) kernel check at `Qed` is included.
+//! - **JSON lines**: `{"theorem": "foo", "duration_ms": 1234}`, optionally
+//!   with `"file"`. Intended for wrapper scripts around provers with no
+//!   native per-declaration timing.
+//!
+//! Output in neither format yields no theorem timings, and theorem
+//! budgets are silently skipped.
+
+use crate::modes::{BudgetAction, BudgetsSection};
+use crate::store::models::FileTimingRecord;
+
+/// Vernacular keywords that open a proof obligation in Coq.
+pub(crate) const COQ_THEOREM_KEYWORDS: &[&str] = &[
+    "Theorem",
+    "Lemma",
+    "Fact",
+    "Remark",
+    "Corollary",
+    "Proposition",
+    "Property",
+    "Example",
+];
+
+/// Vernacular keywords that close one.
+const COQ_PROOF_TERMINATORS: &[&str] = &["Qed", "Defined", "Admitted", "Abort"];
+
+/// Wall time attributed to a single theorem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TheoremTiming {
+    /// Source file, when the timing format carries it.
+    pub file_path: Option<String>,
+    pub theorem: String,
+    pub duration_ms: i64,
+}
+
+/// What a budget was set on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetKind {
+    File,
+    Theorem,
+}
+
+/// A file or theorem that exceeded its configured budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetViolation {
+    pub kind: BudgetKind,
+    /// Repo-relative path (for files) or theorem name.
+    pub name: String,
+    pub actual_ms: i64,
+    pub budget_ms: u64,
+}
+
+/// Extract per-theorem timings from prover output.
+pub fn parse_theorem_timings(output: &str) -> Vec<TheoremTiming> {
+    let mut timings = Vec::new();
+    // Open Coq theorem: (name, accumulated ms).
+    let mut open: Option<(String, f64)> = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if line.starts_with('{') {
+            if let Some(t) = parse_json_timing(line) {
+                timings.push(t);
+            }
+            continue;
+        }
+
+        let Some((sentence, secs)) = parse_coq_time_line(line) else {
+            continue;
+        };
+        let mut words = sentence.split('~').filter(|w| !w.is_empty());
+        let head = words.next().unwrap_or("");
+
+        if COQ_THEOREM_KEYWORDS.contains(&head) {
+            if let Some(name) = words.next() {
+                let name = name.trim_end_matches(':').to_string();
+                open = Some((name, secs * 1000.0));
+            }
+        } else if let Some((_, ref mut acc)) = open {
+            *acc += secs * 1000.0;
+            let head = head.trim_end_matches('.');
+            if COQ_PROOF_TERMINATORS.contains(&head) {
+                let (theorem, ms) = open.take().unwrap();
+                timings.push(TheoremTiming {
+                    file_path: None,
+                    theorem,
+                    duration_ms: ms.round() as i64,
+                });
+            }
+        }
+    }
+
+    timings
+}
+
+/// `Chars A - B [sentence] X secs (...)` → `(sentence, X)`.
+pub(crate) fn parse_coq_time_line(line: &str) -> Option<(&str, f64)> {
+    if !line.starts_with("Chars ") {
+        return None;
+    }
+    let open = line.find('[')?;
+    let close = line.rfind(']')?;
+    if close <= open {
+        return None;
+    }
+    let sentence = &line[open + 1..close];
+    let secs = line[close + 1..]
+        .split_whitespace()
+        .next()?
+        .parse::<f64>()
+        .ok()?;
+    Some((sentence, secs))
+}
+
+fn parse_json_timing(line: &str) -> Option<TheoremTiming> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    Some(TheoremTiming {
+        file_path: value.get("file").and_then(|f| f.as_str()).map(str::to_string),
+        theorem: value.get("theorem")?.as_str()?.to_string(),
+        duration_ms: value.get("duration_ms")?.as_i64()?,
+    })
+}
+
+/// Check every measured file and theorem against its effective budget.
+/// Violations are returned worst-overrun first.
+pub fn evaluate_budgets(
+    budgets: &BudgetsSection,
+    files: &[FileTimingRecord],
+    theorems: &[TheoremTiming],
+) -> Vec<BudgetViolation> {
+    let mut violations = Vec::new();
+
+    for f in files {
+        if let Some(budget_ms) = budgets.file_budget_ms(&f.file_path) {
+            if f.duration_ms > budget_ms as i64 {
+                violations.push(BudgetViolation {
+                    kind: BudgetKind::File,
+                    name: f.file_path.clone(),
+                    actual_ms: f.duration_ms,
+                    budget_ms,
+                });
+            }
+        }
+    }
+
+    for t in theorems {
+        if let Some(budget_ms) = budgets.theorem_budget_ms(&t.theorem) {
+            if t.duration_ms > budget_ms as i64 {
+                violations.push(BudgetViolation {
+                    kind: BudgetKind::Theorem,
+                    name: match t.file_path {
+                        Some(ref file) => format!("{} ({})", t.theorem, file),
+                        None => t.theorem.clone(),
+                    },
+                    actual_ms: t.duration_ms,
+                    budget_ms,
+                });
+            }
+        }
+    }
+
+    violations.sort_by(|a, b| {
+        let ra = a.actual_ms as f64 / a.budget_ms.max(1) as f64;
+        let rb = b.actual_ms as f64 / b.budget_ms.max(1) as f64;
+        rb.total_cmp(&ra)
+    });
+    violations
+}
+
+/// Render violations as a Markdown section. Empty string for no
+/// violations so callers can append unconditionally.
+pub fn format_budget_report(violations: &[BudgetViolation], action: BudgetAction) -> String {
+    if violations.is_empty() {
+        return String::new();
+    }
+
+    let mut out = match action {
+        BudgetAction::Fail => String::from("### ⏱️ Performance budget exceeded (failing)\n\n"),
+        BudgetAction::Warn => String::from("### ⏱️ Performance budget exceeded\n\n"),
+    };
+    out.push_str("| Kind | Name | Took | Budget |\n");
+    out.push_str("|------|------|------|--------|\n");
+    for v in violations {
+        out.push_str(&format!(
+            "| {} | `{}` | {:.1}s | {:.1}s |\n",
+            match v.kind {
+                BudgetKind::File => "file",
+                BudgetKind::Theorem => "theorem",
+            },
+            v.name,
+            v.actual_ms as f64 / 1000.0,
+            v.budget_ms as f64 / 1000.0,
+        ));
+    }
+    out.push_str("\nBudgets are set in the `[budgets]` table of the repo's echidnabot manifest.\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::JobId;
+    use uuid::Uuid;
+
+    fn budgets(toml: &str) -> BudgetsSection {
+        crate::modes::RepoManifest::parse(toml).unwrap().budgets
+    }
+
+    fn file_timing(path: &str, ms: i64) -> FileTimingRecord {
+        FileTimingRecord::new(
+            JobId(Uuid::new_v4()),
+            Uuid::new_v4(),
+            ProverKind::new("coq"),
+            path.to_string(),
+            "abc123".to_string(),
+            ms,
+            true,
+        )
+    }
+
+    #[test]
+    fn coq_time_charges_whole_proof_to_theorem() {
+        let output = "\
+Chars 0 - 40 [Lemma~slow~:~forall~n,~n~+~0~=~n.] 0.001 secs (0.u,0.s)
+Chars 41 - 47 [Proof.] 0. secs (0.u,0.s)
+Chars 48 - 60 [induction~n.] 0.5 secs (0.5u,0.s)
+Chars 61 - 65 [Qed.] 70.2 secs (70.u,0.s)
+Chars 66 - 90 [Definition~x~:=~1.] 0.001 secs (0.u,0.s)
+Chars 91 - 120 [Theorem~fast:~True.] 0.001 secs (0.u,0.s)
+Chars 121 - 128 [exact~I.] 0.01 secs (0.u,0.s)
+Chars 129 - 133 [Qed.] 0.02 secs (0.u,0.s)";
+        let t = parse_theorem_timings(output);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].theorem, "slow");
+        assert_eq!(t[0].duration_ms, 70_701);
+        assert_eq!(t[1].theorem, "fast");
+        assert_eq!(t[1].duration_ms, 31);
+    }
+
+    #[test]
+    fn json_lines_timings() {
+        let output = r#"{"theorem": "big", "duration_ms": 90000, "file": "Big.lean"}
+not json
+{"unrelated": true}"#;
+        let t = parse_theorem_timings(output);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].file_path.as_deref(), Some("Big.lean"));
+        assert_eq!(t[0].duration_ms, 90_000);
+    }
+
+    #[test]
+    fn unstructured_output_yields_nothing() {
+        assert!(parse_theorem_timings("Error: foo\nbar").is_empty());
+    }
+
+    #[test]
+    fn violations_respect_overrides_and_order() {
+        let b = budgets(
+            r#"
+            [budgets]
+            file_seconds = 60
+            theorem_seconds = 60
+            [budgets.files]
+            "Heavy.v" = 300
+            "#,
+        );
+        let files = [
+            file_timing("Heavy.v", 200_000),
+            file_timing("Light.v", 90_000),
+        ];
+        let theorems = [TheoremTiming {
+            file_path: None,
+            theorem: "slow".into(),
+            duration_ms: 180_000,
+        }];
+        let v = evaluate_budgets(&b, &files, &theorems);
+        assert_eq!(v.len(), 2, "Heavy.v is within its override");
+        assert_eq!(v[0].kind, BudgetKind::Theorem, "3× overrun sorts first");
+        assert_eq!(v[1].name, "Light.v");
+    }
+
+    #[test]
+    fn report_mentions_action() {
+        let v = [BudgetViolation {
+            kind: BudgetKind::Theorem,
+            name: "slow".into(),
+            actual_ms: 75_000,
+            budget_ms: 60_000,
+        }];
+        let md = format_budget_report(&v, BudgetAction::Fail);
+        assert!(md.contains("failing"));
+        assert!(md.contains("75.0s"));
+        assert!(format_budget_report(&[], BudgetAction::Warn).is_empty());
+    }
+}
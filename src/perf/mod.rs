@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Verification performance analytics
+//!
+//! A proof that suddenly takes 5× longer is a signal even when it still
+//! passes — it usually means an automation tactic started exploring a
+//! much larger search space, or an upstream lemma changed shape. This
+//! module works over the per-file `file_timings` history kept by the
+//! store:
+//!
+//! - **Trend anomalies** (`anomaly`): robust median/MAD comparison of the
+//!   latest duration against the stored baseline.
+//! - **Budgets** (`budget`): absolute per-file / per-theorem ceilings from
+//!   the repo manifest's `[budgets]` table.
+
+pub mod anomaly;
+pub mod budget;
+
+pub use anomaly::{anomaly_annotations, detect_for_job, format_anomaly_report, AnomalyDetector, DurationAnomaly};
+pub use budget::{
+    evaluate_budgets, format_budget_report, parse_theorem_timings, BudgetKind, BudgetViolation,
+    TheoremTiming,
+};
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! PRs a push-triggered job reports on
+//!
+//! A job enqueued from a pull-request event knows its PR. One enqueued
+//! from a push (or a manual trigger) does not, even when the pushed
+//! commit is the head of an open PR, so Advisor comments and the
+//! stale-failure labels would have nowhere to go. [`for_job`] asks the
+//! platform which open PRs contain the commit, the first time the job
+//! reports, and keeps the answer in the job's payload
+//! (`associated_prs`); later lookups — the stale-failure sweep, a second
+//! report — read it from there.
+//!
+//! A failed lookup is logged and not stored, so the next report tries
+//! again; the job then reports on its check run only. So does a job whose
+//! commit a force-push or branch deletion orphaned: its PRs have moved on,
+//! and a bisection probe ([`crate::bisect`]) of a commit long merged.
+
+use crate::adapters::{PlatformAdapter, RepoId};
+use crate::scheduler::ProofJob;
+use crate::store::Store;
+
+/// The PRs `job`'s result goes to: its own PR, else the open PRs its
+/// commit was found in (looked up and stored on first use).
+pub async fn for_job(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &RepoId,
+    job: &ProofJob,
+) -> Vec<u64> {
+    if let Some(pr) = job.pr_number {
+        return vec![pr];
+    }
+    let mut record = match store.get_job(job.id).await {
+        Ok(Some(record)) => record,
+        Ok(None) => return Vec::new(),
+        Err(e) => {
+            tracing::debug!("Job {} not loaded for PR association: {}", job.id, e);
+            return Vec::new();
+        }
+    };
+    if record.payload.orphaned.is_some() || record.payload.bisection.is_some() {
+        return Vec::new();
+    }
+    if let Some(prs) = &record.payload.associated_prs {
+        return prs.clone();
+    }
+
+    let prs: Vec<u64> = match adapter.list_pull_requests_for_commit(repo, &job.commit_sha).await {
+        Ok(prs) => prs.iter().filter_map(|pr| pr.0.parse().ok()).collect(),
+        Err(e) => {
+            tracing::debug!(
+                "PRs containing {} @ {:.8} not looked up: {}",
+                repo.full_name(),
+                job.commit_sha,
+                e
+            );
+            return Vec::new();
+        }
+    };
+    record.payload.associated_prs = Some(prs.clone());
+    if let Err(e) = store.update_job(&record).await {
+        tracing::warn!("PR association of job {} not stored: {}", job.id, e);
+    }
+    prs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::adapters::github::GitHubAdapter;
+    use crate::adapters::http::Method;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::store::models::{ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn push_jobs_look_up_their_prs_once() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let repo_id = RepoId::new(Platform::GitHub, "o", "r");
+
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Get, "/repos/o/r/commits/abc/pulls", 200, r#"[{"number": 5, "state": "open"}]"#);
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+
+        let pushed = ProofJob::new(repo.id, "abc".into(), ProverKind::new("coq"), vec![]);
+        store.create_job(&ProofJobRecord::from(pushed.clone())).await.unwrap();
+        assert_eq!(for_job(&store, &github, &repo_id, &pushed).await, vec![5]);
+        assert_eq!(for_job(&store, &github, &repo_id, &pushed).await, vec![5]);
+        assert_eq!(api.requests().len(), 1, "the association is stored, not looked up again");
+        let stored = store.get_job(pushed.id).await.unwrap().unwrap();
+        assert_eq!(stored.pull_requests(), vec![5]);
+
+        let from_pr = ProofJob::new(repo.id, "abc".into(), ProverKind::new("coq"), vec![])
+            .with_context(Some(8), None);
+        assert_eq!(for_job(&store, &github, &repo_id, &from_pr).await, vec![8]);
+        assert_eq!(api.requests().len(), 1);
+    }
+}
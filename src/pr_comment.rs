@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Sticky PR result comments
+//!
+//! Every push to a PR re-runs its jobs, and each finished job reports in a
+//! comment. Rather than adding a comment per run, [`upsert`] edits the one
+//! posted for the same PR and prover last time, whose id is kept in the
+//! store. If that comment is gone (deleted by a maintainer, or the update
+//! is refused) a new one is posted and remembered instead.
+//!
+//! Inline review comments are not sticky: they are anchored to a line of
+//! one commit's diff. `[bot] sticky_comments = false` restores one comment
+//! per run.
+
+use chrono::Utc;
+
+use crate::adapters::{CommentId, PlatformAdapter, PrId, RepoId};
+use crate::dispatcher::ProverKind;
+use crate::error::Result;
+use crate::store::models::{PrCommentRecord, Repository};
+use crate::store::Store;
+
+/// Post `body` as `prover`'s result comment on PR `pr_number`, editing
+/// the previous one if there is one.
+pub async fn upsert(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    pr_number: u64,
+    prover: &ProverKind,
+    body: &str,
+) -> Result<CommentId> {
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let pr_id = PrId(pr_number.to_string());
+
+    // Store errors only cost the stickiness; the result is still posted.
+    // So does a platform whose comments can't be edited.
+    let previous = match store.get_pr_comment(repo.id, pr_number, prover.clone()).await {
+        Ok(_) if !adapter.capabilities().comment_updates => None,
+        Ok(previous) => previous,
+        Err(e) => {
+            tracing::debug!("PR comment lookup for {} #{} failed: {}", repo.full_name(), pr_number, e);
+            None
+        }
+    };
+    if let Some(previous) = previous {
+        let id = CommentId(previous.comment_id);
+        match adapter.update_comment(&repo_id, pr_id.clone(), &id, body).await {
+            Ok(()) => {
+                remember(store, repo, pr_number, prover, &id).await;
+                return Ok(id);
+            }
+            Err(e) => tracing::info!(
+                "Result comment {} on {} #{} not updated ({}); posting a new one",
+                id.0,
+                repo.full_name(),
+                pr_number,
+                e
+            ),
+        }
+    }
+
+    let id = adapter.create_comment(&repo_id, pr_id, body).await?;
+    remember(store, repo, pr_number, prover, &id).await;
+    Ok(id)
+}
+
+async fn remember(store: &dyn Store, repo: &Repository, pr_number: u64, prover: &ProverKind, id: &CommentId) {
+    let record = PrCommentRecord {
+        repo_id: repo.id,
+        pr_number,
+        prover: prover.clone(),
+        comment_id: id.0.clone(),
+        updated_at: Utc::now(),
+    };
+    if let Err(e) = store.upsert_pr_comment(&record).await {
+        tracing::warn!("PR comment id for {} #{} not stored: {}", repo.full_name(), pr_number, e);
+    }
+}
@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Per-file and per-theorem results
+//!
+//! A job's result lists which files passed. [`ProofItem`]s break it down
+//! further for dashboards: one item per checked file, with its verdict and
+//! time when it was measured, and one per theorem the prover output names:
+//!
+//! - **Coq `-time`** (`[provers.coq] flags = ["-time"]`): every theorem,
+//!   timed as [`crate::perf::budget`] charges it — verified when its proof
+//!   ends in `Qed`/`Defined`, admitted on `Admitted`, failed on `Abort` or
+//!   when the output stops inside it.
+//! - **Lean**: per file only, from `lake build` ([`crate::executor::lake`]):
+//!   the module's build time, and admitted when a declaration uses `sorry`.
+//! - **Metamath**: every `$p` statement, failed when `verify proof *`
+//!   reports an error on it ([`crate::executor::metamath`]).
+//! - **JSON lines**, for wrapper scripts around any prover:
+//!   `{"theorem": "foo", "status": "failed", "duration_ms": 12, "file": "A.lean", "line": 3}`.
+//!   `status` defaults to `verified`; the rest is optional.
+//!
+//! A job's items are stored in `proof_items`, replaced when it re-runs,
+//! and served as `ProofJob.items` over GraphQL.
+
+use serde::{Deserialize, Serialize};
+
+use crate::executor::lake::LakeOutcome;
+use crate::executor::metamath::MetamathOutcome;
+use crate::perf::budget::{parse_coq_time_line, COQ_THEOREM_KEYWORDS};
+
+/// How a file or theorem fared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemStatus {
+    Verified,
+    Failed,
+    /// Accepted with a placeholder (`Admitted`, `sorry`) standing in for
+    /// (part of) the proof.
+    Admitted,
+}
+
+impl ItemStatus {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "verified" | "ok" | "passed" => Some(Self::Verified),
+            "failed" | "error" => Some(Self::Failed),
+            "admitted" | "sorry" => Some(Self::Admitted),
+            _ => None,
+        }
+    }
+}
+
+/// One file or theorem of a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofItem {
+    /// Repo-relative path.
+    pub file_path: String,
+    /// `None` for the file itself.
+    pub theorem: Option<String>,
+    /// 1-based line of the theorem, when known.
+    pub line: Option<u32>,
+    pub status: ItemStatus,
+    pub duration_ms: Option<i64>,
+}
+
+impl ProofItem {
+    /// The item for a whole file checked with `verified`: admitted when
+    /// one of its `theorems` is.
+    pub fn file(file_path: &str, verified: bool, theorems: &[ProofItem], duration_ms: Option<i64>) -> Self {
+        let status = if !verified {
+            ItemStatus::Failed
+        } else if theorems.iter().any(|t| t.status == ItemStatus::Admitted) {
+            ItemStatus::Admitted
+        } else {
+            ItemStatus::Verified
+        };
+        Self {
+            file_path: file_path.to_string(),
+            theorem: None,
+            line: None,
+            status,
+            duration_ms,
+        }
+    }
+
+    fn theorem(file_path: &str, name: String, line: Option<u32>, status: ItemStatus, duration_ms: Option<i64>) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            theorem: Some(name),
+            line,
+            status,
+            duration_ms,
+        }
+    }
+}
+
+/// Untimed items for files checked together, e.g. an Isabelle session.
+pub fn files(verified: &[String], failed: &[String]) -> Vec<ProofItem> {
+    let verdicts = verified.iter().map(|f| (f, true)).chain(failed.iter().map(|f| (f, false)));
+    verdicts.map(|(file, ok)| ProofItem::file(file, ok, &[], None)).collect()
+}
+
+/// The files of a `lake build`.
+pub fn lake(outcome: &LakeOutcome) -> Vec<ProofItem> {
+    let mut items = files(&outcome.verified, &outcome.failed);
+    for item in &mut items {
+        item.duration_ms = outcome.durations_ms.get(&item.file_path).copied();
+        if item.status == ItemStatus::Verified && outcome.sorry.contains(&item.file_path) {
+            item.status = ItemStatus::Admitted;
+        }
+    }
+    items
+}
+
+/// The files and `$p` statements of a Metamath database.
+pub fn metamath(outcome: &MetamathOutcome) -> Vec<ProofItem> {
+    let mut items = files(&outcome.verified, &outcome.failed);
+    items.extend(outcome.theorems.iter().map(|t| {
+        let status = if t.verified { ItemStatus::Verified } else { ItemStatus::Failed };
+        ProofItem::theorem(&t.file, t.label.clone(), Some(t.line), status, None)
+    }));
+    items
+}
+
+/// The theorems named in the output of checking `file` (with contents
+/// `source`) alone: Coq `-time` lines and JSON lines.
+pub fn theorems(file: &str, source: &str, output: &str) -> Vec<ProofItem> {
+    let mut items = Vec::new();
+    // Open Coq theorem: name, line, accumulated ms.
+    let mut open: Option<(String, Option<u32>, f64)> = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if line.starts_with('{') {
+            items.extend(parse_json_item(file, line));
+            continue;
+        }
+
+        let Some((sentence, secs)) = parse_coq_time_line(line) else {
+            continue;
+        };
+        let mut words = sentence.split('~').filter(|w| !w.is_empty());
+        let head = words.next().unwrap_or("");
+
+        if COQ_THEOREM_KEYWORDS.contains(&head) {
+            if let Some(name) = words.next() {
+                let name = name.trim_end_matches(':').to_string();
+                open = Some((name, coq_line(source, line), secs * 1000.0));
+            }
+        } else if let Some((_, _, ref mut acc)) = open {
+            *acc += secs * 1000.0;
+            let status = match head.trim_end_matches('.') {
+                "Qed" | "Defined" => ItemStatus::Verified,
+                "Admitted" => ItemStatus::Admitted,
+                "Abort" => ItemStatus::Failed,
+                _ => continue,
+            };
+            let (name, at, ms) = open.take().unwrap();
+            items.push(ProofItem::theorem(file, name, at, status, Some(ms.round() as i64)));
+        }
+    }
+
+    // The output stopped inside a proof: Coq gave up on it.
+    if let Some((name, at, ms)) = open {
+        items.push(ProofItem::theorem(file, name, at, ItemStatus::Failed, Some(ms.round() as i64)));
+    }
+    items
+}
+
+/// Line of `source` a `Chars A - B [...]` timing line starts at.
+fn coq_line(source: &str, line: &str) -> Option<u32> {
+    let offset = line.strip_prefix("Chars ")?.split_whitespace().next()?.parse::<usize>().ok()?;
+    let before = source.get(..offset)?;
+    Some(before.matches('\n').count() as u32 + 1)
+}
+
+fn parse_json_item(file: &str, line: &str) -> Option<ProofItem> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let status = match value.get("status").and_then(|s| s.as_str()) {
+        Some(status) => ItemStatus::parse(status)?,
+        None => ItemStatus::Verified,
+    };
+    Some(ProofItem {
+        file_path: value.get("file").and_then(|f| f.as_str()).unwrap_or(file).to_string(),
+        theorem: Some(value.get("theorem")?.as_str()?.to_string()),
+        line: value.get("line").and_then(|l| l.as_u64()).map(|l| l as u32),
+        status,
+        duration_ms: value.get("duration_ms").and_then(|d| d.as_i64()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coq_and_json_lines_become_theorems() {
+        let source = "Lemma a : True.\nProof. exact I. Qed.\n\nLemma b : False.\nAdmitted.\nLemma c : 1 = 2.\n";
+        let output = "\
+Chars 0 - 15 [Lemma~a~:~True.] 0.001 secs (0.u,0.s)
+Chars 23 - 31 [exact~I.] 0.01 secs (0.u,0.s)
+Chars 32 - 36 [Qed.] 0.2 secs (0.u,0.s)
+Chars 38 - 54 [Lemma~b~:~False.] 0. secs (0.u,0.s)
+Chars 55 - 64 [Admitted.] 0. secs (0.u,0.s)
+Chars 65 - 81 [Lemma~c~:~1~=~2.] 0. secs (0.u,0.s)
+File \"./A.v\", line 7, characters 0-11:
+Error: Unable to unify
+{\"theorem\": \"extra\", \"status\": \"failed\", \"duration_ms\": 5}";
+        let items = theorems("A.v", source, output);
+        let summary: Vec<(&str, Option<u32>, ItemStatus)> = items
+            .iter()
+            .map(|i| (i.theorem.as_deref().unwrap(), i.line, i.status))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a", Some(1), ItemStatus::Verified),
+                ("b", Some(4), ItemStatus::Admitted),
+                ("extra", None, ItemStatus::Failed),
+                ("c", Some(6), ItemStatus::Failed),
+            ]
+        );
+        assert_eq!(items[0].duration_ms, Some(211));
+        assert_eq!(ProofItem::file("A.v", true, &items, None).status, ItemStatus::Admitted);
+        assert_eq!(ProofItem::file("A.v", false, &[], None).status, ItemStatus::Failed);
+    }
+}
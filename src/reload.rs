@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Configuration reload on SIGHUP
+//!
+//! `serve` keeps its [`Config`] in a [`SharedConfig`] that every handler
+//! and background task reads from. On SIGHUP, and every `[secrets]
+//! refresh_interval_secs` when that is set, the file is read again, its
+//! secret references resolved (see [`crate::config::secrets`]) and, if
+//! that succeeds, swapped in as a whole; a request or job already running
+//! finishes with the configuration it started with. A missing or broken
+//! file, or a secret that cannot be fetched, leaves the running
+//! configuration in place. Environment and
+//! `--set` overrides are applied again on top, as at startup.
+//!
+//! Settings read per request, job or sweep apply at once: webhook
+//! secrets, platform tokens (adapters are built per use), `[auth]`,
+//! `[bot]` flags, `[commands]`, `[executor]`, `[attestation]`,
+//! `[[grace_periods]]`, `[calendar]` time zones and blackout windows, and
+//! the stale, re-verification, federation and export policies (including
+//! `[[analytics.sinks]]`). `[echidna.auth]` credentials are picked up by
+//! the next `reload_interval_secs` check. The
+//! scheduler's `max_concurrent` and `queue_size` are applied to the live
+//! queue with [`JobScheduler::resize`], `exclusive` with
+//! [`JobScheduler::set_exclusive`], and `[slo]` with
+//! [`JobScheduler::set_slo`]. Everything built once at startup
+//! (the database pool, the ECHIDNA client, listeners, sweeper intervals,
+//! ...) is listed by [`restart_required`] and logged when a reload
+//! changes it.
+
+use std::fmt::Debug;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::JobScheduler;
+use crate::shutdown::ShutdownSignal;
+
+/// Settings that differ between `old` and `new` but only take effect
+/// after a restart.
+pub fn restart_required(old: &Config, new: &Config) -> Vec<&'static str> {
+    fn changed<T: Debug>(old: &T, new: &T) -> bool {
+        format!("{:?}", old) != format!("{:?}", new)
+    }
+
+    let checks = [
+        ("[server] host", changed(&old.server.host, &new.server.host)),
+        ("[server] port", changed(&old.server.port, &new.server.port)),
+        (
+            "[server] rate_limit_rpm",
+            changed(&old.server.rate_limit_rpm, &new.server.rate_limit_rpm),
+        ),
+        (
+            "[server] webhook_max_attempts",
+            changed(&old.server.webhook_max_attempts, &new.server.webhook_max_attempts),
+        ),
+        ("[server.tls]", changed(&old.server.tls, &new.server.tls)),
+        ("[database]", changed(&old.database, &new.database)),
+        ("[echidna]", changed(&old.echidna, &new.echidna)),
+        (
+            "[scheduler] log_buffer_lines",
+            changed(&old.scheduler.log_buffer_lines, &new.scheduler.log_buffer_lines),
+        ),
+        (
+            "[scheduler] log_retain_jobs",
+            changed(&old.scheduler.log_retain_jobs, &new.scheduler.log_retain_jobs),
+        ),
+        (
+            "[scheduler] stale_sweep_interval_secs",
+            changed(
+                &old.scheduler.stale_sweep_interval_secs,
+                &new.scheduler.stale_sweep_interval_secs,
+            ),
+        ),
+        (
+            "[scheduler] reverify_interval_secs",
+            changed(
+                &old.scheduler.reverify_interval_secs,
+                &new.scheduler.reverify_interval_secs,
+            ),
+        ),
+        (
+            "[calendar] check_interval_secs",
+            changed(&old.calendar.check_interval_secs, &new.calendar.check_interval_secs),
+        ),
+        (
+            "[scheduler] freshness_interval_secs",
+            changed(
+                &old.scheduler.freshness_interval_secs,
+                &new.scheduler.freshness_interval_secs,
+            ),
+        ),
+        ("[bot] mode", changed(&old.bot.mode, &new.bot.mode)),
+        (
+            "[federation] sync_interval_secs",
+            changed(&old.federation.sync_interval_secs, &new.federation.sync_interval_secs),
+        ),
+        (
+            "[corpus.export] enabled / interval_secs",
+            changed(
+                &(old.corpus.export.enabled, old.corpus.export.interval_secs),
+                &(new.corpus.export.enabled, new.corpus.export.interval_secs),
+            ),
+        ),
+        (
+            "[analytics] interval_secs, or the first / last sink",
+            changed(
+                &(old.analytics.sinks.is_empty(), old.analytics.interval_secs),
+                &(new.analytics.sinks.is_empty(), new.analytics.interval_secs),
+            ),
+        ),
+        (
+            "[events] enabled / transport / url / token",
+            changed(
+                &(old.events.enabled, old.events.transport, &old.events.url, &old.events.token),
+                &(new.events.enabled, new.events.transport, &new.events.url, &new.events.token),
+            ),
+        ),
+        (
+            "[echidna.auth] reload_interval_secs",
+            changed(
+                &old.echidna.auth.reload_interval_secs,
+                &new.echidna.auth.reload_interval_secs,
+            ),
+        ),
+        ("[tenancy] master_key", changed(&old.tenancy.master_key, &new.tenancy.master_key)),
+        ("[telemetry]", changed(&old.telemetry, &new.telemetry)),
+        ("[health]", changed(&old.health, &new.health)),
+        ("[fleet]", changed(&old.fleet, &new.fleet)),
+        ("[lifecycle]", changed(&old.lifecycle, &new.lifecycle)),
+        ("[observability]", changed(&old.observability, &new.observability)),
+        (
+            "[secrets] refresh_interval_secs",
+            changed(&old.secrets.refresh_interval_secs, &new.secrets.refresh_interval_secs),
+        ),
+    ];
+    checks
+        .into_iter()
+        .filter_map(|(setting, changed)| changed.then_some(setting))
+        .collect()
+}
+
+/// Re-reads the configuration file into the daemon's [`SharedConfig`].
+pub struct ConfigReloader {
+    path: String,
+    overrides: Vec<String>,
+    config: SharedConfig,
+    scheduler: Arc<JobScheduler>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: impl Into<String>, config: SharedConfig, scheduler: Arc<JobScheduler>) -> Self {
+        Self {
+            path: path.into(),
+            overrides: Vec::new(),
+            config,
+            scheduler,
+        }
+    }
+
+    /// `--set` overrides to apply on top of every reload, as at startup.
+    pub fn with_overrides(mut self, overrides: Vec<String>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Load the file, resolve its secret references, apply the scheduler
+    /// limits and swap the new configuration in. Returns the changed
+    /// settings that still need a restart ([`restart_required`]).
+    pub async fn reload(&self) -> Result<Vec<&'static str>> {
+        // `Config::load` falls back to the defaults for a missing file,
+        // which is right at startup but would silently reset a running
+        // daemon.
+        if !Path::new(&self.path).exists() {
+            return Err(Error::Config(format!("{} not found", self.path)));
+        }
+        let mut new = Config::load_layered(&self.path, &self.overrides)?;
+        new.resolve_secrets().await?;
+        new.calendar.validate()?;
+        new.analytics.validate()?;
+        new.events.validate()?;
+        new.tenancy.validate()?;
+        new.echidna.auth.validate()?;
+        let pending = restart_required(&self.config.load(), &new);
+        self.scheduler.resize(new.scheduler.max_concurrent, new.scheduler.queue_size);
+        self.scheduler.set_slo(new.slo.clone());
+        self.scheduler.set_exclusive(new.scheduler.exclusive);
+        self.config.store(Arc::new(new));
+        Ok(pending)
+    }
+}
+
+/// Reload on every SIGHUP, and every `[secrets] refresh_interval_secs`
+/// when set, until shutdown. SIGHUP is not available off Unix.
+pub async fn run_config_reloader(reloader: ConfigReloader, shutdown: ShutdownSignal) {
+    #[cfg(unix)]
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            tracing::warn!("Failed to install SIGHUP handler: {}; config reload on SIGHUP is disabled", e);
+            None
+        }
+    };
+    #[cfg(not(unix))]
+    tracing::debug!("Config reload on SIGHUP is not available on this platform");
+
+    let refresh_secs = reloader.config.load().secrets.refresh_interval_secs;
+    let mut refresh = tokio::time::interval(Duration::from_secs(refresh_secs.max(1)));
+    refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    refresh.tick().await;
+
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        #[cfg(unix)]
+        let hangup_fut = async {
+            match hangup.as_mut() {
+                Some(hangup) => hangup.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let hangup_fut = std::future::pending::<Option<()>>();
+
+        let timed = tokio::select! {
+            received = hangup_fut => {
+                if received.is_none() {
+                    return;
+                }
+                false
+            }
+            _ = refresh.tick(), if refresh_secs > 0 => true,
+            _ = &mut shutdown_fut => {
+                tracing::info!("Config reloader observed shutdown signal — stopping");
+                return;
+            }
+        };
+        if timed {
+            tracing::debug!("Refreshing secrets — reloading {}", reloader.path);
+        } else {
+            tracing::info!("SIGHUP received — reloading {}", reloader.path);
+        }
+        match reloader.reload().await {
+            Ok(pending) if pending.is_empty() && timed => tracing::debug!("Configuration reloaded"),
+            Ok(pending) if pending.is_empty() => tracing::info!("Configuration reloaded"),
+            Ok(pending) => tracing::warn!(
+                "Configuration reloaded; restart to apply the changes to {}",
+                pending.join(", ")
+            ),
+            Err(e) => tracing::error!(
+                "Configuration reload failed, keeping the running configuration: {}",
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_swap::ArcSwap;
+
+    #[tokio::test]
+    async fn reload_swaps_config_and_resizes_the_scheduler() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("echidnabot.toml");
+        std::fs::write(&path, "[scheduler]\nmax_concurrent = 2\nqueue_size = 10\n").unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let config: SharedConfig = Arc::new(ArcSwap::from_pointee(Config::load(&path).unwrap()));
+        let scheduler = Arc::new(JobScheduler::new(2, 10));
+        let reloader = ConfigReloader::new(path.clone(), config.clone(), scheduler.clone());
+
+        std::fs::write(
+            &path,
+            "[scheduler]\nmax_concurrent = 4\nqueue_size = 50\nlog_buffer_lines = 10\n\n\
+             [github]\nwebhook_secret = \"rotated\"\n",
+        )
+        .unwrap();
+        let pending = reloader.reload().await.unwrap();
+        assert_eq!(pending, vec!["[scheduler] log_buffer_lines"]);
+        let current = config.load_full();
+        assert_eq!(current.scheduler.max_concurrent, 4);
+        assert_eq!(
+            current.github.as_ref().and_then(|g| g.webhook_secret.as_deref()),
+            Some("rotated")
+        );
+        let stats = scheduler.stats().await;
+        assert_eq!((stats.max_concurrent, stats.max_queue_size), (4, 50));
+
+        // A broken or missing file keeps what is running.
+        std::fs::write(&path, "[scheduler\nmax_concurrent = 1\n").unwrap();
+        assert!(reloader.reload().await.is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert!(reloader.reload().await.is_err());
+        assert_eq!(config.load().scheduler.max_concurrent, 4);
+        assert_eq!(scheduler.stats().await.max_concurrent, 4);
+    }
+}
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Reporting a result where the platform can show it
+//!
+//! Platforms differ in what they offer (see [`Capabilities`]), so a
+//! finished job's check is published through the best mechanism its
+//! adapter has:
+//!
+//! | Channel           | Shows                                  | Platforms                 |
+//! |-------------------|----------------------------------------|---------------------------|
+//! | check run         | full Markdown summary, action buttons  | GitHub                    |
+//! | commit status     | state and the summary's first line     | all (with a token)        |
+//! | PR comment        | the summary, on each of the job's PRs  | all (with a token)        |
+//!
+//! A channel that fails falls through to the next. When none is left the
+//! result is unreported and [`publish`] says so with an error, rather
+//! than the report being dropped silently.
+
+use crate::adapters::{
+    Capabilities, CheckConclusion, CheckRun, CheckStatus, PlatformAdapter, PrId, RepoId,
+};
+use crate::dispatcher::ProverKind;
+use crate::error::{Error, Result};
+
+/// GitHub's limit on commit-status descriptions; the strictest.
+pub const STATUS_DESCRIPTION_MAX: usize = 140;
+
+/// Name of `prover`'s check run (and commit-status context).
+pub fn check_name(prover: &ProverKind) -> String {
+    format!("echidnabot/{:?}", prover)
+}
+
+/// A way of showing a check's outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    CheckRun,
+    CommitStatus,
+    Comment,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::CheckRun => "check run",
+            Channel::CommitStatus => "commit status",
+            Channel::Comment => "PR comment",
+        }
+    }
+}
+
+/// The channels `capabilities` allow, best first. Comments need a PR to
+/// go to (`has_prs`).
+pub fn channels(capabilities: Capabilities, has_prs: bool) -> Vec<Channel> {
+    [
+        (Channel::CheckRun, capabilities.check_runs),
+        (Channel::CommitStatus, capabilities.commit_statuses),
+        (Channel::Comment, capabilities.comments && has_prs),
+    ]
+    .into_iter()
+    .filter_map(|(channel, available)| available.then_some(channel))
+    .collect()
+}
+
+/// A one-line commit-status description from a Markdown `summary`: its
+/// first line with text, unformatted, cut to [`STATUS_DESCRIPTION_MAX`].
+pub fn status_description(summary: &str) -> String {
+    let line = summary
+        .lines()
+        .map(|line| {
+            line.trim_start_matches(['#', '>', ' '])
+                .replace("**", "")
+                .replace('`', "")
+                .trim()
+                .to_string()
+        })
+        .find(|line| line.chars().any(char::is_alphanumeric))
+        .unwrap_or_default();
+    if line.chars().count() <= STATUS_DESCRIPTION_MAX {
+        return line;
+    }
+    let mut cut: String = line.chars().take(STATUS_DESCRIPTION_MAX - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn outcome_word(status: &CheckStatus) -> &'static str {
+    match status {
+        CheckStatus::Queued => "queued",
+        CheckStatus::InProgress => "running",
+        CheckStatus::Completed { conclusion, .. } => match conclusion {
+            CheckConclusion::Success => "✅ passed",
+            CheckConclusion::Failure => "❌ failed",
+            CheckConclusion::Neutral => "➖ neutral",
+            CheckConclusion::Cancelled => "cancelled",
+            CheckConclusion::Skipped => "skipped",
+            CheckConclusion::TimedOut => "⏱️ timed out",
+            CheckConclusion::ActionRequired => "action required",
+        },
+    }
+}
+
+async fn send(
+    adapter: &dyn PlatformAdapter,
+    repo: &RepoId,
+    check: &CheckRun,
+    channel: Channel,
+    prs: &[u64],
+) -> Result<()> {
+    match channel {
+        Channel::CheckRun => adapter.create_check_run(repo, check.clone()).await.map(|_| ()),
+        Channel::CommitStatus => {
+            let mut status = check.clone();
+            if let CheckStatus::Completed { summary, .. } = &mut status.status {
+                *summary = status_description(summary);
+            }
+            adapter.create_commit_status(repo, status).await
+        }
+        Channel::Comment => {
+            let summary = match &check.status {
+                CheckStatus::Completed { summary, .. } => summary.as_str(),
+                _ => "",
+            };
+            let body = format!(
+                "**{}** {} for `{:.8}`\n\n{}",
+                check.name,
+                outcome_word(&check.status),
+                check.head_sha,
+                summary
+            );
+            for pr in prs {
+                adapter.create_comment(repo, PrId(pr.to_string()), body.trim_end()).await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Publish `check` through the best channel `adapter` supports that
+/// works, falling back to a comment on `prs`. Returns the channel used.
+pub async fn publish(
+    adapter: &dyn PlatformAdapter,
+    repo: &RepoId,
+    check: CheckRun,
+    prs: &[u64],
+) -> Result<Channel> {
+    let mut last_error = None;
+    for channel in channels(adapter.capabilities(), !prs.is_empty()) {
+        match send(adapter, repo, &check, channel, prs).await {
+            Ok(()) => return Ok(channel),
+            Err(e) => {
+                tracing::info!(
+                    "Reporting {} on {} by {} failed ({}); trying the next channel",
+                    check.name,
+                    repo.full_name(),
+                    channel.as_str(),
+                    e
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        Error::Unsupported(format!(
+            "{} can't show {}: no check runs, commit statuses or PR to comment on",
+            repo.full_name(),
+            check.name
+        ))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::adapters::github::GitHubAdapter;
+    use crate::adapters::gitlab::GitLabAdapter;
+    use crate::adapters::http::Method;
+    use crate::adapters::testing::MockApi;
+    use crate::adapters::Platform;
+
+    fn check(summary: &str) -> CheckRun {
+        CheckRun {
+            name: "echidnabot/coq".into(),
+            head_sha: "abc123def456".into(),
+            status: CheckStatus::Completed {
+                conclusion: CheckConclusion::Failure,
+                summary: summary.into(),
+            },
+            details_url: None,
+            external_id: None,
+            actions: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn channels_follow_capabilities() {
+        let all = Capabilities {
+            check_runs: true,
+            commit_statuses: true,
+            comments: true,
+            ..Capabilities::default()
+        };
+        assert_eq!(channels(all, true), [Channel::CheckRun, Channel::CommitStatus, Channel::Comment]);
+        assert_eq!(channels(all, false), [Channel::CheckRun, Channel::CommitStatus]);
+        let comments_only = Capabilities { comments: true, ..Capabilities::default() };
+        assert_eq!(channels(comments_only, true), [Channel::Comment]);
+        assert!(channels(Capabilities::default(), true).is_empty());
+    }
+
+    #[test]
+    fn status_descriptions_are_one_short_plain_line() {
+        let summary = "## ❌ **2 proofs failed** in `theories/`\n\nDetails follow.";
+        assert_eq!(status_description(summary), "❌ 2 proofs failed in theories/");
+        let long = "x".repeat(300);
+        assert_eq!(status_description(&long).chars().count(), STATUS_DESCRIPTION_MAX);
+    }
+
+    #[tokio::test]
+    async fn failing_channels_fall_through_to_a_comment() {
+        let api = Arc::new(MockApi::new());
+        api.respond(Method::Post, "/repos/o/r/check-runs", 403, "Resource not accessible");
+        api.respond(Method::Post, "/repos/o/r/statuses/abc123def456", 403, "Resource not accessible");
+        api.respond(Method::Post, "/repos/o/r/issues/7/comments", 201, r#"{"id": 1}"#);
+        let github = GitHubAdapter::new("t").unwrap().with_http(api.clone());
+        let repo = RepoId::new(Platform::GitHub, "o", "r");
+
+        let channel = publish(&github, &repo, check("**1 proof failed**"), &[7]).await.unwrap();
+        assert_eq!(channel, Channel::Comment);
+        let statuses = api.requests_to(Method::Post, "/repos/o/r/statuses/abc123def456");
+        assert_eq!(statuses[0].body.as_ref().unwrap()["description"], "1 proof failed");
+        let comment = &api.requests_to(Method::Post, "/repos/o/r/issues/7/comments")[0];
+        assert!(comment.body.as_ref().unwrap()["body"].as_str().unwrap().contains("❌ failed"));
+
+        // Without a PR there is nothing left to try.
+        assert!(publish(&github, &repo, check("x"), &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn nothing_available_is_an_error_not_a_no_op() {
+        let gitlab = GitLabAdapter::new(None).with_http(Arc::new(MockApi::new()));
+        let repo = RepoId::new(Platform::GitLab, "o", "r");
+        if gitlab.capabilities().commit_statuses {
+            return; // GITLAB_TOKEN is set in this environment
+        }
+        let err = publish(&gitlab, &repo, check("x"), &[3]).await.unwrap_err();
+        assert!(matches!(err, Error::Unsupported(_)), "{}", err);
+    }
+}
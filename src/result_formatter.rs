@@ -28,7 +28,7 @@ pub fn format_proof_result(
 }
 
 /// Format a tactic suggestion for display
-fn format_tactic_suggestion(suggestion: &TacticSuggestion) -> String {
+pub fn format_tactic_suggestion(suggestion: &TacticSuggestion) -> String {
     let confidence_pct = (suggestion.confidence * 100.0) as u8;
 
     if let Some(ref explanation) = suggestion.explanation {
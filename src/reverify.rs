@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Scheduled full re-verification
+//!
+//! Proofs can rot without a push: a prover upgrade on the ECHIDNA side or
+//! a moved remote dependency breaks a default branch nobody touched. A
+//! repository with a `reverify_cron` setting gets its default branch
+//! fully verified (every enabled prover, every proof file) whenever the
+//! expression fires. [`run_reverify_scheduler`] checks every
+//! `[scheduler] reverify_interval_secs`.
+//!
+//! Expressions are standard five-field cron (`min hour dom month dow`) in
+//! the repository's time zone (see [`crate::calendar`]; UTC unless set);
+//! six- and seven-field forms with seconds and years are accepted too.
+//! `0 3 * * *` is nightly at 03:00, `0 4 * * SUN` weekly. Fires missed
+//! while echidnabot was down collapse into one run, and the first check
+//! after a schedule is set only records a baseline, so setting one never
+//! triggers a run by itself. A run that comes due in a blackout window
+//! waits for the window to end.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use cron::Schedule;
+
+use crate::adapters::{build_adapter, RepoId};
+use crate::config::{Config, SharedConfig};
+use crate::error::{Error, Result};
+use crate::scheduler::{JobPriority, JobScheduler, ProofJob};
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{ProofJobRecord, Repository};
+use crate::store::Store;
+
+/// Parse a repository's `reverify_cron` expression.
+pub fn parse_schedule(expr: &str) -> Result<Schedule> {
+    let expr = expr.trim();
+    let full = match expr.split_whitespace().count() {
+        5 => format!("0 {}", expr),
+        6 | 7 => expr.to_string(),
+        _ => {
+            return Err(Error::InvalidInput(format!(
+                "cron expression '{}' must have 5 fields (min hour dom month dow)",
+                expr
+            )))
+        }
+    };
+    Schedule::from_str(&full)
+        .map_err(|e| Error::InvalidInput(format!("invalid cron expression '{}': {}", expr, e)))
+}
+
+/// Whether `schedule` has fired in `(last, now]`.
+pub fn is_due(schedule: &Schedule, last: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    is_due_in(schedule, &Utc, last, now)
+}
+
+/// Whether `schedule`, read in time zone `tz`, has fired in `(last, now]`.
+pub fn is_due_in<Z: TimeZone>(
+    schedule: &Schedule,
+    tz: &Z,
+    last: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> bool {
+    schedule
+        .after(&last.with_timezone(tz))
+        .next()
+        .is_some_and(|fire| fire.with_timezone(&Utc) <= now)
+}
+
+/// Check the schedules until shutdown.
+pub async fn run_reverify_scheduler(
+    store: Arc<dyn Store>,
+    scheduler: Arc<JobScheduler>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Re-verification scheduler observed shutdown signal — stopping");
+                return;
+            }
+        }
+        match sweep(store.as_ref(), &scheduler, &config.load_full(), Utc::now()).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Scheduled re-verification of {} repo(s)", n),
+            Err(e) => tracing::warn!("Re-verification sweep failed: {}", e),
+        }
+    }
+}
+
+/// Enqueue every enabled repository whose schedule has fired. Returns
+/// how many repositories were enqueued.
+pub async fn sweep(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    config: &Config,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let mut enqueued = 0;
+    for mut repo in store.list_repositories(None).await? {
+        let Some(expr) = repo.reverify_cron.clone().filter(|_| repo.enabled) else {
+            continue;
+        };
+        let schedule = match parse_schedule(&expr) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                tracing::warn!("Skipping re-verification of {}: {}", repo.full_name(), e);
+                continue;
+            }
+        };
+        let tz = config.calendar.timezone_for(&repo);
+        match repo.last_reverify_at {
+            Some(last) if !is_due_in(&schedule, &tz, last, now) => continue,
+            Some(_) => {
+                // Left due, so it runs on the first sweep after the window.
+                if let Some(window) = config.calendar.blackout_for(&repo, now) {
+                    tracing::debug!(
+                        "Re-verification of {} waits for {} to end",
+                        repo.full_name(),
+                        window.label()
+                    );
+                    continue;
+                }
+                let branch = default_branch(&mut repo, config).await;
+                match enqueue(store, scheduler, &repo, branch).await {
+                    Ok(0) => {}
+                    Ok(_) => enqueued += 1,
+                    Err(e) => {
+                        tracing::warn!("Scheduled re-verification of {} failed: {}", repo.full_name(), e);
+                        continue;
+                    }
+                }
+            }
+            None => {}
+        }
+        repo.last_reverify_at = Some(now);
+        store.update_repository(&repo).await?;
+    }
+    Ok(enqueued)
+}
+
+/// The repository's default branch, asking the platform (and
+/// remembering the answer) when no push has reported it yet.
+pub(crate) async fn default_branch(repo: &mut Repository, config: &Config) -> Option<String> {
+    if repo.default_branch.is_none() {
+        let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+        if let Ok(adapter) = build_adapter(config, repo.platform) {
+            repo.default_branch = adapter.get_default_branch(&repo_id).await.ok();
+        }
+    }
+    repo.default_branch.clone()
+}
+
+/// One full-verification job per enabled prover at the default branch
+/// head. Provers already queued for it are not queued twice.
+async fn enqueue(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    repo: &Repository,
+    branch: Option<String>,
+) -> Result<usize> {
+    let mut enqueued = 0;
+    for prover in &repo.enabled_provers {
+        let job = ProofJob::new(repo.id, "HEAD".to_string(), prover.clone(), Vec::new())
+            .with_priority(JobPriority::Low)
+            .with_branch(branch.clone());
+        store.create_job(&ProofJobRecord::from(job.clone())).await?;
+        if scheduler.enqueue(job).await?.is_some() {
+            enqueued += 1;
+        }
+    }
+    Ok(enqueued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::config::BlackoutWindow;
+    use crate::dispatcher::ProverKind;
+    use crate::store::SqliteStore;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn five_field_expressions_fire_on_the_minute() {
+        let nightly = parse_schedule("0 3 * * *").unwrap();
+        let last = at("2026-10-16T03:00:00Z");
+        assert!(!is_due(&nightly, last, at("2026-10-17T02:59:59Z")));
+        assert!(is_due(&nightly, last, at("2026-10-17T03:00:00Z")));
+        // A week of downtime is still one run.
+        assert!(is_due(&nightly, last, at("2026-10-24T12:00:00Z")));
+
+        let weekly = parse_schedule("0 4 * * SUN").unwrap();
+        // 2026-10-17 is a Saturday.
+        assert!(!is_due(&weekly, at("2026-10-17T00:00:00Z"), at("2026-10-17T23:59:00Z")));
+        assert!(is_due(&weekly, at("2026-10-17T00:00:00Z"), at("2026-10-18T04:00:00Z")));
+    }
+
+    #[test]
+    fn schedules_fire_in_the_given_time_zone() {
+        let nightly = parse_schedule("0 3 * * *").unwrap();
+        let tz: chrono_tz::Tz = "America/New_York".parse().unwrap();
+        // 03:00 in New York (EDT) is 07:00 UTC.
+        let last = at("2026-10-16T07:00:00Z");
+        assert!(!is_due_in(&nightly, &tz, last, at("2026-10-17T03:00:00Z")));
+        assert!(is_due_in(&nightly, &tz, last, at("2026-10-17T07:00:00Z")));
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        assert!(parse_schedule("nightly").is_err());
+        assert!(parse_schedule("0 3 * *").is_err());
+        assert!(parse_schedule("0 25 * * *").is_err());
+        assert!(parse_schedule("0 0 3 * * * 2027").is_ok());
+    }
+
+    #[tokio::test]
+    async fn sweep_records_baseline_then_enqueues_when_due() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-reverify-test-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteStore::new(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let scheduler = JobScheduler::new(4, 16);
+        let config = Config::default();
+
+        let mut repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        repo.enabled_provers = vec![ProverKind::new("coq"), ProverKind::new("lean")];
+        repo.default_branch = Some("main".into());
+        repo.reverify_cron = Some("0 3 * * *".into());
+        store.create_repository(&repo).await.unwrap();
+        let mut unscheduled = Repository::new(Platform::GitHub, "o".into(), "other".into());
+        unscheduled.default_branch = Some("main".into());
+        store.create_repository(&unscheduled).await.unwrap();
+
+        let first = at("2026-10-17T01:00:00Z");
+        assert_eq!(sweep(&store, &scheduler, &config, first).await.unwrap(), 0);
+        assert_eq!(scheduler.queue_depth(), 0, "first sight only sets the baseline");
+        let stored = store.get_repository(repo.id).await.unwrap().unwrap();
+        assert_eq!(stored.last_reverify_at, Some(first));
+
+        let fired = at("2026-10-17T03:00:30Z");
+        assert_eq!(sweep(&store, &scheduler, &config, fired).await.unwrap(), 1);
+        assert_eq!(scheduler.queue_depth(), 2);
+        let jobs = store.list_jobs_for_repo(repo.id, 10).await.unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|j| j.commit_sha == "HEAD" && j.payload.file_paths.is_empty()));
+        assert!(jobs.iter().all(|j| j.branch.as_deref() == Some("main")));
+
+        // Not due again until tomorrow.
+        assert_eq!(
+            sweep(&store, &scheduler, &config, at("2026-10-17T12:00:00Z")).await.unwrap(),
+            0
+        );
+        assert!(store.list_jobs_for_repo(unscheduled.id, 10).await.unwrap().is_empty());
+
+        // Due tomorrow at 03:00 but in a night-time blackout: waits for it.
+        let scheduler = JobScheduler::new(4, 16);
+        let mut config = Config::default();
+        config.calendar.blackouts.push(BlackoutWindow {
+            start: Some("02:00".into()),
+            end: Some("05:00".into()),
+            ..Default::default()
+        });
+        let in_window = at("2026-10-18T03:00:30Z");
+        assert_eq!(sweep(&store, &scheduler, &config, in_window).await.unwrap(), 0);
+        let after = at("2026-10-18T05:00:00Z");
+        assert_eq!(sweep(&store, &scheduler, &config, after).await.unwrap(), 1);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -2,51 +2,290 @@
 // Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! Job queue management
-
-use std::collections::VecDeque;
+//!
+//! The queue is sharded to keep webhook handlers and the dispatch loop
+//! off a single lock:
+//!
+//! - one FIFO per [`JobPriority`], each behind its own short-lived
+//!   `std` mutex (never held across an `.await`);
+//! - duplicate detection through a concurrent set of queued
+//!   (repo, commit, prover) keys instead of a scan of the whole queue;
+//! - running jobs and remote claims in `DashMap`s;
+//! - queue length and active jobs in atomics, reserved with
+//!   compare-and-swap so capacity checks never race.
+//!
+//! With `[scheduler] exclusive`, a running job also holds a lock on its
+//! repository (or repository and prover) so jobs sharing a build cache run
+//! one after the other. A queued job whose lock is held keeps its place
+//! and is passed over; jobs of other repositories start as usual.
+//!
+//! Low-priority jobs of repositories in a blackout window (see
+//! `crate::calendar`) are passed over the same way until it ends.
+//!
+//! `benches/scheduler_bench.rs` measures enqueue / dequeue under
+//! contention.
+
+use std::collections::{HashSet, VecDeque};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::{Arc, Mutex as StdMutex};
+
+use arc_swap::ArcSwap;
+use dashmap::{DashMap, DashSet};
+use tokio::sync::{Mutex, Notify};
 use uuid::Uuid;
 
-use super::{JobId, ProofJob};
+use super::logs::JobLogs;
+use super::{JobId, JobPriority, JobResult, ProofJob};
+use crate::dispatcher::ProverKind;
+use crate::config::{ExclusiveScope, SloConfig};
 use crate::error::Result;
+use crate::fleet::workers::{WorkerRegistry, LOCAL_WORKER};
 use crate::fleet::FleetCoordinator;
 
+/// Priorities, highest first — the order queues are served in.
+const PRIORITIES: [JobPriority; 4] = [
+    JobPriority::Critical,
+    JobPriority::High,
+    JobPriority::Normal,
+    JobPriority::Low,
+];
+
+/// Identity used for duplicate detection.
+type QueueKey = (Uuid, String, ProverKind);
+
+fn queue_key(job: &ProofJob) -> QueueKey {
+    (job.repo_id, job.commit_sha.clone(), job.prover.clone())
+}
+
+/// What a running job holds under an [`ExclusiveScope`].
+type LockKey = (Uuid, Option<ProverKind>);
+
+fn lock_key(scope: ExclusiveScope, job: &ProofJob) -> Option<LockKey> {
+    match scope {
+        ExclusiveScope::None => None,
+        ExclusiveScope::Repository => Some((job.repo_id, None)),
+        ExclusiveScope::Prover => Some((job.repo_id, Some(job.prover.clone()))),
+    }
+}
+
+/// Jobs a new head commit makes obsolete: those of the same PR, or for a
+/// push, those of the same branch outside any PR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupersedeScope {
+    PullRequest(u64),
+    Branch(String),
+}
+
+impl SupersedeScope {
+    fn covers(&self, job: &ProofJob) -> bool {
+        match self {
+            SupersedeScope::PullRequest(number) => job.pr_number == Some(*number),
+            SupersedeScope::Branch(branch) => {
+                job.pr_number.is_none() && job.branch.as_deref() == Some(branch.as_str())
+            }
+        }
+    }
+}
+
 /// Job scheduler managing the verification queue
 pub struct JobScheduler {
-    /// Queue of pending jobs (priority-ordered)
-    queue: Arc<Mutex<VecDeque<ProofJob>>>,
+    /// Pending jobs, one FIFO per priority (indexed by `JobPriority as usize`)
+    queues: [StdMutex<VecDeque<ProofJob>>; 4],
+
+    /// Keys of queued jobs, for duplicate detection. Every entry names a
+    /// queued job; see [`Self::unindex`].
+    queued_keys: DashMap<QueueKey, JobId>,
+
+    /// Number of queued jobs
+    queued_count: AtomicUsize,
 
     /// Currently running jobs
-    running: Arc<Mutex<Vec<ProofJob>>>,
+    running: DashMap<JobId, ProofJob>,
 
     /// Number of active jobs
     active_count: AtomicUsize,
 
-    /// Maximum concurrent jobs
-    max_concurrent: usize,
+    /// Maximum concurrent jobs; changed by [`Self::resize`]
+    max_concurrent: AtomicUsize,
 
-    /// Maximum queue size
-    max_queue_size: usize,
+    /// Maximum queue size; changed by [`Self::resize`]
+    max_queue_size: AtomicUsize,
 
     /// Fleet coordinator for publishing findings
     fleet: Arc<Mutex<FleetCoordinator>>,
+
+    /// Workers jobs are routed to, by capability
+    workers: Arc<WorkerRegistry>,
+
+    /// Running jobs claimed by remote workers, by worker id
+    claims: DashMap<JobId, String>,
+
+    /// Results reported by remote workers, awaiting the dispatch loop
+    remote_results: StdMutex<Vec<(ProofJob, JobResult)>>,
+
+    /// Finished results to post to the platform again (see
+    /// `crate::freshness`), awaiting the dispatch loop
+    reports: StdMutex<Vec<(ProofJob, JobResult)>>,
+
+    /// Local running jobs cancelled as superseded, awaiting the dispatch loop
+    cancel_requested: DashSet<JobId>,
+
+    /// Wakes [`Self::cancelled`] waiters
+    cancel_notify: Notify,
+
+    /// Prover output of running and recently finished jobs
+    logs: Arc<JobLogs>,
+
+    /// Queue wait objectives (see `crate::slo`); changed by [`Self::set_slo`]
+    slo: ArcSwap<SloConfig>,
+
+    /// Which jobs exclude each other; changed by [`Self::set_exclusive`]
+    exclusive: ArcSwap<ExclusiveScope>,
+
+    /// Locks held by running jobs
+    locks: DashMap<LockKey, JobId>,
+
+    /// Repositories in a blackout window, whose low-priority jobs wait;
+    /// changed by [`Self::set_held_repos`]
+    held_repos: ArcSwap<HashSet<Uuid>>,
 }
 
 impl JobScheduler {
     /// Create a new job scheduler
     pub fn new(max_concurrent: usize, max_queue_size: usize) -> Self {
         Self {
-            queue: Arc::new(Mutex::new(VecDeque::new())),
-            running: Arc::new(Mutex::new(Vec::new())),
+            queues: Default::default(),
+            queued_keys: DashMap::new(),
+            queued_count: AtomicUsize::new(0),
+            running: DashMap::new(),
             active_count: AtomicUsize::new(0),
-            max_concurrent,
-            max_queue_size,
+            max_concurrent: AtomicUsize::new(max_concurrent),
+            max_queue_size: AtomicUsize::new(max_queue_size),
             fleet: Arc::new(Mutex::new(FleetCoordinator::new())),
+            workers: Arc::new(WorkerRegistry::default()),
+            claims: DashMap::new(),
+            remote_results: StdMutex::new(Vec::new()),
+            reports: StdMutex::new(Vec::new()),
+            cancel_requested: DashSet::new(),
+            cancel_notify: Notify::new(),
+            logs: Arc::new(JobLogs::default()),
+            slo: ArcSwap::from_pointee(SloConfig::default()),
+            exclusive: ArcSwap::from_pointee(ExclusiveScope::None),
+            locks: DashMap::new(),
+            held_repos: ArcSwap::from_pointee(HashSet::new()),
+        }
+    }
+
+    /// Route jobs through `workers` instead of an unrestricted local worker.
+    pub fn with_workers(mut self, workers: Arc<WorkerRegistry>) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    pub fn workers(&self) -> &Arc<WorkerRegistry> {
+        &self.workers
+    }
+
+    /// Buffer job logs in `logs` (sized from `[scheduler]`).
+    pub fn with_logs(mut self, logs: JobLogs) -> Self {
+        self.logs = Arc::new(logs);
+        self
+    }
+
+    pub fn logs(&self) -> &Arc<JobLogs> {
+        &self.logs
+    }
+
+    /// Queue wait objectives the queue is held to.
+    pub fn slo(&self) -> Arc<SloConfig> {
+        self.slo.load_full()
+    }
+
+    /// Replace the queue wait objectives (startup and config reload).
+    pub fn set_slo(&self, slo: SloConfig) {
+        self.slo.store(Arc::new(slo));
+    }
+
+    /// Set which jobs exclude each other (startup and config reload).
+    /// Locks already held stay until their jobs finish.
+    pub fn set_exclusive(&self, scope: ExclusiveScope) {
+        self.exclusive.store(Arc::new(scope));
+    }
+
+    /// Hold the low-priority jobs of `repos` in the queue until the next
+    /// call. Jobs already running are not affected.
+    pub fn set_held_repos(&self, repos: HashSet<Uuid>) {
+        self.held_repos.store(Arc::new(repos));
+    }
+
+    /// Whether `job` waits for its repository's blackout window to end.
+    fn is_held(held: &HashSet<Uuid>, job: &ProofJob) -> bool {
+        job.priority == JobPriority::Low && held.contains(&job.repo_id)
+    }
+
+    /// Take `job`'s lock under `scope`; false when another job holds it.
+    fn try_lock(&self, scope: ExclusiveScope, job: &ProofJob) -> bool {
+        let Some(key) = lock_key(scope, job) else {
+            return true;
+        };
+        match self.locks.entry(key) {
+            dashmap::mapref::entry::Entry::Occupied(_) => false,
+            dashmap::mapref::entry::Entry::Vacant(slot) => {
+                slot.insert(job.id);
+                true
+            }
+        }
+    }
+
+    /// Release whatever lock the running job `job_id` holds.
+    fn unlock(&self, job_id: JobId) {
+        if !self.locks.is_empty() {
+            self.locks.retain(|_, holder| *holder != job_id);
         }
     }
 
+    /// Change the limits at runtime (config reload). Lowering them never
+    /// drops jobs: queued and running jobs above the new limits stay, and
+    /// only new ones are held back until the counts fall below them.
+    pub fn resize(&self, max_concurrent: usize, max_queue_size: usize) {
+        let old_concurrent = self.max_concurrent.swap(max_concurrent, Ordering::AcqRel);
+        let old_queue_size = self.max_queue_size.swap(max_queue_size, Ordering::AcqRel);
+        if (old_concurrent, old_queue_size) != (max_concurrent, max_queue_size) {
+            tracing::info!(
+                "Scheduler limits changed: max_concurrent {} -> {}, queue_size {} -> {}",
+                old_concurrent,
+                max_concurrent,
+                old_queue_size,
+                max_queue_size
+            );
+        }
+    }
+
+    fn queue(&self, priority: JobPriority) -> std::sync::MutexGuard<'_, VecDeque<ProofJob>> {
+        self.queues[priority as usize].lock().expect("job queue poisoned")
+    }
+
+    /// Reserve one unit of `counter` below `limit`.
+    fn reserve(counter: &AtomicUsize, limit: usize) -> bool {
+        counter
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < limit).then_some(n + 1))
+            .is_ok()
+    }
+
+    /// Drop `job`'s index entry as it leaves the queue, unless the entry
+    /// names another job.
+    fn unindex(&self, job: &ProofJob) {
+        self.queued_keys.remove_if(&queue_key(job), |_, id| *id == job.id);
+    }
+
+    /// Snapshot of all queued jobs, in dispatch order.
+    fn queued_jobs(&self) -> Vec<ProofJob> {
+        PRIORITIES
+            .iter()
+            .flat_map(|p| self.queue(*p).iter().cloned().collect::<Vec<_>>())
+            .collect()
+    }
+
     /// Connect to fleet for a repository session
     pub async fn connect_to_fleet(&self, repo_name: &str, repo_path: impl Into<std::path::PathBuf>) -> Result<()> {
         let mut fleet = self.fleet.lock().await;
@@ -67,8 +306,7 @@ pub async fn disconnect_from_fleet(&self) -> Result<()> {
         };
 
         // Count files analyzed from all running/completed jobs
-        let running = self.running.lock().await;
-        let files_analyzed: usize = running.iter().map(|j| j.file_paths.len()).sum();
+        let files_analyzed: usize = self.running.iter().map(|j| j.file_paths.len()).sum();
 
         fleet.disconnect(findings_count, errors_count, files_analyzed)
     }
@@ -77,72 +315,217 @@ pub async fn disconnect_from_fleet(&self) -> Result<()> {
     ///
     /// Returns None if a duplicate job already exists (same repo, commit, prover)
     pub async fn enqueue(&self, job: ProofJob) -> Result<Option<JobId>> {
-        let mut queue = self.queue.lock().await;
-
         // Check queue size limit
-        if queue.len() >= self.max_queue_size {
+        if !Self::reserve(&self.queued_count, self.max_queue_size.load(Ordering::Acquire)) {
             tracing::warn!("Job queue full, rejecting job {}", job.id);
             return Ok(None);
         }
 
         // Check for duplicates
-        let is_duplicate = queue.iter().any(|j| {
-            j.repo_id == job.repo_id
-                && j.commit_sha == job.commit_sha
-                && j.prover == job.prover
-        });
-
-        if is_duplicate {
-            tracing::debug!("Duplicate job detected, skipping");
-            return Ok(None);
-        }
-
         let job_id = job.id;
+        match self.queued_keys.entry(queue_key(&job)) {
+            dashmap::mapref::entry::Entry::Occupied(_) => {
+                self.queued_count.fetch_sub(1, Ordering::AcqRel);
+                tracing::debug!("Duplicate job detected, skipping");
+                return Ok(None);
+            }
+            dashmap::mapref::entry::Entry::Vacant(slot) => {
+                slot.insert(job_id);
+            }
+        }
 
-        // Insert in priority order
-        let insert_pos = queue
-            .iter()
-            .position(|j| j.priority < job.priority)
-            .unwrap_or(queue.len());
+        if !self.workers.is_schedulable(&job.prover) {
+            tracing::warn!(
+                "Job {} is unschedulable: no live worker supports {} (needs {:?})",
+                job_id,
+                job.prover.as_str(),
+                self.workers.required_class(&job.prover)
+            );
+        }
 
-        queue.insert(insert_pos, job);
+        self.queue(job.priority).push_back(job);
 
-        tracing::info!("Enqueued job {} (queue size: {})", job_id, queue.len());
+        tracing::info!(
+            "Enqueued job {} (queue size: {})",
+            job_id,
+            self.queued_count.load(Ordering::Acquire)
+        );
         Ok(Some(job_id))
     }
 
-    /// Try to start the next job if capacity allows
+    /// Enqueue a batch of low-value jobs — one push's earlier commits —
+    /// without crowding out other work: the batch gets at most half the
+    /// queue's free slots, and keeps its last jobs when trimmed. Returns,
+    /// per job, its id, or `None` when it was trimmed or a duplicate.
+    pub async fn enqueue_batch(&self, jobs: Vec<ProofJob>) -> Result<Vec<Option<JobId>>> {
+        let free = self
+            .max_queue_size
+            .load(Ordering::Acquire)
+            .saturating_sub(self.queued_count.load(Ordering::Acquire));
+        let trimmed = jobs.len().saturating_sub(free / 2);
+        if trimmed > 0 {
+            tracing::info!("Batch of {} job(s) trimmed by {} for queue room", jobs.len(), trimmed);
+        }
+        let mut ids = vec![None; trimmed];
+        for job in jobs.into_iter().skip(trimmed) {
+            ids.push(self.enqueue(job).await?);
+        }
+        Ok(ids)
+    }
+
+    /// Try to start the next job the local worker can run, if capacity allows
     pub async fn try_start_next(&self) -> Option<ProofJob> {
-        if self.active_count.load(Ordering::Relaxed) >= self.max_concurrent {
+        self.try_start_next_for(LOCAL_WORKER).await
+    }
+
+    /// Try to start the highest-priority queued job `worker_id` can run.
+    /// Jobs it can't run keep their place. `None` also when the worker is
+    /// unknown or expired.
+    pub async fn try_start_next_for(&self, worker_id: &str) -> Option<ProofJob> {
+        if self.queued_count.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        let capabilities = self.workers.touch(worker_id)?;
+        if !Self::reserve(&self.active_count, self.max_concurrent.load(Ordering::Acquire)) {
             return None;
         }
 
-        let mut queue = self.queue.lock().await;
-        let mut job = queue.pop_front()?;
+        // The lock is taken for the job found, and only for it.
+        let scope = **self.exclusive.load();
+        let held = self.held_repos.load();
+        let taken = PRIORITIES.iter().find_map(|priority| {
+            let mut queue = self.queue(*priority);
+            let pos = queue.iter().position(|j| {
+                !Self::is_held(&held, j)
+                    && self.workers.accepts(&capabilities, &j.prover)
+                    && self.try_lock(scope, j)
+            })?;
+            queue.remove(pos)
+        });
+        let Some(mut job) = taken else {
+            self.active_count.fetch_sub(1, Ordering::AcqRel);
+            return None;
+        };
+        self.queued_count.fetch_sub(1, Ordering::AcqRel);
+        self.unindex(&job);
 
         job.start();
-        self.active_count.fetch_add(1, Ordering::Relaxed);
-
-        let mut running = self.running.lock().await;
-        running.push(job.clone());
+        if worker_id != LOCAL_WORKER {
+            self.claims.insert(job.id, worker_id.to_string());
+        }
+        self.running.insert(job.id, job.clone());
 
         tracing::info!(
-            "Started job {} (active: {}/{})",
+            "Started job {} on {} (active: {}/{})",
             job.id,
-            self.active_count.load(Ordering::Relaxed),
-            self.max_concurrent
+            worker_id,
+            self.active_count.load(Ordering::Acquire),
+            self.max_concurrent.load(Ordering::Acquire)
         );
 
         Some(job)
     }
 
+    /// Accept a result from the remote worker that claimed `job_id`. The
+    /// dispatch loop picks it up with [`Self::take_remote_results`].
+    pub async fn submit_remote_result(&self, worker_id: &str, job_id: JobId, result: JobResult) -> bool {
+        let Some((_, _)) = self.claims.remove_if(&job_id, |_, claimant| claimant == worker_id) else {
+            return false;
+        };
+        let Some(job) = self.running.get(&job_id).map(|j| j.value().clone()) else {
+            return false;
+        };
+        self.remote_results
+            .lock()
+            .expect("remote results poisoned")
+            .push((job, result));
+        true
+    }
+
+    /// Remote results not yet finalized; each is returned once.
+    pub async fn take_remote_results(&self) -> Vec<(ProofJob, JobResult)> {
+        std::mem::take(&mut *self.remote_results.lock().expect("remote results poisoned"))
+    }
+
+    /// Have the dispatch loop report `job`'s stored `result` again; it
+    /// picks them up with [`Self::take_reports`].
+    pub async fn request_report(&self, job: ProofJob, result: JobResult) {
+        self.reports.lock().expect("reports poisoned").push((job, result));
+    }
+
+    /// Reports requested and not yet posted; each is returned once.
+    pub async fn take_reports(&self) -> Vec<(ProofJob, JobResult)> {
+        std::mem::take(&mut *self.reports.lock().expect("reports poisoned"))
+    }
+
+    /// Put jobs claimed by workers that have since expired back at the
+    /// front of their queue. An orphan whose (repo, commit, prover) was
+    /// queued again meanwhile is a duplicate: it is cancelled instead, and
+    /// returned for the caller to record.
+    pub async fn requeue_orphaned(&self) -> Vec<ProofJob> {
+        if self.claims.is_empty() {
+            return Vec::new();
+        }
+        let live: Vec<String> = self.workers.live().into_iter().map(|w| w.id).collect();
+        let orphaned: Vec<JobId> = self
+            .claims
+            .iter()
+            .filter(|claim| !live.contains(claim.value()))
+            .map(|claim| *claim.key())
+            .collect();
+
+        let mut duplicates = Vec::new();
+        for job_id in orphaned {
+            if self.claims.remove(&job_id).is_none() {
+                continue;
+            }
+            if let Some((_, mut job)) = self.running.remove(&job_id) {
+                self.unlock(job_id);
+                self.active_count.fetch_sub(1, Ordering::AcqRel);
+                let queued = match self.queued_keys.entry(queue_key(&job)) {
+                    dashmap::mapref::entry::Entry::Occupied(entry) => Some(*entry.get()),
+                    dashmap::mapref::entry::Entry::Vacant(slot) => {
+                        slot.insert(job.id);
+                        None
+                    }
+                };
+                if let Some(queued) = queued {
+                    tracing::warn!(
+                        "Dropped job {}: its worker stopped responding and job {} repeats it",
+                        job.id,
+                        queued
+                    );
+                    job.cancel();
+                    self.logs.finish(job.id);
+                    duplicates.push(job);
+                    continue;
+                }
+                job.status = super::JobStatus::Queued;
+                job.started_at = None;
+                self.queued_count.fetch_add(1, Ordering::AcqRel);
+                tracing::warn!("Requeued job {}: its worker stopped responding", job.id);
+                self.queue(job.priority).push_front(job);
+            }
+        }
+        duplicates
+    }
+
+    /// Queued jobs no live worker can run.
+    pub async fn unschedulable_jobs(&self) -> Vec<ProofJob> {
+        self.queued_jobs()
+            .into_iter()
+            .filter(|j| !self.workers.is_schedulable(&j.prover))
+            .collect()
+    }
+
     /// Mark a job as completed and publish findings to fleet
     pub async fn complete_job(&self, job_id: JobId, result: super::JobResult) {
-        let mut running = self.running.lock().await;
-
-        if let Some(pos) = running.iter().position(|j| j.id == job_id) {
-            let mut job = running.remove(pos);
+        self.claims.remove(&job_id);
+        self.cancel_requested.remove(&job_id);
+        self.logs.finish(job_id);
 
+        if let Some((_, mut job)) = self.running.remove(&job_id) {
+            self.unlock(job_id);
             // Publish findings to fleet before completing
             let mut fleet = self.fleet.lock().await;
             if let Err(e) = fleet.publish_finding(&job, &result) {
@@ -152,13 +535,13 @@ pub async fn complete_job(&self, job_id: JobId, result: super::JobResult) {
 
             job.complete(result.clone());
 
-            self.active_count.fetch_sub(1, Ordering::Relaxed);
+            self.active_count.fetch_sub(1, Ordering::AcqRel);
 
             tracing::info!(
                 "Completed job {} (success: {}, active: {})",
                 job_id,
                 result.success,
-                self.active_count.load(Ordering::Relaxed)
+                self.active_count.load(Ordering::Acquire)
             );
         }
     }
@@ -166,50 +549,41 @@ pub async fn complete_job(&self, job_id: JobId, result: super::JobResult) {
     /// Get job by ID
     pub async fn get_job(&self, job_id: JobId) -> Option<ProofJob> {
         // Check running jobs
-        {
-            let running = self.running.lock().await;
-            if let Some(job) = running.iter().find(|j| j.id == job_id) {
-                return Some(job.clone());
-            }
+        if let Some(job) = self.running.get(&job_id) {
+            return Some(job.value().clone());
         }
 
-        // Check queue
-        {
-            let queue = self.queue.lock().await;
-            if let Some(job) = queue.iter().find(|j| j.id == job_id) {
-                return Some(job.clone());
-            }
-        }
-
-        None
+        // Check queues
+        PRIORITIES
+            .iter()
+            .find_map(|p| self.queue(*p).iter().find(|j| j.id == job_id).cloned())
     }
 
     /// Get all jobs for a repository
     pub async fn jobs_for_repo(&self, repo_id: Uuid) -> Vec<ProofJob> {
-        let mut jobs = Vec::new();
-
-        {
-            let running = self.running.lock().await;
-            jobs.extend(running.iter().filter(|j| j.repo_id == repo_id).cloned());
-        }
-
-        {
-            let queue = self.queue.lock().await;
-            jobs.extend(queue.iter().filter(|j| j.repo_id == repo_id).cloned());
-        }
-
+        let mut jobs: Vec<ProofJob> = self
+            .running
+            .iter()
+            .filter(|j| j.repo_id == repo_id)
+            .map(|j| j.value().clone())
+            .collect();
+        jobs.sort_by_key(|j| j.started_at);
+        jobs.extend(self.queued_jobs().into_iter().filter(|j| j.repo_id == repo_id));
         jobs
     }
 
     /// Cancel a job
     pub async fn cancel_job(&self, job_id: JobId) -> bool {
-        // Try to remove from queue first
-        {
-            let mut queue = self.queue.lock().await;
+        // Try to remove from the queues first
+        for priority in PRIORITIES {
+            let mut queue = self.queue(priority);
             if let Some(pos) = queue.iter().position(|j| j.id == job_id) {
                 // Safe: pos came from position() while we hold the lock,
                 // so the index is guaranteed in-bounds for VecDeque::remove.
                 let mut job = queue.remove(pos).expect("position() guarantees in-bounds index");
+                drop(queue);
+                self.queued_count.fetch_sub(1, Ordering::AcqRel);
+                self.unindex(&job);
                 job.cancel();
                 tracing::info!("Cancelled queued job {}", job_id);
                 return true;
@@ -221,38 +595,123 @@ pub async fn cancel_job(&self, job_id: JobId) -> bool {
         false
     }
 
+    /// Cancel the jobs in `scope` for commits other than `head_sha`.
+    /// Queued jobs are dropped. Remotely claimed jobs are released at
+    /// once, and their worker's eventual result is refused; local running
+    /// jobs are flagged for the dispatch loop, which stops waiting on them
+    /// (see [`Self::cancelled`]). Returns the cancelled jobs.
+    pub async fn cancel_superseded(
+        &self,
+        repo_id: Uuid,
+        scope: &SupersedeScope,
+        head_sha: &str,
+    ) -> Vec<ProofJob> {
+        let superseded =
+            |job: &ProofJob| job.repo_id == repo_id && job.commit_sha != head_sha && scope.covers(job);
+
+        let mut cancelled = Vec::new();
+        for priority in PRIORITIES {
+            let mut queue = self.queue(priority);
+            if !queue.iter().any(superseded) {
+                continue;
+            }
+            let (dropped, kept): (VecDeque<_>, VecDeque<_>) = queue.drain(..).partition(superseded);
+            *queue = kept;
+            drop(queue);
+            for mut job in dropped {
+                self.queued_count.fetch_sub(1, Ordering::AcqRel);
+                self.unindex(&job);
+                job.cancel();
+                cancelled.push(job);
+            }
+        }
+
+        let running: Vec<JobId> = self
+            .running
+            .iter()
+            .filter(|job| superseded(job.value()))
+            .map(|job| *job.key())
+            .collect();
+        let mut flagged = false;
+        for job_id in running {
+            if self.claims.remove(&job_id).is_some() {
+                if let Some((_, mut job)) = self.running.remove(&job_id) {
+                    self.unlock(job_id);
+                    self.active_count.fetch_sub(1, Ordering::AcqRel);
+                    job.cancel();
+                    cancelled.push(job);
+                }
+            } else if let Some(mut job) = self.running.get(&job_id).map(|j| j.value().clone()) {
+                flagged |= self.cancel_requested.insert(job_id);
+                job.cancel();
+                cancelled.push(job);
+            }
+        }
+        if flagged {
+            self.cancel_notify.notify_waiters();
+        }
+
+        if !cancelled.is_empty() {
+            tracing::info!(
+                "Cancelled {} job(s) superseded by {} ({:?})",
+                cancelled.len(),
+                head_sha,
+                scope
+            );
+        }
+        cancelled
+    }
+
+    /// Resolves once the local running job `job_id` has been cancelled by
+    /// [`Self::cancel_superseded`]; the dispatch loop races it against
+    /// verification and then calls [`Self::release_cancelled`].
+    pub async fn cancelled(&self, job_id: JobId) {
+        loop {
+            let notified = self.cancel_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.cancel_requested.contains(&job_id) {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Free the slot of a running job abandoned after cancellation.
+    pub async fn release_cancelled(&self, job_id: JobId) {
+        self.cancel_requested.remove(&job_id);
+        self.logs.finish(job_id);
+        if self.running.remove(&job_id).is_some() {
+            self.unlock(job_id);
+            self.active_count.fetch_sub(1, Ordering::AcqRel);
+            tracing::info!("Abandoned cancelled job {}", job_id);
+        }
+    }
+
     /// Get queue statistics
     pub async fn stats(&self) -> QueueStats {
-        let queue = self.queue.lock().await;
-        let running = self.running.lock().await;
-
         QueueStats {
-            queued: queue.len(),
-            running: running.len(),
-            max_concurrent: self.max_concurrent,
-            max_queue_size: self.max_queue_size,
+            queued: self.queued_count.load(Ordering::Acquire),
+            running: self.running.len(),
+            unschedulable: self.unschedulable_jobs().await.len(),
+            max_concurrent: self.max_concurrent.load(Ordering::Acquire),
+            max_queue_size: self.max_queue_size.load(Ordering::Acquire),
         }
     }
 
     /// Check if there's capacity for more jobs
     pub fn has_capacity(&self) -> bool {
-        self.active_count.load(Ordering::Relaxed) < self.max_concurrent
+        self.active_count.load(Ordering::Acquire) < self.max_concurrent.load(Ordering::Acquire)
     }
 
     /// Current number of running jobs (lock-free snapshot for metrics).
     pub fn running_count(&self) -> usize {
-        self.active_count.load(Ordering::Relaxed)
+        self.active_count.load(Ordering::Acquire)
     }
 
-    /// Approximate queue depth (lock-free via active count heuristic).
-    /// For an exact count use `stats().await`; this is cheap enough for
-    /// the `/metrics` scrape path where blocking on an async lock is undesirable.
+    /// Current queue depth (lock-free snapshot for metrics).
     pub fn queue_depth(&self) -> usize {
-        // We don't store a separate atomic for pending count, so approximate
-        // via the difference between active_count and max_concurrent clamped
-        // at 0. Under light load this is 0; under saturation it reflects backpressure.
-        // The `/metrics` handler documents this as an approximation.
-        self.active_count.load(Ordering::Relaxed).saturating_sub(self.max_concurrent)
+        self.queued_count.load(Ordering::Acquire)
     }
 }
 
@@ -261,6 +720,8 @@ pub fn queue_depth(&self) -> usize {
 pub struct QueueStats {
     pub queued: usize,
     pub running: usize,
+    /// Queued jobs no live worker can run (counted in `queued` too).
+    pub unschedulable: usize,
     pub max_concurrent: usize,
     pub max_queue_size: usize,
 }
@@ -271,6 +732,22 @@ mod tests {
     use crate::dispatcher::ProverKind;
     use crate::scheduler::{JobPriority, JobStatus};
 
+    #[tokio::test]
+    async fn batches_take_half_the_free_room_keeping_their_last_jobs() {
+        let scheduler = JobScheduler::new(2, 6);
+        let repo = Uuid::new_v4();
+        let job = |sha: &str| ProofJob::new(repo, sha.to_string(), ProverKind::new("coq"), vec![]);
+        scheduler.enqueue(job("other")).await.unwrap();
+
+        let ids = scheduler
+            .enqueue_batch(vec![job("c1"), job("c2"), job("c3"), job("c4")])
+            .await
+            .unwrap();
+        // 5 free slots: room for 2.
+        assert_eq!(ids.iter().map(Option::is_some).collect::<Vec<_>>(), [false, false, true, true]);
+        assert_eq!(scheduler.queued_jobs().len(), 3);
+    }
+
     #[tokio::test]
     async fn test_enqueue_and_start() {
         let scheduler = JobScheduler::new(2, 10);
@@ -330,6 +807,66 @@ async fn test_duplicate_detection() {
         assert!(scheduler.enqueue(job2).await.unwrap().is_none());
     }
 
+    /// The index holds exactly the queued jobs' keys, each naming its job.
+    fn assert_index_matches_queue(scheduler: &JobScheduler) {
+        let queued = scheduler.queued_jobs();
+        assert_eq!(scheduler.queue_depth(), queued.len());
+        assert_eq!(scheduler.queued_keys.len(), queued.len());
+        for job in &queued {
+            assert_eq!(scheduler.queued_keys.get(&queue_key(job)).map(|id| *id), Some(job.id));
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_index_follows_cancel_start_and_complete() {
+        let scheduler = JobScheduler::new(2, 10);
+        let repo_id = Uuid::new_v4();
+        let job = |sha: &str| ProofJob::new(repo_id, sha.into(), ProverKind::new("coq"), vec![]);
+
+        let a = scheduler.enqueue(job("a")).await.unwrap().unwrap();
+        let b = scheduler.enqueue(job("b")).await.unwrap().unwrap();
+        scheduler.enqueue(job("c")).await.unwrap().unwrap();
+        assert!(scheduler.enqueue(job("b")).await.unwrap().is_none());
+        assert_index_matches_queue(&scheduler);
+
+        // A cancelled job no longer blocks its key.
+        assert!(scheduler.cancel_job(b).await);
+        assert_index_matches_queue(&scheduler);
+        assert!(scheduler.enqueue(job("b")).await.unwrap().is_some());
+
+        // Nor does a started one: only queued jobs are duplicates.
+        let started = scheduler.try_start_next().await.unwrap();
+        assert_eq!(started.id, a);
+        assert_index_matches_queue(&scheduler);
+        scheduler.enqueue(job("a")).await.unwrap().unwrap();
+        assert_index_matches_queue(&scheduler);
+
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 1,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        scheduler.complete_job(a, result).await;
+        assert_index_matches_queue(&scheduler);
+        assert!(scheduler.enqueue(job("a")).await.unwrap().is_none(), "the re-enqueued one waits");
+
+        // Superseded jobs leave the index with the queue.
+        let pr = |sha: &str| job(sha).with_context(Some(3), None);
+        scheduler.enqueue(pr("old")).await.unwrap().unwrap();
+        scheduler.cancel_superseded(repo_id, &SupersedeScope::PullRequest(3), "new").await;
+        assert_index_matches_queue(&scheduler);
+        assert!(scheduler.enqueue(pr("old")).await.unwrap().is_some());
+        assert_index_matches_queue(&scheduler);
+    }
+
     #[tokio::test]
     async fn test_priority_ordering() {
         let scheduler = JobScheduler::new(1, 10);
@@ -361,4 +898,256 @@ async fn test_priority_ordering() {
         let started = scheduler.try_start_next().await.unwrap();
         assert_eq!(started.commit_sha, "high");
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_enqueue_dedup_and_capacity() {
+        let scheduler = Arc::new(JobScheduler::new(3, 100));
+        let repo_id = Uuid::new_v4();
+
+        let handles: Vec<_> = (0..32)
+            .map(|_| {
+                let scheduler = scheduler.clone();
+                tokio::spawn(async move {
+                    let job = ProofJob::new(repo_id, "same".into(), ProverKind::new("coq"), vec![]);
+                    scheduler.enqueue(job).await.unwrap()
+                })
+            })
+            .collect();
+        let mut accepted = 0;
+        for handle in handles {
+            accepted += usize::from(handle.await.unwrap().is_some());
+        }
+        assert_eq!(accepted, 1, "racing duplicates are rejected");
+
+        for i in 0..10 {
+            let job = ProofJob::new(repo_id, format!("c{}", i), ProverKind::new("coq"), vec![]);
+            scheduler.enqueue(job).await.unwrap();
+        }
+        let starts: Vec<_> = (0..16)
+            .map(|_| {
+                let scheduler = scheduler.clone();
+                tokio::spawn(async move { scheduler.try_start_next().await })
+            })
+            .collect();
+        let mut started = 0;
+        for handle in starts {
+            started += usize::from(handle.await.unwrap().is_some());
+        }
+        assert_eq!(started, 3, "never more than max_concurrent");
+        assert_eq!(scheduler.queue_depth(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_resize_applies_to_new_jobs_only() {
+        let scheduler = JobScheduler::new(1, 2);
+        let repo_id = Uuid::new_v4();
+        let job = |sha: &str| ProofJob::new(repo_id, sha.into(), ProverKind::new("coq"), vec![]);
+
+        for sha in ["a", "b"] {
+            assert!(scheduler.enqueue(job(sha)).await.unwrap().is_some());
+        }
+        assert!(scheduler.enqueue(job("c")).await.unwrap().is_none(), "queue full");
+
+        scheduler.resize(2, 3);
+        assert!(scheduler.enqueue(job("c")).await.unwrap().is_some());
+        assert!(scheduler.try_start_next().await.is_some());
+        assert!(scheduler.try_start_next().await.is_some());
+        assert!(scheduler.try_start_next().await.is_none());
+
+        // Shrinking keeps what is already running or queued.
+        scheduler.resize(1, 0);
+        let stats = scheduler.stats().await;
+        assert_eq!((stats.running, stats.queued), (2, 1));
+        assert_eq!((stats.max_concurrent, stats.max_queue_size), (1, 0));
+        assert!(scheduler.enqueue(job("d")).await.unwrap().is_none());
+        assert!(!scheduler.has_capacity());
+    }
+
+    #[tokio::test]
+    async fn test_capability_routing() {
+        use crate::config::FleetConfig;
+        use crate::fleet::workers::{ResourceClass, WorkerCapabilities};
+
+        let registry = Arc::new(WorkerRegistry::new(&FleetConfig::default()));
+        registry.register(
+            LOCAL_WORKER,
+            WorkerCapabilities {
+                provers: vec![ProverKind::new("coq")],
+                resource_class: ResourceClass::Standard,
+            },
+        );
+        let scheduler = JobScheduler::new(4, 10).with_workers(registry.clone());
+        let repo_id = Uuid::new_v4();
+
+        let isabelle = ProofJob::new(repo_id, "a".into(), ProverKind::new("isabelle"), vec![])
+            .with_priority(JobPriority::High);
+        let coq = ProofJob::new(repo_id, "b".into(), ProverKind::new("coq"), vec![]);
+        scheduler.enqueue(isabelle).await.unwrap();
+        scheduler.enqueue(coq).await.unwrap();
+        assert_eq!(scheduler.stats().await.unschedulable, 1);
+
+        // The local worker skips the Isabelle job it can't run.
+        let started = scheduler.try_start_next().await.unwrap();
+        assert_eq!(started.prover, ProverKind::new("coq"));
+        assert!(scheduler.try_start_next().await.is_none());
+
+        registry.register(
+            "isabelle-1",
+            WorkerCapabilities {
+                provers: vec![ProverKind::new("isabelle")],
+                resource_class: ResourceClass::Large,
+            },
+        );
+        assert_eq!(scheduler.stats().await.unschedulable, 0);
+        let claimed = scheduler.try_start_next_for("isabelle-1").await.unwrap();
+        assert_eq!(claimed.prover, ProverKind::new("isabelle"));
+
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 1,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        assert!(!scheduler.submit_remote_result("other", claimed.id, result.clone()).await);
+        assert!(scheduler.submit_remote_result("isabelle-1", claimed.id, result).await);
+        assert_eq!(scheduler.take_remote_results().await.len(), 1);
+        assert!(scheduler.take_remote_results().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn orphan_already_queued_again_is_dropped() {
+        use crate::fleet::workers::{ResourceClass, WorkerCapabilities};
+
+        let registry = Arc::new(WorkerRegistry::default());
+        registry.register(
+            "coq-1",
+            WorkerCapabilities {
+                provers: vec![ProverKind::new("coq")],
+                resource_class: ResourceClass::Standard,
+            },
+        );
+        let scheduler = JobScheduler::new(4, 10).with_workers(registry.clone());
+        let repo_id = Uuid::new_v4();
+        let job = |sha: &str| ProofJob::new(repo_id, sha.into(), ProverKind::new("coq"), vec![]);
+
+        scheduler.enqueue(job("a")).await.unwrap().unwrap();
+        scheduler.enqueue(job("b")).await.unwrap().unwrap();
+        let a = scheduler.try_start_next_for("coq-1").await.unwrap();
+        let b = scheduler.try_start_next_for("coq-1").await.unwrap();
+        // While the worker holds `a`, the same check is queued again.
+        let again = scheduler.enqueue(job("a")).await.unwrap().unwrap();
+
+        assert!(registry.deregister("coq-1"));
+        let dropped = scheduler.requeue_orphaned().await;
+        assert_eq!(dropped.len(), 1);
+        assert_eq!((dropped[0].id, dropped[0].status), (a.id, JobStatus::Cancelled));
+        let queued: Vec<JobId> = scheduler.queued_jobs().iter().map(|j| j.id).collect();
+        assert_eq!(queued, [b.id, again]);
+        assert_index_matches_queue(&scheduler);
+        assert!(scheduler.requeue_orphaned().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_superseded_pr_jobs() {
+        let scheduler = JobScheduler::new(1, 10);
+        let repo_id = Uuid::new_v4();
+        let pr_job = |sha: &str, prover: &str, pr: u64| {
+            ProofJob::new(repo_id, sha.into(), ProverKind::new(prover), vec![]).with_context(Some(pr), None)
+        };
+
+        scheduler.enqueue(pr_job("old", "coq", 7)).await.unwrap();
+        let running = scheduler.try_start_next().await.unwrap();
+        scheduler.enqueue(pr_job("old", "lean", 7)).await.unwrap();
+        scheduler.enqueue(pr_job("old", "coq", 8)).await.unwrap();
+        scheduler.enqueue(pr_job("new", "coq", 7)).await.unwrap();
+
+        let cancelled = scheduler
+            .cancel_superseded(repo_id, &SupersedeScope::PullRequest(7), "new")
+            .await;
+        assert_eq!(cancelled.len(), 2);
+        assert!(cancelled.iter().all(|j| j.status == JobStatus::Cancelled && j.commit_sha == "old"));
+        assert_eq!(scheduler.queue_depth(), 2, "PR 8 and the new head stay queued");
+
+        // The dispatch loop sees the running job's cancellation and frees its slot.
+        tokio::time::timeout(std::time::Duration::from_secs(1), scheduler.cancelled(running.id))
+            .await
+            .expect("running job flagged");
+        assert!(!scheduler.has_capacity());
+        scheduler.release_cancelled(running.id).await;
+        assert!(scheduler.has_capacity());
+
+        // A branch scope only touches push jobs of that branch.
+        let push = ProofJob::new(repo_id, "x".into(), ProverKind::new("agda"), vec![])
+            .with_branch(Some("main".into()));
+        scheduler.enqueue(push).await.unwrap();
+        let scope = SupersedeScope::Branch("main".into());
+        assert_eq!(scheduler.cancel_superseded(repo_id, &scope, "y").await.len(), 1);
+        assert_eq!(scheduler.queue_depth(), 2);
+    }
+    #[tokio::test]
+    async fn test_exclusive_repository_serializes_its_jobs() {
+        let scheduler = JobScheduler::new(4, 10);
+        scheduler.set_exclusive(ExclusiveScope::Repository);
+        let (repo, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let job = |repo_id: Uuid, prover: &str| {
+            ProofJob::new(repo_id, "abc".into(), ProverKind::new(prover), vec![])
+        };
+
+        scheduler.enqueue(job(repo, "coq")).await.unwrap();
+        scheduler.enqueue(job(repo, "lean")).await.unwrap();
+        scheduler.enqueue(job(other, "coq")).await.unwrap();
+
+        let first = scheduler.try_start_next().await.unwrap();
+        assert_eq!(first.repo_id, repo);
+        // The second job of `repo` waits; the other repository's starts.
+        let second = scheduler.try_start_next().await.unwrap();
+        assert_eq!(second.repo_id, other);
+        assert!(scheduler.try_start_next().await.is_none());
+        assert_eq!(scheduler.queue_depth(), 1);
+
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 1,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        scheduler.complete_job(first.id, result).await;
+        let third = scheduler.try_start_next().await.unwrap();
+        assert_eq!((third.repo_id, third.prover), (repo, ProverKind::new("lean")));
+    }
+    #[tokio::test]
+    async fn test_held_repos_keep_low_priority_jobs_queued() {
+        let scheduler = JobScheduler::new(4, 10);
+        let repo = Uuid::new_v4();
+        let job = |prover: &str, priority: JobPriority| {
+            ProofJob::new(repo, "abc".into(), ProverKind::new(prover), vec![])
+                .with_priority(priority)
+        };
+        scheduler.enqueue(job("coq", JobPriority::Low)).await.unwrap();
+        scheduler.enqueue(job("lean", JobPriority::Normal)).await.unwrap();
+        scheduler.set_held_repos(HashSet::from([repo]));
+
+        let started = scheduler.try_start_next().await.unwrap();
+        assert_eq!(started.prover, ProverKind::new("lean"));
+        assert!(scheduler.try_start_next().await.is_none());
+        assert_eq!(scheduler.queue_depth(), 1);
+
+        scheduler.set_held_repos(HashSet::new());
+        let released = scheduler.try_start_next().await.unwrap();
+        assert_eq!(released.prover, ProverKind::new("coq"));
+    }
 }
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Live prover output, per job
+//!
+//! The dispatch loop pushes each line a job's prover writes (streamed by
+//! the local executor as it runs; in one piece per file when ECHIDNA
+//! verifies) and [`JobScheduler::complete_job`](super::JobScheduler::complete_job)
+//! ends the log. `GET /jobs/{id}/logs` (`crate::api::logs`) replays what
+//! is buffered and then follows new lines as server-sent events.
+//!
+//! Each log keeps its last `[scheduler] log_buffer_lines` lines, and the
+//! last `log_retain_jobs` finished logs stay readable; older ones fall back
+//! to the stored result's prover output.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::JobId;
+use crate::executor::container::OutputStream;
+
+/// Lines a slow follower may fall behind before it skips ahead.
+const FOLLOW_CAPACITY: usize = 1024;
+
+/// Where a log line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// echidnabot's own progress notes ("verifying Foo.v").
+    Echidnabot,
+}
+
+impl LogStream {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+            LogStream::Echidnabot => "echidnabot",
+        }
+    }
+}
+
+impl From<OutputStream> for LogStream {
+    fn from(stream: OutputStream) -> Self {
+        match stream {
+            OutputStream::Stdout => LogStream::Stdout,
+            OutputStream::Stderr => LogStream::Stderr,
+        }
+    }
+}
+
+/// One line of a job's log. `seq` counts from 0 per job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LogLine {
+    pub seq: u64,
+    pub stream: LogStream,
+    pub text: String,
+}
+
+/// What a follower receives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEvent {
+    Line(LogLine),
+    /// The job finished; nothing follows.
+    End,
+}
+
+/// A log's buffered lines plus, while the job runs, the feed of the rest.
+pub struct LogSubscription {
+    pub backlog: Vec<LogLine>,
+    /// `None` once the job has finished.
+    pub follow: Option<broadcast::Receiver<LogEvent>>,
+}
+
+struct JobLog {
+    lines: VecDeque<LogLine>,
+    next_seq: u64,
+    finished: bool,
+    tx: broadcast::Sender<LogEvent>,
+}
+
+impl JobLog {
+    fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            next_seq: 0,
+            finished: false,
+            tx: broadcast::channel(FOLLOW_CAPACITY).0,
+        }
+    }
+}
+
+/// Every job's log, held by the [`JobScheduler`](super::JobScheduler).
+pub struct JobLogs {
+    logs: DashMap<JobId, JobLog>,
+    /// Finished logs, oldest first, for eviction.
+    finished: Mutex<VecDeque<JobId>>,
+    max_lines: usize,
+    retain_jobs: usize,
+}
+
+impl Default for JobLogs {
+    fn default() -> Self {
+        Self::new(2000, 50)
+    }
+}
+
+impl JobLogs {
+    pub fn new(max_lines: usize, retain_jobs: usize) -> Self {
+        Self {
+            logs: DashMap::new(),
+            finished: Mutex::new(VecDeque::new()),
+            max_lines: max_lines.max(1),
+            retain_jobs,
+        }
+    }
+
+    /// Append one line to `job`'s log.
+    pub fn push(&self, job: JobId, stream: LogStream, text: &str) {
+        let mut log = self.logs.entry(job).or_insert_with(JobLog::new);
+        if log.finished {
+            return;
+        }
+        let line = LogLine {
+            seq: log.next_seq,
+            stream,
+            text: text.to_string(),
+        };
+        log.next_seq += 1;
+        if log.lines.len() == self.max_lines {
+            log.lines.pop_front();
+        }
+        log.lines.push_back(line.clone());
+        // No followers is not an error.
+        let _ = log.tx.send(LogEvent::Line(line));
+    }
+
+    /// Append each line of `output`.
+    pub fn push_output(&self, job: JobId, stream: LogStream, output: &str) {
+        for line in output.lines() {
+            self.push(job, stream, line);
+        }
+    }
+
+    /// End `job`'s log, waking its followers.
+    pub fn finish(&self, job: JobId) {
+        {
+            let mut log = self.logs.entry(job).or_insert_with(JobLog::new);
+            if log.finished {
+                return;
+            }
+            log.finished = true;
+            let _ = log.tx.send(LogEvent::End);
+        }
+        let mut finished = self.finished.lock().unwrap();
+        finished.push_back(job);
+        while finished.len() > self.retain_jobs {
+            if let Some(evicted) = finished.pop_front() {
+                self.logs.remove(&evicted);
+            }
+        }
+    }
+
+    /// `job`'s buffered lines and, while it runs, the feed of later ones.
+    /// `None` if the job has no log here (not started, evicted, or run by
+    /// another instance).
+    pub fn subscribe(&self, job: JobId) -> Option<LogSubscription> {
+        // Under the entry's lock, so no line falls between backlog and feed.
+        let log = self.logs.get(&job)?;
+        Some(LogSubscription {
+            backlog: log.lines.iter().cloned().collect(),
+            follow: (!log.finished).then(|| log.tx.subscribe()),
+        })
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but opens an empty log first if
+    /// `job` has none, so a follower can wait for a job that has not
+    /// started yet. Only for jobs known to be queued or running.
+    pub fn watch(&self, job: JobId) -> LogSubscription {
+        let log = self.logs.entry(job).or_insert_with(JobLog::new);
+        LogSubscription {
+            backlog: log.lines.iter().cloned().collect(),
+            follow: (!log.finished).then(|| log.tx.subscribe()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn followers_get_the_backlog_then_live_lines_then_end() {
+        let logs = JobLogs::new(2, 10);
+        let job = JobId::new();
+        assert!(logs.subscribe(job).is_none());
+
+        logs.push(job, LogStream::Echidnabot, "verifying A.v");
+        logs.push_output(job, LogStream::Stdout, "line 1\nline 2\n");
+        let mut sub = logs.subscribe(job).unwrap();
+        let seqs: Vec<u64> = sub.backlog.iter().map(|l| l.seq).collect();
+        assert_eq!(seqs, vec![1, 2], "only the last max_lines are buffered");
+
+        logs.push(job, LogStream::Stderr, "Error: foo");
+        logs.finish(job);
+        logs.push(job, LogStream::Stdout, "after the end");
+
+        let follow = sub.follow.as_mut().unwrap();
+        match follow.recv().await.unwrap() {
+            LogEvent::Line(line) => {
+                assert_eq!((line.seq, line.stream, line.text.as_str()), (3, LogStream::Stderr, "Error: foo"))
+            }
+            other => panic!("expected a line, got {:?}", other),
+        }
+        assert_eq!(follow.recv().await.unwrap(), LogEvent::End);
+        assert!(logs.subscribe(job).unwrap().follow.is_none());
+    }
+
+    #[test]
+    fn only_the_most_recent_finished_logs_are_kept() {
+        let logs = JobLogs::new(10, 1);
+        let (first, second) = (JobId::new(), JobId::new());
+        logs.push(first, LogStream::Stdout, "a");
+        logs.push(second, LogStream::Stdout, "b");
+        logs.finish(first);
+        logs.finish(second);
+        assert!(logs.subscribe(first).is_none());
+        assert_eq!(logs.subscribe(second).unwrap().backlog.len(), 1);
+    }
+}
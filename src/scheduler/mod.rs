@@ -5,17 +5,21 @@
 
 pub mod job_queue;
 pub mod limiter; // Concurrent job limits to prevent overwhelming prover backends
+pub mod logs; // Live per-job prover output, followed over SSE
+pub mod resubmit; // Retrying finished jobs, linked to the original
 pub mod retry; // Exponential backoff for transient failures
+pub mod supersede; // Cancelling jobs for commits a newer head replaced
 
-pub use job_queue::JobScheduler;
+pub use job_queue::{JobScheduler, SupersedeScope};
 pub use limiter::{JobLimiter, LimiterConfig};
+pub use logs::{JobLogs, LogStream};
 pub use retry::{CircuitBreaker, CircuitState, RetryConfig, RetryPolicy, retry, retry_with_backoff};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::dispatcher::ProverKind;
+use crate::dispatcher::{ExecutionPath, ProverKind};
 use crate::trust::{axiom_tracker::AxiomReport, confidence::ConfidenceReport};
 
 /// Unique job identifier
@@ -64,6 +68,27 @@ pub struct ProofJob {
     /// proof outcome back to the exact webhook that triggered it.
     #[serde(default)]
     pub delivery_id: Option<String>,
+    /// Per-job verification timeout override in seconds. `None` uses
+    /// `[echidna] timeout_secs`. Set by the "Re-run with larger timeout"
+    /// check-run action.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Set for bot-authored dependency PRs matched by the repo's
+    /// `[dependency_prs]` policy; enables auto-merge when green.
+    #[serde(default)]
+    pub dependency_update: bool,
+    /// Base commit of the PR, for the baseline comparison in the check
+    /// summary (see `crate::baseline`). `None` outside PRs.
+    #[serde(default)]
+    pub base_sha: Option<String>,
+    /// Branch whose push triggered the job (`main`); `None` for PRs and
+    /// manual triggers.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// W3C `traceparent` of the span that created the job (see
+    /// `crate::observability`), so its run joins the webhook's trace.
+    #[serde(default)]
+    pub trace_parent: Option<String>,
 }
 
 impl ProofJob {
@@ -82,6 +107,11 @@ pub fn new(repo_id: Uuid, commit_sha: String, prover: ProverKind, file_paths: Ve
             result: None,
             pr_number: None,
             delivery_id: None,
+            timeout_secs: None,
+            dependency_update: false,
+            base_sha: None,
+            branch: None,
+            trace_parent: crate::observability::current_trace_parent(),
         }
     }
 
@@ -102,6 +132,30 @@ pub fn with_context(
         self
     }
 
+    /// Record the PR's base commit (see [`ProofJob::base_sha`]).
+    pub fn with_base(mut self, base_sha: Option<String>) -> Self {
+        self.base_sha = base_sha;
+        self
+    }
+
+    /// Record the pushed branch (see [`ProofJob::branch`]).
+    pub fn with_branch(mut self, branch: Option<String>) -> Self {
+        self.branch = branch;
+        self
+    }
+
+    /// Override the verification timeout for this job only.
+    pub fn with_timeout(mut self, timeout_secs: u64) -> Self {
+        self.timeout_secs = Some(timeout_secs);
+        self
+    }
+
+    /// Tag as a dependency-PR job (see [`ProofJob::dependency_update`]).
+    pub fn with_dependency_update(mut self, dependency_update: bool) -> Self {
+        self.dependency_update = dependency_update;
+        self
+    }
+
     /// Mark as started
     pub fn start(&mut self) {
         self.status = JobStatus::Running;
@@ -169,4 +223,15 @@ pub struct JobResult {
     /// Axiom usage flags found in the aggregated prover output.
     #[serde(default)]
     pub axioms: Option<AxiomReport>,
+    /// Version the prover reported (see `crate::toolchain`).
+    #[serde(default)]
+    pub prover_version: Option<String>,
+    /// Repository flags the prover ran with (see
+    /// `crate::executor::prover_args`).
+    #[serde(default)]
+    pub prover_args: Vec<String>,
+    /// Where the proofs were checked (`[executor] dispatch`); `None` when
+    /// none were.
+    #[serde(default)]
+    pub execution_path: Option<ExecutionPath>,
 }
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Retrying finished jobs (`retryJob` mutation, `echidnabot retry`)
+//!
+//! A retry is a new job with the original's repository, commit, prover,
+//! files, priority and PR context, linked back through
+//! [`ProofJobRecord::retry_of`]. Only finished jobs (completed, failed or
+//! cancelled) can be retried, and a retry is refused when the same
+//! repo/commit/prover is already queued — the rule [`JobScheduler::enqueue`]
+//! applies to the in-memory queue, checked against the store as well so
+//! the CLI (which has no scheduler) gets the same answer.
+//!
+//! The CLI only writes the queued record; a running daemon picks it up
+//! through [`adopt_queued_retries`], which the scheduler loop calls while
+//! idle. That also re-queues retries left in the store by a restart.
+
+use super::{JobId, JobScheduler, JobStatus, ProofJob};
+use crate::error::{Error, Result};
+use crate::store::models::ProofJobRecord;
+use crate::store::Store;
+
+/// Recent jobs scanned for a queued duplicate.
+const DUPLICATE_SCAN_LIMIT: usize = 200;
+
+/// Queued records examined per adoption sweep.
+const ADOPT_SCAN_LIMIT: usize = 100;
+
+/// Build the job that re-runs `original`.
+pub fn retry_job_for(original: &ProofJobRecord) -> ProofJob {
+    let mut job = ProofJob::new(
+        original.repo_id,
+        original.commit_sha.clone(),
+        original.prover.clone(),
+        original.payload.file_paths.clone(),
+    )
+    .with_priority(original.priority)
+    .with_context(original.pr_number, original.delivery_id.clone())
+    .with_base(original.base_sha.clone())
+    .with_branch(original.branch.clone())
+    .with_dependency_update(original.payload.dependency_update);
+    job.timeout_secs = original.payload.timeout_secs;
+    job
+}
+
+/// Retry `job_id`: record the new job linked to the original and, when a
+/// scheduler is given, enqueue it. Returns the new job's record.
+pub async fn retry_job(
+    store: &dyn Store,
+    scheduler: Option<&JobScheduler>,
+    job_id: JobId,
+) -> Result<ProofJobRecord> {
+    let original = store
+        .get_job(job_id)
+        .await?
+        .ok_or(Error::JobNotFound(job_id.0))?;
+    if matches!(original.status, JobStatus::Queued | JobStatus::Running) {
+        return Err(Error::InvalidInput(format!(
+            "job {} is still {:?}; only finished jobs can be retried",
+            job_id, original.status
+        )));
+    }
+
+    let job = retry_job_for(&original);
+    if let Some(existing) = queued_duplicate(store, &job).await? {
+        return Err(Error::InvalidInput(format!(
+            "{} at {} is already queued as job {}",
+            job.prover.display_name(),
+            job.commit_sha,
+            existing
+        )));
+    }
+
+    let mut record = ProofJobRecord::from(job.clone());
+    record.retry_of = Some(original.id);
+    store.create_job(&record).await?;
+
+    if let Some(scheduler) = scheduler {
+        if scheduler.enqueue(job).await?.is_none() {
+            record.status = JobStatus::Cancelled;
+            record.completed_at = Some(chrono::Utc::now());
+            record.error_message = Some("Rejected by the scheduler (duplicate or queue full)".to_string());
+            store.update_job(&record).await?;
+            return Err(Error::InvalidInput(format!(
+                "retry of job {} was rejected by the scheduler (duplicate or queue full)",
+                job_id
+            )));
+        }
+    }
+
+    tracing::info!("Job {} queued as a retry of {}", record.id, original.id);
+    Ok(record)
+}
+
+/// A queued job for the same repo/commit/prover, if the store has one.
+async fn queued_duplicate(store: &dyn Store, job: &ProofJob) -> Result<Option<uuid::Uuid>> {
+    Ok(store
+        .list_jobs_for_repo(job.repo_id, DUPLICATE_SCAN_LIMIT)
+        .await?
+        .into_iter()
+        .find(|j| {
+            j.status == JobStatus::Queued
+                && j.commit_sha == job.commit_sha
+                && j.prover == job.prover
+        })
+        .map(|j| j.id))
+}
+
+/// Enqueue retry records that are queued in the store but unknown to
+/// `scheduler` — written by `echidnabot retry`, or left over from before
+/// a restart. Returns how many were adopted.
+pub async fn adopt_queued_retries(store: &dyn Store, scheduler: &JobScheduler) -> Result<usize> {
+    let mut adopted = 0;
+    for mut record in store.list_pending_jobs(ADOPT_SCAN_LIMIT).await? {
+        if record.retry_of.is_none() || scheduler.get_job(JobId(record.id)).await.is_some() {
+            continue;
+        }
+
+        if scheduler.enqueue(record.to_job()).await?.is_some() {
+            adopted += 1;
+        } else {
+            // Leaving it queued would retry the adoption on every sweep.
+            record.status = JobStatus::Cancelled;
+            record.completed_at = Some(chrono::Utc::now());
+            record.error_message = Some("Rejected by the scheduler (duplicate or queue full)".to_string());
+            store.update_job(&record).await?;
+        }
+    }
+    Ok(adopted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::JobPriority;
+    use crate::store::models::Repository;
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn retry_links_clones_and_dedupes() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-retry-test-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteStore::new(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let original = ProofJob::new(repo.id, "abc".into(), ProverKind::new("lean"), vec!["A.lean".into()])
+            .with_priority(JobPriority::High)
+            .with_context(Some(7), None);
+        let mut record = ProofJobRecord::from(original.clone());
+        store.create_job(&record).await.unwrap();
+
+        let refused = retry_job(&store, None, original.id).await;
+        assert!(matches!(refused, Err(Error::InvalidInput(_))), "queued jobs cannot be retried");
+
+        record.status = JobStatus::Failed;
+        store.update_job(&record).await.unwrap();
+
+        let retry = retry_job(&store, None, original.id).await.unwrap();
+        assert_eq!(retry.retry_of, Some(original.id.0));
+        assert_eq!(retry.payload.file_paths, vec!["A.lean".to_string()]);
+        assert_eq!(retry.priority, JobPriority::High);
+        assert_eq!(retry.pr_number, Some(7));
+        let stored = store.get_job(JobId(retry.id)).await.unwrap().unwrap();
+        assert_eq!(stored.retry_of, Some(original.id.0));
+
+        let duplicate = retry_job(&store, None, original.id).await;
+        assert!(matches!(duplicate, Err(Error::InvalidInput(_))), "second retry is a duplicate");
+
+        let scheduler = JobScheduler::new(1, 10);
+        assert_eq!(adopt_queued_retries(&store, &scheduler).await.unwrap(), 1);
+        assert_eq!(adopt_queued_retries(&store, &scheduler).await.unwrap(), 0);
+        assert!(scheduler.get_job(JobId(retry.id)).await.is_some());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Cancelling jobs a newer head commit replaced (`[bot] cancel_superseded`)
+//!
+//! Once a PR gets a new commit, or a branch is force-pushed, nobody will
+//! look at the old head's results, so its queued and running jobs only
+//! hold up the queue. Webhook processing calls [`cancel_superseded`]
+//! before enqueuing the new head's jobs. Ordinary pushes cancel nothing:
+//! each one's (possibly incremental) results still count.
+//!
+//! Commits a force-push dropped, and every commit of a deleted branch, are
+//! also *orphaned*: their jobs are marked with the reason
+//! (`JobPayload::orphaned`), report on their commit only — never on the
+//! PRs, whose sticky comments now belong to the new head — and leave the
+//! results feed. A deleted branch's pending jobs are cancelled whatever
+//! `cancel_superseded` says.
+
+use uuid::Uuid;
+
+use super::{JobScheduler, JobStatus, ProofJob, SupersedeScope};
+use crate::error::Result;
+use crate::store::Store;
+
+/// How many of a repository's most recent jobs are looked at for orphans.
+const ORPHAN_SCAN_LIMIT: usize = 500;
+
+/// Cancel `scope`'s jobs for commits other than `head_sha`, in the
+/// scheduler and in the store. Returns how many were cancelled.
+pub async fn cancel_superseded(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    repo_id: Uuid,
+    scope: SupersedeScope,
+    head_sha: &str,
+) -> Result<usize> {
+    let cancelled = scheduler.cancel_superseded(repo_id, &scope, head_sha).await;
+    record_cancelled(store, &cancelled, &format!("Superseded by {}", head_sha)).await?;
+    Ok(cancelled.len())
+}
+
+/// `branch` was deleted: cancel its queued and running jobs and mark all
+/// its jobs orphaned. Returns how many were cancelled.
+pub async fn branch_deleted(
+    store: &dyn Store,
+    scheduler: &JobScheduler,
+    repo_id: Uuid,
+    branch: &str,
+) -> Result<usize> {
+    let reason = format!("branch {} deleted", branch);
+    // No job is for the empty commit, so every job of the branch goes.
+    let scope = SupersedeScope::Branch(branch.to_string());
+    let cancelled = scheduler.cancel_superseded(repo_id, &scope, "").await;
+    record_cancelled(store, &cancelled, &format!("Cancelled: {}", reason)).await?;
+    mark_orphaned(store, repo_id, branch, |_| true, &reason).await?;
+    Ok(cancelled.len())
+}
+
+/// A force-push moved `branch` to `head_sha`, dropping `dropped`: mark
+/// the branch's jobs for those commits orphaned. Returns how many were.
+pub async fn force_pushed(
+    store: &dyn Store,
+    repo_id: Uuid,
+    branch: &str,
+    head_sha: &str,
+    dropped: &[String],
+) -> Result<usize> {
+    let reason = format!("force-pushed out of {} (now at {:.8})", branch, head_sha);
+    mark_orphaned(store, repo_id, branch, |sha| dropped.iter().any(|d| d == sha), &reason).await
+}
+
+/// Requeue the jobs of workers that stopped responding, recording the
+/// ones dropped as duplicates of a queued job (see
+/// [`JobScheduler::requeue_orphaned`]). Returns how many were dropped.
+pub async fn requeue_orphaned(store: &dyn Store, scheduler: &JobScheduler) -> Result<usize> {
+    let duplicates = scheduler.requeue_orphaned().await;
+    let message = "Cancelled: its worker stopped responding and the job was queued again";
+    record_cancelled(store, &duplicates, message).await?;
+    Ok(duplicates.len())
+}
+
+async fn record_cancelled(store: &dyn Store, cancelled: &[ProofJob], message: &str) -> Result<()> {
+    for job in cancelled {
+        if let Some(mut record) = store.get_job(job.id).await? {
+            record.status = JobStatus::Cancelled;
+            record.completed_at = job.completed_at;
+            record.error_message = Some(message.to_string());
+            store.update_job(&record).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Mark `branch`'s push jobs for commits `orphaned` accepts with `reason`,
+/// unless already marked. Returns how many were.
+async fn mark_orphaned(
+    store: &dyn Store,
+    repo_id: Uuid,
+    branch: &str,
+    orphaned: impl Fn(&str) -> bool,
+    reason: &str,
+) -> Result<usize> {
+    let mut marked = 0;
+    for mut record in store.list_jobs_for_repo(repo_id, ORPHAN_SCAN_LIMIT).await? {
+        if record.pr_number.is_some()
+            || record.branch.as_deref() != Some(branch)
+            || record.payload.orphaned.is_some()
+            || !orphaned(&record.commit_sha)
+        {
+            continue;
+        }
+        record.payload.orphaned = Some(reason.to_string());
+        store.update_job(&record).await?;
+        marked += 1;
+    }
+    if marked > 0 {
+        tracing::info!("Marked {} job(s) orphaned: {}", marked, reason);
+    }
+    Ok(marked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::JobId;
+    use crate::store::models::{ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn deleted_and_force_pushed_branches_orphan_their_jobs() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let scheduler = JobScheduler::new(2, 10);
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let job = |sha: &str, branch: &str| {
+            ProofJob::new(repo.id, sha.into(), ProverKind::new("coq"), vec![]).with_branch(Some(branch.into()))
+        };
+        let mut finished = ProofJobRecord::from(job("old", "topic"));
+        finished.status = JobStatus::Completed;
+        store.create_job(&finished).await.unwrap();
+        let queued = job("new", "topic");
+        store.create_job(&ProofJobRecord::from(queued.clone())).await.unwrap();
+        scheduler.enqueue(queued.clone()).await.unwrap();
+        let other = job("kept", "main");
+        store.create_job(&ProofJobRecord::from(other.clone())).await.unwrap();
+
+        // A force-push dropping `old` only marks that commit's job.
+        assert_eq!(force_pushed(&store, repo.id, "topic", "new", &["old".into()]).await.unwrap(), 1);
+        let record = store.get_job(JobId(finished.id)).await.unwrap().unwrap();
+        assert!(record.payload.orphaned.unwrap().starts_with("force-pushed out of topic"));
+        assert!(record.pull_requests().is_empty());
+
+        // Deleting the branch cancels what is pending and orphans the rest.
+        assert_eq!(branch_deleted(&store, &scheduler, repo.id, "topic").await.unwrap(), 1);
+        let record = store.get_job(queued.id).await.unwrap().unwrap();
+        assert_eq!(record.status, JobStatus::Cancelled);
+        assert_eq!(record.payload.orphaned.as_deref(), Some("branch topic deleted"));
+        assert!(store.get_job(other.id).await.unwrap().unwrap().payload.orphaned.is_none());
+        assert_eq!(scheduler.queue_depth(), 0);
+    }
+}
@@ -0,0 +1,338 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Queue wait objectives (`[slo]`)
+//!
+//! PR checks are promised to start within two minutes of being queued.
+//! `[slo] queue_wait_secs` sets such a limit per job priority; the share
+//! of a priority's jobs that started within it over a rolling window
+//! (`window_secs`) is its compliance, and the objective holds while that
+//! is at least `target`. Jobs still queued past the limit count against
+//! it; jobs still within it, or cancelled before starting, are not
+//! counted.
+//!
+//! Compliance is computed from the stored jobs when read
+//! ([`Store::queue_waits`]): on `/metrics`, by the `queueSlo` GraphQL
+//! query, and every `check_interval_secs` by [`run_slo_monitor`], which
+//! alerts when an objective becomes breached and again when it recovers.
+//! Alerts are logged and POSTed to `alert_webhook_url` as JSON whose
+//! `text` field suits Slack and Mattermost incoming webhooks.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::SloConfig;
+use crate::error::Result;
+use crate::scheduler::{JobPriority, JobScheduler};
+use crate::shutdown::ShutdownSignal;
+use crate::store::Store;
+
+/// The `[slo.queue_wait_secs]` key naming `priority`.
+pub fn priority_name(priority: JobPriority) -> &'static str {
+    match priority {
+        JobPriority::Low => "low",
+        JobPriority::Normal => "normal",
+        JobPriority::High => "high",
+        JobPriority::Critical => "critical",
+    }
+}
+
+fn parse_priority(name: &str) -> Option<JobPriority> {
+    match name.to_lowercase().as_str() {
+        "low" => Some(JobPriority::Low),
+        "normal" => Some(JobPriority::Normal),
+        "high" => Some(JobPriority::High),
+        "critical" => Some(JobPriority::Critical),
+        _ => None,
+    }
+}
+
+/// `90s`, `2m`, `1h`, `1d`: the largest unit dividing `secs`.
+pub fn format_secs(secs: u64) -> String {
+    match secs {
+        0 => "0s".to_string(),
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3_600 == 0 => format!("{}h", s / 3_600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// One priority's objective over one window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloStatus {
+    #[serde(serialize_with = "serialize_priority")]
+    pub priority: JobPriority,
+    /// Longest allowed wait.
+    pub limit_secs: u64,
+    pub window_secs: u64,
+    pub target: f64,
+    /// Jobs counted: started, or still queued past the limit.
+    pub jobs: u64,
+    /// Of them, how many started within the limit.
+    pub within: u64,
+    /// `within / jobs`; `None` without jobs.
+    pub compliance: Option<f64>,
+    pub avg_wait_ms: Option<f64>,
+    pub max_wait_ms: Option<f64>,
+}
+
+fn serialize_priority<S: serde::Serializer>(priority: &JobPriority, s: S) -> std::result::Result<S::Ok, S::Error> {
+    s.serialize_str(priority_name(*priority))
+}
+
+impl SloStatus {
+    /// Compliance is below target. A window without jobs is never breached.
+    pub fn breached(&self) -> bool {
+        self.compliance.is_some_and(|c| c < self.target)
+    }
+}
+
+/// Every configured objective over every window, highest priority first.
+/// Unknown priority names are skipped with a warning.
+pub async fn evaluate(store: &dyn Store, config: &SloConfig, now: DateTime<Utc>) -> Result<Vec<SloStatus>> {
+    let mut objectives: Vec<(JobPriority, u64)> = Vec::new();
+    for (name, limit_secs) in &config.queue_wait_secs {
+        match parse_priority(name) {
+            Some(priority) => objectives.push((priority, *limit_secs)),
+            None => tracing::warn!("[slo.queue_wait_secs] unknown priority '{}' ignored", name),
+        }
+    }
+    objectives.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut statuses = Vec::new();
+    for (priority, limit_secs) in objectives {
+        for &window_secs in &config.window_secs {
+            let since = now - chrono::Duration::seconds(window_secs as i64);
+            let waits = store.queue_waits(since, priority, limit_secs * 1000, now).await?;
+            statuses.push(SloStatus {
+                priority,
+                limit_secs,
+                window_secs,
+                target: config.target,
+                jobs: waits.jobs,
+                within: waits.within,
+                compliance: (waits.jobs > 0).then(|| waits.within as f64 / waits.jobs as f64),
+                avg_wait_ms: waits.avg_wait_ms,
+                max_wait_ms: waits.max_wait_ms,
+            });
+        }
+    }
+    Ok(statuses)
+}
+
+/// Prometheus text exposition, appended to `/metrics`.
+pub fn to_prometheus(statuses: &[SloStatus]) -> String {
+    let mut compliance = String::new();
+    let mut breached = String::new();
+    let mut avg = String::new();
+    let mut max = String::new();
+    for status in statuses {
+        let labels = format!(
+            "priority=\"{}\",window=\"{}\"",
+            priority_name(status.priority),
+            format_secs(status.window_secs)
+        );
+        if let Some(c) = status.compliance {
+            compliance.push_str(&format!("echidnabot_queue_wait_slo_compliance{{{}}} {}\n", labels, c));
+        }
+        breached.push_str(&format!(
+            "echidnabot_queue_wait_slo_breached{{{}}} {}\n",
+            labels,
+            u8::from(status.breached())
+        ));
+        if let Some(ms) = status.avg_wait_ms {
+            avg.push_str(&format!("echidnabot_queue_wait_avg_seconds{{{}}} {:.3}\n", labels, ms / 1000.0));
+        }
+        if let Some(ms) = status.max_wait_ms {
+            max.push_str(&format!("echidnabot_queue_wait_max_seconds{{{}}} {:.3}\n", labels, ms / 1000.0));
+        }
+    }
+    let target = statuses.first().map(|s| s.target).unwrap_or_default();
+    format!(
+        "# HELP echidnabot_queue_wait_slo_compliance Share of jobs that started within their priority's queue wait limit\n\
+         # TYPE echidnabot_queue_wait_slo_compliance gauge\n\
+         {compliance}\
+         # HELP echidnabot_queue_wait_slo_target Compliance the queue wait objectives require\n\
+         # TYPE echidnabot_queue_wait_slo_target gauge\n\
+         echidnabot_queue_wait_slo_target {target}\n\
+         # HELP echidnabot_queue_wait_slo_breached Whether compliance is below target\n\
+         # TYPE echidnabot_queue_wait_slo_breached gauge\n\
+         {breached}\
+         # HELP echidnabot_queue_wait_avg_seconds Mean time from queued to started\n\
+         # TYPE echidnabot_queue_wait_avg_seconds gauge\n\
+         {avg}\
+         # HELP echidnabot_queue_wait_max_seconds Longest time from queued to started\n\
+         # TYPE echidnabot_queue_wait_max_seconds gauge\n\
+         {max}"
+    )
+}
+
+/// A change in whether an objective holds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloAlert {
+    /// `breached` or `recovered`.
+    pub event: &'static str,
+    pub text: String,
+    pub status: SloStatus,
+}
+
+impl SloAlert {
+    fn new(status: &SloStatus) -> Self {
+        let event = if status.breached() { "breached" } else { "recovered" };
+        let mut text = format!(
+            "Queue wait SLO {}: {:.1}% of {}-priority jobs started within {} over the last {} (target {:.1}%, {} jobs)",
+            event,
+            status.compliance.unwrap_or(1.0) * 100.0,
+            priority_name(status.priority),
+            format_secs(status.limit_secs),
+            format_secs(status.window_secs),
+            status.target * 100.0,
+            status.jobs
+        );
+        if let Some(ms) = status.max_wait_ms {
+            text.push_str(&format!(", longest wait {}s", (ms / 1000.0).round() as u64));
+        }
+        Self { event, text, status: status.clone() }
+    }
+}
+
+/// Objectives currently breached, so that each breach alerts once.
+#[derive(Debug, Default)]
+pub struct AlertState {
+    breached: BTreeSet<(JobPriority, u64, u64)>,
+}
+
+impl AlertState {
+    /// Alerts for objectives that became breached or recovered since the
+    /// last call.
+    pub fn update(&mut self, statuses: &[SloStatus]) -> Vec<SloAlert> {
+        let mut alerts = Vec::new();
+        let mut breached = BTreeSet::new();
+        for status in statuses {
+            let key = (status.priority, status.limit_secs, status.window_secs);
+            if status.breached() {
+                breached.insert(key);
+            }
+            if status.breached() != self.breached.contains(&key) {
+                alerts.push(SloAlert::new(status));
+            }
+        }
+        self.breached = breached;
+        alerts
+    }
+}
+
+pub async fn send_alert(client: &reqwest::Client, url: &str, alert: &SloAlert) -> Result<()> {
+    client.post(url).json(alert).send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Check the scheduler's objectives every `check_interval_secs` until
+/// shutdown, alerting on changes. Reads the objectives afresh each time,
+/// so a reload applies without a restart.
+pub async fn run_slo_monitor(store: Arc<dyn Store>, scheduler: Arc<JobScheduler>, shutdown: ShutdownSignal) {
+    let client = reqwest::Client::new();
+    let mut state = AlertState::default();
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        let config = scheduler.slo();
+        let interval = Duration::from_secs(match config.check_interval_secs {
+            0 => 60,
+            secs => secs,
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("SLO monitor observed shutdown signal — stopping");
+                return;
+            }
+        }
+        let config = scheduler.slo();
+        if config.check_interval_secs == 0 {
+            continue;
+        }
+        let statuses = match evaluate(store.as_ref(), &config, Utc::now()).await {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                tracing::warn!("Evaluating queue wait SLOs failed: {}", e);
+                continue;
+            }
+        };
+        for alert in state.update(&statuses) {
+            if alert.status.breached() {
+                tracing::warn!("{}", alert.text);
+            } else {
+                tracing::info!("{}", alert.text);
+            }
+            if let Some(url) = &config.alert_webhook_url {
+                if let Err(e) = send_alert(&client, url, &alert).await {
+                    tracing::warn!("Sending SLO alert failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobStatus, ProofJob};
+    use crate::store::models::{ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[tokio::test]
+    async fn compliance_counts_late_and_still_waiting_jobs_and_alerts_on_change() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let now = Utc::now();
+        // (priority, queued minutes ago, waited seconds; None = still queued)
+        for (priority, ago, wait) in [
+            (JobPriority::High, 30, Some(10)),
+            (JobPriority::High, 20, Some(60)),
+            (JobPriority::High, 10, Some(300)),
+            (JobPriority::High, 5, None),
+            (JobPriority::High, 0, None),
+            (JobPriority::Low, 10, Some(900)),
+        ] {
+            let mut job = ProofJobRecord::from(
+                ProofJob::new(repo.id, "abc".into(), ProverKind::new("coq"), vec![]).with_priority(priority),
+            );
+            job.queued_at = now - chrono::Duration::minutes(ago);
+            if let Some(wait) = wait {
+                job.started_at = Some(job.queued_at + chrono::Duration::seconds(wait));
+                job.status = JobStatus::Completed;
+            }
+            store.create_job(&job).await.unwrap();
+        }
+
+        let config = SloConfig { window_secs: vec![3600], ..SloConfig::default() };
+        let statuses = evaluate(&store, &config, now).await.unwrap();
+        assert_eq!(statuses.len(), 1, "only high priority has an objective");
+        let status = &statuses[0];
+        // The job queued just now is not counted yet; the one queued five
+        // minutes ago has already waited too long.
+        assert_eq!((status.jobs, status.within), (4, 2));
+        assert!(status.breached());
+        assert!((status.max_wait_ms.unwrap() - 300_000.0).abs() < 1.0);
+        assert!(to_prometheus(&statuses)
+            .contains("echidnabot_queue_wait_slo_compliance{priority=\"high\",window=\"1h\"} 0.5"));
+
+        let mut alerts = AlertState::default();
+        let fired = alerts.update(&statuses);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].event, "breached");
+        assert!(fired[0].text.contains("50.0% of high-priority jobs started within 2m"), "{}", fired[0].text);
+        assert!(alerts.update(&statuses).is_empty(), "a breach alerts once");
+        let recovered = SloStatus { within: 4, compliance: Some(1.0), ..status.clone() };
+        assert_eq!(alerts.update(&[recovered])[0].event, "recovered");
+    }
+}
@@ -0,0 +1,486 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Stale proof-failure policy (`[stale_failures]` in the repo manifest)
+//!
+//! A periodic sweep ([`run_stale_sweeper`], every
+//! `[scheduler] stale_sweep_interval_secs`) walks the PRs of every enabled
+//! repository and works out how long each one's proofs have been failing.
+//! The clock starts at the oldest commit of the PR's current run of
+//! failing head commits, so pushing another broken commit does not reset
+//! it; a green head does, and a head still being verified is left alone.
+//!
+//! What happens at a given age is the repo's own schedule (see
+//! [`StaleFailuresSection`]): a reminder comment, repeated at an interval;
+//! escalation labels; conversion to draft; and finally closing the PR.
+//! Progress is kept per PR in the store, so each step fires once per
+//! failure streak and survives restarts. Every step taken is written to
+//! the `echidnabot::audit` tracing target.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::adapters::{build_adapter, PlatformAdapter, PrId, RepoId};
+use crate::audit::AuditAction;
+use crate::automerge::tally_jobs;
+use crate::config::{Config, SharedConfig};
+use crate::error::Result;
+use crate::modes::{fetch_directive_via_adapter, RepoManifest, StaleFailuresSection};
+use crate::scheduler::JobStatus;
+use crate::shutdown::ShutdownSignal;
+use crate::store::models::{ProofJobRecord, Repository, StaleNagRecord};
+use crate::store::Store;
+
+/// Recent jobs per repository considered by a sweep.
+const JOB_SCAN_LIMIT: usize = 500;
+
+/// Where a PR stands, judged from its newest commits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrHealth {
+    Green,
+    /// The head commit still has queued or running jobs.
+    Pending,
+    Failing {
+        head_sha: String,
+        since: DateTime<Utc>,
+    },
+}
+
+/// One step of the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleAction {
+    Remind,
+    Escalate,
+    Draft,
+    Close,
+}
+
+impl StaleAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StaleAction::Remind => "remind",
+            StaleAction::Escalate => "escalate",
+            StaleAction::Draft => "draft",
+            StaleAction::Close => "close",
+        }
+    }
+}
+
+/// Judge a PR from its jobs, newest first.
+pub async fn pr_health(store: &dyn Store, pr_jobs: &[ProofJobRecord]) -> Result<PrHealth> {
+    let mut commits: Vec<&str> = Vec::new();
+    for job in pr_jobs {
+        if !commits.contains(&job.commit_sha.as_str()) {
+            commits.push(&job.commit_sha);
+        }
+    }
+
+    let mut since: Option<DateTime<Utc>> = None;
+    for (i, sha) in commits.iter().enumerate() {
+        let jobs: Vec<&ProofJobRecord> = pr_jobs.iter().filter(|j| j.commit_sha == *sha).collect();
+        let checks = tally_jobs(store, &jobs).await?;
+        if checks.failed == 0 {
+            if i == 0 {
+                return Ok(if checks.is_green() { PrHealth::Green } else { PrHealth::Pending });
+            }
+            break;
+        }
+        since = jobs
+            .iter()
+            .filter(|j| j.status != JobStatus::Cancelled)
+            .map(|j| j.completed_at.unwrap_or(j.queued_at))
+            .min()
+            .or(since);
+    }
+
+    match (commits.first(), since) {
+        (Some(head), Some(since)) => Ok(PrHealth::Failing {
+            head_sha: head.to_string(),
+            since,
+        }),
+        _ => Ok(PrHealth::Green),
+    }
+}
+
+/// The steps due for `nag` at `now`. Closing supersedes everything else.
+pub fn plan(
+    policy: &StaleFailuresSection,
+    nag: &StaleNagRecord,
+    pr_is_draft: bool,
+    now: DateTime<Utc>,
+) -> Vec<StaleAction> {
+    if nag.closed {
+        return Vec::new();
+    }
+    let days = (now - nag.failing_since).num_days().max(0) as u32;
+    let reached = |threshold: Option<u32>| threshold.is_some_and(|d| days >= d);
+
+    if reached(policy.close_after_days) {
+        return vec![StaleAction::Close];
+    }
+
+    let mut actions = Vec::new();
+    let remind_due = match nag.last_reminded_at {
+        None => true,
+        Some(last) => (now - last).num_days() >= i64::from(policy.remind_every_days.max(1)),
+    };
+    if days >= policy.remind_after_days && remind_due {
+        actions.push(StaleAction::Remind);
+    }
+    if reached(policy.escalate_after_days) && !nag.escalated && !policy.escalate_labels.is_empty() {
+        actions.push(StaleAction::Escalate);
+    }
+    if reached(policy.draft_after_days) && !nag.drafted && !pr_is_draft {
+        actions.push(StaleAction::Draft);
+    }
+    actions
+}
+
+/// Run [`sweep`] every `interval` until shutdown.
+pub async fn run_stale_sweeper(
+    store: Arc<dyn Store>,
+    config: SharedConfig,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Stale-failure sweeper observed shutdown signal — stopping");
+                return;
+            }
+        }
+        match sweep(store.as_ref(), &config.load_full(), Utc::now()).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Stale-failure sweep acted on {} PR(s)", n),
+            Err(e) => tracing::warn!("Stale-failure sweep failed: {}", e),
+        }
+    }
+}
+
+/// Apply each enabled repository's policy once. Returns how many PRs
+/// had at least one step taken.
+pub async fn sweep(store: &dyn Store, config: &Config, now: DateTime<Utc>) -> Result<usize> {
+    let mut acted = 0;
+    for repo in store.list_repositories(None).await? {
+        if !repo.enabled {
+            continue;
+        }
+        let jobs = store.list_jobs_for_repo(repo.id, JOB_SCAN_LIMIT).await?;
+        if !jobs.iter().any(|j| !j.pull_requests().is_empty()) {
+            continue;
+        }
+
+        let adapter = build_adapter(config, repo.platform)?;
+        let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+        let policy = fetch_directive_via_adapter(adapter.as_ref(), &repo_id, None)
+            .await
+            .as_deref()
+            .and_then(RepoManifest::parse)
+            .unwrap_or_default()
+            .stale_failures;
+        if !policy.enabled {
+            continue;
+        }
+        let capabilities = adapter.capabilities();
+        if !capabilities.pull_requests || !capabilities.comments {
+            tracing::warn!(
+                "[stale_failures] is enabled for {}, but its platform adapter can't read or comment on PRs",
+                repo.full_name()
+            );
+            continue;
+        }
+
+        // Push jobs count for the PRs their commit was found in.
+        let mut prs: Vec<u64> = jobs.iter().flat_map(|j| j.pull_requests()).collect();
+        prs.sort_unstable();
+        prs.dedup();
+        for pr in prs {
+            let pr_jobs: Vec<ProofJobRecord> =
+                jobs.iter().filter(|j| j.pull_requests().contains(&pr)).cloned().collect();
+            match sweep_pr(store, adapter.as_ref(), &repo, &policy, pr, &pr_jobs, now).await {
+                Ok(true) => acted += 1,
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Stale-failure check of {} PR #{} failed: {}", repo.full_name(), pr, e),
+            }
+        }
+    }
+    Ok(acted)
+}
+
+async fn sweep_pr(
+    store: &dyn Store,
+    adapter: &dyn PlatformAdapter,
+    repo: &Repository,
+    policy: &StaleFailuresSection,
+    pr_number: u64,
+    pr_jobs: &[ProofJobRecord],
+    now: DateTime<Utc>,
+) -> Result<bool> {
+    let existing = store.get_stale_nag(repo.id, pr_number).await?;
+    let (head_sha, since) = match pr_health(store, pr_jobs).await? {
+        PrHealth::Failing { head_sha, since } => (head_sha, since),
+        PrHealth::Green => {
+            if existing.is_some() {
+                store.delete_stale_nag(repo.id, pr_number).await?;
+            }
+            return Ok(false);
+        }
+        PrHealth::Pending => return Ok(false),
+    };
+
+    let mut nag = match existing {
+        Some(nag) if nag.failing_since == since => nag,
+        _ => StaleNagRecord::new(repo.id, pr_number, since),
+    };
+    if nag.closed {
+        return Ok(false);
+    }
+
+    let repo_id = RepoId::new(repo.platform, repo.owner.clone(), repo.name.clone());
+    let pr = adapter.get_pull_request(&repo_id, PrId(pr_number.to_string())).await?;
+    if !pr.open {
+        store.delete_stale_nag(repo.id, pr_number).await?;
+        return Ok(false);
+    }
+
+    let mut actions = plan(policy, &nag, pr.draft, now);
+    // Without labels, escalation is left to the reminders.
+    if !adapter.capabilities().labels {
+        actions.retain(|action| *action != StaleAction::Escalate);
+    }
+    let days = (now - nag.failing_since).num_days().max(0);
+    let announcement = if actions.is_empty() {
+        None
+    } else {
+        crate::announcement::active(store).await
+    };
+    let with_banner = |body: &str| crate::announcement::with_banner(announcement.as_ref(), body);
+    let mut acted = false;
+    for action in actions {
+        let pr_id = PrId(pr_number.to_string());
+        let (outcome, payload) = match action {
+            StaleAction::Remind => {
+                let body = with_banner(&reminder_body(days, &head_sha, policy));
+                let outcome = adapter.create_comment(&repo_id, pr_id, &body).await.map(|_| {
+                    nag.reminders += 1;
+                    nag.last_reminded_at = Some(now);
+                });
+                (outcome, body)
+            }
+            StaleAction::Escalate => (
+                adapter
+                    .add_labels(&repo_id, pr_id, &policy.escalate_labels)
+                    .await
+                    .map(|_| nag.escalated = true),
+                policy.escalate_labels.join(","),
+            ),
+            StaleAction::Draft => (
+                adapter
+                    .mark_pull_request_draft(&repo_id, pr_id)
+                    .await
+                    .map(|_| nag.drafted = true),
+                String::new(),
+            ),
+            StaleAction::Close => {
+                let body = with_banner(&format!(
+                    "🔒 Closing this PR: its proofs have been failing for {} days (head `{:.8}`). \
+                     Reopen it once they pass.",
+                    days, head_sha
+                ));
+                match adapter.create_comment(&repo_id, pr_id.clone(), &body).await {
+                    Ok(_) => {
+                        let target = Some(format!("PR #{}", pr_number));
+                        crate::audit::record(store, crate::audit::BOT, AuditAction::CommentPosted, Some(repo), target, &body)
+                            .await
+                    }
+                    Err(e) => tracing::warn!("Closing comment on PR #{} failed: {}", pr_number, e),
+                }
+                let outcome = adapter
+                    .close_pull_request(&repo_id, pr_id)
+                    .await
+                    .map(|_| nag.closed = true);
+                (outcome, body)
+            }
+        };
+        audit(repo, pr_number, &head_sha, days, action, &outcome);
+        if outcome.is_ok() {
+            let recorded = match action {
+                StaleAction::Remind => AuditAction::CommentPosted,
+                StaleAction::Escalate => AuditAction::PullRequestLabeled,
+                StaleAction::Draft => AuditAction::PullRequestMarkedDraft,
+                StaleAction::Close => AuditAction::PullRequestClosed,
+            };
+            let target = Some(format!("PR #{}", pr_number));
+            crate::audit::record(store, crate::audit::BOT, recorded, Some(repo), target, payload).await;
+        }
+        acted |= outcome.is_ok();
+    }
+
+    store.upsert_stale_nag(&nag).await?;
+    Ok(acted)
+}
+
+fn reminder_body(days: i64, head_sha: &str, policy: &StaleFailuresSection) -> String {
+    let mut body = format!(
+        "⏰ Proofs on this PR have been failing for {} day{} (head `{:.8}`).",
+        days,
+        if days == 1 { "" } else { "s" },
+        head_sha
+    );
+    let upcoming: Vec<String> = [
+        (policy.draft_after_days, "converted to draft"),
+        (policy.close_after_days, "closed"),
+    ]
+    .iter()
+    .filter_map(|(after, what)| {
+        after
+            .filter(|d| i64::from(*d) > days)
+            .map(|d| format!("{} after {} days", what, d))
+    })
+    .collect();
+    if !upcoming.is_empty() {
+        body.push_str(&format!(" Per this repo's policy it will be {}.", upcoming.join(" and ")));
+    }
+    body
+}
+
+fn audit(
+    repo: &Repository,
+    pr_number: u64,
+    head_sha: &str,
+    days: i64,
+    action: StaleAction,
+    outcome: &Result<()>,
+) {
+    let (result, detail) = match outcome {
+        Ok(()) => ("done", String::new()),
+        Err(e) => ("failed", e.to_string()),
+    };
+    tracing::info!(
+        target: "echidnabot::audit",
+        action = "stale_failure",
+        step = action.as_str(),
+        repo = %repo.full_name(),
+        pr = pr_number,
+        commit = head_sha,
+        days,
+        result,
+        detail = %detail,
+        "Stale-failure {} {} for {} PR #{}",
+        action.as_str(),
+        result,
+        repo.full_name(),
+        pr_number
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobResult, ProofJob};
+    use crate::store::models::ProofResultRecord;
+    use crate::store::SqliteStore;
+    use uuid::Uuid;
+
+    fn policy() -> StaleFailuresSection {
+        RepoManifest::parse(
+            "[stale_failures]\nenabled = true\nremind_after_days = 2\nremind_every_days = 2\n\
+             escalate_after_days = 5\ndraft_after_days = 10\nclose_after_days = 20",
+        )
+        .unwrap()
+        .stale_failures
+    }
+
+    #[test]
+    fn plan_follows_the_schedule() {
+        let now = Utc::now();
+        let nag_aged = |days: i64| StaleNagRecord::new(Uuid::new_v4(), 1, now - chrono::Duration::days(days));
+        let p = policy();
+
+        assert!(plan(&p, &nag_aged(1), false, now).is_empty());
+        assert_eq!(plan(&p, &nag_aged(2), false, now), vec![StaleAction::Remind]);
+
+        let mut nag = nag_aged(6);
+        nag.last_reminded_at = Some(now - chrono::Duration::days(1));
+        assert_eq!(plan(&p, &nag, false, now), vec![StaleAction::Escalate]);
+        nag.escalated = true;
+        nag.last_reminded_at = Some(now - chrono::Duration::days(3));
+        assert_eq!(plan(&p, &nag, false, now), vec![StaleAction::Remind]);
+
+        let nag = nag_aged(12);
+        assert_eq!(
+            plan(&p, &nag, true, now),
+            vec![StaleAction::Remind, StaleAction::Escalate],
+            "already-draft PRs are not converted again"
+        );
+        assert_eq!(plan(&p, &nag_aged(25), false, now), vec![StaleAction::Close]);
+    }
+
+    #[tokio::test]
+    async fn failure_streak_spans_failing_commits_and_green_resets() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-stale-test-{}.db", Uuid::new_v4()));
+        let store = SqliteStore::new(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let finish = |sha: &str, success: bool, days_ago: i64| {
+            let job = ProofJob::new(repo.id, sha.into(), ProverKind::new("coq"), vec![])
+                .with_context(Some(3), None);
+            let mut record = ProofJobRecord::from(job.clone());
+            record.status = if success { JobStatus::Completed } else { JobStatus::Failed };
+            record.completed_at = Some(Utc::now() - chrono::Duration::days(days_ago));
+            let result = ProofResultRecord::new(
+                job.id,
+                &JobResult {
+                    success,
+                    message: String::new(),
+                    prover_output: String::new(),
+                    duration_ms: 1,
+                    verified_files: vec![],
+                    failed_files: vec![],
+                    confidence: None,
+                    axioms: None,
+                    prover_version: None,
+                    prover_args: vec![],
+                    execution_path: None,
+                },
+            );
+            (record, result)
+        };
+
+        // Newest first, as list_jobs_for_repo returns them.
+        let mut jobs = Vec::new();
+        for (sha, success, days_ago) in [("c3", false, 1), ("c2", false, 4), ("c1", true, 6)] {
+            let (record, result) = finish(sha, success, days_ago);
+            store.create_job(&record).await.unwrap();
+            store.save_result(&result).await.unwrap();
+            jobs.push(record);
+        }
+        match pr_health(&store, &jobs).await.unwrap() {
+            PrHealth::Failing { head_sha, since } => {
+                assert_eq!(head_sha, "c3");
+                assert_eq!((Utc::now() - since).num_days(), 4, "clock starts at c2");
+            }
+            other => panic!("expected failing, got {:?}", other),
+        }
+
+        let (record, result) = finish("c4", true, 0);
+        store.create_job(&record).await.unwrap();
+        store.save_result(&result).await.unwrap();
+        jobs.insert(0, record);
+        assert_eq!(pr_health(&store, &jobs).await.unwrap(), PrHealth::Green);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Instance statistics for capacity planning
+//!
+//! An overview of how the instance was used over a [`Period`]: jobs per
+//! day, how long jobs waited in the queue, how much of the scheduler's
+//! capacity each prover took, which repositories used the most run time,
+//! and how many escape hatches (`sorry`, `Admitted`, new axioms; see
+//! [`crate::trust::escape_hatch`]) their jobs' files contained. Computed
+//! with aggregate store queries ([`Store::job_usage`]), not by loading
+//! jobs. Read it with the `instanceStats` GraphQL query (admin scope) or
+//! `echidnabot stats`.
+//!
+//! Utilization is run time over capacity: busy time divided by the
+//! period's length times `[scheduler] max_concurrent`. Jobs are counted in
+//! the period they were queued in, and only finished jobs add run time.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::error::{Error, Result};
+use crate::store::models::{JobUsage, UsageKey};
+use crate::store::Store;
+
+/// How far back statistics go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl Period {
+    pub fn days(self) -> i64 {
+        match self {
+            Period::Day => 1,
+            Period::Week => 7,
+            Period::Month => 30,
+        }
+    }
+}
+
+impl std::str::FromStr for Period {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "day" | "24h" => Ok(Period::Day),
+            "week" | "7d" => Ok(Period::Week),
+            "month" | "30d" => Ok(Period::Month),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown period '{}' (expected day, week or month)",
+                s
+            ))),
+        }
+    }
+}
+
+/// One prover's share of the period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProverStats {
+    pub prover: String,
+    pub jobs: u64,
+    pub failed: u64,
+    pub avg_wait_ms: Option<f64>,
+    pub busy_ms: u64,
+    /// Fraction of the scheduler's capacity its jobs ran for.
+    pub utilization: f64,
+}
+
+/// A repository's use of the period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepoStats {
+    pub repo_id: Uuid,
+    /// `owner/name`; `None` once the repository is deleted.
+    pub name: Option<String>,
+    pub jobs: u64,
+    pub busy_ms: u64,
+    /// Escape hatches found in the files its jobs checked.
+    pub escape_hatches: u64,
+}
+
+/// Statistics for the period `since..until`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceStats {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// `[scheduler] max_concurrent` the utilization is relative to.
+    pub capacity: usize,
+    pub jobs: u64,
+    pub failed: u64,
+    pub jobs_per_day: f64,
+    pub avg_wait_ms: Option<f64>,
+    pub utilization: f64,
+    /// Escape hatches found in the files the period's jobs checked.
+    pub escape_hatches: u64,
+    /// Jobs queued per UTC day (`YYYY-MM-DD`), oldest first; days without
+    /// jobs are left out.
+    pub daily: Vec<(String, u64)>,
+    /// Busiest first.
+    pub provers: Vec<ProverStats>,
+    /// The repositories with the most run time, busiest first.
+    pub top_repos: Vec<RepoStats>,
+}
+
+/// Mean wait over `rows`, weighted by how many jobs of each started.
+fn mean_wait(rows: &[JobUsage]) -> Option<f64> {
+    let started: u64 = rows.iter().filter(|r| r.avg_wait_ms.is_some()).map(|r| r.started).sum();
+    let total: f64 = rows
+        .iter()
+        .filter_map(|r| r.avg_wait_ms.map(|ms| ms * r.started as f64))
+        .sum();
+    (started > 0).then(|| total / started as f64)
+}
+
+/// Statistics for the `period` up to now, utilization relative to
+/// `capacity` concurrent jobs, with the `top` busiest repositories.
+pub async fn collect(store: &dyn Store, period: Period, capacity: usize, top: usize) -> Result<InstanceStats> {
+    let until = Utc::now();
+    let since = until - Duration::days(period.days());
+    let capacity_ms = (period.days() * 86_400_000) as f64 * capacity.max(1) as f64;
+    let utilization = |busy_ms: u64| busy_ms as f64 / capacity_ms;
+
+    let by_prover = store.job_usage(since, UsageKey::Prover).await?;
+    let by_repo = store.job_usage(since, UsageKey::Repository).await?;
+    let by_day = store.job_usage(since, UsageKey::Day).await?;
+
+    let mut provers: Vec<ProverStats> = by_prover
+        .iter()
+        .map(|row| ProverStats {
+            prover: row.key.clone(),
+            jobs: row.jobs,
+            failed: row.failed,
+            avg_wait_ms: row.avg_wait_ms,
+            busy_ms: row.busy_ms,
+            utilization: utilization(row.busy_ms),
+        })
+        .collect();
+    provers.sort_by(|a, b| b.busy_ms.cmp(&a.busy_ms).then(b.jobs.cmp(&a.jobs)));
+
+    let mut repos: Vec<&JobUsage> = by_repo.iter().collect();
+    repos.sort_by(|a, b| b.busy_ms.cmp(&a.busy_ms).then(b.jobs.cmp(&a.jobs)));
+    let repos: Vec<(Uuid, &JobUsage)> = repos
+        .into_iter()
+        .filter_map(|row| Some((Uuid::parse_str(&row.key).ok()?, row)))
+        .take(top)
+        .collect();
+    let ids: Vec<Uuid> = repos.iter().map(|(id, _)| *id).collect();
+    let names: HashMap<Uuid, String> = store
+        .get_repositories_by_ids(&ids)
+        .await?
+        .into_iter()
+        .map(|repo| (repo.id, repo.full_name()))
+        .collect();
+    let top_repos = repos
+        .into_iter()
+        .map(|(id, row)| RepoStats {
+            repo_id: id,
+            name: names.get(&id).cloned(),
+            jobs: row.jobs,
+            busy_ms: row.busy_ms,
+            escape_hatches: row.escape_hatches,
+        })
+        .collect();
+
+    let jobs: u64 = by_prover.iter().map(|r| r.jobs).sum();
+    Ok(InstanceStats {
+        since,
+        until,
+        capacity: capacity.max(1),
+        jobs,
+        failed: by_prover.iter().map(|r| r.failed).sum(),
+        jobs_per_day: jobs as f64 / period.days() as f64,
+        avg_wait_ms: mean_wait(&by_prover),
+        utilization: utilization(by_prover.iter().map(|r| r.busy_ms).sum()),
+        escape_hatches: by_prover.iter().map(|r| r.escape_hatches).sum(),
+        daily: by_day.into_iter().map(|row| (row.key, row.jobs)).collect(),
+        provers,
+        top_repos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::{JobId, JobStatus, ProofJob};
+    use crate::store::models::{EscapeHatchRecord, ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+    use crate::trust::escape_hatch::EscapeHatch;
+
+    #[tokio::test]
+    async fn stats_aggregate_jobs_by_prover_repo_and_day() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let busy = Repository::new(Platform::GitHub, "o".into(), "busy".into());
+        let quiet = Repository::new(Platform::GitHub, "o".into(), "quiet".into());
+        store.create_repository(&busy).await.unwrap();
+        store.create_repository(&quiet).await.unwrap();
+
+        let now = Utc::now();
+        // (repo, prover, wait, run, failed)
+        for (repo, prover, wait, run, failed) in [
+            (&busy, "coq", 10, 3_600, false),
+            (&busy, "coq", 30, 3_600, true),
+            (&quiet, "lean", 20, 60, false),
+        ] {
+            let mut job = ProofJobRecord::from(ProofJob::new(repo.id, "abc".into(), ProverKind::new(prover), vec![]));
+            job.queued_at = now - Duration::hours(2);
+            job.started_at = Some(job.queued_at + Duration::seconds(wait));
+            job.completed_at = Some(job.queued_at + Duration::seconds(wait + run));
+            job.status = if failed { JobStatus::Failed } else { JobStatus::Completed };
+            store.create_job(&job).await.unwrap();
+            if prover == "coq" && !failed {
+                let hatch = EscapeHatch { file_path: "A.v".into(), line: 3, token: "Admitted".into() };
+                let hatches = [EscapeHatchRecord::new(JobId(job.id), hatch)];
+                store.record_escape_hatches(JobId(job.id), &hatches).await.unwrap();
+            }
+        }
+        // Older than the period: not counted.
+        let mut old = ProofJobRecord::from(ProofJob::new(quiet.id, "old".into(), ProverKind::new("lean"), vec![]));
+        old.queued_at = now - Duration::days(3);
+        store.create_job(&old).await.unwrap();
+
+        let stats = collect(&store, Period::Day, 2, 1).await.unwrap();
+        assert_eq!((stats.jobs, stats.failed), (3, 1));
+        assert_eq!(stats.provers[0].prover, "coq");
+        assert_eq!(stats.provers[0].busy_ms, 7_200_000);
+        // Two hours of run time over one day of two slots.
+        assert!((stats.provers[0].utilization - 2.0 / 48.0).abs() < 1e-6);
+        let wait = stats.avg_wait_ms.unwrap();
+        assert!((wait - 20_000.0).abs() < 1.0, "{}", wait);
+        assert_eq!(stats.top_repos.len(), 1);
+        assert_eq!(stats.top_repos[0].name.as_deref(), Some("o/busy"));
+        assert_eq!((stats.escape_hatches, stats.top_repos[0].escape_hatches), (1, 1));
+        assert_eq!(stats.daily.iter().map(|(_, n)| n).sum::<u64>(), 3);
+    }
+}
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Write-behind batching for non-critical store writes
+//!
+//! Per-file timings and tactic outcomes arrive by the dozen per job, and
+//! every authenticated request touches its token's `last_used_at`. Written
+//! one row at a time, each takes SQLite's write lock on its own and
+//! contends with the job and result writes that matter. With
+//! `[database.write_behind]` enabled, [`SqliteStore`] buffers these three
+//! kinds and writes them in one transaction:
+//!
+//! - once `max_rows` are pending,
+//! - every `flush_interval_ms` ([`run_write_behind`]),
+//! - before any read of buffered timings or outcomes, and
+//! - on [`SqliteStore::close`], which the shutdown sequence calls last.
+//!
+//! Token reads overlay pending touches instead of flushing, so
+//! authenticating does not defeat the batching. A failed flush keeps its
+//! rows for the next attempt. Jobs, results and repositories are never
+//! deferred.
+
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::models::{FileTimingRecord, TacticOutcomeRecord};
+use super::SqliteStore;
+use crate::config::WriteBehindConfig;
+use crate::shutdown::ShutdownSignal;
+
+/// Writes waiting for the next flush.
+#[derive(Debug, Default)]
+pub(crate) struct Pending {
+    pub file_timings: Vec<FileTimingRecord>,
+    pub tactic_outcomes: Vec<TacticOutcomeRecord>,
+    pub token_touches: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl Pending {
+    pub fn len(&self) -> usize {
+        self.file_timings.len() + self.tactic_outcomes.len() + self.token_touches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The buffer behind a batching [`SqliteStore`].
+pub(crate) struct WriteBehind {
+    max_rows: usize,
+    pending: Mutex<Pending>,
+}
+
+impl WriteBehind {
+    pub fn new(config: &WriteBehindConfig) -> Self {
+        Self {
+            max_rows: config.max_rows.max(1),
+            pending: Mutex::new(Pending::default()),
+        }
+    }
+
+    /// Buffer a write. Returns whether a flush is due.
+    pub fn push(&self, write: impl FnOnce(&mut Pending)) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        write(&mut pending);
+        pending.len() >= self.max_rows
+    }
+
+    /// Everything pending, leaving the buffer empty.
+    pub fn take(&self) -> Pending {
+        mem::take(&mut *self.pending.lock().unwrap())
+    }
+
+    /// Put back a batch that failed to flush, ahead of anything buffered
+    /// since. A touch buffered since wins over the restored one.
+    pub fn restore(&self, mut batch: Pending) {
+        let mut pending = self.pending.lock().unwrap();
+        batch.file_timings.append(&mut pending.file_timings);
+        batch.tactic_outcomes.append(&mut pending.tactic_outcomes);
+        batch.token_touches.extend(pending.token_touches.drain());
+        *pending = batch;
+    }
+
+    /// The pending `last_used_at` for a token, if any.
+    pub fn touched_at(&self, id: Uuid) -> Option<DateTime<Utc>> {
+        self.pending.lock().unwrap().token_touches.get(&id).copied()
+    }
+}
+
+/// Flush `store` every `interval` until shutdown, then once more.
+pub async fn run_write_behind(store: Arc<SqliteStore>, interval: Duration, shutdown: ShutdownSignal) {
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                if let Err(e) = store.flush().await {
+                    tracing::warn!("Final write-behind flush failed: {}", e);
+                }
+                tracing::info!("Write-behind flusher observed shutdown signal — stopping");
+                return;
+            }
+        }
+        match store.flush().await {
+            Ok(0) => {}
+            Ok(n) => tracing::debug!("Flushed {} buffered store write(s)", n),
+            Err(e) => tracing::warn!("Write-behind flush failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::JobId;
+    use crate::store::models::{ApiTokenRecord, TokenScope};
+    use crate::store::Store;
+
+    fn timing(job_id: JobId, repo_id: Uuid, file: &str) -> FileTimingRecord {
+        FileTimingRecord::new(
+            job_id,
+            repo_id,
+            ProverKind::new("coq"),
+            file.to_string(),
+            "abc123".to_string(),
+            42,
+            true,
+        )
+    }
+
+    #[tokio::test]
+    async fn buffered_writes_reach_the_database_on_flush_and_close() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-write-behind-test-{}.db", Uuid::new_v4()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let config = WriteBehindConfig {
+            enabled: true,
+            max_rows: 3,
+            flush_interval_ms: 60_000,
+        };
+        let store = SqliteStore::new(&url).await.unwrap().with_write_behind(&config);
+        let job_id = JobId::new();
+        let repo_id = Uuid::new_v4();
+
+        store.record_file_timing(&timing(job_id, repo_id, "A.v")).await.unwrap();
+        store.record_file_timing(&timing(job_id, repo_id, "B.v")).await.unwrap();
+        let unflushed: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM file_timings")
+            .fetch_one(store.pool())
+            .await
+            .unwrap();
+        assert_eq!(unflushed.0, 0, "below max_rows nothing is written yet");
+        // Reads see their own writes.
+        assert_eq!(store.list_file_timings_for_job(job_id).await.unwrap().len(), 2);
+
+        // Token touches are overlaid on reads without a flush.
+        let token = ApiTokenRecord::new("ci".into(), "hash".into(), TokenScope::Read);
+        store.create_api_token(&token).await.unwrap();
+        store.touch_api_token(token.id).await.unwrap();
+        let read = store.get_api_token_by_hash("hash").await.unwrap().unwrap();
+        assert!(read.last_used_at.is_some());
+
+        // Reaching max_rows flushes inline.
+        for file in ["C.v", "D.v", "E.v"] {
+            store.record_file_timing(&timing(job_id, repo_id, file)).await.unwrap();
+        }
+        store.touch_api_token(token.id).await.unwrap();
+        store.close().await;
+
+        let reopened = SqliteStore::new(&url).await.unwrap();
+        assert_eq!(reopened.list_file_timings_for_job(job_id).await.unwrap().len(), 5);
+        let read = reopened.get_api_token_by_hash("hash").await.unwrap().unwrap();
+        assert!(read.last_used_at.is_some(), "close() flushes the pending touch");
+        reopened.close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -4,8 +4,12 @@
 //! Persistent state store
 
 pub mod models;
+mod batch;
+mod pool;
 mod sqlite;
 
+pub use batch::run_write_behind;
+pub use pool::PoolStats;
 pub use sqlite::SqliteStore;
 
 use async_trait::async_trait;
@@ -14,8 +18,21 @@
 use crate::adapters::Platform;
 use crate::dispatcher::ProverKind;
 use crate::error::Result;
-use crate::scheduler::JobId;
-use models::{Repository, ProofJobRecord, ProofResultRecord, TacticOutcomeRecord};
+use crate::scheduler::{JobId, JobPriority, JobStatus};
+use crate::tenancy::MasterKey;
+use models::{
+    AnalyticsCursor, AnnouncementRecord, ApiTokenRecord, AuditFilter, AuditRecord, BisectionRecord, CertificateRecord, ConfirmationRecord, DependencyGraphRecord, EscapeHatchRecord, FederatedResultRecord, FileTimingRecord, FlakyRunRecord, JobEventRecord, JobFilter, JobOrder, PrCommentRecord, ProofItemRecord,
+    JobUsage, ProofJobRecord, ProofResultRecord, QuarantinedProof, QueueWaits, Repository, StaleNagRecord, TenantKeyRecord,
+    TacticOutcomeRecord, TrainingExampleRecord, UsageKey, WebhookDeliveryRecord,
+};
+
+/// What [`Store::purge_repository`] deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeSummary {
+    pub jobs: u64,
+    pub results: u64,
+    pub file_timings: u64,
+}
 
 /// Per-commit coverage view — total proof attempts vs successful ones.
 /// Empty results means no jobs run yet for that commit.
@@ -52,19 +69,83 @@ async fn get_repository_by_name(
         name: &str,
     ) -> Result<Option<Repository>>;
     async fn list_repositories(&self, platform: Option<Platform>) -> Result<Vec<Repository>>;
+    /// Like [`Store::list_repositories`], but a row that fails to decode
+    /// is an `Err` in its place instead of failing the whole list.
+    async fn list_repositories_partial(&self, platform: Option<Platform>) -> Result<Vec<Result<Repository>>>;
+    /// The repositories among `ids` that exist, in no particular order.
+    async fn get_repositories_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Repository>>;
     async fn update_repository(&self, repo: &Repository) -> Result<()>;
     async fn delete_repository(&self, id: Uuid) -> Result<()>;
+    /// Delete a repository with its jobs, results, file timings,
+    /// quarantine entries, stale-failure nags, PR comments and dependency
+    /// graphs, all or nothing. Tactic outcomes from its jobs stay, detached
+    /// from the job, for the feedback loop.
+    async fn purge_repository(&self, id: Uuid) -> Result<PurgeSummary>;
 
     // Job operations
     async fn create_job(&self, job: &ProofJobRecord) -> Result<()>;
     async fn get_job(&self, id: JobId) -> Result<Option<ProofJobRecord>>;
     async fn update_job(&self, job: &ProofJobRecord) -> Result<()>;
     async fn list_jobs_for_repo(&self, repo_id: Uuid, limit: usize) -> Result<Vec<ProofJobRecord>>;
+    /// Like [`Store::list_jobs_for_repo`], with per-row results.
+    async fn list_jobs_for_repo_partial(&self, repo_id: Uuid, limit: usize) -> Result<Vec<Result<ProofJobRecord>>>;
+    /// One page of the jobs matching `filter`, in `order`.
+    async fn search_jobs(
+        &self,
+        filter: &JobFilter,
+        order: JobOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>>;
+    /// How many jobs match `filter`.
+    async fn count_jobs(&self, filter: &JobFilter) -> Result<u64>;
+    /// The jobs among `ids` that exist, in no particular order.
+    async fn get_jobs_by_ids(&self, ids: &[JobId]) -> Result<Vec<ProofJobRecord>>;
+    /// Up to `limit` newest jobs of each repository in `repo_ids`.
+    async fn list_jobs_for_repos(&self, repo_ids: &[Uuid], limit: usize) -> Result<Vec<ProofJobRecord>>;
     async fn list_pending_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>>;
+    /// Running jobs, longest-running first.
+    async fn list_running_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>>;
+    /// Completed, failed and cancelled jobs, most recently finished first.
+    async fn list_recent_finished_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>>;
+    /// How many jobs are in each status. Statuses with none are omitted.
+    async fn count_jobs_by_status(&self) -> Result<Vec<(JobStatus, u64)>>;
+    /// Jobs finished after `since`, oldest first (usage telemetry).
+    async fn list_jobs_completed_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>>;
+    /// Jobs queued at or after `since`, aggregated by `key`, in key order
+    /// (see `crate::stats`).
+    async fn job_usage(&self, since: chrono::DateTime<chrono::Utc>, key: UsageKey) -> Result<Vec<JobUsage>>;
+    /// Queue waits of `priority` jobs queued at or after `since`, counting
+    /// jobs that started and those queued longer than `limit_ms` before
+    /// `now` (see `crate::slo`).
+    async fn queue_waits(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        priority: JobPriority,
+        limit_ms: u64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<QueueWaits>;
 
     // Result operations
     async fn save_result(&self, result: &ProofResultRecord) -> Result<()>;
     async fn get_result_for_job(&self, job_id: JobId) -> Result<Option<ProofResultRecord>>;
+    /// The results recorded for any of `job_ids`, in no particular order.
+    async fn get_results_by_job_ids(&self, job_ids: &[JobId]) -> Result<Vec<ProofResultRecord>>;
+    /// Record where the job's artifacts were archived (see `crate::archive`).
+    async fn set_result_archive_cid(&self, job_id: JobId, cid: &str) -> Result<()>;
+    /// Results recorded after `since`, oldest first (the federation feed).
+    /// Those recorded at `since` itself follow when `after` is given: the
+    /// ones whose id sorts after it, so a page boundary splits no ties.
+    async fn list_results_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<ProofResultRecord>>;
 
     /// Coverage for the (repo_id, commit_sha) tuple — counts of total
     /// and successful proof_jobs at that commit. Used by Regulator mode
@@ -90,6 +171,209 @@ async fn list_tactic_outcomes_by_tactic(
         limit: usize,
     ) -> Result<Vec<TacticOutcomeRecord>>;
 
+    // Per-file timing operations (duration trend analysis)
+    async fn record_file_timing(&self, timing: &FileTimingRecord) -> Result<()>;
+    /// Most recent timings for one `(repo, prover, file)` tuple, newest
+    /// first. The anomaly detector uses this as its baseline window.
+    async fn list_file_timings(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<FileTimingRecord>>;
+    async fn list_file_timings_for_job(&self, job_id: JobId) -> Result<Vec<FileTimingRecord>>;
+    /// Every per-file result recorded for `(repo, prover)` at one commit,
+    /// newest first. The PR baseline comparison reads the base commit's.
+    async fn list_file_timings_for_commit(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        commit_sha: &str,
+    ) -> Result<Vec<FileTimingRecord>>;
+
+    // Escape hatches in checked files (see `crate::trust::escape_hatch`)
+    /// Replaces any recorded for the job: a re-run scans the files again.
+    async fn record_escape_hatches(&self, job_id: JobId, hatches: &[EscapeHatchRecord]) -> Result<()>;
+    /// By file, then line.
+    async fn list_escape_hatches(&self, job_id: JobId) -> Result<Vec<EscapeHatchRecord>>;
+
+    // Per-file and per-theorem results (see `crate::proof_items`)
+    /// Replaces any recorded for the job.
+    async fn record_proof_items(&self, job_id: JobId, items: &[ProofItemRecord]) -> Result<()>;
+    /// By file, the file's own item first, then by line.
+    async fn list_proof_items(&self, job_id: JobId) -> Result<Vec<ProofItemRecord>>;
+    /// Most recent timed, verified theorem items of one `(repo, prover,
+    /// file)` tuple across jobs, newest first: the per-theorem baseline of
+    /// the anomaly detector.
+    async fn list_theorem_timings(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<ProofItemRecord>>;
+
+    // Re-runs of failed files (see `crate::flaky`)
+    async fn record_flaky_run(&self, run: &FlakyRunRecord) -> Result<()>;
+    /// A repository's most recent classified re-runs, newest first.
+    async fn list_flaky_runs(&self, repo_id: Uuid, limit: usize) -> Result<Vec<FlakyRunRecord>>;
+
+    // Bisections of default-branch failures (see `crate::bisect`)
+    async fn upsert_bisection(&self, bisection: &BisectionRecord) -> Result<()>;
+    async fn get_bisection(&self, id: Uuid) -> Result<Option<BisectionRecord>>;
+    /// A repository's bisections, newest first.
+    async fn list_bisections(&self, repo_id: Uuid, limit: usize) -> Result<Vec<BisectionRecord>>;
+
+    // Verification certificates (see `crate::certificate`)
+    /// Replaces the repository's certificate for the same commit.
+    async fn save_certificate(&self, certificate: &CertificateRecord) -> Result<()>;
+    async fn get_certificate(
+        &self,
+        repo_id: Uuid,
+        commit_sha: &str,
+    ) -> Result<Option<CertificateRecord>>;
+
+    // Job lifecycle event outbox (see `crate::events`)
+    /// Start (or stop) recording every job insert and status change in
+    /// the outbox, in the same write as the change.
+    async fn set_job_event_outbox(&self, enabled: bool) -> Result<()>;
+    /// The oldest unpublished events, in the order they happened.
+    async fn list_job_events(&self, limit: usize) -> Result<Vec<JobEventRecord>>;
+    /// Remove published events.
+    async fn delete_job_events(&self, seqs: &[i64]) -> Result<()>;
+    async fn record_job_event_failure(&self, seq: i64, error: &str) -> Result<()>;
+
+    // Analytics export (see `crate::analytics`)
+    /// Finished jobs after `after` (a `completed_at` and job id), oldest
+    /// first, ties broken by id; from the first when `None`.
+    async fn list_jobs_finished_after(
+        &self,
+        after: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>>;
+    async fn get_analytics_cursor(&self, sink: &str) -> Result<Option<AnalyticsCursor>>;
+    /// Replaces the sink's cursor.
+    async fn set_analytics_cursor(&self, cursor: &AnalyticsCursor) -> Result<()>;
+    /// The sink starts over from the first finished job.
+    async fn delete_analytics_cursor(&self, sink: &str) -> Result<()>;
+
+    // Pending confirmations of destructive mutations (see
+    // `crate::api::confirm`)
+    async fn create_confirmation(&self, confirmation: &ConfirmationRecord) -> Result<()>;
+    /// Remove and return the confirmation with this token hash, expired or
+    /// not; a token is good for one use. Expired confirmations are
+    /// dropped on the way.
+    async fn take_confirmation(&self, token_hash: &str) -> Result<Option<ConfirmationRecord>>;
+
+    // API token operations (see `crate::api::auth`)
+    async fn create_api_token(&self, token: &ApiTokenRecord) -> Result<()>;
+    async fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenRecord>>;
+    async fn list_api_tokens(&self) -> Result<Vec<ApiTokenRecord>>;
+    /// Mark a token revoked. Returns `false` when no live token had that id.
+    async fn revoke_api_token(&self, id: Uuid) -> Result<bool>;
+    async fn touch_api_token(&self, id: Uuid) -> Result<()>;
+
+    // Per-tenant keys (see `crate::tenancy`)
+    /// Seal per-repository webhook secrets with tenant keys wrapped by
+    /// `master` from now on; `None` stores new secrets as given. Fails
+    /// when the tenant keys already stored don't open with `master`.
+    async fn set_master_key(&self, master: Option<MasterKey>) -> Result<()>;
+    async fn list_tenant_keys(&self) -> Result<Vec<TenantKeyRecord>>;
+    /// Secrets sealed with `owner`'s key can't be opened again. Returns
+    /// `false` when it had none.
+    async fn delete_tenant_key(&self, owner: &str) -> Result<bool>;
+
+    // Quarantine operations (check-run "Quarantine" action)
+    /// Idempotent: quarantining an already-quarantined file is a no-op.
+    async fn quarantine_proof(&self, entry: &QuarantinedProof) -> Result<()>;
+    async fn list_quarantined_proofs(&self, repo_id: Uuid) -> Result<Vec<QuarantinedProof>>;
+    /// Returns `false` when the file was not quarantined.
+    async fn unquarantine_proof(&self, repo_id: Uuid, file_path: &str) -> Result<bool>;
+
+    // Webhook ingest queue (see `crate::api::ingest`)
+    /// Returns `false` when the platform already delivered this
+    /// `delivery_id` (a redelivery); the record is not stored again.
+    async fn record_webhook_delivery(&self, delivery: &WebhookDeliveryRecord) -> Result<bool>;
+    /// Pending deliveries whose next attempt is due, oldest first.
+    async fn list_due_webhook_deliveries(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDeliveryRecord>>;
+    async fn update_webhook_delivery(&self, delivery: &WebhookDeliveryRecord) -> Result<()>;
+
+    // Stale-failure policy state (see `crate::stale`)
+    async fn get_stale_nag(&self, repo_id: Uuid, pr_number: u64) -> Result<Option<StaleNagRecord>>;
+    async fn upsert_stale_nag(&self, nag: &StaleNagRecord) -> Result<()>;
+    async fn delete_stale_nag(&self, repo_id: Uuid, pr_number: u64) -> Result<()>;
+
+    // Sticky PR result comments (see `crate::pr_comment`)
+    async fn get_pr_comment(
+        &self,
+        repo_id: Uuid,
+        pr_number: u64,
+        prover: ProverKind,
+    ) -> Result<Option<PrCommentRecord>>;
+    /// Replaces the comment id stored for the `(repo, PR, prover)` triple.
+    async fn upsert_pr_comment(&self, record: &PrCommentRecord) -> Result<()>;
+
+    // Proof dependency graphs (see `crate::depgraph`)
+    async fn get_dependency_graph(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+    ) -> Result<Option<DependencyGraphRecord>>;
+    /// Replaces any graph already stored for the `(repo, prover)` pair.
+    async fn upsert_dependency_graph(&self, record: &DependencyGraphRecord) -> Result<()>;
+
+    // Results imported from federation peers (see `crate::federation`)
+    /// Replaces the peer's earlier result for the same repo/commit/prover.
+    async fn upsert_federated_result(&self, record: &FederatedResultRecord) -> Result<()>;
+    /// Newest imported result for `repo` (`owner/name`) at `commit_sha`.
+    async fn get_federated_result(
+        &self,
+        platform: Platform,
+        repo: &str,
+        commit_sha: &str,
+        prover: ProverKind,
+    ) -> Result<Option<FederatedResultRecord>>;
+    /// `completed_at` of the newest result imported from `peer`; the
+    /// cursor for the next sync.
+    async fn latest_federated_result_at(
+        &self,
+        peer: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>>;
+
+    // Training-example export queue (see `crate::feedback::export`)
+    /// Queues the examples whose digest is new; returns how many were.
+    async fn queue_training_examples(&self, examples: &[TrainingExampleRecord]) -> Result<usize>;
+    /// Oldest examples not yet exported.
+    async fn list_pending_training_examples(&self, limit: usize) -> Result<Vec<TrainingExampleRecord>>;
+    async fn mark_training_examples_exported(
+        &self,
+        ids: &[Uuid],
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()>;
+
+    // Maintenance announcement (see `crate::announcement`)
+    /// The stored announcement, expired or not.
+    async fn get_announcement(&self) -> Result<Option<AnnouncementRecord>>;
+    /// Replaces any announcement already set.
+    async fn set_announcement(&self, announcement: &AnnouncementRecord) -> Result<()>;
+    /// Returns `false` when none was set.
+    async fn clear_announcement(&self) -> Result<bool>;
+
+    // Audit log (see `crate::audit`); append-only, so no update or delete
+    async fn append_audit(&self, record: &AuditRecord) -> Result<()>;
+    /// Matching rows, newest first.
+    async fn list_audit(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditRecord>>;
+
     // Utility
     async fn health_check(&self) -> Result<bool>;
+    /// Connection and lock-wait figures for `/metrics`; `None` for
+    /// backends without a local pool.
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
 }
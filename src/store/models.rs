@@ -5,10 +5,13 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use crate::adapters::Platform;
-use crate::dispatcher::ProverKind;
+use crate::attest::Envelope;
+use crate::depgraph::DependencyGraph;
+use crate::dispatcher::{ExecutionPath, ProverKind};
 use crate::modes::BotMode;
 use crate::scheduler::{JobId, JobStatus, JobPriority};
 
@@ -41,12 +44,105 @@ pub struct Repository {
     /// for non-Regulator modes.
     #[serde(default = "default_regulator_threshold")]
     pub regulator_coverage_threshold: u8,
+    /// Branch patterns whose pushes are verified; empty means the
+    /// default branch only. A manifest `[branches]` table overrides both
+    /// lists (see `modes::BranchesSection`).
+    #[serde(default)]
+    pub branch_include: Vec<String>,
+    /// Branch patterns never verified on push.
+    #[serde(default)]
+    pub branch_exclude: Vec<String>,
+    /// Path globs whose proof files are verified; empty means all of
+    /// them. A manifest `[proofs]` table overrides both lists (see
+    /// `modes::ProofsSection`).
+    #[serde(default)]
+    pub path_include: Vec<String>,
+    /// Path globs never verified.
+    #[serde(default)]
+    pub path_exclude: Vec<String>,
+    /// When the bootstrap config PR was offered. Set once, whatever the
+    /// outcome, so an unconfigured repo gets at most one such PR.
+    #[serde(default)]
+    pub config_pr_offered_at: Option<DateTime<Utc>>,
+    /// Whether requests without an API token may see this repository's
+    /// status (badge, `/status`, public GraphQL queries).
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// The repository's default branch, as last reported by a push
+    /// payload or the platform API. `None` until first learned.
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    /// Opted in to contributing anonymized (goal, tactic) pairs from its
+    /// passing jobs to the training export (`[corpus.export]`). Off by
+    /// default.
+    #[serde(default)]
+    pub training_export: bool,
+    /// Cron expression for scheduled full re-verification of the default
+    /// branch (see `crate::reverify`). `None` means pushes only.
+    #[serde(default)]
+    pub reverify_cron: Option<String>,
+    /// When the last scheduled re-verification was enqueued.
+    #[serde(default)]
+    pub last_reverify_at: Option<DateTime<Utc>>,
+    /// Required toolchain version per prover slug, e.g. `lean` → `4.7.0`.
+    /// A job whose prover reports another version fails without
+    /// verifying (see `crate::toolchain`).
+    #[serde(default)]
+    pub prover_pins: BTreeMap<String, String>,
+    /// IANA time zone its schedules and blackout windows run in (see
+    /// `crate::calendar`). `None` takes its organisation's, else the
+    /// instance's.
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 fn default_regulator_threshold() -> u8 {
     100
 }
 
+/// Who may see a repository's verification status without an API token;
+/// see [`crate::api::auth`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Badge, status endpoint and public GraphQL queries answer anyone.
+    Public,
+    /// Only token holders see it; anonymous callers get the same answer
+    /// as for an unregistered repository.
+    #[default]
+    Private,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "public" => Ok(Visibility::Public),
+            "private" => Ok(Visibility::Private),
+            _ => Err(crate::error::Error::InvalidInput(format!(
+                "unknown visibility '{}': expected public or private",
+                s
+            ))),
+        }
+    }
+}
+
 impl Repository {
     pub fn new(platform: Platform, owner: String, name: String) -> Self {
         let now = Utc::now();
@@ -66,12 +162,40 @@ pub fn new(platform: Platform, owner: String, name: String) -> Self {
             updated_at: now,
             mode: BotMode::default(), // Verifier
             regulator_coverage_threshold: default_regulator_threshold(),
+            branch_include: Vec::new(),
+            branch_exclude: Vec::new(),
+            path_include: Vec::new(),
+            path_exclude: Vec::new(),
+            config_pr_offered_at: None,
+            visibility: Visibility::default(),
+            default_branch: None,
+            training_export: false,
+            reverify_cron: None,
+            last_reverify_at: None,
+            prover_pins: BTreeMap::new(),
+            timezone: None,
         }
     }
 
     pub fn full_name(&self) -> String {
         format!("{}/{}", self.owner, self.name)
     }
+
+    /// The push branch filter from the repository's own settings.
+    pub fn branch_filter(&self) -> crate::modes::BranchesSection {
+        crate::modes::BranchesSection {
+            include: self.branch_include.clone(),
+            exclude: self.branch_exclude.clone(),
+        }
+    }
+
+    /// The proof-file path filter from the repository's own settings.
+    pub fn path_filter(&self) -> crate::modes::ProofsSection {
+        crate::modes::ProofsSection {
+            include: self.path_include.clone(),
+            exclude: self.path_exclude.clone(),
+        }
+    }
 }
 
 /// Proof job database record
@@ -81,7 +205,8 @@ pub struct ProofJobRecord {
     pub repo_id: Uuid,
     pub commit_sha: String,
     pub prover: ProverKind,
-    pub file_paths: Vec<String>,
+    /// What to verify and how; see [`JobPayload`].
+    pub payload: JobPayload,
     pub status: JobStatus,
     pub priority: JobPriority,
     pub queued_at: DateTime<Utc>,
@@ -95,6 +220,52 @@ pub struct ProofJobRecord {
     /// Webhook delivery ID for traceability.
     #[serde(default)]
     pub delivery_id: Option<String>,
+    /// The job this one re-runs (`retryJob` / `echidnabot retry`).
+    #[serde(default)]
+    pub retry_of: Option<Uuid>,
+    /// Base commit of the PR the job verifies.
+    #[serde(default)]
+    pub base_sha: Option<String>,
+    /// Branch whose push triggered the job.
+    #[serde(default)]
+    pub branch: Option<String>,
+}
+
+impl ProofJobRecord {
+    /// PRs the job reports on: the one that triggered it, else those
+    /// its commit was found in. None once it is orphaned.
+    pub fn pull_requests(&self) -> Vec<u64> {
+        if self.payload.orphaned.is_some() {
+            return Vec::new();
+        }
+        match self.pr_number {
+            Some(pr) => vec![pr],
+            None => self.payload.associated_prs.clone().unwrap_or_default(),
+        }
+    }
+
+    /// The scheduler job this record describes, with its id and context.
+    pub fn to_job(&self) -> crate::scheduler::ProofJob {
+        let mut job = crate::scheduler::ProofJob::new(
+            self.repo_id,
+            self.commit_sha.clone(),
+            self.prover.clone(),
+            self.payload.file_paths.clone(),
+        )
+        .with_priority(self.priority)
+        .with_context(self.pr_number, self.delivery_id.clone())
+        .with_base(self.base_sha.clone())
+        .with_branch(self.branch.clone())
+        .with_dependency_update(self.payload.dependency_update);
+        job.id = crate::scheduler::JobId(self.id);
+        job.status = self.status;
+        job.queued_at = self.queued_at;
+        job.started_at = self.started_at;
+        job.completed_at = self.completed_at;
+        job.timeout_secs = self.payload.timeout_secs;
+        job.trace_parent = self.payload.trace_parent.clone();
+        job
+    }
 }
 
 impl From<crate::scheduler::ProofJob> for ProofJobRecord {
@@ -104,7 +275,18 @@ fn from(job: crate::scheduler::ProofJob) -> Self {
             repo_id: job.repo_id,
             commit_sha: job.commit_sha,
             prover: job.prover,
-            file_paths: job.file_paths,
+            payload: JobPayload {
+                file_paths: job.file_paths,
+                timeout_secs: job.timeout_secs,
+                dependency_update: job.dependency_update,
+                resolved_files: Vec::new(),
+                project_roots: Vec::new(),
+                trace_parent: job.trace_parent,
+                associated_prs: None,
+                orphaned: None,
+                bisection: None,
+                decision: None,
+            },
             status: job.status,
             priority: job.priority,
             queued_at: job.queued_at,
@@ -113,6 +295,111 @@ fn from(job: crate::scheduler::ProofJob) -> Self {
             error_message: job.result.as_ref().filter(|r| !r.success).map(|r| r.message.clone()),
             pr_number: job.pr_number,
             delivery_id: job.delivery_id,
+            retry_of: None,
+            base_sha: job.base_sha,
+            branch: job.branch,
+        }
+    }
+}
+
+/// Payload version written by this build.
+pub const JOB_PAYLOAD_VERSION: u64 = 2;
+
+/// The part of a job that is not queried on, persisted as JSON with a
+/// `schema_version` in `proof_jobs.payload`. Rows written by older builds
+/// are upgraded on load, one version at a time:
+///
+/// | Version | Stored as                                               |
+/// |---------|---------------------------------------------------------|
+/// | 1       | a bare JSON array of file paths in `proof_jobs.file_paths` |
+/// | 2       | `{"schema_version": 2, "file_paths": [...], "timeout_secs", "dependency_update"}` |
+///
+/// New optional fields only need `#[serde(default)]`. Renaming, removing
+/// or reinterpreting one needs a version bump and an arm in `upgrade`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobPayload {
+    /// Files to verify; empty means every file the prover recognises.
+    pub file_paths: Vec<String>,
+    /// See [`crate::scheduler::ProofJob::timeout_secs`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// See [`crate::scheduler::ProofJob::dependency_update`].
+    #[serde(default)]
+    pub dependency_update: bool,
+    /// For a job without `file_paths`: the files discovery resolved in
+    /// its checkout, repo-relative and sorted. Recorded when it runs.
+    #[serde(default)]
+    pub resolved_files: Vec<String>,
+    /// Project roots of `resolved_files` (see
+    /// [`crate::dispatcher::discovery`]); `""` is the repository root.
+    #[serde(default)]
+    pub project_roots: Vec<String>,
+    /// See [`crate::scheduler::ProofJob::trace_parent`].
+    #[serde(default)]
+    pub trace_parent: Option<String>,
+    /// For a job without a `pr_number`: the open PRs containing its
+    /// commit, looked up when it first reports (see
+    /// [`crate::pr_association`]). `None` until then.
+    #[serde(default)]
+    pub associated_prs: Option<Vec<u64>>,
+    /// Why the job's commit is no longer on its branch, e.g. `branch
+    /// topic deleted`. An orphaned job reports on its commit only, never
+    /// on PRs (see [`crate::scheduler::supersede`]).
+    #[serde(default)]
+    pub orphaned: Option<String>,
+    /// The bisection this job probes a commit for (see [`crate::bisect`]).
+    /// Probes report on their commit only, never on PRs.
+    #[serde(default)]
+    pub bisection: Option<Uuid>,
+    /// Why the job was reported the way it was (see [`crate::decision`]).
+    /// Filled in as it runs and reports.
+    #[serde(default)]
+    pub decision: Option<crate::decision::DecisionTrace>,
+}
+
+impl JobPayload {
+    /// Serialize at [`JOB_PAYLOAD_VERSION`].
+    pub fn to_json(&self) -> crate::Result<String> {
+        #[derive(Serialize)]
+        struct Tagged<'a> {
+            schema_version: u64,
+            #[serde(flatten)]
+            payload: &'a JobPayload,
+        }
+        Ok(serde_json::to_string(&Tagged {
+            schema_version: JOB_PAYLOAD_VERSION,
+            payload: self,
+        })?)
+    }
+
+    /// Load a payload stored at any version up to [`JOB_PAYLOAD_VERSION`].
+    pub fn from_json(json: &str) -> crate::Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        let version = match &value {
+            serde_json::Value::Array(_) => 1,
+            serde_json::Value::Object(fields) => fields
+                .get("schema_version")
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| crate::Error::Internal("job payload has no schema_version".into()))?,
+            _ => return Err(crate::Error::Internal(format!("malformed job payload: {}", json))),
+        };
+        if version > JOB_PAYLOAD_VERSION {
+            return Err(crate::Error::Internal(format!(
+                "job payload schema_version {} is newer than this build ({})",
+                version, JOB_PAYLOAD_VERSION
+            )));
+        }
+        for from in version..JOB_PAYLOAD_VERSION {
+            value = Self::upgrade(from, value);
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Rewrite a `from`-version payload as `from + 1`.
+    fn upgrade(from: u64, value: serde_json::Value) -> serde_json::Value {
+        match from {
+            1 => serde_json::json!({ "schema_version": 2, "file_paths": value }),
+            _ => value,
         }
     }
 }
@@ -129,6 +416,19 @@ pub struct ProofResultRecord {
     pub verified_files: Vec<String>,
     pub failed_files: Vec<String>,
     pub created_at: DateTime<Utc>,
+    /// Content address of the archived artifacts (see `crate::archive`).
+    #[serde(default)]
+    pub archive_cid: Option<String>,
+    /// Version the prover reported, when it could be discovered.
+    #[serde(default)]
+    pub prover_version: Option<String>,
+    /// Repository flags the prover ran with (see
+    /// `crate::executor::prover_args`).
+    #[serde(default)]
+    pub prover_args: Vec<String>,
+    /// Where the proofs were checked (`[executor] dispatch`).
+    #[serde(default)]
+    pub execution_path: Option<ExecutionPath>,
 }
 
 impl ProofResultRecord {
@@ -143,6 +443,10 @@ pub fn new(job_id: JobId, result: &crate::scheduler::JobResult) -> Self {
             verified_files: result.verified_files.clone(),
             failed_files: result.failed_files.clone(),
             created_at: Utc::now(),
+            archive_cid: None,
+            prover_version: result.prover_version.clone(),
+            prover_args: result.prover_args.clone(),
+            execution_path: result.execution_path,
         }
     }
 }
@@ -197,6 +501,719 @@ pub fn new(
     }
 }
 
+/// Per-file verification timing — one row per proof file per job.
+///
+/// Feeds the long-term trend analysis in [`crate::perf`]: the anomaly
+/// detector compares the latest duration for a `(repo, prover, file)`
+/// tuple against the stored history to flag sudden slowdowns even when
+/// the proof still passes. `file_path` is repo-relative so rows from
+/// different clones of the same repo line up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTimingRecord {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub repo_id: Uuid,
+    pub prover: ProverKind,
+    pub file_path: String,
+    pub commit_sha: String,
+    pub duration_ms: i64,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FileTimingRecord {
+    pub fn new(
+        job_id: JobId,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: String,
+        commit_sha: String,
+        duration_ms: i64,
+        success: bool,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id: job_id.0,
+            repo_id,
+            prover,
+            file_path,
+            commit_sha,
+            duration_ms,
+            success,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Hierarchical permission scope attached to an API token; see
+/// [`crate::api::auth`] for what each scope grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// Read-only: queries and metrics.
+    Read,
+    /// Read + enqueue work (trigger checks, request suggestions).
+    Trigger,
+    /// Everything, including repository administration.
+    Admin,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Trigger => "trigger",
+            TokenScope::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "read" => Ok(TokenScope::Read),
+            "trigger" => Ok(TokenScope::Trigger),
+            "admin" => Ok(TokenScope::Admin),
+            _ => Err(crate::error::Error::InvalidInput(format!(
+                "unknown token scope '{}': expected one of read, trigger, admin",
+                s
+            ))),
+        }
+    }
+}
+
+/// API token record. Only the SHA-256 hash of the bearer string is kept;
+/// see [`crate::api::auth`] for the scheme and scope semantics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenRecord {
+    pub id: Uuid,
+    /// Human label shown in `echidnabot token list` (e.g. "ci-dashboard").
+    pub name: String,
+    pub token_hash: String,
+    pub scope: TokenScope,
+    /// The repository owner the token is confined to; `None` for an
+    /// instance-wide token. See `crate::tenancy`.
+    pub tenant: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Set once by `revoke`; revoked tokens are kept for audit.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiTokenRecord {
+    pub fn new(name: String, token_hash: String, scope: TokenScope) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            token_hash,
+            scope,
+            tenant: None,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+}
+
+/// A tenant's data key, as listed by `echidnabot tenant keys`. The key
+/// itself never leaves the store unwrapped (see `crate::tenancy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantKeyRecord {
+    pub owner: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A proof file whose failures are still reported but no longer fail the
+/// job. Created from the "Quarantine" check-run action; one row per
+/// `(repo_id, file_path)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedProof {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    /// Repo-relative path, matching `file_timings.file_path`.
+    pub file_path: String,
+    /// Free-form note, e.g. who quarantined it and from which job.
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl QuarantinedProof {
+    pub fn new(repo_id: Uuid, file_path: String, reason: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            repo_id,
+            file_path,
+            reason,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A forbidden token found in one of a job's checked files (see
+/// `crate::trust::escape_hatch`). Recorded whatever the repo's `[axioms]
+/// severity`, so repository statistics count them all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscapeHatchRecord {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    /// Repo-relative path.
+    pub file_path: String,
+    /// 1-based.
+    pub line: u32,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EscapeHatchRecord {
+    pub fn new(job_id: JobId, hatch: crate::trust::escape_hatch::EscapeHatch) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id: job_id.0,
+            file_path: hatch.file_path,
+            line: hatch.line,
+            token: hatch.token,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A file or theorem of a job's result (see `crate::proof_items`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofItemRecord {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    /// Repo-relative path.
+    pub file_path: String,
+    /// `None` for the file itself.
+    pub theorem: Option<String>,
+    /// 1-based.
+    pub line: Option<u32>,
+    pub status: crate::proof_items::ItemStatus,
+    pub duration_ms: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ProofItemRecord {
+    pub fn new(job_id: JobId, item: crate::proof_items::ProofItem) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id: job_id.0,
+            file_path: item.file_path,
+            theorem: item.theorem,
+            line: item.line,
+            status: item.status,
+            duration_ms: item.duration_ms,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A failed file that was re-run under `[flaky] reruns`, and what the
+/// re-runs showed (see [`crate::flaky`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlakyRunRecord {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub repo_id: Uuid,
+    pub prover: ProverKind,
+    /// Repo-relative path.
+    pub file_path: String,
+    pub commit_sha: String,
+    /// Checks of the file, the first failure included.
+    pub attempts: u32,
+    pub verdict: crate::flaky::FlakyVerdict,
+    /// Whether a failed attempt hit the timeout.
+    pub timed_out: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FlakyRunRecord {
+    pub fn new(
+        job_id: JobId,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: String,
+        commit_sha: String,
+        attempts: &[crate::flaky::Attempt],
+        verdict: crate::flaky::FlakyVerdict,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            job_id: job_id.0,
+            repo_id,
+            prover,
+            file_path,
+            commit_sha,
+            attempts: attempts.len() as u32,
+            verdict,
+            timed_out: attempts.iter().any(|a| a.timed_out),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Where a bisection stands (see [`crate::bisect`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BisectStatus {
+    /// Probing commits between the last good and first bad one.
+    Running,
+    /// `bad_sha` is the breaking commit.
+    Found,
+    /// Given up, e.g. too many commits or a probe that could not run.
+    Abandoned,
+}
+
+/// A bisection of a default-branch proof failure: the commits between
+/// the last passing and the first failing run of `file_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BisectionRecord {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    pub prover: ProverKind,
+    pub branch: String,
+    /// The file that started failing, repo-relative.
+    pub file_path: String,
+    /// Its first failing theorem, when the prover output names one.
+    pub theorem: Option<String>,
+    /// Newest commit known to pass.
+    pub good_sha: String,
+    /// Oldest commit known to fail.
+    pub bad_sha: String,
+    /// Untested commits between the two, oldest first.
+    pub pending: Vec<String>,
+    /// The job checking the current midpoint.
+    pub probe_job: Option<Uuid>,
+    /// Commits checked by a probe job.
+    pub probes: u32,
+    /// Commits decided from results already stored.
+    pub reused: u32,
+    pub status: BisectStatus,
+    /// The issue opened for the breaking commit.
+    pub issue: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BisectionRecord {
+    pub fn new(
+        repo_id: Uuid,
+        prover: ProverKind,
+        branch: String,
+        file_path: String,
+        good_sha: String,
+        bad_sha: String,
+        pending: Vec<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            repo_id,
+            prover,
+            branch,
+            file_path,
+            theorem: None,
+            good_sha,
+            bad_sha,
+            pending,
+            probe_job: None,
+            probes: 0,
+            reused: 0,
+            status: BisectStatus::Running,
+            issue: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// A signed verification certificate for one commit (see
+/// `crate::certificate`). At most one per repository and commit; a
+/// re-issue replaces it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateRecord {
+    pub id: Uuid,
+    pub repo_id: Uuid,
+    pub commit_sha: String,
+    pub envelope: Envelope,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CertificateRecord {
+    pub fn new(repo_id: Uuid, commit_sha: String, envelope: Envelope) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            repo_id,
+            commit_sha,
+            envelope,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// A job status change waiting in the outbox for `crate::events` to
+/// publish. Written by the store alongside the change itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobEventRecord {
+    /// Outbox order.
+    pub seq: i64,
+    /// Stable across redeliveries, for consumers to deduplicate on.
+    pub event_id: String,
+    pub job_id: Uuid,
+    /// The status the job changed to.
+    pub status: JobStatus,
+    pub occurred_at: DateTime<Utc>,
+    /// Failed publish attempts so far.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// How far an analytics sink has exported (see `crate::analytics`): the
+/// last finished job it shipped, in completion order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyticsCursor {
+    pub sink: String,
+    pub completed_at: DateTime<Utc>,
+    pub job_id: Uuid,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A destructive mutation waiting for its confirmation token (see
+/// `crate::api::confirm`). Only the token's SHA-256 is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationRecord {
+    pub token_hash: String,
+    /// The mutation, e.g. `purgeRepository`.
+    pub action: String,
+    /// Who asked; only they can confirm.
+    pub actor: String,
+    /// SHA-256 of what the mutation would do, so a confirmation does not
+    /// carry over to a different request.
+    pub subject_digest: String,
+    /// What the caller was shown.
+    pub impact: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Processing state of a stored webhook delivery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    /// Waiting for (another) processing attempt.
+    Pending,
+    Processed,
+    /// Gave up after the configured number of attempts.
+    Failed,
+}
+
+/// A webhook delivery accepted by the ingest endpoint, kept until the
+/// background worker has processed it (see `crate::api::ingest`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDeliveryRecord {
+    pub id: Uuid,
+    pub platform: Platform,
+    /// Platform event name (`push`, `Merge Request Hook`, `repo:push`, …).
+    pub event_type: String,
+    /// Platform delivery id; redeliveries of the same id are dropped.
+    pub delivery_id: Option<String>,
+    /// Raw request body, signature already verified.
+    pub payload: Vec<u8>,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub received_at: DateTime<Utc>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub processed_at: Option<DateTime<Utc>>,
+    /// W3C `traceparent` of the request that delivered it, so processing
+    /// joins its trace (see `crate::observability`).
+    pub trace_parent: Option<String>,
+}
+
+impl WebhookDeliveryRecord {
+    pub fn new(
+        platform: Platform,
+        event_type: String,
+        delivery_id: Option<String>,
+        payload: Vec<u8>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            platform,
+            event_type,
+            delivery_id,
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            received_at: now,
+            next_attempt_at: now,
+            processed_at: None,
+            trace_parent: crate::observability::current_trace_parent(),
+        }
+    }
+}
+
+/// Stale-failure policy progress for one PR (see `crate::stale`).
+///
+/// Keyed by `(repo_id, pr_number)`. Reset whenever the failure streak
+/// changes — a green head clears the row, a new streak replaces it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StaleNagRecord {
+    pub repo_id: Uuid,
+    pub pr_number: u64,
+    /// When the PR's current run of failing commits started.
+    pub failing_since: DateTime<Utc>,
+    pub reminders: u32,
+    pub last_reminded_at: Option<DateTime<Utc>>,
+    pub escalated: bool,
+    pub drafted: bool,
+    pub closed: bool,
+}
+
+impl StaleNagRecord {
+    pub fn new(repo_id: Uuid, pr_number: u64, failing_since: DateTime<Utc>) -> Self {
+        Self {
+            repo_id,
+            pr_number,
+            failing_since,
+            reminders: 0,
+            last_reminded_at: None,
+            escalated: false,
+            drafted: false,
+            closed: false,
+        }
+    }
+}
+
+/// Proof dependency graph last recorded for one `(repo, prover)` (see
+/// `crate::depgraph`). Replaced by every job for that prover.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraphRecord {
+    pub repo_id: Uuid,
+    pub prover: ProverKind,
+    /// Commit whose checkout the graph was built from.
+    pub commit_sha: String,
+    pub graph: DependencyGraph,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DependencyGraphRecord {
+    pub fn new(repo_id: Uuid, prover: ProverKind, commit_sha: String, graph: DependencyGraph) -> Self {
+        Self {
+            repo_id,
+            prover,
+            commit_sha,
+            graph,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// A result imported from a federation peer (see `crate::federation`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedResultRecord {
+    pub id: Uuid,
+    /// Base URL of the instance that signed the result.
+    pub peer: String,
+    pub platform: Platform,
+    /// `owner/name`.
+    pub repo: String,
+    pub commit_sha: String,
+    pub prover: ProverKind,
+    pub success: bool,
+    pub verified_files: Vec<String>,
+    pub failed_files: Vec<String>,
+    /// When the peer recorded the result.
+    pub completed_at: DateTime<Utc>,
+    /// The signed envelope as received, so the import can be re-verified.
+    pub envelope: String,
+    pub imported_at: DateTime<Utc>,
+}
+
+/// The bot's result comment on a PR, edited in place by later runs (see
+/// `crate::pr_comment`).
+///
+/// Keyed by `(repo_id, pr_number, prover)`: each prover's job reports on
+/// its own, so one comment per prover keeps their results side by side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrCommentRecord {
+    pub repo_id: Uuid,
+    pub pr_number: u64,
+    pub prover: ProverKind,
+    /// Platform comment (GitLab: note) id.
+    pub comment_id: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An anonymized (goal, tactic) pair harvested from a verified proof and
+/// queued for the training export (see `crate::feedback::export`).
+///
+/// Carries no repository, path, commit or theorem name. `digest` covers
+/// prover, goal and tactic, so a pair seen again — on a later run or in
+/// another repository — is only queued once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrainingExampleRecord {
+    pub id: Uuid,
+    pub prover: ProverKind,
+    pub goal: String,
+    pub tactic: String,
+    pub digest: String,
+    pub created_at: DateTime<Utc>,
+    /// `None` until shipped in a batch.
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+impl TrainingExampleRecord {
+    pub fn new(prover: ProverKind, goal: String, tactic: String) -> Self {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        for part in [prover.as_str(), goal.as_str(), tactic.as_str()] {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        Self {
+            id: Uuid::new_v4(),
+            prover,
+            goal,
+            tactic,
+            digest: hex::encode(hasher.finalize()),
+            created_at: Utc::now(),
+            exported_at: None,
+        }
+    }
+}
+
+/// The instance-wide maintenance announcement, shown as a banner on bot
+/// comments and check summaries while active (see `crate::announcement`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnouncementRecord {
+    /// Markdown, e.g. "degraded: ECHIDNA latency".
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    /// `None` until cleared.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl AnnouncementRecord {
+    pub fn new(message: String, ttl: Option<chrono::Duration>) -> Self {
+        let created_at = Utc::now();
+        Self {
+            message,
+            created_at,
+            expires_at: ttl.map(|ttl| created_at + ttl),
+        }
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires| now < expires)
+    }
+}
+
+/// One row of the append-only audit log (see `crate::audit`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub id: Uuid,
+    pub at: DateTime<Utc>,
+    /// `echidnabot` for the bot itself, `token:<id>` for API callers,
+    /// `user:<login>` for forge users, `cli` for the command line.
+    pub actor: String,
+    /// e.g. `check_run.created`; see `crate::audit::AuditAction`.
+    pub action: String,
+    pub repo_id: Option<Uuid>,
+    /// `owner/name` when the action happened; kept after the repository
+    /// is unregistered.
+    pub repo: Option<String>,
+    /// What was acted on, e.g. `PR #12` or `echidnabot/lean @ 1a2b3c4`.
+    pub target: Option<String>,
+    /// SHA-256 (hex) of what was sent: the comment body, check summary or
+    /// settings. The payload itself is not kept.
+    pub payload_digest: String,
+}
+
+/// Which audit rows to list; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub repo_id: Option<Uuid>,
+    /// Only entries about this owner's repositories.
+    pub owner: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Which jobs `Store::search_jobs` returns; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct JobFilter {
+    pub repo_id: Option<Uuid>,
+    /// Only jobs of this owner's repositories.
+    pub owner: Option<String>,
+    pub status: Option<JobStatus>,
+    pub prover: Option<ProverKind>,
+    /// Leading characters of the commit SHA, case-insensitive.
+    pub commit_prefix: Option<String>,
+    /// Queued at or after.
+    pub since: Option<DateTime<Utc>>,
+    /// Queued before.
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Order of `Store::search_jobs` results, by queue time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JobOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// How `Store::job_usage` groups jobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageKey {
+    Prover,
+    Repository,
+    /// Day queued, UTC.
+    Day,
+}
+
+/// Jobs queued in a period, aggregated for one [`UsageKey`] value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobUsage {
+    /// Prover slug, repository id or `YYYY-MM-DD`.
+    pub key: String,
+    pub jobs: u64,
+    /// Of them, how many failed.
+    pub failed: u64,
+    /// Of them, how many started.
+    pub started: u64,
+    /// Mean time from queued to started, over the jobs that started.
+    pub avg_wait_ms: Option<f64>,
+    /// Total time from started to finished, over the jobs that finished.
+    pub busy_ms: u64,
+    /// Escape hatches found in their checked files.
+    pub escape_hatches: u64,
+}
+
+/// Queue waits of one priority's jobs queued in a window (see
+/// `crate::slo`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueueWaits {
+    /// Jobs that started, or are still queued past the limit.
+    pub jobs: u64,
+    /// Of them, how many started within the limit.
+    pub within: u64,
+    /// Mean and longest time from queued to started, over those started.
+    pub avg_wait_ms: Option<f64>,
+    pub max_wait_ms: Option<f64>,
+}
+
 /// Stable fingerprint of a goal-state string for reranker similarity lookups.
 /// Normalises whitespace + case, then SHA-256 hex. Not a cryptographic identity;
 /// lexically-identical goals collide by design so the reranker can aggregate.
@@ -238,4 +1255,42 @@ fn fingerprint_distinguishes_distinct_goals() {
     fn fingerprint_is_sha256_hex_length() {
         assert_eq!(goal_fingerprint("any").len(), 64);
     }
+
+    #[test]
+    fn job_payload_round_trips_at_current_version() {
+        let payload = JobPayload {
+            file_paths: vec!["theories/A.v".into()],
+            timeout_secs: Some(1800),
+            dependency_update: true,
+            resolved_files: vec!["theories/A.v".into()],
+            project_roots: vec!["".into()],
+            trace_parent: Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".into()),
+            associated_prs: Some(vec![4, 9]),
+            orphaned: Some("branch topic deleted".into()),
+            bisection: Some(Uuid::nil()),
+            decision: Some(crate::decision::DecisionTrace {
+                conclusion: Some("failure".into()),
+                ..Default::default()
+            }),
+        };
+        let json = payload.to_json().unwrap();
+        assert!(json.contains(&format!("\"schema_version\":{}", JOB_PAYLOAD_VERSION)));
+        assert_eq!(JobPayload::from_json(&json).unwrap(), payload);
+    }
+
+    #[test]
+    fn job_payload_upgrades_v1_file_lists() {
+        let payload = JobPayload::from_json(r#"["A.lean", "B.lean"]"#).unwrap();
+        assert_eq!(payload.file_paths, vec!["A.lean".to_string(), "B.lean".to_string()]);
+        assert_eq!(payload.timeout_secs, None);
+        assert!(!payload.dependency_update);
+        assert_eq!(JobPayload::from_json("[]").unwrap(), JobPayload::default());
+    }
+
+    #[test]
+    fn job_payload_rejects_unknown_versions() {
+        assert!(JobPayload::from_json(r#"{"schema_version": 99, "file_paths": []}"#).is_err());
+        assert!(JobPayload::from_json(r#"{"file_paths": []}"#).is_err());
+        assert!(JobPayload::from_json(r#""A.v""#).is_err());
+    }
 }
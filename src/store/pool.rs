@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! SQLite connection pools and lock-wait accounting
+//!
+//! Webhook bursts used to end in "database is locked": several pooled
+//! connections each tried to write, and whoever lost the file lock for
+//! longer than the busy timeout failed. Under `[database]`:
+//!
+//! - `journal_mode = "wal"` (the default) lets readers run alongside the
+//!   writer, and `synchronous = "normal"` drops the fsync on every commit;
+//! - `busy_timeout_ms` is how long a connection retries a locked file;
+//! - `single_writer = true` (the default) opens one extra connection that
+//!   every write goes through, so writers queue in-process, in arrival
+//!   order, and never contend for the file lock among themselves.
+//!
+//! The time each write spends waiting for a connection is recorded in
+//! [`WriteWaits`] and exposed on `/metrics` as
+//! `echidnabot_db_write_wait_seconds`; with a single writer that is the
+//! lock wait.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::{Pool, Sqlite};
+
+use crate::config::{DatabaseConfig, JournalMode, SynchronousMode};
+use crate::error::Result;
+
+/// Upper bounds, in seconds, of the write-wait histogram buckets.
+const WAIT_BUCKETS: [f64; 7] = [0.001, 0.005, 0.025, 0.1, 0.5, 2.5, 10.0];
+
+/// Open the read pool and, with `single_writer`, the writer pool.
+pub(crate) async fn open(config: &DatabaseConfig) -> Result<(Pool<Sqlite>, Option<Pool<Sqlite>>)> {
+    let options = SqliteConnectOptions::from_str(&config.url)?
+        .journal_mode(match config.journal_mode {
+            JournalMode::Delete => SqliteJournalMode::Delete,
+            JournalMode::Truncate => SqliteJournalMode::Truncate,
+            JournalMode::Persist => SqliteJournalMode::Persist,
+            JournalMode::Memory => SqliteJournalMode::Memory,
+            JournalMode::Wal => SqliteJournalMode::Wal,
+            JournalMode::Off => SqliteJournalMode::Off,
+        })
+        .synchronous(match config.synchronous {
+            SynchronousMode::Off => SqliteSynchronous::Off,
+            SynchronousMode::Normal => SqliteSynchronous::Normal,
+            SynchronousMode::Full => SqliteSynchronous::Full,
+            SynchronousMode::Extra => SqliteSynchronous::Extra,
+        })
+        .busy_timeout(Duration::from_millis(config.busy_timeout_ms));
+    let acquire_timeout = Duration::from_secs(config.acquire_timeout_secs);
+
+    // The writer connects first so the journal mode is switched before
+    // any reader opens the file.
+    let writer = if config.single_writer {
+        Some(
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .acquire_timeout(acquire_timeout)
+                .connect_with(options.clone())
+                .await?,
+        )
+    } else {
+        None
+    };
+    let readers = SqlitePoolOptions::new()
+        .max_connections(config.max_connections.max(1))
+        .min_connections(config.min_connections.min(config.max_connections))
+        .acquire_timeout(acquire_timeout)
+        .connect_with(options)
+        .await?;
+    Ok((readers, writer))
+}
+
+/// Histogram of how long writes waited for a connection.
+#[derive(Debug, Default)]
+pub(crate) struct WriteWaits {
+    buckets: [AtomicU64; WAIT_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl WriteWaits {
+    pub fn observe(&self, wait: Duration) {
+        let secs = wait.as_secs_f64();
+        if let Some(i) = WAIT_BUCKETS.iter().position(|&le| secs <= le) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Point-in-time view of the store's connections, for `/metrics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolStats {
+    /// Open connections, writer included.
+    pub connections: u32,
+    /// Open connections not currently in use.
+    pub idle: u32,
+    /// Cumulative write-wait counts: `(upper bound in seconds, count)`.
+    pub write_wait_buckets: Vec<(f64, u64)>,
+    pub write_wait_count: u64,
+    pub write_wait_seconds: f64,
+}
+
+impl PoolStats {
+    pub(crate) fn collect(readers: &Pool<Sqlite>, writer: Option<&Pool<Sqlite>>, waits: &WriteWaits) -> Self {
+        let pools = std::iter::once(readers).chain(writer);
+        let (connections, idle) = pools.fold((0, 0), |(open, idle), pool| {
+            (open + pool.size(), idle + pool.num_idle() as u32)
+        });
+        let mut cumulative = 0;
+        let write_wait_buckets = WAIT_BUCKETS
+            .iter()
+            .zip(&waits.buckets)
+            .map(|(&le, n)| {
+                cumulative += n.load(Ordering::Relaxed);
+                (le, cumulative)
+            })
+            .collect();
+        Self {
+            connections,
+            idle,
+            write_wait_buckets,
+            write_wait_count: waits.count.load(Ordering::Relaxed),
+            write_wait_seconds: waits.sum_micros.load(Ordering::Relaxed) as f64 / 1e6,
+        }
+    }
+
+    /// Prometheus text exposition, appended to `/metrics`.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = format!(
+            "# HELP echidnabot_db_connections Open database connections\n\
+             # TYPE echidnabot_db_connections gauge\n\
+             echidnabot_db_connections{{state=\"idle\"}} {idle}\n\
+             echidnabot_db_connections{{state=\"busy\"}} {busy}\n\
+             # HELP echidnabot_db_write_wait_seconds Time writes waited for a database connection\n\
+             # TYPE echidnabot_db_write_wait_seconds histogram\n",
+            idle = self.idle,
+            busy = self.connections.saturating_sub(self.idle),
+        );
+        for (le, count) in &self.write_wait_buckets {
+            out.push_str(&format!(
+                "echidnabot_db_write_wait_seconds_bucket{{le=\"{}\"}} {}\n",
+                le, count
+            ));
+        }
+        out.push_str(&format!(
+            "echidnabot_db_write_wait_seconds_bucket{{le=\"+Inf\"}} {count}\n\
+             echidnabot_db_write_wait_seconds_sum {sum}\n\
+             echidnabot_db_write_wait_seconds_count {count}\n",
+            count = self.write_wait_count,
+            sum = self.write_wait_seconds,
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn write_waits_fill_cumulative_buckets() {
+        let waits = WriteWaits::default();
+        waits.observe(Duration::from_micros(200));
+        waits.observe(Duration::from_millis(20));
+        waits.observe(Duration::from_secs(30));
+
+        let pool = SqlitePoolOptions::new().connect_lazy("sqlite::memory:").unwrap();
+        let stats = PoolStats::collect(&pool, None, &waits);
+        assert_eq!(stats.write_wait_count, 3);
+        assert_eq!(stats.write_wait_buckets[0], (0.001, 1));
+        assert_eq!(stats.write_wait_buckets[2], (0.025, 2));
+        assert_eq!(stats.write_wait_buckets.last(), Some(&(10.0, 2)), "30s is only in +Inf");
+
+        let text = stats.to_prometheus();
+        assert!(text.contains("echidnabot_db_write_wait_seconds_bucket{le=\"0.025\"} 2\n"));
+        assert!(text.contains("echidnabot_db_write_wait_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("echidnabot_db_write_wait_seconds_count 3\n"));
+    }
+}
@@ -3,35 +3,199 @@
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 //! SQLite store implementation
 
+use std::time::Instant;
+
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, Pool, Sqlite};
 use uuid::Uuid;
 
-use super::{models::*, Store};
+use super::batch::{Pending, WriteBehind};
+use super::pool::{PoolStats, WriteWaits};
+use super::{models::*, PurgeSummary, Store};
 use crate::adapters::Platform;
-use crate::dispatcher::ProverKind;
+use crate::config::{DatabaseConfig, WriteBehindConfig};
+use crate::dispatcher::{ExecutionPath, ProverKind};
 use crate::error::{Error, Result};
-use crate::scheduler::JobId;
+use crate::scheduler::{JobId, JobPriority, JobStatus};
+use crate::tenancy::{self, Keyring, MasterKey, TenantKey};
 
 /// SQLite-backed store
 pub struct SqliteStore {
     pool: Pool<Sqlite>,
+    /// The dedicated writer with `[database] single_writer`; writes use
+    /// `pool` otherwise. See [`super::pool`].
+    writer: Option<Pool<Sqlite>>,
+    write_waits: WriteWaits,
+    write_behind: Option<WriteBehind>,
+    /// Set by [`Store::set_master_key`]; see [`crate::tenancy`].
+    keyring: ArcSwapOption<Keyring>,
 }
 
 impl SqliteStore {
-    /// Create a new SQLite store
+    /// Create a new SQLite store with the default `[database]` settings
     pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
-            .await?;
+        Self::connect(&DatabaseConfig {
+            url: database_url.to_string(),
+            ..DatabaseConfig::default()
+        })
+        .await
+    }
 
-        let store = Self { pool };
+    /// Create a new SQLite store with the pool and pragmas from `config`
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self> {
+        let (pool, writer) = super::pool::open(config).await?;
+
+        let store = Self {
+            pool,
+            writer,
+            write_waits: WriteWaits::default(),
+            write_behind: None,
+            keyring: ArcSwapOption::empty(),
+        };
         store.run_migrations().await?;
 
         Ok(store)
     }
 
+    /// A connection to write with: the single writer when configured,
+    /// otherwise any pooled connection. The wait is recorded for
+    /// [`SqliteStore::pool_stats`].
+    async fn writer(&self) -> Result<PoolConnection<Sqlite>> {
+        let started = Instant::now();
+        let conn = self.writer.as_ref().unwrap_or(&self.pool).acquire().await?;
+        self.write_waits.observe(started.elapsed());
+        Ok(conn)
+    }
+
+    /// Buffer non-critical writes per `config` (see [`super::batch`]).
+    /// Whoever owns the store runs [`super::run_write_behind`]
+    /// for the periodic flushes.
+    pub fn with_write_behind(mut self, config: &WriteBehindConfig) -> Self {
+        self.write_behind = config.enabled.then(|| WriteBehind::new(config));
+        self
+    }
+
+    /// Write out buffered writes in one transaction. Returns how many
+    /// were written; on error they stay buffered for the next flush.
+    pub async fn flush(&self) -> Result<usize> {
+        let Some(batch) = &self.write_behind else {
+            return Ok(0);
+        };
+        let pending = batch.take();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let rows = pending.len();
+        match self.write_pending(&pending).await {
+            Ok(()) => Ok(rows),
+            Err(e) => {
+                batch.restore(pending);
+                Err(e)
+            }
+        }
+    }
+
+    async fn write_pending(&self, pending: &Pending) -> Result<()> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        for timing in &pending.file_timings {
+            insert_file_timing(&mut *tx, timing).await?;
+        }
+        for outcome in &pending.tactic_outcomes {
+            insert_tactic_outcome(&mut *tx, outcome).await?;
+        }
+        for (id, at) in &pending.token_touches {
+            set_token_last_used(&mut *tx, *id, *at).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Apply a buffered `last_used_at` the database doesn't have yet.
+    fn overlay_touch(&self, token: &mut ApiTokenRecord) {
+        if let Some(at) = self.write_behind.as_ref().and_then(|b| b.touched_at(token.id)) {
+            token.last_used_at = Some(at);
+        }
+    }
+
+    /// `owner`'s data key: cached, else loaded, else (with `create`) made.
+    async fn tenant_key(
+        &self,
+        keyring: &Keyring,
+        owner: &str,
+        create: bool,
+    ) -> Result<Option<TenantKey>> {
+        if let Some(key) = keyring.get(owner) {
+            return Ok(Some(key));
+        }
+        if create {
+            let wrapped = TenantKey::generate().wrap(keyring.master(), owner)?;
+            sqlx::query(
+                "INSERT INTO tenant_keys (owner, wrapped_key, created_at) VALUES (?, ?, ?) \
+                 ON CONFLICT(owner) DO NOTHING",
+            )
+            .bind(owner)
+            .bind(wrapped)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *self.writer().await?)
+            .await?;
+        }
+        // Read back rather than keep the key just made: another process
+        // may have won the insert.
+        let wrapped: Option<(String,)> =
+            sqlx::query_as("SELECT wrapped_key FROM tenant_keys WHERE owner = ?")
+                .bind(owner)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some((wrapped,)) = wrapped else {
+            return Ok(None);
+        };
+        let key = TenantKey::unwrap(&wrapped, keyring.master(), owner)?;
+        keyring.insert(owner, key.clone());
+        Ok(Some(key))
+    }
+
+    /// `repo`'s webhook secret as it is stored: sealed with its owner's
+    /// key when a master key is set.
+    async fn stored_secret(&self, repo: &Repository) -> Result<Option<String>> {
+        let (Some(keyring), Some(secret)) = (self.keyring.load_full(), &repo.webhook_secret) else {
+            return Ok(repo.webhook_secret.clone());
+        };
+        if tenancy::is_sealed(secret) {
+            return Ok(Some(secret.clone()));
+        }
+        let key = self
+            .tenant_key(&keyring, &repo.owner, true)
+            .await?
+            .ok_or_else(|| Error::Internal(format!("no tenant key for '{}'", repo.owner)))?;
+        key.seal(&repo.id.to_string(), secret).map(Some)
+    }
+
+    /// Open `repo`'s sealed webhook secret in place. One that can't be
+    /// opened stays sealed, so its deliveries are refused.
+    async fn open_secret(&self, repo: &mut Repository) {
+        let Some(sealed) = repo.webhook_secret.as_deref().filter(|s| tenancy::is_sealed(s)) else {
+            return;
+        };
+        // Without a master key, `set_master_key` has warned once already.
+        let Some(keyring) = self.keyring.load_full() else {
+            return;
+        };
+        let opened = match self.tenant_key(&keyring, &repo.owner, false).await {
+            Ok(Some(key)) => key.open(&repo.id.to_string(), sealed),
+            Ok(None) => Err(Error::Config(format!("'{}' has no tenant key", repo.owner))),
+            Err(e) => Err(e),
+        };
+        match opened {
+            Ok(secret) => repo.webhook_secret = Some(secret),
+            Err(e) => {
+                tracing::debug!("Cannot open the webhook secret of {}: {}", repo.full_name(), e)
+            }
+        }
+    }
+
     /// Gracefully close the underlying connection pool.
     ///
     /// Called during shutdown to drain outstanding queries and release
@@ -41,14 +205,21 @@ pub async fn new(database_url: &str) -> Result<Self> {
     ///
     /// See `crate::shutdown::ShutdownCoordinator` for the orchestrated
     /// call site (DB close runs after the scheduler drains, so no
-    /// in-flight job tries to write after the pool is closed).
+    /// in-flight job tries to write after the pool is closed). Buffered
+    /// write-behind rows are flushed first.
     pub async fn close(&self) {
+        if let Err(e) = self.flush().await {
+            tracing::error!("Dropping buffered store writes on close: {}", e);
+        }
+        if let Some(writer) = &self.writer {
+            writer.close().await;
+        }
         self.pool.close().await;
     }
 
-    /// Borrow the underlying pool. Exposed for shutdown coordination
-    /// (e.g. wiring `pool.close()` into the shutdown sequence without
-    /// taking ownership of the `SqliteStore`).
+    /// Borrow the underlying (read) pool. Exposed for shutdown
+    /// coordination (e.g. wiring `pool.close()` into the shutdown sequence
+    /// without taking ownership of the `SqliteStore`).
     pub fn pool(&self) -> &Pool<Sqlite> {
         &self.pool
     }
@@ -73,11 +244,23 @@ async fn run_migrations(&self) -> Result<()> {
                 updated_at TEXT NOT NULL,
                 mode TEXT NOT NULL DEFAULT 'verifier',
                 regulator_coverage_threshold INTEGER NOT NULL DEFAULT 100,
+                branch_include TEXT NOT NULL DEFAULT '[]',
+                branch_exclude TEXT NOT NULL DEFAULT '[]',
+                path_include TEXT NOT NULL DEFAULT '[]',
+                path_exclude TEXT NOT NULL DEFAULT '[]',
+                config_pr_offered_at TEXT,
+                visibility TEXT NOT NULL DEFAULT 'private',
+                default_branch TEXT,
+                training_export INTEGER NOT NULL DEFAULT 0,
+                reverify_cron TEXT,
+                last_reverify_at TEXT,
+                prover_pins TEXT NOT NULL DEFAULT '{}',
+                timezone TEXT,
                 UNIQUE(platform, owner, name)
             )
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         sqlx::query(
@@ -95,11 +278,37 @@ async fn run_migrations(&self) -> Result<()> {
                 completed_at TEXT,
                 error_message TEXT,
                 pr_number INTEGER,
-                delivery_id TEXT
+                delivery_id TEXT,
+                retry_of TEXT,
+                base_sha TEXT,
+                branch TEXT,
+                payload TEXT
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS proof_results (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+                success INTEGER NOT NULL,
+                message TEXT NOT NULL,
+                prover_output TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                verified_files TEXT NOT NULL,
+                failed_files TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                archive_cid TEXT,
+                prover_version TEXT,
+                prover_args TEXT NOT NULL DEFAULT '[]',
+                execution_path TEXT
             )
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         // Idempotent migrations for older databases. SQLite returns
@@ -108,10 +317,30 @@ async fn run_migrations(&self) -> Result<()> {
         for ddl in [
             "ALTER TABLE proof_jobs ADD COLUMN pr_number INTEGER",
             "ALTER TABLE proof_jobs ADD COLUMN delivery_id TEXT",
+            "ALTER TABLE proof_jobs ADD COLUMN retry_of TEXT",
+            "ALTER TABLE proof_jobs ADD COLUMN base_sha TEXT",
+            "ALTER TABLE proof_jobs ADD COLUMN branch TEXT",
+            "ALTER TABLE proof_jobs ADD COLUMN payload TEXT",
+            "ALTER TABLE proof_results ADD COLUMN archive_cid TEXT",
+            "ALTER TABLE proof_results ADD COLUMN prover_version TEXT",
+            "ALTER TABLE proof_results ADD COLUMN prover_args TEXT NOT NULL DEFAULT '[]'",
+            "ALTER TABLE proof_results ADD COLUMN execution_path TEXT",
             "ALTER TABLE repositories ADD COLUMN mode TEXT NOT NULL DEFAULT 'verifier'",
             "ALTER TABLE repositories ADD COLUMN regulator_coverage_threshold INTEGER NOT NULL DEFAULT 100",
+            "ALTER TABLE repositories ADD COLUMN branch_include TEXT NOT NULL DEFAULT '[]'",
+            "ALTER TABLE repositories ADD COLUMN branch_exclude TEXT NOT NULL DEFAULT '[]'",
+            "ALTER TABLE repositories ADD COLUMN config_pr_offered_at TEXT",
+            "ALTER TABLE repositories ADD COLUMN path_include TEXT NOT NULL DEFAULT '[]'",
+            "ALTER TABLE repositories ADD COLUMN path_exclude TEXT NOT NULL DEFAULT '[]'",
+            "ALTER TABLE repositories ADD COLUMN visibility TEXT NOT NULL DEFAULT 'private'",
+            "ALTER TABLE repositories ADD COLUMN default_branch TEXT",
+            "ALTER TABLE repositories ADD COLUMN training_export INTEGER NOT NULL DEFAULT 0",
+            "ALTER TABLE repositories ADD COLUMN reverify_cron TEXT",
+            "ALTER TABLE repositories ADD COLUMN last_reverify_at TEXT",
+            "ALTER TABLE repositories ADD COLUMN prover_pins TEXT NOT NULL DEFAULT '{}'",
+            "ALTER TABLE repositories ADD COLUMN timezone TEXT",
         ] {
-            match sqlx::query(ddl).execute(&self.pool).await {
+            match sqlx::query(ddl).execute(&mut *self.writer().await?).await {
                 Ok(_) => {}
                 Err(sqlx::Error::Database(e))
                     if e.message().contains("duplicate column") =>
@@ -125,36 +354,27 @@ async fn run_migrations(&self) -> Result<()> {
 
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS proof_results (
-                id TEXT PRIMARY KEY,
-                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
-                success INTEGER NOT NULL,
-                message TEXT NOT NULL,
-                prover_output TEXT NOT NULL,
-                duration_ms INTEGER NOT NULL,
-                verified_files TEXT NOT NULL,
-                failed_files TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            )
+            CREATE INDEX IF NOT EXISTS idx_jobs_repo_id ON proof_jobs(repo_id);
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         sqlx::query(
             r#"
-            CREATE INDEX IF NOT EXISTS idx_jobs_repo_id ON proof_jobs(repo_id);
+            CREATE INDEX IF NOT EXISTS idx_jobs_status ON proof_jobs(status);
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
+        // Job search orders and filters by queue time.
         sqlx::query(
             r#"
-            CREATE INDEX IF NOT EXISTS idx_jobs_status ON proof_jobs(status);
+            CREATE INDEX IF NOT EXISTS idx_jobs_queued_at ON proof_jobs(queued_at);
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         // Tactic-outcome table — feedback-loop substrate (Package 7b).
@@ -174,7 +394,7 @@ async fn run_migrations(&self) -> Result<()> {
             )
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         sqlx::query(
@@ -183,7 +403,7 @@ async fn run_migrations(&self) -> Result<()> {
                 ON tactic_outcomes(prover, goal_fingerprint);
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
         sqlx::query(
@@ -192,704 +412,4603 @@ async fn run_migrations(&self) -> Result<()> {
                 ON tactic_outcomes(prover, tactic);
             "#,
         )
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
-}
-
-#[async_trait]
-impl Store for SqliteStore {
-    async fn create_repository(&self, repo: &Repository) -> Result<()> {
-        let enabled_provers = serde_json::to_string(&repo.enabled_provers)?;
-
+        // Per-file timing history — substrate for duration trend anomaly
+        // detection (`crate::perf`). One row per proof file per job.
         sqlx::query(
             r#"
-            INSERT INTO repositories (
-                id, platform, owner, name, webhook_secret, enabled_provers,
-                check_on_push, check_on_pr, auto_comment, enabled,
-                last_checked_commit, created_at, updated_at, mode,
-                regulator_coverage_threshold
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            CREATE TABLE IF NOT EXISTS file_timings (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                prover TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                success INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )
             "#,
         )
-        .bind(repo.id.to_string())
-        .bind(format!("{:?}", repo.platform))
-        .bind(&repo.owner)
-        .bind(&repo.name)
-        .bind(&repo.webhook_secret)
-        .bind(&enabled_provers)
-        .bind(repo.check_on_push)
-        .bind(repo.check_on_pr)
-        .bind(repo.auto_comment)
-        .bind(repo.enabled)
-        .bind(&repo.last_checked_commit)
-        .bind(repo.created_at.to_rfc3339())
-        .bind(repo.updated_at.to_rfc3339())
-        .bind(serde_json::to_value(&repo.mode)?.as_str().unwrap_or("verifier"))
-        .bind(repo.regulator_coverage_threshold as i64)
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
-
-    async fn get_repository(&self, id: Uuid) -> Result<Option<Repository>> {
-        let row: Option<RepoRow> = sqlx::query_as(
-            "SELECT * FROM repositories WHERE id = ?",
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_file_timings_repo_prover_file
+                ON file_timings(repo_id, prover, file_path);
+            "#,
         )
-        .bind(id.to_string())
-        .fetch_optional(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        row.map(|r| r.try_into()).transpose()
-    }
-
-    async fn get_repository_by_name(
-        &self,
-        platform: Platform,
-        owner: &str,
-        name: &str,
-    ) -> Result<Option<Repository>> {
-        let row: Option<RepoRow> = sqlx::query_as(
-            "SELECT * FROM repositories WHERE platform = ? AND owner = ? AND name = ?",
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_file_timings_job_id ON file_timings(job_id);
+            "#,
         )
-        .bind(format!("{:?}", platform))
-        .bind(owner)
-        .bind(name)
-        .fetch_optional(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        row.map(|r| r.try_into()).transpose()
-    }
-
-    async fn list_repositories(&self, platform: Option<Platform>) -> Result<Vec<Repository>> {
-        let rows: Vec<RepoRow> = match platform {
-            Some(p) => {
-                sqlx::query_as("SELECT * FROM repositories WHERE platform = ? ORDER BY created_at DESC")
-                    .bind(format!("{:?}", p))
-                    .fetch_all(&self.pool)
-                    .await?
-            }
-            None => {
-                sqlx::query_as("SELECT * FROM repositories ORDER BY created_at DESC")
-                    .fetch_all(&self.pool)
-                    .await?
-            }
-        };
-
-        rows.into_iter().map(|r| r.try_into()).collect()
-    }
-
-    async fn update_repository(&self, repo: &Repository) -> Result<()> {
-        let enabled_provers = serde_json::to_string(&repo.enabled_provers)?;
-
         sqlx::query(
             r#"
-            UPDATE repositories SET
-                webhook_secret = ?,
-                enabled_provers = ?,
-                check_on_push = ?,
-                check_on_pr = ?,
-                auto_comment = ?,
-                enabled = ?,
-                last_checked_commit = ?,
-                updated_at = ?
-            WHERE id = ?
+            CREATE INDEX IF NOT EXISTS idx_file_timings_repo_commit
+                ON file_timings(repo_id, commit_sha);
             "#,
         )
-        .bind(&repo.webhook_secret)
-        .bind(&enabled_provers)
-        .bind(repo.check_on_push)
-        .bind(repo.check_on_pr)
-        .bind(repo.auto_comment)
-        .bind(repo.enabled)
-        .bind(&repo.last_checked_commit)
-        .bind(repo.updated_at.to_rfc3339())
-        .bind(repo.id.to_string())
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
-
-    async fn delete_repository(&self, id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM repositories WHERE id = ?")
-            .bind(id.to_string())
-            .execute(&self.pool)
-            .await?;
-        Ok(())
-    }
-
-    async fn create_job(&self, job: &ProofJobRecord) -> Result<()> {
-        let file_paths = serde_json::to_string(&job.file_paths)?;
-
+        // API tokens — bearer-token auth for GraphQL / admin routes. Only
+        // the SHA-256 hash is stored (see `crate::api::auth`).
         sqlx::query(
             r#"
-            INSERT INTO proof_jobs (
-                id, repo_id, commit_sha, prover, file_paths,
-                status, priority, queued_at, started_at, completed_at, error_message,
-                pr_number, delivery_id
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            CREATE TABLE IF NOT EXISTS api_tokens (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                token_hash TEXT NOT NULL UNIQUE,
+                scope TEXT NOT NULL,
+                tenant TEXT,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                revoked_at TEXT
+            )
             "#,
         )
-        .bind(job.id.to_string())
-        .bind(job.repo_id.to_string())
-        .bind(&job.commit_sha)
-        .bind(format!("{:?}", job.prover))
-        .bind(&file_paths)
-        .bind(format!("{:?}", job.status))
-        .bind(job.priority as i32)
-        .bind(job.queued_at.to_rfc3339())
-        .bind(job.started_at.map(|t| t.to_rfc3339()))
-        .bind(job.completed_at.map(|t| t.to_rfc3339()))
-        .bind(&job.error_message)
-        .bind(job.pr_number.map(|n| n as i64))
-        .bind(&job.delivery_id)
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
+        // Added after the table; see the ALTERs above.
+        match sqlx::query("ALTER TABLE api_tokens ADD COLUMN tenant TEXT")
+            .execute(&mut *self.writer().await?)
+            .await
+        {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.message().contains("duplicate column") => {}
+            Err(e) => return Err(e.into()),
+        }
 
-    async fn get_job(&self, id: JobId) -> Result<Option<ProofJobRecord>> {
-        let row: Option<JobRow> = sqlx::query_as(
-            "SELECT * FROM proof_jobs WHERE id = ?",
+        // Per-tenant data keys, wrapped by `[tenancy] master_key`.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS tenant_keys (
+                owner TEXT PRIMARY KEY,
+                wrapped_key TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
         )
-        .bind(id.0.to_string())
-        .fetch_optional(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        row.map(|r| r.try_into()).transpose()
-    }
-
-    async fn update_job(&self, job: &ProofJobRecord) -> Result<()> {
+        // Escape hatches (`sorry`, `Admitted`, new axioms) in the files
+        // each job checked.
         sqlx::query(
             r#"
-            UPDATE proof_jobs SET
-                status = ?,
-                started_at = ?,
-                completed_at = ?,
-                error_message = ?
-            WHERE id = ?
+            CREATE TABLE IF NOT EXISTS escape_hatches (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+                file_path TEXT NOT NULL,
+                line INTEGER NOT NULL,
+                token TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
             "#,
         )
-        .bind(format!("{:?}", job.status))
-        .bind(job.started_at.map(|t| t.to_rfc3339()))
-        .bind(job.completed_at.map(|t| t.to_rfc3339()))
-        .bind(&job.error_message)
-        .bind(job.id.to_string())
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_escape_hatches_job ON escape_hatches(job_id)")
+            .execute(&mut *self.writer().await?)
+            .await?;
 
-    async fn list_jobs_for_repo(&self, repo_id: Uuid, limit: usize) -> Result<Vec<ProofJobRecord>> {
-        let rows: Vec<JobRow> = sqlx::query_as(
-            "SELECT * FROM proof_jobs WHERE repo_id = ? ORDER BY queued_at DESC LIMIT ?",
+        // Per-file and per-theorem results of each job.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS proof_items (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+                file_path TEXT NOT NULL,
+                theorem TEXT,
+                line INTEGER,
+                status TEXT NOT NULL,
+                duration_ms INTEGER,
+                created_at TEXT NOT NULL
+            )
+            "#,
         )
-        .bind(repo_id.to_string())
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        rows.into_iter().map(|r| r.try_into()).collect()
-    }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_proof_items_job ON proof_items(job_id)")
+            .execute(&mut *self.writer().await?)
+            .await?;
 
-    async fn list_pending_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>> {
-        let rows: Vec<JobRow> = sqlx::query_as(
-            "SELECT * FROM proof_jobs WHERE status = 'Queued' ORDER BY priority DESC, queued_at ASC LIMIT ?",
+        // Failed files re-run under `[flaky] reruns`, with their verdict.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS flaky_runs (
+                id TEXT PRIMARY KEY,
+                job_id TEXT NOT NULL REFERENCES proof_jobs(id),
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                prover TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                verdict TEXT NOT NULL,
+                timed_out INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#,
         )
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        rows.into_iter().map(|r| r.try_into()).collect()
-    }
-
-    async fn save_result(&self, result: &ProofResultRecord) -> Result<()> {
-        let verified_files = serde_json::to_string(&result.verified_files)?;
-        let failed_files = serde_json::to_string(&result.failed_files)?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_flaky_runs_repo ON flaky_runs(repo_id, created_at)")
+            .execute(&mut *self.writer().await?)
+            .await?;
 
+        // Bisections of default-branch proof failures.
         sqlx::query(
             r#"
-            INSERT INTO proof_results (
-                id, job_id, success, message, prover_output,
-                duration_ms, verified_files, failed_files, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            CREATE TABLE IF NOT EXISTS bisections (
+                id TEXT PRIMARY KEY,
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                prover TEXT NOT NULL,
+                branch TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                theorem TEXT,
+                good_sha TEXT NOT NULL,
+                bad_sha TEXT NOT NULL,
+                pending TEXT NOT NULL,
+                probe_job TEXT,
+                probes INTEGER NOT NULL DEFAULT 0,
+                reused INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL,
+                issue TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
             "#,
         )
-        .bind(result.id.to_string())
-        .bind(result.job_id.to_string())
-        .bind(result.success)
-        .bind(&result.message)
-        .bind(&result.prover_output)
-        .bind(result.duration_ms)
-        .bind(&verified_files)
-        .bind(&failed_files)
-        .bind(result.created_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_bisections_repo ON bisections(repo_id, created_at)")
+            .execute(&mut *self.writer().await?)
+            .await?;
 
-    async fn get_result_for_job(&self, job_id: JobId) -> Result<Option<ProofResultRecord>> {
-        let row: Option<ResultRow> = sqlx::query_as(
-            "SELECT * FROM proof_results WHERE job_id = ?",
+        // Signed verification certificates, one per repository and commit.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS certificates (
+                id TEXT PRIMARY KEY,
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                commit_sha TEXT NOT NULL,
+                envelope TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(repo_id, commit_sha)
+            )
+            "#,
         )
-        .bind(job_id.0.to_string())
-        .fetch_optional(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        row.map(|r| r.try_into()).transpose()
-    }
-
-    async fn commit_coverage(
-        &self,
-        repo_id: Uuid,
-        commit_sha: &str,
-    ) -> Result<super::CommitCoverage> {
-        // LEFT JOIN proof_results onto proof_jobs so jobs without results
-        // (still running) are counted as not-proven. Coverage is a
-        // running tally — Regulator runs at each finalize_job, so the
-        // last job to finalize for a commit gets the final say.
-        let row: (i64, i64) = sqlx::query_as(
+        // Job lifecycle events waiting to be published. Rows are written
+        // by the triggers `set_job_event_outbox` installs.
+        sqlx::query(
             r#"
-            SELECT
-                COUNT(*) as total,
-                COALESCE(SUM(CASE WHEN pr.success = 1 THEN 1 ELSE 0 END), 0) as proven
-            FROM proof_jobs pj
-            LEFT JOIN proof_results pr ON pr.job_id = pj.id
-            WHERE pj.repo_id = ? AND pj.commit_sha = ?
+            CREATE TABLE IF NOT EXISTS job_event_outbox (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                event_id TEXT NOT NULL,
+                job_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                occurred_at TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT
+            )
             "#,
         )
-        .bind(repo_id.to_string())
-        .bind(commit_sha)
-        .fetch_one(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(super::CommitCoverage {
-            total: row.0.max(0) as u64,
-            proven: row.1.max(0) as u64,
-        })
-    }
+        // Per-sink progress of the analytics export.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS analytics_cursors (
+                sink TEXT PRIMARY KEY,
+                completed_at TEXT NOT NULL,
+                job_id TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-    async fn record_tactic_outcome(&self, outcome: &TacticOutcomeRecord) -> Result<()> {
+        // Pending confirmations of destructive GraphQL mutations.
         sqlx::query(
             r#"
-            INSERT INTO tactic_outcomes (
-                id, job_id, prover, goal_fingerprint, tactic,
-                succeeded, duration_ms, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            CREATE TABLE IF NOT EXISTS confirmations (
+                token_hash TEXT PRIMARY KEY,
+                action TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                subject_digest TEXT NOT NULL,
+                impact TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            )
             "#,
         )
-        .bind(outcome.id.to_string())
-        .bind(outcome.job_id.map(|id| id.to_string()))
-        .bind(format!("{:?}", outcome.prover))
-        .bind(&outcome.goal_fingerprint)
-        .bind(&outcome.tactic)
-        .bind(outcome.succeeded)
-        .bind(outcome.duration_ms)
-        .bind(outcome.created_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        Ok(())
-    }
+        // Quarantined proof files — failures reported but not gating.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS quarantined_proofs (
+                id TEXT PRIMARY KEY,
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                file_path TEXT NOT NULL,
+                reason TEXT,
+                created_at TEXT NOT NULL,
+                UNIQUE(repo_id, file_path)
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-    async fn list_tactic_outcomes_by_fingerprint(
-        &self,
-        prover: ProverKind,
-        goal_fingerprint: &str,
-        limit: usize,
-    ) -> Result<Vec<TacticOutcomeRecord>> {
-        let rows: Vec<OutcomeRow> = sqlx::query_as(
-            "SELECT * FROM tactic_outcomes \
-             WHERE prover = ? AND goal_fingerprint = ? \
-             ORDER BY created_at DESC LIMIT ?",
+        // Accepted webhook deliveries awaiting background processing.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id TEXT PRIMARY KEY,
+                platform TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                delivery_id TEXT,
+                payload BLOB NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                received_at TEXT NOT NULL,
+                next_attempt_at TEXT NOT NULL,
+                processed_at TEXT,
+                trace_parent TEXT,
+                UNIQUE(platform, delivery_id)
+            )
+            "#,
         )
-        .bind(format!("{:?}", prover))
-        .bind(goal_fingerprint)
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        rows.into_iter().map(|r| r.try_into()).collect()
-    }
+        // Added after the table; see the ALTERs above.
+        match sqlx::query("ALTER TABLE webhook_deliveries ADD COLUMN trace_parent TEXT")
+            .execute(&mut *self.writer().await?)
+            .await
+        {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.message().contains("duplicate column") => {}
+            Err(e) => return Err(e.into()),
+        }
 
-    async fn list_tactic_outcomes_by_tactic(
-        &self,
-        prover: ProverKind,
-        tactic: &str,
-        limit: usize,
-    ) -> Result<Vec<TacticOutcomeRecord>> {
-        let rows: Vec<OutcomeRow> = sqlx::query_as(
-            "SELECT * FROM tactic_outcomes \
-             WHERE prover = ? AND tactic = ? \
-             ORDER BY created_at DESC LIMIT ?",
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at)",
         )
-        .bind(format!("{:?}", prover))
-        .bind(tactic)
-        .bind(limit as i64)
-        .fetch_all(&self.pool)
+        .execute(&mut *self.writer().await?)
         .await?;
 
-        rows.into_iter().map(|r| r.try_into()).collect()
-    }
+        // Stale-failure policy progress per PR.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS stale_nags (
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                pr_number INTEGER NOT NULL,
+                failing_since TEXT NOT NULL,
+                reminders INTEGER NOT NULL DEFAULT 0,
+                last_reminded_at TEXT,
+                escalated INTEGER NOT NULL DEFAULT 0,
+                drafted INTEGER NOT NULL DEFAULT 0,
+                closed INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (repo_id, pr_number)
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-    async fn health_check(&self) -> Result<bool> {
-        let result: (i32,) = sqlx::query_as("SELECT 1")
-            .fetch_one(&self.pool)
-            .await?;
-        Ok(result.0 == 1)
-    }
-}
+        // Sticky result comment per PR and prover.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS pr_comments (
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                pr_number INTEGER NOT NULL,
+                prover TEXT NOT NULL,
+                comment_id TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (repo_id, pr_number, prover)
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-// =============================================================================
-// Row types for sqlx
-// =============================================================================
+        // Proof import graph per (repo, prover), for incremental pushes.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS dependency_graphs (
+                repo_id TEXT NOT NULL REFERENCES repositories(id),
+                prover TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                graph TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (repo_id, prover)
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-#[derive(sqlx::FromRow)]
-struct RepoRow {
-    id: String,
-    platform: String,
-    owner: String,
-    name: String,
-    webhook_secret: Option<String>,
-    enabled_provers: String,
-    check_on_push: bool,
-    check_on_pr: bool,
-    auto_comment: bool,
-    enabled: bool,
-    last_checked_commit: Option<String>,
-    created_at: String,
-    updated_at: String,
-    #[sqlx(default)]
-    mode: Option<String>,
-    #[sqlx(default)]
-    regulator_coverage_threshold: Option<i64>,
-}
+        // Results imported from federation peers, with their envelopes.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS federated_results (
+                id TEXT PRIMARY KEY,
+                peer TEXT NOT NULL,
+                platform TEXT NOT NULL,
+                repo TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                prover TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                verified_files TEXT NOT NULL,
+                failed_files TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                envelope TEXT NOT NULL,
+                imported_at TEXT NOT NULL,
+                UNIQUE (peer, platform, repo, commit_sha, prover)
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-impl TryFrom<RepoRow> for Repository {
-    type Error = Error;
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_federated_results_lookup
+                ON federated_results(platform, repo, commit_sha, prover);
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-    fn try_from(row: RepoRow) -> Result<Self> {
-        let platform = match row.platform.as_str() {
-            "GitHub" => Platform::GitHub,
-            "GitLab" => Platform::GitLab,
-            "Bitbucket" => Platform::Bitbucket,
-            "Codeberg" => Platform::Codeberg,
-            _ => return Err(Error::Internal(format!("Unknown platform: {}", row.platform))),
-        };
+        // Anonymized (goal, tactic) pairs queued for the training export.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS training_examples (
+                id TEXT PRIMARY KEY,
+                prover TEXT NOT NULL,
+                goal TEXT NOT NULL,
+                tactic TEXT NOT NULL,
+                digest TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                exported_at TEXT
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-        let enabled_provers: Vec<ProverKind> = serde_json::from_str(&row.enabled_provers)?;
+        sqlx::query(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_training_examples_pending
+                ON training_examples(exported_at, created_at);
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-        // Parse mode via serde, falling back to Verifier if the column was
-        // null (older DB pre-migration) or contained an unrecognised value.
-        let mode = row
-            .mode
-            .as_deref()
-            .and_then(|s| {
-                serde_json::from_value::<crate::modes::BotMode>(serde_json::Value::String(
-                    s.to_string(),
-                ))
-                .ok()
-            })
-            .unwrap_or_default();
+        // The maintenance announcement; one row at most.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS announcements (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                message TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT
+            )
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
 
-        Ok(Repository {
-            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
-            platform,
-            owner: row.owner,
-            name: row.name,
-            webhook_secret: row.webhook_secret,
-            enabled_provers,
-            check_on_push: row.check_on_push,
-            check_on_pr: row.check_on_pr,
-            auto_comment: row.auto_comment,
-            enabled: row.enabled,
-            last_checked_commit: row.last_checked_commit,
-            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                .map_err(|e| Error::Internal(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&row.updated_at)
-                .map_err(|e| Error::Internal(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-            mode,
-            regulator_coverage_threshold: row
-                .regulator_coverage_threshold
-                .map(|v| v.clamp(0, 100) as u8)
-                .unwrap_or(100),
-        })
+        // The audit log. Rows are never changed or removed, not even with
+        // their repository; the triggers hold that against stray SQL too.
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                at TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                repo_id TEXT,
+                repo TEXT,
+                target TEXT,
+                payload_digest TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log(at);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_repo ON audit_log(repo_id, at);
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_log is append-only');
+            END;
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'audit_log is append-only');
+            END;
+            "#,
+        )
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
     }
 }
 
-#[derive(sqlx::FromRow)]
-struct JobRow {
-    id: String,
-    repo_id: String,
-    commit_sha: String,
-    prover: String,
-    file_paths: String,
-    status: String,
-    priority: i32,
-    queued_at: String,
-    started_at: Option<String>,
-    completed_at: Option<String>,
-    error_message: Option<String>,
-    #[sqlx(default)]
-    pr_number: Option<i64>,
-    #[sqlx(default)]
-    delivery_id: Option<String>,
-}
+#[async_trait]
+impl Store for SqliteStore {
+    async fn create_repository(&self, repo: &Repository) -> Result<()> {
+        let enabled_provers = serde_json::to_string(&repo.enabled_provers)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO repositories (
+                id, platform, owner, name, webhook_secret, enabled_provers,
+                check_on_push, check_on_pr, auto_comment, enabled,
+                last_checked_commit, created_at, updated_at, mode,
+                regulator_coverage_threshold, branch_include, branch_exclude,
+                config_pr_offered_at, path_include, path_exclude, visibility,
+                default_branch, training_export, reverify_cron, last_reverify_at,
+                prover_pins, timezone
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(repo.id.to_string())
+        .bind(format!("{:?}", repo.platform))
+        .bind(&repo.owner)
+        .bind(&repo.name)
+        .bind(self.stored_secret(repo).await?)
+        .bind(&enabled_provers)
+        .bind(repo.check_on_push)
+        .bind(repo.check_on_pr)
+        .bind(repo.auto_comment)
+        .bind(repo.enabled)
+        .bind(&repo.last_checked_commit)
+        .bind(repo.created_at.to_rfc3339())
+        .bind(repo.updated_at.to_rfc3339())
+        .bind(serde_json::to_value(&repo.mode)?.as_str().unwrap_or("verifier"))
+        .bind(repo.regulator_coverage_threshold as i64)
+        .bind(serde_json::to_string(&repo.branch_include)?)
+        .bind(serde_json::to_string(&repo.branch_exclude)?)
+        .bind(repo.config_pr_offered_at.map(|t| t.to_rfc3339()))
+        .bind(serde_json::to_string(&repo.path_include)?)
+        .bind(serde_json::to_string(&repo.path_exclude)?)
+        .bind(repo.visibility.as_str())
+        .bind(&repo.default_branch)
+        .bind(repo.training_export)
+        .bind(&repo.reverify_cron)
+        .bind(repo.last_reverify_at.map(|t| t.to_rfc3339()))
+        .bind(serde_json::to_string(&repo.prover_pins)?)
+        .bind(&repo.timezone)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_repository(&self, id: Uuid) -> Result<Option<Repository>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let row: Option<RepoRow> = sqlx::query_as(
+            "SELECT * FROM repositories WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let mut repo: Option<Repository> = row.map(|r| r.try_into()).transpose()?;
+        if let Some(repo) = &mut repo {
+            self.open_secret(repo).await;
+        }
+        Ok(repo)
+    }
+
+    async fn get_repositories_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Repository>> {
+        let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+        let rows: Vec<RepoRow> =
+            select_in(&self.pool, "SELECT * FROM repositories WHERE id IN ({ids})", &ids).await?;
+        let mut repos: Vec<Repository> =
+            rows.into_iter().map(|r| r.try_into()).collect::<Result<_>>()?;
+        for repo in &mut repos {
+            self.open_secret(repo).await;
+        }
+        Ok(repos)
+    }
+
+    async fn get_repository_by_name(
+        &self,
+        platform: Platform,
+        owner: &str,
+        name: &str,
+    ) -> Result<Option<Repository>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let row: Option<RepoRow> = sqlx::query_as(
+            "SELECT * FROM repositories WHERE platform = ? AND owner = ? AND name = ?",
+        )
+        .bind(format!("{:?}", platform))
+        .bind(owner)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let mut repo: Option<Repository> = row.map(|r| r.try_into()).transpose()?;
+        if let Some(repo) = &mut repo {
+            self.open_secret(repo).await;
+        }
+        Ok(repo)
+    }
+
+    async fn list_repositories(&self, platform: Option<Platform>) -> Result<Vec<Repository>> {
+        self.list_repositories_partial(platform).await?.into_iter().collect()
+    }
+
+    async fn list_repositories_partial(&self, platform: Option<Platform>) -> Result<Vec<Result<Repository>>> {
+        let rows: Vec<RepoRow> = match platform {
+            Some(p) => {
+                sqlx::query_as("SELECT * FROM repositories WHERE platform = ? ORDER BY created_at DESC")
+                    .bind(format!("{:?}", p))
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query_as("SELECT * FROM repositories ORDER BY created_at DESC")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        let mut repos = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = row.id.clone();
+            let mut repo = Repository::try_from(row)
+                .map_err(|e| Error::Internal(format!("repository {}: {}", id, e)));
+            if let Ok(repo) = &mut repo {
+                self.open_secret(repo).await;
+            }
+            repos.push(repo);
+        }
+        Ok(repos)
+    }
+
+    async fn update_repository(&self, repo: &Repository) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let enabled_provers = serde_json::to_string(&repo.enabled_provers)?;
+
+        sqlx::query(
+            r#"
+            UPDATE repositories SET
+                webhook_secret = ?,
+                enabled_provers = ?,
+                check_on_push = ?,
+                check_on_pr = ?,
+                auto_comment = ?,
+                enabled = ?,
+                last_checked_commit = ?,
+                updated_at = ?,
+                branch_include = ?,
+                branch_exclude = ?,
+                config_pr_offered_at = ?,
+                path_include = ?,
+                path_exclude = ?,
+                visibility = ?,
+                default_branch = ?,
+                training_export = ?,
+                reverify_cron = ?,
+                last_reverify_at = ?,
+                prover_pins = ?,
+                timezone = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(self.stored_secret(repo).await?)
+        .bind(&enabled_provers)
+        .bind(repo.check_on_push)
+        .bind(repo.check_on_pr)
+        .bind(repo.auto_comment)
+        .bind(repo.enabled)
+        .bind(&repo.last_checked_commit)
+        .bind(repo.updated_at.to_rfc3339())
+        .bind(serde_json::to_string(&repo.branch_include)?)
+        .bind(serde_json::to_string(&repo.branch_exclude)?)
+        .bind(repo.config_pr_offered_at.map(|t| t.to_rfc3339()))
+        .bind(serde_json::to_string(&repo.path_include)?)
+        .bind(serde_json::to_string(&repo.path_exclude)?)
+        .bind(repo.visibility.as_str())
+        .bind(&repo.default_branch)
+        .bind(repo.training_export)
+        .bind(&repo.reverify_cron)
+        .bind(repo.last_reverify_at.map(|t| t.to_rfc3339()))
+        .bind(serde_json::to_string(&repo.prover_pins)?)
+        .bind(&repo.timezone)
+        .bind(repo.id.to_string())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_repository(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM repositories WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *self.writer().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_repository(&self, id: Uuid) -> Result<PurgeSummary> {
+        // Buffered timings for the repository's jobs would otherwise be
+        // written after their job is gone.
+        self.flush().await?;
+        let id = id.to_string();
+        let jobs = "SELECT id FROM proof_jobs WHERE repo_id = ?";
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+
+        sqlx::query(&format!(
+            "UPDATE tactic_outcomes SET job_id = NULL WHERE job_id IN ({})",
+            jobs
+        ))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+        let results = sqlx::query(&format!(
+            "DELETE FROM proof_results WHERE job_id IN ({})",
+            jobs
+        ))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query(&format!(
+            "DELETE FROM escape_hatches WHERE job_id IN ({})",
+            jobs
+        ))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "DELETE FROM proof_items WHERE job_id IN ({})",
+            jobs
+        ))
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+        let file_timings = sqlx::query("DELETE FROM file_timings WHERE repo_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        for table in [
+            "quarantined_proofs",
+            "stale_nags",
+            "pr_comments",
+            "dependency_graphs",
+            "flaky_runs",
+            "bisections",
+            "certificates",
+        ] {
+            sqlx::query(&format!("DELETE FROM {} WHERE repo_id = ?", table))
+                .bind(&id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let jobs = sqlx::query("DELETE FROM proof_jobs WHERE repo_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        sqlx::query("DELETE FROM repositories WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(PurgeSummary {
+            jobs,
+            results,
+            file_timings,
+        })
+    }
+
+    async fn create_job(&self, job: &ProofJobRecord) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        // `file_paths` keeps the version-1 form for older builds reading
+        // the same database.
+        let file_paths = serde_json::to_string(&job.payload.file_paths)?;
+        let payload = job.payload.to_json()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO proof_jobs (
+                id, repo_id, commit_sha, prover, file_paths,
+                status, priority, queued_at, started_at, completed_at, error_message,
+                pr_number, delivery_id, retry_of, base_sha, branch, payload
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(job.id.to_string())
+        .bind(job.repo_id.to_string())
+        .bind(&job.commit_sha)
+        .bind(format!("{:?}", job.prover))
+        .bind(&file_paths)
+        .bind(format!("{:?}", job.status))
+        .bind(job.priority as i32)
+        .bind(job.queued_at.to_rfc3339())
+        .bind(job.started_at.map(|t| t.to_rfc3339()))
+        .bind(job.completed_at.map(|t| t.to_rfc3339()))
+        .bind(&job.error_message)
+        .bind(job.pr_number.map(|n| n as i64))
+        .bind(&job.delivery_id)
+        .bind(job.retry_of.map(|id| id.to_string()))
+        .bind(&job.base_sha)
+        .bind(&job.branch)
+        .bind(&payload)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_job(&self, id: JobId) -> Result<Option<ProofJobRecord>> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let row: Option<JobRow> = sqlx::query_as(
+            "SELECT * FROM proof_jobs WHERE id = ?",
+        )
+        .bind(id.0.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn update_job(&self, job: &ProofJobRecord) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        sqlx::query(
+            r#"
+            UPDATE proof_jobs SET
+                status = ?,
+                started_at = ?,
+                completed_at = ?,
+                error_message = ?,
+                file_paths = ?,
+                payload = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(format!("{:?}", job.status))
+        .bind(job.started_at.map(|t| t.to_rfc3339()))
+        .bind(job.completed_at.map(|t| t.to_rfc3339()))
+        .bind(&job.error_message)
+        .bind(serde_json::to_string(&job.payload.file_paths)?)
+        .bind(job.payload.to_json()?)
+        .bind(job.id.to_string())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_jobs_for_repo(&self, repo_id: Uuid, limit: usize) -> Result<Vec<ProofJobRecord>> {
+        self.list_jobs_for_repo_partial(repo_id, limit).await?.into_iter().collect()
+    }
+
+    async fn list_jobs_for_repo_partial(&self, repo_id: Uuid, limit: usize) -> Result<Vec<Result<ProofJobRecord>>> {
+        let rows: Vec<JobRow> = sqlx::query_as(
+            "SELECT * FROM proof_jobs WHERE repo_id = ? ORDER BY queued_at DESC LIMIT ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                let id = r.id.clone();
+                ProofJobRecord::try_from(r)
+                    .map_err(|e| Error::Internal(format!("job {}: {}", id, e)))
+            })
+            .collect())
+    }
+
+    async fn search_jobs(
+        &self,
+        filter: &JobFilter,
+        order: JobOrder,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>> {
+        let direction = match order {
+            JobOrder::NewestFirst => "DESC",
+            JobOrder::OldestFirst => "ASC",
+        };
+        let sql = format!(
+            "SELECT * FROM proof_jobs WHERE {} ORDER BY queued_at {}, id LIMIT ?8 OFFSET ?9",
+            JOB_FILTER, direction
+        );
+        let rows: Vec<JobRow> = bind_job_filter(sqlx::query_as(&sql), filter)
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn count_jobs(&self, filter: &JobFilter) -> Result<u64> {
+        let sql = format!("SELECT COUNT(*) FROM proof_jobs WHERE {}", JOB_FILTER);
+        let (count,): (i64,) = bind_job_filter(sqlx::query_as(&sql), filter)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count as u64)
+    }
+
+    async fn get_jobs_by_ids(&self, ids: &[JobId]) -> Result<Vec<ProofJobRecord>> {
+        let ids: Vec<String> = ids.iter().map(|id| id.0.to_string()).collect();
+        let rows: Vec<JobRow> =
+            select_in(&self.pool, "SELECT * FROM proof_jobs WHERE id IN ({ids})", &ids).await?;
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_jobs_for_repos(&self, repo_ids: &[Uuid], limit: usize) -> Result<Vec<ProofJobRecord>> {
+        let ids: Vec<String> = repo_ids.iter().map(Uuid::to_string).collect();
+        // Each repository's newest `limit` jobs, ranked within the repository.
+        let sql = format!(
+            "SELECT * FROM ( \
+               SELECT *, ROW_NUMBER() OVER (PARTITION BY repo_id ORDER BY queued_at DESC) AS recency \
+               FROM proof_jobs WHERE repo_id IN ({{ids}}) \
+             ) WHERE recency <= {} ORDER BY repo_id, queued_at DESC",
+            limit
+        );
+        let rows: Vec<JobRow> = select_in(&self.pool, &sql, &ids).await?;
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_pending_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>> {
+        let rows: Vec<JobRow> = sqlx::query_as(
+            "SELECT * FROM proof_jobs WHERE status = 'Queued' ORDER BY priority DESC, queued_at ASC LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_running_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>> {
+        let rows: Vec<JobRow> = sqlx::query_as(
+            "SELECT * FROM proof_jobs WHERE status = 'Running' ORDER BY started_at ASC LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_recent_finished_jobs(&self, limit: usize) -> Result<Vec<ProofJobRecord>> {
+        let rows: Vec<JobRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM proof_jobs
+            WHERE status IN ('Completed', 'Failed', 'Cancelled')
+            ORDER BY completed_at DESC LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn count_jobs_by_status(&self) -> Result<Vec<(JobStatus, u64)>> {
+        let rows: Vec<(String, i64)> =
+            sqlx::query_as("SELECT status, COUNT(*) FROM proof_jobs GROUP BY status")
+                .fetch_all(&self.pool)
+                .await?;
+
+        rows.into_iter()
+            .map(|(status, count)| Ok((parse_job_status(&status)?, count.max(0) as u64)))
+            .collect()
+    }
+
+    async fn list_jobs_completed_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>> {
+        let rows: Vec<JobRow> = sqlx::query_as(
+            "SELECT * FROM proof_jobs WHERE completed_at > ? ORDER BY completed_at ASC LIMIT ?",
+        )
+        .bind(since.to_rfc3339())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn job_usage(&self, since: chrono::DateTime<chrono::Utc>, key: UsageKey) -> Result<Vec<JobUsage>> {
+        // Timestamps are RFC 3339 in UTC, so the day is the first ten
+        // characters and `julianday` reads them as they are.
+        let column = match key {
+            UsageKey::Prover => "prover",
+            UsageKey::Repository => "repo_id",
+            UsageKey::Day => "substr(queued_at, 1, 10)",
+        };
+        let rows: Vec<(String, i64, i64, i64, Option<f64>, Option<f64>, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                {column} AS usage_key,
+                COUNT(*),
+                COALESCE(SUM(CASE WHEN status = 'Failed' THEN 1 ELSE 0 END), 0),
+                COUNT(started_at),
+                AVG((julianday(started_at) - julianday(queued_at)) * 86400000.0),
+                SUM((julianday(completed_at) - julianday(started_at)) * 86400000.0),
+                COALESCE(SUM((SELECT COUNT(*) FROM escape_hatches e WHERE e.job_id = proof_jobs.id)), 0)
+            FROM proof_jobs
+            WHERE queued_at >= ?
+            GROUP BY usage_key
+            ORDER BY usage_key
+            "#
+        ))
+        .bind(since.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(key, jobs, failed, started, avg_wait_ms, busy_ms, escape_hatches)| JobUsage {
+                key,
+                jobs: jobs.max(0) as u64,
+                failed: failed.max(0) as u64,
+                started: started.max(0) as u64,
+                avg_wait_ms: avg_wait_ms.map(|ms| ms.max(0.0)),
+                busy_ms: busy_ms.unwrap_or(0.0).max(0.0).round() as u64,
+                escape_hatches: escape_hatches.max(0) as u64,
+            })
+            .collect())
+    }
+
+    async fn queue_waits(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        priority: JobPriority,
+        limit_ms: u64,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<QueueWaits> {
+        // Jobs still queued count once they are past the limit; those
+        // cancelled before starting never do.
+        let waited_out = now - chrono::Duration::milliseconds(limit_ms as i64);
+        let (jobs, within, avg_wait_ms, max_wait_ms): (i64, i64, Option<f64>, Option<f64>) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(CASE WHEN wait_ms <= ? THEN 1 ELSE 0 END), 0),
+                AVG(wait_ms),
+                MAX(wait_ms)
+            FROM (
+                SELECT (julianday(started_at) - julianday(queued_at)) * 86400000.0 AS wait_ms
+                FROM proof_jobs
+                WHERE priority = ? AND queued_at >= ?
+                  AND (started_at IS NOT NULL OR (status = 'Queued' AND queued_at <= ?))
+            )
+            "#,
+        )
+        .bind(limit_ms as f64)
+        .bind(priority as i32)
+        .bind(since.to_rfc3339())
+        .bind(waited_out.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(QueueWaits {
+            jobs: jobs.max(0) as u64,
+            within: within.max(0) as u64,
+            avg_wait_ms: avg_wait_ms.map(|ms| ms.max(0.0)),
+            max_wait_ms: max_wait_ms.map(|ms| ms.max(0.0)),
+        })
+    }
+
+    async fn save_result(&self, result: &ProofResultRecord) -> Result<()> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let verified_files = serde_json::to_string(&result.verified_files)?;
+        let failed_files = serde_json::to_string(&result.failed_files)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO proof_results (
+                id, job_id, success, message, prover_output,
+                duration_ms, verified_files, failed_files, created_at, archive_cid,
+                prover_version, prover_args, execution_path
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(result.id.to_string())
+        .bind(result.job_id.to_string())
+        .bind(result.success)
+        .bind(&result.message)
+        .bind(&result.prover_output)
+        .bind(result.duration_ms)
+        .bind(&verified_files)
+        .bind(&failed_files)
+        .bind(result.created_at.to_rfc3339())
+        .bind(&result.archive_cid)
+        .bind(&result.prover_version)
+        .bind(serde_json::to_string(&result.prover_args)?)
+        .bind(result.execution_path.map(|path| path.as_str()))
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_result_archive_cid(&self, job_id: JobId, cid: &str) -> Result<()> {
+        sqlx::query("UPDATE proof_results SET archive_cid = ? WHERE job_id = ?")
+            .bind(cid)
+            .bind(job_id.0.to_string())
+            .execute(&mut *self.writer().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_result_for_job(&self, job_id: JobId) -> Result<Option<ProofResultRecord>> {
+        let row: Option<ResultRow> = sqlx::query_as(
+            "SELECT * FROM proof_results WHERE job_id = ?",
+        )
+        .bind(job_id.0.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn get_results_by_job_ids(&self, job_ids: &[JobId]) -> Result<Vec<ProofResultRecord>> {
+        let ids: Vec<String> = job_ids.iter().map(|id| id.0.to_string()).collect();
+        let rows: Vec<ResultRow> =
+            select_in(&self.pool, "SELECT * FROM proof_results WHERE job_id IN ({ids})", &ids).await?;
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_results_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<Vec<ProofResultRecord>> {
+        // Without `after`, `id > NULL` holds for no row: ties at `since`
+        // are left out.
+        let rows: Vec<ResultRow> = sqlx::query_as(
+            "SELECT * FROM proof_results \
+             WHERE created_at > ? OR (created_at = ? AND id > ?) \
+             ORDER BY created_at ASC, id ASC LIMIT ?",
+        )
+        .bind(since.to_rfc3339())
+        .bind(since.to_rfc3339())
+        .bind(after.map(|id| id.to_string()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn commit_coverage(
+        &self,
+        repo_id: Uuid,
+        commit_sha: &str,
+    ) -> Result<super::CommitCoverage> {
+        // LEFT JOIN proof_results onto proof_jobs so jobs without results
+        // (still running) are counted as not-proven. Coverage is a
+        // running tally — Regulator runs at each finalize_job, so the
+        // last job to finalize for a commit gets the final say.
+        let row: (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) as total,
+                COALESCE(SUM(CASE WHEN pr.success = 1 THEN 1 ELSE 0 END), 0) as proven
+            FROM proof_jobs pj
+            LEFT JOIN proof_results pr ON pr.job_id = pj.id
+            WHERE pj.repo_id = ? AND pj.commit_sha = ?
+            "#,
+        )
+        .bind(repo_id.to_string())
+        .bind(commit_sha)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(super::CommitCoverage {
+            total: row.0.max(0) as u64,
+            proven: row.1.max(0) as u64,
+        })
+    }
+
+    async fn record_tactic_outcome(&self, outcome: &TacticOutcomeRecord) -> Result<()> {
+        if let Some(batch) = &self.write_behind {
+            if batch.push(|p| p.tactic_outcomes.push(outcome.clone())) {
+                self.flush().await?;
+            }
+            return Ok(());
+        }
+        insert_tactic_outcome(&mut *self.writer().await?, outcome).await
+    }
+
+    async fn list_tactic_outcomes_by_fingerprint(
+        &self,
+        prover: ProverKind,
+        goal_fingerprint: &str,
+        limit: usize,
+    ) -> Result<Vec<TacticOutcomeRecord>> {
+        self.flush().await?;
+        let rows: Vec<OutcomeRow> = sqlx::query_as(
+            "SELECT * FROM tactic_outcomes \
+             WHERE prover = ? AND goal_fingerprint = ? \
+             ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(format!("{:?}", prover))
+        .bind(goal_fingerprint)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_tactic_outcomes_by_tactic(
+        &self,
+        prover: ProverKind,
+        tactic: &str,
+        limit: usize,
+    ) -> Result<Vec<TacticOutcomeRecord>> {
+        self.flush().await?;
+        let rows: Vec<OutcomeRow> = sqlx::query_as(
+            "SELECT * FROM tactic_outcomes \
+             WHERE prover = ? AND tactic = ? \
+             ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(format!("{:?}", prover))
+        .bind(tactic)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn record_file_timing(&self, timing: &FileTimingRecord) -> Result<()> {
+        if let Some(batch) = &self.write_behind {
+            if batch.push(|p| p.file_timings.push(timing.clone())) {
+                self.flush().await?;
+            }
+            return Ok(());
+        }
+        insert_file_timing(&mut *self.writer().await?, timing).await
+    }
+
+    async fn list_file_timings(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<FileTimingRecord>> {
+        self.flush().await?;
+        let rows: Vec<TimingRow> = sqlx::query_as(
+            "SELECT * FROM file_timings \
+             WHERE repo_id = ? AND prover = ? AND file_path = ? \
+             ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(format!("{:?}", prover))
+        .bind(file_path)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_file_timings_for_job(&self, job_id: JobId) -> Result<Vec<FileTimingRecord>> {
+        self.flush().await?;
+        let rows: Vec<TimingRow> = sqlx::query_as(
+            "SELECT * FROM file_timings WHERE job_id = ? ORDER BY file_path ASC",
+        )
+        .bind(job_id.0.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_file_timings_for_commit(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        commit_sha: &str,
+    ) -> Result<Vec<FileTimingRecord>> {
+        self.flush().await?;
+        let rows: Vec<TimingRow> = sqlx::query_as(
+            "SELECT * FROM file_timings \
+             WHERE repo_id = ? AND prover = ? AND commit_sha = ? \
+             ORDER BY created_at DESC",
+        )
+        .bind(repo_id.to_string())
+        .bind(format!("{:?}", prover))
+        .bind(commit_sha)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn create_api_token(&self, token: &ApiTokenRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_tokens (
+                id, name, token_hash, scope, tenant, created_at, last_used_at, revoked_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(token.id.to_string())
+        .bind(&token.name)
+        .bind(&token.token_hash)
+        .bind(token.scope.as_str())
+        .bind(&token.tenant)
+        .bind(token.created_at.to_rfc3339())
+        .bind(token.last_used_at.map(|t| t.to_rfc3339()))
+        .bind(token.revoked_at.map(|t| t.to_rfc3339()))
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiTokenRecord>> {
+        let row: Option<TokenRow> = sqlx::query_as(
+            "SELECT * FROM api_tokens WHERE token_hash = ?",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let mut token: Option<ApiTokenRecord> = row.map(|r| r.try_into()).transpose()?;
+        if let Some(token) = &mut token {
+            self.overlay_touch(token);
+        }
+        Ok(token)
+    }
+
+    async fn list_api_tokens(&self) -> Result<Vec<ApiTokenRecord>> {
+        let rows: Vec<TokenRow> = sqlx::query_as(
+            "SELECT * FROM api_tokens ORDER BY created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tokens: Vec<ApiTokenRecord> =
+            rows.into_iter().map(|r| r.try_into()).collect::<Result<_>>()?;
+        for token in &mut tokens {
+            self.overlay_touch(token);
+        }
+        Ok(tokens)
+    }
+
+    async fn revoke_api_token(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL",
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn touch_api_token(&self, id: Uuid) -> Result<()> {
+        let now = chrono::Utc::now();
+        if let Some(batch) = &self.write_behind {
+            if batch.push(|p| {
+                p.token_touches.insert(id, now);
+            }) {
+                self.flush().await?;
+            }
+            return Ok(());
+        }
+        set_token_last_used(&mut *self.writer().await?, id, now).await
+    }
+
+    async fn set_master_key(&self, master: Option<MasterKey>) -> Result<()> {
+        let Some(master) = master else {
+            self.keyring.store(None);
+            let (keys,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tenant_keys")
+                .fetch_one(&self.pool)
+                .await?;
+            if keys > 0 {
+                tracing::warn!(
+                    "{} tenant key(s) stored but no [tenancy] master_key set: \
+                     sealed webhook secrets can't be opened",
+                    keys
+                );
+            }
+            return Ok(());
+        };
+        // Unwrap every stored key now: a wrong master key fails startup
+        // instead of every webhook delivery.
+        let keyring = Keyring::new(master);
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT owner, wrapped_key FROM tenant_keys")
+                .fetch_all(&self.pool)
+                .await?;
+        for (owner, wrapped) in rows {
+            let key = TenantKey::unwrap(&wrapped, keyring.master(), &owner)?;
+            keyring.insert(&owner, key);
+        }
+        self.keyring.store(Some(std::sync::Arc::new(keyring)));
+        Ok(())
+    }
+
+    async fn list_tenant_keys(&self) -> Result<Vec<TenantKeyRecord>> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT owner, created_at FROM tenant_keys ORDER BY owner")
+                .fetch_all(&self.pool)
+                .await?;
+        rows.into_iter()
+            .map(|(owner, created_at)| {
+                let created_at = chrono::DateTime::parse_from_rfc3339(&created_at)
+                    .map_err(|e| Error::Internal(e.to_string()))?
+                    .with_timezone(&chrono::Utc);
+                Ok(TenantKeyRecord { owner, created_at })
+            })
+            .collect()
+    }
+
+    async fn delete_tenant_key(&self, owner: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM tenant_keys WHERE owner = ?")
+            .bind(owner)
+            .execute(&mut *self.writer().await?)
+            .await?;
+        if let Some(keyring) = self.keyring.load_full() {
+            keyring.remove(owner);
+        }
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_escape_hatches(&self, job_id: JobId, hatches: &[EscapeHatchRecord]) -> Result<()> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM escape_hatches WHERE job_id = ?")
+            .bind(job_id.0.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for hatch in hatches {
+            sqlx::query(
+                r#"
+                INSERT INTO escape_hatches (
+                    id, job_id, file_path, line, token, created_at
+                ) VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(hatch.id.to_string())
+            .bind(job_id.0.to_string())
+            .bind(&hatch.file_path)
+            .bind(hatch.line as i64)
+            .bind(&hatch.token)
+            .bind(hatch.created_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_escape_hatches(&self, job_id: JobId) -> Result<Vec<EscapeHatchRecord>> {
+        let rows: Vec<EscapeHatchRow> = sqlx::query_as(
+            "SELECT * FROM escape_hatches WHERE job_id = ? ORDER BY file_path ASC, line ASC",
+        )
+        .bind(job_id.0.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn record_proof_items(&self, job_id: JobId, items: &[ProofItemRecord]) -> Result<()> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        sqlx::query("DELETE FROM proof_items WHERE job_id = ?")
+            .bind(job_id.0.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for item in items {
+            sqlx::query(
+                r#"
+                INSERT INTO proof_items (
+                    id, job_id, file_path, theorem, line, status, duration_ms, created_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(item.id.to_string())
+            .bind(job_id.0.to_string())
+            .bind(&item.file_path)
+            .bind(&item.theorem)
+            .bind(item.line.map(|l| l as i64))
+            .bind(format!("{:?}", item.status))
+            .bind(item.duration_ms)
+            .bind(item.created_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_proof_items(&self, job_id: JobId) -> Result<Vec<ProofItemRecord>> {
+        // NULLs sort first: the file's own item, then its theorems.
+        let rows: Vec<ProofItemRow> = sqlx::query_as(
+            "SELECT * FROM proof_items WHERE job_id = ? ORDER BY file_path ASC, line ASC, theorem ASC",
+        )
+        .bind(job_id.0.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn list_theorem_timings(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+        file_path: &str,
+        limit: usize,
+    ) -> Result<Vec<ProofItemRecord>> {
+        let rows: Vec<ProofItemRow> = sqlx::query_as(
+            "SELECT i.* FROM proof_items i JOIN proof_jobs j ON j.id = i.job_id \
+             WHERE j.repo_id = ? AND j.prover = ? AND i.file_path = ? \
+             AND i.theorem IS NOT NULL AND i.status = 'Verified' AND i.duration_ms IS NOT NULL \
+             ORDER BY i.created_at DESC LIMIT ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(format!("{:?}", prover))
+        .bind(file_path)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn record_flaky_run(&self, run: &FlakyRunRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO flaky_runs (
+                id, job_id, repo_id, prover, file_path, commit_sha,
+                attempts, verdict, timed_out, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(run.id.to_string())
+        .bind(run.job_id.to_string())
+        .bind(run.repo_id.to_string())
+        .bind(format!("{:?}", run.prover))
+        .bind(&run.file_path)
+        .bind(&run.commit_sha)
+        .bind(run.attempts as i64)
+        .bind(format!("{:?}", run.verdict))
+        .bind(run.timed_out)
+        .bind(run.created_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_flaky_runs(&self, repo_id: Uuid, limit: usize) -> Result<Vec<FlakyRunRecord>> {
+        let rows: Vec<FlakyRunRow> = sqlx::query_as(
+            "SELECT * FROM flaky_runs WHERE repo_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn upsert_bisection(&self, bisection: &BisectionRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO bisections (
+                id, repo_id, prover, branch, file_path, theorem, good_sha, bad_sha,
+                pending, probe_job, probes, reused, status, issue, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                theorem = excluded.theorem,
+                good_sha = excluded.good_sha,
+                bad_sha = excluded.bad_sha,
+                pending = excluded.pending,
+                probe_job = excluded.probe_job,
+                probes = excluded.probes,
+                reused = excluded.reused,
+                status = excluded.status,
+                issue = excluded.issue,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(bisection.id.to_string())
+        .bind(bisection.repo_id.to_string())
+        .bind(format!("{:?}", bisection.prover))
+        .bind(&bisection.branch)
+        .bind(&bisection.file_path)
+        .bind(&bisection.theorem)
+        .bind(&bisection.good_sha)
+        .bind(&bisection.bad_sha)
+        .bind(serde_json::to_string(&bisection.pending)?)
+        .bind(bisection.probe_job.map(|id| id.to_string()))
+        .bind(bisection.probes as i64)
+        .bind(bisection.reused as i64)
+        .bind(format!("{:?}", bisection.status))
+        .bind(&bisection.issue)
+        .bind(bisection.created_at.to_rfc3339())
+        .bind(bisection.updated_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_bisection(&self, id: Uuid) -> Result<Option<BisectionRecord>> {
+        let row: Option<BisectionRow> = sqlx::query_as("SELECT * FROM bisections WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn list_bisections(&self, repo_id: Uuid, limit: usize) -> Result<Vec<BisectionRecord>> {
+        let rows: Vec<BisectionRow> = sqlx::query_as(
+            "SELECT * FROM bisections WHERE repo_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn save_certificate(&self, certificate: &CertificateRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO certificates (id, repo_id, commit_sha, envelope, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(repo_id, commit_sha) DO UPDATE SET
+                id = excluded.id,
+                envelope = excluded.envelope,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(certificate.id.to_string())
+        .bind(certificate.repo_id.to_string())
+        .bind(&certificate.commit_sha)
+        .bind(serde_json::to_string(&certificate.envelope)?)
+        .bind(certificate.created_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_certificate(
+        &self,
+        repo_id: Uuid,
+        commit_sha: &str,
+    ) -> Result<Option<CertificateRecord>> {
+        let row: Option<CertificateRow> = sqlx::query_as(
+            "SELECT * FROM certificates WHERE repo_id = ? AND commit_sha = ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(commit_sha)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn set_job_event_outbox(&self, enabled: bool) -> Result<()> {
+        let mut conn = self.writer().await?;
+        if !enabled {
+            for trigger in ["job_event_outbox_insert", "job_event_outbox_status"] {
+                sqlx::query(&format!("DROP TRIGGER IF EXISTS {}", trigger))
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            return Ok(());
+        }
+        // Triggers rather than application writes: the event commits or
+        // rolls back with the change, whichever code path makes it.
+        let record = r#"
+                INSERT INTO job_event_outbox (event_id, job_id, status, occurred_at)
+                VALUES (
+                    lower(hex(randomblob(16))),
+                    NEW.id,
+                    NEW.status,
+                    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                );
+        "#;
+        sqlx::query(&format!(
+            "CREATE TRIGGER IF NOT EXISTS job_event_outbox_insert              AFTER INSERT ON proof_jobs BEGIN {} END",
+            record
+        ))
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(&format!(
+            "CREATE TRIGGER IF NOT EXISTS job_event_outbox_status              AFTER UPDATE OF status ON proof_jobs              WHEN NEW.status IS NOT OLD.status BEGIN {} END",
+            record
+        ))
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_job_events(&self, limit: usize) -> Result<Vec<JobEventRecord>> {
+        let rows: Vec<JobEventRow> =
+            sqlx::query_as("SELECT * FROM job_event_outbox ORDER BY seq ASC LIMIT ?")
+                .bind(limit as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn delete_job_events(&self, seqs: &[i64]) -> Result<()> {
+        if seqs.is_empty() {
+            return Ok(());
+        }
+        let placeholders = vec!["?"; seqs.len()].join(", ");
+        let sql = format!("DELETE FROM job_event_outbox WHERE seq IN ({})", placeholders);
+        let mut query = sqlx::query(&sql);
+        for seq in seqs {
+            query = query.bind(seq);
+        }
+        query.execute(&mut *self.writer().await?).await?;
+        Ok(())
+    }
+
+    async fn record_job_event_failure(&self, seq: i64, error: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE job_event_outbox SET attempts = attempts + 1, last_error = ? WHERE seq = ?",
+        )
+        .bind(error)
+        .bind(seq)
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_jobs_finished_after(
+        &self,
+        after: Option<(chrono::DateTime<chrono::Utc>, Uuid)>,
+        limit: usize,
+    ) -> Result<Vec<ProofJobRecord>> {
+        // Timestamps are RFC 3339 in UTC, so they compare as text.
+        let (at, id) = match after {
+            Some((at, id)) => (at.to_rfc3339(), id.to_string()),
+            None => (String::new(), String::new()),
+        };
+        let rows: Vec<JobRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM proof_jobs
+            WHERE completed_at IS NOT NULL
+              AND (completed_at > ? OR (completed_at = ? AND id > ?))
+            ORDER BY completed_at ASC, id ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(&at)
+        .bind(&at)
+        .bind(&id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn get_analytics_cursor(&self, sink: &str) -> Result<Option<AnalyticsCursor>> {
+        let row: Option<AnalyticsCursorRow> =
+            sqlx::query_as("SELECT * FROM analytics_cursors WHERE sink = ?")
+                .bind(sink)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn set_analytics_cursor(&self, cursor: &AnalyticsCursor) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_cursors (sink, completed_at, job_id, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(sink) DO UPDATE SET
+                completed_at = excluded.completed_at,
+                job_id = excluded.job_id,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&cursor.sink)
+        .bind(cursor.completed_at.to_rfc3339())
+        .bind(cursor.job_id.to_string())
+        .bind(cursor.updated_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_analytics_cursor(&self, sink: &str) -> Result<()> {
+        sqlx::query("DELETE FROM analytics_cursors WHERE sink = ?")
+            .bind(sink)
+            .execute(&mut *self.writer().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn create_confirmation(&self, confirmation: &ConfirmationRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO confirmations (
+                token_hash, action, actor, subject_digest, impact, created_at, expires_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&confirmation.token_hash)
+        .bind(&confirmation.action)
+        .bind(&confirmation.actor)
+        .bind(&confirmation.subject_digest)
+        .bind(&confirmation.impact)
+        .bind(confirmation.created_at.to_rfc3339())
+        .bind(confirmation.expires_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn take_confirmation(&self, token_hash: &str) -> Result<Option<ConfirmationRecord>> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        let row: Option<ConfirmationRow> =
+            sqlx::query_as("SELECT * FROM confirmations WHERE token_hash = ?")
+                .bind(token_hash)
+                .fetch_optional(&mut *tx)
+                .await?;
+        sqlx::query("DELETE FROM confirmations WHERE token_hash = ? OR expires_at < ?")
+            .bind(token_hash)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn quarantine_proof(&self, entry: &QuarantinedProof) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO quarantined_proofs (
+                id, repo_id, file_path, reason, created_at
+            ) VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(entry.id.to_string())
+        .bind(entry.repo_id.to_string())
+        .bind(&entry.file_path)
+        .bind(&entry.reason)
+        .bind(entry.created_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_quarantined_proofs(&self, repo_id: Uuid) -> Result<Vec<QuarantinedProof>> {
+        let rows: Vec<QuarantineRow> = sqlx::query_as(
+            "SELECT * FROM quarantined_proofs WHERE repo_id = ? ORDER BY file_path ASC",
+        )
+        .bind(repo_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn unquarantine_proof(&self, repo_id: Uuid, file_path: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM quarantined_proofs WHERE repo_id = ? AND file_path = ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(file_path)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_webhook_delivery(&self, delivery: &WebhookDeliveryRecord) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO webhook_deliveries (
+                id, platform, event_type, delivery_id, payload, status,
+                attempts, last_error, received_at, next_attempt_at, processed_at,
+                trace_parent
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(delivery.id.to_string())
+        .bind(format!("{:?}", delivery.platform))
+        .bind(&delivery.event_type)
+        .bind(&delivery.delivery_id)
+        .bind(&delivery.payload)
+        .bind(format!("{:?}", delivery.status))
+        .bind(delivery.attempts as i64)
+        .bind(&delivery.last_error)
+        .bind(delivery.received_at.to_rfc3339())
+        .bind(delivery.next_attempt_at.to_rfc3339())
+        .bind(delivery.processed_at.map(|t| t.to_rfc3339()))
+        .bind(&delivery.trace_parent)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_due_webhook_deliveries(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDeliveryRecord>> {
+        let rows: Vec<DeliveryRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM webhook_deliveries
+            WHERE status = 'Pending' AND next_attempt_at <= ?
+            ORDER BY received_at ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(now.to_rfc3339())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn update_webhook_delivery(&self, delivery: &WebhookDeliveryRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE webhook_deliveries SET
+                status = ?,
+                attempts = ?,
+                last_error = ?,
+                next_attempt_at = ?,
+                processed_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(format!("{:?}", delivery.status))
+        .bind(delivery.attempts as i64)
+        .bind(&delivery.last_error)
+        .bind(delivery.next_attempt_at.to_rfc3339())
+        .bind(delivery.processed_at.map(|t| t.to_rfc3339()))
+        .bind(delivery.id.to_string())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_stale_nag(&self, repo_id: Uuid, pr_number: u64) -> Result<Option<StaleNagRecord>> {
+        let row: Option<StaleNagRow> = sqlx::query_as(
+            "SELECT * FROM stale_nags WHERE repo_id = ? AND pr_number = ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(pr_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn upsert_stale_nag(&self, nag: &StaleNagRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO stale_nags (
+                repo_id, pr_number, failing_since, reminders, last_reminded_at,
+                escalated, drafted, closed
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(repo_id, pr_number) DO UPDATE SET
+                failing_since = excluded.failing_since,
+                reminders = excluded.reminders,
+                last_reminded_at = excluded.last_reminded_at,
+                escalated = excluded.escalated,
+                drafted = excluded.drafted,
+                closed = excluded.closed
+            "#,
+        )
+        .bind(nag.repo_id.to_string())
+        .bind(nag.pr_number as i64)
+        .bind(nag.failing_since.to_rfc3339())
+        .bind(nag.reminders as i64)
+        .bind(nag.last_reminded_at.map(|t| t.to_rfc3339()))
+        .bind(nag.escalated)
+        .bind(nag.drafted)
+        .bind(nag.closed)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_stale_nag(&self, repo_id: Uuid, pr_number: u64) -> Result<()> {
+        sqlx::query("DELETE FROM stale_nags WHERE repo_id = ? AND pr_number = ?")
+            .bind(repo_id.to_string())
+            .bind(pr_number as i64)
+            .execute(&mut *self.writer().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_pr_comment(
+        &self,
+        repo_id: Uuid,
+        pr_number: u64,
+        prover: ProverKind,
+    ) -> Result<Option<PrCommentRecord>> {
+        let row: Option<PrCommentRow> = sqlx::query_as(
+            "SELECT * FROM pr_comments WHERE repo_id = ? AND pr_number = ? AND prover = ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(pr_number as i64)
+        .bind(format!("{:?}", prover))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn upsert_pr_comment(&self, record: &PrCommentRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO pr_comments (repo_id, pr_number, prover, comment_id, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(repo_id, pr_number, prover) DO UPDATE SET
+                comment_id = excluded.comment_id,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(record.repo_id.to_string())
+        .bind(record.pr_number as i64)
+        .bind(format!("{:?}", record.prover))
+        .bind(&record.comment_id)
+        .bind(record.updated_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_dependency_graph(
+        &self,
+        repo_id: Uuid,
+        prover: ProverKind,
+    ) -> Result<Option<DependencyGraphRecord>> {
+        let row: Option<GraphRow> = sqlx::query_as(
+            "SELECT * FROM dependency_graphs WHERE repo_id = ? AND prover = ?",
+        )
+        .bind(repo_id.to_string())
+        .bind(format!("{:?}", prover))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn upsert_dependency_graph(&self, record: &DependencyGraphRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO dependency_graphs (
+                repo_id, prover, commit_sha, graph, updated_at
+            ) VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(repo_id, prover) DO UPDATE SET
+                commit_sha = excluded.commit_sha,
+                graph = excluded.graph,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(record.repo_id.to_string())
+        .bind(format!("{:?}", record.prover))
+        .bind(&record.commit_sha)
+        .bind(serde_json::to_string(&record.graph)?)
+        .bind(record.updated_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upsert_federated_result(&self, record: &FederatedResultRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO federated_results (
+                id, peer, platform, repo, commit_sha, prover, success,
+                verified_files, failed_files, completed_at, envelope, imported_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(peer, platform, repo, commit_sha, prover) DO UPDATE SET
+                success = excluded.success,
+                verified_files = excluded.verified_files,
+                failed_files = excluded.failed_files,
+                completed_at = excluded.completed_at,
+                envelope = excluded.envelope,
+                imported_at = excluded.imported_at
+            "#,
+        )
+        .bind(record.id.to_string())
+        .bind(&record.peer)
+        .bind(format!("{:?}", record.platform))
+        .bind(&record.repo)
+        .bind(&record.commit_sha)
+        .bind(format!("{:?}", record.prover))
+        .bind(record.success)
+        .bind(serde_json::to_string(&record.verified_files)?)
+        .bind(serde_json::to_string(&record.failed_files)?)
+        .bind(record.completed_at.to_rfc3339())
+        .bind(&record.envelope)
+        .bind(record.imported_at.to_rfc3339())
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_federated_result(
+        &self,
+        platform: Platform,
+        repo: &str,
+        commit_sha: &str,
+        prover: ProverKind,
+    ) -> Result<Option<FederatedResultRecord>> {
+        let row: Option<FederatedRow> = sqlx::query_as(
+            "SELECT * FROM federated_results \
+             WHERE platform = ? AND repo = ? AND commit_sha = ? AND prover = ? \
+             ORDER BY completed_at DESC LIMIT 1",
+        )
+        .bind(format!("{:?}", platform))
+        .bind(repo)
+        .bind(commit_sha)
+        .bind(format!("{:?}", prover))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn latest_federated_result_at(
+        &self,
+        peer: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let row: (Option<String>,) = sqlx::query_as(
+            "SELECT MAX(completed_at) FROM federated_results WHERE peer = ?",
+        )
+        .bind(peer)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.0
+            .map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&chrono::Utc))
+            })
+            .transpose()
+            .map_err(|e| Error::Internal(e.to_string()))
+    }
+
+    async fn queue_training_examples(&self, examples: &[TrainingExampleRecord]) -> Result<usize> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        let mut queued = 0;
+        for example in examples {
+            let result = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO training_examples (
+                    id, prover, goal, tactic, digest, created_at, exported_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(example.id.to_string())
+            .bind(format!("{:?}", example.prover))
+            .bind(&example.goal)
+            .bind(&example.tactic)
+            .bind(&example.digest)
+            .bind(example.created_at.to_rfc3339())
+            .bind(example.exported_at.map(|t| t.to_rfc3339()))
+            .execute(&mut *tx)
+            .await?;
+            queued += result.rows_affected() as usize;
+        }
+        tx.commit().await?;
+        Ok(queued)
+    }
+
+    async fn list_pending_training_examples(&self, limit: usize) -> Result<Vec<TrainingExampleRecord>> {
+        let rows: Vec<TrainingExampleRow> = sqlx::query_as(
+            "SELECT * FROM training_examples WHERE exported_at IS NULL ORDER BY created_at LIMIT ?",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn mark_training_examples_exported(
+        &self,
+        ids: &[Uuid],
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let mut conn = self.writer().await?;
+        let mut tx = conn.begin().await?;
+        for id in ids {
+            sqlx::query("UPDATE training_examples SET exported_at = ? WHERE id = ?")
+                .bind(at.to_rfc3339())
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_announcement(&self) -> Result<Option<AnnouncementRecord>> {
+        let row: Option<AnnouncementRow> = sqlx::query_as(
+            "SELECT message, created_at, expires_at FROM announcements WHERE id = 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    async fn set_announcement(&self, announcement: &AnnouncementRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO announcements (id, message, created_at, expires_at)
+            VALUES (1, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                message = excluded.message,
+                created_at = excluded.created_at,
+                expires_at = excluded.expires_at
+            "#,
+        )
+        .bind(&announcement.message)
+        .bind(announcement.created_at.to_rfc3339())
+        .bind(announcement.expires_at.map(|t| t.to_rfc3339()))
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear_announcement(&self) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM announcements")
+            .execute(&mut *self.writer().await?)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn append_audit(&self, record: &AuditRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_log (id, at, actor, action, repo_id, repo, target, payload_digest)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(record.id.to_string())
+        .bind(record.at.to_rfc3339())
+        .bind(&record.actor)
+        .bind(&record.action)
+        .bind(record.repo_id.map(|id| id.to_string()))
+        .bind(&record.repo)
+        .bind(&record.target)
+        .bind(&record.payload_digest)
+        .execute(&mut *self.writer().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_audit(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditRecord>> {
+        let repo_id = filter.repo_id.map(|id| id.to_string());
+        let since = filter.since.map(|t| t.to_rfc3339());
+        let until = filter.until.map(|t| t.to_rfc3339());
+        let rows: Vec<AuditRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM audit_log
+            WHERE (?1 IS NULL OR repo_id = ?1)
+              AND (?2 IS NULL OR action = ?2)
+              AND (?3 IS NULL OR actor = ?3)
+              AND (?4 IS NULL OR at >= ?4)
+              AND (?5 IS NULL OR at < ?5)
+              AND (?7 IS NULL OR repo_id IN (SELECT id FROM repositories WHERE owner = ?7))
+            ORDER BY at DESC, id
+            LIMIT ?6
+            "#,
+        )
+        .bind(repo_id)
+        .bind(&filter.action)
+        .bind(&filter.actor)
+        .bind(since)
+        .bind(until)
+        .bind(limit as i64)
+        .bind(&filter.owner)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Store).await?;
+        let result: (i32,) = sqlx::query_as("SELECT 1")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(result.0 == 1)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats::collect(&self.pool, self.writer.as_ref(), &self.write_waits))
+    }
+}
+
+// =============================================================================
+// Statements shared by the direct and write-behind paths
+// =============================================================================
+
+async fn insert_tactic_outcome<'e, E>(executor: E, outcome: &TacticOutcomeRecord) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO tactic_outcomes (
+            id, job_id, prover, goal_fingerprint, tactic,
+            succeeded, duration_ms, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(outcome.id.to_string())
+    .bind(outcome.job_id.map(|id| id.to_string()))
+    .bind(format!("{:?}", outcome.prover))
+    .bind(&outcome.goal_fingerprint)
+    .bind(&outcome.tactic)
+    .bind(outcome.succeeded)
+    .bind(outcome.duration_ms)
+    .bind(outcome.created_at.to_rfc3339())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn insert_file_timing<'e, E>(executor: E, timing: &FileTimingRecord) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO file_timings (
+            id, job_id, repo_id, prover, file_path,
+            commit_sha, duration_ms, success, created_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(timing.id.to_string())
+    .bind(timing.job_id.to_string())
+    .bind(timing.repo_id.to_string())
+    .bind(format!("{:?}", timing.prover))
+    .bind(&timing.file_path)
+    .bind(&timing.commit_sha)
+    .bind(timing.duration_ms)
+    .bind(timing.success)
+    .bind(timing.created_at.to_rfc3339())
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn set_token_last_used<'e, E>(
+    executor: E,
+    id: Uuid,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<()>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE id = ?")
+        .bind(at.to_rfc3339())
+        .bind(id.to_string())
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// `WHERE` clause of a job search, binding `?1`..`?7` via
+/// [`bind_job_filter`].
+const JOB_FILTER: &str = "(?1 IS NULL OR repo_id = ?1) \
+    AND (?2 IS NULL OR status = ?2) \
+    AND (?3 IS NULL OR prover = ?3) \
+    AND (?4 IS NULL OR substr(lower(commit_sha), 1, length(?4)) = ?4) \
+    AND (?5 IS NULL OR queued_at >= ?5) \
+    AND (?6 IS NULL OR queued_at < ?6) \
+    AND (?7 IS NULL OR repo_id IN (SELECT id FROM repositories WHERE owner = ?7))";
+
+fn bind_job_filter<'q, O>(
+    query: sqlx::query::QueryAs<'q, Sqlite, O, sqlx::sqlite::SqliteArguments<'q>>,
+    filter: &JobFilter,
+) -> sqlx::query::QueryAs<'q, Sqlite, O, sqlx::sqlite::SqliteArguments<'q>> {
+    query
+        .bind(filter.repo_id.map(|id| id.to_string()))
+        .bind(filter.status.map(|s| format!("{:?}", s)))
+        .bind(filter.prover.as_ref().map(|p| format!("{:?}", p)))
+        .bind(filter.commit_prefix.as_ref().map(|p| p.to_lowercase()))
+        .bind(filter.since.map(|t| t.to_rfc3339()))
+        .bind(filter.until.map(|t| t.to_rfc3339()))
+        .bind(filter.owner.clone())
+}
+
+/// Keys bound per `IN (...)` statement, well under SQLite's variable limit.
+const IN_CHUNK: usize = 500;
+
+/// Run `sql`, whose `{ids}` stands for an `IN` list, once per chunk of
+/// `ids` and concatenate the rows. Backs the batched lookups the GraphQL
+/// loaders use.
+async fn select_in<R>(pool: &Pool<Sqlite>, sql: &str, ids: &[String]) -> Result<Vec<R>>
+where
+    R: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    let mut rows = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(IN_CHUNK) {
+        let list = vec!["?"; chunk.len()].join(", ");
+        let sql = sql.replace("{ids}", &list);
+        let mut query = sqlx::query_as::<_, R>(&sql);
+        for id in chunk {
+            query = query.bind(id);
+        }
+        rows.extend(query.fetch_all(pool).await?);
+    }
+    Ok(rows)
+}
+
+// =============================================================================
+// Row types for sqlx
+// =============================================================================
+
+#[derive(sqlx::FromRow)]
+struct RepoRow {
+    id: String,
+    platform: String,
+    owner: String,
+    name: String,
+    webhook_secret: Option<String>,
+    enabled_provers: String,
+    check_on_push: bool,
+    check_on_pr: bool,
+    auto_comment: bool,
+    enabled: bool,
+    last_checked_commit: Option<String>,
+    created_at: String,
+    updated_at: String,
+    #[sqlx(default)]
+    mode: Option<String>,
+    #[sqlx(default)]
+    regulator_coverage_threshold: Option<i64>,
+    #[sqlx(default)]
+    branch_include: Option<String>,
+    #[sqlx(default)]
+    branch_exclude: Option<String>,
+    #[sqlx(default)]
+    config_pr_offered_at: Option<String>,
+    #[sqlx(default)]
+    path_include: Option<String>,
+    #[sqlx(default)]
+    path_exclude: Option<String>,
+    #[sqlx(default)]
+    visibility: Option<String>,
+    #[sqlx(default)]
+    default_branch: Option<String>,
+    #[sqlx(default)]
+    training_export: Option<bool>,
+    #[sqlx(default)]
+    reverify_cron: Option<String>,
+    #[sqlx(default)]
+    last_reverify_at: Option<String>,
+    #[sqlx(default)]
+    prover_pins: Option<String>,
+    #[sqlx(default)]
+    timezone: Option<String>,
+}
+
+impl TryFrom<RepoRow> for Repository {
+    type Error = Error;
+
+    fn try_from(row: RepoRow) -> Result<Self> {
+        let platform = parse_platform(&row.platform)?;
+
+        let enabled_provers: Vec<ProverKind> = serde_json::from_str(&row.enabled_provers)?;
+
+        // Parse mode via serde, falling back to Verifier if the column was
+        // null (older DB pre-migration) or contained an unrecognised value.
+        let mode = row
+            .mode
+            .as_deref()
+            .and_then(|s| {
+                serde_json::from_value::<crate::modes::BotMode>(serde_json::Value::String(
+                    s.to_string(),
+                ))
+                .ok()
+            })
+            .unwrap_or_default();
+
+        Ok(Repository {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            platform,
+            owner: row.owner,
+            name: row.name,
+            webhook_secret: row.webhook_secret,
+            enabled_provers,
+            check_on_push: row.check_on_push,
+            check_on_pr: row.check_on_pr,
+            auto_comment: row.auto_comment,
+            enabled: row.enabled,
+            last_checked_commit: row.last_checked_commit,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.updated_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            mode,
+            regulator_coverage_threshold: row
+                .regulator_coverage_threshold
+                .map(|v| v.clamp(0, 100) as u8)
+                .unwrap_or(100),
+            branch_include: row
+                .branch_include
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            branch_exclude: row
+                .branch_exclude
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            config_pr_offered_at: row
+                .config_pr_offered_at
+                .as_deref()
+                .map(chrono::DateTime::parse_from_rfc3339)
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            path_include: row
+                .path_include
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            path_exclude: row
+                .path_exclude
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            // Unreadable values fail closed.
+            visibility: row
+                .visibility
+                .as_deref()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            default_branch: row.default_branch,
+            training_export: row.training_export.unwrap_or(false),
+            reverify_cron: row.reverify_cron,
+            last_reverify_at: row
+                .last_reverify_at
+                .as_deref()
+                .map(chrono::DateTime::parse_from_rfc3339)
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .map(|t| t.with_timezone(&chrono::Utc)),
+            prover_pins: row
+                .prover_pins
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            timezone: row.timezone,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct JobRow {
+    id: String,
+    repo_id: String,
+    commit_sha: String,
+    prover: String,
+    file_paths: String,
+    status: String,
+    priority: i32,
+    queued_at: String,
+    started_at: Option<String>,
+    completed_at: Option<String>,
+    error_message: Option<String>,
+    #[sqlx(default)]
+    pr_number: Option<i64>,
+    #[sqlx(default)]
+    delivery_id: Option<String>,
+    #[sqlx(default)]
+    retry_of: Option<String>,
+    #[sqlx(default)]
+    base_sha: Option<String>,
+    #[sqlx(default)]
+    branch: Option<String>,
+    #[sqlx(default)]
+    payload: Option<String>,
+}
+
+impl TryFrom<JobRow> for ProofJobRecord {
+    type Error = Error;
+
+    fn try_from(row: JobRow) -> Result<Self> {
+        let prover = parse_prover(&row.prover)?;
+        let status = parse_job_status(&row.status)?;
+        let priority = match row.priority {
+            0 => JobPriority::Low,
+            1 => JobPriority::Normal,
+            2 => JobPriority::High,
+            3 => JobPriority::Critical,
+            _ => JobPriority::Normal,
+        };
+        // Rows from before the payload column hold a version-1 file list.
+        let payload = JobPayload::from_json(row.payload.as_deref().unwrap_or(&row.file_paths))?;
+
+        Ok(ProofJobRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            commit_sha: row.commit_sha,
+            prover,
+            payload,
+            status,
+            priority,
+            queued_at: chrono::DateTime::parse_from_rfc3339(&row.queued_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            started_at: row.started_at.map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|t| t.with_timezone(&chrono::Utc))
+            }).transpose().map_err(|e| Error::Internal(e.to_string()))?,
+            completed_at: row.completed_at.map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|t| t.with_timezone(&chrono::Utc))
+            }).transpose().map_err(|e| Error::Internal(e.to_string()))?,
+            error_message: row.error_message,
+            pr_number: row.pr_number.map(|n| n as u64),
+            delivery_id: row.delivery_id,
+            retry_of: row
+                .retry_of
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?,
+            base_sha: row.base_sha,
+            branch: row.branch,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ResultRow {
+    id: String,
+    job_id: String,
+    success: bool,
+    message: String,
+    prover_output: String,
+    duration_ms: i64,
+    verified_files: String,
+    failed_files: String,
+    created_at: String,
+    #[sqlx(default)]
+    archive_cid: Option<String>,
+    #[sqlx(default)]
+    prover_version: Option<String>,
+    #[sqlx(default)]
+    prover_args: Option<String>,
+    #[sqlx(default)]
+    execution_path: Option<String>,
+}
+
+impl TryFrom<ResultRow> for ProofResultRecord {
+    type Error = Error;
+
+    fn try_from(row: ResultRow) -> Result<Self> {
+        let verified_files: Vec<String> = serde_json::from_str(&row.verified_files)?;
+        let failed_files: Vec<String> = serde_json::from_str(&row.failed_files)?;
+
+        Ok(ProofResultRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            success: row.success,
+            message: row.message,
+            prover_output: row.prover_output,
+            duration_ms: row.duration_ms,
+            verified_files,
+            failed_files,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            archive_cid: row.archive_cid,
+            prover_version: row.prover_version,
+            prover_args: match row.prover_args {
+                Some(args) => serde_json::from_str(&args)?,
+                None => Vec::new(),
+            },
+            execution_path: row.execution_path.as_deref().and_then(ExecutionPath::parse),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct OutcomeRow {
+    id: String,
+    job_id: Option<String>,
+    prover: String,
+    goal_fingerprint: String,
+    tactic: String,
+    succeeded: bool,
+    duration_ms: i64,
+    created_at: String,
+}
+
+impl TryFrom<OutcomeRow> for TacticOutcomeRecord {
+    type Error = Error;
+
+    fn try_from(row: OutcomeRow) -> Result<Self> {
+        let prover = parse_prover(&row.prover)?;
+        let job_id = row
+            .job_id
+            .map(|s| Uuid::parse_str(&s).map_err(|e| Error::Internal(e.to_string())))
+            .transpose()?;
+
+        Ok(TacticOutcomeRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id,
+            prover,
+            goal_fingerprint: row.goal_fingerprint,
+            tactic: row.tactic,
+            succeeded: row.succeeded,
+            duration_ms: row.duration_ms,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TimingRow {
+    id: String,
+    job_id: String,
+    repo_id: String,
+    prover: String,
+    file_path: String,
+    commit_sha: String,
+    duration_ms: i64,
+    success: bool,
+    created_at: String,
+}
+
+impl TryFrom<TimingRow> for FileTimingRecord {
+    type Error = Error;
+
+    fn try_from(row: TimingRow) -> Result<Self> {
+        Ok(FileTimingRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            prover: parse_prover(&row.prover)?,
+            file_path: row.file_path,
+            commit_sha: row.commit_sha,
+            duration_ms: row.duration_ms,
+            success: row.success,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TokenRow {
+    id: String,
+    name: String,
+    token_hash: String,
+    scope: String,
+    tenant: Option<String>,
+    created_at: String,
+    last_used_at: Option<String>,
+    revoked_at: Option<String>,
+}
+
+impl TryFrom<TokenRow> for ApiTokenRecord {
+    type Error = Error;
+
+    fn try_from(row: TokenRow) -> Result<Self> {
+        let parse_ts = |s: String| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+
+        Ok(ApiTokenRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            name: row.name,
+            token_hash: row.token_hash,
+            scope: row.scope.parse()?,
+            tenant: row.tenant,
+            created_at: parse_ts(row.created_at)?,
+            last_used_at: row.last_used_at.map(parse_ts).transpose()?,
+            revoked_at: row.revoked_at.map(parse_ts).transpose()?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct QuarantineRow {
+    id: String,
+    repo_id: String,
+    file_path: String,
+    reason: Option<String>,
+    created_at: String,
+}
+
+impl TryFrom<QuarantineRow> for QuarantinedProof {
+    type Error = Error;
+
+    fn try_from(row: QuarantineRow) -> Result<Self> {
+        Ok(QuarantinedProof {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            file_path: row.file_path,
+            reason: row.reason,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct EscapeHatchRow {
+    id: String,
+    job_id: String,
+    file_path: String,
+    line: i64,
+    token: String,
+    created_at: String,
+}
+
+impl TryFrom<EscapeHatchRow> for EscapeHatchRecord {
+    type Error = Error;
+
+    fn try_from(row: EscapeHatchRow) -> Result<Self> {
+        Ok(EscapeHatchRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            file_path: row.file_path,
+            line: row.line.max(0) as u32,
+            token: row.token,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ProofItemRow {
+    id: String,
+    job_id: String,
+    file_path: String,
+    theorem: Option<String>,
+    line: Option<i64>,
+    status: String,
+    duration_ms: Option<i64>,
+    created_at: String,
+}
+
+impl TryFrom<ProofItemRow> for ProofItemRecord {
+    type Error = Error;
+
+    fn try_from(row: ProofItemRow) -> Result<Self> {
+        use crate::proof_items::ItemStatus;
+        let status = match row.status.as_str() {
+            "Verified" => ItemStatus::Verified,
+            "Failed" => ItemStatus::Failed,
+            "Admitted" => ItemStatus::Admitted,
+            _ => return Err(Error::Internal(format!("Unknown proof item status: {}", row.status))),
+        };
+        Ok(ProofItemRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            file_path: row.file_path,
+            theorem: row.theorem,
+            line: row.line.map(|l| l.max(0) as u32),
+            status,
+            duration_ms: row.duration_ms,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FlakyRunRow {
+    id: String,
+    job_id: String,
+    repo_id: String,
+    prover: String,
+    file_path: String,
+    commit_sha: String,
+    attempts: i64,
+    verdict: String,
+    timed_out: bool,
+    created_at: String,
+}
+
+impl TryFrom<FlakyRunRow> for FlakyRunRecord {
+    type Error = Error;
+
+    fn try_from(row: FlakyRunRow) -> Result<Self> {
+        use crate::flaky::FlakyVerdict;
+        let verdict = match row.verdict.as_str() {
+            "Deterministic" => FlakyVerdict::Deterministic,
+            "Flaky" => FlakyVerdict::Flaky,
+            _ => return Err(Error::Internal(format!("Unknown flaky verdict: {}", row.verdict))),
+        };
+        Ok(FlakyRunRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            prover: parse_prover(&row.prover)?,
+            file_path: row.file_path,
+            commit_sha: row.commit_sha,
+            attempts: row.attempts.max(0) as u32,
+            verdict,
+            timed_out: row.timed_out,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct BisectionRow {
+    id: String,
+    repo_id: String,
+    prover: String,
+    branch: String,
+    file_path: String,
+    theorem: Option<String>,
+    good_sha: String,
+    bad_sha: String,
+    pending: String,
+    probe_job: Option<String>,
+    probes: i64,
+    reused: i64,
+    status: String,
+    issue: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<BisectionRow> for BisectionRecord {
+    type Error = Error;
+
+    fn try_from(row: BisectionRow) -> Result<Self> {
+        let parse_ts = |s: String| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|e| Error::Internal(e.to_string()));
+        let status = match row.status.as_str() {
+            "Running" => BisectStatus::Running,
+            "Found" => BisectStatus::Found,
+            "Abandoned" => BisectStatus::Abandoned,
+            _ => return Err(Error::Internal(format!("Unknown bisection status: {}", row.status))),
+        };
+        Ok(BisectionRecord {
+            id: parse_id(&row.id)?,
+            repo_id: parse_id(&row.repo_id)?,
+            prover: parse_prover(&row.prover)?,
+            branch: row.branch,
+            file_path: row.file_path,
+            theorem: row.theorem,
+            good_sha: row.good_sha,
+            bad_sha: row.bad_sha,
+            pending: serde_json::from_str(&row.pending)?,
+            probe_job: row.probe_job.as_deref().map(parse_id).transpose()?,
+            probes: row.probes.max(0) as u32,
+            reused: row.reused.max(0) as u32,
+            status,
+            issue: row.issue,
+            created_at: parse_ts(row.created_at)?,
+            updated_at: parse_ts(row.updated_at)?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CertificateRow {
+    id: String,
+    repo_id: String,
+    commit_sha: String,
+    envelope: String,
+    created_at: String,
+}
+
+impl TryFrom<CertificateRow> for CertificateRecord {
+    type Error = Error;
+
+    fn try_from(row: CertificateRow) -> Result<Self> {
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|e| Error::Internal(e.to_string()));
+        Ok(CertificateRecord {
+            id: parse_id(&row.id)?,
+            repo_id: parse_id(&row.repo_id)?,
+            commit_sha: row.commit_sha,
+            envelope: serde_json::from_str(&row.envelope)?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct JobEventRow {
+    seq: i64,
+    event_id: String,
+    job_id: String,
+    status: String,
+    occurred_at: String,
+    attempts: i64,
+    last_error: Option<String>,
+}
+
+impl TryFrom<JobEventRow> for JobEventRecord {
+    type Error = Error;
+
+    fn try_from(row: JobEventRow) -> Result<Self> {
+        Ok(JobEventRecord {
+            seq: row.seq,
+            event_id: row.event_id,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            status: parse_job_status(&row.status)?,
+            occurred_at: chrono::DateTime::parse_from_rfc3339(&row.occurred_at)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))?,
+            attempts: row.attempts.max(0) as u32,
+            last_error: row.last_error,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AnalyticsCursorRow {
+    sink: String,
+    completed_at: String,
+    job_id: String,
+    updated_at: String,
+}
+
+impl TryFrom<AnalyticsCursorRow> for AnalyticsCursor {
+    type Error = Error;
+
+    fn try_from(row: AnalyticsCursorRow) -> Result<Self> {
+        let parse_ts = |s: &str| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+        Ok(AnalyticsCursor {
+            sink: row.sink,
+            completed_at: parse_ts(&row.completed_at)?,
+            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
+            updated_at: parse_ts(&row.updated_at)?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ConfirmationRow {
+    token_hash: String,
+    action: String,
+    actor: String,
+    subject_digest: String,
+    impact: String,
+    created_at: String,
+    expires_at: String,
+}
+
+impl TryFrom<ConfirmationRow> for ConfirmationRecord {
+    type Error = Error;
+
+    fn try_from(row: ConfirmationRow) -> Result<Self> {
+        let parse_ts = |s: &str| {
+            chrono::DateTime::parse_from_rfc3339(s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+        Ok(ConfirmationRecord {
+            token_hash: row.token_hash,
+            action: row.action,
+            actor: row.actor,
+            subject_digest: row.subject_digest,
+            impact: row.impact,
+            created_at: parse_ts(&row.created_at)?,
+            expires_at: parse_ts(&row.expires_at)?,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    id: String,
+    platform: String,
+    event_type: String,
+    delivery_id: Option<String>,
+    payload: Vec<u8>,
+    status: String,
+    attempts: i64,
+    last_error: Option<String>,
+    received_at: String,
+    next_attempt_at: String,
+    processed_at: Option<String>,
+    trace_parent: Option<String>,
+}
+
+impl TryFrom<DeliveryRow> for WebhookDeliveryRecord {
+    type Error = Error;
+
+    fn try_from(row: DeliveryRow) -> Result<Self> {
+        let parse_ts = |s: String| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+        let status = match row.status.as_str() {
+            "Pending" => DeliveryStatus::Pending,
+            "Processed" => DeliveryStatus::Processed,
+            "Failed" => DeliveryStatus::Failed,
+            _ => return Err(Error::Internal(format!("Unknown delivery status: {}", row.status))),
+        };
+
+        Ok(WebhookDeliveryRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            platform: parse_platform(&row.platform)?,
+            event_type: row.event_type,
+            delivery_id: row.delivery_id,
+            payload: row.payload,
+            status,
+            attempts: row.attempts.max(0) as u32,
+            last_error: row.last_error,
+            received_at: parse_ts(row.received_at)?,
+            next_attempt_at: parse_ts(row.next_attempt_at)?,
+            processed_at: row.processed_at.map(parse_ts).transpose()?,
+            trace_parent: row.trace_parent,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct StaleNagRow {
+    repo_id: String,
+    pr_number: i64,
+    failing_since: String,
+    reminders: i64,
+    last_reminded_at: Option<String>,
+    escalated: bool,
+    drafted: bool,
+    closed: bool,
+}
+
+impl TryFrom<StaleNagRow> for StaleNagRecord {
+    type Error = Error;
+
+    fn try_from(row: StaleNagRow) -> Result<Self> {
+        let parse_ts = |s: String| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .map_err(|e| Error::Internal(e.to_string()))
+        };
+
+        Ok(StaleNagRecord {
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            pr_number: row.pr_number.max(0) as u64,
+            failing_since: parse_ts(row.failing_since)?,
+            reminders: row.reminders.max(0) as u32,
+            last_reminded_at: row.last_reminded_at.map(parse_ts).transpose()?,
+            escalated: row.escalated,
+            drafted: row.drafted,
+            closed: row.closed,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct GraphRow {
+    repo_id: String,
+    prover: String,
+    commit_sha: String,
+    graph: String,
+    updated_at: String,
+}
+
+impl TryFrom<GraphRow> for DependencyGraphRecord {
+    type Error = Error;
+
+    fn try_from(row: GraphRow) -> Result<Self> {
+        Ok(DependencyGraphRecord {
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            prover: parse_prover(&row.prover)?,
+            commit_sha: row.commit_sha,
+            graph: serde_json::from_str(&row.graph)?,
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.updated_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PrCommentRow {
+    repo_id: String,
+    pr_number: i64,
+    prover: String,
+    comment_id: String,
+    updated_at: String,
+}
+
+impl TryFrom<PrCommentRow> for PrCommentRecord {
+    type Error = Error;
+
+    fn try_from(row: PrCommentRow) -> Result<Self> {
+        Ok(PrCommentRecord {
+            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
+            pr_number: row.pr_number.max(0) as u64,
+            prover: parse_prover(&row.prover)?,
+            comment_id: row.comment_id,
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.updated_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AnnouncementRow {
+    message: String,
+    created_at: String,
+    expires_at: Option<String>,
+}
+
+impl TryFrom<AnnouncementRow> for AnnouncementRecord {
+    type Error = Error;
+
+    fn try_from(row: AnnouncementRow) -> Result<Self> {
+        Ok(AnnouncementRecord {
+            message: row.message,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            expires_at: row
+                .expires_at
+                .as_deref()
+                .map(chrono::DateTime::parse_from_rfc3339)
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .map(|t| t.with_timezone(&chrono::Utc)),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: String,
+    at: String,
+    actor: String,
+    action: String,
+    repo_id: Option<String>,
+    repo: Option<String>,
+    target: Option<String>,
+    payload_digest: String,
+}
+
+impl TryFrom<AuditRow> for AuditRecord {
+    type Error = Error;
+
+    fn try_from(row: AuditRow) -> Result<Self> {
+        Ok(AuditRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            at: chrono::DateTime::parse_from_rfc3339(&row.at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            actor: row.actor,
+            action: row.action,
+            repo_id: row
+                .repo_id
+                .as_deref()
+                .map(Uuid::parse_str)
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?,
+            repo: row.repo,
+            target: row.target,
+            payload_digest: row.payload_digest,
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TrainingExampleRow {
+    id: String,
+    prover: String,
+    goal: String,
+    tactic: String,
+    digest: String,
+    created_at: String,
+    exported_at: Option<String>,
+}
+
+impl TryFrom<TrainingExampleRow> for TrainingExampleRecord {
+    type Error = Error;
+
+    fn try_from(row: TrainingExampleRow) -> Result<Self> {
+        Ok(TrainingExampleRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            prover: parse_prover(&row.prover)?,
+            goal: row.goal,
+            tactic: row.tactic,
+            digest: row.digest,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            exported_at: row
+                .exported_at
+                .as_deref()
+                .map(chrono::DateTime::parse_from_rfc3339)
+                .transpose()
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .map(|t| t.with_timezone(&chrono::Utc)),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct FederatedRow {
+    id: String,
+    peer: String,
+    platform: String,
+    repo: String,
+    commit_sha: String,
+    prover: String,
+    success: bool,
+    verified_files: String,
+    failed_files: String,
+    completed_at: String,
+    envelope: String,
+    imported_at: String,
+}
+
+impl TryFrom<FederatedRow> for FederatedResultRecord {
+    type Error = Error;
+
+    fn try_from(row: FederatedRow) -> Result<Self> {
+        Ok(FederatedResultRecord {
+            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
+            peer: row.peer,
+            platform: parse_platform(&row.platform)?,
+            repo: row.repo,
+            commit_sha: row.commit_sha,
+            prover: parse_prover(&row.prover)?,
+            success: row.success,
+            verified_files: serde_json::from_str(&row.verified_files)?,
+            failed_files: serde_json::from_str(&row.failed_files)?,
+            completed_at: chrono::DateTime::parse_from_rfc3339(&row.completed_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+            envelope: row.envelope,
+            imported_at: chrono::DateTime::parse_from_rfc3339(&row.imported_at)
+                .map_err(|e| Error::Internal(e.to_string()))?
+                .with_timezone(&chrono::Utc),
+        })
+    }
+}
+
+fn parse_platform(s: &str) -> Result<Platform> {
+    match s {
+        "GitHub" => Ok(Platform::GitHub),
+        "GitLab" => Ok(Platform::GitLab),
+        "Bitbucket" => Ok(Platform::Bitbucket),
+        "Codeberg" => Ok(Platform::Codeberg),
+        _ => Err(Error::Internal(format!("Unknown platform: {}", s))),
+    }
+}
+
+fn parse_prover(s: &str) -> Result<ProverKind> {
+    match s {
+        "Agda" => Ok(ProverKind::new("agda")),
+        "Coq" => Ok(ProverKind::new("coq")),
+        "Lean" => Ok(ProverKind::new("lean")),
+        "Isabelle" => Ok(ProverKind::new("isabelle")),
+        "Z3" => Ok(ProverKind::new("z3")),
+        "Cvc5" => Ok(ProverKind::new("cvc5")),
+        "Metamath" => Ok(ProverKind::new("metamath")),
+        "HolLight" => Ok(ProverKind::new("hol-light")),
+        "Mizar" => Ok(ProverKind::new("mizar")),
+        "Pvs" => Ok(ProverKind::new("pvs")),
+        "Acl2" => Ok(ProverKind::new("acl2")),
+        "Hol4" => Ok(ProverKind::new("hol4")),
+        _ => Ok(ProverKind::new(s)),  // Support all 113 provers dynamically
+    }
+}
+
+fn parse_job_status(s: &str) -> Result<JobStatus> {
+    match s {
+        "Queued" => Ok(JobStatus::Queued),
+        "Running" => Ok(JobStatus::Running),
+        "Completed" => Ok(JobStatus::Completed),
+        "Failed" => Ok(JobStatus::Failed),
+        "Cancelled" => Ok(JobStatus::Cancelled),
+        _ => Err(Error::Internal(format!("Unknown status: {}", s))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::models::{goal_fingerprint, TacticOutcomeRecord};
+
+    async fn fresh_store() -> (SqliteStore, std::path::PathBuf) {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-store-test-{}.db", Uuid::new_v4()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let store = SqliteStore::new(&url).await.expect("open store");
+        (store, path)
+    }
+
+    #[tokio::test]
+    async fn tactic_outcome_insert_then_lookup_by_fingerprint() {
+        let (store, path) = fresh_store().await;
+        let fp = goal_fingerprint("forall x : Nat, x = x");
+
+        let first = TacticOutcomeRecord::new(
+            None, ProverKind::new("coq"), fp.clone(), "reflexivity".into(), true, 12,
+        );
+        let second = TacticOutcomeRecord::new(
+            None, ProverKind::new("coq"), fp.clone(), "auto".into(), false, 30,
+        );
+        store.record_tactic_outcome(&first).await.unwrap();
+        store.record_tactic_outcome(&second).await.unwrap();
+
+        let found = store
+            .list_tactic_outcomes_by_fingerprint(ProverKind::new("coq"), &fp, 10)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        // DESC by created_at — second row (auto) is newer
+        assert_eq!(found[0].tactic, "auto");
+        assert!(!found[0].succeeded);
+        assert_eq!(found[1].tactic, "reflexivity");
+        assert!(found[1].succeeded);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn tactic_outcome_filters_by_prover() {
+        let (store, path) = fresh_store().await;
+        let fp = goal_fingerprint("P /\\ Q -> P");
+
+        store
+            .record_tactic_outcome(&TacticOutcomeRecord::new(
+                None, ProverKind::new("coq"), fp.clone(), "split".into(), true, 5,
+            ))
+            .await
+            .unwrap();
+        store
+            .record_tactic_outcome(&TacticOutcomeRecord::new(
+                None, ProverKind::new("lean"), fp.clone(), "exact".into(), true, 5,
+            ))
+            .await
+            .unwrap();
+
+        let coq_hits = store
+            .list_tactic_outcomes_by_fingerprint(ProverKind::new("coq"), &fp, 10)
+            .await
+            .unwrap();
+        let lean_hits = store
+            .list_tactic_outcomes_by_fingerprint(ProverKind::new("lean"), &fp, 10)
+            .await
+            .unwrap();
+        assert_eq!(coq_hits.len(), 1);
+        assert_eq!(coq_hits[0].tactic, "split");
+        assert_eq!(lean_hits.len(), 1);
+        assert_eq!(lean_hits[0].tactic, "exact");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn file_timings_roundtrip_newest_first() {
+        use crate::adapters::Platform;
+        use crate::scheduler::ProofJob;
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let mut job_ids = Vec::new();
+        for (i, ms) in [100_i64, 120, 900].iter().enumerate() {
+            let job = ProofJob::new(repo.id, format!("sha{}", i), ProverKind::new("coq"), vec![]);
+            store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+            store
+                .record_file_timing(&FileTimingRecord::new(
+                    job.id,
+                    repo.id,
+                    ProverKind::new("coq"),
+                    "theories/A.v".into(),
+                    format!("sha{}", i),
+                    *ms,
+                    true,
+                ))
+                .await
+                .unwrap();
+            job_ids.push(job.id);
+        }
+
+        let history = store
+            .list_file_timings(repo.id, ProverKind::new("coq"), "theories/A.v", 10)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].duration_ms, 900);
+
+        let for_job = store.list_file_timings_for_job(job_ids[1]).await.unwrap();
+        assert_eq!(for_job.len(), 1);
+        assert_eq!(for_job[0].duration_ms, 120);
+
+        let for_commit = store
+            .list_file_timings_for_commit(repo.id, ProverKind::new("coq"), "sha2")
+            .await
+            .unwrap();
+        assert_eq!(for_commit.len(), 1);
+        assert_eq!(for_commit[0].duration_ms, 900);
+        assert!(store
+            .list_file_timings_for_commit(repo.id, ProverKind::new("lean"), "sha2")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn api_token_lookup_and_revoke() {
+        use crate::api::auth::{hash_token, TokenScope};
+
+        let (store, path) = fresh_store().await;
+        let record = ApiTokenRecord::new("ci".into(), hash_token("ebt_secret"), TokenScope::Trigger);
+        store.create_api_token(&record).await.unwrap();
+
+        let found = store
+            .get_api_token_by_hash(&hash_token("ebt_secret"))
+            .await
+            .unwrap()
+            .expect("token present");
+        assert_eq!(found.scope, TokenScope::Trigger);
+        assert!(found.revoked_at.is_none());
+        assert!(store.get_api_token_by_hash(&hash_token("nope")).await.unwrap().is_none());
+
+        assert!(store.revoke_api_token(record.id).await.unwrap());
+        assert!(!store.revoke_api_token(record.id).await.unwrap(), "second revoke is a no-op");
+        let revoked = store.get_api_token_by_hash(&found.token_hash).await.unwrap().unwrap();
+        assert!(revoked.revoked_at.is_some());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn tenant_keys_seal_webhook_secrets() {
+        use crate::tenancy::{is_sealed, MasterKey};
+
+        let (store, path) = fresh_store().await;
+        let master = |byte: u8| MasterKey::parse(&hex::encode([byte; 32])).unwrap();
+        store.set_master_key(Some(master(1))).await.unwrap();
+
+        let mut repo = Repository::new(Platform::GitHub, "acme".into(), "proofs".into());
+        repo.webhook_secret = Some("hook".into());
+        store.create_repository(&repo).await.unwrap();
+        let (stored,): (String,) =
+            sqlx::query_as("SELECT webhook_secret FROM repositories WHERE id = ?")
+                .bind(repo.id.to_string())
+                .fetch_one(store.pool())
+                .await
+                .unwrap();
+        assert!(is_sealed(&stored), "sealed at rest");
+        let read = store.get_repository(repo.id).await.unwrap().unwrap();
+        assert_eq!(read.webhook_secret.as_deref(), Some("hook"));
+        let keys = store.list_tenant_keys().await.unwrap();
+        assert_eq!(keys.iter().map(|k| k.owner.as_str()).collect::<Vec<_>>(), ["acme"]);
+
+        assert!(store.set_master_key(Some(master(2))).await.is_err(), "wrong master key");
+        store.set_master_key(Some(master(1))).await.unwrap();
+        assert!(store.delete_tenant_key("acme").await.unwrap());
+        let shredded = store.get_repository(repo.id).await.unwrap().unwrap();
+        assert!(shredded.webhook_secret.as_deref().is_some_and(is_sealed), "stays sealed");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn owner_filters_partition_jobs_and_audit() {
+        use crate::audit::{entry, AuditAction, BOT};
+
+        let (store, path) = fresh_store().await;
+        let acme = Repository::new(Platform::GitHub, "acme".into(), "a".into());
+        let globex = Repository::new(Platform::GitHub, "globex".into(), "b".into());
+        for repo in [&acme, &globex] {
+            store.create_repository(repo).await.unwrap();
+            let job = crate::scheduler::ProofJob::new(
+                repo.id,
+                "abc".into(),
+                ProverKind::new("coq"),
+                Vec::new(),
+            );
+            store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+            let audit = entry(BOT, AuditAction::CommentPosted, Some(repo), None, b"x");
+            store.append_audit(&audit).await.unwrap();
+        }
+
+        let mine = JobFilter { owner: Some("acme".into()), ..Default::default() };
+        let jobs = store.search_jobs(&mine, JobOrder::NewestFirst, 0, 10).await.unwrap();
+        assert_eq!(jobs.iter().map(|j| j.repo_id).collect::<Vec<_>>(), [acme.id]);
+        assert_eq!(store.count_jobs(&mine).await.unwrap(), 1);
+        assert_eq!(store.count_jobs(&JobFilter::default()).await.unwrap(), 2);
+
+        let mine = AuditFilter { owner: Some("acme".into()), ..Default::default() };
+        let entries = store.list_audit(&mine, 10).await.unwrap();
+        assert_eq!(entries.iter().map(|e| e.repo_id).collect::<Vec<_>>(), [Some(acme.id)]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn quarantine_is_idempotent_and_reversible() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let entry = QuarantinedProof::new(repo.id, "theories/Flaky.v".into(), None);
+        store.quarantine_proof(&entry).await.unwrap();
+        store
+            .quarantine_proof(&QuarantinedProof::new(repo.id, "theories/Flaky.v".into(), None))
+            .await
+            .unwrap();
+        let listed = store.list_quarantined_proofs(repo.id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, entry.id, "second insert is ignored");
+
+        assert!(store.unquarantine_proof(repo.id, "theories/Flaky.v").await.unwrap());
+        assert!(!store.unquarantine_proof(repo.id, "theories/Flaky.v").await.unwrap());
+        assert!(store.list_quarantined_proofs(repo.id).await.unwrap().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn webhook_deliveries_dedupe_and_become_due() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let mut delivery = WebhookDeliveryRecord::new(
+            Platform::GitHub,
+            "push".into(),
+            Some("d-1".into()),
+            br#"{"after":"abc"}"#.to_vec(),
+        );
+        assert!(store.record_webhook_delivery(&delivery).await.unwrap());
+        let redelivery = WebhookDeliveryRecord::new(
+            Platform::GitHub,
+            "push".into(),
+            Some("d-1".into()),
+            Vec::new(),
+        );
+        assert!(!store.record_webhook_delivery(&redelivery).await.unwrap());
+
+        let now = chrono::Utc::now();
+        let due = store.list_due_webhook_deliveries(now, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].payload, delivery.payload);
+
+        delivery.attempts = 1;
+        delivery.next_attempt_at = now + chrono::Duration::seconds(30);
+        store.update_webhook_delivery(&delivery).await.unwrap();
+        assert!(store.list_due_webhook_deliveries(now, 10).await.unwrap().is_empty());
+
+        delivery.status = DeliveryStatus::Processed;
+        store.update_webhook_delivery(&delivery).await.unwrap();
+        let later = now + chrono::Duration::seconds(60);
+        assert!(store.list_due_webhook_deliveries(later, 10).await.unwrap().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn tactic_outcome_lookup_by_tactic() {
+        let (store, path) = fresh_store().await;
+        let fp1 = goal_fingerprint("goal 1");
+        let fp2 = goal_fingerprint("goal 2");
+
+        store
+            .record_tactic_outcome(&TacticOutcomeRecord::new(
+                None, ProverKind::new("coq"), fp1, "intros".into(), true, 3,
+            ))
+            .await
+            .unwrap();
+        store
+            .record_tactic_outcome(&TacticOutcomeRecord::new(
+                None, ProverKind::new("coq"), fp2, "intros".into(), false, 99,
+            ))
+            .await
+            .unwrap();
+
+        let hits = store
+            .list_tactic_outcomes_by_tactic(ProverKind::new("coq"), "intros", 10)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        // Both rows share the tactic name but have different fingerprints
+        let successes = hits.iter().filter(|h| h.succeeded).count();
+        assert_eq!(successes, 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn stale_nag_upsert_and_delete() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        assert!(store.get_stale_nag(repo.id, 7).await.unwrap().is_none());
+
+        let mut nag = StaleNagRecord::new(repo.id, 7, chrono::Utc::now());
+        store.upsert_stale_nag(&nag).await.unwrap();
+        nag.reminders = 2;
+        nag.escalated = true;
+        nag.last_reminded_at = Some(chrono::Utc::now());
+        store.upsert_stale_nag(&nag).await.unwrap();
+
+        let stored = store.get_stale_nag(repo.id, 7).await.unwrap().unwrap();
+        assert_eq!(stored.reminders, 2);
+        assert!(stored.escalated && !stored.drafted);
+        assert!(stored.last_reminded_at.is_some());
+
+        store.delete_stale_nag(repo.id, 7).await.unwrap();
+        assert!(store.get_stale_nag(repo.id, 7).await.unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn training_examples_dedup_and_export() {
+        let (store, path) = fresh_store().await;
+        let coq = ProverKind::new("coq");
+        let example = TrainingExampleRecord::new(coq.clone(), "n + 0 = n".into(), "lia.".into());
+        let again = TrainingExampleRecord::new(coq.clone(), "n + 0 = n".into(), "lia.".into());
+        let other = TrainingExampleRecord::new(coq, "0 + n = n".into(), "reflexivity.".into());
+        assert_eq!(store.queue_training_examples(&[example.clone(), other]).await.unwrap(), 2);
+        assert_eq!(store.queue_training_examples(&[again]).await.unwrap(), 0, "same pair queued once");
+
+        let pending = store.list_pending_training_examples(10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        store
+            .mark_training_examples_exported(&[example.id], chrono::Utc::now())
+            .await
+            .unwrap();
+        let pending = store.list_pending_training_examples(10).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].tactic, "reflexivity.");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn announcement_is_replaced_and_cleared() {
+        let (store, path) = fresh_store().await;
+        assert!(store.get_announcement().await.unwrap().is_none());
+        assert!(!store.clear_announcement().await.unwrap());
+
+        store
+            .set_announcement(&AnnouncementRecord::new("maintenance".into(), None))
+            .await
+            .unwrap();
+        let degraded = AnnouncementRecord::new(
+            "degraded: ECHIDNA latency".into(),
+            Some(chrono::Duration::hours(2)),
+        );
+        store.set_announcement(&degraded).await.unwrap();
+        let stored = store.get_announcement().await.unwrap().unwrap();
+        assert_eq!(stored.message, degraded.message);
+        assert!(stored.expires_at.is_some());
+
+        assert!(store.clear_announcement().await.unwrap());
+        assert!(store.get_announcement().await.unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn audit_log_is_append_only_and_filtered() {
+        use crate::adapters::Platform;
+        use crate::audit::{entry, AuditAction, BOT, CLI};
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        let mut first = entry(CLI, AuditAction::RepoRegistered, Some(&repo), None, b"{}");
+        first.at -= chrono::Duration::minutes(5);
+        let second = entry(BOT, AuditAction::CommentPosted, Some(&repo), Some("PR #3".into()), b"body");
+        let other = entry(BOT, AuditAction::AnnouncementSet, None, None, b"maintenance");
+        for e in [&first, &second, &other] {
+            store.append_audit(e).await.unwrap();
+        }
+
+        let for_repo = AuditFilter {
+            repo_id: Some(repo.id),
+            ..Default::default()
+        };
+        let listed = store.list_audit(&for_repo, 10).await.unwrap();
+        assert_eq!(listed, vec![second.clone(), first.clone()], "newest first");
+        let comments = AuditFilter {
+            action: Some("comment.posted".into()),
+            ..Default::default()
+        };
+        assert_eq!(store.list_audit(&comments, 10).await.unwrap(), vec![second.clone()]);
+        let recent = AuditFilter {
+            since: Some(first.at + chrono::Duration::minutes(1)),
+            ..Default::default()
+        };
+        assert_eq!(store.list_audit(&recent, 10).await.unwrap().len(), 2);
+
+        assert!(sqlx::query("DELETE FROM audit_log").execute(store.pool()).await.is_err());
+        assert!(sqlx::query("UPDATE audit_log SET actor = 'someone'")
+            .execute(store.pool())
+            .await
+            .is_err());
+        assert_eq!(store.list_audit(&AuditFilter::default(), 10).await.unwrap().len(), 3);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn partial_listing_isolates_a_corrupt_row() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let good = Repository::new(Platform::GitHub, "o".into(), "good".into());
+        let bad = Repository::new(Platform::GitHub, "o".into(), "bad".into());
+        store.create_repository(&good).await.unwrap();
+        store.create_repository(&bad).await.unwrap();
+        sqlx::query("UPDATE repositories SET enabled_provers = 'not json' WHERE id = ?")
+            .bind(bad.id.to_string())
+            .execute(store.pool())
+            .await
+            .unwrap();
+
+        assert!(store.list_repositories(None).await.is_err());
+        let rows = store.list_repositories_partial(None).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        let loaded: Vec<_> = rows.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "good");
+        let err = rows.iter().find_map(|r| r.as_ref().err()).unwrap().to_string();
+        assert!(err.contains(&bad.id.to_string()), "{}", err);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn prover_pins_and_versions_round_trip() {
+        use crate::adapters::Platform;
+        use crate::scheduler::{JobResult, ProofJob};
+
+        let (store, path) = fresh_store().await;
+        let mut repo = Repository::new(Platform::GitHub, "o".into(), "pinned".into());
+        repo.prover_pins.insert("lean".into(), "4.7.0".into());
+        store.create_repository(&repo).await.unwrap();
+        repo.prover_pins.insert("coq".into(), "8.18".into());
+        repo.timezone = Some("Europe/London".into());
+        store.update_repository(&repo).await.unwrap();
+        let stored = store.get_repository(repo.id).await.unwrap().unwrap();
+        assert_eq!(stored.prover_pins, repo.prover_pins);
+        assert_eq!(stored.timezone.as_deref(), Some("Europe/London"));
+
+        let job = ProofJob::new(repo.id, "sha".into(), ProverKind::new("lean"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 1,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: Some("4.7.0".into()),
+            prover_args: vec!["-DmaxHeartbeats=400000".into()],
+            execution_path: Some(ExecutionPath::LocalFallback),
+        };
+        store.save_result(&ProofResultRecord::new(job.id, &result)).await.unwrap();
+        let stored = store.get_result_for_job(job.id).await.unwrap().unwrap();
+        assert_eq!(stored.prover_version.as_deref(), Some("4.7.0"));
+        assert_eq!(stored.prover_args, ["-DmaxHeartbeats=400000"]);
+        assert_eq!(stored.execution_path, Some(ExecutionPath::LocalFallback));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn job_search_filters_orders_and_pages() {
+        use crate::adapters::Platform;
+        use crate::scheduler::ProofJob;
+
+        let (store, path) = fresh_store().await;
+        let a = Repository::new(Platform::GitHub, "o".into(), "a".into());
+        let b = Repository::new(Platform::GitHub, "o".into(), "b".into());
+        store.create_repository(&a).await.unwrap();
+        store.create_repository(&b).await.unwrap();
+
+        let start = chrono::Utc::now() - chrono::Duration::days(10);
+        let specs = [
+            (a.id, "abc111", "lean", JobStatus::Failed, 0),
+            (a.id, "abc222", "lean", JobStatus::Completed, 1),
+            (a.id, "def333", "coq", JobStatus::Failed, 2),
+            (b.id, "ABC444", "lean", JobStatus::Failed, 8),
+        ];
+        for (repo_id, sha, prover, status, day) in specs {
+            let mut job = ProofJob::new(repo_id, sha.into(), ProverKind::new(prover), vec![]);
+            job.status = status;
+            job.queued_at = start + chrono::Duration::days(day);
+            store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+        }
+        let shas = |jobs: Vec<ProofJobRecord>| -> Vec<String> {
+            jobs.into_iter().map(|j| j.commit_sha).collect()
+        };
+
+        let failed_lean = JobFilter {
+            status: Some(JobStatus::Failed),
+            prover: Some(ProverKind::new("lean")),
+            ..Default::default()
+        };
+        let found = store.search_jobs(&failed_lean, JobOrder::NewestFirst, 0, 10).await.unwrap();
+        assert_eq!(shas(found), ["ABC444", "abc111"]);
+        assert_eq!(store.count_jobs(&failed_lean).await.unwrap(), 2);
+
+        let prefix = JobFilter { commit_prefix: Some("abc".into()), ..Default::default() };
+        let oldest = store.search_jobs(&prefix, JobOrder::OldestFirst, 0, 2).await.unwrap();
+        assert_eq!(shas(oldest), ["abc111", "abc222"]);
+        let next = store.search_jobs(&prefix, JobOrder::OldestFirst, 2, 2).await.unwrap();
+        assert_eq!(shas(next), ["ABC444"]);
+
+        let window = JobFilter {
+            repo_id: Some(a.id),
+            since: Some(start + chrono::Duration::days(1)),
+            until: Some(start + chrono::Duration::days(2)),
+            ..Default::default()
+        };
+        let found = store.search_jobs(&window, JobOrder::NewestFirst, 0, 10).await.unwrap();
+        assert_eq!(shas(found), ["abc222"]);
+        assert_eq!(store.count_jobs(&JobFilter::default()).await.unwrap(), 4);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn batched_lookups_return_what_exists() {
+        use crate::adapters::Platform;
+        use crate::scheduler::{JobResult, ProofJob};
+
+        let (store, path) = fresh_store().await;
+        let a = Repository::new(Platform::GitHub, "o".into(), "a".into());
+        let b = Repository::new(Platform::GitHub, "o".into(), "b".into());
+        store.create_repository(&a).await.unwrap();
+        store.create_repository(&b).await.unwrap();
+
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec![],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        let mut jobs = Vec::new();
+        for repo_id in [a.id, a.id, a.id, b.id] {
+            let job = ProofJob::new(repo_id, "sha".into(), ProverKind::new("coq"), vec![]);
+            store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+            jobs.push(job.id);
+        }
+        store.save_result(&ProofResultRecord::new(jobs[0], &result)).await.unwrap();
+        store.save_result(&ProofResultRecord::new(jobs[3], &result)).await.unwrap();
+
+        let missing = JobId(Uuid::new_v4());
+        let found = store.get_jobs_by_ids(&[jobs[1], jobs[3], missing]).await.unwrap();
+        assert_eq!(found.len(), 2);
+
+        let results = store.get_results_by_job_ids(&jobs).await.unwrap();
+        let mut with_results: Vec<Uuid> = results.iter().map(|r| r.job_id).collect();
+        with_results.sort();
+        let mut expected = vec![jobs[0].0, jobs[3].0];
+        expected.sort();
+        assert_eq!(with_results, expected);
+
+        let recent = store.list_jobs_for_repos(&[a.id, b.id], 2).await.unwrap();
+        assert_eq!(recent.iter().filter(|j| j.repo_id == a.id).count(), 2);
+        assert_eq!(recent.iter().filter(|j| j.repo_id == b.id).count(), 1);
+
+        let repos = store.get_repositories_by_ids(&[b.id, Uuid::new_v4()]).await.unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].name, "b");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn pr_comment_upsert_replaces() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let lean = ProverKind::new("lean");
+        assert!(store.get_pr_comment(repo.id, 7, lean.clone()).await.unwrap().is_none());
+
+        let mut record = PrCommentRecord {
+            repo_id: repo.id,
+            pr_number: 7,
+            prover: lean.clone(),
+            comment_id: "100".into(),
+            updated_at: chrono::Utc::now(),
+        };
+        store.upsert_pr_comment(&record).await.unwrap();
+        record.comment_id = "200".into();
+        store.upsert_pr_comment(&record).await.unwrap();
+
+        let stored = store.get_pr_comment(repo.id, 7, lean).await.unwrap().unwrap();
+        assert_eq!(stored.comment_id, "200");
+        assert!(store
+            .get_pr_comment(repo.id, 7, ProverKind::new("coq"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn dependency_graph_upsert_replaces() {
+        use crate::adapters::Platform;
+        use crate::depgraph::DependencyGraph;
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let coq = ProverKind::new("coq");
+        assert!(store.get_dependency_graph(repo.id, coq.clone()).await.unwrap().is_none());
+
+        let mut graph = DependencyGraph::default();
+        graph.imports.insert("A.v".into(), Default::default());
+        store
+            .upsert_dependency_graph(&DependencyGraphRecord::new(repo.id, coq.clone(), "c1".into(), graph.clone()))
+            .await
+            .unwrap();
+        graph.imports.insert("B.v".into(), ["A.v".to_string()].into_iter().collect());
+        store
+            .upsert_dependency_graph(&DependencyGraphRecord::new(repo.id, coq.clone(), "c2".into(), graph.clone()))
+            .await
+            .unwrap();
+
+        let stored = store.get_dependency_graph(repo.id, coq).await.unwrap().unwrap();
+        assert_eq!(stored.commit_sha, "c2");
+        assert_eq!(stored.graph, graph);
+        assert!(store
+            .get_dependency_graph(repo.id, ProverKind::new("lean"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn federated_results_upsert_and_cursor() {
+        use crate::adapters::Platform;
+
+        let (store, path) = fresh_store().await;
+        let peer = "https://peer.example.org";
+        assert!(store.latest_federated_result_at(peer).await.unwrap().is_none());
+
+        let record = |success: bool, completed_at: chrono::DateTime<chrono::Utc>| FederatedResultRecord {
+            id: Uuid::new_v4(),
+            peer: peer.into(),
+            platform: Platform::GitHub,
+            repo: "metamath/set.mm".into(),
+            commit_sha: "c1".into(),
+            prover: ProverKind::new("metamath"),
+            success,
+            verified_files: vec!["set.mm".into()],
+            failed_files: vec![],
+            completed_at,
+            envelope: "{}".into(),
+            imported_at: chrono::Utc::now(),
+        };
+        let first = chrono::Utc::now() - chrono::Duration::minutes(5);
+        let second = chrono::Utc::now();
+        store.upsert_federated_result(&record(false, first)).await.unwrap();
+        store.upsert_federated_result(&record(true, second)).await.unwrap();
+
+        let found = store
+            .get_federated_result(Platform::GitHub, "metamath/set.mm", "c1", ProverKind::new("metamath"))
+            .await
+            .unwrap()
+            .expect("imported");
+        assert!(found.success, "re-import replaces the earlier result");
+        assert_eq!(found.verified_files, vec!["set.mm".to_string()]);
+        assert_eq!(
+            store.latest_federated_result_at(peer).await.unwrap().map(|t| t.timestamp()),
+            Some(second.timestamp())
+        );
+        assert!(store
+            .get_federated_result(Platform::GitHub, "metamath/set.mm", "c2", ProverKind::new("metamath"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn job_payload_survives_updates_and_legacy_rows_load() {
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let job = crate::scheduler::ProofJob::new(repo.id, "abc".into(), ProverKind::new("lean"), vec![])
+            .with_timeout(1200);
+        let mut record = ProofJobRecord::from(job.clone());
+        store.create_job(&record).await.unwrap();
+
+        // Auto-discovered files are written back after the clone.
+        record.payload.file_paths = vec!["A.lean".into()];
+        store.update_job(&record).await.unwrap();
+        let stored = store.get_job(job.id).await.unwrap().unwrap();
+        assert_eq!(stored.payload.file_paths, vec!["A.lean".to_string()]);
+        assert_eq!(stored.payload.timeout_secs, Some(1200));
+
+        // A row written before the payload column existed.
+        sqlx::query("UPDATE proof_jobs SET payload = NULL, file_paths = '[\"B.lean\"]' WHERE id = ?")
+            .bind(job.id.0.to_string())
+            .execute(store.pool())
+            .await
+            .unwrap();
+        let legacy = store.get_job(job.id).await.unwrap().unwrap();
+        assert_eq!(legacy.payload.file_paths, vec!["B.lean".to_string()]);
+        assert_eq!(legacy.payload.timeout_secs, None);
 
-impl TryFrom<JobRow> for ProofJobRecord {
-    type Error = Error;
+        let _ = std::fs::remove_file(&path);
+    }
 
-    fn try_from(row: JobRow) -> Result<Self> {
-        use crate::scheduler::{JobPriority, JobStatus};
+    #[tokio::test]
+    async fn proof_items_are_replaced_and_listed_by_file() {
+        use crate::proof_items::{ItemStatus, ProofItem};
 
-        let prover = parse_prover(&row.prover)?;
-        let status = match row.status.as_str() {
-            "Queued" => JobStatus::Queued,
-            "Running" => JobStatus::Running,
-            "Completed" => JobStatus::Completed,
-            "Failed" => JobStatus::Failed,
-            "Cancelled" => JobStatus::Cancelled,
-            _ => return Err(Error::Internal(format!("Unknown status: {}", row.status))),
-        };
-        let priority = match row.priority {
-            0 => JobPriority::Low,
-            1 => JobPriority::Normal,
-            2 => JobPriority::High,
-            3 => JobPriority::Critical,
-            _ => JobPriority::Normal,
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let job = crate::scheduler::ProofJob::new(repo.id, "abc".into(), ProverKind::new("coq"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+
+        let theorem = ProofItem {
+            file_path: "A.v".into(),
+            theorem: Some("a".into()),
+            line: Some(3),
+            status: ItemStatus::Admitted,
+            duration_ms: Some(12),
         };
-        let file_paths: Vec<String> = serde_json::from_str(&row.file_paths)?;
+        let items = [
+            ProofItemRecord::new(job.id, ProofItem::file("B.v", false, &[], None)),
+            ProofItemRecord::new(job.id, theorem.clone()),
+            ProofItemRecord::new(job.id, ProofItem::file("A.v", true, &[theorem], Some(40))),
+        ];
+        store.record_proof_items(job.id, &items[..1]).await.unwrap();
+        store.record_proof_items(job.id, &items).await.unwrap();
 
-        Ok(ProofJobRecord {
-            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
-            repo_id: Uuid::parse_str(&row.repo_id).map_err(|e| Error::Internal(e.to_string()))?,
-            commit_sha: row.commit_sha,
-            prover,
-            file_paths,
-            status,
-            priority,
-            queued_at: chrono::DateTime::parse_from_rfc3339(&row.queued_at)
-                .map_err(|e| Error::Internal(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-            started_at: row.started_at.map(|s| {
-                chrono::DateTime::parse_from_rfc3339(&s)
-                    .map(|t| t.with_timezone(&chrono::Utc))
-            }).transpose().map_err(|e| Error::Internal(e.to_string()))?,
-            completed_at: row.completed_at.map(|s| {
-                chrono::DateTime::parse_from_rfc3339(&s)
-                    .map(|t| t.with_timezone(&chrono::Utc))
-            }).transpose().map_err(|e| Error::Internal(e.to_string()))?,
-            error_message: row.error_message,
-            pr_number: row.pr_number.map(|n| n as u64),
-            delivery_id: row.delivery_id,
-        })
+        let stored = store.list_proof_items(job.id).await.unwrap();
+        let summary: Vec<(&str, Option<&str>, ItemStatus)> = stored
+            .iter()
+            .map(|i| (i.file_path.as_str(), i.theorem.as_deref(), i.status))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("A.v", None, ItemStatus::Admitted),
+                ("A.v", Some("a"), ItemStatus::Admitted),
+                ("B.v", None, ItemStatus::Failed),
+            ]
+        );
+        assert_eq!((stored[1].line, stored[1].duration_ms), (Some(3), Some(12)));
+
+        let _ = std::fs::remove_file(&path);
     }
-}
 
-#[derive(sqlx::FromRow)]
-struct ResultRow {
-    id: String,
-    job_id: String,
-    success: bool,
-    message: String,
-    prover_output: String,
-    duration_ms: i64,
-    verified_files: String,
-    failed_files: String,
-    created_at: String,
-}
+    #[tokio::test]
+    async fn flaky_runs_are_listed_newest_first() {
+        use crate::flaky::{Attempt, FlakyVerdict};
 
-impl TryFrom<ResultRow> for ProofResultRecord {
-    type Error = Error;
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+        let job = crate::scheduler::ProofJob::new(repo.id, "abc".into(), ProverKind::new("z3"), vec![]);
+        store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
 
-    fn try_from(row: ResultRow) -> Result<Self> {
-        let verified_files: Vec<String> = serde_json::from_str(&row.verified_files)?;
-        let failed_files: Vec<String> = serde_json::from_str(&row.failed_files)?;
+        let attempts = [Attempt::new(false, "timeout"), Attempt::new(true, "")];
+        let run = |file: &str, verdict| {
+            let prover = job.prover.clone();
+            FlakyRunRecord::new(job.id, repo.id, prover, file.into(), "abc".into(), &attempts, verdict)
+        };
+        let mut older = run("a.smt2", FlakyVerdict::Deterministic);
+        older.created_at -= chrono::Duration::minutes(1);
+        store.record_flaky_run(&older).await.unwrap();
+        store.record_flaky_run(&run("b.smt2", FlakyVerdict::Flaky)).await.unwrap();
 
-        Ok(ProofResultRecord {
-            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
-            job_id: Uuid::parse_str(&row.job_id).map_err(|e| Error::Internal(e.to_string()))?,
-            success: row.success,
-            message: row.message,
-            prover_output: row.prover_output,
-            duration_ms: row.duration_ms,
-            verified_files,
-            failed_files,
-            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                .map_err(|e| Error::Internal(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-        })
+        let runs = store.list_flaky_runs(repo.id, 10).await.unwrap();
+        let summary: Vec<(&str, FlakyVerdict, u32, bool)> = runs
+            .iter()
+            .map(|r| (r.file_path.as_str(), r.verdict, r.attempts, r.timed_out))
+            .collect();
+        assert_eq!(
+            summary,
+            [("b.smt2", FlakyVerdict::Flaky, 2, true), ("a.smt2", FlakyVerdict::Deterministic, 2, true)]
+        );
+        assert_eq!(runs[0].prover, ProverKind::new("z3"));
+        assert_eq!(store.list_flaky_runs(repo.id, 1).await.unwrap().len(), 1);
+
+        let _ = std::fs::remove_file(&path);
     }
-}
 
-#[derive(sqlx::FromRow)]
-struct OutcomeRow {
-    id: String,
-    job_id: Option<String>,
-    prover: String,
-    goal_fingerprint: String,
-    tactic: String,
-    succeeded: bool,
-    duration_ms: i64,
-    created_at: String,
-}
+    #[tokio::test]
+    async fn bisection_upsert_round_trips() {
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
 
-impl TryFrom<OutcomeRow> for TacticOutcomeRecord {
-    type Error = Error;
+        let pending = vec!["c2".to_string(), "c3".to_string()];
+        let mut bisection = BisectionRecord::new(
+            repo.id,
+            ProverKind::new("coq"),
+            "main".into(),
+            "theories/A.v".into(),
+            "c1".into(),
+            "c4".into(),
+            pending,
+        );
+        store.upsert_bisection(&bisection).await.unwrap();
+        bisection.pending.clear();
+        bisection.good_sha = "c3".into();
+        bisection.probes = 2;
+        bisection.status = BisectStatus::Found;
+        bisection.issue = Some("17".into());
+        store.upsert_bisection(&bisection).await.unwrap();
 
-    fn try_from(row: OutcomeRow) -> Result<Self> {
-        let prover = parse_prover(&row.prover)?;
-        let job_id = row
-            .job_id
-            .map(|s| Uuid::parse_str(&s).map_err(|e| Error::Internal(e.to_string())))
-            .transpose()?;
+        assert_eq!(store.get_bisection(bisection.id).await.unwrap(), Some(bisection.clone()));
+        assert_eq!(store.list_bisections(repo.id, 10).await.unwrap(), vec![bisection]);
 
-        Ok(TacticOutcomeRecord {
-            id: Uuid::parse_str(&row.id).map_err(|e| Error::Internal(e.to_string()))?,
-            job_id,
-            prover,
-            goal_fingerprint: row.goal_fingerprint,
-            tactic: row.tactic,
-            succeeded: row.succeeded,
-            duration_ms: row.duration_ms,
-            created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                .map_err(|e| Error::Internal(e.to_string()))?
-                .with_timezone(&chrono::Utc),
-        })
+        let _ = std::fs::remove_file(&path);
     }
-}
 
-fn parse_prover(s: &str) -> Result<ProverKind> {
-    match s {
-        "Agda" => Ok(ProverKind::new("agda")),
-        "Coq" => Ok(ProverKind::new("coq")),
-        "Lean" => Ok(ProverKind::new("lean")),
-        "Isabelle" => Ok(ProverKind::new("isabelle")),
-        "Z3" => Ok(ProverKind::new("z3")),
-        "Cvc5" => Ok(ProverKind::new("cvc5")),
-        "Metamath" => Ok(ProverKind::new("metamath")),
-        "HolLight" => Ok(ProverKind::new("hol-light")),
-        "Mizar" => Ok(ProverKind::new("mizar")),
-        "Pvs" => Ok(ProverKind::new("pvs")),
-        "Acl2" => Ok(ProverKind::new("acl2")),
-        "Hol4" => Ok(ProverKind::new("hol4")),
-        _ => Ok(ProverKind::new(s)),  // Support all 113 provers dynamically
+    #[tokio::test]
+    async fn certificate_is_replaced_per_commit() {
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let envelope = |payload: &str| crate::attest::Envelope {
+            payload_type: crate::certificate::PAYLOAD_TYPE.into(),
+            payload: payload.into(),
+            signatures: vec![],
+        };
+        let first = CertificateRecord::new(repo.id, "c1".into(), envelope("{}"));
+        store.save_certificate(&first).await.unwrap();
+        let second = CertificateRecord::new(repo.id, "c1".into(), envelope("{\"v\":2}"));
+        store.save_certificate(&second).await.unwrap();
+
+        let stored = store.get_certificate(repo.id, "c1").await.unwrap().unwrap();
+        assert_eq!(stored.id, second.id);
+        assert_eq!(stored.envelope.payload, "{\"v\":2}");
+        assert!(store.get_certificate(repo.id, "c2").await.unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::store::models::{goal_fingerprint, TacticOutcomeRecord};
+    #[tokio::test]
+    async fn a_confirmation_is_taken_once() {
+        let (store, path) = fresh_store().await;
+        let now = chrono::Utc::now();
+        let confirmation = |hash: &str, expires_in: i64| ConfirmationRecord {
+            token_hash: hash.into(),
+            action: "purgeRepository".into(),
+            actor: "token:1".into(),
+            subject_digest: "d".into(),
+            impact: "Deletes o/r".into(),
+            created_at: now,
+            expires_at: now + chrono::Duration::seconds(expires_in),
+        };
+        store.create_confirmation(&confirmation("live", 300)).await.unwrap();
+        store.create_confirmation(&confirmation("stale", -1)).await.unwrap();
 
-    async fn fresh_store() -> (SqliteStore, std::path::PathBuf) {
-        let path = std::env::temp_dir()
-            .join(format!("echidnabot-store-test-{}.db", Uuid::new_v4()));
-        let url = format!("sqlite://{}?mode=rwc", path.display());
-        let store = SqliteStore::new(&url).await.expect("open store");
-        (store, path)
+        let taken = store.take_confirmation("live").await.unwrap().unwrap();
+        assert_eq!(taken.impact, "Deletes o/r");
+        assert!(store.take_confirmation("live").await.unwrap().is_none());
+        // Expired rows went with the first take.
+        assert!(store.take_confirmation("stale").await.unwrap().is_none());
+
+        let _ = std::fs::remove_file(&path);
     }
 
     #[tokio::test]
-    async fn tactic_outcome_insert_then_lookup_by_fingerprint() {
+    async fn the_outbox_records_inserts_and_status_changes() {
+        use crate::scheduler::ProofJob;
+
         let (store, path) = fresh_store().await;
-        let fp = goal_fingerprint("forall x : Nat, x = x");
+        let new_job = || {
+            ProofJobRecord::from(ProofJob::new(
+                Uuid::new_v4(),
+                "abc".into(),
+                ProverKind::new("coq"),
+                vec![],
+            ))
+        };
+        store.create_job(&new_job()).await.unwrap();
+        assert!(store.list_job_events(10).await.unwrap().is_empty(), "off by default");
 
-        let first = TacticOutcomeRecord::new(
-            None, ProverKind::new("coq"), fp.clone(), "reflexivity".into(), true, 12,
-        );
-        let second = TacticOutcomeRecord::new(
-            None, ProverKind::new("coq"), fp.clone(), "auto".into(), false, 30,
-        );
-        store.record_tactic_outcome(&first).await.unwrap();
-        store.record_tactic_outcome(&second).await.unwrap();
+        store.set_job_event_outbox(true).await.unwrap();
+        store.set_job_event_outbox(true).await.unwrap();
+        let mut job = new_job();
+        store.create_job(&job).await.unwrap();
+        job.status = JobStatus::Running;
+        store.update_job(&job).await.unwrap();
+        // Not a status change.
+        store.update_job(&job).await.unwrap();
 
-        let found = store
-            .list_tactic_outcomes_by_fingerprint(ProverKind::new("coq"), &fp, 10)
-            .await
-            .unwrap();
-        assert_eq!(found.len(), 2);
-        // DESC by created_at — second row (auto) is newer
-        assert_eq!(found[0].tactic, "auto");
-        assert!(!found[0].succeeded);
-        assert_eq!(found[1].tactic, "reflexivity");
-        assert!(found[1].succeeded);
+        let events = store.list_job_events(10).await.unwrap();
+        let statuses: Vec<_> = events.iter().map(|e| e.status).collect();
+        assert_eq!(statuses, vec![JobStatus::Queued, JobStatus::Running]);
+        assert!(events.iter().all(|e| e.job_id == job.id));
+        assert_ne!(events[0].event_id, events[1].event_id);
+
+        store.record_job_event_failure(events[0].seq, "no responders").await.unwrap();
+        let events = store.list_job_events(10).await.unwrap();
+        assert_eq!(events[0].attempts, 1);
+        assert_eq!(events[0].last_error.as_deref(), Some("no responders"));
+        store.delete_job_events(&[events[0].seq]).await.unwrap();
+        assert_eq!(store.list_job_events(10).await.unwrap().len(), 1);
+
+        store.set_job_event_outbox(false).await.unwrap();
+        job.status = JobStatus::Completed;
+        store.update_job(&job).await.unwrap();
+        assert_eq!(store.list_job_events(10).await.unwrap().len(), 1);
 
         let _ = std::fs::remove_file(&path);
     }
 
     #[tokio::test]
-    async fn tactic_outcome_filters_by_prover() {
+    async fn finished_jobs_page_after_an_analytics_cursor() {
+        use crate::scheduler::ProofJob;
+
         let (store, path) = fresh_store().await;
-        let fp = goal_fingerprint("P /\\ Q -> P");
+        let at = chrono::Utc::now();
+        let mut jobs: Vec<ProofJobRecord> = (0..3)
+            .map(|_| {
+                let mut record = ProofJobRecord::from(ProofJob::new(
+                    Uuid::new_v4(),
+                    "abc".into(),
+                    ProverKind::new("coq"),
+                    vec![],
+                ));
+                record.status = JobStatus::Completed;
+                record.completed_at = Some(at);
+                record
+            })
+            .collect();
+        jobs[2].completed_at = Some(at - chrono::Duration::minutes(1));
+        let queued = ProofJobRecord::from(ProofJob::new(
+            Uuid::new_v4(),
+            "abc".into(),
+            ProverKind::new("coq"),
+            vec![],
+        ));
+        store.create_job(&queued).await.unwrap();
+        for record in &jobs {
+            store.create_job(record).await.unwrap();
+        }
+        let mut tied = [jobs[0].id, jobs[1].id];
+        tied.sort_by_key(|id| id.to_string());
 
-        store
-            .record_tactic_outcome(&TacticOutcomeRecord::new(
-                None, ProverKind::new("coq"), fp.clone(), "split".into(), true, 5,
-            ))
-            .await
-            .unwrap();
-        store
-            .record_tactic_outcome(&TacticOutcomeRecord::new(
-                None, ProverKind::new("lean"), fp.clone(), "exact".into(), true, 5,
-            ))
-            .await
-            .unwrap();
+        let first = store.list_jobs_finished_after(None, 2).await.unwrap();
+        let ids: Vec<_> = first.iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec![jobs[2].id, tied[0]]);
+        let rest = store.list_jobs_finished_after(Some((at, tied[0])), 10).await.unwrap();
+        assert_eq!(rest.iter().map(|j| j.id).collect::<Vec<_>>(), vec![tied[1]]);
 
-        let coq_hits = store
-            .list_tactic_outcomes_by_fingerprint(ProverKind::new("coq"), &fp, 10)
-            .await
-            .unwrap();
-        let lean_hits = store
-            .list_tactic_outcomes_by_fingerprint(ProverKind::new("lean"), &fp, 10)
-            .await
-            .unwrap();
-        assert_eq!(coq_hits.len(), 1);
-        assert_eq!(coq_hits[0].tactic, "split");
-        assert_eq!(lean_hits.len(), 1);
-        assert_eq!(lean_hits[0].tactic, "exact");
+        assert!(store.get_analytics_cursor("ch").await.unwrap().is_none());
+        let cursor = AnalyticsCursor {
+            sink: "ch".into(),
+            completed_at: at,
+            job_id: tied[1],
+            updated_at: at,
+        };
+        store.set_analytics_cursor(&cursor).await.unwrap();
+        store.set_analytics_cursor(&cursor).await.unwrap();
+        let stored = store.get_analytics_cursor("ch").await.unwrap().unwrap();
+        assert_eq!(stored.job_id, tied[1]);
+        store.delete_analytics_cursor("ch").await.unwrap();
+        assert!(store.get_analytics_cursor("ch").await.unwrap().is_none());
 
         let _ = std::fs::remove_file(&path);
     }
 
     #[tokio::test]
-    async fn tactic_outcome_lookup_by_tactic() {
+    async fn running_and_recent_jobs_for_the_dashboard() {
+        use crate::scheduler::ProofJob;
+
         let (store, path) = fresh_store().await;
-        let fp1 = goal_fingerprint("goal 1");
-        let fp2 = goal_fingerprint("goal 2");
+        let repo_id = Uuid::new_v4();
+        let now = chrono::Utc::now();
+        let job = |status, minutes_ago: i64| {
+            let mut record = ProofJobRecord::from(ProofJob::new(
+                repo_id,
+                "abc".into(),
+                ProverKind::new("coq"),
+                vec![],
+            ));
+            record.status = status;
+            let at = now - chrono::Duration::minutes(minutes_ago);
+            match status {
+                JobStatus::Running => record.started_at = Some(at),
+                JobStatus::Queued => {}
+                _ => record.completed_at = Some(at),
+            }
+            record
+        };
+        let jobs = [
+            job(JobStatus::Queued, 0),
+            job(JobStatus::Running, 1),
+            job(JobStatus::Running, 9),
+            job(JobStatus::Completed, 30),
+            job(JobStatus::Failed, 5),
+        ];
+        for record in &jobs {
+            store.create_job(record).await.unwrap();
+        }
+
+        let running = store.list_running_jobs(10).await.unwrap();
+        assert_eq!(running.iter().map(|j| j.id).collect::<Vec<_>>(), vec![jobs[2].id, jobs[1].id]);
+        let recent = store.list_recent_finished_jobs(10).await.unwrap();
+        assert_eq!(recent.iter().map(|j| j.id).collect::<Vec<_>>(), vec![jobs[4].id, jobs[3].id]);
+
+        let mut counts = store.count_jobs_by_status().await.unwrap();
+        counts.sort_by_key(|(status, _)| format!("{:?}", status));
+        assert_eq!(
+            counts,
+            vec![
+                (JobStatus::Completed, 1),
+                (JobStatus::Failed, 1),
+                (JobStatus::Queued, 1),
+                (JobStatus::Running, 2),
+            ]
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn purge_repository_removes_its_history_only() {
+        use crate::adapters::Platform;
+        use crate::scheduler::{JobResult, ProofJob};
+
+        let (store, path) = fresh_store().await;
+        let repo = Repository::new(Platform::GitHub, "o".into(), "gone".into());
+        let other = Repository::new(Platform::GitHub, "o".into(), "kept".into());
+        store.create_repository(&repo).await.unwrap();
+        store.create_repository(&other).await.unwrap();
 
+        let result = JobResult {
+            success: true,
+            message: "ok".into(),
+            prover_output: String::new(),
+            duration_ms: 5,
+            verified_files: vec!["A.v".into()],
+            failed_files: vec![],
+            confidence: None,
+            axioms: None,
+            prover_version: None,
+            prover_args: vec![],
+            execution_path: None,
+        };
+        let mut jobs = Vec::new();
+        for repo_id in [repo.id, repo.id, other.id] {
+            let job = ProofJob::new(repo_id, "sha".into(), ProverKind::new("coq"), vec![]);
+            store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+            store.save_result(&ProofResultRecord::new(job.id, &result)).await.unwrap();
+            store
+                .record_file_timing(&FileTimingRecord::new(
+                    job.id,
+                    repo_id,
+                    ProverKind::new("coq"),
+                    "A.v".into(),
+                    "sha".into(),
+                    5,
+                    true,
+                ))
+                .await
+                .unwrap();
+            jobs.push(job.id);
+        }
+        let outcome = TacticOutcomeRecord::new(
+            Some(jobs[0].0),
+            ProverKind::new("coq"),
+            goal_fingerprint("goal"),
+            "auto".into(),
+            true,
+            1,
+        );
+        store.record_tactic_outcome(&outcome).await.unwrap();
         store
-            .record_tactic_outcome(&TacticOutcomeRecord::new(
-                None, ProverKind::new("coq"), fp1, "intros".into(), true, 3,
-            ))
+            .quarantine_proof(&QuarantinedProof::new(repo.id, "A.v".into(), None))
             .await
             .unwrap();
-        store
-            .record_tactic_outcome(&TacticOutcomeRecord::new(
-                None, ProverKind::new("coq"), fp2, "intros".into(), false, 99,
-            ))
+
+        let summary = store.purge_repository(repo.id).await.unwrap();
+        assert_eq!(
+            summary,
+            PurgeSummary {
+                jobs: 2,
+                results: 2,
+                file_timings: 2,
+            }
+        );
+        assert!(store.get_repository(repo.id).await.unwrap().is_none());
+        assert!(store.get_job(jobs[0]).await.unwrap().is_none());
+        assert!(store.list_quarantined_proofs(repo.id).await.unwrap().is_empty());
+        let kept = store
+            .list_tactic_outcomes_by_tactic(ProverKind::new("coq"), "auto", 10)
             .await
             .unwrap();
+        assert_eq!(kept.len(), 1, "learning data outlives the job");
+        assert!(kept[0].job_id.is_none());
 
-        let hits = store
-            .list_tactic_outcomes_by_tactic(ProverKind::new("coq"), "intros", 10)
+        assert!(store.get_repository(other.id).await.unwrap().is_some());
+        assert!(store.get_result_for_job(jobs[2]).await.unwrap().is_some());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_queue_on_the_single_writer() {
+        let path = std::env::temp_dir()
+            .join(format!("echidnabot-store-test-{}.db", Uuid::new_v4()));
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", path.display()),
+            max_connections: 8,
+            ..DatabaseConfig::default()
+        };
+        let store = std::sync::Arc::new(SqliteStore::connect(&config).await.unwrap());
+        let mode: (String,) = sqlx::query_as("PRAGMA journal_mode")
+            .fetch_one(store.pool())
             .await
             .unwrap();
-        assert_eq!(hits.len(), 2);
-        // Both rows share the tactic name but have different fingerprints
-        let successes = hits.iter().filter(|h| h.succeeded).count();
-        assert_eq!(successes, 1);
+        assert_eq!(mode.0, "wal");
+
+        let writes: Vec<_> = (0..64)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    let repo = Repository::new(Platform::GitHub, "burst".into(), format!("r{}", i));
+                    store.create_repository(&repo).await
+                })
+            })
+            .collect();
+        for write in writes {
+            write.await.unwrap().expect("no write fails with database is locked");
+        }
+        assert_eq!(store.list_repositories(None).await.unwrap().len(), 64);
 
+        let stats = store.pool_stats().unwrap();
+        assert!(stats.write_wait_count >= 64);
+        assert!(stats.connections >= 2, "reader pool plus the writer");
+        store.close().await;
         let _ = std::fs::remove_file(&path);
     }
 }
@@ -0,0 +1,265 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Anonymous usage telemetry (`[telemetry]`)
+//!
+//! Off by default. When enabled, a report of aggregate counts over the
+//! jobs finished since the previous report is POSTed to `endpoint` every
+//! `interval_secs`: jobs per prover with pass/fail counts and duration
+//! percentiles, and failures by error class.
+//!
+//! Nothing naming a repository, owner, file or commit is included, and
+//! error messages never leave the process: they are reduced to a fixed set
+//! of classes ([`error_class`]) first. `echidnabot telemetry preview`
+//! prints exactly what would be sent.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::scheduler::JobStatus;
+use crate::shutdown::ShutdownSignal;
+use crate::store::Store;
+
+/// Bumped whenever a field changes meaning or is removed.
+pub const REPORT_SCHEMA: u32 = 1;
+
+/// Jobs considered per report.
+const JOB_SCAN_LIMIT: usize = 100_000;
+
+/// Aggregate usage over one window.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UsageReport {
+    pub schema: u32,
+    /// echidnabot version.
+    pub version: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub jobs: u64,
+    /// Keyed by prover slug.
+    pub provers: BTreeMap<String, ProverUsage>,
+    /// Failed jobs by [`error_class`].
+    pub errors: BTreeMap<&'static str, u64>,
+}
+
+/// One prover's jobs in a report window.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ProverUsage {
+    pub jobs: u64,
+    pub passed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    /// Run time (start to finish) percentiles, over jobs that started.
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub mean_ms: u64,
+}
+
+/// The class a failed job's message falls into. Only the class is
+/// reported.
+pub fn error_class(message: &str) -> &'static str {
+    let lower = message.to_lowercase();
+    if lower.starts_with("failed ") && lower.contains("file(s)") {
+        "proof_failed"
+    } else if lower.starts_with("no proof files") {
+        "no_proof_files"
+    } else if lower.contains("timeout") || lower.contains("timed out") {
+        "timeout"
+    } else if lower.contains("not available") {
+        "prover_unavailable"
+    } else if lower.contains("unhealthy") {
+        "echidna_unhealthy"
+    } else if lower.contains("isolation") {
+        "executor"
+    } else if lower.contains("clone") {
+        "clone_failed"
+    } else if lower.starts_with("echidna communication error") {
+        "echidna_error"
+    } else if lower.starts_with("http error") {
+        "http"
+    } else if lower.starts_with("database error") || lower.starts_with("sql error") {
+        "database"
+    } else if lower.starts_with("io error") {
+        "io"
+    } else {
+        "other"
+    }
+}
+
+/// Report over the jobs finished in `(since, until]`.
+pub async fn build_report(
+    store: &dyn Store,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<UsageReport> {
+    let jobs = store.list_jobs_completed_since(since, JOB_SCAN_LIMIT).await?;
+
+    let mut provers: BTreeMap<String, ProverUsage> = BTreeMap::new();
+    let mut durations: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    let mut errors: BTreeMap<&'static str, u64> = BTreeMap::new();
+    let mut total = 0;
+    for job in jobs.iter().filter(|j| j.completed_at.is_some_and(|t| t <= until)) {
+        let slug = job.prover.as_str().to_string();
+        let usage = provers.entry(slug.clone()).or_default();
+        usage.jobs += 1;
+        total += 1;
+        match job.status {
+            JobStatus::Completed => usage.passed += 1,
+            JobStatus::Failed => {
+                usage.failed += 1;
+                *errors
+                    .entry(error_class(job.error_message.as_deref().unwrap_or_default()))
+                    .or_default() += 1;
+            }
+            JobStatus::Cancelled => usage.cancelled += 1,
+            JobStatus::Queued | JobStatus::Running => {}
+        }
+        if let (Some(started), Some(completed)) = (job.started_at, job.completed_at) {
+            let ms = (completed - started).num_milliseconds().max(0) as u64;
+            durations.entry(slug).or_default().push(ms);
+        }
+    }
+    for (slug, mut samples) in durations {
+        samples.sort_unstable();
+        if let Some(usage) = provers.get_mut(&slug) {
+            usage.p50_ms = percentile(&samples, 50);
+            usage.p90_ms = percentile(&samples, 90);
+            usage.mean_ms = samples.iter().sum::<u64>() / samples.len() as u64;
+        }
+    }
+
+    Ok(UsageReport {
+        schema: REPORT_SCHEMA,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        window_start: since,
+        window_end: until,
+        jobs: total,
+        provers,
+        errors,
+    })
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`.
+fn percentile(samples: &[u64], p: usize) -> u64 {
+    let rank = (samples.len() * p).div_ceil(100).max(1);
+    samples[rank - 1]
+}
+
+pub async fn send_report(client: &reqwest::Client, endpoint: &str, report: &UsageReport) -> Result<()> {
+    client.post(endpoint).json(report).send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Report every `interval` until shutdown. A window whose report could
+/// not be sent is folded into the next one.
+pub async fn run_telemetry(
+    store: Arc<dyn Store>,
+    config: Arc<Config>,
+    interval: Duration,
+    shutdown: ShutdownSignal,
+) {
+    let Some(endpoint) = config.telemetry.endpoint.clone() else {
+        tracing::warn!("[telemetry] is enabled without an endpoint; nothing will be sent");
+        return;
+    };
+    let client = reqwest::Client::new();
+    let mut window_start = Utc::now();
+    let shutdown_fut = shutdown.triggered();
+    tokio::pin!(shutdown_fut);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = &mut shutdown_fut => {
+                tracing::info!("Telemetry observed shutdown signal — stopping");
+                return;
+            }
+        }
+        let window_end = Utc::now();
+        let report = match build_report(store.as_ref(), window_start, window_end).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!("Building telemetry report failed: {}", e);
+                continue;
+            }
+        };
+        if report.jobs > 0 {
+            if let Err(e) = send_report(&client, &endpoint, &report).await {
+                tracing::debug!("Sending telemetry report failed: {}", e);
+                continue;
+            }
+        }
+        window_start = window_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+    use crate::dispatcher::ProverKind;
+    use crate::scheduler::ProofJob;
+    use crate::store::models::{ProofJobRecord, Repository};
+    use crate::store::SqliteStore;
+
+    #[test]
+    fn error_classes_hide_messages() {
+        assert_eq!(error_class("Failed 2 file(s)"), "proof_failed");
+        assert_eq!(error_class("No proof files found for prover"), "no_proof_files");
+        assert_eq!(error_class("Proof verification timeout"), "timeout");
+        assert_eq!(
+            error_class("ECHIDNA communication error: Prover Coq not available (status: down)"),
+            "prover_unavailable"
+        );
+        assert_eq!(error_class("IO error: /home/alice/secret-repo/A.v"), "io");
+        assert_eq!(error_class("something unexpected"), "other");
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples = [10, 20, 30, 40, 50, 60, 70, 80, 90, 100];
+        assert_eq!(percentile(&samples, 50), 50);
+        assert_eq!(percentile(&samples, 90), 90);
+        assert_eq!(percentile(&[7], 90), 7);
+    }
+
+    #[tokio::test]
+    async fn report_counts_jobs_in_window() {
+        let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+        let repo = Repository::new(Platform::GitHub, "alice".into(), "private-proofs".into());
+        store.create_repository(&repo).await.unwrap();
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let finished = |prover: &str, status: JobStatus, error: Option<&str>, secs: i64| {
+            let job = ProofJob::new(repo.id, "abc".into(), ProverKind::new(prover), vec![]);
+            let mut record = ProofJobRecord::from(job);
+            record.status = status;
+            record.started_at = Some(Utc::now() - chrono::Duration::seconds(secs));
+            record.completed_at = Some(Utc::now());
+            record.error_message = error.map(String::from);
+            record
+        };
+        for record in [
+            finished("coq", JobStatus::Completed, None, 2),
+            finished("coq", JobStatus::Failed, Some("Failed 1 file(s)"), 4),
+            finished("lean", JobStatus::Failed, Some("Proof verification timeout"), 6),
+        ] {
+            store.create_job(&record).await.unwrap();
+        }
+
+        let report = build_report(&store, since, Utc::now()).await.unwrap();
+        assert_eq!(report.jobs, 3);
+        let coq = &report.provers["coq"];
+        assert_eq!((coq.jobs, coq.passed, coq.failed), (2, 1, 1));
+        assert!(coq.p90_ms >= coq.p50_ms);
+        assert_eq!(report.errors["proof_failed"], 1);
+        assert_eq!(report.errors["timeout"], 1);
+
+        let rendered = serde_json::to_string(&report).unwrap();
+        assert!(!rendered.contains("alice") && !rendered.contains("private-proofs"));
+    }
+}
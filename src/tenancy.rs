@@ -0,0 +1,240 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Tenant isolation (`[tenancy]`)
+//!
+//! A tenant is a repository owner: the user, group or organisation a
+//! repository is registered under. One instance hosting several tenants
+//! keeps them apart three ways:
+//!
+//! - **Tokens.** `echidnabot token mint --tenant <owner>` mints a token
+//!   that only sees that owner's repositories, their jobs and results and
+//!   the audit entries about them, whatever its scope (see
+//!   [`crate::api::auth`]). Instance-wide operations (statistics,
+//!   announcements, tactic history, `/metrics`, `/fleet`) need a token
+//!   without a tenant.
+//! - **Partitioning.** Job searches and the audit log carry the tenant as
+//!   an owner filter the store applies in SQL, so other tenants' rows
+//!   never leave the database.
+//! - **Keys.** With `[tenancy] master_key` set, each tenant gets its own
+//!   random data key, kept in `tenant_keys` wrapped by the master key.
+//!   Per-repository webhook secrets are sealed with their tenant's key
+//!   (ChaCha20-Poly1305, bound to the repository id) when written and
+//!   opened when read. Forgetting a tenant's key (`echidnabot tenant
+//!   forget <owner>`) leaves its secrets unreadable for good.
+//!
+//! A secret that can't be opened — no master key, or its tenant key is
+//! gone — stays sealed: deliveries for that repository are then refused
+//! with 401 rather than falling back to the platform-wide secret.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+
+use crate::config::TenancyConfig;
+use crate::error::{Error, Result};
+
+/// Prefix of a sealed value as stored.
+pub const SEALED_PREFIX: &str = "sealed:v1:";
+
+const NONCE_LEN: usize = 12;
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// Whether `value` is a sealed secret rather than plaintext.
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// Encrypt `plaintext` under `key`, binding `aad`: nonce ‖ ciphertext.
+fn encrypt(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+        .map_err(|_| Error::Internal("encryption failed".to_string()))?;
+    Ok([nonce.as_slice(), sealed.as_slice()].concat())
+}
+
+fn decrypt(key: &[u8; 32], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(Error::InvalidInput("sealed value is truncated".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| Error::InvalidInput("sealed value does not open with this key".to_string()))
+}
+
+/// `[tenancy] master_key`: 32 bytes, as 64 hex digits or base64.
+#[derive(Clone)]
+pub struct MasterKey([u8; 32]);
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterKey(<redacted>)")
+    }
+}
+
+impl MasterKey {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let bytes = if value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            hex::decode(value).ok()
+        } else {
+            b64().decode(value).ok()
+        };
+        bytes
+            .and_then(|b| <[u8; 32]>::try_from(b).ok())
+            .map(Self)
+            .ok_or_else(|| {
+                Error::Config(
+                    "[tenancy] master_key must be 32 bytes, as 64 hex digits or base64".to_string(),
+                )
+            })
+    }
+}
+
+/// One tenant's data key.
+#[derive(Clone)]
+pub struct TenantKey([u8; 32]);
+
+impl TenantKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    fn wrap_aad(owner: &str) -> Vec<u8> {
+        format!("echidnabot tenant key:{}", owner).into_bytes()
+    }
+
+    /// The key encrypted under `master`, bound to `owner`, for storage.
+    pub fn wrap(&self, master: &MasterKey, owner: &str) -> Result<String> {
+        Ok(b64().encode(encrypt(&master.0, &Self::wrap_aad(owner), &self.0)?))
+    }
+
+    /// Fails when `wrapped` was made with another master key or owner.
+    pub fn unwrap(wrapped: &str, master: &MasterKey, owner: &str) -> Result<Self> {
+        let sealed = b64()
+            .decode(wrapped.trim())
+            .map_err(|_| Error::InvalidInput(format!("tenant key of '{}' is not base64", owner)))?;
+        let key = decrypt(&master.0, &Self::wrap_aad(owner), &sealed).map_err(|_| {
+            Error::Config(format!(
+                "the tenant key of '{}' does not open with [tenancy] master_key",
+                owner
+            ))
+        })?;
+        <[u8; 32]>::try_from(key)
+            .map(Self)
+            .map_err(|_| Error::Internal(format!("tenant key of '{}' has the wrong length", owner)))
+    }
+
+    /// Seal `plaintext`, bound to `scope` (a repository id).
+    pub fn seal(&self, scope: &str, plaintext: &str) -> Result<String> {
+        let sealed = encrypt(&self.0, scope.as_bytes(), plaintext.as_bytes())?;
+        Ok(format!("{}{}", SEALED_PREFIX, b64().encode(sealed)))
+    }
+
+    /// Open a value [`TenantKey::seal`] made for the same `scope`.
+    pub fn open(&self, scope: &str, sealed: &str) -> Result<String> {
+        let encoded = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| Error::InvalidInput("value is not sealed".to_string()))?;
+        let bytes = b64()
+            .decode(encoded)
+            .map_err(|_| Error::InvalidInput("sealed value is not base64".to_string()))?;
+        String::from_utf8(decrypt(&self.0, scope.as_bytes(), &bytes)?)
+            .map_err(|_| Error::InvalidInput("sealed value is not UTF-8".to_string()))
+    }
+}
+
+/// The master key and the tenant keys unwrapped so far. Held by the
+/// store, which loads and creates the keys.
+pub struct Keyring {
+    master: MasterKey,
+    keys: Mutex<HashMap<String, TenantKey>>,
+}
+
+impl Keyring {
+    pub fn new(master: MasterKey) -> Self {
+        Self { master, keys: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn master(&self) -> &MasterKey {
+        &self.master
+    }
+
+    pub fn get(&self, owner: &str) -> Option<TenantKey> {
+        self.keys.lock().expect("tenant keyring poisoned").get(owner).cloned()
+    }
+
+    pub fn insert(&self, owner: &str, key: TenantKey) {
+        self.keys.lock().expect("tenant keyring poisoned").insert(owner.to_string(), key);
+    }
+
+    pub fn remove(&self, owner: &str) {
+        self.keys.lock().expect("tenant keyring poisoned").remove(owner);
+    }
+}
+
+impl TenancyConfig {
+    /// The parsed master key, if one is set.
+    pub fn master_key(&self) -> Result<Option<MasterKey>> {
+        self.master_key.as_deref().map(MasterKey::parse).transpose()
+    }
+
+    /// Run at startup and on reload.
+    pub fn validate(&self) -> Result<()> {
+        self.master_key().map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master(byte: u8) -> MasterKey {
+        MasterKey::parse(&hex::encode([byte; 32])).unwrap()
+    }
+
+    #[test]
+    fn master_key_parses_hex_and_base64() {
+        let hex_key = MasterKey::parse(&"ab".repeat(32)).unwrap();
+        let base64_key = MasterKey::parse(&b64().encode([0xab; 32])).unwrap();
+        assert_eq!(hex_key.0, base64_key.0);
+        assert!(MasterKey::parse("too short").is_err());
+        assert!(MasterKey::parse(&b64().encode([1u8; 16])).is_err());
+        assert_eq!(format!("{:?}", hex_key), "MasterKey(<redacted>)");
+    }
+
+    #[test]
+    fn tenant_keys_only_unwrap_for_their_master_and_owner() {
+        let key = TenantKey::generate();
+        let wrapped = key.wrap(&master(1), "acme").unwrap();
+        assert_eq!(TenantKey::unwrap(&wrapped, &master(1), "acme").unwrap().0, key.0);
+        assert!(TenantKey::unwrap(&wrapped, &master(2), "acme").is_err());
+        assert!(TenantKey::unwrap(&wrapped, &master(1), "globex").is_err());
+    }
+
+    #[test]
+    fn sealed_secrets_are_bound_to_key_and_repository() {
+        let key = TenantKey::generate();
+        let sealed = key.seal("repo-1", "hook secret").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("hook secret"));
+        assert_ne!(sealed, key.seal("repo-1", "hook secret").unwrap(), "fresh nonce");
+        assert_eq!(key.open("repo-1", &sealed).unwrap(), "hook secret");
+
+        assert!(key.open("repo-2", &sealed).is_err(), "moved to another repository");
+        assert!(TenantKey::generate().open("repo-1", &sealed).is_err(), "another tenant");
+        assert!(key.open("repo-1", "hook secret").is_err());
+    }
+}
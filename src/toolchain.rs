@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Prover toolchain versions and per-repository pins
+//!
+//! Before verifying anything, a job asks which version of its prover will
+//! run: the sandbox image (`lean --version`, `coqc --version`, ...) with
+//! `[executor] local_isolation`, else ECHIDNA. The answer is stored with
+//! the result and shown on the check run.
+//!
+//! A repository can pin versions per prover in its settings
+//! (`proverPins`, e.g. `lean` → `4.7.0`). A job whose toolchain reports
+//! anything else — or whose version cannot be determined — fails without
+//! verifying, and its check run fails in every mode with a message naming
+//! both versions.
+//!
+//! A pin matches the version it names and any version extending it by
+//! components: `4.7` accepts `4.7.0` and `4.7.2`; `4.7.0` only `4.7.0`.
+//!
+//! With `[executor] toolchain_image` set (e.g.
+//! `echidna-provers:{prover}-{version}`), the Podman sandbox also runs
+//! the version the project itself asks for ([`requested`]): Lean's
+//! `lean-toolchain`, the `coq` dependency of a root `*.opam` file, or a
+//! `.tool-versions` entry. `.ocamlformat` is not read: its `version` is
+//! the formatter's, not Coq's. A requested version without an image
+//! fails the job with a message naming the file, the version and the
+//! image ([`select_image`]).
+
+use std::path::Path;
+
+use crate::dispatcher::{EchidnaClient, ProverKind};
+use crate::error::{Error, Result};
+use crate::executor::container::{IsolationBackend, PodmanExecutor};
+use crate::store::models::Repository;
+
+/// The version in a prover's `--version` output: the first dotted number
+/// (`Lean (version 4.7.0, ...)` → `4.7.0`), else the first word with a
+/// digit in it (`Isabelle2024`).
+pub fn parse_version(output: &str) -> Option<String> {
+    let words = || {
+        output
+            .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '[' | ']' | ';'))
+            .map(|w| w.trim_end_matches(['.', ':']))
+            .filter(|w| !w.is_empty())
+    };
+    words()
+        .map(|w| w.strip_prefix(['v', 'V']).unwrap_or(w))
+        .find(|w| w.starts_with(|c: char| c.is_ascii_digit()) && w.contains('.'))
+        .or_else(|| words().find(|w| w.contains(|c: char| c.is_ascii_digit())))
+        .map(str::to_string)
+}
+
+/// Whether `version` satisfies `pin`; see the module docs.
+pub fn satisfies(pin: &str, version: &str) -> bool {
+    let normalize = |s: &str| {
+        let s = s.trim();
+        s.strip_prefix(['v', 'V']).unwrap_or(s).to_string()
+    };
+    let (pin, version) = (normalize(pin), normalize(version));
+    let version: Vec<&str> = version.split('.').collect();
+    let pin: Vec<&str> = pin.split('.').collect();
+    pin.len() <= version.len() && pin.iter().zip(&version).all(|(p, v)| p == v)
+}
+
+/// A pin from settings input, trimmed; empty and whitespace-bearing pins
+/// are rejected.
+pub fn validate_pin(prover: &ProverKind, version: &str) -> Result<String> {
+    let version = version.trim();
+    if version.is_empty() || version.contains(char::is_whitespace) {
+        return Err(Error::InvalidInput(format!(
+            "invalid version pin '{}' for {}",
+            version,
+            prover.display_name()
+        )));
+    }
+    Ok(version.to_string())
+}
+
+/// Hold `version`, as discovered for `prover`, to `repo`'s pin. `Err`
+/// carries the message for the check run.
+pub fn check(repo: &Repository, prover: &ProverKind, version: Option<&str>) -> std::result::Result<(), String> {
+    let Some(pin) = repo.prover_pins.get(prover.as_str()) else {
+        return Ok(());
+    };
+    match version {
+        Some(version) if satisfies(pin, version) => Ok(()),
+        Some(version) => Err(format!(
+            "{}: version {} is pinned for this repository, but the toolchain provides {}. \
+             Update the pin in the repository settings, or the prover image.",
+            prover.display_name(),
+            pin,
+            version
+        )),
+        None => Err(format!(
+            "{}: version {} is pinned for this repository, but the toolchain's version could not be determined.",
+            prover.display_name(),
+            pin
+        )),
+    }
+}
+
+/// The version of `prover` a job will run: the sandbox's when `local` is
+/// given, else ECHIDNA's. `None` when it can't be told; failures are
+/// logged, not returned.
+pub async fn discover(
+    local: Option<&PodmanExecutor>,
+    echidna: &EchidnaClient,
+    prover: &ProverKind,
+) -> Option<String> {
+    let found = match local {
+        Some(executor) => executor.prover_version(prover.clone()).await,
+        None => echidna.prover_version(prover).await,
+    };
+    found.unwrap_or_else(|e| {
+        tracing::debug!("Discovering the {} version failed: {}", prover.display_name(), e);
+        None
+    })
+}
+
+/// A toolchain version a project's files ask for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requested {
+    /// Repo-relative file the version was read from.
+    pub file: String,
+    pub version: String,
+}
+
+/// The version of `prover` the checkout asks for, if it names one.
+pub fn requested(checkout: &Path, prover: &ProverKind) -> Option<Requested> {
+    let read = |file: &str| std::fs::read_to_string(checkout.join(file)).ok();
+    let found = |file: &str, version: Option<String>| {
+        version.map(|version| Requested { file: file.to_string(), version })
+    };
+    let specific = match prover.as_str() {
+        "lean" | "lean4" => found("lean-toolchain", read("lean-toolchain").and_then(|t| lean_toolchain(&t))),
+        "coq" | "rocq" => opam_files(checkout)
+            .into_iter()
+            .find_map(|file| found(&file, read(&file).and_then(|t| opam_coq_version(&t)))),
+        _ => None,
+    };
+    specific.or_else(|| {
+        let tools = read(".tool-versions")?;
+        found(".tool-versions", tool_version(&tools, prover.as_str()))
+    })
+}
+
+/// `leanprover/lean4:v4.8.0` → `4.8.0`.
+fn lean_toolchain(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let version = line.rsplit(':').next()?;
+    let version = version.strip_prefix('v').unwrap_or(version);
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Root `*.opam` files, `coq.opam` first.
+fn opam_files(checkout: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(checkout)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".opam") || name.as_str() == "opam")
+        .collect();
+    files.sort_by_key(|name| (name.as_str() != "coq.opam", name.clone()));
+    files
+}
+
+/// The lowest version an opam file's Coq dependency allows:
+/// `"coq" {>= "8.18" & < "8.19~"}` → `8.18`, `"coq" {= "8.18.0"}` →
+/// `8.18.0`.
+fn opam_coq_version(text: &str) -> Option<String> {
+    for package in ["\"coq\"", "\"coq-core\"", "\"rocq-prover\"", "\"rocq-core\""] {
+        let Some(at) = text.find(package) else {
+            continue;
+        };
+        let rest = text[at + package.len()..].trim_start();
+        let Some(constraint) = rest.strip_prefix('{').and_then(|r| r.split('}').next()) else {
+            continue;
+        };
+        let version = constraint
+            .split(['&', '|'])
+            .map(str::trim)
+            .filter(|c| c.starts_with(">=") || c.starts_with('='))
+            .find_map(|c| c.split('"').nth(1))
+            .map(|v| v.trim_end_matches('~'));
+        if let Some(version) = version.filter(|v| !v.is_empty()) {
+            return Some(version.to_string());
+        }
+    }
+    None
+}
+
+/// `tool`'s version in an asdf `.tool-versions` file.
+fn tool_version(text: &str, tool: &str) -> Option<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .find_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next()? != tool {
+                return None;
+            }
+            words.next().map(str::to_string)
+        })
+}
+
+/// `template` with `{prover}` and `{version}` filled in. `None` when the
+/// version has characters an image tag can't carry.
+pub fn image_for(template: &str, prover: &ProverKind, version: &str) -> Option<String> {
+    let valid = !version.is_empty()
+        && version.len() <= 100
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    valid.then(|| template.replace("{prover}", prover.as_str()).replace("{version}", version))
+}
+
+/// `executor` switched to the `template` image for the version the
+/// checkout asks for, pulled if missing. Unchanged when the checkout asks
+/// for none, or outside Podman (bubblewrap runs the host's binaries).
+/// `Err` carries the message for the check run.
+pub async fn select_image(
+    executor: PodmanExecutor,
+    template: &str,
+    checkout: &Path,
+    prover: &ProverKind,
+) -> std::result::Result<PodmanExecutor, String> {
+    if executor.backend() != IsolationBackend::Podman {
+        return Ok(executor);
+    }
+    let Some(requested) = requested(checkout, prover) else {
+        return Ok(executor);
+    };
+    let Some(image) = image_for(template, prover, &requested.version) else {
+        return Err(format!(
+            "{}: {} asks for version '{}', which is not a valid image tag.",
+            prover.display_name(),
+            requested.file,
+            requested.version
+        ));
+    };
+    let executor = executor.with_image(image.clone());
+    match executor.ensure_image().await {
+        Ok(()) => Ok(executor),
+        Err(e) => Err(format!(
+            "{}: {} asks for version {}, but no prover image {} is available ({}). \
+             Use a version this instance has an image for, or ask its operator to publish one.",
+            prover.display_name(),
+            requested.file,
+            requested.version,
+            image,
+            e
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Platform;
+
+    #[test]
+    fn versions_are_read_from_common_banners() {
+        let cases = [
+            ("Lean (version 4.7.0, x86_64-unknown-linux-gnu, commit 6fce8f7d5cd1, Release)", "4.7.0"),
+            ("The Coq Proof Assistant, version 8.18.0\ncompiled with OCaml 4.14.1", "8.18.0"),
+            ("Z3 version 4.12.2 - 64 bit", "4.12.2"),
+            ("Agda version 2.6.4.1", "2.6.4.1"),
+            ("Isabelle2024", "Isabelle2024"),
+            ("cvc5 version v1.1.2 [git tag 1.1.2 branch HEAD]", "1.1.2"),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(parse_version(output).as_deref(), Some(expected), "{}", output);
+        }
+        assert_eq!(parse_version("command not found"), None);
+    }
+
+    #[test]
+    fn pins_match_by_component_prefix() {
+        assert!(satisfies("4.7.0", "4.7.0"));
+        assert!(satisfies("4.7", "4.7.2"));
+        assert!(satisfies("v4.7", "4.7.0"));
+        assert!(!satisfies("4.7", "4.70.0"));
+        assert!(!satisfies("4.7.0", "4.7"));
+        assert!(!satisfies("4.7.0", "4.9.1"));
+    }
+
+    #[test]
+    fn only_pinned_provers_are_checked() {
+        let mut repo = Repository::new(Platform::GitHub, "o".into(), "r".into());
+        let lean = ProverKind::new("lean");
+        assert!(check(&repo, &lean, None).is_ok());
+
+        repo.prover_pins.insert("lean".into(), "4.7.0".into());
+        assert!(check(&repo, &lean, Some("4.7.0")).is_ok());
+        assert!(check(&repo, &ProverKind::new("coq"), Some("8.18.0")).is_ok());
+        let mismatch = check(&repo, &lean, Some("4.9.1")).unwrap_err();
+        assert!(mismatch.contains("4.7.0") && mismatch.contains("4.9.1"), "{}", mismatch);
+        assert!(check(&repo, &lean, None).unwrap_err().contains("could not be determined"));
+    }
+
+    #[test]
+    fn requested_versions_come_from_project_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (lean, coq, agda) = (ProverKind::new("lean"), ProverKind::new("coq"), ProverKind::new("agda"));
+        assert_eq!(requested(dir.path(), &lean), None);
+
+        std::fs::write(dir.path().join("lean-toolchain"), "leanprover/lean4:v4.8.0\n").unwrap();
+        std::fs::write(
+            dir.path().join("coq.opam"),
+            "depends: [\n  \"ocaml\"\n  \"coq\" {>= \"8.18\" & < \"8.19~\"}\n]\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join(".tool-versions"), "agda 2.6.4 # pinned\ncoq 8.17.1\n").unwrap();
+
+        let found = requested(dir.path(), &lean).unwrap();
+        assert_eq!((found.file.as_str(), found.version.as_str()), ("lean-toolchain", "4.8.0"));
+        // The opam constraint wins over `.tool-versions`.
+        assert_eq!(requested(dir.path(), &coq).unwrap().version, "8.18");
+        assert_eq!(requested(dir.path(), &agda).unwrap().version, "2.6.4");
+
+        let template = "echidna-provers:{prover}-{version}";
+        assert_eq!(image_for(template, &lean, "4.8.0").as_deref(), Some("echidna-provers:lean-4.8.0"));
+        assert_eq!(image_for(template, &lean, "4.8.0;rm"), None);
+    }
+}
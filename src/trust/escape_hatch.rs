@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+//! Escape-hatch scanning of proof sources
+//!
+//! A file can check while still leaning on `sorry`, `Admitted.` or an
+//! axiom it introduced. [`crate::trust::axiom_tracker`] only sees what the
+//! prover prints; this module reads the checked files themselves and
+//! reports each escape hatch with its line.
+//!
+//! The tokens are the `[axioms] forbid` list of the repo's manifest, or
+//! [`default_tokens`] for the prover when that is empty. Matches are whole
+//! words outside comments. `[axioms] severity` decides what happens to
+//! them: `info` only counts them in the statistics, `warning` annotates the
+//! lines on the check run, `error` also fails it.
+
+use std::path::Path;
+
+use crate::adapters::{AnnotationLevel, CheckAnnotation};
+use crate::dispatcher::ProverKind;
+use crate::modes::{AxiomSeverity, AxiomsSection};
+use crate::store::models::EscapeHatchRecord;
+
+/// One use of a forbidden token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscapeHatch {
+    /// Repo-relative path.
+    pub file_path: String,
+    /// 1-based.
+    pub line: u32,
+    pub token: String,
+}
+
+/// Placeholders that admit an unproven goal, per prover.
+pub fn placeholders(prover: &ProverKind) -> &'static [&'static str] {
+    match prover.as_str() {
+        "coq" => &["Admitted", "admit"],
+        "lean" => &["sorry"],
+        "agda" => &["postulate"],
+        "isabelle" => &["sorry", "oops"],
+        _ => &[],
+    }
+}
+
+/// Keywords that declare a new axiom, per prover.
+pub fn axiom_keywords(prover: &ProverKind) -> &'static [&'static str] {
+    match prover.as_str() {
+        "coq" => &["Axiom"],
+        "lean" => &["axiom"],
+        "isabelle" => &["axiomatization"],
+        _ => &[],
+    }
+}
+
+/// What is scanned for without an `[axioms] forbid` list.
+pub fn default_tokens(prover: &ProverKind) -> Vec<String> {
+    placeholders(prover)
+        .iter()
+        .chain(axiom_keywords(prover))
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// The tokens `policy` forbids in `prover`'s files.
+pub fn tokens(policy: &AxiomsSection, prover: &ProverKind) -> Vec<String> {
+    if policy.forbid.is_empty() {
+        default_tokens(prover)
+    } else {
+        policy.forbid.clone()
+    }
+}
+
+/// Comment syntax of a prover's sources.
+struct Comments {
+    line: Option<&'static str>,
+    block: Option<(&'static str, &'static str)>,
+}
+
+fn comments(prover: &ProverKind) -> Comments {
+    match prover.as_str() {
+        "lean" => Comments { line: Some("--"), block: Some(("/-", "-/")) },
+        "agda" => Comments { line: Some("--"), block: Some(("{-", "-}")) },
+        "coq" | "isabelle" => Comments { line: None, block: Some(("(*", "*)")) },
+        _ => Comments { line: None, block: None },
+    }
+}
+
+/// `text` with comments removed and line breaks kept. Block comments
+/// nest; Agda pragmas (`{-# OPTIONS --type-in-type #-}`) are code.
+fn strip_comments(text: &str, syntax: &Comments) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some((open, close)) = syntax.block {
+            if depth == 0 && rest.starts_with(open) && rest[open.len()..].starts_with('#') {
+                let end = rest.find("#-}").map(|i| i + 3).unwrap_or(rest.len());
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+                continue;
+            }
+            if rest.starts_with(open) {
+                depth += 1;
+                rest = &rest[open.len()..];
+                continue;
+            }
+            if depth > 0 && rest.starts_with(close) {
+                depth -= 1;
+                rest = &rest[close.len()..];
+                continue;
+            }
+        }
+        if depth == 0 {
+            if syntax.line.is_some_and(|line| rest.starts_with(line)) {
+                rest = &rest[rest.find('\n').unwrap_or(rest.len())..];
+                continue;
+            }
+            out.push(c);
+        } else if c == '\n' {
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+/// Whether `token` occurs in `line` as a whole word.
+fn contains_word(line: &str, token: &str) -> bool {
+    line.match_indices(token).any(|(i, _)| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + token.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+/// Lines (1-based) of `text` using any of `tokens`, with the token.
+pub fn scan(prover: &ProverKind, text: &str, tokens: &[String]) -> Vec<(u32, String)> {
+    let code = strip_comments(text, &comments(prover));
+    let mut found = Vec::new();
+    for (n, line) in code.lines().enumerate() {
+        for token in tokens.iter().filter(|t| !t.is_empty()) {
+            if contains_word(line, token) {
+                found.push((n as u32 + 1, token.clone()));
+            }
+        }
+    }
+    found
+}
+
+/// Scan `files` (relative to `root`) of a checkout. Unreadable files are
+/// skipped; the prover reports on those.
+pub async fn scan_files(
+    prover: &ProverKind,
+    root: &Path,
+    files: &[String],
+    tokens: &[String],
+) -> Vec<EscapeHatch> {
+    let mut hatches = Vec::new();
+    for file in files {
+        let Ok(text) = tokio::fs::read_to_string(root.join(file)).await else {
+            continue;
+        };
+        hatches.extend(scan(prover, &text, tokens).into_iter().map(|(line, token)| EscapeHatch {
+            file_path: file.clone(),
+            line,
+            token,
+        }));
+    }
+    hatches
+}
+
+/// Whether `severity` fails the check run.
+pub fn fails(hatches: &[EscapeHatchRecord], severity: AxiomSeverity) -> bool {
+    !hatches.is_empty() && severity == AxiomSeverity::Error
+}
+
+/// Check-run annotations for `hatches`; none at `info`.
+pub fn annotations(hatches: &[EscapeHatchRecord], severity: AxiomSeverity) -> Vec<CheckAnnotation> {
+    let level = match severity {
+        AxiomSeverity::Info => return Vec::new(),
+        AxiomSeverity::Warning => AnnotationLevel::Warning,
+        AxiomSeverity::Error => AnnotationLevel::Failure,
+    };
+    hatches
+        .iter()
+        .map(|h| CheckAnnotation {
+            path: h.file_path.clone(),
+            line: h.line,
+            level,
+            message: format!("`{}` is forbidden by the [axioms] policy", h.token),
+        })
+        .collect()
+}
+
+/// Render `hatches` as a Markdown section. Empty string when there are
+/// none or at `info`, so callers can append unconditionally.
+pub fn format_report(hatches: &[EscapeHatchRecord], severity: AxiomSeverity) -> String {
+    if hatches.is_empty() || severity == AxiomSeverity::Info {
+        return String::new();
+    }
+
+    let mut out = if fails(hatches, severity) {
+        String::from("### 🕳️ Escape hatches found (failing)\n\n")
+    } else {
+        String::from("### 🕳️ Escape hatches found\n\n")
+    };
+    out.push_str("| File | Line | Token |\n");
+    out.push_str("|------|------|-------|\n");
+    for h in hatches {
+        out.push_str(&format!("| `{}` | {} | `{}` |\n", h.file_path, h.line, h.token));
+    }
+    out.push_str("\nForbidden tokens are set in the `[axioms]` table of the repo's echidnabot manifest.\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(prover: &str, text: &str) -> Vec<(u32, String)> {
+        let prover = ProverKind::new(prover);
+        scan(&prover, text, &default_tokens(&prover))
+    }
+
+    #[test]
+    fn finds_placeholders_and_axioms_outside_comments() {
+        let coq = "(* no Admitted here (* nested admit *) *)\n\
+                   Lemma a : True.\nProof. admit. Admitted.\n\
+                   Axiom choice : forall A, A.\nTheorem AdmittedLemma : True.\n";
+        assert_eq!(
+            lines("coq", coq),
+            vec![(3, "Admitted".to_string()), (3, "admit".to_string()), (4, "Axiom".to_string())]
+        );
+
+        let lean = "/- sorry in a block -/\ntheorem t : p := by\n  sorry -- sorry again\naxiom ax : False\n";
+        assert_eq!(lines("lean", lean), vec![(3, "sorry".to_string()), (4, "axiom".to_string())]);
+
+        // Pragmas are code; the forbid list replaces the defaults.
+        let agda = "{-# OPTIONS --type-in-type #-}\n-- postulate\npostulate x : A\n";
+        let tokens = vec!["--type-in-type".to_string(), "postulate".to_string()];
+        assert_eq!(
+            scan(&ProverKind::new("agda"), agda, &tokens),
+            vec![(1, "--type-in-type".to_string()), (3, "postulate".to_string())]
+        );
+    }
+}
@@ -8,6 +8,7 @@
 //! 1. **Proof Confidence Levels** (1-5): quantify trust in verification results
 //! 2. **Solver Integrity Verification**: check that solver binaries are untampered
 //! 3. **Axiom Usage Tracking**: detect sorry, Admitted, postulate, etc.
+//! 4. **Escape-Hatch Scanning**: the same, with lines, in the checked sources
 //!
 //! Confidence levels:
 //! - Level 5: Cross-checked by 2+ independent small-kernel systems
@@ -18,6 +19,7 @@
 
 pub mod axiom_tracker;
 pub mod confidence;
+pub mod escape_hatch;
 pub mod solver_integrity;
 
 pub use axiom_tracker::{AxiomFlag, AxiomReport, AxiomTracker};
@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! `echidnabot watch` — terminal dashboard for operators
+//!
+//! Reads the store the daemon writes to (so it runs on the server, next to
+//! the database) and asks ECHIDNA for its health and that of every prover a
+//! repository enables. Shows job counts, running jobs with their elapsed
+//! time, and the most recent results. Every `interval` a new
+//! [`Snapshot`] is taken in the background; the screen redraws each second
+//! so elapsed times keep moving. `q`, `Esc` or `Ctrl-C` quits.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::Frame;
+use tokio::sync::{mpsc, watch};
+use uuid::Uuid;
+
+use crate::dispatcher::echidna_client::ProverStatus;
+use crate::dispatcher::{EchidnaClient, ProverKind};
+use crate::error::{Error, Result};
+use crate::scheduler::JobStatus;
+use crate::store::models::ProofJobRecord;
+use crate::store::Store;
+
+/// Rows fetched for the running and recent tables.
+const TABLE_ROWS: usize = 50;
+
+/// Everything one refresh of the dashboard shows.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub taken_at: Option<DateTime<Utc>>,
+    pub counts: Vec<(JobStatus, u64)>,
+    pub running: Vec<ProofJobRecord>,
+    pub recent: Vec<ProofJobRecord>,
+    /// `owner/name` by repository id.
+    pub repo_names: HashMap<Uuid, String>,
+    /// `None` when ECHIDNA could not be reached.
+    pub echidna_healthy: Option<bool>,
+    pub provers: Vec<(ProverKind, ProverStatus)>,
+    /// Why the last refresh failed, if it did.
+    pub error: Option<String>,
+}
+
+impl Snapshot {
+    /// Jobs in `status`.
+    pub fn count(&self, status: JobStatus) -> u64 {
+        self.counts
+            .iter()
+            .find(|(s, _)| *s == status)
+            .map_or(0, |(_, n)| *n)
+    }
+
+    fn repo_name(&self, id: Uuid) -> &str {
+        self.repo_names.get(&id).map_or("?", String::as_str)
+    }
+}
+
+/// Take a snapshot. Store errors fail it; ECHIDNA being down does not.
+pub async fn collect(store: &dyn Store, echidna: &EchidnaClient) -> Result<Snapshot> {
+    let repos = store.list_repositories(None).await?;
+    let provers: BTreeSet<String> = repos
+        .iter()
+        .flat_map(|r| r.enabled_provers.iter().map(|p| p.as_str().to_string()))
+        .collect();
+
+    let echidna_healthy = echidna.health_check().await.ok();
+    let mut prover_status = Vec::new();
+    if echidna_healthy.is_some() {
+        for slug in provers {
+            let prover = ProverKind::new(slug);
+            let status = echidna.prover_status(&prover).await.unwrap_or(ProverStatus::Unknown);
+            prover_status.push((prover, status));
+        }
+    }
+
+    Ok(Snapshot {
+        taken_at: Some(Utc::now()),
+        counts: store.count_jobs_by_status().await?,
+        running: store.list_running_jobs(TABLE_ROWS).await?,
+        recent: store.list_recent_finished_jobs(TABLE_ROWS).await?,
+        repo_names: repos.iter().map(|r| (r.id, r.full_name())).collect(),
+        echidna_healthy,
+        provers: prover_status,
+        error: None,
+    })
+}
+
+/// Run the dashboard until the user quits.
+pub async fn run(store: Arc<dyn Store>, echidna: Arc<EchidnaClient>, interval: Duration) -> Result<()> {
+    let (snapshot_tx, mut snapshots) = watch::channel(Snapshot::default());
+    let poller = tokio::spawn(async move {
+        let mut last = Snapshot::default();
+        loop {
+            match collect(store.as_ref(), &echidna).await {
+                Ok(snapshot) => last = snapshot,
+                Err(e) => last.error = Some(e.to_string()),
+            }
+            if snapshot_tx.send(last.clone()).is_err() {
+                return;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    // crossterm's reads block, so keys come from a thread of their own.
+    let (key_tx, mut keys) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if let Event::Key(key) = event {
+                if key_tx.send(key).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut terminal = ratatui::try_init()?;
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let result = loop {
+        let snapshot = snapshots.borrow_and_update().clone();
+        if let Err(e) = terminal.draw(|frame| render(frame, &snapshot, Utc::now())) {
+            break Err(Error::from(e));
+        }
+        tokio::select! {
+            key = keys.recv() => match key {
+                Some(key) if is_quit(&key) => break Ok(()),
+                Some(_) => {}
+                None => break Ok(()),
+            },
+            changed = snapshots.changed() => {
+                if changed.is_err() {
+                    break Ok(());
+                }
+            }
+            _ = tick.tick() => {}
+        }
+    };
+    ratatui::restore();
+    poller.abort();
+    result
+}
+
+fn is_quit(key: &KeyEvent) -> bool {
+    key.kind == KeyEventKind::Press
+        && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+            || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)))
+}
+
+/// Draw `snapshot` as of `now`.
+pub fn render(frame: &mut Frame, snapshot: &Snapshot, now: DateTime<Utc>) {
+    let [header, summary, running, recent] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(4),
+        Constraint::Percentage(40),
+        Constraint::Fill(1),
+    ])
+    .areas(frame.area());
+
+    let updated = match (&snapshot.error, snapshot.taken_at) {
+        (Some(e), _) => Span::styled(format!("refresh failed: {}", e), Style::default().fg(Color::Red)),
+        (None, Some(at)) => Span::raw(format!("updated {}", at.format("%H:%M:%S"))),
+        (None, None) => Span::raw("loading…"),
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled("echidnabot watch", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw("  "),
+            updated,
+            Span::styled("  (q to quit)", Style::default().fg(Color::DarkGray)),
+        ])),
+        header,
+    );
+
+    let counts = Line::from(format!(
+        "queued {}   running {}   completed {}   failed {}   cancelled {}",
+        snapshot.count(JobStatus::Queued),
+        snapshot.count(JobStatus::Running),
+        snapshot.count(JobStatus::Completed),
+        snapshot.count(JobStatus::Failed),
+        snapshot.count(JobStatus::Cancelled),
+    ));
+    let echidna = match snapshot.echidna_healthy {
+        Some(true) => Span::styled("healthy", Style::default().fg(Color::Green)),
+        Some(false) => Span::styled("unhealthy", Style::default().fg(Color::Yellow)),
+        None => Span::styled("unreachable", Style::default().fg(Color::Red)),
+    };
+    let mut provers = vec![Span::raw("provers ")];
+    for (prover, status) in &snapshot.provers {
+        provers.push(Span::styled(
+            format!("{} ", prover.display_name()),
+            Style::default().fg(prover_color(*status)),
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(vec![counts, Line::from(vec![Span::raw("ECHIDNA "), echidna]), Line::from(provers)])
+            .block(Block::bordered().title("Queue")),
+        summary,
+    );
+
+    let rows = snapshot.running.iter().map(|job| {
+        Row::new(vec![
+            short_id(job.id),
+            snapshot.repo_name(job.repo_id).to_string(),
+            job.prover.display_name().to_string(),
+            short_sha(&job.commit_sha),
+            job.started_at.map_or_else(String::new, |at| elapsed(now - at)),
+        ])
+    });
+    frame.render_widget(
+        Table::new(rows, [
+            Constraint::Length(8),
+            Constraint::Fill(1),
+            Constraint::Length(14),
+            Constraint::Length(8),
+            Constraint::Length(9),
+        ])
+        .header(header_row(["job", "repository", "prover", "commit", "elapsed"]))
+        .block(Block::bordered().title(format!("Running ({})", snapshot.running.len()))),
+        running,
+    );
+
+    let rows = snapshot.recent.iter().map(|job| {
+        let (label, color) = match job.status {
+            JobStatus::Completed => ("passed", Color::Green),
+            JobStatus::Failed => ("failed", Color::Red),
+            _ => ("cancelled", Color::DarkGray),
+        };
+        let took = match (job.started_at, job.completed_at) {
+            (Some(start), Some(end)) => elapsed(end - start),
+            _ => String::new(),
+        };
+        Row::new(vec![
+            short_id(job.id),
+            snapshot.repo_name(job.repo_id).to_string(),
+            job.prover.display_name().to_string(),
+            short_sha(&job.commit_sha),
+            label.to_string(),
+            took,
+            job.completed_at.map_or_else(String::new, |at| format!("{} ago", elapsed(now - at))),
+        ])
+        .style(Style::default().fg(color))
+    });
+    frame.render_widget(
+        Table::new(rows, [
+            Constraint::Length(8),
+            Constraint::Fill(1),
+            Constraint::Length(14),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(13),
+        ])
+        .header(header_row(["job", "repository", "prover", "commit", "result", "took", "finished"]))
+        .block(Block::bordered().title("Recent results")),
+        recent,
+    );
+}
+
+fn header_row<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn prover_color(status: ProverStatus) -> Color {
+    match status {
+        ProverStatus::Available => Color::Green,
+        ProverStatus::Degraded => Color::Yellow,
+        ProverStatus::Unavailable => Color::Red,
+        ProverStatus::Unknown => Color::DarkGray,
+    }
+}
+
+fn short_id(id: Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
+
+fn short_sha(sha: &str) -> String {
+    sha.chars().take(7).collect()
+}
+
+/// `42s`, `3m05s`, `2h07m`.
+fn elapsed(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::ProofJob;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn screen(snapshot: &Snapshot, now: DateTime<Utc>) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|frame| render(frame, snapshot, now)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn shows_counts_running_jobs_and_results() {
+        let now = Utc::now();
+        let repo_id = Uuid::new_v4();
+        let job = |status| {
+            let mut record = ProofJobRecord::from(ProofJob::new(
+                repo_id,
+                "0123456789abcdef".into(),
+                ProverKind::new("coq"),
+                vec![],
+            ));
+            record.status = status;
+            record.started_at = Some(now - chrono::Duration::seconds(125));
+            if status != JobStatus::Running {
+                record.completed_at = Some(now - chrono::Duration::seconds(5));
+            }
+            record
+        };
+        let snapshot = Snapshot {
+            taken_at: Some(now),
+            counts: vec![(JobStatus::Queued, 3), (JobStatus::Running, 1)],
+            running: vec![job(JobStatus::Running)],
+            recent: vec![job(JobStatus::Failed)],
+            repo_names: HashMap::from([(repo_id, "acme/proofs".to_string())]),
+            echidna_healthy: Some(true),
+            provers: vec![(ProverKind::new("coq"), ProverStatus::Available)],
+            error: None,
+        };
+
+        let text = screen(&snapshot, now);
+        assert!(text.contains("queued 3   running 1"), "{}", text);
+        assert!(text.contains("ECHIDNA healthy"));
+        assert!(text.contains("acme/proofs"));
+        assert!(text.contains("0123456"));
+        assert!(text.contains("2m05s"), "elapsed time of the running job");
+        assert!(text.contains("failed"));
+        assert!(text.contains("5s ago"));
+    }
+
+    #[test]
+    fn elapsed_is_compact() {
+        assert_eq!(elapsed(chrono::Duration::seconds(42)), "42s");
+        assert_eq!(elapsed(chrono::Duration::seconds(185)), "3m05s");
+        assert_eq!(elapsed(chrono::Duration::seconds(7620)), "2h07m");
+        assert_eq!(elapsed(chrono::Duration::seconds(-3)), "0s");
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: MPL-2.0
+// Copyright (c) Jonathan D.A. Jewell <j.d.a.jewell@open.ac.uk>
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+//! Chaos tests — behaviour under injected dependency failures.
+//!
+//! Only built with `cargo test --features chaos`. The injector is
+//! process-global, so every scenario runs inside the one test below.
+#![cfg(feature = "chaos")]
+
+use std::time::{Duration, Instant};
+
+use echidnabot::api::health::{Dependency, HealthMonitor};
+use echidnabot::chaos::{self, ChaosConfig, FaultSpec, Target};
+use echidnabot::config::{Config, HealthConfig};
+use echidnabot::dispatcher::{EchidnaClient, ProverKind};
+use echidnabot::store::{SqliteStore, Store};
+use echidnabot::Error;
+
+fn failing(spec: FaultSpec, target: Target) -> ChaosConfig {
+    let mut config = ChaosConfig {
+        enabled: true,
+        seed: Some(7),
+        ..ChaosConfig::default()
+    };
+    match target {
+        Target::Echidna => config.echidna = spec,
+        Target::Store => config.store = spec,
+        Target::Executor => config.executor = spec,
+    }
+    config
+}
+
+fn always_fail() -> FaultSpec {
+    FaultSpec {
+        error_rate: 1.0,
+        ..FaultSpec::default()
+    }
+}
+
+#[tokio::test]
+async fn chaos_faults_reach_callers() {
+    let store = SqliteStore::new("sqlite::memory:").await.unwrap();
+    let echidna = EchidnaClient::new(&Config::default().echidna);
+
+    // DB outage: store calls fail the way a dead pool does, and the
+    // health monitor marks the store down.
+    chaos::install(&failing(always_fail(), Target::Store));
+    assert!(matches!(
+        store.health_check().await,
+        Err(Error::Database(sqlx::Error::PoolTimedOut))
+    ));
+    let monitor = HealthMonitor::new(&HealthConfig {
+        failure_threshold: 1,
+        require_echidna: false,
+        ..HealthConfig::default()
+    });
+    monitor.probe(&store, &echidna).await;
+    assert_eq!(monitor.unavailable(), vec![Dependency::Store]);
+
+    // ECHIDNA flakiness: calls fail before any request is made.
+    chaos::install(&failing(always_fail(), Target::Echidna));
+    let err = echidna.verify_proof(&ProverKind::new("coq"), "Lemma x : True.").await.unwrap_err();
+    assert!(err.to_string().contains("chaos"));
+    assert!(store.health_check().await.unwrap(), "other targets are untouched");
+
+    // Latency without errors.
+    chaos::install(&failing(
+        FaultSpec {
+            latency_rate: 1.0,
+            latency_ms: 50,
+            ..FaultSpec::default()
+        },
+        Target::Store,
+    ));
+    let start = Instant::now();
+    assert!(store.health_check().await.unwrap());
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // A fixed seed replays the same faults.
+    let flaky = failing(
+        FaultSpec {
+            error_rate: 0.5,
+            ..FaultSpec::default()
+        },
+        Target::Store,
+    );
+    let mut runs = Vec::new();
+    for _ in 0..2 {
+        chaos::install(&flaky);
+        let mut outcomes = Vec::new();
+        for _ in 0..32 {
+            outcomes.push(store.health_check().await.is_ok());
+        }
+        runs.push(outcomes);
+    }
+    assert_eq!(runs[0], runs[1]);
+    assert!(runs[0].contains(&true) && runs[0].contains(&false));
+
+    chaos::clear();
+    assert!(store.health_check().await.unwrap());
+}
@@ -298,6 +298,9 @@ fn test_job_complete_success() {
         failed_files: vec![],
         confidence: None,
         axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
     };
 
     job.complete(result);
@@ -325,6 +328,9 @@ fn test_job_complete_failure() {
         failed_files: vec!["test.lean".to_string()],
         confidence: None,
         axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
     };
 
     job.complete(result);
@@ -397,6 +403,9 @@ fn test_proof_result_record() {
         failed_files: vec![],
         confidence: None,
         axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
     };
 
     let record = ProofResultRecord::new(job_id, &result);
@@ -493,6 +502,9 @@ async fn test_scheduler_enqueue_dequeue_cycle() {
         failed_files: vec![],
         confidence: None,
         axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
     };
 
     scheduler.complete_job(job_id, result).await;
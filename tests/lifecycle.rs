@@ -33,6 +33,9 @@ fn make_job_result(success: bool) -> JobResult {
         failed_files: if success { vec![] } else { vec!["main.v".to_string()] },
         confidence: None,
         axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
     }
 }
 
@@ -34,6 +34,7 @@
 //! the canonical push-event shape used here. The Rust test inlines equivalent
 //! payloads for speed; the JSON files serve as human-readable references.
 
+use arc_swap::ArcSwap;
 use async_graphql_axum::GraphQLRequest;
 use axum::{routing::get, Extension, Router};
 use axum_test::TestServer;
@@ -69,10 +70,10 @@ async fn make_server_with_repo(
     Arc<JobScheduler>,
     Uuid, // repo_id
 ) {
-    let config = Arc::new(Config::default());
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
     let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
     let scheduler = Arc::new(JobScheduler::new(4, 100));
-    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
 
     // Pre-register the repository that the webhook payload will reference.
     // The handler silently skips unregistered repos (see enqueue_repo_jobs),
@@ -98,6 +99,8 @@ async fn make_server_with_repo(
         // The daemon-wide mode selector acts as the final fallback; set it to
         // Verifier (the built-in default) unless the test wants to override it.
         mode_selector: ModeSelector::new(BotMode::Verifier),
+        ingest: None,
+        health: None,
     };
 
     let app = Router::new()
@@ -138,7 +141,8 @@ fn lean_push_payload() -> serde_json::Value {
             "full_name": "test-owner/lean-proof-repo",
             "id": 12345,
             "name": "lean-proof-repo",
-            "private": false
+            "private": false,
+            "default_branch": "main"
         }
     })
 }
@@ -162,7 +166,8 @@ fn coq_push_payload(owner_repo: &str) -> serde_json::Value {
             "full_name": owner_repo,
             "id": 12346,
             "name": "coq-proof-repo",
-            "private": false
+            "private": false,
+            "default_branch": "main"
         }
     })
 }
@@ -275,10 +280,10 @@ async fn seam_7a_regulator_mode_enqueues_job_on_push() {
 async fn seam_7a_unregistered_repo_does_not_enqueue() {
     // The server has no repos pre-registered — but it has a different repo registered.
     // The coq payload targets "test-owner/coq-proof-repo" which is unregistered.
-    let config = Arc::new(Config::default());
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
     let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
     let scheduler = Arc::new(JobScheduler::new(4, 100));
-    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
 
     let graphql_state = GraphQLState {
         store: store.clone(),
@@ -293,6 +298,8 @@ async fn seam_7a_unregistered_repo_does_not_enqueue() {
         scheduler: scheduler.clone(),
         rate_limiter: None,
         mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
     };
 
     let app = Router::new()
@@ -321,10 +328,10 @@ async fn seam_7a_unregistered_repo_does_not_enqueue() {
 /// auto-triggers on push).
 #[tokio::test]
 async fn seam_7a_daemon_default_mode_override_advisor_still_enqueues() {
-    let config = Arc::new(Config::default());
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
     let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
     let scheduler = Arc::new(JobScheduler::new(4, 100));
-    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
 
     // Register a repo with the built-in default (Verifier).
     let repo = Repository::new(Platform::GitHub, "test-owner".into(), "lean-proof-repo".into());
@@ -344,6 +351,8 @@ async fn seam_7a_daemon_default_mode_override_advisor_still_enqueues() {
         rate_limiter: None,
         // Daemon default is Advisor — should win over built-in Verifier.
         mode_selector: ModeSelector::new(BotMode::Advisor),
+        ingest: None,
+        health: None,
     };
 
     let app = Router::new()
@@ -721,3 +730,1162 @@ async fn seam_unknown_event_type_returns_200_no_jobs_enqueued() {
         "unknown event types must not enqueue jobs"
     );
 }
+
+// ═══════════════════════════════════════════════════════════════════════════════
+// Check-run requested actions
+// ═══════════════════════════════════════════════════════════════════════════════
+
+/// Create a failed job with one failing and one passing file timing, as the
+/// worker would have left it.
+async fn seed_failed_job(store: &SqliteStore, repo_id: Uuid) -> echidnabot::scheduler::JobId {
+    use echidnabot::scheduler::ProofJob;
+    use echidnabot::store::models::{FileTimingRecord, ProofJobRecord};
+
+    let job = ProofJob::new(repo_id, "cafebabe".into(), ProverKind::new("lean"), vec![]);
+    store.create_job(&ProofJobRecord::from(job.clone())).await.unwrap();
+    for (path, ok) in [("Proofs/Bad.lean", false), ("Proofs/Good.lean", true)] {
+        store
+            .record_file_timing(&FileTimingRecord::new(
+                job.id,
+                repo_id,
+                ProverKind::new("lean"),
+                path.into(),
+                "cafebabe".into(),
+                1_000,
+                ok,
+            ))
+            .await
+            .unwrap();
+    }
+    job.id
+}
+
+fn requested_action_payload(job_id: &str, identifier: &str, repo: &str) -> serde_json::Value {
+    serde_json::json!({
+        "action": "requested_action",
+        "check_run": { "external_id": job_id, "head_sha": "cafebabe" },
+        "requested_action": { "identifier": identifier },
+        "repository": { "full_name": repo },
+        "sender": { "login": "reviewer" }
+    })
+}
+
+/// "Quarantine" quarantines exactly the job's failing files.
+#[tokio::test]
+async fn seam_check_run_quarantine_action() {
+    let (server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let job_id = seed_failed_job(&store, repo_id).await;
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "check_run")
+        .json(&requested_action_payload(
+            &job_id.to_string(),
+            "quarantine",
+            "test-owner/lean-proof-repo",
+        ))
+        .await
+        .assert_status_ok();
+
+    let quarantined = store.list_quarantined_proofs(repo_id).await.unwrap();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].file_path, "Proofs/Bad.lean");
+}
+
+/// "Re-run (longer)" enqueues a fresh job; a click relayed from another
+/// repository is ignored.
+#[tokio::test]
+async fn seam_check_run_rerun_action_checks_repo() {
+    let (server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let job_id = seed_failed_job(&store, repo_id).await;
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "check_run")
+        .json(&requested_action_payload(
+            &job_id.to_string(),
+            "rerun_longer",
+            "someone-else/other-repo",
+        ))
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 0, "foreign repo must be ignored");
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "check_run")
+        .json(&requested_action_payload(
+            &job_id.to_string(),
+            "rerun_longer",
+            "test-owner/lean-proof-repo",
+        ))
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1);
+}
+
+/// Stored prover output → per-line SARIF results on repo-relative paths.
+#[tokio::test]
+async fn seam_job_result_exports_as_sarif() {
+    use echidnabot::scheduler::JobResult;
+    use echidnabot::store::models::ProofResultRecord;
+
+    let (_server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let job_id = seed_failed_job(&store, repo_id).await;
+    store
+        .save_result(&ProofResultRecord::new(
+            job_id,
+            &JobResult {
+                success: false,
+                message: "1 of 2 files failed".into(),
+                prover_output: "/tmp/clone/Proofs/Bad.lean:12:4: error: unsolved goals\n".into(),
+                duration_ms: 2_000,
+                verified_files: vec![],
+                failed_files: vec![],
+                confidence: None,
+                axioms: None,
+                prover_version: None,
+                prover_args: vec![],
+                execution_path: None,
+            },
+        ))
+        .await
+        .unwrap();
+
+    let log = echidnabot::diagnostics::sarif::build_job_sarif(store.as_ref(), job_id)
+        .await
+        .unwrap()
+        .expect("job has a result");
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    let loc = &results[0]["locations"][0]["physicalLocation"];
+    assert_eq!(loc["artifactLocation"]["uri"], "Proofs/Bad.lean");
+    assert_eq!(loc["region"]["startLine"], 12);
+}
+
+/// The badge reflects the newest finished default-branch job, filtered by
+/// prover.
+#[tokio::test]
+async fn seam_badge_tracks_latest_finished_job() {
+    use echidnabot::api::badge::{latest_status, BadgeStatus};
+    use echidnabot::scheduler::{JobStatus, ProofJob};
+    use echidnabot::store::models::ProofJobRecord;
+
+    async fn badge(store: &SqliteStore, prover: Option<&str>) -> BadgeStatus {
+        let prover = prover.map(ProverKind::new);
+        latest_status(store, Platform::GitHub, "test-owner", "lean-proof-repo", prover.as_ref())
+            .await
+            .unwrap()
+    }
+
+    let (_server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    assert_eq!(badge(&store, None).await, BadgeStatus::Unknown);
+
+    let mut job = ProofJobRecord::from(ProofJob::new(
+        repo_id,
+        "cafebabe".into(),
+        ProverKind::new("lean"),
+        vec![],
+    ));
+    job.status = JobStatus::Completed;
+    job.branch = Some("main".into());
+    store.create_job(&job).await.unwrap();
+    assert_eq!(badge(&store, None).await, BadgeStatus::Verified);
+    assert_eq!(badge(&store, Some("lean")).await, BadgeStatus::Verified);
+    assert_eq!(badge(&store, Some("coq")).await, BadgeStatus::Unknown);
+
+    // A newer failed result takes over.
+    let failed = seed_failed_job(&store, repo_id).await;
+    let mut record = store.get_job(failed).await.unwrap().unwrap();
+    record.status = JobStatus::Failed;
+    record.branch = Some("main".into());
+    store.update_job(&record).await.unwrap();
+    assert_eq!(badge(&store, None).await, BadgeStatus::Failing);
+}
+
+/// A failing PR check leaves the badge alone, and with two provers a
+/// passing one doesn't hide a failing one.
+#[tokio::test]
+async fn seam_badge_combines_provers_on_the_default_branch() {
+    use echidnabot::api::badge::{repo_status, BadgeStatus};
+    use echidnabot::scheduler::{JobStatus, ProofJob};
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (_server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let mut repo = store.get_repository(repo_id).await.unwrap().unwrap();
+    repo.enabled_provers = vec![ProverKind::new("lean"), ProverKind::new("coq")];
+    repo.default_branch = Some("main".into());
+    store.update_repository(&repo).await.unwrap();
+    let finish = |prover: &str, status: JobStatus, pr: Option<u64>| {
+        let job = ProofJob::new(repo_id, "cafebabe".into(), ProverKind::new(prover), vec![])
+            .with_context(pr, None)
+            .with_branch(pr.is_none().then(|| "main".to_string()));
+        let mut record = ProofJobRecord::from(job);
+        record.status = status;
+        record
+    };
+    let badge = |prover: Option<&'static str>| {
+        let (store, repo) = (store.clone(), repo.clone());
+        async move {
+            let prover = prover.map(ProverKind::new);
+            repo_status(store.as_ref(), &repo, prover.as_ref()).await.unwrap()
+        }
+    };
+
+    store.create_job(&finish("coq", JobStatus::Failed, None)).await.unwrap();
+    store.create_job(&finish("lean", JobStatus::Completed, None)).await.unwrap();
+    assert_eq!(badge(None).await, BadgeStatus::Failing, "coq still fails");
+    assert_eq!(badge(Some("lean")).await, BadgeStatus::Verified);
+
+    store.create_job(&finish("coq", JobStatus::Completed, None)).await.unwrap();
+    assert_eq!(badge(None).await, BadgeStatus::Verified);
+    store.create_job(&finish("coq", JobStatus::Failed, Some(7))).await.unwrap();
+    assert_eq!(badge(None).await, BadgeStatus::Verified, "a failing PR doesn't count");
+    assert_eq!(badge(Some("coq")).await, BadgeStatus::Verified);
+}
+
+/// `/status` reports the default branch, not whichever PR ran last.
+#[tokio::test]
+async fn seam_status_ignores_pr_checks() {
+    use echidnabot::api::status::build_status;
+    use echidnabot::scheduler::{JobStatus, ProofJob};
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (_server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let repo = store.get_repository(repo_id).await.unwrap().unwrap();
+    let finish = |sha: &str, status: JobStatus, pr: Option<u64>| {
+        let job = ProofJob::new(repo_id, sha.into(), ProverKind::new("lean"), vec![])
+            .with_context(pr, None)
+            .with_branch(pr.is_none().then(|| "main".to_string()));
+        let mut record = ProofJobRecord::from(job);
+        record.status = status;
+        record
+    };
+
+    store.create_job(&finish("main1", JobStatus::Completed, None)).await.unwrap();
+    store.create_job(&finish("pr1", JobStatus::Failed, Some(7))).await.unwrap();
+    let mut orphaned = finish("gone1", JobStatus::Failed, None);
+    orphaned.payload.orphaned = Some("force-pushed out of main".into());
+    store.create_job(&orphaned).await.unwrap();
+
+    let status = build_status(store.as_ref(), &repo).await.unwrap();
+    assert_eq!(status.status, "verified");
+    assert_eq!(status.provers[0].commit.as_deref(), Some("main1"));
+}
+
+/// Tokenless (public) principals only see repositories marked public.
+#[tokio::test]
+async fn seam_public_principal_respects_visibility() {
+    use echidnabot::api::auth::AuthContext;
+    use echidnabot::api::status::build_status;
+    use echidnabot::store::models::Visibility;
+
+    let (_server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+    let query = r#"{ repositories { owner name visibility } }"#;
+    let public_query = || async_graphql::Request::new(query).data(AuthContext::public());
+
+    let res = schema.execute(public_query()).await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    assert_eq!(res.data.into_json().unwrap()["repositories"], serde_json::json!([]));
+
+    let mut repo = store.get_repository(repo_id).await.unwrap().unwrap();
+    repo.visibility = Visibility::Public;
+    store.update_repository(&repo).await.unwrap();
+
+    let res = schema.execute(public_query()).await;
+    let data = res.data.into_json().unwrap();
+    assert_eq!(data["repositories"][0]["visibility"], "PUBLIC");
+
+    // Mutations stay closed to tokenless callers.
+    let res = schema
+        .execute(
+            async_graphql::Request::new(format!(
+                r#"mutation {{ setRepoEnabled(repoId: "{}", enabled: false) {{ id }} }}"#,
+                repo_id
+            ))
+            .data(AuthContext::public()),
+        )
+        .await;
+    assert!(!res.errors.is_empty());
+
+    let status = build_status(store.as_ref(), &repo).await.unwrap();
+    assert_eq!(status.status, "unknown");
+    assert_eq!(status.provers.len(), 1);
+    assert_eq!(status.provers[0].prover, "lean");
+}
+
+/// One unreadable repository row nulls its list item, not the query.
+#[tokio::test]
+async fn seam_graphql_lists_degrade_per_item() {
+    use echidnabot::api::auth::AuthContext;
+
+    let (_server, store, scheduler, _repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let broken = Repository::new(Platform::GitHub, "test-owner".into(), "broken".into());
+    store.create_repository(&broken).await.unwrap();
+    sqlx::query("UPDATE repositories SET enabled_provers = 'not json' WHERE id = ?")
+        .bind(broken.id.to_string())
+        .execute(store.pool())
+        .await
+        .unwrap();
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+    let query = r#"{ repositories { name } announcement { message } }"#;
+
+    let res = schema.execute(query).await;
+    assert_eq!(res.errors.len(), 1, "{:?}", res.errors);
+    let error = &res.errors[0];
+    assert!(error.message.contains(&broken.id.to_string()), "{}", error.message);
+    assert_eq!(
+        error.extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&async_graphql::Value::from("INTERNAL"))
+    );
+    let data = res.data.into_json().unwrap();
+    let repos = data["repositories"].as_array().unwrap();
+    assert_eq!(repos.len(), 2);
+    let failed = repos.iter().position(|r| r.is_null()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&error.path).unwrap(),
+        serde_json::json!(["repositories", failed])
+    );
+    assert_eq!(repos[1 - failed]["name"], "lean-proof-repo", "the readable repository still resolves");
+    assert!(data["announcement"].is_null());
+
+    // Public callers cannot be told about rows they might not see.
+    let res = schema
+        .execute(async_graphql::Request::new(query).data(AuthContext::public()))
+        .await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+}
+
+/// Repositories → jobs → results resolve through the batching loader.
+#[tokio::test]
+async fn seam_graphql_nested_jobs_and_results() {
+    use echidnabot::scheduler::{JobResult, ProofJob};
+    use echidnabot::store::models::{ProofJobRecord, ProofResultRecord};
+
+    let (_server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let finished = ProofJob::new(repo_id, "sha1".into(), ProverKind::new("lean"), vec![]);
+    let queued = ProofJob::new(repo_id, "sha2".into(), ProverKind::new("lean"), vec![]);
+    store.create_job(&ProofJobRecord::from(finished.clone())).await.unwrap();
+    store.create_job(&ProofJobRecord::from(queued.clone())).await.unwrap();
+    let result = JobResult {
+        success: true,
+        message: "ok".into(),
+        prover_output: String::new(),
+        duration_ms: 12,
+        verified_files: vec!["A.lean".into()],
+        failed_files: vec![],
+        confidence: None,
+        axioms: None,
+        prover_version: None,
+        prover_args: vec![],
+        execution_path: None,
+    };
+    store
+        .save_result(&ProofResultRecord::new(finished.id, &result))
+        .await
+        .unwrap();
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+
+    let res = schema
+        .execute(
+            r#"{ repositories { name jobs(limit: 5) {
+                commitSha
+                result { success durationMs verifiedFiles }
+                repository { name }
+            } } }"#,
+        )
+        .await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    let data = res.data.into_json().unwrap();
+    let jobs = data["repositories"][0]["jobs"].as_array().unwrap();
+    assert_eq!(jobs.len(), 2);
+    let by_sha = |sha: &str| jobs.iter().find(|j| j["commitSha"] == sha).unwrap();
+    assert_eq!(by_sha("sha1")["result"]["success"], true);
+    assert_eq!(by_sha("sha1")["result"]["durationMs"], 12);
+    assert_eq!(by_sha("sha1")["result"]["verifiedFiles"][0], "A.lean");
+    assert!(by_sha("sha2")["result"].is_null(), "no result until the job finishes");
+    assert_eq!(by_sha("sha2")["repository"]["name"], "lean-proof-repo");
+}
+
+/// `searchJobs` finds jobs across repositories for token holders only.
+#[tokio::test]
+async fn seam_graphql_search_jobs() {
+    use echidnabot::api::auth::AuthContext;
+    use echidnabot::scheduler::{JobStatus, ProofJob};
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (_server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    for (sha, status) in [("aaa1", JobStatus::Failed), ("aaa2", JobStatus::Completed), ("bbb3", JobStatus::Failed)] {
+        let mut job = ProofJob::new(repo_id, sha.into(), ProverKind::new("lean"), vec![]);
+        job.status = status;
+        store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+    }
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+    let query = r#"{ searchJobs(filter: { status: FAILED, prover: LEAN }, limit: 1) {
+        totalCount hasNextPage jobs { commitSha status }
+    } }"#;
+
+    let res = schema.execute(query).await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    let page = &res.data.into_json().unwrap()["searchJobs"];
+    assert_eq!(page["totalCount"], 2);
+    assert_eq!(page["hasNextPage"], true);
+    assert_eq!(page["jobs"].as_array().unwrap().len(), 1);
+    assert_eq!(page["jobs"][0]["status"], "FAILED");
+
+    let res = schema
+        .execute(async_graphql::Request::new(query).data(AuthContext::public()))
+        .await;
+    assert_eq!(
+        res.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+}
+
+/// `queueSlo` covers every repository's jobs, so it needs a token that is
+/// not confined to a tenant.
+#[tokio::test]
+async fn seam_graphql_queue_slo_requires_a_token() {
+    use echidnabot::api::auth::AuthContext;
+
+    let (_server, store, scheduler, _repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let schema = create_schema(GraphQLState {
+        store,
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+    let query = "{ queueSlo { priority breached } }";
+
+    let res = schema.execute(query).await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+
+    let res = schema
+        .execute(async_graphql::Request::new(query).data(AuthContext::public()))
+        .await;
+    assert_eq!(
+        res.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+
+    let acme = AuthContext { tenant: Some("acme".into()), ..AuthContext::open() };
+    let res = schema.execute(async_graphql::Request::new(query).data(acme)).await;
+    assert_eq!(
+        res.errors[0].extensions.as_ref().and_then(|e| e.get("code")),
+        Some(&async_graphql::Value::from("FORBIDDEN"))
+    );
+}
+
+/// `explainJob` serves the decision trace stored with a job.
+#[tokio::test]
+async fn seam_graphql_explain_job() {
+    use echidnabot::decision::DecisionTrace;
+    use echidnabot::modes::ModeSource;
+    use echidnabot::scheduler::ProofJob;
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (_server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let job = ProofJob::new(repo_id, "sha1".into(), ProverKind::new("lean"), vec![]);
+    let mut record = ProofJobRecord::from(job.clone());
+    let mut trace = DecisionTrace {
+        mode: Some(BotMode::Regulator),
+        mode_source: Some(ModeSource::Directive),
+        directive: true,
+        trust_level: Some(4),
+        cache_hits: vec!["incremental: 2 file(s) affected by the push".into()],
+        conclusion: Some("failure".into()),
+        blocks_merge: true,
+        ..Default::default()
+    };
+    trace.policy("axioms", "1 escape hatch(es): failure");
+    record.payload.decision = Some(trace);
+    store.create_job(&record).await.unwrap();
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler,
+        echidna: Arc::new(EchidnaClient::new(&Config::default().echidna)),
+    });
+
+    let query = format!(
+        r#"{{ explainJob(jobId: "{}") {{
+            mode modeSource directive trustLevel cacheHits
+            policies {{ policy effect }} conclusion blocksMerge
+        }} }}"#,
+        job.id
+    );
+    let res = schema.execute(query.as_str()).await;
+    assert!(res.errors.is_empty(), "{:?}", res.errors);
+    let explained = &res.data.into_json().unwrap()["explainJob"];
+    assert_eq!(explained["mode"], "regulator");
+    assert_eq!(explained["modeSource"], "directive");
+    assert_eq!(explained["trustLevel"], 4);
+    assert_eq!(explained["policies"][0]["policy"], "axioms");
+    assert_eq!(explained["blocksMerge"], true);
+}
+
+/// A PR with a known base also queues the base commit, once, so the check
+/// can tell new failures from ones the base already had.
+#[tokio::test]
+async fn seam_pr_queues_baseline_once() {
+    let (server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+
+    for head in ["head1", "head2"] {
+        server
+            .post("/webhooks/github")
+            .add_header("X-GitHub-Event", "pull_request")
+            .json(&serde_json::json!({
+                "action": "synchronize",
+                "pull_request": {
+                    "number": 7,
+                    "head": { "sha": head },
+                    "base": { "sha": "base0" }
+                },
+                "repository": { "full_name": "test-owner/lean-proof-repo" }
+            }))
+            .await
+            .assert_status_ok();
+    }
+
+    let jobs = store.list_jobs_for_repo(repo_id, 10).await.unwrap();
+    let base_jobs: Vec<_> = jobs.iter().filter(|j| j.commit_sha == "base0").collect();
+    assert_eq!(base_jobs.len(), 1, "base verified once");
+    assert_eq!(base_jobs[0].pr_number, None);
+    let head_jobs: Vec<_> = jobs.iter().filter(|j| j.commit_sha != "base0").collect();
+    assert_eq!(head_jobs.len(), 2);
+    assert!(head_jobs.iter().all(|j| j.base_sha.as_deref() == Some("base0")));
+}
+
+/// A baseline the queue has no room for is recorded cancelled, so the
+/// next PR event queues it again.
+#[tokio::test]
+async fn seam_rejected_baseline_is_queued_again() {
+    use echidnabot::scheduler::{JobStatus, ProofJob};
+
+    let (server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let mut fillers = Vec::new();
+    while let Some(id) = scheduler
+        .enqueue(ProofJob::new(Uuid::new_v4(), "filler".into(), ProverKind::new("lean"), vec![]))
+        .await
+        .unwrap()
+    {
+        fillers.push(id);
+    }
+    let synchronize = |head: &str| {
+        serde_json::json!({
+            "action": "synchronize",
+            "pull_request": { "number": 7, "head": { "sha": head }, "base": { "sha": "base0" } },
+            "repository": { "full_name": "test-owner/lean-proof-repo" }
+        })
+    };
+    let base_statuses = || async {
+        let jobs = store.list_jobs_for_repo(repo_id, 10).await.unwrap();
+        let mut statuses: Vec<_> =
+            jobs.iter().filter(|j| j.commit_sha == "base0").map(|j| j.status).collect();
+        statuses.sort_by_key(|s| format!("{:?}", s));
+        statuses
+    };
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "pull_request")
+        .json(&synchronize("head1"))
+        .await
+        .assert_status_ok();
+    assert_eq!(base_statuses().await, [JobStatus::Cancelled]);
+
+    for id in fillers {
+        assert!(scheduler.cancel_job(id).await);
+    }
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "pull_request")
+        .json(&synchronize("head2"))
+        .await
+        .assert_status_ok();
+    assert_eq!(base_statuses().await, [JobStatus::Cancelled, JobStatus::Queued]);
+}
+
+/// A new PR head cancels the old head's jobs, queued or running; the
+/// baseline job and other PRs are left alone.
+#[tokio::test]
+async fn seam_pr_synchronize_cancels_superseded_jobs() {
+    use echidnabot::scheduler::JobStatus;
+
+    let (server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let synchronize = |number: u64, head: &str| {
+        serde_json::json!({
+            "action": "synchronize",
+            "pull_request": {
+                "number": number,
+                "head": { "sha": head },
+                "base": { "sha": "base0" }
+            },
+            "repository": { "full_name": "test-owner/lean-proof-repo" }
+        })
+    };
+
+    for (number, head) in [(7, "head1"), (8, "other1"), (7, "head2")] {
+        server
+            .post("/webhooks/github")
+            .add_header("X-GitHub-Event", "pull_request")
+            .json(&synchronize(number, head))
+            .await
+            .assert_status_ok();
+    }
+
+    let jobs = store.list_jobs_for_repo(repo_id, 10).await.unwrap();
+    let status_of = |sha: &str| jobs.iter().find(|j| j.commit_sha == sha).unwrap().status;
+    assert_eq!(status_of("head1"), JobStatus::Cancelled);
+    assert_eq!(status_of("head2"), JobStatus::Queued);
+    assert_eq!(status_of("other1"), JobStatus::Queued);
+    assert_eq!(status_of("base0"), JobStatus::Queued);
+    assert_eq!(scheduler.queue_depth(), 3);
+}
+
+fn pr_comment_payload(body: &str, login: &str, association: &str) -> serde_json::Value {
+    serde_json::json!({
+        "action": "created",
+        "issue": { "number": 7 },
+        "comment": {
+            "body": body,
+            "user": { "login": login, "type": "User" },
+            "author_association": association
+        },
+        "repository": { "full_name": "test-owner/lean-proof-repo" }
+    })
+}
+
+/// `rerun --full` is maintainer-only by default; a contributor is refused
+/// and nothing is queued.
+#[tokio::test]
+async fn seam_comment_command_permissions() {
+    use echidnabot::scheduler::ProofJob;
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let job = ProofJob::new(repo_id, "cafebabe".into(), ProverKind::new("lean"), vec![])
+        .with_context(Some(7), None);
+    store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "issue_comment")
+        .json(&pr_comment_payload("@echidnabot rerun --full", "drive-by", "CONTRIBUTOR"))
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 0, "contributor must be denied");
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "issue_comment")
+        .json(&pr_comment_payload("@echidnabot rerun --full", "maint", "MEMBER"))
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1, "one job per enabled prover");
+}
+
+/// GitLab merge request notes and Bitbucket pull request comments run the
+/// same commands. Their payloads carry no association, so only the
+/// repository owner is more than `anyone`. A Bitbucket actor only counts
+/// when the delivery is signed with the repository's secret; unsigned,
+/// a comment claiming to be the owner's runs nothing.
+#[tokio::test]
+async fn seam_comment_commands_on_gitlab_and_bitbucket() {
+    use echidnabot::scheduler::ProofJob;
+    use echidnabot::store::models::ProofJobRecord;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let (server, store, scheduler, _repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    for platform in [Platform::GitLab, Platform::Bitbucket] {
+        let mut repo = Repository::new(platform, "test-owner".into(), "lean-proof-repo".into());
+        repo.enabled_provers = vec![ProverKind::new("lean")];
+        store.create_repository(&repo).await.unwrap();
+        let job = ProofJob::new(repo.id, "cafebabe".into(), ProverKind::new("lean"), vec![])
+            .with_context(Some(7), None);
+        store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+    }
+
+    let gitlab_note = |login: &str| {
+        serde_json::json!({
+            "object_kind": "note",
+            "object_attributes": { "note": "@echidnabot check", "noteable_type": "MergeRequest" },
+            "project": { "path_with_namespace": "test-owner/lean-proof-repo" },
+            "user": { "username": login },
+            "merge_request": { "iid": 7 }
+        })
+    };
+    for login in ["drive-by", "test-owner"] {
+        server
+            .post("/webhooks/gitlab")
+            .add_header("X-Gitlab-Event", "Note Hook")
+            .json(&gitlab_note(login))
+            .await
+            .assert_status_ok();
+    }
+    assert_eq!(scheduler.stats().await.queued, 1, "only the owner may run `check`");
+
+    let bitbucket_comment = |login: &str| {
+        serde_json::json!({
+            "repository": { "full_name": "test-owner/lean-proof-repo" },
+            "pullrequest": { "id": 7 },
+            "comment": { "content": { "raw": "@echidnabot check" } },
+            "actor": { "username": login }
+        })
+    };
+    let post = |login: &str, secret: Option<&str>| {
+        let body = serde_json::to_vec(&bitbucket_comment(login)).unwrap();
+        let request = server
+            .post("/webhooks/bitbucket")
+            .add_header("X-Event-Key", "pullrequest:comment_created")
+            .add_header("Content-Type", "application/json");
+        let request = match secret {
+            Some(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+                mac.update(&body);
+                let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+                request.add_header("X-Hub-Signature", signature)
+            }
+            None => request,
+        };
+        request.bytes(body.into())
+    };
+    for login in ["drive-by", "test-owner"] {
+        post(login, None).await.assert_status_ok();
+    }
+    assert_eq!(scheduler.stats().await.queued, 1, "unsigned, the actor is not trusted");
+
+    let mut repo = store
+        .get_repository_by_name(Platform::Bitbucket, "test-owner", "lean-proof-repo")
+        .await
+        .unwrap()
+        .unwrap();
+    repo.webhook_secret = Some("bitbucket-secret".into());
+    store.update_repository(&repo).await.unwrap();
+    post("test-owner", None)
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    post("test-owner", Some("other-secret"))
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    post("test-owner", Some("bitbucket-secret")).await.assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 2);
+}
+
+/// Codeberg comment payloads carry the commenter's repository permissions:
+/// write access makes a collaborator, who may run `check`.
+#[tokio::test]
+async fn seam_codeberg_comment_role_from_permissions() {
+    use echidnabot::scheduler::ProofJob;
+    use echidnabot::store::models::ProofJobRecord;
+
+    let (server, store, scheduler, _repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let mut repo =
+        Repository::new(Platform::Codeberg, "test-owner".into(), "lean-proof-repo".into());
+    repo.enabled_provers = vec![ProverKind::new("lean")];
+    store.create_repository(&repo).await.unwrap();
+    let job = ProofJob::new(repo.id, "cafebabe".into(), ProverKind::new("lean"), vec![])
+        .with_context(Some(7), None);
+    store.create_job(&ProofJobRecord::from(job)).await.unwrap();
+
+    let comment = |push: bool| {
+        serde_json::json!({
+            "action": "created",
+            "issue": { "number": 7 },
+            "comment": { "body": "@echidnabot check", "user": { "login": "helper" } },
+            "repository": {
+                "full_name": "test-owner/lean-proof-repo",
+                "permissions": { "admin": false, "push": push, "pull": true }
+            }
+        })
+    };
+    let post = |payload: serde_json::Value| {
+        server
+            .post("/webhooks/codeberg")
+            .add_header("X-Gitea-Event", "issue_comment")
+            .json(&payload)
+    };
+
+    post(comment(false)).await.assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 0, "read access is `anyone`");
+    post(comment(true)).await.assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1);
+}
+
+/// With async ingest enabled the handler answers 202 before any job is
+/// queued; the worker pass does the enqueueing, and a redelivery of the
+/// same delivery id is acknowledged without being stored again.
+#[tokio::test]
+async fn seam_webhook_ingest_returns_202_then_processes() {
+    use echidnabot::api::ingest::{drain_due, WebhookIngest};
+
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let scheduler = Arc::new(JobScheduler::new(4, 100));
+    let mut repo = Repository::new(Platform::GitHub, "test-owner".into(), "lean-proof-repo".into());
+    repo.enabled_provers = vec![ProverKind::new("lean")];
+    store.create_repository(&repo).await.unwrap();
+
+    let ingest = Arc::new(WebhookIngest::new(3));
+    let app_state = AppState {
+        config: Arc::new(ArcSwap::from_pointee(Config::default())),
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: Some(ingest.clone()),
+        health: None,
+    };
+    let app = Router::new()
+        .merge(webhook_router(app_state.clone()))
+        .with_state(app_state.clone());
+    let server = TestServer::new(app).unwrap();
+
+    let response = server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .add_header("X-GitHub-Delivery", "ingest-delivery-001")
+        .json(&lean_push_payload())
+        .await;
+    response.assert_status(axum::http::StatusCode::ACCEPTED);
+    assert_eq!(response.text(), "Accepted");
+    assert_eq!(scheduler.stats().await.queued, 0, "nothing is processed inline");
+
+    assert_eq!(drain_due(&app_state, &ingest).await.unwrap(), 1);
+    assert_eq!(scheduler.stats().await.queued, 1);
+    assert_eq!(drain_due(&app_state, &ingest).await.unwrap(), 0, "processed deliveries are not retried");
+
+    let redelivery = server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .add_header("X-GitHub-Delivery", "ingest-delivery-001")
+        .json(&lean_push_payload())
+        .await;
+    redelivery.assert_status(axum::http::StatusCode::ACCEPTED);
+    assert_eq!(redelivery.text(), "Duplicate delivery");
+    assert_eq!(drain_due(&app_state, &ingest).await.unwrap(), 0);
+}
+
+/// While the store is down, webhooks are refused with 503 and a
+/// `Retry-After` hint so the platform redelivers them; one good probe
+/// lets them through again.
+#[tokio::test]
+async fn seam_webhook_backpressure_while_store_down() {
+    use echidnabot::api::health::{Dependency, HealthMonitor};
+    use echidnabot::config::HealthConfig;
+
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let scheduler = Arc::new(JobScheduler::new(4, 100));
+    let mut repo = Repository::new(Platform::GitHub, "test-owner".into(), "lean-proof-repo".into());
+    repo.enabled_provers = vec![ProverKind::new("lean")];
+    store.create_repository(&repo).await.unwrap();
+
+    let monitor = Arc::new(HealthMonitor::new(&HealthConfig {
+        failure_threshold: 1,
+        retry_after_secs: 120,
+        ..HealthConfig::default()
+    }));
+    monitor.record(Dependency::Store, Err("database is locked".into()));
+
+    let app_state = AppState {
+        config: Arc::new(ArcSwap::from_pointee(Config::default())),
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: Some(monitor.clone()),
+    };
+    let app = Router::new()
+        .route("/health", get(echidnabot::api::health::health))
+        .merge(webhook_router(app_state.clone()))
+        .with_state(app_state);
+    let server = TestServer::new(app).unwrap();
+
+    let refused = server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .json(&lean_push_payload())
+        .await;
+    refused.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(refused.header("Retry-After"), "120");
+    assert_eq!(scheduler.stats().await.queued, 0);
+
+    let health = server.get("/health").await;
+    health.assert_status(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    assert!(health.text().contains("store"));
+
+    monitor.record(Dependency::Store, Ok(()));
+    let accepted = server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .json(&lean_push_payload())
+        .await;
+    accepted.assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1);
+    server.get("/health").await.assert_status_ok();
+}
+
+/// Bodies over `[server] max_webhook_body_bytes` are refused with 413;
+/// ignored event types are answered without reading the body at all.
+#[tokio::test]
+async fn seam_webhook_body_size_cap() {
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let scheduler = Arc::new(JobScheduler::new(4, 100));
+    let mut repo = Repository::new(Platform::GitHub, "test-owner".into(), "lean-proof-repo".into());
+    repo.enabled_provers = vec![ProverKind::new("lean")];
+    store.create_repository(&repo).await.unwrap();
+
+    let mut config = Config::default();
+    config.server.max_webhook_body_bytes = 1024;
+    let app_state = AppState {
+        config: Arc::new(ArcSwap::from_pointee(config)),
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
+    };
+    let app = Router::new()
+        .merge(webhook_router(app_state.clone()))
+        .with_state(app_state);
+    let server = TestServer::new(app).unwrap();
+
+    let mut huge = lean_push_payload();
+    huge["commits"] = serde_json::json!([{
+        "added": (0..200).map(|i| format!("Proofs/File{}.lean", i)).collect::<Vec<_>>()
+    }]);
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .json(&huge)
+        .await
+        .assert_status(axum::http::StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(scheduler.stats().await.queued, 0);
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "star")
+        .json(&huge)
+        .await
+        .assert_status_ok();
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .json(&lean_push_payload())
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1);
+}
+
+/// A repository with its own webhook secret only accepts deliveries
+/// signed with it; repositories without one fall back to the global
+/// `[github] webhook_secret`. One whose secret stays sealed accepts none.
+#[tokio::test]
+async fn seam_per_repo_webhook_secret() {
+    use echidnabot::config::GitHubConfig;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    let config = Config {
+        github: Some(GitHubConfig {
+            app_id: None,
+            private_key_path: None,
+            token: None,
+            webhook_secret: Some("global-secret".into()),
+            auto_register: true,
+        }),
+        ..Config::default()
+    };
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let mut tenant = Repository::new(Platform::GitHub, "test-owner".into(), "lean-proof-repo".into());
+    tenant.webhook_secret = Some("tenant-secret".into());
+    tenant.enabled_provers = vec![ProverKind::new("lean")];
+    store.create_repository(&tenant).await.unwrap();
+    // A secret whose tenant key is gone stays sealed when read back.
+    let mut locked = Repository::new(Platform::GitHub, "locked".into(), "repo".into());
+    locked.webhook_secret = Some("sealed:v1:AAAA".into());
+    locked.enabled_provers = vec![ProverKind::new("coq")];
+    store.create_repository(&locked).await.unwrap();
+
+    let scheduler = Arc::new(JobScheduler::new(4, 100));
+    let app_state = AppState {
+        config: Arc::new(ArcSwap::from_pointee(config)),
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
+    };
+    let app = Router::new()
+        .merge(webhook_router(app_state.clone()))
+        .with_state(app_state);
+    let server = TestServer::new(app).unwrap();
+
+    let post = |body: Vec<u8>, secret: &'static str| {
+        let signature = sign(secret, &body);
+        server
+            .post("/webhooks/github")
+            .add_header("X-GitHub-Event", "push")
+            .add_header("X-Hub-Signature-256", signature)
+            .add_header("Content-Type", "application/json")
+            .bytes(body.into())
+    };
+
+    let tenant_body = serde_json::to_vec(&lean_push_payload()).unwrap();
+    post(tenant_body.clone(), "global-secret")
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    post(tenant_body, "tenant-secret").await.assert_status_ok();
+
+    let other_body = serde_json::to_vec(&coq_push_payload("someone/else")).unwrap();
+    post(other_body.clone(), "tenant-secret")
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    post(other_body, "global-secret").await.assert_status_ok();
+    let queued = scheduler.stats().await.queued;
+
+    // Neither the sealed string nor the global secret signs for `locked`.
+    let locked_body = serde_json::to_vec(&coq_push_payload("locked/repo")).unwrap();
+    for secret in ["sealed:v1:AAAA", "global-secret"] {
+        post(locked_body.clone(), secret)
+            .await
+            .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(scheduler.stats().await.queued, queued);
+}
+
+/// Pushes to branches other than the default one are skipped unless the
+/// repository's branch patterns include them; tag pushes never verify.
+#[tokio::test]
+async fn seam_push_branch_filter() {
+    let (server, store, scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+    let push_to = |git_ref: &str| {
+        let mut payload = lean_push_payload();
+        payload["ref"] = serde_json::Value::String(git_ref.to_string());
+        payload
+    };
+
+    for git_ref in ["refs/heads/feature/x", "refs/tags/v1.0"] {
+        server
+            .post("/webhooks/github")
+            .add_header("X-GitHub-Event", "push")
+            .json(&push_to(git_ref))
+            .await
+            .assert_status_ok();
+    }
+    assert_eq!(scheduler.stats().await.queued, 0, "only the default branch by default");
+
+    let mut repo = store.get_repository(repo_id).await.unwrap().unwrap();
+    repo.branch_include = vec!["feature/*".into()];
+    store.update_repository(&repo).await.unwrap();
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "push")
+        .json(&push_to("refs/heads/feature/x"))
+        .await
+        .assert_status_ok();
+    assert_eq!(scheduler.stats().await.queued, 1);
+}
+
+/// Installing the GitHub App registers the listed repositories; removing
+/// them from the installation disables them, and adding them back
+/// enables them again.
+#[tokio::test]
+async fn seam_installation_auto_registers() {
+    use echidnabot::store::models::Visibility;
+
+    let (server, store, _scheduler, repo_id) =
+        make_server_with_repo(BotMode::Verifier, "lean").await;
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "installation")
+        .json(&serde_json::json!({
+            "action": "created",
+            "installation": { "id": 1 },
+            "repositories": [
+                { "full_name": "test-owner/lean-proof-repo", "private": false },
+                { "full_name": "test-owner/new-proofs", "private": false }
+            ]
+        }))
+        .await
+        .assert_status_ok();
+    let added = store
+        .get_repository_by_name(Platform::GitHub, "test-owner", "new-proofs")
+        .await
+        .unwrap()
+        .expect("registered on installation");
+    assert!(added.enabled);
+    assert_eq!(added.visibility, Visibility::Public);
+    let existing = store.get_repository(repo_id).await.unwrap().unwrap();
+    assert_eq!(existing.enabled_provers, vec![ProverKind::new("lean")], "existing settings kept");
+
+    let change = |action: &str, key: &str| {
+        serde_json::json!({
+            "action": action,
+            "installation": { "id": 1 },
+            key: [{ "full_name": "test-owner/new-proofs", "private": false }]
+        })
+    };
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "installation_repositories")
+        .json(&change("removed", "repositories_removed"))
+        .await
+        .assert_status_ok();
+    let removed = store.get_repository(added.id).await.unwrap().unwrap();
+    assert!(!removed.enabled, "removed repositories are disabled, not deleted");
+
+    server
+        .post("/webhooks/github")
+        .add_header("X-GitHub-Event", "installation_repositories")
+        .json(&change("added", "repositories_added"))
+        .await
+        .assert_status_ok();
+    assert!(store.get_repository(added.id).await.unwrap().unwrap().enabled);
+}
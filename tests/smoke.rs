@@ -7,6 +7,7 @@
 //! Verifies: "does it start, does it respond, are the obvious paths alive?"
 //! Runs before deeper tests in CI to fail-fast on build regressions.
 
+use arc_swap::ArcSwap;
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::{routing::get, Extension, Router};
 use axum_test::TestServer;
@@ -22,10 +23,10 @@
 use std::sync::Arc;
 
 async fn make_test_server() -> TestServer {
-    let config = Arc::new(Config::default());
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
     let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
     let scheduler = Arc::new(JobScheduler::new(2, 10));
-    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
 
     let graphql_state = GraphQLState {
         store: store.clone(),
@@ -40,10 +41,16 @@ async fn make_test_server() -> TestServer {
         scheduler,
         rate_limiter: None,
         mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
     };
 
     let app = Router::new()
         .route("/health", get(|| async { "OK" }))
+        .route(
+            "/badge/{platform}/{owner}/{repo}",
+            get(echidnabot::api::badge::repo_badge),
+        )
         .route(
             "/graphql",
             axum::routing::post(
@@ -68,6 +75,18 @@ async fn smoke_health_endpoint_returns_200() {
     response.assert_text("OK");
 }
 
+#[tokio::test]
+async fn smoke_badge_for_unregistered_repo_is_unknown() {
+    let server = make_test_server().await;
+    let response = server.get("/badge/github/nobody/nothing.svg").await;
+    response.assert_status_ok();
+    assert_eq!(
+        response.headers()["content-type"],
+        "image/svg+xml;charset=utf-8"
+    );
+    assert!(response.text().contains(">unknown</text>"));
+}
+
 #[tokio::test]
 async fn smoke_unknown_route_returns_404() {
     let server = make_test_server().await;
@@ -147,10 +166,10 @@ async fn smoke_graphql_repositories_empty() {
 
 #[tokio::test]
 async fn smoke_rate_limiting_returns_429_at_limit() {
-    let config = Arc::new(Config::default());
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
     let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
     let scheduler = Arc::new(JobScheduler::new(2, 10));
-    let echidna = Arc::new(EchidnaClient::new(&config.echidna));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
 
     let graphql_state = GraphQLState {
         store: store.clone(),
@@ -165,6 +184,8 @@ async fn smoke_rate_limiting_returns_429_at_limit() {
         scheduler,
         rate_limiter: Some(Arc::new(WebhookRateLimiter::new(2))),
         mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
     };
 
     let app = Router::new()
@@ -202,3 +223,152 @@ async fn smoke_rate_limiting_returns_429_at_limit() {
         "rate-limited response must include Retry-After header"
     );
 }
+
+#[tokio::test]
+async fn smoke_graphql_requires_token_when_auth_required() {
+    use axum::handler::Handler;
+    use echidnabot::api::auth::{auth_middleware, generate_token, hash_token, AuthContext, TokenScope};
+    use echidnabot::store::models::ApiTokenRecord;
+    use echidnabot::store::Store;
+
+    let mut config = Config::default();
+    config.auth.required = true;
+    let config = Arc::new(ArcSwap::from_pointee(config));
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let scheduler = Arc::new(JobScheduler::new(2, 10));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
+
+    let token = generate_token();
+    store
+        .create_api_token(&ApiTokenRecord::new("smoke".into(), hash_token(&token), TokenScope::Read))
+        .await
+        .unwrap();
+
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        echidna,
+    });
+    let app_state = AppState {
+        config,
+        store,
+        scheduler,
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
+    };
+
+    let graphql = |Extension(schema): Extension<echidnabot::api::graphql::EchidnabotSchema>,
+                   Extension(auth): Extension<AuthContext>,
+                   req: GraphQLRequest| async move {
+        GraphQLResponse::from(schema.execute(req.into_inner().data(auth)).await)
+    };
+    let auth_layer = axum::middleware::from_fn_with_state(app_state.clone(), auth_middleware);
+    let app = Router::new()
+        .route("/graphql", axum::routing::post(graphql.layer(auth_layer)))
+        .layer(Extension(schema))
+        .with_state(app_state);
+    let server = TestServer::new(app).unwrap();
+    let query = serde_json::json!({ "query": "{ repositories { id } }" });
+
+    let anonymous = server.post("/graphql").json(&query).await;
+    assert_eq!(anonymous.status_code(), 401, "missing token must be rejected");
+
+    let bogus = server
+        .post("/graphql")
+        .authorization_bearer("ebt_not-a-real-token")
+        .json(&query)
+        .await;
+    assert_eq!(bogus.status_code(), 401, "unknown token must be rejected");
+
+    let ok = server
+        .post("/graphql")
+        .authorization_bearer(&token)
+        .json(&query)
+        .await;
+    ok.assert_status_ok();
+
+    // A read-scoped token cannot reach admin mutations.
+    let forbidden = server
+        .post("/graphql")
+        .authorization_bearer(&token)
+        .json(&serde_json::json!({
+            "query": "mutation { setRepoEnabled(repoId: \"00000000-0000-0000-0000-000000000000\", enabled: false) { id } }"
+        }))
+        .await;
+    let body: serde_json::Value = forbidden.json();
+    assert!(
+        body["errors"][0]["message"].as_str().unwrap_or("").contains("Forbidden"),
+        "read scope must not pass admin gate: {body}"
+    );
+    assert_eq!(body["errors"][0]["extensions"]["code"], "FORBIDDEN");
+}
+
+/// With `[auth] required = false` (the default) a tokenless `/graphql`
+/// caller still only sees public repositories and runs no mutations.
+#[tokio::test]
+async fn smoke_anonymous_graphql_is_public_only() {
+    use axum::handler::Handler;
+    use echidnabot::adapters::Platform;
+    use echidnabot::api::auth::{public_auth_middleware, AuthContext};
+    use echidnabot::store::models::{Repository, Visibility};
+    use echidnabot::store::Store;
+
+    let config = Arc::new(ArcSwap::from_pointee(Config::default()));
+    let store = Arc::new(SqliteStore::new("sqlite::memory:").await.unwrap());
+    let scheduler = Arc::new(JobScheduler::new(2, 10));
+    let echidna = Arc::new(EchidnaClient::new(&config.load().echidna));
+    let mut open = Repository::new(Platform::GitHub, "org".into(), "open".into());
+    open.visibility = Visibility::Public;
+    store.create_repository(&open).await.unwrap();
+    let mut closed = Repository::new(Platform::GitHub, "org".into(), "closed".into());
+    closed.visibility = Visibility::Private;
+    store.create_repository(&closed).await.unwrap();
+
+    let schema = create_schema(GraphQLState {
+        store: store.clone(),
+        scheduler: scheduler.clone(),
+        echidna,
+    });
+    let app_state = AppState {
+        config,
+        store,
+        scheduler,
+        rate_limiter: None,
+        mode_selector: ModeSelector::default(),
+        ingest: None,
+        health: None,
+    };
+    let graphql = |Extension(schema): Extension<echidnabot::api::graphql::EchidnabotSchema>,
+                   Extension(auth): Extension<AuthContext>,
+                   req: GraphQLRequest| async move {
+        GraphQLResponse::from(schema.execute(req.into_inner().data(auth)).await)
+    };
+    let public_layer =
+        axum::middleware::from_fn_with_state(app_state.clone(), public_auth_middleware);
+    let app = Router::new()
+        .route("/graphql", axum::routing::post(graphql.layer(public_layer)))
+        .layer(Extension(schema))
+        .with_state(app_state);
+    let server = TestServer::new(app).unwrap();
+
+    let listed = server
+        .post("/graphql")
+        .json(&serde_json::json!({ "query": "{ repositories { name } }" }))
+        .await;
+    listed.assert_status_ok();
+    let body: serde_json::Value = listed.json();
+    assert_eq!(body["data"]["repositories"], serde_json::json!([{ "name": "open" }]), "{body}");
+
+    let mutation = format!(
+        "mutation {{ setRepoEnabled(repoId: \"{}\", enabled: false) {{ id }} }}",
+        closed.id
+    );
+    let refused = server
+        .post("/graphql")
+        .json(&serde_json::json!({ "query": mutation }))
+        .await;
+    let body: serde_json::Value = refused.json();
+    assert_eq!(body["errors"][0]["extensions"]["code"], "FORBIDDEN", "{body}");
+}